@@ -482,6 +482,8 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
         );
     }
 
+    currTask.AccountPageFault();
+
     let signal;
     // no need loop, just need to enable break
     loop {
@@ -539,6 +541,33 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
                 return;
             }
 
+            // The missing page is in a range registered with a userfaultfd:
+            // wait for its reader to populate it, then retry the access.
+            match vma.uffd.clone() {
+                Some(uffd) if fromUser => {
+                    let write = (errbits & PageFaultErrorCode::CAUSED_BY_WRITE)
+                        == PageFaultErrorCode::CAUSED_BY_WRITE;
+                    currTask.SaveFp();
+                    match uffd.HandleFault(currTask, _ml, pageAddr, write) {
+                        Err(Error::ErrInterrupted) => {
+                            MainRun(currTask, TaskRunState::RunApp);
+                            currTask.RestoreFp();
+                            CPULocal::Myself().SetMode(VcpuMode::User);
+                            currTask.mm.HandleTlbShootdown();
+                            ReturnToApp(ptRegs);
+                        }
+                        _ => (),
+                    }
+
+                    currTask.RestoreFp();
+                    currTask.AccountTaskEnter(SchedState::RunningApp);
+                    CPULocal::Myself().SetMode(VcpuMode::User);
+                    currTask.mm.HandleTlbShootdown();
+                    return;
+                }
+                _ => (),
+            }
+
             match currTask
                 .mm
                 .InstallPageLocked(currTask, &vma, pageAddr, &range)
@@ -572,6 +601,13 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
                 }
             }
 
+            // Charge the new pages to the memory limit of the container, which
+            // can't be done with the mapping lock held.
+            core::mem::drop(_ml);
+            if currTask.mm.ChargePending(currTask).is_err() && fromUser {
+                HandleFault(currTask, fromUser, errorCode, cr2, ptRegs, Signal::SIGBUS);
+            }
+
             if fromUser {
                 //PerfGoto(PerfType::User);
                 currTask.AccountTaskEnter(SchedState::RunningApp);
@@ -598,6 +634,11 @@ pub extern "C" fn PageFaultHandler(ptRegs: &mut PtRegs, errorCode: u64) {
 
             currTask.mm.CopyOnWriteLocked(pageAddr, &vma);
             currTask.mm.TlbShootdown();
+
+            core::mem::drop(_ml);
+            if currTask.mm.ChargePending(currTask).is_err() && fromUser {
+                HandleFault(currTask, fromUser, errorCode, cr2, ptRegs, Signal::SIGBUS);
+            }
             if fromUser {
                 //PerfGoto(PerfType::User);
                 currTask.AccountTaskEnter(SchedState::RunningApp);
@@ -730,7 +771,7 @@ pub extern "C" fn VirtualizationHandler(ptRegs: &mut PtRegs) {
         currTask.AccountTaskLeave(SchedState::RunningApp);
         //currTask.SaveFp();
         CPULocal::Myself().ResetEnterAppTimestamp();
-        super::qlib::kernel::taskMgr::Yield();
+        super::qlib::kernel::taskMgr::Preempt();
         MainRun(currTask, TaskRunState::RunApp);
         currTask.RestoreFp();
         CPULocal::Myself().SetEnterAppTimestamp(TSC.Rdtsc());
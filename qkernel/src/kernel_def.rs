@@ -214,6 +214,7 @@ impl CounterSet {
 pub fn switch(from: TaskId, to: TaskId) {
     //Task::Current().PerfGoto(PerfType::Blocked);
     Task::Current().AccountTaskEnter(SchedState::Blocked);
+    SchedSwitch(Some(from), to);
 
     CPULocal::SetCurrentTask(to.Addr());
     let fromCtx = from.GetTask();
@@ -361,6 +362,14 @@ pub fn child_clone(userSp: u64) {
     CPULocal::SetUserStack(userSp);
     CPULocal::SetKernelStack(currTask.GetKernelSp());
 
+    // A child auto-attached by its parent's tracer has a pending stop (or
+    // SIGSTOP) that must be reported before it runs any application code.
+    let traced = currTask.Thread().HasTracer();
+    if traced {
+        super::MainRun(currTask, TaskRunState::RunApp);
+        currTask.DoStop();
+    }
+
     currTask.AccountTaskEnter(SchedState::RunningApp);
     let pt = currTask.GetPtRegs();
 
@@ -369,6 +378,14 @@ pub fn child_clone(userSp: u64) {
     //currTask.mm.VcpuEnter();
     CPULocal::Myself().SetMode(VcpuMode::User);
     currTask.mm.HandleTlbShootdown();
+    if traced {
+        // The tracer may have changed the child's registers while it was
+        // stopped.
+        currTask.RestoreFp();
+        if !(pt.rip == pt.rcx && pt.r11 == pt.eflags) {
+            IRet(kernalRsp)
+        }
+    }
     SyscallRet(kernalRsp)
 }
 
@@ -21,12 +21,18 @@ pub mod sys_file;
 pub mod sys_futex;
 pub mod sys_getdents;
 pub mod sys_identity;
+pub mod sys_io_uring;
 pub mod sys_membarrier;
 pub mod sys_mempolicy;
 pub mod sys_mmap;
+pub mod sys_mount;
+pub mod sys_mqueue;
+pub mod sys_perf_event;
+pub mod sys_pidfd;
 pub mod sys_pipe;
 pub mod sys_poll;
 pub mod sys_prctl;
+pub mod sys_ptrace;
 pub mod sys_random;
 pub mod sys_read;
 pub mod sys_rlimit;
@@ -43,6 +49,7 @@ pub mod sys_time;
 pub mod sys_timer;
 pub mod sys_timerfd;
 pub mod sys_tls;
+pub mod sys_userfaultfd;
 pub mod sys_utsname;
 pub mod sys_write;
 pub mod syscalls;
@@ -23,6 +23,8 @@ use super::super::Kernel::HostSpace;
 use super::sys_file::*;
 
 fn Chmod(task: &Task, d: &Dirent, mode: FileMode) -> Result<()> {
+    task.mountNS.CheckWritable(d)?;
+
     // Must own file to change mode.
     let mut inode = d.Inode();
     if !inode.CheckOwnership(task) {
@@ -14,6 +14,7 @@
 
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 
 use super::super::fs::dirent::*;
 use super::super::fs::file::*;
@@ -34,6 +35,7 @@ use super::super::qlib::auth::*;
 use super::super::qlib::common::*;
 use super::super::qlib::limits::*;
 use super::super::qlib::linux::fcntl::*;
+use super::super::qlib::linux::openat2::*;
 use super::super::qlib::linux::time::*;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::path::*;
@@ -49,17 +51,28 @@ fn fileOpAt(
     dirFd: i32,
     path: &str,
     func: &mut FnMut(&Dirent, &Dirent, &str, u32) -> Result<()>,
+) -> Result<()> {
+    return fileOpAtWithResolve(task, dirFd, path, 0, func);
+}
+
+fn fileOpAtWithResolve(
+    task: &Task,
+    dirFd: i32,
+    path: &str,
+    resolveFlags: u64,
+    func: &mut FnMut(&Dirent, &Dirent, &str, u32) -> Result<()>,
 ) -> Result<()> {
     let (dir, name) = SplitLast(path);
 
-    if dir == "/" {
+    // The shortcuts below would bypass the openat2(2) RESOLVE_* checks.
+    if resolveFlags == 0 && dir == "/" {
         return func(
             &task.Root(),
             &task.Root(),
             &name.to_string(),
             MAX_SYMLINK_TRAVERSALS,
         );
-    } else if dir == "." && dirFd == AT_FDCWD {
+    } else if resolveFlags == 0 && dir == "." && dirFd == AT_FDCWD {
         return func(
             &task.Root(),
             &task.Workdir(),
@@ -68,11 +81,12 @@ fn fileOpAt(
         );
     }
 
-    return fileOpOn(
+    return fileOpOnWithResolve(
         task,
         dirFd,
         &dir.to_string(),
         true,
+        resolveFlags,
         &mut |root: &Dirent, d: &Dirent, remainingTraversals: u32| -> Result<()> {
             return func(root, d, &name.to_string(), remainingTraversals);
         },
@@ -85,11 +99,53 @@ pub fn fileOpOn(
     path: &str,
     resolve: bool,
     func: &mut FnMut(&Dirent, &Dirent, u32) -> Result<()>,
+) -> Result<()> {
+    return fileOpOnWithResolve(task, dirFd, path, resolve, 0, func);
+}
+
+// fileOpOnWithResolve is fileOpOn with the openat2(2) RESOLVE_* flags in
+// resolveFlags. With RESOLVE_BENEATH or RESOLVE_IN_ROOT the lookup is scoped
+// to dirFd, which is then passed to func as the root.
+pub fn fileOpOnWithResolve(
+    task: &Task,
+    dirFd: i32,
+    path: &str,
+    resolve: bool,
+    resolveFlags: u64,
+    func: &mut FnMut(&Dirent, &Dirent, u32) -> Result<()>,
 ) -> Result<()> {
     let d: Dirent;
     let wd: Dirent;
     let mut rel: Option<Dirent> = None;
 
+    if resolveFlags & RESOLVE_SCOPED != 0 {
+        // The scope is needed even for absolute paths.
+        let anchor = if dirFd == ATType::AT_FDCWD {
+            task.Workdir()
+        } else {
+            let file = task.GetFile(dirFd)?;
+            let inode = file.Dirent.Inode();
+            if !inode.StableAttr().IsDir() {
+                return Err(Error::SysError(SysErr::ENOTDIR));
+            }
+
+            file.Dirent.clone()
+        };
+
+        let mut remainTraversals = MAX_SYMLINK_TRAVERSALS;
+        d = task.mountNS.FindDirentWithResolve(
+            task,
+            &anchor,
+            Some(anchor.clone()),
+            path,
+            &mut remainTraversals,
+            resolve,
+            resolveFlags,
+        )?;
+
+        return func(&anchor, &d, remainTraversals);
+    }
+
     if path.len() > 0 && path.as_bytes()[0] == '/' as u8 {
         // Absolute path; rel can be nil.
     } else if dirFd == ATType::AT_FDCWD {
@@ -110,9 +166,15 @@ pub fn fileOpOn(
     let root = task.Root();
     let mut remainTraversals = MAX_SYMLINK_TRAVERSALS;
 
-    d = task
-        .mountNS
-        .FindDirent(task, &root, rel, path, &mut remainTraversals, resolve)?;
+    d = task.mountNS.FindDirentWithResolve(
+        task,
+        &root,
+        rel,
+        path,
+        &mut remainTraversals,
+        resolve,
+        resolveFlags,
+    )?;
 
     return func(&root, &d, remainTraversals);
 }
@@ -147,6 +209,120 @@ pub fn SysOpenAt(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Ok(res as i64);
 }
 
+fn copyInOpenHow(task: &Task, addr: u64, size: usize) -> Result<OpenHow> {
+    if size > MemoryDef::PAGE_SIZE as usize {
+        return Err(Error::SysError(SysErr::E2BIG));
+    }
+
+    if size < OPEN_HOW_SIZE_VER0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let mut how = OpenHow::default();
+    let ksize = core::mem::size_of::<OpenHow>();
+    let n = core::cmp::min(size, ksize);
+    let buf: Vec<u8> = task.CopyInVec(addr, n)?;
+    unsafe {
+        core::ptr::copy_nonoverlapping(buf.as_ptr(), &mut how as *mut _ as *mut u8, n);
+    }
+
+    // A newer struct is only accepted if the fields we don't know about are
+    // unset.
+    if size > ksize {
+        let rest: Vec<u8> = task.CopyInVec(addr + ksize as u64, size - ksize)?;
+        if rest.iter().any(|b| *b != 0) {
+            return Err(Error::SysError(SysErr::E2BIG));
+        }
+    }
+
+    return Ok(how);
+}
+
+// Openat2 implements linux syscall openat2(2).
+pub fn SysOpenat2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let dirFd = args.arg0 as i32;
+    let addr = args.arg1 as u64;
+    let howAddr = args.arg2 as u64;
+    let size = args.arg3 as usize;
+
+    let how = copyInOpenHow(task, howAddr, size)?;
+
+    // Unlike openat(2), openat2(2) rejects unknown flags and modes instead of
+    // silently ignoring them. Source: fs/open.c:build_open_flags()
+    const VALID_OPEN_FLAGS: u64 = (Flags::O_ACCMODE
+        | Flags::O_CREAT
+        | Flags::O_EXCL
+        | Flags::O_NOCTTY
+        | Flags::O_TRUNC
+        | Flags::O_APPEND
+        | Flags::O_NONBLOCK
+        | Flags::O_DSYNC
+        | Flags::O_ASYNC
+        | Flags::O_DIRECT
+        | Flags::O_LARGEFILE
+        | Flags::O_DIRECTORY
+        | Flags::O_NOFOLLOW
+        | Flags::O_NOATIME
+        | Flags::O_CLOEXEC
+        | Flags::O_SYNC
+        | Flags::O_PATH
+        | Flags::O_TMPFILE) as u64;
+    const O_PATH_FLAGS: u64 =
+        (Flags::O_DIRECTORY | Flags::O_NOFOLLOW | Flags::O_PATH | Flags::O_CLOEXEC) as u64;
+
+    if how.Flags & !VALID_OPEN_FLAGS != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if how.Flags & (Flags::O_CREAT | Flags::O_TMPFILE) as u64 != 0 {
+        if how.Mode & !0o7777 != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+    } else if how.Mode != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if how.Flags & Flags::O_PATH as u64 != 0 && how.Flags & !O_PATH_FLAGS != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if how.Resolve & !RESOLVE_ALL != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // RESOLVE_BENEATH and RESOLVE_IN_ROOT are mutually exclusive.
+    if how.Resolve & RESOLVE_SCOPED == RESOLVE_SCOPED {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // We don't have a dcache-only lookup, so RESOLVE_CACHED is always
+    // satisfied, except for the operations Linux refuses to do without
+    // blocking.
+    if how.Resolve & RESOLVE_CACHED != 0
+        && how.Flags & (Flags::O_CREAT | Flags::O_TRUNC | Flags::O_TMPFILE) as u64 != 0
+    {
+        return Err(Error::SysError(SysErr::EAGAIN));
+    }
+
+    let cloexec = how.Flags as u32 & Flags::O_CLOEXEC as u32;
+    let flags = CleanOpenFlags(how.Flags as i32)? as u32 | cloexec;
+
+    if flags & Flags::O_CREAT as u32 != 0 {
+        let res = createAtWithResolve(
+            task,
+            dirFd,
+            addr,
+            flags,
+            FileMode(how.Mode as u16),
+            how.Resolve,
+        )?;
+        return Ok(res as i64);
+    }
+
+    let res = openAtWithResolve(task, dirFd, addr, flags, how.Resolve)?;
+    return Ok(res as i64);
+}
+
 pub fn CleanOpenFlags(flags: i32) -> Result<i32> {
     let mut flags = flags & (Flags::O_ACCMODE
         | Flags::O_CREAT
@@ -232,6 +408,16 @@ pub fn SysCreate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 }
 
 pub fn openAt(task: &Task, dirFd: i32, addr: u64, flags: u32) -> Result<i32> {
+    return openAtWithResolve(task, dirFd, addr, flags, 0);
+}
+
+pub fn openAtWithResolve(
+    task: &Task,
+    dirFd: i32,
+    addr: u64,
+    flags: u32,
+    resolveFlags: u64,
+) -> Result<i32> {
     //task.PerfGoto(PerfType::Open);
     //defer!(task.PerfGofrom(PerfType::Open));
 
@@ -248,16 +434,21 @@ pub fn openAt(task: &Task, dirFd: i32, addr: u64, flags: u32) -> Result<i32> {
     let resolve = !fileFlags.NoFollow && !fileFlags.Path;
     let mut fd = -1;
 
-    fileOpOn(
+    fileOpOnWithResolve(
         task,
         dirFd,
         &path,
         resolve,
+        resolveFlags,
         &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
             let mut inode = d.Inode();
 
             if !fileFlags.Path {
-                inode.CheckPermission(task, &PermMask::FromFlags(flags))?;
+                let perms = PermMask::FromFlags(flags);
+                inode.CheckPermission(task, &perms)?;
+                if perms.write {
+                    task.mountNS.CheckWritable(d)?;
+                }
             }
 
             if inode.StableAttr().IsSymlink() && !resolve && !fileFlags.Path {
@@ -401,6 +592,17 @@ pub fn mknodeAt(task: &Task, dirFd: i32, addr: u64, mode: FileMode) -> Result<()
 }
 
 pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode) -> Result<i32> {
+    return createAtWithResolve(task, dirFd, addr, flags, mode, 0);
+}
+
+pub fn createAtWithResolve(
+    task: &Task,
+    dirFd: i32,
+    addr: u64,
+    flags: u32,
+    mode: FileMode,
+    resolveFlags: u64,
+) -> Result<i32> {
     let (path, dirPath) = copyInPath(task, addr, false)?;
 
     info!(
@@ -425,10 +627,10 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
     let mut fd = 0;
     let mnt = task.mountNS.clone();
 
-    fileOpAt(task, dirFd, &path, &mut |root: &Dirent,
-                                       parent: &Dirent,
-                                       name: &str,
-                                       remainingTraversals: u32|
+    fileOpAtWithResolve(task, dirFd, &path, resolveFlags, &mut |root: &Dirent,
+                                                                parent: &Dirent,
+                                                                name: &str,
+                                                                remainingTraversals: u32|
      -> Result<()> {
         let mut found = parent.clone();
 
@@ -443,13 +645,14 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
                 return Err(Error::SysError(SysErr::ENOTDIR));
             }
 
-            found = match mnt.FindDirent(
+            found = match mnt.FindDirentWithResolve(
                 task,
                 root,
                 Some(parent.clone()),
                 &name,
                 &mut remainingTraversals,
                 false,
+                resolveFlags,
             ) {
                 Ok(d) => d,
                 Err(e) => {
@@ -475,6 +678,8 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
                 return Err(Error::SysError(SysErr::ELOOP));
             }
 
+            mnt.CheckResolveLink(task, &found, resolveFlags)?;
+
             match foundInode.GetLink(task) {
                 Err(Error::ErrResolveViaReadlink) => (),
                 Err(e) => return Err(e),
@@ -496,13 +701,14 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
             remainingTraversals -= 1;
 
             let (newParentPath, newName) = SplitLast(&path);
-            let newParent = match mnt.FindDirent(
+            let newParent = match mnt.FindDirentWithResolve(
                 task,
                 root,
                 Some(parent.clone()),
                 &newParentPath.to_string(),
                 &mut remainingTraversals,
                 true,
+                resolveFlags,
             ) {
                 Err(e) => {
                     err = e;
@@ -519,6 +725,10 @@ pub fn createAt(task: &Task, dirFd: i32, addr: u64, flags: u32, mode: FileMode)
             Error::None => {
                 let mut foundInode = found.Inode();
 
+                if fileFlags.Write || flags & Flags::O_TRUNC as u32 != 0 {
+                    task.mountNS.CheckWritable(&found)?;
+                }
+
                 if flags & Flags::O_TRUNC as u32 != 0 {
                     if foundInode.StableAttr().IsDir() {
                         return Err(Error::SysError(SysErr::EISDIR))
@@ -588,7 +798,7 @@ pub fn SysAccess(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let pathName = args.arg0 as u64;
     let mode = args.arg1 as u32;
 
-    accessAt(task, ATType::AT_FDCWD, pathName, mode)?;
+    accessAt(task, ATType::AT_FDCWD, pathName, mode, 0)?;
     return Ok(0);
 }
 
@@ -597,58 +807,95 @@ pub fn SysFaccessat(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let addr = args.arg1 as u64;
     let mode = args.arg2 as u16 as u32;
 
-    accessAt(
-        task,
-        dirfd,
-        addr,
-        mode,
-    )?;
+    accessAt(task, dirfd, addr, mode, 0)?;
+    return Ok(0);
+}
+
+// Faccessat2 implements linux syscall faccessat2(2).
+pub fn SysFaccessat2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let dirfd = args.arg0 as i32;
+    let addr = args.arg1 as u64;
+    let mode = args.arg2 as u16 as u32;
+    let flags = args.arg3 as i32;
+
+    if flags & !(ATType::AT_EACCESS | ATType::AT_SYMLINK_NOFOLLOW | ATType::AT_EMPTY_PATH) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    accessAt(task, dirfd, addr, mode, flags)?;
     return Ok(0);
 }
 
-pub fn accessAt(task: &Task, dirFd: i32, addr: u64, mode: u32) -> Result<()> {
+pub fn accessAt(task: &mut Task, dirFd: i32, addr: u64, mode: u32, flags: i32) -> Result<()> {
     const R_OK: u32 = 4;
     const W_OK: u32 = 2;
     const X_OK: u32 = 1;
 
-    let (path, _) = copyInPath(task, addr, false)?;
+    let (path, _) = copyInPath(task, addr, flags & ATType::AT_EMPTY_PATH != 0)?;
 
     info!("accessAt dirfd is {}, path is {}", dirFd, &path);
     if mode & !(R_OK | W_OK | X_OK) != 0 {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
-    return fileOpOn(
-        task,
-        dirFd,
-        &path.to_string(),
-        true,
-        &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
-            {
-                let creds = task.Creds().Fork();
-                let mut creds = creds.lock();
+    // access(2) checks the real user and group IDs rather than the effective
+    // ones, for the whole lookup. faccessat2(2) uses the effective IDs as
+    // usual when given AT_EACCESS.
+    let savedCreds = task.creds.clone();
+    if flags & ATType::AT_EACCESS == 0 {
+        let creds = savedCreds.Fork();
+        {
+            let mut creds = creds.lock();
 
-                creds.EffectiveKUID = creds.RealKUID;
-                creds.EffectiveKGID = creds.RealKGID;
+            creds.EffectiveKUID = creds.RealKUID;
+            creds.EffectiveKGID = creds.RealKGID;
 
-                if creds.EffectiveKGID.In(&creds.UserNamespace).0 == ROOT_UID.0 {
-                    creds.EffectiveCaps = creds.PermittedCaps
-                } else {
-                    creds.EffectiveCaps = CapSet::New(0)
-                }
+            if creds.EffectiveKUID.In(&creds.UserNamespace).0 == ROOT_UID.0 {
+                creds.EffectiveCaps = creds.PermittedCaps
+            } else {
+                creds.EffectiveCaps = CapSet::New(0)
             }
+        }
 
-            let inode = d.Inode();
-            return inode.CheckPermission(
+        task.creds = creds;
+    }
+
+    let perms = PermMask {
+        read: mode & R_OK != 0,
+        write: mode & W_OK != 0,
+        execute: mode & X_OK != 0,
+    };
+
+    let res = {
+        let task: &Task = task;
+        let check = |d: &Dirent| -> Result<()> {
+            if perms.write {
+                task.mountNS.CheckWritable(d)?;
+            }
+
+            return d.Inode().CheckPermission(task, &perms);
+        };
+
+        if path == "" {
+            match task.GetFile(dirFd) {
+                Err(e) => Err(e),
+                Ok(file) => check(&file.Dirent),
+            }
+        } else {
+            fileOpOn(
                 task,
-                &PermMask {
-                    read: mode & R_OK != 0,
-                    write: mode & W_OK != 0,
-                    execute: mode & X_OK != 0,
+                dirFd,
+                &path.to_string(),
+                flags & ATType::AT_SYMLINK_NOFOLLOW == 0,
+                &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+                    return check(d);
                 },
-            );
-        },
-    );
+            )
+        }
+    };
+
+    task.creds = savedCreds;
+    return res;
 }
 
 pub fn SysIoctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
@@ -1086,7 +1333,12 @@ pub fn FSetOwner(task: &Task, fd: i32, file: &File, who: i32) -> Result<()> {
     return Ok(());
 }
 
-pub fn PosixLock(task: &Task, flockAddr: u64, file: &File, block: bool) -> Result<()> {
+// PosixLock implements F_SETLK(W) and, if ofd is set, F_OFD_SETLK(W).
+//
+// POSIX locks are owned by the fd table of the process and OFD locks by the
+// open file description, both live in the same lock set of the inode so that
+// they conflict with each other as in Linux.
+pub fn PosixLock(task: &Task, flockAddr: u64, file: &File, block: bool, ofd: bool) -> Result<()> {
     let inode = file.Dirent.Inode();
     // In Linux the file system can choose to provide lock operations for an inode.
     // Normally pipe and socket types lack lock operations. We diverge and use a heavy
@@ -1097,55 +1349,98 @@ pub fn PosixLock(task: &Task, flockAddr: u64, file: &File, block: bool) -> Resul
 
     let flock: Flock = task.CopyInObj(flockAddr)?;
 
+    // fcntl(2): "l_pid must be set to zero" for open file description locks.
+    if ofd && flock.Pid != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
     let rng = file.ComputeLockRange(task, flock.Start, flock.Len, flock.Whence as _)?;
 
-    // The lock uid is that of the fdtble's UniqueId.
-    let lockUniqueID = task.fdTbl.Id();
+    // The lock uid is that of the fdtble's UniqueId, or of the file for OFD locks.
+    let (lockUniqueID, pid) = if ofd {
+        (file.UniqueId(), OFD_LOCK_PID)
+    } else {
+        (task.fdTbl.Id(), task.Thread().ThreadGroup().ID())
+    };
 
     // These locks don't block; execute the non-blocking operation using the inode's lock
     // context directly.
     let fflags = file.Flags();
 
-    let pid = task.Thread().ThreadGroup().ID();
-    match flock.Type as u64 {
+    let lock = inode.lock().LockCtx.Posix.clone();
+    let typ = match flock.Type as u64 {
         LibcConst::F_RDLCK => {
             if !fflags.Read {
                 return Err(Error::SysError(SysErr::EBADF));
             }
 
-            let lock = inode.lock().LockCtx.Posix.clone();
-            if !lock.LockRegion(task, lockUniqueID, OwnerInfo::New(pid), LockType::ReadLock, &rng, block)? {
-                return Err(Error::SysError(SysErr::EAGAIN));
-            }
-
-            return Ok(());
+            LockType::ReadLock
         }
         LibcConst::F_WRLCK => {
             if !fflags.Write {
                 return Err(Error::SysError(SysErr::EBADF));
             }
 
-            let lock = inode.lock().LockCtx.Posix.clone();
-            if !lock.LockRegion(task, lockUniqueID, OwnerInfo::New(pid), LockType::WriteLock, &rng, block)? {
-                return Err(Error::SysError(SysErr::EAGAIN));
-            }
-
-            return Ok(());
+            LockType::WriteLock
         }
         LibcConst::F_UNLCK => {
-            let lock = inode.lock().LockCtx.Posix.clone();
             lock.UnlockRegion(task, lockUniqueID, &rng);
+            if let Some(fd) = file.HostLockFd() {
+                lock.SyncHostRegion(fd, &rng)?;
+            }
 
             return Ok(());
         }
         _ => return Err(Error::SysError(SysErr::EINVAL)),
+    };
+
+    if !lock.LockRegion(task, lockUniqueID, OwnerInfo::New(pid), typ, &rng, block)? {
+        return Err(Error::SysError(SysErr::EAGAIN));
     }
 
+    let fd = match file.HostLockFd() {
+        None => return Ok(()),
+        Some(fd) => fd,
+    };
+
+    // Mirror the lock to the host once it is held in the guest. The host lock
+    // can't be waited for without blocking the vcpu, so a blocking request
+    // polls it. If the host lock can't be taken, the range is unlocked in the
+    // guest, which unlike Linux also drops a lock previously held there.
+    loop {
+        let err = match lock.SyncHostRegion(fd, &rng) {
+            Ok(true) => return Ok(()),
+            Ok(false) => {
+                if !block {
+                    Error::SysError(SysErr::EAGAIN)
+                } else {
+                    let (_, res) = task
+                        .blocker
+                        .BlockWithMonoTimeout(false, Some(HOST_LOCK_RETRY_INTERVAL));
+                    match res {
+                        Err(Error::SysError(SysErr::ETIMEDOUT)) | Ok(()) => continue,
+                        Err(Error::ErrInterrupted) => Error::SysError(SysErr::ERESTARTSYS),
+                        Err(e) => e,
+                    }
+                }
+            }
+            Err(e) => e,
+        };
+
+        lock.UnlockRegion(task, lockUniqueID, &rng);
+        lock.SyncHostRegion(fd, &rng)?;
+        return Err(err);
+    }
 }
 
-pub fn PosixTestLock(task: &Task, flockAddr: u64, file: &File) -> Result<()> {
+// PosixTestLock implements F_GETLK and, if ofd is set, F_OFD_GETLK.
+pub fn PosixTestLock(task: &Task, flockAddr: u64, file: &File, ofd: bool) -> Result<()> {
     let flock: Flock = task.CopyInObj(flockAddr)?;
 
+    if ofd && flock.Pid != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
     let typ = match flock.Type as i32 {
         F_RDLCK => LockType::ReadLock,
         F_WRLCK => LockType::WriteLock,
@@ -1154,8 +1449,12 @@ pub fn PosixTestLock(task: &Task, flockAddr: u64, file: &File) -> Result<()> {
 
     let r = file.ComputeLockRange(task, flock.Start, flock.Len, flock.Whence as _)?;
 
-    // The lock uid is that of the fdtble's UniqueId.
-    let lockUniqueID = task.fdTbl.Id();
+    // The lock uid is that of the fdtble's UniqueId, or of the file for OFD locks.
+    let lockUniqueID = if ofd {
+        file.UniqueId()
+    } else {
+        task.fdTbl.Id()
+    };
     let inode = file.Dirent.Inode();
     let lock = inode.lock().LockCtx.Posix.clone();
     let newFlock = lock.TestRegion(task, lockUniqueID, typ, &r);
@@ -1206,28 +1505,28 @@ pub fn SysFcntl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             file.SetFlags(task, FileFlags::FromFlags(flags).SettableFileFlags());
             Ok(0)
         }
-        Cmd::F_SETLK => {
+        Cmd::F_SETLK | Cmd::F_OFD_SETLK => {
             if file.Flags().Path {
                 return Err(Error::SysError(SysErr::EBADF));
             }
 
-            PosixLock(task, val, &file, false)?;
+            PosixLock(task, val, &file, false, cmd == Cmd::F_OFD_SETLK)?;
             return Ok(0)
         }
-        Cmd::F_SETLKW => {
+        Cmd::F_SETLKW | Cmd::F_OFD_SETLKW => {
             if file.Flags().Path {
                 return Err(Error::SysError(SysErr::EBADF));
             }
 
-            PosixLock(task, val, &file, true)?;
+            PosixLock(task, val, &file, true, cmd == Cmd::F_OFD_SETLKW)?;
             return Ok(0)
         }
-        Cmd::F_GETLK => {
+        Cmd::F_GETLK | Cmd::F_OFD_GETLK => {
             if file.Flags().Path {
                 return Err(Error::SysError(SysErr::EBADF));
             }
 
-            PosixTestLock(task, val, &file)?;
+            PosixTestLock(task, val, &file, cmd == Cmd::F_OFD_GETLK)?;
             return Ok(0)
         }
         Cmd::F_GETOWN => {
@@ -1947,6 +2246,7 @@ pub fn SysTruncate(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
                 },
             )?;
 
+            task.mountNS.CheckWritable(d)?;
             inode.Truncate(task, d, len)?;
 
             // File length modified, generate notification.
@@ -1997,6 +2297,8 @@ pub fn SysUmask(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 }
 
 fn chown(task: &Task, d: &Dirent, uid: UID, gid: GID) -> Result<i64> {
+    task.mountNS.CheckWritable(d)?;
+
     let mut owner = FileOwner {
         UID: KUID(NO_ID),
         GID: KGID(NO_ID),
@@ -2147,6 +2449,8 @@ pub fn SysFchownat(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
 
 fn utime(task: &Task, dirfd: i32, addr: u64, ts: &InterTimeSpec, resolve: bool) -> Result<i64> {
     let setTimestamp = &mut |_root: &Dirent, d: &Dirent, _remainingTraversals: u32| -> Result<()> {
+        task.mountNS.CheckWritable(d)?;
+
         let mut inode = d.Inode();
         if !inode.CheckOwnership(task) {
             if (ts.ATimeOmit || !ts.ATimeSetSystemTime) && (ts.MTimeOmit || !ts.MTimeSetSystemTime)
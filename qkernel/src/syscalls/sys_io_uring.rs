@@ -0,0 +1,829 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use super::super::fs::attr::*;
+use super::super::fs::file::*;
+use super::super::fs::inotify::*;
+use super::super::kernel::fd_table::*;
+use super::super::kernel::timer::*;
+use super::super::kernel::waiter::*;
+use super::super::qlib::common::*;
+use super::super::qlib::kernel::kernel::io_uring::*;
+use super::super::qlib::linux::io_uring::*;
+use super::super::qlib::linux::time::*;
+use super::super::qlib::linux_def::*;
+use super::super::quring::uring_async::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+use super::super::SignalDef::*;
+use super::super::IOURING;
+use super::sys_file::*;
+use super::sys_socket::*;
+use super::sys_splice::MAX_RW_COUNT;
+use super::sys_stat::*;
+
+// GetIoUring returns the io_uring instance of the file descriptor fd.
+pub fn GetIoUring(task: &Task, fd: i32) -> Result<IoUring> {
+    let file = task.GetFile(fd)?;
+
+    match file.FileOp {
+        FileOps::IoUringOperations(ref ops) => return Ok(ops.ring.clone()),
+        _ => return Err(Error::SysError(SysErr::EOPNOTSUPP)),
+    }
+}
+
+// IoUringSetup implements linux syscall io_uring_setup(2).
+pub fn SysIoUringSetup(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let entries = args.arg0 as u32;
+    let paramsAddr = args.arg1 as u64;
+
+    let mut params: IoUringParams = task.CopyInObj(paramsAddr)?;
+
+    for r in &params.Resv {
+        if *r != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+    }
+
+    // There is no SQ polling thread and no polled (IOPOLL) completion: the
+    // rings are only ever driven by io_uring_enter(2).
+    if params.Flags & !(IORING_SETUP_CQSIZE | IORING_SETUP_CLAMP) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if entries == 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let mut entries = entries;
+    if entries > IORING_MAX_ENTRIES {
+        if params.Flags & IORING_SETUP_CLAMP == 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+        entries = IORING_MAX_ENTRIES;
+    }
+
+    // "Use twice as many entries for the CQ ring. It's possible for the
+    // application to drive a higher depth than the size of the SQ ring,
+    // since the sqes are only used at submission time." - io_uring.c
+    let sqEntries = entries.next_power_of_two();
+    let cqEntries = if params.Flags & IORING_SETUP_CQSIZE != 0 {
+        let mut cqEntries = params.CqEntries;
+        if cqEntries == 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        if cqEntries > IORING_MAX_CQ_ENTRIES {
+            if params.Flags & IORING_SETUP_CLAMP == 0 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+            cqEntries = IORING_MAX_CQ_ENTRIES;
+        }
+
+        let cqEntries = cqEntries.next_power_of_two();
+        if cqEntries < sqEntries {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+        cqEntries
+    } else {
+        2 * sqEntries
+    };
+
+    let ring = IoUring::New(task, sqEntries, cqEntries, &mut params)?;
+    task.CopyOutObj(&params, paramsAddr)?;
+
+    let file = NewIoUringFile(task, ring);
+    let fd = task.NewFDFrom(0, &file, &FDFlags { CloseOnExec: true })?;
+    return Ok(fd as i64);
+}
+
+// IoUringEnter implements linux syscall io_uring_enter(2).
+pub fn SysIoUringEnter(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let toSubmit = args.arg1 as u32;
+    let minComplete = args.arg2 as u32;
+    let flags = args.arg3 as u32;
+    let sigAddr = args.arg4 as u64;
+    let sigSize = args.arg5 as usize;
+
+    // IORING_ENTER_EXT_ARG is not supported, as IORING_FEAT_EXT_ARG is not
+    // advertised. The SQ_WAKEUP and SQ_WAIT flags are no-ops without an SQ
+    // polling thread.
+    if flags & !(IORING_ENTER_GETEVENTS | IORING_ENTER_SQ_WAKEUP | IORING_ENTER_SQ_WAIT) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let ring = GetIoUring(task, fd)?;
+
+    Reissue(task, &ring);
+    ring.FlushOverflow();
+
+    // As io_submit_sqes() does, a call consumes no more SQEs than the SQ
+    // ring holds, so the application can't keep it submitting by moving the
+    // tail. Nothing is submitted while completions are left in the overflow
+    // list, which bounds it.
+    let sqEntries = ring.lock().sqEntries;
+    let toSubmit = core::cmp::min(toSubmit, core::cmp::min(sqEntries, ring.SqReady()));
+    if toSubmit > 0 && ring.lock().overflow.len() > 0 {
+        return Err(Error::SysError(SysErr::EBUSY));
+    }
+
+    let mut submitted = 0;
+    while submitted < toSubmit {
+        let sqe = match ring.NextSqe() {
+            None => break,
+            Some(sqe) => sqe,
+        };
+
+        submitted += 1;
+        Submit(task, &ring, &sqe);
+    }
+
+    if flags & IORING_ENTER_GETEVENTS != 0 && minComplete > 0 {
+        if sigAddr != 0 {
+            let mask = CopyInSigSet(task, sigAddr, sigSize)?;
+            let thread = task.Thread();
+            let oldmask = thread.SignalMask();
+            thread.SetSignalMask(mask);
+            thread.SetSavedSignalMask(oldmask);
+        }
+
+        let minComplete = core::cmp::min(minComplete, ring.lock().cqEntries);
+        match WaitCqes(task, &ring, minComplete) {
+            Err(e) => {
+                if submitted == 0 {
+                    return Err(e);
+                }
+            }
+            Ok(()) => (),
+        }
+    }
+
+    return Ok(submitted as i64);
+}
+
+// WaitCqes waits for the CQ ring to hold at least minComplete completions.
+fn WaitCqes(task: &mut Task, ring: &IoUring, minComplete: u32) -> Result<()> {
+    let queue = ring.lock().queue.clone();
+    let general = task.blocker.generalEntry.clone();
+
+    queue.EventRegister(task, &general, READABLE_EVENT);
+    let res = loop {
+        Reissue(task, ring);
+        ring.FlushOverflow();
+        if ring.CqReady() >= minComplete {
+            break Ok(());
+        }
+
+        match task.blocker.BlockWithMonoTimer(true, None) {
+            Err(Error::ErrInterrupted) => break Err(Error::SysError(SysErr::EINTR)),
+            Err(e) => break Err(e),
+            _ => (),
+        }
+    };
+    queue.EventUnregister(task, &general);
+
+    return res;
+}
+
+// Reissue reissues the requests whose file became ready. A request is
+// canceled if the ring is entered from another address space than the one
+// it was submitted from, e.g. by a child after fork(2) or by a process the
+// ring was passed to, as its buffers are in the submitter's memory.
+fn Reissue(task: &mut Task, ring: &IoUring) {
+    ring.Cleanup(task);
+
+    let ready = ring.TakeReady();
+    for req in ready {
+        let res = if req.mm.ID() != task.mm.ID() {
+            Err(Error::SysError(SysErr::ECANCELED))
+        } else {
+            Execute(task, ring, &req)
+        };
+        Complete(ring, &req.sqe, res);
+    }
+}
+
+// Submit issues the request of one SQE.
+fn Submit(task: &mut Task, ring: &IoUring, sqe: &IoUringSqe) {
+    let res = Issue(task, ring, sqe);
+    Complete(ring, sqe, res);
+}
+
+fn Complete(ring: &IoUring, sqe: &IoUringSqe, res: Result<Option<i64>>) {
+    let res = match res {
+        // The request completes asynchronously.
+        Ok(None) => return,
+        Ok(Some(n)) => n as i32,
+        Err(Error::SysError(e)) => -e,
+        Err(Error::ErrInterrupted) => -SysErr::EINTR,
+        Err(_) => -SysErr::EIO,
+    };
+
+    ring.PostCompletion(sqe.UserData, res, true);
+}
+
+fn NeedsFile(opcode: u8) -> bool {
+    match opcode {
+        IORING_OP_READV | IORING_OP_WRITEV | IORING_OP_READ | IORING_OP_WRITE | IORING_OP_FSYNC
+        | IORING_OP_POLL_ADD | IORING_OP_ACCEPT | IORING_OP_CONNECT | IORING_OP_SEND
+        | IORING_OP_RECV => return true,
+        _ => return false,
+    }
+}
+
+fn Issue(task: &mut Task, ring: &IoUring, sqe: &IoUringSqe) -> Result<Option<i64>> {
+    // Fixed files, drains, links and provided buffers are not supported;
+    // every request is issued on its own. IOSQE_ASYNC is a hint only.
+    if sqe.Flags & !IOSQE_ASYNC != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // Personalities and fixed buffers are not supported either.
+    if sqe.Personality != 0 || sqe.BufIndex != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if sqe.Opcode >= IORING_OP_LAST {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // Requests hold on to their file, so that they still complete if the
+    // file descriptor is closed while they are in flight.
+    let file = if NeedsFile(sqe.Opcode) {
+        Some(task.GetFile(sqe.Fd)?)
+    } else {
+        None
+    };
+
+    let req = IoUringRequest {
+        sqe: *sqe,
+        file: file,
+        connecting: false,
+        mm: task.mm.clone(),
+    };
+
+    return Execute(task, ring, &req);
+}
+
+// Execute runs a request. It returns None if the request completes
+// asynchronously: either it was handed to QUring, or it waits for its file
+// to become ready.
+fn Execute(task: &mut Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    match req.sqe.Opcode {
+        IORING_OP_NOP => return Ok(Some(0)),
+        IORING_OP_READV | IORING_OP_READ => return ExecuteRead(task, ring, req),
+        IORING_OP_WRITEV | IORING_OP_WRITE => return ExecuteWrite(task, ring, req),
+        IORING_OP_FSYNC => return ExecuteFsync(task, ring, req),
+        IORING_OP_POLL_ADD => return ExecutePollAdd(task, ring, req),
+        IORING_OP_TIMEOUT => return ExecuteTimeout(task, ring, req),
+        IORING_OP_ACCEPT => return ExecuteAccept(task, ring, req),
+        IORING_OP_CONNECT => return ExecuteConnect(task, ring, req),
+        IORING_OP_SEND => return ExecuteSend(task, ring, req),
+        IORING_OP_RECV => return ExecuteRecv(task, ring, req),
+        IORING_OP_OPENAT => return ExecuteOpenat(task, req),
+        IORING_OP_CLOSE => return ExecuteClose(task, req),
+        IORING_OP_STATX => return ExecuteStatx(task, req),
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+}
+
+// Supported returns whether the opcode is implemented.
+fn Supported(opcode: u8) -> bool {
+    match opcode {
+        IORING_OP_NOP | IORING_OP_READV | IORING_OP_WRITEV | IORING_OP_FSYNC
+        | IORING_OP_POLL_ADD | IORING_OP_TIMEOUT | IORING_OP_ACCEPT | IORING_OP_CONNECT
+        | IORING_OP_OPENAT | IORING_OP_CLOSE | IORING_OP_STATX | IORING_OP_READ
+        | IORING_OP_WRITE | IORING_OP_SEND | IORING_OP_RECV => return true,
+        _ => return false,
+    }
+}
+
+// WaitReady parks the request until its file signals one of the events in
+// mask. It is then reissued by io_uring_enter(2).
+fn WaitReady(
+    task: &Task,
+    ring: &IoUring,
+    req: &IoUringRequest,
+    mask: EventMask,
+) -> Result<Option<i64>> {
+    let file = req.file.clone().unwrap();
+    ring.ArmPoll(task, &file, mask, IoUringPollKind::Retry(req.clone()));
+    return Ok(None);
+}
+
+// HostRegularFd returns the host fd backing file if it is a host regular
+// file, whose I/O can be handed to QUring.
+fn HostRegularFd(file: &File) -> Option<i32> {
+    let inode = file.Dirent.Inode();
+    if inode.InodeType() != InodeType::RegularFile {
+        return None;
+    }
+
+    let iops = inode.lock().InodeOp.clone();
+    match iops.HostInodeOp() {
+        None => return None,
+        Some(h) => return Some(h.HostFd()),
+    }
+}
+
+// RwIovs returns the buffers of a read or write request. As
+// rw_copy_check_uvector() does, the buffers are validated and the request is
+// truncated to MAX_RW_COUNT bytes, which also bounds the buffer a request
+// handed to QUring is staged in.
+fn RwIovs(task: &Task, sqe: &IoUringSqe) -> Result<Vec<IoVec>> {
+    let iovs = match sqe.Opcode {
+        IORING_OP_READV | IORING_OP_WRITEV => task.IovsFromAddr(sqe.Addr, sqe.Len as usize)?,
+        _ => vec![IoVec::NewFromAddr(sqe.Addr, sqe.Len as usize)],
+    };
+
+    let mut left = MAX_RW_COUNT as usize;
+    let mut ret = Vec::with_capacity(iovs.len());
+    for iov in &iovs {
+        if iov.len > i64::MAX as usize {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        if iov.start.checked_add(iov.len as u64).is_none() {
+            return Err(Error::SysError(SysErr::EFAULT));
+        }
+
+        let len = iov.len.min(left);
+        if len > 0 {
+            ret.push(IoVec::NewFromAddr(iov.start, len));
+            left -= len;
+        }
+    }
+
+    return Ok(ret);
+}
+
+fn ExecuteRead(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+    let offset = sqe.Off as i64;
+    let rwFlags = sqe.OpFlags as i32;
+
+    if rwFlags & !(Flags::RWF_VALID | Flags::RWF_NOWAIT) != 0 {
+        return Err(Error::SysError(SysErr::EOPNOTSUPP));
+    }
+
+    // An offset of -1 uses the file position (IORING_FEAT_RW_CUR_POS).
+    if offset < -1 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if !file.Flags().Read {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    let iovs = RwIovs(task, sqe)?;
+
+    // Positional reads of host files are handed to QUring, as io_submit(2)
+    // does.
+    if offset >= 0 && file.Flags().Pread {
+        if let Some(fd) = HostRegularFd(file) {
+            let ops = IoUringRead::New(task, ring, file, fd, iovs, offset, sqe.UserData)?;
+            IOURING.AUCall(AsyncOps::IoUringRead(ops));
+            return Ok(None);
+        }
+    }
+
+    let mut iovs = task.AdjustIOVecPermission(&iovs, true, true)?;
+    let res = if !file.FileOp.Seekable() {
+        file.FileOp.ReadAt(task, file, &mut iovs, 0, false)
+    } else if offset == -1 {
+        file.Readv(task, &mut iovs)
+    } else if !file.Flags().Pread {
+        Err(Error::SysError(SysErr::ESPIPE))
+    } else {
+        file.Preadv(task, &mut iovs, offset)
+    };
+
+    match res {
+        Err(Error::SysError(SysErr::EWOULDBLOCK)) if rwFlags & Flags::RWF_NOWAIT == 0 => {
+            return WaitReady(task, ring, req, EVENT_READ);
+        }
+        Err(e) => return Err(e),
+        Ok(n) => {
+            if n > 0 {
+                file.Dirent
+                    .InotifyEvent(InotifyEvent::IN_ACCESS, 0, EventType::InodeEvent);
+            }
+            return Ok(Some(n));
+        }
+    }
+}
+
+fn ExecuteWrite(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+    let offset = sqe.Off as i64;
+    let rwFlags = sqe.OpFlags as i32;
+
+    if rwFlags & !(Flags::RWF_HIPRI | Flags::RWF_DSYNC | Flags::RWF_SYNC | Flags::RWF_NOWAIT) != 0 {
+        return Err(Error::SysError(SysErr::EOPNOTSUPP));
+    }
+
+    if offset < -1 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if !file.Flags().Write {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    let iovs = RwIovs(task, sqe)?;
+
+    if offset >= 0 && file.Flags().Pwrite {
+        if let Some(fd) = HostRegularFd(file) {
+            let ops = IoUringWrite::New(task, ring, file, fd, &iovs, offset, sqe.UserData)?;
+            IOURING.AUCall(AsyncOps::IoUringWrite(ops));
+            return Ok(None);
+        }
+    }
+
+    let iovs = task.AdjustIOVecPermission(&iovs, false, true)?;
+    let res = if !file.FileOp.Seekable() {
+        file.FileOp.WriteAt(task, file, &iovs, 0, false)
+    } else if offset == -1 {
+        file.Writev(task, &iovs)
+    } else if !file.Flags().Pwrite {
+        Err(Error::SysError(SysErr::ESPIPE))
+    } else {
+        file.Pwritev(task, &iovs, offset)
+    };
+
+    match res {
+        Err(Error::SysError(SysErr::EWOULDBLOCK)) if rwFlags & Flags::RWF_NOWAIT == 0 => {
+            return WaitReady(task, ring, req, EVENT_WRITE);
+        }
+        Err(e) => return Err(e),
+        Ok(n) => {
+            if n > 0 {
+                file.Dirent
+                    .InotifyEvent(InotifyEvent::IN_MODIFY, 0, EventType::PathEvent);
+            }
+            return Ok(Some(n));
+        }
+    }
+}
+
+fn ExecuteFsync(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+
+    if sqe.Addr != 0 || sqe.Ioprio != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if sqe.OpFlags & !IORING_FSYNC_DATASYNC != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let dataSyncOnly = sqe.OpFlags & IORING_FSYNC_DATASYNC != 0;
+
+    if let Some(fd) = HostRegularFd(file) {
+        let ops = IoUringFsync::New(ring, file, fd, dataSyncOnly, sqe.UserData);
+        IOURING.AUCall(AsyncOps::IoUringFsync(ops));
+        return Ok(None);
+    }
+
+    let start = sqe.Off as i64;
+    let end = if sqe.Len == 0 {
+        FILE_MAX_OFFSET
+    } else {
+        start + sqe.Len as i64
+    };
+
+    let syncType = if dataSyncOnly {
+        SyncType::SyncData
+    } else {
+        SyncType::SyncAll
+    };
+
+    file.Fsync(task, start, end, syncType)?;
+    return Ok(Some(0));
+}
+
+fn ExecutePollAdd(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+
+    if sqe.Addr != 0 || sqe.Ioprio != 0 || sqe.Off != 0 || sqe.Len != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // Without IORING_FEAT_POLL_32BITS only the low 16 bits of poll32_events
+    // are the requested events.
+    let events = (sqe.OpFlags & 0xffff) as EventMask;
+    let kind = IoUringPollKind::PollAdd {
+        userData: sqe.UserData,
+        events: events,
+    };
+
+    ring.ArmPoll(task, file, events, kind);
+    return Ok(None);
+}
+
+fn ExecuteTimeout(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+
+    if sqe.Ioprio != 0 || sqe.Len != 1 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if sqe.OpFlags & !IORING_TIMEOUT_ABS != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let ts: Timespec = task.CopyInObj(sqe.Addr)?;
+    if !ts.IsValid() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // Absolute timeouts are measured against CLOCK_MONOTONIC.
+    let mut ns = ts.ToNs()?;
+    if sqe.OpFlags & IORING_TIMEOUT_ABS != 0 {
+        ns -= MonotonicNow();
+        if ns < 0 {
+            ns = 0;
+        }
+    }
+
+    // sqe->off is the number of completions to wait for, if any.
+    ring.AddTimeout(sqe.UserData, sqe.Off as u32, ns);
+    return Ok(None);
+}
+
+fn ExecuteAccept(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+    let flags = sqe.OpFlags as i32;
+
+    if sqe.Ioprio != 0 || sqe.Len != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if flags & !(SocketFlags::SOCK_CLOEXEC | SocketFlags::SOCK_NONBLOCK) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // sqe->addr2 is the address length pointer.
+    match AcceptFile(task, file, sqe.Addr, sqe.Off, flags, false) {
+        Err(Error::SysError(SysErr::EWOULDBLOCK)) => {
+            return WaitReady(task, ring, req, EVENT_READ);
+        }
+        Err(e) => return Err(e),
+        Ok(fd) => return Ok(Some(fd)),
+    }
+}
+
+fn ExecuteConnect(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+
+    if req.connecting {
+        // The connection attempt is over; collect its result.
+        let mut val = [0u8; 4];
+        file.FileOp.GetSockOpt(
+            task,
+            LibcConst::SOL_SOCKET as i32,
+            LibcConst::SO_ERROR as i32,
+            &mut val,
+        )?;
+
+        let err = i32::from_le_bytes(val);
+        if err != 0 {
+            return Err(Error::SysError(err));
+        }
+
+        return Ok(Some(0));
+    }
+
+    if sqe.Ioprio != 0 || sqe.Len != 0 || sqe.OpFlags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // sqe->addr2 is the address length.
+    let addrlen = sqe.Off as u32;
+    if addrlen > MAX_ADDR_LEN {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let addr = CaptureAddress(task, sqe.Addr, addrlen)?;
+    match file.FileOp.Connect(task, &addr, false) {
+        Err(Error::SysError(SysErr::EINPROGRESS)) => {
+            let mut req = req.clone();
+            req.connecting = true;
+            return WaitReady(task, ring, &req, EVENT_WRITE);
+        }
+        Err(e) => return Err(e),
+        Ok(_) => return Ok(Some(0)),
+    }
+}
+
+fn ExecuteSend(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+    let flags = sqe.OpFlags as i32;
+
+    task.CheckPermission(sqe.Addr, sqe.Len as u64, false, false)?;
+    let iovs = [IoVec::NewFromAddr(sqe.Addr, sqe.Len as usize)];
+
+    let mut msgHdr = MsgHdr::default();
+    let res = file.FileOp.SendMsg(
+        task,
+        &iovs,
+        flags | MsgType::MSG_DONTWAIT,
+        &mut msgHdr,
+        None,
+    );
+
+    match res {
+        Err(Error::SysError(SysErr::EWOULDBLOCK)) if flags & MsgType::MSG_DONTWAIT == 0 => {
+            return WaitReady(task, ring, req, EVENT_WRITE);
+        }
+        Err(e) => return Err(e),
+        Ok(n) => return Ok(Some(n)),
+    }
+}
+
+fn ExecuteRecv(task: &Task, ring: &IoUring, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+    let file = req.file.as_ref().unwrap();
+    let flags = sqe.OpFlags as i32;
+
+    let mut iovs = [IoVec::NewFromAddr(sqe.Addr, sqe.Len as usize)];
+    let res = file.FileOp.RecvMsg(
+        task,
+        &mut iovs,
+        flags | MsgType::MSG_DONTWAIT,
+        None,
+        false,
+        0,
+    );
+
+    match res {
+        Err(Error::SysError(SysErr::EWOULDBLOCK)) if flags & MsgType::MSG_DONTWAIT == 0 => {
+            return WaitReady(task, ring, req, EVENT_READ);
+        }
+        Err(e) => return Err(e),
+        Ok((n, _, _, _)) => return Ok(Some(n)),
+    }
+}
+
+fn ExecuteOpenat(task: &mut Task, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+
+    if sqe.Ioprio != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let args = SyscallArguments {
+        arg0: sqe.Fd as u64,
+        arg1: sqe.Addr,
+        arg2: sqe.OpFlags as u64,
+        arg3: sqe.Len as u64,
+        arg4: 0,
+        arg5: 0,
+    };
+
+    let fd = SysOpenAt(task, &args)?;
+    return Ok(Some(fd));
+}
+
+fn ExecuteClose(task: &mut Task, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+
+    if sqe.Ioprio != 0 || sqe.Off != 0 || sqe.Addr != 0 || sqe.Len != 0 || sqe.OpFlags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // io_uring file descriptors can't be closed through io_uring.
+    let file = task.GetFile(sqe.Fd)?;
+    if file.FileOp.FopsType() == FileOpsType::IoUringOperations {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    close(task, sqe.Fd)?;
+    return Ok(Some(0));
+}
+
+fn ExecuteStatx(task: &mut Task, req: &IoUringRequest) -> Result<Option<i64>> {
+    let sqe = &req.sqe;
+
+    if sqe.Ioprio != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // sqe->addr2 is the statx buffer.
+    let args = SyscallArguments {
+        arg0: sqe.Fd as u64,
+        arg1: sqe.Addr,
+        arg2: sqe.OpFlags as u64,
+        arg3: sqe.Len as u64,
+        arg4: sqe.Off,
+        arg5: 0,
+    };
+
+    SysStatx(task, &args)?;
+    return Ok(Some(0));
+}
+
+// IoUringRegister implements linux syscall io_uring_register(2).
+pub fn SysIoUringRegister(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let opcode = args.arg1 as u32;
+    let arg = args.arg2 as u64;
+    let nrArgs = args.arg3 as u32;
+
+    let ring = GetIoUring(task, fd)?;
+
+    match opcode {
+        IORING_REGISTER_EVENTFD | IORING_REGISTER_EVENTFD_ASYNC => {
+            if nrArgs != 1 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let efd: i32 = task.CopyInObj(arg)?;
+            let file = task.GetFile(efd)?;
+            let eventfd = match file.FileOp {
+                FileOps::EventOperations(ref e) => e.clone(),
+                _ => return Err(Error::SysError(SysErr::EINVAL)),
+            };
+
+            ring.RegisterEventfd(eventfd, opcode == IORING_REGISTER_EVENTFD_ASYNC)?;
+            return Ok(0);
+        }
+        IORING_UNREGISTER_EVENTFD => {
+            if arg != 0 || nrArgs != 0 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            ring.UnregisterEventfd()?;
+            return Ok(0);
+        }
+        IORING_REGISTER_PROBE => {
+            if arg == 0 || nrArgs as usize > IO_URING_PROBE_MAX_OPS {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            return Probe(task, arg, nrArgs);
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+}
+
+// Probe fills in the struct io_uring_probe at addr with the supported
+// opcodes. Equivalent to io_uring.c:io_probe.
+fn Probe(task: &Task, addr: u64, nrArgs: u32) -> Result<i64> {
+    let nr = core::cmp::min(nrArgs, IORING_OP_LAST as u32) as usize;
+    let headerSize = core::mem::size_of::<IoUringProbe>();
+    let size = headerSize + nr * core::mem::size_of::<IoUringProbeOp>();
+
+    // The application must pass a zeroed probe.
+    let buf: Vec<u8> = task.CopyInVec(addr, size)?;
+    for b in &buf {
+        if *b != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+    }
+
+    let probe = IoUringProbe {
+        LastOp: IORING_OP_LAST - 1,
+        OpsLen: nr as u8,
+        ..Default::default()
+    };
+
+    let mut ops = Vec::with_capacity(nr);
+    for i in 0..nr {
+        let op = i as u8;
+        ops.push(IoUringProbeOp {
+            Op: op,
+            Flags: if Supported(op) {
+                IO_URING_OP_SUPPORTED
+            } else {
+                0
+            },
+            ..Default::default()
+        });
+    }
+
+    task.CopyOutObj(&probe, addr)?;
+    task.CopyOutSlice(&ops, addr + headerSize as u64, nr)?;
+    return Ok(0);
+}
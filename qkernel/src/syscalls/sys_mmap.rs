@@ -15,6 +15,7 @@
 use alloc::string::ToString;
 use alloc::sync::Arc;
 
+use super::super::fs::file::*;
 use super::super::fs::host::hostinodeop::*;
 use super::super::memmgr::mm::*;
 use super::super::memmgr::vma::*;
@@ -120,6 +121,17 @@ pub fn SysMmap(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             Err(e) => return Err(e),
             Ok(m) => opts.Mappable = m,
         }
+
+        // The io_uring regions are shared with the kernel, which must see
+        // the application's updates: only shared mappings of a whole region
+        // make sense.
+        if let FileOps::IoUringOperations(ref ops) = file.FileOp {
+            if opts.Private {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            ops.ring.ValidateMmap(opts.Offset, opts.Length)?;
+        }
     } else if shared {
         let memfdIops = HostInodeOp::NewMemfdIops(len as i64)?;
         opts.Mappable = MMappable::FromHostIops(memfdIops);
@@ -193,14 +205,20 @@ pub fn SysMadvise(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         MAdviseOp::MADV_DONTNEED => {
             task.mm.MDontneed(task, addr, length, adv)?;
         }
-        MAdviseOp::MADV_HUGEPAGE | MAdviseOp::MADV_NOHUGEPAGE => {
-            //task.mm.MAdvise(task, addr, length, adv)?;
+        MAdviseOp::MADV_HUGEPAGE => {
+            task.mm.SetHugePage(task, addr, length, true)?;
+        }
+        MAdviseOp::MADV_NOHUGEPAGE => {
+            task.mm.SetHugePage(task, addr, length, false)?;
         }
         MAdviseOp::MADV_MERGEABLE | MAdviseOp::MADV_UNMERGEABLE => {
             //task.mm.MAdvise(task, addr, length, adv)?;
         }
-        MAdviseOp::MADV_DONTDUMP | MAdviseOp::MADV_DODUMP => {
-            // Core dumping isn't implemented, so do nothing
+        MAdviseOp::MADV_DONTDUMP => {
+            task.mm.SetDontDump(task, addr, length, true)?;
+        }
+        MAdviseOp::MADV_DODUMP => {
+            task.mm.SetDontDump(task, addr, length, false)?;
         }
         MAdviseOp::MADV_NORMAL
         | MAdviseOp::MADV_RANDOM
@@ -0,0 +1,249 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+
+use super::super::fs::dirent::*;
+use super::super::fs::filesystems::*;
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+use super::sys_file::*;
+
+// mountSourceFlags converts the MS_* flags passed to mount(2) into
+// MountSourceFlags.
+fn mountSourceFlags(flags: u64) -> MountSourceFlags {
+    return MountSourceFlags {
+        ReadOnly: flags & LibcConst::MS_RDONLY != 0,
+        NoAtime: flags & LibcConst::MS_NOATIME != 0,
+        NoExec: flags & LibcConst::MS_NOEXEC != 0,
+        NoSuid: flags & LibcConst::MS_NOSUID != 0,
+        NoDev: flags & LibcConst::MS_NODEV != 0,
+        ..Default::default()
+    };
+}
+
+// Mount implements Linux syscall mount(2).
+pub fn SysMount(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let sourceAddr = args.arg0 as u64;
+    let targetAddr = args.arg1 as u64;
+    let typeAddr = args.arg2 as u64;
+    let mut flags = args.arg3 as u64;
+    let dataAddr = args.arg4 as u64;
+
+    // Ignore magic value that was required before Linux 2.4.
+    if flags & LibcConst::MS_MGC_MSK == LibcConst::MS_MGC_VAL {
+        flags &= !LibcConst::MS_MGC_MSK;
+    }
+
+    // The filesystem type is ignored for bind mounts and remounts, and may
+    // well be NULL.
+    let mut fsType = String::new();
+    if flags & (LibcConst::MS_REMOUNT | LibcConst::MS_BIND) == 0 {
+        let (str, err) = task.CopyInString(typeAddr, MemoryDef::PAGE_SIZE as usize);
+        err?;
+        fsType = str;
+    }
+
+    let (sourcePath, _) = if sourceAddr != 0 {
+        copyInPath(task, sourceAddr, true)?
+    } else {
+        (String::new(), false)
+    };
+
+    let (targetPath, _) = copyInPath(task, targetAddr, false)?;
+
+    let mut data = String::new();
+    if dataAddr != 0 {
+        // In Linux, a full page is always copied in regardless of null
+        // character placement, and the address is passed to each file system.
+        // Most file systems always treat this data as a string, though, and so
+        // do all of the ones we implement.
+        let (str, err) = task.CopyInString(dataAddr, MemoryDef::PAGE_SIZE as usize);
+        err?;
+        data = str;
+    }
+
+    // Must have CAP_SYS_ADMIN in the mount namespace's associated user
+    // namespace.
+    let userns = task.mountNS.UserNamespace();
+    if !task
+        .Creds()
+        .HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
+    {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    const PROPAGATION_FLAGS: u64 = LibcConst::MS_SHARED
+        | LibcConst::MS_PRIVATE
+        | LibcConst::MS_SLAVE
+        | LibcConst::MS_UNBINDABLE;
+
+    const UNSUPPORTED_FLAGS: u64 =
+        LibcConst::MS_MOVE | LibcConst::MS_NODIRATIME | LibcConst::MS_STRICTATIME;
+
+    // Linux just allows passing any flags to mount(2) - it won't fail when
+    // unknown or unsupported flags are passed. Since we don't implement
+    // everything, we fail explicitly on flags that are unimplemented.
+    if flags & UNSUPPORTED_FLAGS != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let mountns = task.mountNS.clone();
+
+    if flags & PROPAGATION_FLAGS != 0 {
+        if flags & (LibcConst::MS_REMOUNT | LibcConst::MS_BIND) != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // Only one propagation type may be changed at a time.
+        let propagation = flags & PROPAGATION_FLAGS;
+        if propagation.count_ones() != 1 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let recursive = flags & LibcConst::MS_REC != 0;
+        fileOpOn(
+            task,
+            ATType::AT_FDCWD,
+            &targetPath,
+            true,
+            &mut |_root: &Dirent, d: &Dirent, _: u32| -> Result<()> {
+                return mountns.SetPropagation(d, propagation, recursive);
+            },
+        )?;
+        return Ok(0);
+    }
+
+    if flags & LibcConst::MS_REMOUNT != 0 {
+        // "MS_REMOUNT ... The mountflags and data arguments should match the
+        // values used in the original mount() call, except for those
+        // parameters that are being deliberately changed." - mount(2)
+        let msflags = mountSourceFlags(flags);
+        fileOpOn(
+            task,
+            ATType::AT_FDCWD,
+            &targetPath,
+            true,
+            &mut |_root: &Dirent, d: &Dirent, _: u32| -> Result<()> {
+                return mountns.Remount(d, &msflags);
+            },
+        )?;
+        return Ok(0);
+    }
+
+    if flags & LibcConst::MS_BIND != 0 {
+        if sourcePath.len() == 0 {
+            return Err(Error::SysError(SysErr::ENOENT));
+        }
+
+        let mut source = None;
+        fileOpOn(
+            task,
+            ATType::AT_FDCWD,
+            &sourcePath,
+            true,
+            &mut |_root: &Dirent, d: &Dirent, _: u32| -> Result<()> {
+                source = Some(d.clone());
+                return Ok(());
+            },
+        )?;
+        let source = source.unwrap();
+
+        let recursive = flags & LibcConst::MS_REC != 0;
+        fileOpOn(
+            task,
+            ATType::AT_FDCWD,
+            &targetPath,
+            true,
+            &mut |_root: &Dirent, d: &Dirent, _: u32| -> Result<()> {
+                // The mount point and the bound file must both be directories,
+                // or both be non-directories.
+                if d.Inode().StableAttr().IsDir() != source.Inode().StableAttr().IsDir() {
+                    return Err(Error::SysError(SysErr::ENOTDIR));
+                }
+
+                return mountns.BindMount(task, d, &source, recursive);
+            },
+        )?;
+        return Ok(0);
+    }
+
+    let rsys = match FindFilesystem(&fsType) {
+        None => return Err(Error::SysError(SysErr::ENODEV)),
+        Some(f) => f,
+    };
+
+    if !rsys.lock().AllowUserMount() {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let superFlags = mountSourceFlags(flags);
+    let rootInode = match rsys.lock().Mount(task, &sourcePath, &superFlags, &data) {
+        Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+        Ok(inode) => inode,
+    };
+
+    fileOpOn(
+        task,
+        ATType::AT_FDCWD,
+        &targetPath,
+        true,
+        &mut |_root: &Dirent, d: &Dirent, _: u32| -> Result<()> {
+            return mountns.Mount(d, &rootInode);
+        },
+    )?;
+
+    return Ok(0);
+}
+
+// Umount2 implements Linux syscall umount2(2).
+pub fn SysUmount2(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0 as u64;
+    let flags = args.arg1 as i32 as u64;
+
+    const UNSUPPORTED: u64 = LibcConst::MNT_FORCE | LibcConst::MNT_EXPIRE;
+    if flags & UNSUPPORTED != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let (path, _) = copyInPath(task, addr, false)?;
+
+    // Must have CAP_SYS_ADMIN in the mount namespace's associated user
+    // namespace.
+    let userns = task.mountNS.UserNamespace();
+    if !task
+        .Creds()
+        .HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
+    {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let resolve = flags & LibcConst::UMOUNT_NOFOLLOW != LibcConst::UMOUNT_NOFOLLOW;
+    let detachOnly = flags & LibcConst::MNT_DETACH == LibcConst::MNT_DETACH;
+
+    let mountns = task.mountNS.clone();
+    fileOpOn(
+        task,
+        ATType::AT_FDCWD,
+        &path,
+        resolve,
+        &mut |_root: &Dirent, d: &Dirent, _: u32| -> Result<()> {
+            return mountns.Unmount(d, detachOnly);
+        },
+    )?;
+
+    return Ok(0);
+}
@@ -0,0 +1,298 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::kernel::fd_table::*;
+use super::super::kernel::time::*;
+use super::super::qlib::common::*;
+use super::super::qlib::kernel::kernel::mqueue::*;
+use super::super::qlib::linux::mqueue::*;
+use super::super::qlib::linux::time::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+use super::super::util::cstring::*;
+use super::super::SignalDef::*;
+
+// copyInQueueName copies in the name of a queue passed to mq_open(3) or
+// mq_unlink(3). The C library strips the leading slash, so the kernel sees
+// a plain file name in the mqueue filesystem.
+fn copyInQueueName(task: &Task, addr: u64) -> Result<String> {
+    let name = CString::ToString(task, addr)?;
+
+    if name.len() == 0 {
+        return Err(Error::SysError(SysErr::ENOENT));
+    }
+
+    if name.len() > NAME_MAX {
+        return Err(Error::SysError(SysErr::ENAMETOOLONG));
+    }
+
+    if name.contains('/') || name == "." || name == ".." {
+        return Err(Error::SysError(SysErr::EACCES));
+    }
+
+    return Ok(name);
+}
+
+// copyInDeadline copies in the absolute CLOCK_REALTIME timeout passed to
+// mq_timedsend(2) and mq_timedreceive(2). A NULL timeout blocks forever.
+fn copyInDeadline(task: &Task, addr: u64) -> Result<Option<Time>> {
+    if addr == 0 {
+        return Ok(None);
+    }
+
+    let ts: Timespec = task.CopyInObj(addr)?;
+    if !ts.IsValid() {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    return Ok(Some(Time(ts.ToDuration()?)));
+}
+
+// getQueue returns the file for message queue descriptor fd and its queue.
+fn getQueue(task: &Task, fd: i32) -> Result<(File, PosixQueue)> {
+    let file = task.GetFile(fd)?;
+
+    let queue = match file.FileOp {
+        FileOps::PosixQueueFileOperations(ref ops) => ops.queue.clone(),
+        _ => return Err(Error::SysError(SysErr::EBADF)),
+    };
+
+    return Ok((file, queue));
+}
+
+// MqOpen implements mq_open(2).
+pub fn SysMqOpen(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let nameAddr = args.arg0 as u64;
+    let flags = args.arg1 as i32;
+    let mode = args.arg2 as u16;
+    let attrAddr = args.arg3 as u64;
+
+    let name = copyInQueueName(task, nameAddr)?;
+
+    let accMode = flags & Flags::O_ACCMODE;
+    if accMode == Flags::O_ACCMODE {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let create = flags & Flags::O_CREAT != 0;
+    let exclusive = flags & Flags::O_EXCL != 0;
+
+    let fileFlags = FileFlags {
+        Read: accMode != Flags::O_WRONLY,
+        Write: accMode != Flags::O_RDONLY,
+        NonBlocking: flags & Flags::O_NONBLOCK != 0,
+        ..Default::default()
+    };
+
+    let perms = FilePermissions::FromMode(FileMode(mode & 0o777 & !task.Umask() as u16));
+
+    let mut attr = None;
+    if create && attrAddr != 0 {
+        attr = Some(task.CopyInObj::<MqAttr>(attrAddr)?);
+    }
+
+    let r = task.IPCNamespace().PosixQueueRegistry();
+    let file = r.FindOrCreate(task, &name, &fileFlags, create, exclusive, &perms, attr)?;
+
+    let fd = task.NewFDFrom(
+        0,
+        &file,
+        &FDFlags {
+            CloseOnExec: flags & Flags::O_CLOEXEC != 0,
+        },
+    )?;
+
+    return Ok(fd as i64);
+}
+
+// MqUnlink implements mq_unlink(2).
+pub fn SysMqUnlink(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let nameAddr = args.arg0 as u64;
+
+    let name = copyInQueueName(task, nameAddr)?;
+
+    let r = task.IPCNamespace().PosixQueueRegistry();
+    r.Remove(task, &name)?;
+    return Ok(0);
+}
+
+// MqTimedsend implements mq_timedsend(2).
+pub fn SysMqTimedsend(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let msgAddr = args.arg1 as u64;
+    let msgLen = args.arg2 as u64;
+    let priority = args.arg3 as u32;
+    let timeoutAddr = args.arg4 as u64;
+
+    if priority >= MQ_PRIO_MAX {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let deadline = copyInDeadline(task, timeoutAddr)?;
+
+    let (file, queue) = getQueue(task, fd)?;
+    let flags = file.Flags();
+    if !flags.Write {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    // "EMSGSIZE msg_len was greater than the mq_msgsize attribute of the
+    // message queue." - mq_send(3)
+    if msgLen > queue.MaxMessageSize() as u64 {
+        return Err(Error::SysError(SysErr::EMSGSIZE));
+    }
+
+    let text: Vec<u8> = task.CopyInVec(msgAddr, msgLen as usize)?;
+    let msg = PosixMessage {
+        Text: text,
+        Priority: priority,
+    };
+
+    queue.Send(task, msg, !flags.NonBlocking, deadline)?;
+    return Ok(0);
+}
+
+// MqTimedreceive implements mq_timedreceive(2).
+pub fn SysMqTimedreceive(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let msgAddr = args.arg1 as u64;
+    let msgLen = args.arg2 as i64;
+    let priorityAddr = args.arg3 as u64;
+    let timeoutAddr = args.arg4 as u64;
+
+    let deadline = copyInDeadline(task, timeoutAddr)?;
+
+    let (file, queue) = getQueue(task, fd)?;
+    let flags = file.Flags();
+    if !flags.Read {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    let msg = queue.Receive(task, msgLen, !flags.NonBlocking, deadline)?;
+
+    if msg.Text.len() > 0 {
+        task.CopyOutSlice(&msg.Text, msgAddr, msg.Text.len())?;
+    }
+
+    if priorityAddr != 0 {
+        task.CopyOutObj(&msg.Priority, priorityAddr)?;
+    }
+
+    return Ok(msg.Text.len() as i64);
+}
+
+// MqNotify implements mq_notify(2).
+pub fn SysMqNotify(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let sevAddr = args.arg1 as u64;
+
+    let (_, queue) = getQueue(task, fd)?;
+
+    // "If sevp is NULL, and the calling process is currently registered to
+    // receive notifications for this message queue, then the registration is
+    // removed; another process can then register to receive a message
+    // notification for this queue." - mq_notify(3)
+    if sevAddr == 0 {
+        queue.Unsubscribe(task);
+        return Ok(0);
+    }
+
+    let sev: Sigevent = task.CopyInObj(sevAddr)?;
+
+    let mut sock = None;
+    let mut cookie = [0u8; NOTIFY_COOKIE_LEN];
+    match sev.Notify {
+        SIGEV_NONE => (),
+        SIGEV_SIGNAL => {
+            if !Signal(sev.Signo).IsValid() {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+        }
+        SIGEV_THREAD => {
+            // The C library implements SIGEV_THREAD with a helper thread that
+            // waits on a netlink socket, passed in sigev_signo. The kernel
+            // sends the NOTIFY_COOKIE_LEN bytes at sigev_value to that socket
+            // when the notification fires.
+            cookie = task.CopyInObj(sev.Value)?;
+
+            let file = task.GetFile(sev.Signo)?;
+            if !file.Dirent.Inode().StableAttr().IsSocket() {
+                return Err(Error::SysError(SysErr::ENOTSOCK));
+            }
+
+            match file.FileOp {
+                FileOps::HostSocketOperations(ref s) if s.family == AFType::AF_NETLINK => (),
+                _ => return Err(Error::SysError(SysErr::ECONNREFUSED)),
+            }
+
+            sock = Some(file);
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+
+    let subscriber = Subscriber {
+        tg: task.Thread().ThreadGroup().Downgrade(),
+        userns: task.Creds().lock().UserNamespace.clone(),
+        sigev: sev,
+        sock: sock,
+        cookie: cookie,
+    };
+
+    queue.Subscribe(subscriber)?;
+    return Ok(0);
+}
+
+// MqGetsetattr implements mq_getsetattr(2).
+pub fn SysMqGetsetattr(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let newAttrAddr = args.arg1 as u64;
+    let oldAttrAddr = args.arg2 as u64;
+
+    let mut newAttr = None;
+    if newAttrAddr != 0 {
+        let attr: MqAttr = task.CopyInObj(newAttrAddr)?;
+
+        // Only O_NONBLOCK may be changed.
+        if attr.MqFlags & !(Flags::O_NONBLOCK as i64) != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        newAttr = Some(attr);
+    }
+
+    let (file, queue) = getQueue(task, fd)?;
+
+    let mut attr = queue.Attr();
+    let mut flags = file.Flags().SettableFileFlags();
+    if flags.NonBlocking {
+        attr.MqFlags = Flags::O_NONBLOCK as i64;
+    }
+
+    if let Some(newAttr) = newAttr {
+        flags.NonBlocking = newAttr.MqFlags & Flags::O_NONBLOCK as i64 != 0;
+        file.SetFlags(task, flags);
+    }
+
+    if oldAttrAddr != 0 {
+        task.CopyOutObj(&attr, oldAttrAddr)?;
+    }
+
+    return Ok(0);
+}
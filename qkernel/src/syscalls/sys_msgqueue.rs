@@ -51,7 +51,7 @@ pub fn SysMsgsnd(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let size = args.arg2 as i64;
     let flags = args.arg3 as i16;
 
-    if size < 0 || size > MSGMAX as _ {
+    if size < 0 || size > task.IPCNamespace().Limits().msgMax as i64 {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
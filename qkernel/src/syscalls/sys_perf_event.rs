@@ -0,0 +1,188 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+
+use super::super::fs::file::*;
+use super::super::kernel::fd_table::*;
+use super::super::qlib::common::*;
+use super::super::qlib::kernel::kernel::perf_event::*;
+use super::super::qlib::linux::perf_event::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+
+fn copyInPerfEventAttr(task: &Task, addr: u64) -> Result<PerfEventAttr> {
+    let ksize = core::mem::size_of::<PerfEventAttr>();
+    let mut size: u32 = task.CopyInObj(addr + 4)?;
+    if size == 0 {
+        size = PERF_ATTR_SIZE_VER0 as u32;
+    }
+
+    let size = size as usize;
+    if size < PERF_ATTR_SIZE_VER0 || size > MemoryDef::PAGE_SIZE as usize {
+        task.CopyOutObj(&(ksize as u32), addr + 4)?;
+        return Err(Error::SysError(SysErr::E2BIG));
+    }
+
+    let mut attr = PerfEventAttr::default();
+    let n = core::cmp::min(size, ksize);
+    let buf: Vec<u8> = task.CopyInVec(addr, n)?;
+    unsafe {
+        core::ptr::copy_nonoverlapping(buf.as_ptr(), &mut attr as *mut _ as *mut u8, n);
+    }
+
+    // A newer struct is only accepted if the fields we don't know about are
+    // unset. Otherwise the size we support is reported back.
+    if size > ksize {
+        let rest: Vec<u8> = task.CopyInVec(addr + ksize as u64, size - ksize)?;
+        if rest.iter().any(|b| *b != 0) {
+            task.CopyOutObj(&(ksize as u32), addr + 4)?;
+            return Err(Error::SysError(SysErr::E2BIG));
+        }
+    }
+
+    attr.Size = size as u32;
+    return Ok(attr);
+}
+
+// PerfEventOpen implements linux syscall perf_event_open(2).
+//
+// Only software counters in counting mode are supported. The counters are
+// kept per task, so an event must count a task on any CPU.
+pub fn SysPerfEventOpen(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let attrAddr = args.arg0 as u64;
+    let pid = args.arg1 as i32;
+    let cpu = args.arg2 as i32;
+    let groupFd = args.arg3 as i32;
+    let flags = args.arg4 as u64;
+
+    if flags & !PERF_FLAG_ALL != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let attr = copyInPerfEventAttr(task, attrAddr)?;
+
+    // There is no cgroup hierarchy, so pid can't refer to a cgroup
+    // directory.
+    if flags & PERF_FLAG_PID_CGROUP != 0 {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    // "PERF_FLAG_FD_OUTPUT (broken since Linux 2.6.35)" - perf_event_open(2)
+    if flags & PERF_FLAG_FD_OUTPUT != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if attr.ReadFormat & !PERF_FORMAT_ALL != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    match attr.Type {
+        PERF_TYPE_SOFTWARE => (),
+        // There is no PMU to count hardware, cache, raw, tracepoint or
+        // breakpoint events, as on a host without perf support.
+        _ => return Err(Error::SysError(SysErr::ENOENT)),
+    }
+
+    if attr.Config > PERF_COUNT_SW_DUMMY {
+        return Err(Error::SysError(SysErr::ENOENT));
+    }
+
+    // There is no sampling ring buffer.
+    if attr.SamplePeriod != 0 || attr.Flags & PERF_ATTR_FLAG_FREQ != 0 {
+        return Err(Error::SysError(SysErr::EOPNOTSUPP));
+    }
+
+    if attr.Flags & PERF_ATTR_FLAG_SIGTRAP != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // "pid == -1 and cpu == -1: This setting is invalid and will return an
+    // error." - perf_event_open(2)
+    if pid == -1 && cpu == -1 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if pid < -1 || cpu < -1 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let ncpus = task.Thread().lock().k.ApplicationCores() as i32;
+    if cpu >= ncpus {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // CPU-wide events would need counters that aren't attached to a task,
+    // which is denied as for an unprivileged user with the default
+    // perf_event_paranoid.
+    if pid == -1 {
+        return Err(Error::SysError(SysErr::EACCES));
+    }
+
+    if cpu != -1 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let target = if pid == 0 {
+        task.Thread()
+    } else {
+        let pidns = task.Thread().PIDNamespace();
+        match pidns.TaskWithID(pid) {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(t) => t,
+        }
+    };
+
+    // "Permission to monitor another process is governed by a ptrace access
+    // mode PTRACE_MODE_READ_REALCREDS check" - perf_event_open(2)
+    if !task.Thread().CanTrace(&target) {
+        return Err(Error::SysError(SysErr::EACCES));
+    }
+
+    let sched = target.lock().sched.clone();
+
+    let mut leader = None;
+    if groupFd != -1 {
+        let file = task.GetFile(groupFd)?;
+        let event = match file.FileOp {
+            FileOps::PerfEventOperations(ref ops) => ops.Event(),
+            _ => return Err(Error::SysError(SysErr::EBADF)),
+        };
+
+        if flags & PERF_FLAG_FD_NO_GROUP == 0 {
+            // Group members must count the same task, and only a group
+            // leader can take new members.
+            if !event.IsGroupLeader() || !event.IsCounting(&sched) {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            leader = Some(event);
+        }
+    }
+
+    let event = PerfEvent::New(&attr, &sched, leader.as_ref());
+    AttachPerfEvent(&target, &event);
+
+    let file = NewPerfEventFile(task, &event);
+    let fd = task.NewFDFrom(
+        0,
+        &file,
+        &FDFlags {
+            CloseOnExec: flags & PERF_FLAG_FD_CLOEXEC != 0,
+        },
+    )?;
+
+    return Ok(fd as i64);
+}
@@ -0,0 +1,114 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::fs::file::*;
+use super::super::kernel::fd_table::*;
+use super::super::qlib::common::*;
+use super::super::qlib::kernel::kernel::pidfd::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+use super::super::threadmgr::task_exit::*;
+use super::super::threadmgr::thread_group::*;
+
+// GetPidfd returns the file for the pidfd fd and the thread group it refers
+// to.
+pub fn GetPidfd(task: &Task, fd: i32) -> Result<(File, ThreadGroup)> {
+    let file = task.GetFile(fd)?;
+
+    let tg = match file.FileOp {
+        FileOps::PidfdOperations(ref ops) => ops.ThreadGroup(),
+        _ => return Err(Error::SysError(SysErr::EBADF)),
+    };
+
+    return Ok((file, tg));
+}
+
+// PidfdOpen implements linux syscall pidfd_open(2).
+pub fn SysPidfdOpen(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let flags = args.arg1 as u32 as i32;
+
+    if flags & !PIDFD_NONBLOCK != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if pid <= 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let pidns = task.Thread().PIDNamespace();
+    let tg = match pidns.ThreadGroupWithID(pid) {
+        Some(tg) => tg,
+        None => {
+            // pid exists, but refers to a thread other than the thread group
+            // leader.
+            if pidns.TaskWithID(pid).is_some() {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+    };
+
+    let file = NewPidfd(task, &tg, flags & PIDFD_NONBLOCK != 0);
+
+    // "The close-on-exec flag is set on the file descriptor." - pidfd_open(2)
+    let fd = task.NewFDFrom(0, &file, &FDFlags { CloseOnExec: true })?;
+    return Ok(fd as i64);
+}
+
+// PidfdGetfd implements linux syscall pidfd_getfd(2).
+pub fn SysPidfdGetfd(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pidfd = args.arg0 as i32;
+    let targetfd = args.arg1 as i32;
+    let flags = args.arg2 as u32;
+
+    // "The flags argument is reserved for future use. Currently, it must be
+    // specified as 0." - pidfd_getfd(2)
+    if flags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let (_, tg) = GetPidfd(task, pidfd)?;
+    let target = match tg.Leader() {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(t) => t,
+    };
+
+    let exitState = target.lock().exitState;
+    if exitState == TaskExitState::TaskExitDead {
+        return Err(Error::SysError(SysErr::ESRCH));
+    }
+
+    // "Permission to duplicate another process's file descriptor is governed
+    // by a ptrace access mode PTRACE_MODE_ATTACH_REALCREDS check." -
+    // pidfd_getfd(2)
+    if !task.Thread().CanTrace(&target) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    // The file descriptor table of an exited process has been released.
+    if exitState >= TaskExitState::TaskExitZombie {
+        return Err(Error::SysError(SysErr::EBADF));
+    }
+
+    let fdTbl = target.lock().fdTbl.clone();
+    let (file, _) = fdTbl.Get(targetfd)?;
+
+    // "The close-on-exec flag (FD_CLOEXEC) is set on the file descriptor
+    // returned by pidfd_getfd()." - pidfd_getfd(2)
+    let fd = task.NewFDFrom(0, &file, &FDFlags { CloseOnExec: true })?;
+    return Ok(fd as i64);
+}
@@ -17,6 +17,7 @@ use super::super::memmgr::metadata::*;
 use super::super::threadmgr::pid_namespace::*;
 use super::super::qlib::auth::cap_set::*;
 use super::super::qlib::common::*;
+use super::super::qlib::linux::seccomp::*;
 use super::super::qlib::linux_def::*;
 use super::super::syscalls::syscalls::*;
 use super::super::task::*;
@@ -320,12 +321,10 @@ pub fn SysPrctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
                 return Err(Error::SysError(SysErr::EINVAL));
             }
 
-            panic!("SysPrctl::PR_SET_SECCOMP doesn't support.... ");
-            //return seccomp(task, SECCOMP_SET_MODE_FILTER as u64, 0, args.arg2 as u64)
+            return seccomp(task, SECCOMP_SET_MODE_FILTER, 0, args.arg2 as u64);
         }
         PR_GET_SECCOMP => {
-            panic!("SysPrctl::PR_GET_SECCOMP doesn't support.... ");
-            //return Err(Error::SysError(SysErr::ENOSYS))
+            return Ok(task.Thread().SeccompMode() as i64);
         }
         PR_CAPBSET_READ => {
             let cap = args.arg1 as i32;
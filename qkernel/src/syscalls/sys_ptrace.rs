@@ -0,0 +1,535 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use super::super::qlib::common::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+use super::super::threadmgr::task_ptrace::*;
+use super::super::threadmgr::thread::*;
+use super::super::vcpu::*;
+use super::super::SignalDef::*;
+use super::sys_splice::MAX_RW_COUNT;
+use super::sys_tls::*;
+
+// ELF note types used by PTRACE_GETREGSET/PTRACE_SETREGSET, from
+// include/uapi/linux/elf.h.
+pub const NT_PRSTATUS: u64 = 1;
+pub const NT_PRFPREG: u64 = 2;
+pub const NT_X86_XSTATE: u64 = 0x202;
+
+// PROCESS_VM_CHUNK is the size of the kernel buffer that process_vm_readv(2)
+// and process_vm_writev(2) copy through.
+const PROCESS_VM_CHUNK: usize = 64 * 1024;
+
+// fxsaveBytes is the size of the legacy FXSAVE area exposed by
+// PTRACE_GETFPREGS/PTRACE_SETFPREGS (struct user_fpregs_struct).
+const FXSAVE_BYTES: usize = 512;
+
+// SysPtrace implements linux syscall ptrace(2).
+pub fn SysPtrace(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let req = args.arg0 as u64;
+    let pid = args.arg1 as i32;
+    let addr = args.arg2 as u64;
+    let data = args.arg3 as u64;
+
+    let t = task.Thread();
+
+    // PTRACE_TRACEME ignores all other arguments.
+    if req == LibcConst::PTRACE_TRACEME {
+        t.PtraceTraceme()?;
+        return Ok(0);
+    }
+
+    // All other ptrace requests operate on a current or future tracee
+    // specified by pid.
+    let target = match t.PIDNamespace().TaskWithID(pid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(target) => target,
+    };
+
+    // PTRACE_ATTACH and PTRACE_SEIZE do not require that target is not already
+    // a tracee.
+    if req == LibcConst::PTRACE_ATTACH || req == LibcConst::PTRACE_SEIZE {
+        let seize = req == LibcConst::PTRACE_SEIZE;
+        if seize && addr != 0 {
+            return Err(Error::SysError(SysErr::EIO));
+        }
+
+        let opts = if seize { data } else { 0 };
+        t.PtraceAttach(&target, seize, opts)?;
+        return Ok(0);
+    }
+
+    // PTRACE_KILL and PTRACE_INTERRUPT require that the target is a tracee,
+    // but does not require that it is ptrace-stopped.
+    if req == LibcConst::PTRACE_KILL {
+        t.PtraceKill(&target)?;
+        return Ok(0);
+    }
+
+    if req == LibcConst::PTRACE_INTERRUPT {
+        t.PtraceInterrupt(&target)?;
+        return Ok(0);
+    }
+
+    // All other ptrace requests require that the target is a ptrace-stopped
+    // tracee, and freeze the ptrace-stop so the tracee can be operated on.
+    {
+        let owner = t.TaskSet();
+        let _r = owner.ReadLock();
+        if target.Tracer() != Some(t.clone()) {
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+
+        if !target.ptraceFreeze() {
+            // "Most ptrace commands (all except PTRACE_ATTACH, PTRACE_SEIZE,
+            // PTRACE_TRACEME, PTRACE_INTERRUPT, and PTRACE_KILL) require the
+            // tracee to be in a ptrace-stop, otherwise they fail with ESRCH." -
+            // ptrace(2)
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+    }
+
+    // The tracee saved its FPU state before entering the ptrace-stop, and
+    // its other registers are on its kernel stack, so they are stable while
+    // the stop is frozen even if the tracee isn't switched out yet.
+    let targetTask = TaskId::New(target.lock().taskId).GetTask();
+
+    // Resuming commands end the ptrace stop, but only if successful.
+    // PTRACE_LISTEN ends the ptrace stop if trapNotifyPending is already set
+    // on the tracee.
+    let res = match req {
+        LibcConst::PTRACE_DETACH => {
+            let sig = Signal(data as i32);
+            let ret = t.PtraceDetach(&target, sig);
+            if ret.is_ok() {
+                targetTask.GetPtRegs().eflags &= !EflagsDef::EFLAGS_TF;
+                return Ok(0);
+            }
+            ret
+        }
+        LibcConst::PTRACE_CONT
+        | LibcConst::PTRACE_SYSCALL
+        | LibcConst::PTRACE_SINGLESTEP
+        | LibcConst::PTRACE_SYSEMU
+        | LibcConst::PTRACE_SYSEMU_SINGLESTEP => {
+            let (mode, singlestep) = match req {
+                LibcConst::PTRACE_CONT => (PtraceSyscallMode::None, false),
+                LibcConst::PTRACE_SYSCALL => (PtraceSyscallMode::Intercept, false),
+                LibcConst::PTRACE_SINGLESTEP => (PtraceSyscallMode::None, true),
+                LibcConst::PTRACE_SYSEMU => (PtraceSyscallMode::Emu, false),
+                _ => (PtraceSyscallMode::Emu, true),
+            };
+            let ret = ptraceResume(&target, targetTask, mode, singlestep, data);
+            if ret.is_ok() {
+                return Ok(0);
+            }
+            ret
+        }
+        LibcConst::PTRACE_LISTEN => {
+            let ret = target.PtraceListen();
+            if ret.is_ok() {
+                return Ok(0);
+            }
+            ret
+        }
+        _ => ptraceRequest(task, &target, targetTask, req, addr, data),
+    };
+
+    // All other ptrace requests expect us to unfreeze the stop.
+    target.PtraceUnfreeze();
+    res?;
+    return Ok(0);
+}
+
+// ptraceResume implements PTRACE_CONT, PTRACE_SYSCALL, PTRACE_SINGLESTEP,
+// PTRACE_SYSEMU and PTRACE_SYSEMU_SINGLESTEP. If it returns Ok(()) the
+// ptrace stop has ended, and the caller must not unfreeze it.
+fn ptraceResume(
+    target: &Thread,
+    targetTask: &mut Task,
+    mode: PtraceSyscallMode,
+    singlestep: bool,
+    data: u64,
+) -> Result<()> {
+    let sig = Signal(data as i32);
+    if sig.0 != 0 && !sig.IsValid() {
+        return Err(Error::SysError(SysErr::EIO));
+    }
+
+    let pt = targetTask.GetPtRegs();
+    if singlestep {
+        pt.eflags |= EflagsDef::EFLAGS_TF;
+    } else {
+        pt.eflags &= !EflagsDef::EFLAGS_TF;
+    }
+
+    return target.PtraceUnstop(mode, singlestep, sig);
+}
+
+// ptraceRequest implements the ptrace requests that inspect or modify a
+// frozen tracee without ending its ptrace stop.
+fn ptraceRequest(
+    task: &mut Task,
+    target: &Thread,
+    targetTask: &mut Task,
+    req: u64,
+    addr: u64,
+    data: u64,
+) -> Result<()> {
+    match req {
+        LibcConst::PTRACE_PEEKTEXT | LibcConst::PTRACE_PEEKDATA => {
+            // "At the system call level ... the kernel stores the word at the
+            // address specified by data." - ptrace(2)
+            let mut word: u64 = 0;
+            target.MemoryManager().CopyDataInManual(
+                task,
+                addr,
+                &mut word as *mut _ as u64,
+                mem::size_of::<u64>(),
+                false,
+            )?;
+            task.CopyOutObj(&word, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_POKETEXT | LibcConst::PTRACE_POKEDATA => {
+            // Tracers may write to read-only mappings, e.g. to insert
+            // breakpoints into text.
+            target.MemoryManager().CopyDataOutForceManual(
+                task,
+                &data as *const _ as u64,
+                addr,
+                mem::size_of::<u64>(),
+            )?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_PEEKUSR => {
+            let regs = PtraceRegs::New(targetTask.GetPtRegs(), targetTask.context.fs);
+            let word = peekUser(&regs, addr)?;
+            task.CopyOutObj(&word, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_POKEUSR => {
+            let mut regs = PtraceRegs::New(targetTask.GetPtRegs(), targetTask.context.fs);
+            pokeUser(&mut regs, addr, data)?;
+            setRegs(targetTask, &regs)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_GETREGS => {
+            let regs = PtraceRegs::New(targetTask.GetPtRegs(), targetTask.context.fs);
+            task.CopyOutObj(&regs, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_SETREGS => {
+            let regs: PtraceRegs = task.CopyInObj(data)?;
+            setRegs(targetTask, &regs)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_GETFPREGS => {
+            let fp = targetTask.context.X86fpstate.as_ref().unwrap();
+            let buf = &fp.Slice()[..FXSAVE_BYTES];
+            task.CopyOutSlice(buf, data, FXSAVE_BYTES)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_SETFPREGS => {
+            let buf: Vec<u8> = task.CopyInVec(data, FXSAVE_BYTES)?;
+            let fp = targetTask.context.X86fpstate.as_ref().unwrap();
+            fp.Slice()[..FXSAVE_BYTES].copy_from_slice(&buf);
+            fp.SanitizeUser();
+            return Ok(());
+        }
+        LibcConst::PTRACE_GETREGSET => {
+            let mut iov: IoVec = task.CopyInObj(data)?;
+            let n = getRegSet(task, targetTask, addr, &iov)?;
+            iov.len = n;
+            task.CopyOutObj(&iov, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_SETREGSET => {
+            let mut iov: IoVec = task.CopyInObj(data)?;
+            let n = setRegSet(task, targetTask, addr, &iov)?;
+            iov.len = n;
+            task.CopyOutObj(&iov, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_GETSIGINFO => {
+            let info = match &target.lock().ptraceSiginfo {
+                None => return Err(Error::SysError(SysErr::EINVAL)),
+                Some(info) => **info,
+            };
+            task.CopyOutObj(&info, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_SETSIGINFO => {
+            let info: SignalInfo = task.CopyInObj(data)?;
+            let tg = target.ThreadGroup();
+            let lock = tg.lock().signalLock.clone();
+            let _s = lock.lock();
+            let mut t = target.lock();
+            match &mut t.ptraceSiginfo {
+                None => return Err(Error::SysError(SysErr::EINVAL)),
+                Some(old) => **old = info,
+            }
+            return Ok(());
+        }
+        LibcConst::PTRACE_GETSIGMASK => {
+            if addr != mem::size_of::<SignalSet>() as u64 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let mask = target.SignalMask();
+            task.CopyOutObj(&mask.0, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_SETSIGMASK => {
+            if addr != mem::size_of::<SignalSet>() as u64 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let mask: u64 = task.CopyInObj(data)?;
+            // The tracee's signal mask can be set by the tracer because the
+            // tracee is frozen in a ptrace stop.
+            target.SetSignalMask(SignalSet(mask & !UnblockableSignals().0));
+            return Ok(());
+        }
+        LibcConst::PTRACE_SETOPTIONS => {
+            let owner = target.TaskSet();
+            let _w = owner.WriteLock();
+            return target.lock().ptraceSetOptionsLocked(data);
+        }
+        LibcConst::PTRACE_GETEVENTMSG => {
+            let msg = target.lock().ptraceEventMsg;
+            task.CopyOutObj(&msg, data)?;
+            return Ok(());
+        }
+        LibcConst::PTRACE_ARCH_PRCTL => {
+            // "addr" and "data" are swapped relative to arch_prctl(2).
+            match data {
+                x if x == PrCtlEnum::ARCH_GET_FS as u64 => {
+                    task.CopyOutObj(&targetTask.context.fs, addr)?;
+                }
+                x if x == PrCtlEnum::ARCH_SET_FS as u64 => {
+                    if !IsValidSegmentBase(addr) {
+                        return Err(Error::SysError(SysErr::EPERM));
+                    }
+                    targetTask.context.fs = addr;
+                }
+                _ => return Err(Error::SysError(SysErr::EINVAL)),
+            }
+            return Ok(());
+        }
+        _ => {
+            // PEEKUSR and POKEUSR of debug registers, and other unsupported
+            // requests.
+            return Err(Error::SysError(SysErr::EIO));
+        }
+    }
+}
+
+// peekUser returns the word at offset addr in struct user. Only the
+// user_regs_struct portion is supported.
+fn peekUser(regs: &PtraceRegs, addr: u64) -> Result<u64> {
+    let size = mem::size_of::<PtraceRegs>() as u64;
+    if addr & 7 != 0 || addr >= size {
+        return Err(Error::SysError(SysErr::EIO));
+    }
+
+    let base = regs as *const _ as u64;
+    return Ok(unsafe { *((base + addr) as *const u64) });
+}
+
+// pokeUser sets the word at offset addr in struct user to data.
+fn pokeUser(regs: &mut PtraceRegs, addr: u64, data: u64) -> Result<()> {
+    let size = mem::size_of::<PtraceRegs>() as u64;
+    if addr & 7 != 0 || addr >= size {
+        return Err(Error::SysError(SysErr::EIO));
+    }
+
+    let base = regs as *mut _ as u64;
+    unsafe { *((base + addr) as *mut u64) = data };
+    return Ok(());
+}
+
+// setRegs applies regs to the tracee's saved user registers.
+fn setRegs(targetTask: &mut Task, regs: &PtraceRegs) -> Result<()> {
+    if !IsValidSegmentBase(regs.fs_base) {
+        return Err(Error::SysError(SysErr::EIO));
+    }
+
+    regs.Apply(targetTask.GetPtRegs(), EflagsDef::EFLAGS_PTRACE_MUTABLE);
+    targetTask.context.fs = regs.fs_base;
+    return Ok(());
+}
+
+// getRegSet implements PTRACE_GETREGSET and returns the number of bytes
+// written to iov.
+fn getRegSet(task: &mut Task, targetTask: &mut Task, note: u64, iov: &IoVec) -> Result<usize> {
+    match note {
+        NT_PRSTATUS => {
+            let regs = PtraceRegs::New(targetTask.GetPtRegs(), targetTask.context.fs);
+            let len = iov.len.min(mem::size_of::<PtraceRegs>());
+            let buf = unsafe { core::slice::from_raw_parts(&regs as *const _ as *const u8, len) };
+            task.CopyOutSlice(buf, iov.start, len)?;
+            return Ok(len);
+        }
+        NT_PRFPREG | NT_X86_XSTATE => {
+            let fp = targetTask.context.X86fpstate.as_ref().unwrap();
+            let size = if note == NT_PRFPREG { FXSAVE_BYTES } else { fp.Size() };
+            let len = iov.len.min(size);
+            task.CopyOutSlice(&fp.Slice()[..len], iov.start, len)?;
+            return Ok(len);
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+}
+
+// setRegSet implements PTRACE_SETREGSET and returns the number of bytes
+// consumed from iov.
+fn setRegSet(task: &mut Task, targetTask: &mut Task, note: u64, iov: &IoVec) -> Result<usize> {
+    match note {
+        NT_PRSTATUS => {
+            let size = mem::size_of::<PtraceRegs>();
+            if iov.len < size {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let regs: PtraceRegs = task.CopyInObj(iov.start)?;
+            setRegs(targetTask, &regs)?;
+            return Ok(size);
+        }
+        NT_PRFPREG | NT_X86_XSTATE => {
+            let fp = targetTask.context.X86fpstate.as_ref().unwrap();
+            let size = if note == NT_PRFPREG { FXSAVE_BYTES } else { fp.Size() };
+            let len = iov.len.min(size);
+            let buf: Vec<u8> = task.CopyInVec(iov.start, len)?;
+            fp.Slice()[..len].copy_from_slice(&buf);
+            fp.SanitizeUser();
+            return Ok(len);
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+}
+
+// SysProcessVMReadv implements linux syscall process_vm_readv(2).
+pub fn SysProcessVMReadv(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    return processVMRW(task, args, false);
+}
+
+// SysProcessVMWritev implements linux syscall process_vm_writev(2).
+pub fn SysProcessVMWritev(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    return processVMRW(task, args, true);
+}
+
+fn processVMRW(task: &mut Task, args: &SyscallArguments, isWrite: bool) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let lvec = args.arg1 as u64;
+    let liovcnt = args.arg2 as i64;
+    let rvec = args.arg3 as u64;
+    let riovcnt = args.arg4 as i64;
+    let flags = args.arg5 as u64;
+
+    if flags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if liovcnt < 0 || liovcnt > UIO_MAXIOV as i64 || riovcnt < 0 || riovcnt > UIO_MAXIOV as i64 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if liovcnt == 0 || riovcnt == 0 {
+        return Ok(0);
+    }
+
+    let t = task.Thread();
+    let target = match t.PIDNamespace().TaskWithID(pid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(target) => target,
+    };
+
+    // "Permission to read from or write to another process is governed by a
+    // ptrace access mode PTRACE_MODE_ATTACH_REALCREDS check" -
+    // process_vm_readv(2)
+    if !t.CanTrace(&target) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let localIovs = task.IovsFromAddr(lvec, liovcnt as usize)?;
+    let remoteIovs = task.IovsFromAddr(rvec, riovcnt as usize)?;
+
+    // As rw_copy_check_uvector() does, the total is capped at MAX_RW_COUNT.
+    let localSize = localIovs.iter().fold(0usize, |n, iov| n.saturating_add(iov.len));
+    let remoteSize = remoteIovs.iter().fold(0usize, |n, iov| n.saturating_add(iov.len));
+    let total = localSize.min(remoteSize).min(MAX_RW_COUNT as usize);
+    if total == 0 {
+        return Ok(0);
+    }
+
+    // Copy through a bounded buffer, one piece of the current local and
+    // remote iovecs at a time.
+    let remoteMM = target.MemoryManager();
+    let buf = DataBuff::New(total.min(PROCESS_VM_CHUNK));
+    let (mut li, mut loff) = (0, 0);
+    let (mut ri, mut roff) = (0, 0);
+    let mut done = 0;
+    while done < total {
+        while localIovs[li].len == loff {
+            li += 1;
+            loff = 0;
+        }
+
+        while remoteIovs[ri].len == roff {
+            ri += 1;
+            roff = 0;
+        }
+
+        let len = (total - done)
+            .min(buf.Len())
+            .min(localIovs[li].len - loff)
+            .min(remoteIovs[ri].len - roff);
+        let local = localIovs[li].start + loff as u64;
+        let remote = remoteIovs[ri].start + roff as u64;
+
+        let ret = if isWrite {
+            match task.mm.CopyDataIn(task, local, buf.Ptr(), len, false) {
+                Err(e) => Err(e),
+                Ok(()) => remoteMM.CopyDataOutManual(task, buf.Ptr(), remote, len, false),
+            }
+        } else {
+            match remoteMM.CopyDataInManual(task, remote, buf.Ptr(), len, false) {
+                Err(e) => Err(e),
+                Ok(()) => task.mm.CopyDataOut(task, buf.Ptr(), local, len, false),
+            }
+        };
+
+        match ret {
+            Err(e) => {
+                if done == 0 {
+                    return Err(e);
+                }
+                break;
+            }
+            Ok(()) => (),
+        }
+
+        done += len;
+        loff += len;
+        roff += len;
+    }
+
+    return Ok(done as i64);
+}
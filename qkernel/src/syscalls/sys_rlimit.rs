@@ -66,7 +66,15 @@ pub fn PrLimit64(thread: &Thread, resource: LimitType, newLimit: Option<Limit>)
     let root = kernel.RootUserNamespace();
     let privileged = thread.HasCapabilityIn(Capability::CAP_SYS_RESOURCE, &root);
 
-    let oldLim = limits.Set(resource, newLimit.unwrap(), privileged)?;
+    let newLimit = newLimit.unwrap();
+
+    // As for Linux, the hard limit of RLIMIT_NOFILE can't be raised above
+    // fs.nr_open, even by a privileged process.
+    if resource == LimitType::NumberOfFiles && newLimit.Max > kernel.NrOpen() {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let oldLim = limits.Set(resource, newLimit, privileged)?;
 
     if resource == LimitType::CPU {
         thread.NotifyRlimitCPUUpdated()
@@ -12,14 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::vec::Vec;
+
 use super::super::qlib::common::*;
+use super::super::qlib::linux::time::*;
 use super::super::qlib::linux_def::*;
+use super::super::qlib::task_mgr::*;
 use super::super::task::*;
+use super::super::threadmgr::thread::*;
 use super::super::syscalls::syscalls::*;
 
-pub const ONLY_SCHEDULER : i32 = Sched::SCHED_NORMAL;
-pub const ONLY_PRIORITY : i32 = 0;
-
 // SchedParam replicates struct sched_param in sched.h.
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -27,6 +29,81 @@ pub struct SchedParam {
     pub schedPriority: i32,
 }
 
+// SchedAttr replicates struct sched_attr in sched.h, up to
+// SCHED_ATTR_SIZE_VER0. The SCHED_DEADLINE and utilization clamping fields
+// are not supported.
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SchedAttr {
+    pub size: u32,
+    pub schedPolicy: u32,
+    pub schedFlags: u64,
+    pub schedNice: i32,
+    pub schedPriority: u32,
+    pub schedRuntime: u64,
+    pub schedDeadline: u64,
+    pub schedPeriod: u64,
+}
+
+// schedTarget returns the thread pid refers to, 0 is the calling thread.
+fn schedTarget(task: &Task, pid: i32) -> Result<Thread> {
+    if pid < 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if pid == 0 {
+        return Ok(task.Thread());
+    }
+
+    let pidns = task.Thread().PIDNamespace();
+    match pidns.TaskWithID(pid) {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(t) => return Ok(t),
+    }
+}
+
+// setScheduler sets the scheduling policy and the real-time priority of t,
+// as __sched_setscheduler in Linux.
+fn setScheduler(task: &Task, t: &Thread, policy: i32, rtPriority: i32, resetOnFork: bool) -> Result<()> {
+    match policy {
+        Sched::SCHED_NORMAL | Sched::SCHED_BATCH | Sched::SCHED_IDLE => {
+            if rtPriority != 0 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+        }
+        Sched::SCHED_FIFO | Sched::SCHED_RR => {
+            if rtPriority < MIN_RT_PRIO || rtPriority > MAX_RT_PRIO {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+        }
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
+    }
+
+    if !task.Thread().MaySetSched(t) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    if !task.Thread().HasCapability(Capability::CAP_SYS_NICE) {
+        let (curPolicy, curRTPriority, curResetOnFork) = t.SchedPolicy();
+
+        // Without CAP_SYS_NICE, as with a RLIMIT_RTPRIO of 0, a task may only
+        // keep or lower its real-time priority.
+        if SchedEntity::IsRealtime(policy) {
+            if !SchedEntity::IsRealtime(curPolicy) || rtPriority > curRTPriority {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+        }
+
+        // Nor clear SCHED_RESET_ON_FORK.
+        if curResetOnFork && !resetOnFork {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+    }
+
+    t.SetSchedPolicy(policy, rtPriority, resetOnFork);
+    return Ok(());
+}
+
 // SchedGetparam implements linux syscall sched_getparam(2).
 pub fn SysSchedGetparam(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let pid = args.arg0 as i32;
@@ -36,41 +113,43 @@ pub fn SysSchedGetparam(task: &mut Task, args: &SyscallArguments) -> Result<i64>
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
-    if pid < 0 {
-        return Err(Error::SysError(SysErr::EINVAL));
-    }
-
-    let thread = task.Thread();
-    let tg = thread.ThreadGroup();
-    let pidns = tg.PIDNamespace();
-    if pid != 0 && pidns.TaskWithID(pid).is_none() {
-        return Err(Error::SysError(SysErr::ESRCH));
-    }
-
+    let t = schedTarget(task, pid)?;
+    let (_, rtPriority, _) = t.SchedPolicy();
     let r = SchedParam {
-        schedPriority: ONLY_PRIORITY,
+        schedPriority: rtPriority,
     };
 
     task.CopyOutObj(&r, param)?;
     return Ok(0)
 }
 
-// SchedGetscheduler implements linux syscall sched_getscheduler(2).
-pub fn SysSchedGetscheduler(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+// SchedSetparam implements linux syscall sched_setparam(2).
+pub fn SysSchedSetparam(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let pid = args.arg0 as i32;
+    let param = args.arg1 as u64;
 
-    if pid < 0 {
+    if param == 0 {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
-    let thread = task.Thread();
-    let tg = thread.ThreadGroup();
-    let pidns = tg.PIDNamespace();
-    if pid != 0 && pidns.TaskWithID(pid).is_none() {
-        return Err(Error::SysError(SysErr::ESRCH));
+    let t = schedTarget(task, pid)?;
+    let r: SchedParam = task.CopyInObj(param)?;
+    let (policy, _, resetOnFork) = t.SchedPolicy();
+    setScheduler(task, &t, policy, r.schedPriority, resetOnFork)?;
+    return Ok(0)
+}
+
+// SchedGetscheduler implements linux syscall sched_getscheduler(2).
+pub fn SysSchedGetscheduler(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pid = args.arg0 as i32;
+
+    let t = schedTarget(task, pid)?;
+    let (mut policy, _, resetOnFork) = t.SchedPolicy();
+    if resetOnFork {
+        policy |= Sched::SCHED_RESET_ON_FORK;
     }
 
-    return Ok(ONLY_SCHEDULER as i64)
+    return Ok(policy as i64)
 }
 
 // SchedSetscheduler implements linux syscall sched_setscheduler(2).
@@ -79,35 +158,169 @@ pub fn SysSchedSetscheduler(task: &mut Task, args: &SyscallArguments) -> Result<
     let policy = args.arg1 as i32;
     let param = args.arg2 as u64;
 
-    if pid < 0 {
+    if pid < 0 || param == 0 {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
-    if policy != ONLY_SCHEDULER {
-        return Err(Error::SysError(SysErr::EINVAL));
+    let resetOnFork = policy & Sched::SCHED_RESET_ON_FORK != 0;
+    let policy = policy & !Sched::SCHED_RESET_ON_FORK;
+
+    let t = schedTarget(task, pid)?;
+    let r: SchedParam = task.CopyInObj(param)?;
+    setScheduler(task, &t, policy, r.schedPriority, resetOnFork)?;
+    return Ok(0)
+}
+
+// SchedGetPriorityMax implements linux syscall sched_get_priority_max(2).
+pub fn SysSchedGetPriorityMax(_task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let policy = args.arg0 as i32;
+    match policy {
+        Sched::SCHED_FIFO | Sched::SCHED_RR => return Ok(MAX_RT_PRIO as i64),
+        Sched::SCHED_NORMAL | Sched::SCHED_BATCH | Sched::SCHED_IDLE => return Ok(0),
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
     }
+}
 
-    let thread = task.Thread();
-    let tg = thread.ThreadGroup();
-    let pidns = tg.PIDNamespace();
-    if pid != 0 && pidns.TaskWithID(pid).is_none() {
-        return Err(Error::SysError(SysErr::ESRCH));
+// SchedGetPriorityMin implements linux syscall sched_get_priority_min(2).
+pub fn SysSchedGetPriorityMin(_task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let policy = args.arg0 as i32;
+    match policy {
+        Sched::SCHED_FIFO | Sched::SCHED_RR => return Ok(MIN_RT_PRIO as i64),
+        Sched::SCHED_NORMAL | Sched::SCHED_BATCH | Sched::SCHED_IDLE => return Ok(0),
+        _ => return Err(Error::SysError(SysErr::EINVAL)),
     }
+}
+
+// SchedRRGetInterval implements linux syscall sched_rr_get_interval(2).
+pub fn SysSchedRRGetInterval(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let addr = args.arg1 as u64;
+
+    let t = schedTarget(task, pid)?;
+    let slice = t.SchedSlice();
+
+    let ts = Timespec::FromNs(slice as i64);
+    task.CopyOutObj(&ts, addr)?;
+    return Ok(0)
+}
+
+// copyInSchedAttr copies in the sched_attr of sched_setattr(2). A size which
+// is not supported is reported to the caller by writing the supported size
+// to attr.size.
+fn copyInSchedAttr(task: &Task, addr: u64) -> Result<SchedAttr> {
+    let ksize = core::mem::size_of::<SchedAttr>();
+    let mut size = task.CopyInObj::<u32>(addr)? as usize;
+    if size == 0 {
+        size = Sched::SCHED_ATTR_SIZE_VER0;
+    }
+
+    if size < Sched::SCHED_ATTR_SIZE_VER0 || size > MemoryDef::PAGE_SIZE as usize {
+        task.CopyOutObj(&(ksize as u32), addr)?;
+        return Err(Error::SysError(SysErr::E2BIG));
+    }
+
+    let attr: SchedAttr = task.CopyInObj(addr)?;
 
-    let r : SchedParam = task.CopyInObj(param)?;
-    if r.schedPriority != ONLY_PRIORITY {
+    // A newer struct is only accepted if the fields we don't know about are
+    // unset.
+    if size > ksize {
+        let rest: Vec<u8> = task.CopyInVec(addr + ksize as u64, size - ksize)?;
+        if rest.iter().any(|b| *b != 0) {
+            task.CopyOutObj(&(ksize as u32), addr)?;
+            return Err(Error::SysError(SysErr::E2BIG));
+        }
+    }
+
+    return Ok(attr);
+}
+
+// SchedSetattr implements linux syscall sched_setattr(2).
+pub fn SysSchedSetattr(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let addr = args.arg1 as u64;
+    let flags = args.arg2 as u32;
+
+    if addr == 0 || pid < 0 || flags != 0 {
         return Err(Error::SysError(SysErr::EINVAL));
     }
 
+    let attr = copyInSchedAttr(task, addr)?;
+
+    let supported = Sched::SCHED_FLAG_RESET_ON_FORK
+        | Sched::SCHED_FLAG_KEEP_POLICY
+        | Sched::SCHED_FLAG_KEEP_PARAMS;
+    if attr.schedFlags & !supported != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let t = schedTarget(task, pid)?;
+    let (curPolicy, curRTPriority, _) = t.SchedPolicy();
+
+    let policy = if attr.schedFlags & Sched::SCHED_FLAG_KEEP_POLICY != 0 {
+        curPolicy
+    } else {
+        attr.schedPolicy as i32
+    };
+
+    let keepParams = attr.schedFlags & Sched::SCHED_FLAG_KEEP_PARAMS != 0;
+    let rtPriority = if keepParams {
+        if SchedEntity::IsRealtime(policy) { curRTPriority } else { 0 }
+    } else {
+        attr.schedPriority as i32
+    };
+
+    // Values outside of [-20, 19] are truncated, as setpriority(2).
+    let setNice = !keepParams && !SchedEntity::IsRealtime(policy);
+    let nice = attr.schedNice.max(-20).min(19);
+    if setNice && nice < t.Niceness() && !task.Thread().CanNice(&t, nice) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let resetOnFork = attr.schedFlags & Sched::SCHED_FLAG_RESET_ON_FORK != 0;
+    setScheduler(task, &t, policy, rtPriority, resetOnFork)?;
+
+    if setNice {
+        t.SetNiceness(nice);
+    }
+
     return Ok(0)
 }
 
-// SchedGetPriorityMax implements linux syscall sched_get_priority_max(2).
-pub fn SysSchedGetPriorityMax(_task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
-    return Ok(ONLY_PRIORITY as i64)
-}
+// SchedGetattr implements linux syscall sched_getattr(2).
+pub fn SysSchedGetattr(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pid = args.arg0 as i32;
+    let addr = args.arg1 as u64;
+    let size = args.arg2 as u32 as usize;
+    let flags = args.arg3 as u32;
 
-// SchedGetPriorityMin implements linux syscall sched_get_priority_min(2).
-pub fn SysSchedGetPriorityMin(_task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
-    return Ok(ONLY_PRIORITY as i64)
-}
\ No newline at end of file
+    if addr == 0
+        || pid < 0
+        || flags != 0
+        || size < Sched::SCHED_ATTR_SIZE_VER0
+        || size > MemoryDef::PAGE_SIZE as usize
+    {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let t = schedTarget(task, pid)?;
+    let (policy, rtPriority, resetOnFork) = t.SchedPolicy();
+
+    let mut attr = SchedAttr {
+        size: core::mem::size_of::<SchedAttr>() as u32,
+        schedPolicy: policy as u32,
+        ..Default::default()
+    };
+
+    if resetOnFork {
+        attr.schedFlags |= Sched::SCHED_FLAG_RESET_ON_FORK;
+    }
+
+    if SchedEntity::IsRealtime(policy) {
+        attr.schedPriority = rtPriority as u32;
+    } else {
+        attr.schedNice = t.Niceness();
+    }
+
+    task.CopyOutObj(&attr, addr)?;
+    return Ok(0)
+}
@@ -12,21 +12,71 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::vec::Vec;
+
+use super::super::qlib::bpf::interpreter::*;
+use super::super::qlib::bpf::*;
 use super::super::qlib::common::*;
+use super::super::qlib::linux::seccomp::*;
 use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
 use super::super::task::*;
 
-pub const SECCOMP_MODE_NONE: i32 = 0;
-pub const SECCOMP_MODE_FILTER: i32 = 2;
+pub fn seccomp(task: &mut Task, mode: u64, flags: u64, addr: u64) -> Result<i64> {
+    if mode == SECCOMP_GET_ACTION_AVAIL {
+        if flags != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let action: u32 = task.CopyInObj(addr)?;
+        match action {
+            SECCOMP_RET_KILL_PROCESS
+            | SECCOMP_RET_KILL_THREAD
+            | SECCOMP_RET_TRAP
+            | SECCOMP_RET_ERRNO
+            | SECCOMP_RET_TRACE
+            | SECCOMP_RET_LOG
+            | SECCOMP_RET_ALLOW => return Ok(0),
+            _ => return Err(Error::SysError(SysErr::EOPNOTSUPP)),
+        }
+    }
+
+    // We only support SECCOMP_SET_MODE_FILTER at the moment.
+    if mode != SECCOMP_SET_MODE_FILTER {
+        // Unsupported mode.
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let tsync = flags & SECCOMP_FILTER_FLAG_TSYNC != 0;
 
-pub const SECCOMP_RET_ACTION_FULL: u64 = 0xffff0000;
-pub const SECCOMP_RET_ACTION: u64 = 0x7fff0000;
-pub const SECCOMP_RET_DATA: u64 = 0x0000ffff;
+    // The only flag we support now is SECCOMP_FILTER_FLAG_TSYNC.
+    if flags & !SECCOMP_FILTER_FLAG_TSYNC != 0 {
+        // Unsupported flag.
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
 
-pub const SECCOMP_SET_MODE_FILTER: u64 = 1;
-pub const SECCOMP_FILTER_FLAG_TSYNC: u64 = 1;
-pub const SECCOMP_GET_ACTION_AVAIL: u64 = 2;
+    let fprog: SockFprog = task.CopyInObj(addr)?;
+    if fprog.Len == 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let filter: Vec<BPFInstruction> = task.CopyInVec(fprog.Filter, fprog.Len as usize)?;
+    let compiledFilter = match Program::Compile(&filter) {
+        Err(e) => {
+            debug!("Invalid seccomp-bpf filter: {:?}", e);
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+        Ok(p) => p,
+    };
+
+    // "If any thread cannot synchronize to the same filter tree, the call
+    // will not attach the new seccomp filter, and will fail, returning the
+    // first thread ID found that cannot synchronize." - seccomp(2)
+    let tid = task.Thread().AppendSyscallFilter(compiledFilter, tsync)?;
+    return Ok(tid as i64);
+}
 
-pub fn seccomp(_task: &mut Task, _mode: u64, _flags: u64, _addr: u64) -> Result<i64> {
-    return Err(Error::SysError(SysErr::ENOSYS));
+// Seccomp implements linux syscall seccomp(2).
+pub fn SysSeccomp(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    return seccomp(task, args.arg0 as u64, args.arg1 as u64, args.arg2 as u64);
 }
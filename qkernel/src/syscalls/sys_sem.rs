@@ -26,8 +26,6 @@ use super::super::syscalls::syscalls::*;
 use super::super::task::*;
 use kernel::time::Time;
 
-pub const OPS_MAX : i32 = 500; // SEMOPM
-
 // Semget handles: semget(key_t key, int nsems, int semflg)
 pub fn SysSemgetl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let key = args.arg0 as i32;
@@ -60,7 +58,8 @@ pub fn SysSemtimedop(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         return Err(Error::SysError(SysErr::EINVAL))
     }
 
-    if nsops > OPS_MAX as _ {
+    // SEMOPM, as set by the kernel.sem sysctl.
+    if nsops > task.IPCNamespace().Limits().semOpm {
         return Err(Error::SysError(SysErr::E2BIG))
     }
 
@@ -80,7 +79,8 @@ pub fn SysSemop(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         return Err(Error::SysError(SysErr::EINVAL))
     }
 
-    if nsops > OPS_MAX as _ {
+    // SEMOPM, as set by the kernel.sem sysctl.
+    if nsops > task.IPCNamespace().Limits().semOpm {
         return Err(Error::SysError(SysErr::E2BIG))
     }
 
@@ -197,7 +197,7 @@ pub fn SysSemctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         IPC_INFO => {
             let buf = args.arg3 as u64;
             let r = task.IPCNamespace().SemaphoreRegistry();
-            let info = r.IPCInfo();
+            let info = r.IPCInfo(task);
             task.CopyOutObj(&info, buf)?;
             let lastIdx = r.lock().HighestIndex();
             return Ok(lastIdx as _)
@@ -205,7 +205,7 @@ pub fn SysSemctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         SEM_INFO => {
             let buf = args.arg3 as u64;
             let r = task.IPCNamespace().SemaphoreRegistry();
-            let info = r.SemInfo();
+            let info = r.SemInfo(task);
             task.CopyOutObj(&info, buf)?;
             let lastIdx = r.lock().HighestIndex();
             return Ok(lastIdx as _)
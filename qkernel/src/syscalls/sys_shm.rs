@@ -105,7 +105,7 @@ pub fn SysShmctl(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
             return Ok(0);
         }
         IPC_INFO => {
-            let params = r.IPCInfo();
+            let params = r.IPCInfo(task);
             task.CopyOutObj(&params, buf)?;
             return Ok(0);
         }
@@ -22,6 +22,7 @@ use super::super::task::*;
 use super::super::threadmgr::thread::*;
 use super::super::threadmgr::pid_namespace::*;
 use super::super::SignalDef::*;
+use super::sys_pidfd::*;
 use super::sys_poll::*;
 
 // "For a process to have permission to send a signal it must
@@ -575,6 +576,89 @@ pub fn SysTgkill(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Ok(0);
 }
 
+// PidfdSendSignal implements linux syscall pidfd_send_signal(2).
+pub fn SysPidfdSendSignal(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let pidfd = args.arg0 as i32;
+    let sig = args.arg1 as i32;
+    let infoAddr = args.arg2 as u64;
+    let flags = args.arg3 as u32;
+
+    // "The flags argument is reserved for future use; currently, this
+    // argument must be specified as 0." - pidfd_send_signal(2)
+    if flags != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let (_, tg) = GetPidfd(task, pidfd)?;
+
+    let t = task.Thread();
+    let pidns = t.PIDNamespace();
+
+    // "EINVAL pidfd refers to a process that is not in a PID namespace that
+    // is the same as or a descendant of the PID namespace of the calling
+    // process." - pidfd_send_signal(2)
+    let mut visible = false;
+    let mut ns = Some(tg.PIDNamespace());
+    while let Some(cur) = ns {
+        if cur == pidns {
+            visible = true;
+            break;
+        }
+
+        ns = cur.Parent();
+    }
+
+    if !visible {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let target = match tg.Leader() {
+        None => return Err(Error::SysError(SysErr::ESRCH)),
+        Some(t) => t,
+    };
+
+    let info = if infoAddr != 0 {
+        // As in RtSigqueueinfo, except that Linux rejects a mismatched
+        // si_signo rather than overriding it.
+        let info: SignalInfo = task.CopyInObj(infoAddr)?;
+        if info.Signo != sig {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // If the sender is not the receiver, it can't use si_codes used by the
+        // kernel or SI_TKILL.
+        if (info.Code >= 0 || info.Code == SignalInfo::SIGNAL_INFO_TKILL) && tg != t.ThreadGroup() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        info
+    } else {
+        // "If the info argument is a NULL pointer, this is equivalent to
+        // specifying a pointer to a siginfo_t buffer whose fields match the
+        // values that are implicitly supplied when a signal is sent using
+        // kill(2)" - pidfd_send_signal(2)
+        let mut info = SignalInfo {
+            Signo: sig,
+            Code: SignalInfo::SIGNAL_INFO_USER,
+            ..Default::default()
+        };
+
+        let creds = t.Credentials();
+        let sigRt = info.SigRt();
+        sigRt.pid = pidns.IDOfTask(&t);
+        let tuserns = target.UserNamespace();
+        sigRt.uid = creds.lock().RealKUID.In(&tuserns).OrOverflow().0;
+        info
+    };
+
+    if !mayKill(&t, &target, Signal(sig)) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    target.SendGroupSignal(&info)?;
+    return Ok(0);
+}
+
 pub fn SysRestartSyscall(task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
     let r = task.TakeSyscallRestartBlock();
     match r {
@@ -33,10 +33,9 @@ use super::super::kernel::timer::*;
 const MIN_LISTEN_BACKLOG: u32 = 8;
 
 // maxListenBacklog is the maximum allowed backlog for listening sockets.
-const MAX_LISTEN_BACKLOG: u32 = 1024;
 
 // maxAddrLen is the maximum socket address length we're willing to accept.
-const MAX_ADDR_LEN: u32 = 200;
+pub const MAX_ADDR_LEN: u32 = 200;
 
 // maxOptLen is the maximum sockopt parameter length we're willing to accept.
 const MAX_OPT_LEN: u32 = 1024;
@@ -181,10 +180,23 @@ pub fn Accept4(task: &Task, fd: i32, addr: u64, addrlen: u64, flags: i32) -> Res
 
     let file = task.GetFile(fd)?;
 
-    let sock = file.FileOp.clone();
-
     let blocking = !file.Flags().NonBlocking;
 
+    return AcceptFile(task, &file, addr, addrlen, flags, blocking);
+}
+
+// AcceptFile accepts a connection on the socket file, which may be looked up
+// by the caller in some other way than through the file descriptor table.
+pub fn AcceptFile(
+    task: &Task,
+    file: &File,
+    addr: u64,
+    addrlen: u64,
+    flags: i32,
+    blocking: bool,
+) -> Result<i64> {
+    let sock = file.FileOp.clone();
+
     let len = if addrlen == 0 {
         0
     } else {
@@ -260,8 +272,10 @@ pub fn SysListen(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let sock = file.FileOp.clone();
     let mut backlog = backlog;
 
-    if backlog >= MAX_LISTEN_BACKLOG {
-        backlog = MAX_LISTEN_BACKLOG;
+    // The backlog is capped by net.core.somaxconn.
+    let somaxconn = task.Thread().Kernel().Somaxconn();
+    if backlog >= somaxconn {
+        backlog = somaxconn;
     }
 
     // Accept one more than the configured listen backlog to keep in parity with
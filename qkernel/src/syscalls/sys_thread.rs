@@ -25,13 +25,16 @@ pub use xmas_elf::{P32, P64};
 
 use arch::x86_64::arch_x86::X86fpstate;
 use super::super::asm::*;
+use super::super::fs::fsutil::inode::simple_file_inode::*;
 use super::super::kernel::cpuset::*;
+use super::super::kernel::perf_event::*;
 use super::super::loader::loader::*;
 use super::super::memmgr::mm::*;
 use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::path::*;
 use super::super::qlib::LoadAddr;
+use super::super::qlib::linux::clone::*;
 use super::super::qlib::linux::rusage::*;
 use super::super::syscalls::syscalls::*;
 use super::super::task::*;
@@ -42,8 +45,10 @@ use super::super::threadmgr::task_exit::*;
 use super::super::threadmgr::task_sched::*;
 use super::super::threadmgr::thread::*;
 use super::super::vcpu::*;
+use super::super::qlib::vcpu_mgr::*;
 use super::super::SignalDef::*;
 use super::super::SHARESPACE;
+use super::sys_pidfd::*;
 use super::sys_rusage::*;
 
 #[derive(Default, Debug)]
@@ -181,6 +186,7 @@ pub fn ExecvFilleName(task: &mut Task, dirfd: i32, filename: &str, flags: i32) -
 }
 
 pub fn Execvat(task: &mut Task, dirfd: i32, filenameAddr: u64, argvAddr: u64, envvAddr: u64, flags: i32) -> Result<i64> {
+    let mut oldTID = 0;
     let (entry, usersp, kernelsp) = {
         let (fileName, err) = task.CopyInString(filenameAddr, PATH_MAX);
         match err {
@@ -211,6 +217,12 @@ pub fn Execvat(task: &mut Task, dirfd: i32, filenameAddr: u64, argvAddr: u64, en
     
         {
             let t = task.Thread().clone();
+            // oldTID is reported to the tracer by PTRACE_EVENT_EXEC, and must be
+            // read before promoteLocked changes t's thread ID.
+            oldTID = match t.Tracer() {
+                None => 0,
+                Some(tracer) => tracer.PIDNamespace().IDOfTask(&t),
+            };
             let tg = t.lock().tg.clone();
             let pidns = tg.PIDNamespace();
             let owner = pidns.lock().owner.clone();
@@ -346,7 +358,34 @@ pub fn Execvat(task: &mut Task, dirfd: i32, filenameAddr: u64, argvAddr: u64, en
         let extraAxv = Vec::new();
         Load(task, &fileName, &mut argv, &envv, &extraAxv)?
     };
+
+    EnablePerfEventsOnExec(&task.Thread());
     
+    if task.Thread().HasTracer() {
+        // Report the exec to the tracer with the new image's initial register
+        // state in place, so that it can be inspected and modified.
+        let pt = task.GetPtRegs();
+        *pt = Default::default();
+        pt.rip = entry;
+        pt.cs = 0x23;
+        pt.eflags = 0x2 | 1 << 9 | 1 << 12 | 1 << 13; //USER_FLAGS_SET;
+        pt.rsp = usersp;
+        pt.ss = 0x1b;
+
+        if task.Thread().PtraceExec(oldTID) {
+            task.DoStop();
+        }
+
+        CPULocal::SetKernelStack(kernelsp);
+        CPULocal::SetUserStack(pt.rsp);
+        task.AccountTaskEnter(SchedState::RunningApp);
+        // Load the new image's floating point state, which the tracer may also
+        // have changed.
+        task.context.X86fpstate.as_ref().unwrap().RestoreFp();
+        task.context.savefpsate = false;
+        IRet(pt as *const _ as u64);
+    }
+
     //need to clean object on stack before enter_user as the stack will be destroyed
     task.AccountTaskEnter(SchedState::RunningApp);
 
@@ -406,6 +445,134 @@ pub fn SysClone(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Ok(pid as i64);
 }
 
+// copyInCloneArgs copies in a struct clone_args of the given size, which may
+// be an older or newer version of the struct than CloneArgs. Equivalent to
+// kernel/fork.c:copy_clone_args_from_user.
+fn copyInCloneArgs(task: &Task, addr: u64, size: usize) -> Result<CloneArgs> {
+    if size > MemoryDef::PAGE_SIZE as usize {
+        return Err(Error::SysError(SysErr::E2BIG));
+    }
+
+    if size < CLONE_ARGS_SIZE_VER0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    let mut cargs = CloneArgs::default();
+    let ksize = core::mem::size_of::<CloneArgs>();
+    let n = core::cmp::min(size, ksize);
+    let buf: Vec<u8> = task.CopyInVec(addr, n)?;
+    unsafe {
+        core::ptr::copy_nonoverlapping(buf.as_ptr(), &mut cargs as *mut _ as *mut u8, n);
+    }
+
+    // A newer struct is only accepted if the fields we don't know about are
+    // unset.
+    if size > ksize {
+        let rest: Vec<u8> = task.CopyInVec(addr + ksize as u64, size - ksize)?;
+        if rest.iter().any(|b| *b != 0) {
+            return Err(Error::SysError(SysErr::E2BIG));
+        }
+    }
+
+    if cargs.SetTIDSize > MAX_PID_NS_LEVEL as u64 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if (cargs.SetTID == 0) != (cargs.SetTIDSize == 0) {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if cargs.ExitSignal & !CSIGNAL != 0 || cargs.ExitSignal > Signal::SIGNAL_MAX as u64 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if cargs.Flags & CLONE_INTO_CGROUP != 0
+        && (cargs.Cgroup > i32::MAX as u64 || size < CLONE_ARGS_SIZE_VER2)
+    {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    return Ok(cargs);
+}
+
+// Clone3 implements linux syscall clone3(2).
+pub fn SysClone3(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let addr = args.arg0;
+    let size = args.arg1 as usize;
+
+    let cargs = copyInCloneArgs(task, addr, size)?;
+
+    // Equivalent to kernel/fork.c:clone3_args_valid.
+    if cargs.Flags & !(0xffffffff | CLONE_CLEAR_SIGHAND | CLONE_INTO_CGROUP) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // The exit signal has its own field, and CLONE_DETACHED is no longer
+    // ignored.
+    if cargs.Flags & (LibcConst::CLONE_DETACHED | CSIGNAL) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // "EINVAL Both CLONE_SIGHAND and CLONE_CLEAR_SIGHAND were specified in
+    // flags." - clone(2)
+    if cargs.Flags & LibcConst::CLONE_SIGHAND != 0 && cargs.Flags & CLONE_CLEAR_SIGHAND != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if cargs.Flags & (LibcConst::CLONE_THREAD | LibcConst::CLONE_PARENT) != 0
+        && cargs.ExitSignal != 0
+    {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // "EINVAL The stack and stack_size fields ... are inconsistent: one of
+    // them is zero and the other is not." - clone(2)
+    if (cargs.Stack == 0) != (cargs.StackSize == 0) {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // The cgroup hierarchies of the sandbox only have the root cgroup, which
+    // all the processes are in, so the child of CLONE_INTO_CGROUP stays where
+    // it would be anyway. As in Linux, a file which isn't a cgroup2 directory
+    // is EBADF.
+    if cargs.Flags & CLONE_INTO_CGROUP != 0 {
+        let file = task.GetFile(cargs.Cgroup as i32)?;
+        let inode = file.Dirent.Inode();
+        let fsType = inode.lock().MountSource.lock().FileSystemType.clone();
+        if !inode.StableAttr().IsDir() || fsType.as_str() != "cgroup2" {
+            return Err(Error::SysError(SysErr::EBADF));
+        }
+    }
+
+    let mut stack = 0;
+    if cargs.Stack != 0 {
+        // The stack grows down, so the child starts at the top of the area.
+        stack = cargs.Stack + cargs.StackSize;
+    }
+
+    let flags = (cargs.Flags & 0xffffffff) | cargs.ExitSignal;
+    let mut opts = CloneOptions::New(
+        flags,
+        stack,
+        cargs.ParentTID,
+        cargs.ChildTID,
+        cargs.TLS,
+        false,
+    )?;
+
+    opts.PIDFDAddr = cargs.Pidfd;
+    opts.ClearSignalHandlers = cargs.Flags & CLONE_CLEAR_SIGHAND != 0;
+
+    if cargs.SetTIDSize > 0 {
+        let tids: Vec<ThreadID> = task.CopyInVec(cargs.SetTID, cargs.SetTIDSize as usize)?;
+        opts.SetTIDSize = tids.len();
+        opts.SetTID[..tids.len()].copy_from_slice(&tids);
+    }
+
+    let pid = task.CloneWithOptions(&opts)?;
+    return Ok(pid as i64);
+}
+
 // Fork implements Linux syscall fork(2).
 pub fn SysFork(task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
     let pid = task.Clone(Signal::SIGCHLD as u64, 0, 0, 0, 0)?;
@@ -467,6 +634,7 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         ..Default::default()
     };
 
+    let mut pidfdNonBlocking = false;
     match idtype {
         IDType::P_ALL | IDType::P_PID => {
             wopts.SpecificTID = id;
@@ -474,10 +642,34 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         IDType::P_PGID => {
             wopts.SpecificPGID = id;
         }
+        IDType::P_PIDFD => {
+            if id < 0 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let (file, tg) = GetPidfd(task, id)?;
+
+            // A process that isn't visible in the caller's PID namespace, or
+            // has already been reaped, can't be a waitable child.
+            let tid = task.Thread().PIDNamespace().IDOfThreadGroup(&tg);
+            if tid == 0 {
+                return Err(Error::SysError(SysErr::ECHILD));
+            }
+
+            wopts.SpecificTID = tid;
+            pidfdNonBlocking = !file.Blocking();
+        }
         _ => return Err(Error::SysError(SysErr::EINVAL)),
     }
 
-    parseCommonWaitOptions(&mut wopts, options)?;
+    // "EAGAIN The PID file descriptor specified in id is nonblocking and the
+    // process that it refers to has not terminated." - waitid(2)
+    let mut waitOptions = options;
+    if pidfdNonBlocking {
+        waitOptions |= WaitOption::WNOHANG;
+    }
+
+    parseCommonWaitOptions(&mut wopts, waitOptions)?;
     if options & WaitOption::WEXITED != 0 {
         wopts.Events |= EVENT_EXIT;
     }
@@ -487,6 +679,11 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     }
 
     let wr = match task.Wait(&wopts) {
+        Err(Error::ErrNoWaitableEvent)
+            if pidfdNonBlocking && options & WaitOption::WNOHANG == 0 =>
+        {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
         Err(Error::ErrNoWaitableEvent) => {
             // "If WNOHANG was specified in options and there were no children
             // in a waitable state, then waitid() returns 0 immediately and the
@@ -530,12 +727,12 @@ pub fn SysWaitid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     if s.Exited() {
         siCode = SignalInfo::CLD_EXITED;
         sigChld.status = s.ExitStatus();
-    } else if s.Signaled() {
-        siCode = SignalInfo::CLD_KILLED;
-        sigChld.status = s.Signal();
     } else if s.CoreDump() {
         siCode = SignalInfo::CLD_DUMPED;
         sigChld.status = s.Signal();
+    } else if s.Signaled() {
+        siCode = SignalInfo::CLD_KILLED;
+        sigChld.status = s.Signal();
     } else if s.Stopped() {
         if wr.Event == EVENT_TRACEE_STOP {
             siCode = SignalInfo::CLD_TRAPPED;
@@ -672,6 +869,7 @@ pub fn SysUnshare(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         NewFSContext: flags & CloneOp::CLONE_FS == CloneOp::CLONE_FS,
         NewUTSNamespace: flags & CloneOp::CLONE_NEWUTS == CloneOp::CLONE_NEWUTS,
         NewIPCNamespace: flags & CloneOp::CLONE_NEWIPC == CloneOp::CLONE_NEWIPC,
+        NewMountNamespace: flags & CloneOp::CLONE_NEWNS == CloneOp::CLONE_NEWNS,
         ..Default::default()
     };
 
@@ -685,13 +883,40 @@ pub fn SysUnshare(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     if opts.NewUserNamespace {
         opts.NewThreadGroup = true;
         opts.NewFSContext = true;
-        panic!("Doesn't support create new usernamespace...");
+    }
+
+    // "CLONE_NEWNS ... Use of CLONE_NEWNS requires the CAP_SYS_ADMIN
+    // capability. ... Specifying this flag automatically implies CLONE_FS as
+    // well." - unshare(2)
+    if opts.NewMountNamespace {
+        opts.NewFSContext = true;
     }
 
     task.Unshare(&opts)?;
     return Ok(0);
 }
 
+// Setns implements linux syscall setns(2).
+pub fn SysSetns(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let fd = args.arg0 as i32;
+    let nstype = args.arg1 as i32;
+
+    let file = task.GetFile(fd)?;
+
+    let inode = file.Dirent.Inode();
+    let iops = inode.lock().InodeOp.clone();
+    let ns = match iops.as_any().downcast_ref::<SimpleFileInode>() {
+        None => return Err(Error::SysError(SysErr::EINVAL)),
+        Some(iops) => match &iops.read().data {
+            SimpleFileImpl::NamespaceFile(f) => f.ns.clone(),
+            _ => return Err(Error::SysError(SysErr::EINVAL)),
+        },
+    };
+
+    task.Setns(&ns, nstype)?;
+    return Ok(0);
+}
+
 // SchedYield implements linux syscall sched_yield(2).
 pub fn SysScheduleYield(_task: &mut Task, _args: &SyscallArguments) -> Result<i64> {
     Yield();
@@ -921,9 +1146,7 @@ pub fn SysGetsid(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     return Ok(pidns.IDOfSession(&session) as i64);
 }
 
-// Getpriority pretends to implement the linux syscall getpriority(2).
-//
-// This is a stub; real priorities require a full scheduler.
+// Getpriority implements the linux syscall getpriority(2).
 pub fn SysGetpriority(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let which = args.arg0 as i32;
     let who = args.arg1 as i32;
@@ -952,9 +1175,8 @@ pub fn SysGetpriority(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     }
 }
 
-// Setpriority pretends to implement the linux syscall setpriority(2).
-//
-// This is a stub; real priorities require a full scheduler.
+// Setpriority implements the linux syscall setpriority(2). The niceness
+// sets the weight of the task in the fair scheduling class.
 pub fn SysSetpriority(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
     let which = args.arg0 as i32;
     let who = args.arg1 as i32;
@@ -985,6 +1207,15 @@ pub fn SysSetpriority(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
                 }
             };
 
+            let thread = task.Thread();
+            if !thread.MaySetSched(&t) {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+
+            if niceval < t.Niceness() && !thread.CanNice(&t, niceval) {
+                return Err(Error::SysError(SysErr::EACCES));
+            }
+
             t.SetNiceness(niceval);
             return Ok(0);
         }
@@ -997,3 +1228,18 @@ pub fn SysSetpriority(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
         }
     }
 }
+
+// SysPersonality implements linux syscall personality(2). The sandbox only
+// runs PER_LINUX binaries, the other personalities are recorded but have no
+// effect.
+pub fn SysPersonality(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let persona = args.arg0 as u32;
+
+    let thread = task.Thread();
+    // 0xffffffff queries the personality without changing it.
+    if persona == 0xffffffff {
+        return Ok(thread.Personality() as i64);
+    }
+
+    return Ok(thread.SetPersonality(persona) as i64);
+}
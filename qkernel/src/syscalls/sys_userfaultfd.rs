@@ -0,0 +1,53 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::kernel::fd_table::*;
+use super::super::qlib::common::*;
+use super::super::qlib::kernel::kernel::userfaultfd::*;
+use super::super::qlib::linux::userfaultfd::*;
+use super::super::qlib::linux_def::*;
+use super::super::syscalls::syscalls::*;
+use super::super::task::*;
+
+// Userfaultfd implements linux syscall userfaultfd(2).
+pub fn SysUserfaultfd(task: &mut Task, args: &SyscallArguments) -> Result<i64> {
+    let flags = args.arg0 as i32;
+
+    if flags & !(UFFD_CLOEXEC | UFFD_NONBLOCK | UFFD_USER_MODE_ONLY) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // Like Linux with vm.unprivileged_userfaultfd=0, a userfaultfd which
+    // handles kernel mode faults requires CAP_SYS_PTRACE.
+    //
+    // Kernel mode faults are handled for the user buffers the kernel checks
+    // before using them, e.g. those of read(2), write(2) and their variants,
+    // whose syscalls block until the page is populated. The other kernel
+    // accesses to a missing page of a registered range, such as copying in
+    // the arguments of a syscall, still fail with EFAULT.
+    if flags & UFFD_USER_MODE_ONLY == 0 && !task.Creds().HasCapability(Capability::CAP_SYS_PTRACE) {
+        return Err(Error::SysError(SysErr::EPERM));
+    }
+
+    let file = NewUserfaultfd(task, flags);
+    let fd = task.NewFDFrom(
+        0,
+        &file,
+        &FDFlags {
+            CloseOnExec: flags & UFFD_CLOEXEC != 0,
+        },
+    )?;
+
+    return Ok(fd as i64);
+}
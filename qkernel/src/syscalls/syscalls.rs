@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::sync::atomic::Ordering;
+
 use super::super::syscalls::sys_aio::*;
 use super::super::syscalls::sys_capability::*;
 use super::super::syscalls::sys_chmod::*;
@@ -21,13 +23,20 @@ use super::super::syscalls::sys_file::*;
 use super::super::syscalls::sys_futex::*;
 use super::super::syscalls::sys_getdents::*;
 use super::super::syscalls::sys_identity::*;
+use super::super::syscalls::sys_io_uring::*;
 use super::super::syscalls::sys_membarrier::*;
 use super::super::syscalls::sys_mempolicy::*;
 use super::super::syscalls::sys_mmap::*;
+use super::super::syscalls::sys_mount::*;
+use super::super::syscalls::sys_mqueue::*;
+use super::super::syscalls::sys_perf_event::*;
+use super::super::syscalls::sys_pidfd::*;
 use super::super::syscalls::sys_pipe::*;
 use super::super::syscalls::sys_poll::*;
 use super::super::syscalls::sys_prctl::*;
+use super::super::syscalls::sys_ptrace::*;
 use super::super::syscalls::sys_random::*;
+use super::super::syscalls::sys_seccomp::*;
 use super::super::syscalls::sys_read::*;
 use super::super::syscalls::sys_rlimit::*;
 use super::super::syscalls::sys_rusage::*;
@@ -42,6 +51,7 @@ use super::super::syscalls::sys_time::*;
 use super::super::syscalls::sys_timer::*;
 use super::super::syscalls::sys_timerfd::*;
 use super::super::syscalls::sys_tls::*;
+use super::super::syscalls::sys_userfaultfd::*;
 use super::super::syscalls::sys_utsname::*;
 use super::super::syscalls::sys_write::*;
 use super::super::syscalls::sys_memfd::*;
@@ -59,6 +69,9 @@ use super::super::qlib::common::*;
 use super::super::qlib::linux_def::*;
 use super::super::qlib::SysCallID;
 use super::super::task::*;
+use super::super::threadmgr::task_exit::*;
+use super::super::threadmgr::task_ptrace::*;
+use super::super::threadmgr::task_seccomp::*;
 
 //#[repr(align(128))]
 #[derive(Debug)]
@@ -73,6 +86,153 @@ pub struct SyscallArguments {
 
 #[inline]
 pub fn SysCall(task: &mut Task, nr: u64, args: &SyscallArguments) -> TaskRunState {
+    // Check seccomp filters, including the linux.seccomp profile of the
+    // container. The hasSyscallFilters check is for performance (as seccomp
+    // use is rare), not needed for correctness.
+    if task.hasSyscallFilters.load(Ordering::Acquire) {
+        let thread = task.Thread();
+        let ip = task.GetPtRegs().rip;
+        let sargs = [args.arg0, args.arg1, args.arg2, args.arg3, args.arg4, args.arg5];
+        match task.CheckSeccompSyscall(nr as i32, &sargs, ip) {
+            SeccompAction::Allow => (),
+            SeccompAction::Deny => {
+                debug!("Syscall {}: denied by seccomp", nr);
+                return TaskRunState::RunApp;
+            }
+            SeccompAction::KillThread => {
+                info!("Syscall {}: killed by seccomp", nr);
+                thread.PrepareExit(ExitStatus::New(0, Signal::SIGSYS));
+                return TaskRunState::RunThreadExit;
+            }
+            SeccompAction::KillProcess => {
+                info!("Syscall {}: killed by seccomp", nr);
+                thread.PrepareGroupExit(ExitStatus::New(0, Signal::SIGSYS));
+                return TaskRunState::RunExit;
+            }
+            SeccompAction::Trace => {
+                debug!("Syscall {}: stopping for PTRACE_EVENT_SECCOMP", nr);
+                task.DoStop();
+                if thread.lock().killed() {
+                    return TaskRunState::RunInterrupt;
+                }
+
+                // "The tracer will be notified of a PTRACE_EVENT_SECCOMP and the
+                // SECCOMP_RET_DATA portion of the BPF program return value will be
+                // available to the tracer via PTRACE_GETEVENTMSG. The tracer can
+                // skip the system call by changing the syscall number to -1.
+                // Alternatively, the tracer can change the system call requested
+                // by changing the system call to a valid syscall number." -
+                // seccomp(2)
+                let pt = task.GetPtRegs();
+                let nr = pt.orig_rax;
+                if nr as i64 == -1 {
+                    return TaskRunState::RunApp;
+                }
+
+                let args = SyscallArguments {
+                    arg0: pt.rdi,
+                    arg1: pt.rsi,
+                    arg2: pt.rdx,
+                    arg3: pt.r10,
+                    arg4: pt.r8,
+                    arg5: pt.r9,
+                };
+
+                return doSyscallEnter(task, nr, &args);
+            }
+        }
+    }
+
+    return doSyscallEnter(task, nr, args);
+}
+
+// doSyscallEnter runs the syscall, routing it through the ptrace syscall
+// stops if the task is traced.
+pub fn doSyscallEnter(task: &mut Task, nr: u64, args: &SyscallArguments) -> TaskRunState {
+    if task.traced.load(Ordering::Acquire) {
+        return PtraceSysCall(task, nr, args);
+    }
+
+    if (nr as usize) >= SYS_CALL_TABLE.len()
+        && ((nr as usize) < EXTENSION_CALL_OFFSET
+            || (nr as usize) - EXTENSION_CALL_OFFSET >= EXTENSION_CALL_TABLE.len())
+    {
+        task.haveSyscallReturn = true;
+        task.SetReturn(-SysErr::ENOSYS as u64);
+        return TaskRunState::RunApp;
+    }
+
+    return doSyscallInvoke(task, nr, args);
+}
+
+// PtraceSysCall runs a syscall made by a ptraced task, entering
+// syscall-enter-stop and syscall-exit-stop as requested by the tracer.
+pub fn PtraceSysCall(task: &mut Task, nr: u64, args: &SyscallArguments) -> TaskRunState {
+    let thread = task.Thread();
+
+    // Tracers expect to see this between when the task traps into the kernel
+    // to perform a syscall and when the syscall is actually invoked.
+    task.SetReturn(-SysErr::ENOSYS as u64);
+
+    let mut nr = nr;
+    let mut args = SyscallArguments {
+        arg0: args.arg0,
+        arg1: args.arg1,
+        arg2: args.arg2,
+        arg3: args.arg3,
+        arg4: args.arg4,
+        arg5: args.arg5,
+    };
+
+    if let Some(mode) = thread.PtraceSyscallEnter() {
+        task.DoStop();
+        if thread.PtraceAfterSyscallStop() {
+            return TaskRunState::RunInterrupt;
+        }
+
+        // Skip invocation.
+        if mode == PtraceSyscallMode::Emu {
+            return TaskRunState::RunApp;
+        }
+
+        // The tracer may have changed the syscall number and arguments.
+        let pt = task.GetPtRegs();
+        nr = pt.orig_rax;
+        args = SyscallArguments {
+            arg0: pt.rdi,
+            arg1: pt.rsi,
+            arg2: pt.rdx,
+            arg3: pt.r10,
+            arg4: pt.r8,
+            arg5: pt.r9,
+        };
+    }
+
+    let state = if (nr as i64) < 0 {
+        // The tracer asked for the syscall to be skipped; leave the return
+        // value as it set it.
+        TaskRunState::RunApp
+    } else if (nr as usize) >= SYS_CALL_TABLE.len()
+        && ((nr as usize) < EXTENSION_CALL_OFFSET
+            || (nr as usize) - EXTENSION_CALL_OFFSET >= EXTENSION_CALL_TABLE.len())
+    {
+        task.haveSyscallReturn = true;
+        task.SetReturn(-SysErr::ENOSYS as u64);
+        TaskRunState::RunApp
+    } else {
+        doSyscallInvoke(task, nr, &args)
+    };
+
+    if state == TaskRunState::RunApp && thread.PtraceSyscallExit() {
+        task.DoStop();
+        thread.PtraceAfterSyscallStop();
+    }
+
+    return state;
+}
+
+#[inline]
+pub fn doSyscallInvoke(task: &mut Task, nr: u64, args: &SyscallArguments) -> TaskRunState {
     let idx = nr as usize;
     let func = match SYS_CALL_TABLE.get(idx) {
         Some(f) => f,
@@ -81,7 +241,14 @@ pub fn SysCall(task: &mut Task, nr: u64, args: &SyscallArguments) -> TaskRunStat
         },
     };
 
-    match func(task, args) {
+    let res = func(task, args);
+
+    // Charge the pages which the syscall populated, e.g. by copying out to
+    // user memory that wasn't faulted in yet, to the memory limit of the
+    // container. If this OOM kills the task, SIGKILL is delivered on return.
+    let _ = task.mm.ChargePending(task);
+
+    match res {
         Err(Error::SysCallRetCtrlWithRet(state, ret)) => {
             task.SetReturn(ret);
             return state;
@@ -236,7 +403,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysGetrusage,        // 098 sys_getrusage,
     SysInfo,             // 099 sys_sysinfo,
     SysTimes,            // 100 sys_times,
-    SysNoSupport,        // 101 sys_ptrace,
+    SysPtrace,           // 101 sys_ptrace,
     SysGetuid,           // 102 sys_getuid,
     SysSysLog,           // 103 sys_syslog,
     SysGetgid,           // 104 sys_getgid,
@@ -270,20 +437,20 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysUtime,            // 132 sys_utime,
     SysMknode,           // 133 sys_mknod,
     SysObsolete,         // 134 sys_uselib,
-    SysInvalid,          // 135 sys_personality,
+    SysPersonality,      // 135 sys_personality,
     SysNoSys,            // 136 sys_ustat,      Needs filesystem support.
     SysStatfs,           // 137 sys_statfs,
     SysFstatfs,          // 138 sys_fstatfs,
     NotImplementSyscall, // 139 sys_sysfs,
     SysGetpriority,      // 140 sys_getpriority,
     SysSetpriority,      // 141 sys_setpriority,
-    SysCapErr,           // 142 sys_sched_setparam,
+    SysSchedSetparam,    // 142 sys_sched_setparam,
     SysSchedGetparam,    // 143 sys_sched_getparam	,
     SysSchedSetscheduler,// 144 sys_sched_setscheduler,
     SysSchedGetscheduler, // 145 sys_sched_getscheduler,
     SysSchedGetPriorityMax,// 146 sys_sched_get_priority_max,
     SysSchedGetPriorityMin,// 147 sys_sched_get_priority_min,
-    SysNoPermission,     // 148 sys_sched_rr_get_interval,
+    SysSchedRRGetInterval,// 148 sys_sched_rr_get_interval,
     SysMlock,            // 149 sys_mlock,
     SysMunlock,          // 150 sys_munlock,
     SysMlockall,         // 151 sys_mlockall,
@@ -300,8 +467,8 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysSync,             // 162 sys_sync,
     SysCapErr,           // 163 sys_acct,
     SysCapErr,           // 164 sys_settimeofday,
-    NotImplementSyscall, // 165 sys_mount,
-    NotImplementSyscall, // 166 sys_umount2,
+    SysMount,            // 165 sys_mount,
+    SysUmount2,          // 166 sys_umount2,
     SysCapErr,           // 167 sys_swapon,
     SysCapErr,           // 168 sys_swapoff,
     SysCapErr,           // 169 sys_reboot,
@@ -375,12 +542,12 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysMbind,            // 237 sys_mbind, just workaround
     SysSetMempolicy,     // 238 sys_set_mempolicy,
     SysGetMempolicy,     // 239 sys_get_mempolicy,
-    SysNoSupport,        // 240 sys_mq_open,
-    SysNoSupport,        // 241 sys_mq_unlink,
-    SysNoSupport,        // 242 sys_mq_timedsend,
-    SysNoSupport,        // 243 sys_mq_timedreceive,
-    SysNoSupport,        // 244 sys_mq_notify,
-    SysNoSupport,        // 245 sys_mq_getsetattr,
+    SysMqOpen,           // 240 sys_mq_open,
+    SysMqUnlink,         // 241 sys_mq_unlink,
+    SysMqTimedsend,      // 242 sys_mq_timedsend,
+    SysMqTimedreceive,   // 243 sys_mq_timedreceive,
+    SysMqNotify,         // 244 sys_mq_notify,
+    SysMqGetsetattr,     // 245 sys_mq_getsetattr,
     SysCapErr,           // 246 sys_kexec_load,          CAP_SYS_BOOT
     SysWaitid,           // 247 sys_waitid,
     SysNoAccess,         // 248 sys_add_key,              Not available to user.
@@ -407,7 +574,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysFaccessat,        // 269 sys_faccessat,
     SysPSelect,          // 270 sys_pselect6,
     SysPpoll,            // 271 sys_ppoll,
-    NotImplementSyscall, // 272 sys_unshare,
+    SysUnshare,          // 272 sys_unshare,
     SysSetRobustList,    // 273 sys_set_robust_list,
     SysGetRobustList,    // 274 sys_get_robust_list,
     SysSplice,           // 275 sys_splice,
@@ -433,7 +600,7 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysPreadv,           // 295 sys_preadv,
     SysPwritev,          // 296 sys_pwritev,
     SysRtTgsigqueueinfo, // 297 sys_rt_tgsigqueueinfo,
-    SysNoDev,            // 298 sys_perf_event_open,     No support for perf counters
+    SysPerfEventOpen,    // 298 sys_perf_event_open,
     SysRecvMMsg,         // 299 sys_recvmmsg,
     SysNoSys,            //	300 sys_fanotify_init,       Needs CONFIG_FANOTIFY
     SysNoSys,            //	309 sys_fanotify_mark,       Needs CONFIG_FANOTIFY
@@ -443,22 +610,22 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     SysCapErr,           //	305 sys_clock_adjtime,       CAP_SYS_TIME
     SysSyncFs,           //	304 sys_syncfs,
     SysSendMMsg,         //	303 sys_sendmmsg,
-    SysOpNotSupport,     //	302 sys_setns,                   Needs filesystem support
+    SysSetns,            //	308 sys_setns,
     SysGetcpu,           //	301 sys_getcpu,
-    SysNoSys,            //	310 sys_process_vm_readv    Need ptrace
-    SysNoSys,            //	311 sys_process_vm_writev
+    SysProcessVMReadv,   //	310 sys_process_vm_readv
+    SysProcessVMWritev,  //	311 sys_process_vm_writev
     SysCapErr,           //	312 sys_kcmp,                CAP_SYS_PTRACE
     SysCapErr,           //	313 sys_finit_module,        CAP_SYS_MODULE
-    SysNoSys,            //	314 sys_sched_setattr,       implement scheduler?
-    SysNoSys,            //	315 sys_sched_getattr,       implement scheduler?
+    SysSchedSetattr,     //	314 sys_sched_setattr,
+    SysSchedGetattr,     //	315 sys_sched_getattr,
     SysNoSupport,        //	316 sys_renameat2,
-    NotImplementSyscall, //	317 sys_seccomp,
+    SysSeccomp,          //	317 sys_seccomp,
     SysGetRandom,        //	318 sys_getrandom,
     SysMemfdCreate,      //	319 sys_memfd_create,
     SysCapErr,           //	320 sys_kexec_file_load    CAP_SYS_BOOT
     SysCapErr,           //	321 sys_bpf,                 CAP_SYS_ADMIN
     SysExecveat,         //	322 sys_stub_execveat,
-    NotImplementSyscall, //	323 sys_userfaultfd,
+    SysUserfaultfd,      //	323 sys_userfaultfd,
     SysMembarrier,       //	324 sys_membarrier,
     SysMlock2,           //	325 mlock2,
 
@@ -567,22 +734,22 @@ pub const SYS_CALL_TABLE: &'static [SyscallFn] = &[
     //don't use numbers 334 through 423
 
     // Linux skips ahead to syscall 424 to sync numbers between arches.
-    NotImplementSyscall, //	424 sys_pidfd_send_signal
-    SysNoSys,            //	425 sys_io_uring_setup
-    SysNoSys,            //	426 sys_io_uring_enter
-    SysNoSys,            //	427 sys_io_uring_register
+    SysPidfdSendSignal,  //	424 sys_pidfd_send_signal
+    SysIoUringSetup,     //	425 sys_io_uring_setup
+    SysIoUringEnter,     //	426 sys_io_uring_enter
+    SysIoUringRegister,  //	427 sys_io_uring_register
     NotImplementSyscall, //	428 sys_open_tree
     NotImplementSyscall, //	429 sys_move_mount
     NotImplementSyscall, //	430 sys_fsopen
     NotImplementSyscall, //	431 sys_fsconfig
     NotImplementSyscall, //	432 sys_fsmount
     NotImplementSyscall, //	433 sys_fspick
-    NotImplementSyscall, //	434 sys_pidfd_open
-    SysNoSys,            //	435 sys_clone3
+    SysPidfdOpen,        //	434 sys_pidfd_open
+    SysClone3,           //	435 sys_clone3
     SysCloseRange,       //	436 sys_close_range
-    NotImplementSyscall, //	437 sys_openat2
-    NotImplementSyscall, //	438 sys_pidfd_getfd
-    SysNoSys,            //	439 sys_faccessat2
+    SysOpenat2,          //	437 sys_openat2
+    SysPidfdGetfd,       //	438 sys_pidfd_getfd
+    SysFaccessat2,       //	439 sys_faccessat2
     NotImplementSyscall, //	440 sys_process_madvise
     SysPwait2,           //	441 sys_epoll_pwait2
     NotImplementSyscall, //	442 sys_mouLoad(nt_setattr
@@ -328,9 +328,12 @@ impl Credentials {
             return Err(Error::SysError(SysErr::EPERM));
         }
 
+        let setgroupsDenied = ns.lock().setgroupsDenied;
         let internal = UserNameSpaceInternal {
             parent: Some(ns),
             owner: self.lock().EffectiveKUID,
+            setgroupsDenied: setgroupsDenied,
+            id: NewNamespaceInode(),
             ..Default::default()
         };
 
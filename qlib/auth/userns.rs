@@ -21,6 +21,7 @@ use core::ops::Deref;
 
 use super::super::common::*;
 use super::super::linux_def::*;
+use super::super::kernel::kernel::nsfs::*;
 use super::id::*;
 
 #[derive(Default, Debug)]
@@ -32,6 +33,14 @@ pub struct UserNameSpaceInternal {
     pub uidMapToParent: IdMap,
     pub gidMapFromParent: IdMap,
     pub gidMapToParent: IdMap,
+
+    // setgroupsDenied is set once "deny" is written to /proc/[pid]/setgroups,
+    // setgroups(2) is then not permitted in the namespace. It is inherited by
+    // child namespaces.
+    pub setgroupsDenied: bool,
+
+    // id is the namespace's inode number.
+    pub id: u64,
 }
 
 impl UserNameSpaceInternal {
@@ -85,11 +94,17 @@ impl UserNameSpace {
             uidMapToParent: IdMap::All(),
             gidMapFromParent: IdMap::All(),
             gidMapToParent: IdMap::All(),
+            setgroupsDenied: false,
+            id: NewNamespaceInode(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.lock().id;
+    }
+
     /*pub fn SetUIDMap(&mut self, task: &Task, entries: &Vec<IdMapEntry>) -> Result<()> {
         let creds = &task.creds;
 
@@ -199,6 +214,30 @@ impl UserNameSpace {
     pub fn GIDMap(&self) -> Vec<IdMapEntry> {
         return self.lock().GIDMap();
     }
+
+    pub fn SetgroupsAllowed(&self) -> bool {
+        return !self.lock().setgroupsDenied;
+    }
+
+    // SetSetgroups implements writing to /proc/[pid]/setgroups. As in Linux,
+    // setgroups(2) can only be denied before the gid mapping of the namespace
+    // is written, and can't be allowed again once it is denied.
+    pub fn SetSetgroups(&self, allow: bool) -> Result<()> {
+        let mut me = self.lock();
+        if allow {
+            if me.setgroupsDenied {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+            return Ok(());
+        }
+
+        if me.parent.is_none() || me.gidMapToParent.map.len() != 0 {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        me.setgroupsDenied = true;
+        return Ok(());
+    }
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -0,0 +1,399 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use super::super::common::*;
+use super::super::linux::seccomp::*;
+use super::super::linux_def::*;
+use super::*;
+
+// Program is a BPF program that has been validated for consistency.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    instructions: Vec<BPFInstruction>,
+}
+
+impl Program {
+    // Compile performs validation on a sequence of seccomp-bpf instructions
+    // before wrapping them in a Program.
+    pub fn Compile(insns: &[BPFInstruction]) -> Result<Self> {
+        if insns.len() == 0 || insns.len() > MAX_INSTRUCTIONS {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // The last instruction must be a return.
+        let last = insns[insns.len() - 1];
+        if last.OpCode & INSTRUCTION_CLASS_MASK != RET {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // Validate each instruction. Only the instructions seccomp filters may
+        // use are accepted, as in seccomp_check_filter() (kernel/seccomp.c):
+        // the input is always a struct seccomp_data, so there are no
+        // indirect, half-word or byte loads. Note that we skip a validation
+        // Linux does: Linux additionally verifies that every load from an M
+        // register is preceded, in every path, by a store to the same M
+        // register, in order to avoid having to clear M between programs
+        // (net/core/filter.c:check_load_and_stores). We always start with a
+        // zeroed M array.
+        for (pc, i) in insns.iter().enumerate() {
+            if i.OpCode & UNUSED_OPCODE_BITS != 0 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            match i.OpCode & INSTRUCTION_CLASS_MASK {
+                LD | LDX => {
+                    let ld = i.OpCode & INSTRUCTION_CLASS_MASK;
+                    match i.OpCode & !INSTRUCTION_CLASS_MASK {
+                        op if op == W | ABS && ld == LD => {
+                            // The fields of struct seccomp_data are all 32-bit
+                            // aligned.
+                            if i.K as usize >= size_of::<SeccompData>() || i.K & 3 != 0 {
+                                return Err(Error::SysError(SysErr::EINVAL));
+                            }
+                        }
+                        op if op == W | IMM || op == W | LEN => (),
+                        op if op == W | MEM => {
+                            if i.K as usize >= SCRATCH_MEM_REGISTERS {
+                                return Err(Error::SysError(SysErr::EINVAL));
+                            }
+                        }
+                        _ => return Err(Error::SysError(SysErr::EINVAL)),
+                    }
+                }
+                ST | STX => {
+                    if i.OpCode & STORE_UNUSED_BITS != 0 {
+                        return Err(Error::SysError(SysErr::EINVAL));
+                    }
+
+                    if i.K as usize >= SCRATCH_MEM_REGISTERS {
+                        return Err(Error::SysError(SysErr::EINVAL));
+                    }
+                }
+                ALU => match i.OpCode & ALU_OPERATOR_MASK {
+                    ADD | SUB | MUL | OR | AND | LSH | RSH | XOR => (),
+                    DIV => {
+                        if i.OpCode & SRC_OPERAND_MASK == K && i.K == 0 {
+                            return Err(Error::SysError(SysErr::EINVAL));
+                        }
+                    }
+                    NEG => {
+                        if i.OpCode & SRC_OPERAND_MASK != K {
+                            return Err(Error::SysError(SysErr::EINVAL));
+                        }
+                    }
+                    _ => return Err(Error::SysError(SysErr::EINVAL)),
+                },
+                JMP => match i.OpCode & JMP_OPERATOR_MASK {
+                    JA => {
+                        if i.OpCode & SRC_OPERAND_MASK != K {
+                            return Err(Error::SysError(SysErr::EINVAL));
+                        }
+
+                        if pc as u64 + i.K as u64 + 1 >= insns.len() as u64 {
+                            return Err(Error::SysError(SysErr::EINVAL));
+                        }
+                    }
+                    JEQ | JGT | JGE | JSET => {
+                        if pc + i.JumpIfTrue as usize + 1 >= insns.len()
+                            || pc + i.JumpIfFalse as usize + 1 >= insns.len()
+                        {
+                            return Err(Error::SysError(SysErr::EINVAL));
+                        }
+                    }
+                    _ => return Err(Error::SysError(SysErr::EINVAL)),
+                },
+                RET => {
+                    let src = i.OpCode & RET_SOURCE_MASK;
+                    if i.OpCode & RET_UNUSED_BITS != 0 || (src != RET_A && src != RET_K) {
+                        return Err(Error::SysError(SysErr::EINVAL));
+                    }
+                }
+                MISC => {
+                    let op = i.OpCode & MISC_OPERATOR_MASK;
+                    if op != TAX && op != TXA {
+                        return Err(Error::SysError(SysErr::EINVAL));
+                    }
+                }
+                _ => return Err(Error::SysError(SysErr::EINVAL)),
+            }
+        }
+
+        return Ok(Self {
+            instructions: insns.to_vec(),
+        });
+    }
+
+    // Length returns the number of instructions in the program.
+    pub fn Length(&self) -> usize {
+        return self.instructions.len();
+    }
+
+    // Exec executes the program on the given input and returns the value
+    // from the terminating RET instruction. Loads from input use the
+    // native (little-endian) byte order, which is what seccomp filters
+    // expect for struct seccomp_data.
+    pub fn Exec(&self, input: &[u8]) -> Result<u32> {
+        let mut m = Machine::default();
+        let mut pc: usize = 0;
+        let insns = &self.instructions;
+
+        loop {
+            // Compile guarantees that every path ends in RET, so pc can't run
+            // off the end of the program.
+            let i = insns[pc];
+            match i.OpCode {
+                // LD
+                op if op == LD | IMM | W => m.A = i.K,
+                op if op == LD | ABS | W => m.A = Load(input, i.K as u64)?,
+                op if op == LD | MEM | W => m.A = m.M[i.K as usize],
+                op if op == LD | LEN | W => m.A = input.len() as u32,
+
+                // LDX
+                op if op == LDX | IMM | W => m.X = i.K,
+                op if op == LDX | MEM | W => m.X = m.M[i.K as usize],
+                op if op == LDX | LEN | W => m.X = input.len() as u32,
+
+                // ST, STX
+                op if op == ST => m.M[i.K as usize] = m.A,
+                op if op == STX => m.M[i.K as usize] = m.X,
+
+                // ALU
+                op if op & INSTRUCTION_CLASS_MASK == ALU => {
+                    let src = if op & SRC_OPERAND_MASK == X { m.X } else { i.K };
+                    m.A = match op & ALU_OPERATOR_MASK {
+                        ADD => m.A.wrapping_add(src),
+                        SUB => m.A.wrapping_sub(src),
+                        MUL => m.A.wrapping_mul(src),
+                        DIV => {
+                            // "If the divisor is zero, the filter returns 0"
+                            // - net/core/filter.c
+                            if src == 0 {
+                                return Ok(0);
+                            }
+                            m.A / src
+                        }
+                        OR => m.A | src,
+                        AND => m.A & src,
+                        LSH => m.A.checked_shl(src).unwrap_or(0),
+                        RSH => m.A.checked_shr(src).unwrap_or(0),
+                        NEG => (m.A as i32).wrapping_neg() as u32,
+                        XOR => m.A ^ src,
+                        _ => return Err(Error::Common(String::from("bpf: invalid ALU instruction"))),
+                    };
+                }
+
+                // JMP
+                op if op == JMP | JA | K => {
+                    pc += i.K as usize;
+                }
+                op if op & INSTRUCTION_CLASS_MASK == JMP => {
+                    let src = if op & SRC_OPERAND_MASK == X { m.X } else { i.K };
+                    let cond = match op & JMP_OPERATOR_MASK {
+                        JEQ => m.A == src,
+                        JGT => m.A > src,
+                        JGE => m.A >= src,
+                        JSET => m.A & src != 0,
+                        _ => return Err(Error::Common(String::from("bpf: invalid JMP instruction"))),
+                    };
+
+                    if cond {
+                        pc += i.JumpIfTrue as usize;
+                    } else {
+                        pc += i.JumpIfFalse as usize;
+                    }
+                }
+
+                // RET
+                op if op == RET | RET_K => return Ok(i.K),
+                op if op == RET | RET_A => return Ok(m.A),
+
+                // MISC
+                op if op == MISC | TAX => m.X = m.A,
+                op if op == MISC | TXA => m.A = m.X,
+
+                _ => return Err(Error::Common(String::from("bpf: invalid instruction"))),
+            }
+
+            pc += 1;
+        }
+    }
+}
+
+// UNUSED_OPCODE_BITS are bits that are never set in a valid opcode.
+const UNUSED_OPCODE_BITS: u16 = 0xff00;
+
+// STORE_UNUSED_BITS are the bits that must be clear in ST and STX.
+const STORE_UNUSED_BITS: u16 = 0xf8;
+
+// RET_UNUSED_BITS are the bits that must be clear in RET, besides the return
+// value source.
+const RET_UNUSED_BITS: u16 = 0xe0;
+
+// Machine represents the state of a BPF virtual machine.
+#[derive(Debug, Default)]
+struct Machine {
+    A: u32,
+    X: u32,
+    M: [u32; SCRATCH_MEM_REGISTERS],
+}
+
+// Load reads the 32-bit word at offset off from input. Out-of-bounds loads
+// cause the program to fail.
+fn Load(input: &[u8], off: u64) -> Result<u32> {
+    if off + 4 > input.len() as u64 {
+        return Err(Error::Common(String::from("bpf: load out of bounds")));
+    }
+
+    let off = off as usize;
+    let val = u32::from_le_bytes([input[off], input[off + 1], input[off + 2], input[off + 3]]);
+    return Ok(val);
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARG0: u32 = 16;
+
+    fn input(nr: i32, arg0: u64) -> SeccompData {
+        let mut data = SeccompData {
+            Nr: nr,
+            Arch: AUDIT_ARCH_X86_64,
+            ..Default::default()
+        };
+        data.Args[0] = arg0;
+        return data;
+    }
+
+    fn exec(insns: &[BPFInstruction], data: &SeccompData) -> u32 {
+        let p = Program::Compile(insns).unwrap();
+        return p.Exec(data.AsBytes()).unwrap();
+    }
+
+    #[test]
+    fn test_CompileRejectsInvalidPrograms() {
+        let invalid: &[&[BPFInstruction]] = &[
+            // empty program
+            &[],
+            // doesn't end with a return
+            &[Stmt(LD | W | ABS, SECCOMP_DATA_NR)],
+            // loads seccomp filters can't use
+            &[Stmt(LD | H | ABS, SECCOMP_DATA_NR), Stmt(RET | K, 0)],
+            &[Stmt(LD | B | ABS, SECCOMP_DATA_NR), Stmt(RET | K, 0)],
+            &[Stmt(LD | W | IND, SECCOMP_DATA_NR), Stmt(RET | K, 0)],
+            &[Stmt(LDX | B | MSH, SECCOMP_DATA_NR), Stmt(RET | K, 0)],
+            &[Stmt(LDX | W | ABS, SECCOMP_DATA_NR), Stmt(RET | K, 0)],
+            // unaligned and out of bounds loads
+            &[Stmt(LD | W | ABS, 2), Stmt(RET | K, 0)],
+            &[Stmt(LD | W | ABS, 64), Stmt(RET | K, 0)],
+            // out of bounds scratch memory
+            &[Stmt(LD | W | MEM, 16), Stmt(RET | K, 0)],
+            &[Stmt(ST, 16), Stmt(RET | K, 0)],
+            // MOD isn't allowed, nor a division by a zero constant
+            &[Stmt(ALU | MOD | K, 2), Stmt(RET | K, 0)],
+            &[Stmt(ALU | DIV | K, 0), Stmt(RET | K, 0)],
+            // jumps past the end of the program
+            &[Stmt(JMP | JA, 1), Stmt(RET | K, 0)],
+            &[Jump(JMP | JEQ | K, 0, 1, 0), Stmt(RET | K, 0)],
+            // unused opcode bits
+            &[Stmt(0x100 | RET | K, 0)],
+        ];
+
+        for insns in invalid {
+            assert!(Program::Compile(insns).is_err(), "{:?}", insns);
+        }
+    }
+
+    #[test]
+    fn test_ExecSyscallFilter() {
+        // Allow getpid, fail write(2, ...) with EPERM, kill everything else.
+        let insns = [
+            Stmt(LD | W | ABS, SECCOMP_DATA_NR),
+            Jump(JMP | JEQ | K, 39, 4, 0),
+            Jump(JMP | JEQ | K, 1, 0, 2),
+            Stmt(LD | W | ABS, SECCOMP_DATA_ARG0),
+            Jump(JMP | JEQ | K, 2, 2, 0),
+            Stmt(RET | K, SECCOMP_RET_KILL_THREAD),
+            Stmt(RET | K, SECCOMP_RET_ALLOW),
+            Stmt(RET | K, SECCOMP_RET_ERRNO | SysErr::EPERM as u32),
+        ];
+
+        assert_eq!(exec(&insns, &input(39, 0)), SECCOMP_RET_ALLOW);
+        assert_eq!(
+            exec(&insns, &input(1, 2)),
+            SECCOMP_RET_ERRNO | SysErr::EPERM as u32
+        );
+        assert_eq!(exec(&insns, &input(1, 1)), SECCOMP_RET_KILL_THREAD);
+        assert_eq!(exec(&insns, &input(0, 0)), SECCOMP_RET_KILL_THREAD);
+    }
+
+    #[test]
+    fn test_ExecJumpOffsets() {
+        let insns = [
+            Stmt(LD | W | ABS, SECCOMP_DATA_NR),
+            Jump(JMP | JEQ | K, 39, 0, 1),
+            Stmt(RET | K, SECCOMP_RET_ALLOW),
+            Jump(JMP | JGE | K, 100, 0, 1),
+            Stmt(RET | K, SECCOMP_RET_TRAP),
+            Stmt(RET | K, SECCOMP_RET_KILL_THREAD),
+        ];
+
+        assert_eq!(exec(&insns, &input(39, 0)), SECCOMP_RET_ALLOW);
+        assert_eq!(exec(&insns, &input(100, 0)), SECCOMP_RET_TRAP);
+        assert_eq!(exec(&insns, &input(1, 0)), SECCOMP_RET_KILL_THREAD);
+    }
+
+    #[test]
+    fn test_ExecAluAndScratchMemory() {
+        let insns = [
+            Stmt(LD | W | ABS, SECCOMP_DATA_ARG0),
+            Stmt(ST, 3),
+            Stmt(ALU | MUL | K, 3),
+            Stmt(ALU | ADD | K, 1),
+            Stmt(MISC | TAX, 0),
+            Stmt(LD | W | MEM, 3),
+            Stmt(ALU | SUB | X, 0),
+            Stmt(ALU | NEG, 0),
+            Stmt(RET | A, 0),
+        ];
+
+        // -(5 - (5 * 3 + 1))
+        assert_eq!(exec(&insns, &input(0, 5)), 11);
+    }
+
+    #[test]
+    fn test_ExecDivisionByZero() {
+        // "If the divisor is zero, the filter returns 0"
+        let insns = [
+            Stmt(LD | W | IMM, 10),
+            Stmt(LDX | W | IMM, 0),
+            Stmt(ALU | DIV | X, 0),
+            Stmt(RET | K, SECCOMP_RET_ALLOW),
+        ];
+
+        assert_eq!(exec(&insns, &input(0, 0)), 0);
+    }
+
+    #[test]
+    fn test_ExecLen() {
+        let insns = [Stmt(LD | W | LEN, 0), Stmt(RET | A, 0)];
+        assert_eq!(exec(&insns, &input(0, 0)), 64);
+    }
+}
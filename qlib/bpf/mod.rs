@@ -0,0 +1,138 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod interpreter;
+
+// Parts of BPF instructions, from include/uapi/linux/filter.h and
+// include/uapi/linux/bpf_common.h.
+
+// Instruction class, stored in bits 0-2.
+pub const LD: u16 = 0x00; // load into A
+pub const LDX: u16 = 0x01; // load into X
+pub const ST: u16 = 0x02; // store from A
+pub const STX: u16 = 0x03; // store from X
+pub const ALU: u16 = 0x04; // arithmetic
+pub const JMP: u16 = 0x05; // jump
+pub const RET: u16 = 0x06; // return
+pub const MISC: u16 = 0x07;
+pub const INSTRUCTION_CLASS_MASK: u16 = 0x07;
+
+// Size of a load, stored in bits 3-4.
+pub const W: u16 = 0x00; // 32 bits
+pub const H: u16 = 0x08; // 16 bits
+pub const B: u16 = 0x10; // 8 bits
+pub const LOAD_SIZE_MASK: u16 = 0x18;
+
+// Source operand for a load, stored in bits 5-7.
+pub const IMM: u16 = 0x00; // immediate value K
+pub const ABS: u16 = 0x20; // data[K:K+size]
+pub const IND: u16 = 0x40; // data[X+K:X+K+size]
+pub const MEM: u16 = 0x60; // scratch memory[K]
+pub const LEN: u16 = 0x80; // length of data
+pub const MSH: u16 = 0xa0; // 4 * lower nibble of data[K]
+pub const LOAD_MODE_MASK: u16 = 0xe0;
+
+// Arithmetic and jump operators, stored in bits 4-7.
+pub const ADD: u16 = 0x00;
+pub const SUB: u16 = 0x10;
+pub const MUL: u16 = 0x20;
+pub const DIV: u16 = 0x30;
+pub const OR: u16 = 0x40;
+pub const AND: u16 = 0x50;
+pub const LSH: u16 = 0x60;
+pub const RSH: u16 = 0x70;
+pub const NEG: u16 = 0x80;
+pub const MOD: u16 = 0x90;
+pub const XOR: u16 = 0xa0;
+pub const ALU_OPERATOR_MASK: u16 = 0xf0;
+
+pub const JA: u16 = 0x00;
+pub const JEQ: u16 = 0x10;
+pub const JGT: u16 = 0x20;
+pub const JGE: u16 = 0x30;
+pub const JSET: u16 = 0x40;
+pub const JMP_OPERATOR_MASK: u16 = 0xf0;
+
+// Source operand for arithmetic and jump, stored in bit 3.
+pub const K: u16 = 0x00; // immediate value K
+pub const X: u16 = 0x08; // register X
+pub const SRC_OPERAND_MASK: u16 = 0x08;
+
+// Return value source for RET instructions, stored in bits 3-4.
+pub const RET_A: u16 = 0x10; // register A
+pub const RET_K: u16 = 0x00; // immediate value K
+pub const RET_SOURCE_MASK: u16 = 0x18;
+
+// Operators for MISC instructions, stored in bits 3-7.
+pub const TAX: u16 = 0x00; // A = X
+pub const TXA: u16 = 0x80; // X = A
+pub const MISC_OPERATOR_MASK: u16 = 0xf8;
+
+// MAX_INSTRUCTIONS is the maximum number of instructions in a BPF program,
+// and is equal to Linux's BPF_MAXINSNS.
+pub const MAX_INSTRUCTIONS: usize = 4096;
+
+// SCRATCH_MEM_REGISTERS is the number of M registers in a BPF virtual
+// machine, and is equal to Linux's BPF_MEMWORDS.
+pub const SCRATCH_MEM_REGISTERS: usize = 16;
+
+// BPFInstruction is a raw BPF virtual machine instruction, equivalent to
+// Linux's struct sock_filter.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct BPFInstruction {
+    // OpCode is the operation to execute.
+    pub OpCode: u16,
+
+    // JumpIfTrue is the number of instructions to skip if OpCode is a
+    // conditional instruction and the condition is true.
+    pub JumpIfTrue: u8,
+
+    // JumpIfFalse is the number of instructions to skip if OpCode is a
+    // conditional instruction and the condition is false.
+    pub JumpIfFalse: u8,
+
+    // K is a constant parameter. The meaning depends on the value of OpCode.
+    pub K: u32,
+}
+
+// SockFprog is the user-space struct sock_fprog that carries a BPF program
+// into the kernel, e.g. for seccomp(SECCOMP_SET_MODE_FILTER).
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SockFprog {
+    pub Len: u16,
+    pub pad: [u8; 6],
+    pub Filter: u64,
+}
+
+// Stmt returns a BPFInstruction representing a BPF non-jump instruction.
+pub fn Stmt(code: u16, k: u32) -> BPFInstruction {
+    return BPFInstruction {
+        OpCode: code,
+        JumpIfTrue: 0,
+        JumpIfFalse: 0,
+        K: k,
+    };
+}
+
+// Jump returns a BPFInstruction representing a BPF jump instruction.
+pub fn Jump(code: u16, k: u32, jt: u8, jf: u8) -> BPFInstruction {
+    return BPFInstruction {
+        OpCode: code,
+        JumpIfTrue: jt,
+        JumpIfFalse: jf,
+        K: k,
+    };
+}
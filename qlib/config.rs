@@ -42,6 +42,32 @@ pub struct Config {
     pub TlbShootdownWait: bool,
     #[serde(default)]
     pub Sandboxed: bool,
+    #[serde(default)]
+    pub TransparentHugepage: THPMode,
+    // HostFileLock mirrors the fcntl byte range locks of host backed files to
+    // the host, so that they are honored by processes outside of the sandbox.
+    #[serde(default)]
+    pub HostFileLock: bool,
+    // HostInotify forwards the host inotify events of host backed files to the
+    // guest inotify watches, so that changes made outside of the sandbox, e.g.
+    // on the host side of a bind mount, are observed by the guest.
+    #[serde(default)]
+    pub HostInotify: bool,
+    // HostSeccomp confines the qvisor process itself with a host seccomp
+    // filter, a reduced capability set and fresh user/mount namespaces.
+    #[serde(default)]
+    pub HostSeccomp: HostSeccompMode,
+    // HostSeccompIDBase is the first of the 65536 host uids and gids the user
+    // ns of a sandbox confined by HostSeccomp maps its ids to, so that root in
+    // the sandbox is not root on the host. As with the user ns remapping of
+    // dockerd, the rootfs and volumes of the containers need to be owned by
+    // the mapped ids.
+    #[serde(default = "DefaultHostSeccompIDBase")]
+    pub HostSeccompIDBase: u32,
+}
+
+fn DefaultHostSeccompIDBase() -> u32 {
+    return 100000;
 }
 
 impl Config {
@@ -85,7 +111,12 @@ impl Default for Config {
             DisableCgroup: true,
             CopyDataWithPf: false,
             TlbShootdownWait: false,
-            Sandboxed: false
+            Sandboxed: false,
+            TransparentHugepage: THPMode::Madvise,
+            HostFileLock: false,
+            HostInotify: false,
+            HostSeccomp: HostSeccompMode::Disable,
+            HostSeccompIDBase: DefaultHostSeccompIDBase(),
         };
     }
 }
@@ -106,6 +137,44 @@ impl Default for DebugLevel {
     }
 }
 
+// THPMode is the transparent huge page policy for anonymous memory, as
+// /sys/kernel/mm/transparent_hugepage/enabled in Linux.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum THPMode {
+    // Always backs aligned anonymous regions with 2MB pages unless the vma is
+    // advised with MADV_NOHUGEPAGE.
+    Always,
+    // Madvise only backs the vmas advised with MADV_HUGEPAGE.
+    Madvise,
+    // Never disables 2MB pages.
+    Never,
+}
+
+impl Default for THPMode {
+    fn default() -> Self {
+        return Self::Madvise;
+    }
+}
+
+// HostSeccompMode is the confinement policy of the qvisor host process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HostSeccompMode {
+    // Disable runs qvisor without the host seccomp filter.
+    Disable,
+    // Log installs the filter but only logs the syscalls it doesn't allow,
+    // which is used to debug the allow list.
+    Log,
+    // Enforce kills the qvisor process on the first syscall which is not
+    // allowed by the filter.
+    Enforce,
+}
+
+impl Default for HostSeccompMode {
+    fn default() -> Self {
+        return Self::Disable;
+    }
+}
+
 pub const ENABLE_BUFF_IO: bool = false;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
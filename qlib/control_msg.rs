@@ -103,6 +103,27 @@ pub struct StartArgs {
     pub process: Process,
 }
 
+// UpdateArgs is payload for UpdateContainer control msg, it carries the new
+// resource limits of a running container.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateArgs {
+    pub cid: String,
+    pub memoryLimit: MemoryLimit,
+    pub cpuLimit: CPULimit,
+    pub pidsLimit: u64,
+}
+
+// CheckpointArgs is payload for Checkpoint control msg, it asks the sandbox to
+// write its image to imagePath.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CheckpointArgs {
+    pub cid: String,
+    pub imagePath: String,
+    // leaveRunning resumes the sandbox once the image is written, otherwise
+    // the sandbox stays paused until it is destroyed.
+    pub leaveRunning: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum Payload {
     RootContainerStart(RootProcessStart),
@@ -112,10 +133,13 @@ pub enum Payload {
     WaitContainer(Cid),
     WaitPid(WaitPid),
     Ps(Cid),
+    ContainerStats(Cid),
     Signal(SignalArgs),
     ContainerDestroy(Cid),
     CreateSubContainer(CreateArgs),
     StartSubContainer(StartArgs),
+    UpdateContainer(UpdateArgs),
+    Checkpoint(CheckpointArgs),
     WaitAll,
 }
 
@@ -143,13 +167,17 @@ pub enum UCallResp {
     PauseResp,
     UnpauseResp,
     PsResp(Vec<ProcessInfo>),
+    ContainerStatsResp(ContainerStats),
     WaitContainerResp(u32),
     WaitPidResp(u32),
     SignalResp,
     ContainerDestroyResp,
     CreateSubContainerResp,
     StartSubContainerResp,
+    UpdateContainerResp,
+    CheckpointResp,
     WaitAllResp(WaitAllResp),
+    OOMEventResp(OOMEventResp),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -168,9 +196,58 @@ pub struct ProcessInfo {
     pub Cmd: String,
 }
 
+// ContainerStats is the resource usage of all the processes of a container,
+// it is reported through the containerd shim Stats call.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ContainerStats {
+    // UserTime is the CPU time spent executing application code in
+    // nanoseconds.
+    pub UserTime: u64,
+
+    // SysTime is the CPU time spent in the sentry on behalf of the
+    // application in nanoseconds.
+    pub SysTime: u64,
+
+    // MemoryUsage is the resident memory of the container in bytes,
+    // MemoryRSS + MemoryCache.
+    pub MemoryUsage: u64,
+
+    // MemoryRSS is the resident anonymous memory of the container in bytes.
+    pub MemoryRSS: u64,
+
+    // MemoryCache is the resident file backed memory of the container in
+    // bytes.
+    pub MemoryCache: u64,
+
+    // MemoryLimit is the memory limit of the container in bytes, 0 means
+    // unlimited.
+    pub MemoryLimit: u64,
+
+    // OOMKills is the number of processes killed by the OOM killer.
+    pub OOMKills: u64,
+
+    // Pids is the number of live tasks of the container.
+    pub Pids: u64,
+
+    // BytesRead and BytesWritten are the storage IO byte counters.
+    pub BytesRead: u64,
+    pub BytesWritten: u64,
+
+    // ReadSyscalls and WriteSyscalls are the number of IO operations.
+    pub ReadSyscalls: u64,
+    pub WriteSyscalls: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WaitAllResp {
     pub cid: String,
     pub execId: String,
     pub status: i32,
 }
+
+// OOMEventResp is sent on the WaitAll stream when the OOM killer of a
+// container kills one of its processes.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OOMEventResp {
+    pub cid: String,
+}
@@ -31,6 +31,8 @@ pub static PROC_DEVICE: Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<
 pub static SHM_DEVICE: Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<Device>>>::New();
 pub static SYS_DEVICE: Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<Device>>>::New();
 pub static TMPFS_DEVICE: Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<Device>>>::New();
+pub static MQUEUE_DEVICE: Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<Device>>>::New();
+pub static CGROUP_DEVICE: Singleton<Arc<QMutex<Device>>> = Singleton::<Arc<QMutex<Device>>>::New();
 
 pub unsafe fn InitSingleton() {
     SIMPLE_DEVICES.Init(QMutex::new(Registry::New()));
@@ -42,6 +44,8 @@ pub unsafe fn InitSingleton() {
     SHM_DEVICE.Init(NewAnonDevice());
     SYS_DEVICE.Init(NewAnonDevice());
     TMPFS_DEVICE.Init(NewAnonDevice());
+    MQUEUE_DEVICE.Init(NewAnonDevice());
+    CGROUP_DEVICE.Init(NewAnonDevice());
 }
 
 // TTYAUX_MAJOR is the major device number for alternate TTY devices.
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::fmt;
 use core::ops::Deref;
 use core::sync::atomic::AtomicI32;
@@ -142,6 +143,17 @@ impl FdTbl {
         return self.map[fd].is_some();
     }
 
+    pub fn Fds(&self) -> Vec<i32> {
+        let mut fds = Vec::new();
+        for i in 0..self.map.len() {
+            if self.map[i].is_some() {
+                fds.push(i as i32);
+            }
+        }
+
+        return fds;
+    }
+
     pub fn Insert(&mut self, fd: i32, fdInfo: FdInfo) {
         let fd = fd as usize;
         if fd >= self.map.len() {
@@ -367,6 +367,34 @@ impl HostSpace {
         return HostSpace::HCall(&mut msg, false) as i64;
     }
 
+    pub fn Checkpoint(imagePath: u64) -> i64 {
+        let mut msg = Msg::Checkpoint(Checkpoint { imagePath });
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
+    pub fn HostInotifyInit() -> i64 {
+        let mut msg = Msg::HostInotifyInit(HostInotifyInit {});
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
+    pub fn HostInotifyAddWatch(fd: i32, targetFd: i32, mask: u32) -> i64 {
+        let mut msg = Msg::HostInotifyAddWatch(HostInotifyAddWatch {
+            fd,
+            targetFd,
+            mask,
+        });
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
+    pub fn HostInotifyRmWatch(fd: i32, wd: i32) -> i64 {
+        let mut msg = Msg::HostInotifyRmWatch(HostInotifyRmWatch { fd, wd });
+
+        return HostSpace::HCall(&mut msg, false) as i64;
+    }
+
     pub fn SwapIn() -> i64 {
         let mut msg = Msg::SwapIn(SwapIn {});
 
@@ -87,6 +87,96 @@ impl PtRegs {
     }
 }
 
+// PtraceRegs is the register layout exposed to ptrace(PTRACE_GETREGS) and
+// friends, i.e. struct user_regs_struct.
+//copy from https://elixir.bootlin.com/linux/latest/source/arch/x86/include/asm/user_64.h#L69
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PtraceRegs {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+impl PtraceRegs {
+    pub fn New(pt: &PtRegs, fsBase: u64) -> Self {
+        return Self {
+            r15: pt.r15,
+            r14: pt.r14,
+            r13: pt.r13,
+            r12: pt.r12,
+            rbp: pt.rbp,
+            rbx: pt.rbx,
+            r11: pt.r11,
+            r10: pt.r10,
+            r9: pt.r9,
+            r8: pt.r8,
+            rax: pt.rax,
+            rcx: pt.rcx,
+            rdx: pt.rdx,
+            rsi: pt.rsi,
+            rdi: pt.rdi,
+            orig_rax: pt.orig_rax,
+            rip: pt.rip,
+            cs: pt.cs,
+            eflags: pt.eflags,
+            rsp: pt.rsp,
+            ss: pt.ss,
+            fs_base: fsBase,
+            ..Default::default()
+        };
+    }
+
+    // Apply copies the general purpose registers back to pt. Segment
+    // selectors are not changeable, and only the bits of eflags in
+    // mutableFlags are taken from the tracer.
+    pub fn Apply(&self, pt: &mut PtRegs, mutableFlags: u64) {
+        pt.r15 = self.r15;
+        pt.r14 = self.r14;
+        pt.r13 = self.r13;
+        pt.r12 = self.r12;
+        pt.rbp = self.rbp;
+        pt.rbx = self.rbx;
+        pt.r11 = self.r11;
+        pt.r10 = self.r10;
+        pt.r9 = self.r9;
+        pt.r8 = self.r8;
+        pt.rax = self.rax;
+        pt.rcx = self.rcx;
+        pt.rdx = self.rdx;
+        pt.rsi = self.rsi;
+        pt.rdi = self.rdi;
+        pt.orig_rax = self.orig_rax;
+        pt.rip = self.rip;
+        pt.eflags = (pt.eflags & !mutableFlags) | (self.eflags & mutableFlags);
+        pt.rsp = self.rsp;
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default)]
 pub struct SigRetInfo {
@@ -152,6 +242,15 @@ pub struct SigPoll {
     pub fd: i32,
 }
 
+/* SIGSYS */
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SigSys {
+    pub callAddr: u64,
+    pub syscall: i32,
+    pub arch: u32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct SignalInfo {
@@ -240,6 +339,11 @@ impl SignalInfo {
         return unsafe { &mut *(addr as *mut SigPoll) };
     }
 
+    pub fn SigSys(&self) -> &mut SigSys {
+        let addr = &self.fields[0] as *const _ as u64;
+        return unsafe { &mut *(addr as *mut SigSys) };
+    }
+
     // SignalInfoUser (properly SI_USER) indicates that a signal was sent from
     // a kill() or raise() syscall.
     pub const SIGNAL_INFO_USER: i32 = 0;
@@ -28,7 +28,9 @@ use super::super::super::common::*;
 use super::super::super::control_msg::*;
 use super::super::super::vcpu_mgr::*;
 use super::super::task::*;
+use super::super::util::cstring::CString;
 use super::super::taskMgr;
+use super::super::GlobalIOMgr;
 use super::super::Kernel;
 use super::super::SetWaitContainerfd;
 use super::super::WaitContainerfd;
@@ -46,6 +48,10 @@ pub fn ControllerProcessHandler() -> Result<()> {
     }
 }
 
+fn RestoredControllerProcess(_para: *const u8) {
+    ControllerProcessHandler().expect("ControllerProcess crash");
+}
+
 pub fn HandleSignal(signalArgs: &SignalArgs) {
     info!("HandleSignal: get signal {:?}", &signalArgs);
 
@@ -174,6 +180,27 @@ pub fn ControlMsgHandler(fd: *const u8) {
             let ps = Processes(&kernel, &cid);
             WriteControlMsgResp(fd, &UCallResp::PsResp(ps), true);
         }
+        Payload::ContainerStats(cid) => {
+            let kernel = LOADER.Lock(task).unwrap().kernel.clone();
+            let stats = GetContainerStats(&kernel, &cid);
+            WriteControlMsgResp(fd, &UCallResp::ContainerStatsResp(stats), true);
+        }
+        Payload::UpdateContainer(args) => {
+            let kernel = LOADER.Lock(task).unwrap().kernel.clone();
+            kernel.UpdateContainer(&args);
+            WriteControlMsgResp(fd, &UCallResp::UpdateContainerResp, true);
+        }
+        Payload::Checkpoint(args) => match CheckpointSandbox(&args) {
+            // the sandbox has been restored from the image, the connection the
+            // request came from belongs to the checkpointed qvisor process.
+            Ok(true) => (),
+            Ok(false) => {
+                WriteControlMsgResp(fd, &UCallResp::CheckpointResp, true);
+            }
+            Err(e) => {
+                WriteControlMsgResp(fd, &UCallResp::UCallRespErr(format!("{:?}", e)), true);
+            }
+        },
         Payload::Signal(signalArgs) => {
             HandleSignal(&signalArgs);
             WriteControlMsgResp(fd, &UCallResp::SignalResp, true);
@@ -238,8 +265,51 @@ pub fn ControlMsgHandler(fd: *const u8) {
     super::super::taskMgr::SwitchToNewTask();
 }
 
+// CheckpointSandbox pauses all the tasks and asks qvisor to write the sandbox
+// image to args.imagePath. The vcpu returns from the hypercall twice: once in
+// the checkpointed sandbox after the image is written and once in the sandbox
+// restored from the image. It returns true in the latter case.
+pub fn CheckpointSandbox(args: &CheckpointArgs) -> Result<bool> {
+    if SHARESPACE.hibernatePause.load(atomic::Ordering::Relaxed) {
+        return Err(Error::SysError(SysErr::EBUSY));
+    }
+
+    let kernel = GetKernel();
+    kernel.Pause();
+    kernel.ClearFsCache();
+
+    let imagePath = CString::New(&args.imagePath);
+    let ret = HostSpace::Checkpoint(imagePath.Ptr());
+
+    if SHARESPACE.restored.swap(false, atomic::Ordering::SeqCst) {
+        // the io_uring requests in flight, including the host epoll poll and
+        // the accept request of the controller, and the WaitAll connection are
+        // gone with the checkpointed qvisor process.
+        GlobalIOMgr().InitPollHostEpoll(SHARESPACE.HostHostEpollfd());
+        SetWaitContainerfd(-1);
+        taskMgr::CreateTask(RestoredControllerProcess as u64, ptr::null(), false);
+        kernel.Unpause();
+        return Ok(true);
+    }
+
+    if ret < 0 {
+        kernel.Unpause();
+        return Err(Error::SysError(-ret as i32));
+    }
+
+    if args.leaveRunning {
+        kernel.Unpause();
+    }
+
+    return Ok(false);
+}
+
 pub fn WriteWaitAllResponse(cid: String, execId: String, status: i32) {
     let fd = WaitContainerfd();
+    if fd < 0 {
+        return;
+    }
+
     WriteControlMsgResp(
         fd,
         &UCallResp::WaitAllResp(WaitAllResp {
@@ -251,6 +321,15 @@ pub fn WriteWaitAllResponse(cid: String, execId: String, status: i32) {
     );
 }
 
+pub fn WriteOOMEventResponse(cid: String) {
+    let fd = WaitContainerfd();
+    if fd < 0 {
+        return;
+    }
+
+    WriteControlMsgResp(fd, &UCallResp::OOMEventResp(OOMEventResp { cid }), false);
+}
+
 pub fn WriteControlMsgResp(fd: i32, msg: &UCallResp, close: bool) {
     let data: Vec<u8> = serde_json::to_vec(&msg).expect("LoadProcessKernel ser fail...");
     let addr = &data[0] as *const _ as u64;
@@ -21,7 +21,8 @@ use alloc::vec::Vec;
 
 use super::super::super::auth::*;
 use super::super::super::common::*;
-use super::super::super::linux_def::{FileMode, FilePermissions, SysErr};
+use super::super::super::linux_def::{FileMode, FilePermissions, LibcConst, LibcStat, SysErr};
+use super::super::super::loader;
 use super::super::super::path::*;
 use super::super::fs::dirent::*;
 use super::super::fs::filesystems::*;
@@ -46,6 +47,14 @@ const PROCFS: &str = "proc";
 const SYSFS: &str = "sysfs";
 const TMPFS: &str = "tmpfs";
 const NONEFS: &str = "none";
+const MQUEUE: &str = "mqueue";
+const CGROUP: &str = "cgroup";
+const CGROUP2: &str = "cgroup2";
+
+// Mounts that the host has already set up in the container rootfs. The guest
+// mounts the host directory again with the flags from the spec.
+const BIND: &str = "bind";
+const OVERLAY: &str = "overlay";
 
 fn CreateRootMount(
     task: &Task,
@@ -103,8 +112,12 @@ fn GetMountNameAndOptions(_conf: &config::Config, m: &oci::Mount) -> Result<(Str
     let fsName;
     let mut opts = Vec::new();
 
+    if IsBindMount(m) {
+        return Ok((BIND.to_string(), opts));
+    }
+
     match m.typ.as_str() {
-        DEVPTS | DEVTMPFS | PROCFS | SYSFS => {
+        DEVPTS | DEVTMPFS | PROCFS | SYSFS | MQUEUE | CGROUP | CGROUP2 => {
             fsName = m.typ.to_string();
         }
         NONEFS => {
@@ -112,7 +125,13 @@ fn GetMountNameAndOptions(_conf: &config::Config, m: &oci::Mount) -> Result<(Str
         }
         TMPFS => {
             fsName = m.typ.to_string();
-            opts = ParseAndFilterOptions(&m.options, &vec!["mode", "uid", "gid"])?;
+            opts = ParseAndFilterOptions(
+                &ParseMountOptions(&m.options).data,
+                &vec!["mode", "uid", "gid", "size", "nr_blocks", "nr_inodes"],
+            )?;
+        }
+        OVERLAY => {
+            fsName = m.typ.to_string();
         }
         _ => {
             info!("ignoring unknown filesystem type {}", m.typ);
@@ -126,6 +145,54 @@ fn GetMountNameAndOptions(_conf: &config::Config, m: &oci::Mount) -> Result<(Str
     return Ok((fsName, opts));
 }
 
+// IsBindMount returns whether m is a bind mount, either by type or by the
+// "bind"/"rbind" options.
+fn IsBindMount(m: &oci::Mount) -> bool {
+    if m.typ.as_str() == BIND {
+        return true;
+    }
+
+    return ParseMountOptions(&m.options).flags & LibcConst::MS_BIND != 0;
+}
+
+// IsHostMount returns whether m is backed by a mount the host made in the
+// container rootfs.
+fn IsHostMount(m: &oci::Mount) -> bool {
+    return IsBindMount(m) || m.typ.as_str() == OVERLAY;
+}
+
+// IsHostFileMount returns whether m is a host mount of a non-directory, e.g.
+// the bind mount of /etc/hosts.
+fn IsHostFileMount(conf: &config::Config, m: &oci::Mount) -> bool {
+    if !IsHostMount(m) {
+        return false;
+    }
+
+    let mut fstat = LibcStat::default();
+    let path = Join(&conf.RootDir, &m.destination);
+    if Fstatat(-100, &path, &mut fstat, 0) < 0 {
+        return false;
+    }
+
+    return !fstat.IsDir();
+}
+
+// InitSpec returns the spec of a container holding the OCI mounts of its
+// process.
+pub fn InitSpec(mounts: &Vec<loader::Mount>) -> oci::Spec {
+    let mut spec = InitTestSpec();
+    for m in mounts {
+        spec.mounts.push(oci::Mount {
+            destination: m.Destination.to_string(),
+            typ: m.Type.to_string(),
+            source: m.Source.to_string(),
+            options: m.Options.clone(),
+        });
+    }
+
+    return spec;
+}
+
 pub fn InitTestSpec() -> oci::Spec {
     return oci::Spec {
         version: "".to_string(),
@@ -145,7 +212,7 @@ pub fn InitTestSpec() -> oci::Spec {
     };
 }
 
-pub fn InitRootFs(task: &mut Task, root: &str) -> Result<MountNs> {
+pub fn InitRootFs(task: &mut Task, root: &str, mounts: &Vec<loader::Mount>) -> Result<MountNs> {
     let config = config::Config {
         RootDir: root.to_string(),
         Debug: true,
@@ -153,7 +220,7 @@ pub fn InitRootFs(task: &mut Task, root: &str) -> Result<MountNs> {
 
     debug!("init rootfs under {} for container", root);
 
-    return SetupContainerFS(task, &InitTestSpec(), &config);
+    return SetupContainerFS(task, &InitSpec(mounts), &config);
 }
 
 // This function will be used by both root container and subcontainer
@@ -166,18 +233,26 @@ pub fn SetupContainerFS(
 
     //error!("SetupRootContainerFS 1.0 mounts[0].destination is {:?}", &mounts[0].destination);
 
-    let rootInode = CreateRootMount(task, spec, conf, &mounts)?;
+    // Host mounts of files are visible in the host root already, they don't
+    // need a directory in the submount overlays.
+    let dirMounts: Vec<oci::Mount> = mounts
+        .iter()
+        .filter(|m| !IsHostFileMount(conf, m))
+        .cloned()
+        .collect();
+
+    let rootInode = CreateRootMount(task, spec, conf, &dirMounts)?;
     let mns = MountNs::New(task, &rootInode);
 
     let root = mns.Root();
 
-    MountSubmounts(task, conf, &mns, &root, &mounts)?;
+    MountSubmounts(task, conf, &mns, &root, &mounts, &dirMounts)?;
     return Ok(mns);
 }
 
 fn CompileMounts(spec: &oci::Spec) -> Vec<oci::Mount> {
-    let mut _procMounted = false;
-    let mut _sysMounted = false;
+    let mut procMounted = false;
+    let mut sysMounted = false;
     let mut mounts = Vec::new();
 
     mounts.push(oci::Mount {
@@ -195,20 +270,6 @@ fn CompileMounts(spec: &oci::Spec) -> Vec<oci::Mount> {
         options: Vec::new(),
     });
 
-    mounts.push(oci::Mount {
-        destination: "/proc".to_string(),
-        typ: PROCFS.to_string(),
-        source: "".to_string(),
-        options: Vec::new(),
-    });
-
-    mounts.push(oci::Mount {
-        destination: "/sys".to_string(),
-        typ: SYSFS.to_string(),
-        source: "".to_string(),
-        options: Vec::new(),
-    });
-
     /*mounts.push(oci::Mount {
         destination: "/tmp".to_string(),
         typ: TMPFS.to_string(),
@@ -224,14 +285,14 @@ fn CompileMounts(spec: &oci::Spec) -> Vec<oci::Mount> {
 
         mounts.push(m.clone());
         match Clean(&m.destination).as_str() {
-            "/proc" => _procMounted = true,
-            "/sys" => _sysMounted = true,
+            "/proc" => procMounted = true,
+            "/sys" => sysMounted = true,
             _ => (),
         }
     }
 
     let mut mandatoryMounts = Vec::new();
-    /*if !procMounted {
+    if !procMounted {
         mandatoryMounts.push(oci::Mount {
             destination: "/proc".to_string(),
             typ: PROCFS.to_string(),
@@ -247,7 +308,7 @@ fn CompileMounts(spec: &oci::Spec) -> Vec<oci::Mount> {
             source: "".to_string(),
             options: Vec::new(),
         })
-    }*/
+    }
 
     mandatoryMounts.append(&mut mounts);
 
@@ -260,10 +321,11 @@ fn MountSubmounts(
     mns: &MountNs,
     root: &Dirent,
     mounts: &Vec<oci::Mount>,
+    dirMounts: &Vec<oci::Mount>,
 ) -> Result<()> {
     for m in mounts {
         debug!("mounting submounts {:?}", m);
-        MountSubmount(task, config, mns, root, m, mounts)?;
+        MountSubmount(task, config, mns, root, m, dirMounts)?;
     }
 
     //todo: mount tmp
@@ -320,18 +382,30 @@ fn MountSubmount(
         return Ok(());
     }
 
-    let filesystem = MustFindFilesystem(&fsName);
-    let mf = mountFlags(&m.options);
+    let options = ParseMountOptions(&m.options);
+    let mf = mountFlags(options.flags);
+
+    let mut inode = match fsName.as_str() {
+        BIND | OVERLAY => MountHostPath(task, config, m, &fsName, &mf)?,
+        _ => {
+            let filesystem = MustFindFilesystem(&fsName);
+            let inode = filesystem
+                .lock()
+                .Mount(task, &"none".to_string(), &mf, &opts.join(","))?;
+            inode
+        }
+    };
 
-    let mut inode = filesystem
-        .lock()
-        .Mount(task, &"none".to_string(), &mf, &opts.join(","))?;
-    let submounts = SubTargets(&m.destination, mounts);
-    if submounts.len() > 0 {
-        info!("adding submount overlay over {}", m.destination);
-        inode = AddSubmountOverlay(task, &inode, &submounts)?;
+    let isDir = inode.StableAttr().IsDir();
+    if isDir {
+        let submounts = SubTargets(&m.destination, mounts);
+        if submounts.len() > 0 {
+            info!("adding submount overlay over {}", m.destination);
+            inode = AddSubmountOverlay(task, &inode, &submounts)?;
+        }
+        MakeMountPoint(task, mns, root, &m.destination)?;
     }
-    MakeMountPoint(task, mns, root, &m.destination)?;
+
     let mut maxTraversals = 0;
     let dirent = mns.FindDirent(
         task,
@@ -343,24 +417,158 @@ fn MountSubmount(
     )?;
     mns.Mount(&dirent, &inode)?;
 
+    if options.propagation.len() > 0 {
+        let mut maxTraversals = 0;
+        let dirent = mns.FindDirent(
+            task,
+            root,
+            Some(root.clone()),
+            &m.destination,
+            &mut maxTraversals,
+            true,
+        )?;
+        for propagation in &options.propagation {
+            let recursive = propagation & LibcConst::MS_REC != 0;
+            mns.SetPropagation(&dirent, propagation & !LibcConst::MS_REC, recursive)?;
+        }
+    }
+
     info!("Mounted {} to {} type {}", m.source, m.destination, m.typ);
     return Ok(());
 }
 
-fn mountFlags(opts: &Vec<String>) -> MountSourceFlags {
-    let mut mf = MountSourceFlags::default();
+// MountHostPath returns the root of a mount backed by the host path of the
+// mount destination in the container rootfs, where the host has set up the
+// bind or overlay mount from the spec.
+fn MountHostPath(
+    task: &Task,
+    config: &config::Config,
+    m: &oci::Mount,
+    fsName: &str,
+    mf: &MountSourceFlags,
+) -> Result<Inode> {
+    let path = Join(&config.RootDir, &m.destination);
+    let (fd, writeable, fstat) = TryOpenAt(-100, &path)?;
+
+    let mut ms = MountSource::NewHostMountSource(
+        &path,
+        &ROOT_OWNER,
+        &WhitelistFileSystem::New(),
+        mf,
+        false,
+    );
+    if fsName == OVERLAY {
+        ms.FileSystemType = OVERLAY.to_string();
+    }
+
+    return Inode::NewHostInode(
+        task,
+        &Arc::new(QMutex::new(ms)),
+        fd,
+        &fstat,
+        writeable,
+        false,
+    );
+}
+
+// ParsedMountOptions is the result of parsing the options of an OCI mount.
+#[derive(Default, Debug)]
+pub struct ParsedMountOptions {
+    // flags are the MS_* mount flags.
+    pub flags: u64,
+    // propagation are the propagation changes to apply after the mount, in
+    // order, each a propagation type optionally with MS_REC.
+    pub propagation: Vec<u64>,
+    // data are the filesystem specific options.
+    pub data: Vec<String>,
+}
+
+// MountOptionFlag returns the mount flag of the option opt, and whether the
+// option clears the flag rather than set it.
+fn MountOptionFlag(opt: &str) -> Option<(bool, u64)> {
+    let ret = match opt {
+        "defaults" => (false, 0),
+        "ro" => (false, LibcConst::MS_RDONLY),
+        "rw" => (true, LibcConst::MS_RDONLY),
+        "suid" => (true, LibcConst::MS_NOSUID),
+        "nosuid" => (false, LibcConst::MS_NOSUID),
+        "dev" => (true, LibcConst::MS_NODEV),
+        "nodev" => (false, LibcConst::MS_NODEV),
+        "exec" => (true, LibcConst::MS_NOEXEC),
+        "noexec" => (false, LibcConst::MS_NOEXEC),
+        "sync" => (false, LibcConst::MS_SYNCHRONOUS),
+        "async" => (true, LibcConst::MS_SYNCHRONOUS),
+        "dirsync" => (false, LibcConst::MS_DIRSYNC),
+        "remount" => (false, LibcConst::MS_REMOUNT),
+        "mand" => (false, LibcConst::MS_MANDLOCK),
+        "nomand" => (true, LibcConst::MS_MANDLOCK),
+        "atime" => (true, LibcConst::MS_NOATIME),
+        "noatime" => (false, LibcConst::MS_NOATIME),
+        "diratime" => (true, LibcConst::MS_NODIRATIME),
+        "nodiratime" => (false, LibcConst::MS_NODIRATIME),
+        "relatime" => (false, LibcConst::MS_RELATIME),
+        "norelatime" => (true, LibcConst::MS_RELATIME),
+        "strictatime" => (false, LibcConst::MS_STRICTATIME),
+        "nostrictatime" => (true, LibcConst::MS_STRICTATIME),
+        "bind" => (false, LibcConst::MS_BIND),
+        "rbind" => (false, LibcConst::MS_BIND | LibcConst::MS_REC),
+        _ => return None,
+    };
+
+    return Some(ret);
+}
+
+// PropagationFlag returns the propagation change of the option opt.
+fn PropagationFlag(opt: &str) -> Option<u64> {
+    let ret = match opt {
+        "private" => LibcConst::MS_PRIVATE,
+        "rprivate" => LibcConst::MS_PRIVATE | LibcConst::MS_REC,
+        "shared" => LibcConst::MS_SHARED,
+        "rshared" => LibcConst::MS_SHARED | LibcConst::MS_REC,
+        "slave" => LibcConst::MS_SLAVE,
+        "rslave" => LibcConst::MS_SLAVE | LibcConst::MS_REC,
+        "unbindable" => LibcConst::MS_UNBINDABLE,
+        "runbindable" => LibcConst::MS_UNBINDABLE | LibcConst::MS_REC,
+        _ => return None,
+    };
+
+    return Some(ret);
+}
+
+// ParseMountOptions parses the options of an OCI mount the way runc does.
+pub fn ParseMountOptions(opts: &Vec<String>) -> ParsedMountOptions {
+    let mut res = ParsedMountOptions::default();
 
     for o in opts {
-        match o.as_str() {
-            "rw" => mf.ReadOnly = false,
-            "ro" => mf.ReadOnly = true,
-            "noatime" => mf.NoAtime = true,
-            "noexec" => mf.NoExec = true,
-            _ => info!("ignoring unknown mount option {}", o),
+        if let Some((clear, flag)) = MountOptionFlag(o) {
+            if clear {
+                res.flags &= !flag;
+            } else {
+                res.flags |= flag;
+            }
+            continue;
         }
+
+        if let Some(propagation) = PropagationFlag(o) {
+            res.propagation.push(propagation);
+            continue;
+        }
+
+        res.data.push(o.to_string());
     }
 
-    return mf;
+    return res;
+}
+
+fn mountFlags(flags: u64) -> MountSourceFlags {
+    return MountSourceFlags {
+        ReadOnly: flags & LibcConst::MS_RDONLY != 0,
+        NoAtime: flags & LibcConst::MS_NOATIME != 0,
+        NoExec: flags & LibcConst::MS_NOEXEC != 0,
+        NoSuid: flags & LibcConst::MS_NOSUID != 0,
+        NoDev: flags & LibcConst::MS_NODEV != 0,
+        ..Default::default()
+    };
 }
 
 fn MustFindFilesystem(name: &str) -> Arc<QMutex<Filesystem>> {
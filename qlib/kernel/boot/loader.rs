@@ -356,12 +356,16 @@ impl Loader {
             Some(&processSpec.TaskCaps()),
             &userns,
         );
-        let rootMounts = InitRootFs(Task::Current(), &processSpec.Root)
+        kernel.SetContainerSeccomp(&processSpec.ID, &processSpec.Seccomp)?;
+        kernel.SetContainerSysctls(&processSpec.Sysctl)?;
+        let rootMounts = InitRootFs(Task::Current(), &processSpec.Root, &processSpec.Mounts)
             .expect("in loader::StartSubContainer, InitRootfs fail");
         kernel
             .mounts
             .write()
             .insert(processSpec.ID.clone(), rootMounts);
+        kernel.SetMemCgroup(&processSpec.ID, &processSpec.memoryLimit);
+        kernel.SetContainerCgroups(&processSpec.ID, &processSpec.cpuLimit, processSpec.pidsLimit);
 
         //todo: investigate PID namespace and whether we need it.
         let mut createProcessArgs = NewProcess(processSpec, &creds, &kernel);
@@ -489,9 +493,17 @@ impl LoaderInternal {
         let kernel = Kernel::Init(kernalArgs);
         *SHARESPACE.kernel.lock() = Some(kernel.clone());
 
-        let rootMounts =
-            InitRootFs(Task::Current(), &process.Root).expect("in loader::New, InitRootfs fail");
+        let rootMounts = InitRootFs(Task::Current(), &process.Root, &process.Mounts)
+            .expect("in loader::New, InitRootfs fail");
         kernel.mounts.write().insert(sandboxID.clone(), rootMounts);
+        kernel.SetMemCgroup(&sandboxID, &process.memoryLimit);
+        kernel.SetContainerCgroups(&sandboxID, &process.cpuLimit, process.pidsLimit);
+        kernel
+            .SetContainerSeccomp(&sandboxID, &process.Seccomp)
+            .expect("in loader::New, invalid seccomp profile");
+        kernel
+            .SetContainerSysctls(&process.Sysctl)
+            .expect("in loader::New, invalid sysctl");
 
         let processArgs = NewProcess(process, &creds, &kernel);
         self.kernel = kernel;
@@ -683,6 +695,9 @@ impl LoaderInternal {
         }
 
         l.processes.remove(&execId);
+        l.kernel.RemoveMemCgroup(&cid);
+        l.kernel.RemoveContainerCgroups(&cid);
+        l.kernel.RemoveContainerSeccomp(&cid);
 
         info!("Container {} destroyed", &cid);
         return Ok(());
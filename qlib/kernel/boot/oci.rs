@@ -340,19 +340,22 @@ pub struct LinuxDevice {
     pub gid: Option<u32>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u32)]
 pub enum LinuxSeccompAction {
+    #[serde(alias = "SCMP_ACT_KILL_THREAD")]
     SCMP_ACT_KILL = 0x00000000,
+    SCMP_ACT_KILL_PROCESS = 0x80000000,
     SCMP_ACT_TRAP = 0x00030000,
     SCMP_ACT_ERRNO = 0x00050001,
     /* ERRNO + EPERM */
     SCMP_ACT_TRACE = 0x7ff00001,
     /* TRACE + EPERM */
+    SCMP_ACT_LOG = 0x7ffc0000,
     SCMP_ACT_ALLOW = 0x7fff0000,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Arch {
     SCMP_ARCH_NATIVE = 0x00000000,
     SCMP_ARCH_X86 = 0x40000003,
@@ -373,7 +376,7 @@ pub enum Arch {
     SCMP_ARCH_S390X = 0x80000016,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u32)]
 pub enum LinuxSeccompOperator {
     SCMP_CMP_NE = 1,
@@ -392,7 +395,7 @@ pub enum LinuxSeccompOperator {
     /* masked equality */
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct LinuxSeccompArg {
     pub index: usize,
     pub value: u64,
@@ -400,18 +403,20 @@ pub struct LinuxSeccompArg {
     pub op: LinuxSeccompOperator,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct LinuxSyscall {
     // old version used name
     pub name: String,
     pub names: Vec<String>,
     pub action: LinuxSeccompAction,
+    pub errno_ret: Option<u32>,
     pub args: Vec<LinuxSeccompArg>,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct LinuxSeccomp {
     pub default_action: LinuxSeccompAction,
+    pub default_errno_ret: Option<u32>,
     pub architectures: Vec<Arch>,
     pub syscalls: Vec<LinuxSyscall>,
 }
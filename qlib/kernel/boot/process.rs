@@ -12,10 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::collections::btree_set::BTreeSet;
 use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
 
 use super::super::super::control_msg::*;
 use super::super::kernel::kernel::*;
+use super::super::threadmgr::task_acct::*;
+use super::super::Tsc;
 
 pub fn Processes(k: &Kernel, containerID: &str) -> Vec<ProcessInfo> {
     let ts = k.TaskSet();
@@ -56,3 +60,53 @@ pub fn Processes(k: &Kernel, containerID: &str) -> Vec<ProcessInfo> {
 
     return ret;
 }
+
+// GetContainerStats returns the resource usage of the processes of the
+// container containerID.
+pub fn GetContainerStats(k: &Kernel, containerID: &str) -> ContainerStats {
+    let mut stats = ContainerStats::default();
+
+    // Thread groups created by vfork share the memory manager of their parent
+    // until they exec, count it once.
+    let mut mms = BTreeSet::new();
+    for (tg, _) in k.ContainerThreadGroups(containerID) {
+        let mut cpu = tg.CPUStats();
+        cpu.Accumulate(&tg.JoinedChildCPUStats());
+        // the cpu stats are in TSC ticks
+        stats.UserTime += (Tsc::Scale(cpu.UserTime) * 1000) as u64;
+        stats.SysTime += (Tsc::Scale(cpu.SysTime) * 1000) as u64;
+
+        let io = tg.IOUsage();
+        stats.BytesRead += io.BytesRead.load(Ordering::SeqCst);
+        stats.BytesWritten += io.BytesWritten.load(Ordering::SeqCst);
+        stats.ReadSyscalls += io.ReadSyscalls.load(Ordering::SeqCst);
+        stats.WriteSyscalls += io.WriteSyscalls.load(Ordering::SeqCst);
+
+        stats.Pids += tg.lock().tasks.len() as u64;
+
+        let leader = match tg.lock().leader.Upgrade() {
+            None => continue,
+            Some(l) => l,
+        };
+
+        let mm = leader.MemoryManager();
+        if mms.insert(mm.uid) {
+            let rss = mm.ResidentSetSize();
+            let cache = core::cmp::min(mm.FileResidentSetSize(), rss);
+            stats.MemoryUsage += rss;
+            stats.MemoryCache += cache;
+            stats.MemoryRSS += rss - cache;
+        }
+    }
+
+    if let Some(cg) = k.MemCgroup(containerID) {
+        stats.MemoryLimit = cg.limit;
+        stats.OOMKills = cg.oomKills;
+
+        // As in Linux, tmpfs pages are accounted as page cache.
+        stats.MemoryUsage += cg.shmem;
+        stats.MemoryCache += cg.shmem;
+    }
+
+    return stats;
+}
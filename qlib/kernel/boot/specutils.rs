@@ -45,7 +45,7 @@ pub fn IsSupportedDevMount(m: &oci::Mount) -> bool {
     ];
 
     let dst = Clean(&m.destination);
-    if dst.as_str() == "dev" {
+    if dst.as_str() == "/dev" {
         return false;
     }
 
@@ -161,6 +161,10 @@ impl StableAttr {
         return self.Type == InodeType::CharacterDevice;
     }
 
+    pub fn IsBlockDevice(&self) -> bool {
+        return self.Type == InodeType::BlockDevice;
+    }
+
     pub fn DentAttr(&self) -> DentAttr {
         return DentAttr {
             Type: self.Type,
@@ -0,0 +1,207 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::super::super::auth::*;
+use super::super::super::super::common::*;
+use super::super::super::super::device::*;
+use super::super::super::super::linux_def::*;
+use super::super::super::task::*;
+use super::super::attr::*;
+use super::super::dirent::*;
+use super::super::file::*;
+use super::super::filesystems::*;
+use super::super::flags::*;
+use super::super::fsutil::file::readonly_file::*;
+use super::super::fsutil::inode::simple_file_inode::*;
+use super::super::inode::*;
+use super::super::mount::*;
+use super::super::ramfs::dir::*;
+
+// CONTROLLERS are the cgroup controllers which the sandbox enforces for each
+// container, see kernel::cgroup.
+pub const CONTROLLERS: [&str; 4] = ["cpuset", "cpu", "memory", "pids"];
+
+// CgroupFileSystem is a read-only cgroup hierarchy, cgroup v1 or cgroup2. The
+// resource limits of the containers are enforced by the sandbox itself, the
+// hierarchy only exposes the controllers so that a cgroup mount from the OCI
+// spec has the expected layout. It doesn't support creating cgroups.
+pub struct CgroupFileSystem {
+    pub v2: bool,
+}
+
+impl Filesystem for CgroupFileSystem {
+    fn Name(&self) -> String {
+        if self.v2 {
+            return "cgroup2".to_string();
+        }
+
+        return "cgroup".to_string();
+    }
+
+    fn Flags(&self) -> FilesystemFlags {
+        return 0;
+    }
+
+    fn Mount(
+        &mut self,
+        task: &Task,
+        _device: &str,
+        flags: &MountSourceFlags,
+        _data: &str,
+    ) -> Result<Inode> {
+        // The hierarchy is the same whatever controllers the mount options
+        // ask for.
+        let msrc = Arc::new(QMutex::new(MountSource::NewCachingMountSource(self, flags)));
+        if self.v2 {
+            return Ok(NewCgroup2Root(task, &msrc));
+        }
+
+        return Ok(NewCgroupRoot(task, &msrc));
+    }
+
+    fn AllowUserMount(&self) -> bool {
+        return false;
+    }
+
+    fn AllowUserList(&self) -> bool {
+        return true;
+    }
+}
+
+fn NewDir(
+    task: &Task,
+    msrc: &Arc<QMutex<MountSource>>,
+    contents: BTreeMap<String, Inode>,
+) -> Inode {
+    let d = Dir::New(
+        task,
+        contents,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o0555)),
+    );
+
+    let deviceId = CGROUP_DEVICE.lock().id.DeviceID();
+    let inodeId = CGROUP_DEVICE.lock().NextIno();
+
+    let sattr = StableAttr {
+        Type: InodeType::SpecialDirectory,
+        DeviceId: deviceId,
+        InodeId: inodeId,
+        BlockSize: 4096,
+        DeviceFileMajor: 0,
+        DeviceFileMinor: 0,
+    };
+
+    return Inode::New(d.into(), msrc, &sattr);
+}
+
+fn NewFile(task: &Task, msrc: &Arc<QMutex<MountSource>>, fsType: u64, content: &str) -> Inode {
+    let data = CgroupFileData {
+        content: content.to_string(),
+    };
+
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o444)),
+        fsType,
+        false,
+        data.into(),
+    );
+
+    let deviceId = CGROUP_DEVICE.lock().id.DeviceID();
+    let inodeId = CGROUP_DEVICE.lock().NextIno();
+
+    let sattr = StableAttr {
+        Type: InodeType::SpecialFile,
+        DeviceId: deviceId,
+        InodeId: inodeId,
+        BlockSize: 4096,
+        DeviceFileMajor: 0,
+        DeviceFileMinor: 0,
+    };
+
+    return Inode::New(v.into(), msrc, &sattr);
+}
+
+// NewCgroupRoot returns the root of a cgroup v1 mount, which holds one
+// hierarchy for each controller as runc sets up /sys/fs/cgroup.
+pub fn NewCgroupRoot(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let mut contents = BTreeMap::new();
+    for c in CONTROLLERS.iter() {
+        let mut hierarchy = BTreeMap::new();
+        hierarchy.insert(
+            "cgroup.procs".to_string(),
+            NewFile(task, msrc, FSMagic::CGROUP_SUPER_MAGIC, ""),
+        );
+        hierarchy.insert(
+            "tasks".to_string(),
+            NewFile(task, msrc, FSMagic::CGROUP_SUPER_MAGIC, ""),
+        );
+        contents.insert(c.to_string(), NewDir(task, msrc, hierarchy));
+    }
+
+    return NewDir(task, msrc, contents);
+}
+
+// NewCgroup2Root returns the root of a cgroup2 mount.
+pub fn NewCgroup2Root(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let controllers = CONTROLLERS.join(" ") + "\n";
+
+    let mut contents = BTreeMap::new();
+    contents.insert(
+        "cgroup.controllers".to_string(),
+        NewFile(task, msrc, FSMagic::CGROUP2_SUPER_MAGIC, &controllers),
+    );
+    contents.insert(
+        "cgroup.subtree_control".to_string(),
+        NewFile(task, msrc, FSMagic::CGROUP2_SUPER_MAGIC, ""),
+    );
+    contents.insert(
+        "cgroup.procs".to_string(),
+        NewFile(task, msrc, FSMagic::CGROUP2_SUPER_MAGIC, ""),
+    );
+
+    return NewDir(task, msrc, contents);
+}
+
+pub struct CgroupFileData {
+    pub content: String,
+}
+
+impl CgroupFileData {
+    pub fn GenSnapshot(&self, _task: &Task) -> Vec<u8> {
+        return self.content.as_bytes().to_vec();
+    }
+}
+
+impl SimpleFileTrait for CgroupFileData {
+    fn GetFile(
+        &self,
+        task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
@@ -491,6 +491,29 @@ impl Dirent {
         self.children.lock().remove(name);
     }
 
+    // InvalidateChild drops the cached child with the given name and the cached
+    // directory entries of the backing host directory, so that the next lookup
+    // goes to the host again. It is called when the host directory is changed
+    // outside of the sandbox.
+    pub fn InvalidateChild(&self, name: &str) {
+        {
+            let _cl = self.cacheMu.lock();
+            let mounted = match self.GetCacheChild(name) {
+                None => false,
+                Some(child) => child.main.lock().mounted,
+            };
+
+            if !mounted {
+                self.children.lock().remove(name);
+            }
+        }
+
+        let iops = self.Inode().lock().InodeOp.clone();
+        if let Some(dirOps) = iops.HostDirOp() {
+            dirOps.lock().readdirCache = None;
+        }
+    }
+
     pub fn IsRoot(&self) -> bool {
         return self.main.lock().IsRoot()
     }
@@ -556,6 +579,8 @@ impl Dirent {
             return Err(Error::SysError(SysErr::EEXIST));
         }
 
+        task.mountNS.CheckWritable(self)?;
+
         let mut inode = self.Inode();
         let file = inode.Create(task, self, name, flags, perms)?;
 
@@ -588,6 +613,8 @@ impl Dirent {
             return Err(Error::SysError(SysErr::EEXIST));
         }
 
+        task.mountNS.CheckWritable(self)?;
+
         self.children.lock().remove(name);
         return create();
     }
@@ -812,6 +839,8 @@ impl Dirent {
         let _a = RENAME.read();
         let _dm = self.dirMu.write();
 
+        task.mountNS.CheckWritable(self)?;
+
         let mut inode = self.Inode();
 
         let child = self.walk(task, root, name)?;
@@ -856,6 +885,8 @@ impl Dirent {
         let _a = RENAME.read();
         let _dm = self.dirMu.write();
 
+        task.mountNS.CheckWritable(self)?;
+
         let mut inode = self.Inode();
         if name == "." {
             return Err(Error::SysError(SysErr::EINVAL));
@@ -909,6 +940,9 @@ impl Dirent {
     ) -> Result<()> {
         let _a = RENAME.write();
 
+        task.mountNS.CheckWritable(oldParent)?;
+        task.mountNS.CheckWritable(newParent)?;
+
         if Arc::ptr_eq(oldParent, newParent) {
             if oldName == newName {
                 return Ok(());
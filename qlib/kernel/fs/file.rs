@@ -60,6 +60,7 @@ use crate::qlib::kernel::fs::fsutil::file::static_dir_file_operations::StaticDir
 use crate::qlib::kernel::fs::fsutil::file::StaticFile;
 use crate::qlib::kernel::fs::fsutil::file::readonly_file::*;
 use crate::qlib::kernel::fs::host::hostdirfops::HostDirFops;
+use crate::qlib::kernel::fs::mqueue::queue::PosixQueueFileOperations;
 use crate::qlib::kernel::fs::procfs::seqfile::SeqFileOperations;
 use crate::qlib::kernel::fs::procfs::proc::RootProcFile;
 use crate::qlib::kernel::fs::ramfs::dir::DirFileOperation;
@@ -69,7 +70,16 @@ use crate::qlib::kernel::fs::tty::dir::DirFileOperations;
 use crate::qlib::kernel::fs::tty::master::MasterFileOperations;
 use crate::qlib::kernel::fs::tty::slave::SlaveFileOperations;
 use crate::qlib::kernel::kernel::eventfd::EventOperations;
+use crate::qlib::kernel::kernel::io_uring::IoUringOperations;
+use crate::qlib::kernel::kernel::perf_event::PerfEventOperations;
+use crate::qlib::kernel::fs::procfs::sys::sysctl::SysctlFileOperations;
+use crate::qlib::kernel::fs::procfs::task::oom_score_adj::OOMScoreAdjFileOperations;
+use crate::qlib::kernel::fs::procfs::task::comm::CommFileOperations;
+use crate::qlib::kernel::fs::procfs::task::loginuid::LoginUIDFileOperations;
+use crate::qlib::kernel::fs::procfs::task::setgroups::SetgroupsFileOperations;
+use crate::qlib::kernel::kernel::pidfd::PidfdOperations;
 use crate::qlib::kernel::kernel::signalfd::SignalOperation;
+use crate::qlib::kernel::kernel::userfaultfd::UserfaultfdOperations;
 use crate::qlib::kernel::kernel::epoll::epoll::EventPoll;
 use crate::qlib::kernel::kernel::pipe::reader::Reader;
 use crate::qlib::kernel::kernel::pipe::reader_writer::ReaderWriter;
@@ -302,7 +312,17 @@ pub enum FileOpsType {
     DynamicDirFileOperations,
     SignalOperation,
     InotifyFileOperations,
-    ProxyFileOperations
+    ProxyFileOperations,
+    PosixQueueFileOperations,
+    PidfdOperations,
+    PerfEventOperations,
+    IoUringOperations,
+    UserfaultfdOperations,
+    SysctlFileOperations,
+    OOMScoreAdjFileOperations,
+    CommFileOperations,
+    LoginUIDFileOperations,
+    SetgroupsFileOperations,
 }
 
 #[derive(Clone)]
@@ -344,7 +364,17 @@ pub enum FileOps {
     SocketOperations(SocketOperations),
     UringSocketOperations(UringSocketOperations),
     UnixSocketOperations(UnixSocketOperations),
-    RootProcFile(RootProcFile)
+    RootProcFile(RootProcFile),
+    PosixQueueFileOperations(PosixQueueFileOperations),
+    PidfdOperations(PidfdOperations),
+    PerfEventOperations(PerfEventOperations),
+    IoUringOperations(IoUringOperations),
+    UserfaultfdOperations(UserfaultfdOperations),
+    SysctlFileOperations(SysctlFileOperations),
+    OOMScoreAdjFileOperations(OOMScoreAdjFileOperations),
+    CommFileOperations(CommFileOperations),
+    LoginUIDFileOperations(LoginUIDFileOperations),
+    SetgroupsFileOperations(SetgroupsFileOperations),
 }
 
 impl FileOps {
@@ -507,6 +537,12 @@ impl Drop for File {
             let lockUniqueID = self.UniqueId();
             lockCtx.BSD.UnlockRegion(task, lockUniqueID, &Range::Max());
 
+            // Drop open file description locks.
+            lockCtx.Posix.UnlockRegion(task, lockUniqueID, &Range::Max());
+            if let Some(fd) = self.HostLockFd() {
+                let _ = lockCtx.Posix.SyncHostRegion(fd, &Range::Max());
+            }
+
             // Only unregister if we are currently registered. There is nothing
             // to register if f.async is nil (this happens when async mode is
             // enabled without setting an owner). Also, we unregister during
@@ -982,8 +1018,10 @@ impl File {
     pub fn Flush(&self, task: &Task) -> Result<()> {
         let fops = self.FileOp.clone();
 
+        // POSIX message queues drop the closing process's notification
+        // registration on flush, whatever the descriptor's access mode.
         let flags = self.Flags();
-        if flags.Write {
+        if flags.Write || fops.FopsType() == FileOpsType::PosixQueueFileOperations {
             let res = fops.Flush(task, self);
             return res;
         }
@@ -53,6 +53,8 @@ pub struct MountSourceFlags {
     pub NoAtime: bool,
     pub ForcePageCache: bool,
     pub NoExec: bool,
+    pub NoSuid: bool,
+    pub NoDev: bool,
 }
 
 pub struct FileSystems {
@@ -31,8 +31,8 @@ use super::*;
 use crate::qlib::kernel::fs::procfs::meminfo::MeminfoFileNode;
 use crate::qlib::kernel::fs::procfs::net::NetTCPReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::task::auxvec::AUXVecReadonlyFileNode;
-use crate::qlib::kernel::fs::procfs::task::comm::CommReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::task::exec_args::ExecArgReadonlyFileNode;
+use crate::qlib::kernel::fs::procfs::task::oom_score::OOMScoreReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::uptime::UptimeFileNode;
 use crate::qlib::kernel::fs::procfs::task::uid_pid_map::IdMapReadonlyFileNode;
 use crate::qlib::kernel::fs::procfs::net::NetUnixReadonlyFileNode;
@@ -85,10 +85,10 @@ pub enum ReadonlyFileNode {
     NetUDPReadonlyFileNode(NetUDPReadonlyFileNode),
     NetUnixReadonlyFileNode(NetUnixReadonlyFileNode),
     AUXVecReadonlyFileNode(AUXVecReadonlyFileNode),
-    CommReadonlyFileNode(CommReadonlyFileNode),
     ExecArgReadonlyFileNode(ExecArgReadonlyFileNode),
     IdMapReadonlyFileNode(IdMapReadonlyFileNode),
     UptimeFileNode(UptimeFileNode),
+    OOMScoreReadonlyFileNode(OOMScoreReadonlyFileNode),
 }
 
 #[enum_dispatch(ReadonlyFileNode)]
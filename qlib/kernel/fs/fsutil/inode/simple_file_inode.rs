@@ -34,6 +34,8 @@ use super::super::super::host::hostinodeop::*;
 use super::super::super::inode::*;
 use super::super::super::mount::*;
 
+use crate::qlib::kernel::fs::cgroupfs::fs::CgroupFileData;
+use crate::qlib::kernel::fs::mqueue::queue::PosixQueueFile;
 use crate::qlib::kernel::fs::procfs::task::stat::TaskStatData;
 use crate::qlib::kernel::fs::procfs::filesystems::FileSystemData;
 use crate::qlib::kernel::fs::procfs::loadavg::LoadAvgData;
@@ -49,7 +51,19 @@ use crate::qlib::kernel::fs::procfs::task::io::IOData;
 use crate::qlib::kernel::fs::procfs::task::maps::MapsData;
 use crate::qlib::kernel::fs::procfs::task::mounts::MountInfoFile;
 use crate::qlib::kernel::fs::procfs::task::mounts::MountsFile;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NamespaceFile;
+use crate::qlib::kernel::fs::procfs::task::oom_score::OOMScoreSimpleFileTrait;
+use crate::qlib::kernel::fs::procfs::task::oom_score_adj::OOMScoreAdjSimpleFileTrait;
+use crate::qlib::kernel::fs::procfs::task::smaps::SmapsData;
+use crate::qlib::kernel::fs::procfs::task::cgroup::CgroupData;
+use crate::qlib::kernel::fs::procfs::task::limits::LimitsData;
+use crate::qlib::kernel::fs::procfs::task::loginuid::LoginUIDSimpleFileTrait;
+use crate::qlib::kernel::fs::procfs::task::personality::PersonalityData;
+use crate::qlib::kernel::fs::procfs::task::sched::SchedData;
+use crate::qlib::kernel::fs::procfs::task::setgroups::SetgroupsSimpleFileTrait;
+use crate::qlib::kernel::fs::procfs::task::wchan::WchanData;
 use crate::qlib::kernel::fs::procfs::stat::StatData;
+use crate::qlib::kernel::fs::procfs::sys::sysctl::SysctlSimpleFileTrait;
 use crate::qlib::kernel::fs::procfs::task::statm::StatmData;
 use crate::qlib::kernel::fs::procfs::task::status::StatusData;
 use crate::qlib::kernel::fs::procfs::task::uid_pid_map::IdMapSimpleFileTrait;
@@ -85,13 +99,27 @@ pub enum SimpleFileImpl {
     ExecArgSimpleFileTrait(ExecArgSimpleFileTrait),
     IOData(IOData),
     MapsData(MapsData),
+    SmapsData(SmapsData),
     MountInfoFile(MountInfoFile),
     MountsFile(MountsFile),
+    NamespaceFile(NamespaceFile),
+    PosixQueueFile(PosixQueueFile),
     StatData(StatData),
     StatmData(StatmData),
     StatusData(StatusData),
     IdMapSimpleFileTrait(IdMapSimpleFileTrait),
     PossibleData(PossibleData),
+    CgroupFileData(CgroupFileData),
+    SysctlSimpleFileTrait(SysctlSimpleFileTrait),
+    OOMScoreSimpleFileTrait(OOMScoreSimpleFileTrait),
+    OOMScoreAdjSimpleFileTrait(OOMScoreAdjSimpleFileTrait),
+    LimitsData(LimitsData),
+    CgroupData(CgroupData),
+    SchedData(SchedData),
+    WchanData(WchanData),
+    PersonalityData(PersonalityData),
+    LoginUIDSimpleFileTrait(LoginUIDSimpleFileTrait),
+    SetgroupsSimpleFileTrait(SetgroupsSimpleFileTrait),
     Dummy(Dummy),
 }
 
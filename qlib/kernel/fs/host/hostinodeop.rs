@@ -47,6 +47,7 @@ use super::super::file::*;
 use super::super::filesystems::*;
 use super::super::flags::*;
 use super::super::inode::*;
+use super::super::tmpfs::usage::*;
 use super::fs::*;
 use super::hostfileop::*;
 use super::util::*;
@@ -187,6 +188,9 @@ pub struct HostInodeOpIntern {
     pub hasMappable: bool,
 
     pub isMemfd: bool,
+
+    // tmpfsUsage is the space charged by the file if it backs a tmpfs file.
+    pub tmpfsUsage: Option<TmpfsInodeUsage>,
 }
 
 impl Default for HostInodeOpIntern {
@@ -204,6 +208,7 @@ impl Default for HostInodeOpIntern {
             bufWriteLock: QAsyncLock::default(),
             hasMappable: false,
             isMemfd: false,
+            tmpfsUsage: None,
         };
     }
 }
@@ -257,7 +262,8 @@ impl HostInodeOpIntern {
             size: fstat.st_size,
             bufWriteLock: QAsyncLock::default(),
             hasMappable: false,
-            isMemfd: isMemfd
+            isMemfd: isMemfd,
+            tmpfsUsage: None,
         };
 
         if ret.CanMap() {
@@ -743,6 +749,14 @@ impl HostInodeOp {
             size
         };
 
+        // The pages of a tmpfs file are charged before the write, which is
+        // shortened if the mount is full.
+        let tmpfsUsage = self.lock().tmpfsUsage.clone();
+        let size = match tmpfsUsage {
+            None => size,
+            Some(usage) => usage.Reserve(task, offset, size as i64)? as usize,
+        };
+
         let mut buf = DataBuff::New(size);
         let len = task.CopyDataInFromIovs(&mut buf.buf, srcs, true)?;
         let iovs = buf.Iovs(len);
@@ -807,6 +821,14 @@ impl HostInodeOp {
         let inodeType = hostIops.InodeType();
         if inodeType == InodeType::RegularFile || inodeType == InodeType::SpecialFile {
             let size = IoVec::NumBytes(srcs);
+            let tmpfsUsage = hostIops.lock().tmpfsUsage.clone();
+            let size = match tmpfsUsage {
+                None => size,
+                Some(usage) => {
+                    let (fileSize, _) = hostIops.Size()?;
+                    usage.Reserve(task, fileSize, size as i64)? as usize
+                }
+            };
             /*let size = if size >= MemoryDef::HUGE_PAGE_SIZE as usize {
                 MemoryDef::HUGE_PAGE_SIZE as usize
             } else {
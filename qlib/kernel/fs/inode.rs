@@ -97,6 +97,14 @@ pub fn ContextCanAccessFile(task: &Task, inode: &Inode, reqPerms: &PermMask) ->
         return Ok(false);
     }
 
+    // Device files on a nodev mount can't be opened at all.
+    if (inode.StableAttr().IsCharDevice() || inode.StableAttr().IsBlockDevice())
+        && (reqPerms.read || reqPerms.write)
+        && inode.lock().MountSource.lock().Flags.NoDev
+    {
+        return Ok(false);
+    }
+
     //info!("ContextCanAccessFile 3, p is {:?}, reqPerms is {:?}", &p, reqPerms);
     if p.SupersetOf(reqPerms) {
         return Ok(true);
@@ -198,6 +206,13 @@ impl Iops {
         }
     } 
 
+    pub fn HostDirOp(&self) -> Option<HostDirOp> {
+        match self {
+            Self::HostDirOp(inner) => Some(inner.clone()),
+            _ => None,
+        }
+    } 
+
     pub fn TTYDevice(&self) -> Option<TTYDevice> {
         match self {
             Self::TTYDevice(inner) => Some(inner.clone()),
@@ -20,6 +20,7 @@ use spin::Mutex;
 use core::ops::Deref;
 use core::any::Any;
 use alloc::string::String;
+use alloc::string::ToString;
 
 use crate::qlib::mutex::*;
 use crate::qlib::kernel::kernel::waiter::*;
@@ -32,12 +33,35 @@ use super::super::super::linux_def::*;
 use super::super::uid::*;
 use super::super::kernel::waiter::Queue;
 use super::super::fs::dirent::*;
+use super::super::guestfdnotifier::*;
+use super::super::Kernel::HostSpace;
+use super::super::SHARESPACE;
 use super::file::*;
 
 // inotifyEventBaseSize is the base size of linux's struct inotify_event. This
 // must be a power 2 for rounding below.
 pub const INOTIFY_EVENT_BASE_SIZE: usize = 16;
 
+// HOST_INOTIFY_MASK is the set of events watched on the host for host backed
+// targets. Access/open/close-nowrite are left out as they are generated by the
+// guest itself for all the operations going through the sandbox.
+pub const HOST_INOTIFY_MASK: u32 = InotifyEvent::IN_MODIFY
+    | InotifyEvent::IN_ATTRIB
+    | InotifyEvent::IN_CLOSE_WRITE
+    | InotifyEvent::IN_MOVED_FROM
+    | InotifyEvent::IN_MOVED_TO
+    | InotifyEvent::IN_CREATE
+    | InotifyEvent::IN_DELETE
+    | InotifyEvent::IN_DELETE_SELF
+    | InotifyEvent::IN_MOVE_SELF;
+
+// HOST_INOTIFY_DIR_EVENTS are the host events which change the entries of the
+// watched directory and so invalidate the guest dirent cache.
+pub const HOST_INOTIFY_DIR_EVENTS: u32 = InotifyEvent::IN_MOVED_FROM
+    | InotifyEvent::IN_MOVED_TO
+    | InotifyEvent::IN_CREATE
+    | InotifyEvent::IN_DELETE;
+
 // PathEvent and InodeEvent correspond to FSNOTIFY_EVENT_PATH and
 // FSNOTIFY_EVENT_INODE in Linux.
 #[derive(PartialEq, Clone, Copy)]
@@ -338,6 +362,59 @@ impl WatchList {
     }
 }
 
+// HostInotify tracks the host inotify instance backing the watches of an
+// inotify instance whose targets are host files.
+//
+// Operations done by the guest on a host file are reported both by the guest
+// and by the host, so a watcher may see such an event twice. Consecutive
+// identical events are merged by QueueEvent.
+pub struct HostInotify {
+    // The host inotify fd, -1 until the first host watch is added.
+    pub fd: i32,
+
+    // Map from host watch descriptors to the guest watch descriptors sharing
+    // them. Several guest watches map to the same host watch when their targets
+    // are the same host inode.
+    pub hostWds: BTreeMap<i32, Vec<i32>>,
+
+    // Map from guest watch descriptors to host watch descriptors.
+    pub guestWds: BTreeMap<i32, i32>,
+}
+
+impl HostInotify {
+    pub fn New() -> Self {
+        return Self {
+            fd: -1,
+            hostWds: BTreeMap::new(),
+            guestWds: BTreeMap::new(),
+        }
+    }
+
+    // RemoveGuestWd drops the mapping of the guest watch wd and returns the host
+    // watch descriptor if it is not used by any other guest watch.
+    pub fn RemoveGuestWd(&mut self, wd: i32) -> Option<i32> {
+        let hostWd = match self.guestWds.remove(&wd) {
+            None => return None,
+            Some(hostWd) => hostWd,
+        };
+
+        let empty = match self.hostWds.get_mut(&hostWd) {
+            None => return None,
+            Some(wds) => {
+                wds.retain(|w| *w != wd);
+                wds.len() == 0
+            }
+        };
+
+        if !empty {
+            return None;
+        }
+
+        self.hostWds.remove(&hostWd);
+        return Some(hostWd);
+    }
+}
+
 pub struct InotifyIntern {
     // Unique identifier for this inotify instance. We don't just reuse the
     // inotify fd because fds can be duped. These should not be exposed to the
@@ -350,7 +427,10 @@ pub struct InotifyIntern {
     pub events: Mutex<LinkedList<Event>>,
 
     // Map from watch descriptors to watch objects.
-    pub watches: Mutex<WatchList>
+    pub watches: Mutex<WatchList>,
+
+    // Host watches mirroring the watches on host backed targets.
+    pub host: Mutex<HostInotify>,
 }
 
 #[derive(Clone)]
@@ -378,18 +458,178 @@ impl Inotify {
             id: NewUID(),
             queue: Queue::default(),
             events: Mutex::new(LinkedList::new()),
-            watches: Mutex::new(WatchList::New())
+            watches: Mutex::new(WatchList::New()),
+            host: Mutex::new(HostInotify::New()),
         };
         return Self(Arc::new(internl));
     }
 
     pub fn Release(&self) {
-        let ws = self.watches.lock();
-        for (_, w) in &ws.watches {
-            let inode = w.lock().target.clone();
-            match inode {
-                None => (),
-                Some(i) => i.Watches().Remove(w.Id())
+        {
+            let ws = self.watches.lock();
+            for (_, w) in &ws.watches {
+                let inode = w.lock().target.clone();
+                match inode {
+                    None => (),
+                    Some(i) => i.Watches().Remove(w.Id())
+                }
+            }
+        }
+
+        let mut host = self.host.lock();
+        if host.fd >= 0 {
+            HostSpace::Close(host.fd);
+            host.fd = -1;
+        }
+    }
+
+    // HostTargetFd returns the host fd of target if it is backed by a host file.
+    pub fn HostTargetFd(target: &Dirent) -> Option<i32> {
+        let iops = target.Inode().lock().InodeOp.clone();
+        let fd = if let Some(iops) = iops.HostInodeOp() {
+            iops.HostFd()
+        } else if let Some(iops) = iops.HostDirOp() {
+            iops.HostFd()
+        } else {
+            return None;
+        };
+
+        if fd < 0 {
+            return None;
+        }
+
+        return Some(fd);
+    }
+
+    // AddHostWatch adds a host watch for the guest watch wd if its target is a
+    // host file. The host inotify instance is created on first use and its
+    // readiness is reported through the queue of this inotify instance.
+    pub fn AddHostWatch(&self, wd: i32, target: &Dirent) {
+        if !SHARESPACE.config.read().HostInotify {
+            return;
+        }
+
+        let targetFd = match Self::HostTargetFd(target) {
+            None => return,
+            Some(fd) => fd,
+        };
+
+        let mut host = self.host.lock();
+        if host.fd < 0 {
+            let fd = HostSpace::HostInotifyInit();
+            if fd < 0 {
+                info!("AddHostWatch: host inotify init fail with error {}", fd);
+                return;
+            }
+
+            host.fd = fd as i32;
+            SetWaitInfo(host.fd, self.queue.clone());
+            UpdateFD(host.fd).unwrap();
+        }
+
+        let hostWd = HostSpace::HostInotifyAddWatch(host.fd, targetFd, HOST_INOTIFY_MASK);
+        if hostWd < 0 {
+            info!("AddHostWatch: host inotify add watch fail with error {}", hostWd);
+            return;
+        }
+
+        let hostWd = hostWd as i32;
+        host.guestWds.insert(wd, hostWd);
+        host.hostWds.entry(hostWd).or_insert_with(Vec::new).push(wd);
+    }
+
+    // RemoveHostWatch removes the host watch of the guest watch wd once no other
+    // guest watch uses it.
+    pub fn RemoveHostWatch(&self, wd: i32) {
+        let mut host = self.host.lock();
+        if host.fd < 0 {
+            return;
+        }
+
+        if let Some(hostWd) = host.RemoveGuestWd(wd) {
+            // The host watch might already be gone with its target.
+            HostSpace::HostInotifyRmWatch(host.fd, hostWd);
+        }
+    }
+
+    // ProcessHostEvents drains the host inotify fd and forwards the events to the
+    // guest watches of the host targets.
+    pub fn ProcessHostEvents(&self) {
+        let fd = self.host.lock().fd;
+        if fd < 0 {
+            return;
+        }
+
+        let buf = DataBuff::New(4096);
+        loop {
+            let iovs = buf.Iovs(buf.Len());
+            let ret = HostSpace::IORead(fd, &iovs[0] as *const _ as u64, 1);
+            if ret <= 0 {
+                return;
+            }
+
+            let cnt = ret as usize;
+            let mut pos = 0;
+            while pos + INOTIFY_EVENT_BASE_SIZE <= cnt {
+                let (wd, mask, cookie, len) = unsafe {
+                    let e = &buf.buf[pos] as *const _ as *const u32;
+                    (*e as i32, *e.offset(1), *e.offset(2), *e.offset(3) as usize)
+                };
+
+                let start = pos + INOTIFY_EVENT_BASE_SIZE;
+                pos = start + len;
+
+                let name = &buf.buf[start..pos];
+                let nameLen = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+                let name = String::from_utf8_lossy(&name[..nameLen]).to_string();
+
+                self.HandleHostEvent(wd, mask, cookie, &name);
+            }
+        }
+    }
+
+    pub fn HandleHostEvent(&self, hostWd: i32, mask: u32, cookie: u32, name: &str) {
+        if mask & InotifyEvent::IN_Q_OVERFLOW != 0 {
+            self.QueueEvent(Event::New(-1, "", InotifyEvent::IN_Q_OVERFLOW, 0));
+            return;
+        }
+
+        let wds = {
+            let mut host = self.host.lock();
+            if mask & InotifyEvent::IN_IGNORED != 0 {
+                // The host watch is removed together with its target.
+                if let Some(wds) = host.hostWds.remove(&hostWd) {
+                    for wd in &wds {
+                        host.guestWds.remove(wd);
+                    }
+                }
+
+                return;
+            }
+
+            match host.hostWds.get(&hostWd) {
+                None => return,
+                Some(wds) => wds.clone(),
+            }
+        };
+
+        let watches: Vec<Watch> = {
+            let ws = self.watches.lock();
+            wds.iter().filter_map(|wd| ws.watches.get(wd).cloned()).collect()
+        };
+
+        for w in &watches {
+            let target = match w.lock().target.clone() {
+                None => continue,
+                Some(t) => t,
+            };
+
+            if name.len() > 0 && mask & HOST_INOTIFY_DIR_EVENTS != 0 {
+                target.InvalidateChild(name);
+            }
+
+            if w.Notify(name, mask, cookie) {
+                target.Watches().cleanupExpiredWatches();
             }
         }
     }
@@ -412,6 +652,15 @@ impl Inotify {
         self.queue.Notify(READABLE_EVENT)
     }
 
+    // UpdateHostFD refreshes the host readiness registration of the host inotify
+    // fd after the waiters of this inotify instance change.
+    pub fn UpdateHostFD(&self) {
+        let fd = self.host.lock().fd;
+        if fd >= 0 {
+            UpdateFD(fd).unwrap();
+        }
+    }
+
     // newWatchLocked creates and adds a new watch to target.
     pub fn NewWatchLocked(&self, target: &Dirent, mask: u32) -> Watch {
         let mut ws = self.watches.lock();
@@ -447,6 +696,7 @@ impl Inotify {
 
         if found {
             let wd = w.lock().wd;
+            self.RemoveHostWatch(wd);
             self.QueueEvent(Event::New(wd, "", InotifyEvent::IN_IGNORED, 0))
         }
     }
@@ -476,7 +726,9 @@ impl Inotify {
 
         // No existing watch, create a new watch.
         let watch = self.NewWatchLocked(target, mask);
-        return watch.lock().wd;
+        let wd = watch.lock().wd;
+        self.AddHostWatch(wd, target);
+        return wd;
     }
 
     // RmWatch implements watcher.Watchable.RmWatch.
@@ -503,6 +755,7 @@ impl Inotify {
         }
 
         let wd = watch.lock().wd;
+        self.RemoveHostWatch(wd);
         self.QueueEvent(Event::New(wd, "", InotifyEvent::IN_IGNORED, 0));
         watch.Destroy();
         return Ok(())
@@ -558,6 +811,7 @@ impl FileOperations for Inotify {
         };
         let mut buf = DataBuff::New(size);
 
+        self.ProcessHostEvents();
         let mut events = self.events.lock();
         if events.len() == 0 {
             return Err(Error::SysError(SysErr::EAGAIN))
@@ -629,6 +883,7 @@ impl FileOperations for Inotify {
     fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
         match request {
             IoCtlCmd::FIONREAD => {
+                self.ProcessHostEvents();
                 let events = self.events.lock();
                 loop {
                     let mut size : u32 = 0;
@@ -663,6 +918,7 @@ impl FileOperations for Inotify {
 
 impl Waitable for Inotify {
     fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        self.ProcessHostEvents();
         let ready = if self.events.lock().len() > 0 {
             READABLE_EVENT
         } else {
@@ -675,11 +931,13 @@ impl Waitable for Inotify {
     fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
         let queue = self.queue.clone();
         queue.EventRegister(task, e, mask);
+        self.UpdateHostFD();
     }
 
     fn EventUnregister(&self, task: &Task, e: &WaitEntry) {
         let queue = self.queue.clone();
         queue.EventUnregister(task, e);
+        self.UpdateHostFD();
     }
 }
 
@@ -16,6 +16,7 @@ use crate::qlib::mutex::*;
 use alloc::collections::btree_map::BTreeMap;
 use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ops::Deref;
 
 use super::super::super::common::*;
@@ -26,6 +27,11 @@ use super::super::super::kernel::fs::file::*;
 use super::super::super::range::*;
 use super::super::kernel::waiter::*;
 use super::super::task::*;
+use super::super::SHARESPACE;
+use super::attr::*;
+use super::host::hostinodeop::*;
+use super::host::util::Fcntl;
+use super::inode::*;
 
 #[derive(Clone, Copy, Debug)]
 pub enum LockType {
@@ -56,6 +62,14 @@ impl OwnerInfo {
 
 type UniqueId = u64;
 
+// OFD_LOCK_PID is the pid reported by F_GETLK and F_OFD_GETLK for an open
+// file description lock, which is not owned by a process.
+pub const OFD_LOCK_PID: i32 = -1;
+
+// HOST_LOCK_RETRY_INTERVAL is how long a blocking lock request waits before
+// retrying a host lock held by a process outside of the sandbox, in ns.
+pub const HOST_LOCK_RETRY_INTERVAL: i64 = 10_000_000;
+
 pub const READ_LOCK: u32 = 0;
 pub const WRITE_LOCK: u32 = 1;
 
@@ -533,6 +547,73 @@ impl Locks {
         return f;
     }
 
+    // HostLockTypes splits r into the ranges covered by the same kind of
+    // lock, F_UNLCK, F_RDLCK or F_WRLCK, whoever holds it in the guest.
+    pub fn HostLockTypes(&self, r: &Range) -> Vec<(Range, i32)> {
+        let l = self.lock();
+
+        let mut pieces = Vec::new();
+        let mut cur = r.Start();
+        let mut seg = l.locks.LowerBoundSeg(r.Start());
+        while cur < r.End() {
+            let (end, typ) = if seg.Ok() && seg.Range().Start() <= cur {
+                let typ = if seg.Value().lock().Writer.is_some() {
+                    F_WRLCK
+                } else {
+                    F_RDLCK
+                };
+                let end = seg.Range().End();
+                seg = seg.NextSeg();
+                (end, typ)
+            } else if seg.Ok() {
+                (seg.Range().Start(), F_UNLCK)
+            } else {
+                (r.End(), F_UNLCK)
+            };
+
+            let end = if end > r.End() { r.End() } else { end };
+            pieces.push((Range::New(cur, end - cur), typ));
+            cur = end;
+        }
+
+        return pieces;
+    }
+
+    // SyncHostRegion mirrors the locks held on r in the guest to the host
+    // file fd, so that they are honored by processes outside of the sandbox.
+    //
+    // The host lock is an open file description lock on the host fd of the
+    // Inode, which is shared by all the guest owners, so it covers the union
+    // of their locks. Returns false if a part of r can't be locked on the host
+    // because of a conflicting lock outside of the sandbox. Unlocking never
+    // fails.
+    pub fn SyncHostRegion(&self, fd: i32, r: &Range) -> Result<bool> {
+        let mut ret = true;
+        for (pr, typ) in self.HostLockTypes(r) {
+            let flock = Flock {
+                Type: typ as i16,
+                Whence: SeekWhence::SEEK_SET as i16,
+                Start: pr.Start() as i64,
+                // 0 locks up to the end of the file, however large it grows.
+                Len: if pr.End() == MAX_RANGE { 0 } else { pr.Len() as i64 },
+                Pid: 0,
+            };
+
+            let res = Fcntl(fd, F_OFD_SETLK, &flock as *const _ as u64);
+            if res < 0 {
+                let errno = -res as i32;
+                if errno == SysErr::EAGAIN || errno == SysErr::EACCES {
+                    ret = false;
+                    continue;
+                }
+
+                return Err(Error::SysError(errno));
+            }
+        }
+
+        return Ok(ret);
+    }
+
     pub fn testRegion(&self, r: &Range, mut check: impl FnMut(&Lock, u64, u64) -> bool) {
         let l = self.lock();
 
@@ -568,6 +649,28 @@ impl File {
 
         return ComputeRange(start, len, offset);
     }
+
+    // HostLockFd returns the host fd to mirror the byte range locks of the
+    // file to, if the HostFileLock option is set and the file is backed by a
+    // regular host file.
+    pub fn HostLockFd(&self) -> Option<i32> {
+        if !SHARESPACE.config.read().HostFileLock {
+            return None;
+        }
+
+        let inode = self.Dirent.Inode();
+        let iops = inode.lock().InodeOp.clone();
+        let hostIops = match iops.as_any().downcast_ref::<HostInodeOp>() {
+            None => return None,
+            Some(iops) => iops.clone(),
+        };
+
+        if hostIops.InodeType() != InodeType::RegularFile {
+            return None;
+        }
+
+        return Some(hostIops.HostFd());
+    }
 }
 
 // ComputeRange takes a positive file offset and computes the start of a LockRange
@@ -18,6 +18,7 @@ pub mod inode;
 //pub mod inodeOperations;
 pub mod anon;
 pub mod attr;
+pub mod cgroupfs;
 pub mod copy_up;
 pub mod dentry;
 pub mod dev;
@@ -30,6 +31,7 @@ pub mod inode_overlay;
 pub mod lock;
 pub mod mount;
 pub mod mount_overlay;
+pub mod mqueue;
 pub mod overlay;
 pub mod procfs;
 pub mod ramfs;
@@ -45,4 +47,6 @@ pub fn Init() {
     self::procfs::Init();
     self::sys::Init();
     self::tmpfs::Init();
+    self::mqueue::Init();
+    self::cgroupfs::Init();
 }
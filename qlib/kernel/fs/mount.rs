@@ -27,9 +27,11 @@ use core::sync::atomic::Ordering;
 use super::super::super::auth::userns::*;
 use super::super::super::auth::*;
 use super::super::super::common::*;
+use super::super::super::linux::openat2::*;
 use super::super::super::linux_def::*;
 use super::super::super::lrc_cache::*;
 use super::super::super::path::*;
+use super::super::kernel::nsfs::*;
 use super::super::task::*;
 use super::dirent::*;
 use super::filesystems::*;
@@ -48,11 +50,39 @@ pub enum ResolveResult {
     Path(LookupContext),
 }
 
+// PEER_GROUP_ID is the id of the next peer group of shared mounts.
+static PEER_GROUP_ID: AtomicU64 = AtomicU64::new(1);
+
 pub struct Mount {
     pub Id: u64,
     pub Pid: u64,
     pub root: Dirent,
     pub prev: Option<Arc<QMutex<Mount>>>,
+
+    // bindRoot is the path, relative to the root of its filesystem, of the
+    // directory that forms the root of this mount. It is None for mounts
+    // which are not bind mounts, i.e. which expose the whole filesystem.
+    pub bindRoot: Option<String>,
+
+    // shared is set if the mount's MountSource is also used by other
+    // mounts, i.e. for bind mounts and for the copies of mounts made for a
+    // new mount namespace.
+    pub shared: bool,
+
+    // propagation is the propagation type of the mount, one of MS_PRIVATE,
+    // MS_SHARED, MS_SLAVE and MS_UNBINDABLE. Mount and unmount events are not
+    // propagated between mounts, the type only shows in
+    // /proc/[pid]/mountinfo and restricts bind mounts.
+    pub propagation: u64,
+
+    // peerGroup is the peer group of a shared mount, or the peer group of
+    // the master of a slave mount.
+    pub peerGroup: u64,
+
+    // flags are the per-mount flags, initially those of the MountSource
+    // and changed by MS_REMOUNT. They are kept here rather than on the
+    // MountSource since bind mounts share the MountSource of their source.
+    pub flags: MountSourceFlags,
 }
 
 impl Mount {
@@ -64,6 +94,11 @@ impl Mount {
             Pid: pid,
             root: root.clone(),
             prev: None,
+            bindRoot: None,
+            shared: false,
+            propagation: LibcConst::MS_PRIVATE,
+            peerGroup: 0,
+            flags: root.Inode().lock().MountSource.lock().Flags,
         };
     }
 
@@ -73,6 +108,11 @@ impl Mount {
             Pid: Self::INVALID_MOUNT_ID,
             root: root.clone(),
             prev: None,
+            bindRoot: None,
+            shared: false,
+            propagation: LibcConst::MS_PRIVATE,
+            peerGroup: 0,
+            flags: root.Inode().lock().MountSource.lock().Flags,
         };
     }
 
@@ -82,6 +122,11 @@ impl Mount {
             Pid: Self::INVALID_MOUNT_ID,
             root: root.clone(),
             prev: None,
+            bindRoot: None,
+            shared: false,
+            propagation: LibcConst::MS_PRIVATE,
+            peerGroup: 0,
+            flags: MountSourceFlags::default(),
         };
     }
 
@@ -89,6 +134,52 @@ impl Mount {
         return self.root.clone();
     }
 
+    // BindRoot returns the root of the mount within its filesystem, as
+    // shown in field (4) of /proc/[pid]/mountinfo.
+    pub fn BindRoot(&self) -> String {
+        match &self.bindRoot {
+            None => return "/".to_string(),
+            Some(r) => return r.clone(),
+        }
+    }
+
+    // SetPropagation changes the propagation type of the mount as
+    // mount(2) with the MS_* flag propagation does.
+    pub fn SetPropagation(&mut self, propagation: u64) {
+        match propagation {
+            LibcConst::MS_SHARED => {
+                if self.propagation != LibcConst::MS_SHARED {
+                    self.peerGroup = PEER_GROUP_ID.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            LibcConst::MS_SLAVE => {
+                // "If this is a shared mount that is a member of a peer group
+                // that contains other members, convert it to a slave mount.
+                // ... If this is a private mount, ... it has no effect" -
+                // mount(2)
+                if self.propagation == LibcConst::MS_PRIVATE
+                    || self.propagation == LibcConst::MS_UNBINDABLE
+                {
+                    return;
+                }
+            }
+            _ => self.peerGroup = 0,
+        }
+
+        self.propagation = propagation;
+    }
+
+    // PropagationString formats the optional fields of the mount in
+    // /proc/[pid]/mountinfo.
+    pub fn PropagationString(&self) -> String {
+        match self.propagation {
+            LibcConst::MS_SHARED => return format!("shared:{} ", self.peerGroup),
+            LibcConst::MS_SLAVE => return format!("master:{} ", self.peerGroup),
+            LibcConst::MS_UNBINDABLE => return "unbindable ".to_string(),
+            _ => return "".to_string(),
+        }
+    }
+
     //whether it has no parent
     pub fn IsRoot(&self) -> bool {
         return !self.IsUndo() && self.Pid == Self::INVALID_MOUNT_ID;
@@ -112,6 +203,9 @@ pub struct MountNsInternal {
     pub root: Dirent,
     pub mounts: QMutex<BTreeMap<u64, Arc<QMutex<Mount>>>>,
     pub mountId: AtomicU64,
+
+    // id is the namespace's inode number.
+    pub id: u64,
 }
 
 impl Default for MountNsInternal {
@@ -121,6 +215,7 @@ impl Default for MountNsInternal {
             root: Dirent::default(),
             mounts: QMutex::new(BTreeMap::new()),
             mountId: AtomicU64::new(0),
+            id: 0,
         };
     }
 }
@@ -147,11 +242,90 @@ impl MountNs {
             root: d,
             mounts: QMutex::new(mounts),
             mountId: AtomicU64::new(2),
+            id: NewNamespaceInode(),
         };
 
         return Self(Arc::new(internal));
     }
 
+    // Fork returns a new mount namespace owned by userns, holding a copy of
+    // every mount in ns, for CLONE_NEWNS.
+    pub fn Fork(&self, task: &Task, userns: &UserNameSpace) -> Result<Self> {
+        let root = Dirent::New(&self.root.Inode(), &"/".to_string());
+        let mut mounts = BTreeMap::new();
+        let mut rootMount = Mount::NewRootMount(1, &root);
+        if let Some(m) = self.mounts.lock().get(&self.root.ID()) {
+            rootMount.flags = m.lock().flags;
+        }
+        mounts.insert(root.ID(), Arc::new(QMutex::new(rootMount)));
+        let internal = MountNsInternal {
+            userns: userns.clone(),
+            root: root.clone(),
+            mounts: QMutex::new(mounts),
+            mountId: AtomicU64::new(self.mountId.load(Ordering::SeqCst)),
+            id: NewNamespaceInode(),
+        };
+        let ns = Self(Arc::new(internal));
+
+        let mut copies = Vec::new();
+        for (_, mp) in self.mounts.lock().iter() {
+            let m = mp.lock();
+            if m.IsUndo() || m.IsRoot() {
+                continue;
+            }
+
+            let (path, reachable) = m.root.FullName(&self.root);
+            if !reachable {
+                continue;
+            }
+
+            copies.push((
+                path,
+                m.root.Inode(),
+                m.bindRoot.clone(),
+                m.propagation,
+                m.peerGroup,
+                m.flags,
+            ));
+        }
+
+        // Mount parents before their children.
+        copies.sort_by(|a, b| a.0.len().cmp(&b.0.len()));
+
+        for (path, inode, bindRoot, propagation, peerGroup, flags) in copies {
+            let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+            let mp = ns.FindDirent(task, &root, None, &path, &mut remainingTraversals, false)?;
+            let replacement = ns.mount(&mp, &inode, bindRoot, true)?;
+
+            // The copies of shared mounts are peers of the original mounts.
+            let mnt = ns.mounts.lock().get(&replacement.ID()).unwrap().clone();
+            let mut mnt = mnt.lock();
+            mnt.propagation = propagation;
+            mnt.peerGroup = peerGroup;
+            mnt.flags = flags;
+        }
+
+        return Ok(ns);
+    }
+
+    // Translate returns the dirent in ns at the same path as d in other, for
+    // moving a task's root and working directories into a new mount
+    // namespace.
+    pub fn Translate(&self, task: &Task, other: &MountNs, d: &Dirent) -> Result<Dirent> {
+        let (path, reachable) = d.FullName(&other.root);
+        if !reachable {
+            return Ok(self.Root());
+        }
+
+        let root = self.Root();
+        let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+        return self.FindDirent(task, &root, None, &path, &mut remainingTraversals, true);
+    }
+
+    pub fn ID(&self) -> u64 {
+        return self.id;
+    }
+
     pub fn ClearFsCache(&self) {
         let rootDirent = self.root.clone();
         rootDirent.inode.ClearFsCache();
@@ -166,6 +340,161 @@ impl MountNs {
     }
 
     pub fn Mount(&self, mountPoint: &Dirent, inode: &Inode) -> Result<()> {
+        self.mount(mountPoint, inode, None, false)?;
+        return Ok(());
+    }
+
+    // BindMount makes the subtree rooted at source visible at mountPoint.
+    // If recursive is set, the mounts under source are bound at the
+    // corresponding places under mountPoint as well (MS_BIND|MS_REC).
+    pub fn BindMount(
+        &self,
+        task: &Task,
+        mountPoint: &Dirent,
+        source: &Dirent,
+        recursive: bool,
+    ) -> Result<()> {
+        let srcMnt = match self.FindMount(source) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(m) => m,
+        };
+
+        // "EINVAL In an unprivileged mount namespace ... or the source is
+        // an unbindable mount" - mount(2)
+        if srcMnt.lock().propagation == LibcConst::MS_UNBINDABLE {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let bindRoot = {
+            let srcMnt = srcMnt.lock();
+            let (path, _) = source.FullName(&srcMnt.root);
+            match &srcMnt.bindRoot {
+                None => path,
+                Some(r) if path == "/" => r.clone(),
+                Some(r) if r == "/" => path,
+                Some(r) => r.clone() + &path,
+            }
+        };
+
+        // Collect the submounts before mounting, since the new mount may
+        // itself be under source.
+        let mut submounts = Vec::new();
+        if recursive {
+            for (_, mp) in self.mounts.lock().iter() {
+                let m = mp.lock();
+                if m.IsUndo() || m.root == *source || !m.root.DescendantOf(source) {
+                    continue;
+                }
+
+                let (path, _) = m.root.FullName(source);
+                submounts.push((path, m.root.Inode(), m.bindRoot.clone(), m.flags));
+            }
+
+            // Mount parents before their children.
+            submounts.sort_by(|a, b| a.0.len().cmp(&b.0.len()));
+        }
+
+        let replacement = self.mount(mountPoint, &source.Inode(), Some(bindRoot), true)?;
+
+        {
+            let mnt = self.mounts.lock().get(&replacement.ID()).unwrap().clone();
+            let mut mnt = mnt.lock();
+            let srcMnt = srcMnt.lock();
+
+            // The bind mount starts with the flags of its source mount.
+            mnt.flags = srcMnt.flags;
+
+            // A bind mount of a shared mount joins its peer group.
+            if srcMnt.propagation == LibcConst::MS_SHARED {
+                mnt.propagation = LibcConst::MS_SHARED;
+                mnt.peerGroup = srcMnt.peerGroup;
+            }
+        }
+
+        let root = task.Root();
+        for (path, inode, bindRoot, flags) in submounts {
+            let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+            let mp = self.FindDirent(
+                task,
+                &root,
+                Some(replacement.clone()),
+                path.trim_start_matches('/'),
+                &mut remainingTraversals,
+                false,
+            )?;
+            let d = self.mount(&mp, &inode, Some(bindRoot.unwrap_or("/".to_string())), true)?;
+            let mnt = self.mounts.lock().get(&d.ID()).unwrap().clone();
+            mnt.lock().flags = flags;
+        }
+
+        return Ok(());
+    }
+
+    // SetPropagation changes the propagation type of the mount rooted at
+    // node, and of all the mounts under it if recursive is set.
+    pub fn SetPropagation(&self, node: &Dirent, propagation: u64, recursive: bool) -> Result<()> {
+        let mnt = match self.mounts.lock().get(&node.ID()) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(m) => m.clone(),
+        };
+
+        if !recursive {
+            mnt.lock().SetPropagation(propagation);
+            return Ok(());
+        }
+
+        for m in self.AllMountsUnder(&mnt) {
+            m.lock().SetPropagation(propagation);
+        }
+
+        return Ok(());
+    }
+
+    // Remount changes the flags of the mount rooted at node. Only that mount
+    // is changed, not the other mounts sharing its MountSource.
+    pub fn Remount(&self, node: &Dirent, flags: &MountSourceFlags) -> Result<()> {
+        let mnt = match self.mounts.lock().get(&node.ID()) {
+            None => return Err(Error::SysError(SysErr::EINVAL)),
+            Some(m) => m.clone(),
+        };
+
+        let mut mnt = mnt.lock();
+        let forcePageCache = mnt.flags.ForcePageCache;
+        mnt.flags = *flags;
+        mnt.flags.ForcePageCache = forcePageCache;
+
+        // The MountSource flags are still checked by the Inodes. A
+        // filesystem mounted read-only may be remounted read-write, which
+        // doesn't loosen the other mounts as each of them checks its own
+        // copy of the flags on the write paths.
+        if !flags.ReadOnly {
+            node.Inode().lock().MountSource.lock().Flags.ReadOnly = false;
+        }
+
+        return Ok(());
+    }
+
+    // CheckWritable returns EROFS if d is in a read-only mount, as Linux's
+    // mnt_want_write does.
+    pub fn CheckWritable(&self, d: &Dirent) -> Result<()> {
+        match self.FindMount(d) {
+            Some(m) if m.lock().flags.ReadOnly => return Err(Error::SysError(SysErr::EROFS)),
+            _ => return Ok(()),
+        }
+    }
+
+    fn mount(
+        &self,
+        mountPoint: &Dirent,
+        inode: &Inode,
+        bindRoot: Option<String>,
+        shared: bool,
+    ) -> Result<Dirent> {
+        // Mounting over the root of the namespace is not supported.
+        if mountPoint.Parent().is_none() {
+            return Err(Error::SysError(SysErr::EBUSY));
+        }
+
         let replacement = mountPoint.Mount(inode)?;
 
         let parentMnt = self.FindMount(mountPoint).unwrap();
@@ -174,6 +503,8 @@ impl MountNs {
             parentMnt.lock().Id,
             &replacement,
         );
+        childMnt.bindRoot = bindRoot;
+        childMnt.shared = shared;
 
         mountPoint.clone().DropExtendedReference();
 
@@ -190,12 +521,12 @@ impl MountNs {
             childMnt.prev = Some(prev.unwrap().clone());
             mounts.remove(&mntId);
             mounts.insert(replacement.ID(), Arc::new(QMutex::new(childMnt)));
-            return Ok(());
+            return Ok(replacement);
         }
 
         childMnt.prev = Some(Arc::new(QMutex::new(Mount::NewUndoMount(mountPoint))));
         mounts.insert(replacement.ID(), Arc::new(QMutex::new(childMnt)));
-        return Ok(());
+        return Ok(replacement);
     }
 
     pub fn Unmount(&self, node: &Dirent, detachOnly: bool) -> Result<()> {
@@ -206,13 +537,17 @@ impl MountNs {
             Some(n) => n,
         };
 
+        // The initial mount has nothing to go back to.
         let prev = match &orig.lock().prev {
-            None => panic!("cannot unmount initial dirent"),
+            None => return Err(Error::SysError(SysErr::EBUSY)),
             Some(prev) => prev.clone(),
         };
 
+        // A shared MountSource is referenced by other mounts too, so the
+        // reference count doesn't tell whether this mount is in use.
+        let shared = orig.lock().shared;
         let m = node.Inode().lock().MountSource.clone();
-        if !detachOnly && Arc::strong_count(&m) != 2 {
+        if !detachOnly && !shared && Arc::strong_count(&m) != 2 {
             return Err(Error::SysError(SysErr::EBUSY));
         }
 
@@ -345,6 +680,57 @@ impl MountNs {
         }
     }
 
+    // CheckResolveLink enforces the openat2(2) RESOLVE_* restrictions on the
+    // symlink d which is about to be followed.
+    pub fn CheckResolveLink(&self, task: &Task, d: &Dirent, resolveFlags: u64) -> Result<()> {
+        if resolveFlags & (RESOLVE_NO_SYMLINKS | RESOLVE_NO_MAGICLINKS | RESOLVE_SCOPED) == 0 {
+            return Ok(());
+        }
+
+        let inode = d.Inode();
+        match inode.GetLink(task) {
+            Ok(_) => {
+                // "magic-links" jump straight to their target and can't be
+                // checked lexically. Source: fs/namei.c:nd_jump_link()
+                if resolveFlags & (RESOLVE_NO_SYMLINKS | RESOLVE_NO_MAGICLINKS) != 0 {
+                    return Err(Error::SysError(SysErr::ELOOP));
+                }
+
+                return Err(Error::SysError(SysErr::EXDEV));
+            }
+            Err(Error::ErrResolveViaReadlink) => {
+                if resolveFlags & RESOLVE_NO_SYMLINKS != 0 {
+                    return Err(Error::SysError(SysErr::ELOOP));
+                }
+
+                return Ok(());
+            }
+            Err(_) => return Ok(()),
+        }
+    }
+
+    // ResolvePathWithFlags is ResolvePath with the openat2(2) RESOLVE_*
+    // restrictions applied.
+    pub fn ResolvePathWithFlags(
+        &self,
+        task: &Task,
+        current: &Dirent,
+        remainingTraversals: &mut u32,
+        resolveFlags: u64,
+    ) -> Result<ResolveResult> {
+        self.CheckResolveLink(task, current, resolveFlags)?;
+
+        let res = self.ResolvePath(task, current, remainingTraversals)?;
+        if let ResolveResult::Path(ref context) = res {
+            // An absolute symlink escapes the dirfd for RESOLVE_BENEATH.
+            if resolveFlags & RESOLVE_BENEATH != 0 && IsAbs(&context.path) {
+                return Err(Error::SysError(SysErr::EXDEV));
+            }
+        }
+
+        return Ok(res);
+    }
+
     pub fn FindDirent(
         &self,
         task: &Task,
@@ -353,16 +739,44 @@ impl MountNs {
         path: &str,
         remainingTraversals: &mut u32,
         resolve: bool,
+    ) -> Result<Dirent> {
+        return self.FindDirentWithResolve(task, root, wd, path, remainingTraversals, resolve, 0);
+    }
+
+    // FindDirentWithResolve is FindDirent with the openat2(2) RESOLVE_* flags
+    // in resolveFlags. For RESOLVE_BENEATH and RESOLVE_IN_ROOT, root must be
+    // the dirent the lookup is scoped to: RESOLVE_IN_ROOT treats it as the
+    // root of the lookup, while RESOLVE_BENEATH fails with EXDEV on any
+    // attempt to leave it.
+    pub fn FindDirentWithResolve(
+        &self,
+        task: &Task,
+        root: &Dirent,
+        wd: Option<Dirent>,
+        path: &str,
+        remainingTraversals: &mut u32,
+        resolve: bool,
+        resolveFlags: u64,
     ) -> Result<Dirent> {
         if path.len() == 0 {
             return Err(Error::SysError(SysErr::ENOENT));
         }
 
+        if resolveFlags & RESOLVE_BENEATH != 0 && IsAbs(path) {
+            return Err(Error::SysError(SysErr::EXDEV));
+        }
+
         let (mut current, mut first, mut remain) = match self.InitPath(root, &wd, path) {
             None => return Ok(root.clone()),
             Some(res) => res,
         };
 
+        let startMount = if resolveFlags & RESOLVE_NO_XDEV != 0 {
+            self.FindMount(&current)
+        } else {
+            None
+        };
+
         let mut remainStr;
 
         let mut contexts = Vec::new();
@@ -383,6 +797,10 @@ impl MountNs {
                 )?
             }
 
+            if resolveFlags & RESOLVE_BENEATH != 0 && first == ".." && Arc::ptr_eq(&current, root) {
+                return Err(Error::SysError(SysErr::EXDEV));
+            }
+
             let next = match current.Walk(task, root, first) {
                 Err(e) => {
                     current.ExtendReference();
@@ -391,9 +809,26 @@ impl MountNs {
                 Ok(n) => n,
             };
 
+            if resolveFlags & RESOLVE_NO_XDEV != 0 {
+                let crossed = match (&startMount, self.FindMount(&next)) {
+                    (Some(start), Some(mount)) => !Arc::ptr_eq(start, &mount),
+                    (None, None) => false,
+                    _ => true,
+                };
+
+                if crossed {
+                    return Err(Error::SysError(SysErr::EXDEV));
+                }
+            }
+
             if !resolve {
                 if remain != "" {
-                    match self.ResolvePath(task, &next, remainingTraversals)? {
+                    match self.ResolvePathWithFlags(
+                        task,
+                        &next,
+                        remainingTraversals,
+                        resolveFlags,
+                    )? {
                         ResolveResult::Dirent(d) => current = d,
                         ResolveResult::Path(context) => {
                             contexts.push(remain.to_string());
@@ -426,7 +861,7 @@ impl MountNs {
                     }
                 }
             } else {
-                match self.ResolvePath(task, &next, remainingTraversals)? {
+                match self.ResolvePathWithFlags(task, &next, remainingTraversals, resolveFlags)? {
                     ResolveResult::Dirent(d) => {
                         current = d;
 
@@ -0,0 +1,65 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use alloc::string::ToString;
+
+use super::super::super::super::common::*;
+use super::super::super::super::linux_def::*;
+use super::super::super::task::*;
+use super::super::filesystems::*;
+use super::super::host::fs::*;
+use super::super::inode::*;
+use super::super::mount::*;
+
+// MqueueFileSystem is the mqueue filesystem, which exposes the POSIX message
+// queues of an IPC namespace. Each mount shows the queues of the mounting
+// task's IPC namespace; all mounts in the same namespace share one root.
+pub struct MqueueFileSystem {}
+
+impl Filesystem for MqueueFileSystem {
+    fn Name(&self) -> String {
+        return "mqueue".to_string();
+    }
+
+    fn Flags(&self) -> FilesystemFlags {
+        return 0;
+    }
+
+    fn Mount(
+        &mut self,
+        task: &Task,
+        _device: &str,
+        _flags: &MountSourceFlags,
+        data: &str,
+    ) -> Result<Inode> {
+        // mqueue takes no mount options.
+        let options = WhitelistFileSystem::GenericMountSourceOptions(data);
+        if options.len() > 0 {
+            info!("unsupported mount options: {:?}", options);
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let registry = task.IPCNamespace().PosixQueueRegistry();
+        return Ok(registry.Root(task).Inode());
+    }
+
+    fn AllowUserMount(&self) -> bool {
+        return true;
+    }
+
+    fn AllowUserList(&self) -> bool {
+        return true;
+    }
+}
@@ -0,0 +1,293 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::sync::Arc;
+use core::any::Any;
+
+use super::super::super::super::auth::*;
+use super::super::super::super::common::*;
+use super::super::super::super::device::*;
+use super::super::super::super::linux_def::*;
+use super::super::super::kernel::mqueue::*;
+use super::super::super::kernel::waiter::*;
+use super::super::super::task::*;
+use super::super::attr::*;
+use super::super::dentry::*;
+use super::super::dirent::*;
+use super::super::file::*;
+use super::super::flags::*;
+use super::super::fsutil::file::*;
+use super::super::fsutil::inode::simple_file_inode::*;
+use super::super::host::hostinodeop::*;
+use super::super::inode::*;
+use super::super::mount::*;
+use super::super::ramfs::dir::*;
+use super::fs::*;
+
+// NewMqueueRoot returns the root directory of a new mqueue filesystem. Like
+// Linux, it is world writable with the sticky bit set, so that any user may
+// create queues but only remove their own.
+pub fn NewMqueueRoot(task: &Task) -> Inode {
+    let d = Dir::New(
+        task,
+        BTreeMap::new(),
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o1777)),
+    );
+
+    {
+        let mut internal = d.write();
+        internal.fsType = FSMagic::MQUEUE_MAGIC;
+        internal.CreateOps = CreateOps {
+            NewFile: Some(newQueueFn),
+            ..Default::default()
+        };
+    }
+
+    let msrc =
+        MountSource::NewNonCachingMountSource(&MqueueFileSystem {}, &MountSourceFlags::default());
+
+    let deviceId = MQUEUE_DEVICE.lock().DeviceID();
+    let inodeId = MQUEUE_DEVICE.lock().NextIno();
+    let attr = StableAttr {
+        Type: InodeType::Directory,
+        DeviceId: deviceId,
+        InodeId: inodeId,
+        BlockSize: MemoryDef::PAGE_SIZE as i64,
+        DeviceFileMajor: 0,
+        DeviceFileMinor: 0,
+    };
+
+    return Inode::New(d.into(), &Arc::new(QMutex::new(msrc)), &attr);
+}
+
+// newQueueFn creates a queue with the default attributes when a file is
+// created in the mqueue filesystem with open(2).
+fn newQueueFn(task: &Task, dir: &Inode, perms: &FilePermissions) -> Result<Inode> {
+    return Ok(NewQueueInode(
+        task,
+        dir,
+        &task.FileOwner(),
+        perms,
+        &PosixQueue::NewDefault(),
+    ));
+}
+
+// NewQueueInode returns an inode in the mqueue filesystem rooted at dir for
+// queue.
+pub fn NewQueueInode(
+    task: &Task,
+    dir: &Inode,
+    owner: &FileOwner,
+    perms: &FilePermissions,
+    queue: &PosixQueue,
+) -> Inode {
+    let iops = SimpleFileInode::New(
+        task,
+        owner,
+        perms,
+        FSMagic::MQUEUE_MAGIC,
+        true,
+        PosixQueueFile {
+            queue: queue.clone(),
+        }
+        .into(),
+    );
+
+    let deviceId = MQUEUE_DEVICE.lock().DeviceID();
+    let inodeId = MQUEUE_DEVICE.lock().NextIno();
+    let attr = StableAttr {
+        Type: InodeType::RegularFile,
+        DeviceId: deviceId,
+        InodeId: inodeId,
+        BlockSize: MemoryDef::PAGE_SIZE as i64,
+        DeviceFileMajor: 0,
+        DeviceFileMinor: 0,
+    };
+
+    let msrc = dir.lock().MountSource.clone();
+    return Inode::New(iops.into(), &msrc, &attr);
+}
+
+// AddQueueToRoot links the queue inode into the mqueue filesystem root dir
+// under name.
+pub fn AddQueueToRoot(task: &Task, dir: &Inode, name: &str, inode: &mut Inode) -> Result<()> {
+    let iops = dir.lock().InodeOp.clone();
+    match iops {
+        Iops::Dir(d) => {
+            d.AddChild(task, name, inode);
+            return Ok(());
+        }
+        _ => return Err(Error::SysError(SysErr::ENOTDIR)),
+    }
+}
+
+// PosixQueueFile is the inode data of a queue in the mqueue filesystem.
+pub struct PosixQueueFile {
+    pub queue: PosixQueue,
+}
+
+impl SimpleFileTrait for PosixQueueFile {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = PosixQueueFileOperations {
+            queue: self.queue.clone(),
+        };
+
+        return Ok(File::New(dirent, &flags, fops.into()));
+    }
+}
+
+// PosixQueueFileOperations implements FileOperations for a descriptor
+// returned by mq_open(3), or by open(2) in the mqueue filesystem.
+#[derive(Clone)]
+pub struct PosixQueueFileOperations {
+    pub queue: PosixQueue,
+}
+
+impl Waitable for PosixQueueFileOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        return self.queue.Readiness(mask);
+    }
+
+    fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
+        let q = self.queue.lock().queue.clone();
+        q.EventRegister(task, e, mask)
+    }
+
+    fn EventUnregister(&self, task: &Task, e: &WaitEntry) {
+        let q = self.queue.lock().queue.clone();
+        q.EventUnregister(task, e)
+    }
+}
+
+impl SpliceOperations for PosixQueueFileOperations {}
+
+impl FileOperations for PosixQueueFileOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::PosixQueueFileOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return true;
+    }
+
+    fn Seek(&self, task: &Task, f: &File, whence: i32, current: i64, offset: i64) -> Result<i64> {
+        return SeekWithDirCursor(task, f, whence, current, offset, None);
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    // ReadAt returns the queue's status, as read(2) does on Linux.
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let status = self.queue.Status(task);
+        let bytes = status.as_bytes();
+        if offset as usize >= bytes.len() {
+            return Ok(0);
+        }
+
+        let n = task.CopyDataOutToIovs(&bytes[offset as usize..], dsts, true)?;
+        return Ok(n as i64);
+    }
+
+    fn WriteAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    // Flush removes the caller's notification registration. "If the calling
+    // process ... closes the message queue descriptor, then the registration
+    // is removed." - mq_notify(3)
+    fn Flush(&self, task: &Task, _f: &File) -> Result<()> {
+        self.queue.Unsubscribe(task);
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for PosixQueueFileOperations {}
@@ -35,11 +35,14 @@ use super::super::ramfs::dir::*;
 use crate::qlib::kernel::fs::procfs::sys::net::net::SysNetDirNode;
 use crate::qlib::kernel::fs::procfs::proc::ProcNode;
 use crate::qlib::kernel::fs::procfs::sys::vm::vm::ProcSysVMDirNode;
+use crate::qlib::kernel::fs::procfs::sys::kernel::kernel::ProcSysKernelDirNode;
 use crate::qlib::kernel::fs::procfs::sys::net::ipv4::Ipv4Node;
 use crate::qlib::kernel::fs::procfs::net::NetDirNode;
 use crate::qlib::kernel::fs::procfs::task::fds::FdDirNode;
 use crate::qlib::kernel::fs::procfs::sys::sys::ProcSysDirNode;
+use crate::qlib::kernel::fs::procfs::sys::sysctl::SysctlDirNode;
 use crate::qlib::kernel::fs::procfs::task::fds::FdInfoDirNode;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NamespaceDirNode;
 use crate::qlib::kernel::fs::procfs::task::subtasks::SubTasksNode;
 use crate::qlib::kernel::fs::procfs::task::task::TaskDirNode;
 
@@ -66,13 +69,16 @@ pub enum DirDataNode {
     SysNetDirNode(SysNetDirNode),
     ProcNode(ProcNode),
     ProcSysVMDirNode(ProcSysVMDirNode),
+    ProcSysKernelDirNode(ProcSysKernelDirNode),
     Ipv4Node(Ipv4Node),
     NetDirNode(NetDirNode),
     ProcSysDirNode(ProcSysDirNode),
+    SysctlDirNode(SysctlDirNode),
     FdDirNode(FdDirNode),
     FdInfoDirNode(FdInfoDirNode),
     SubTasksNode(SubTasksNode),
     TaskDirNode(TaskDirNode),
+    NamespaceDirNode(NamespaceDirNode),
 }
 
 impl DirDataNode {
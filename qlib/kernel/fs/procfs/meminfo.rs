@@ -15,6 +15,7 @@
 use crate::qlib::mutex::*;
 use alloc::string::ToString;
 use alloc::sync::Arc;
+use core::sync::atomic::Ordering;
 
 use super::super::attr::*;
 use super::super::dirent::*;
@@ -26,6 +27,7 @@ use super::super::mount::*;
 use super::super::super::super::auth::*;
 use super::super::super::super::common::*;
 use super::super::super::super::linux_def::*;
+use super::super::super::super::pagetable::ANON_HUGE_PAGES;
 use super::super::super::task::*;
 use super::super::super::Kernel::HostSpace;
 use super::super::fsutil::file::readonly_file::*;
@@ -80,6 +82,10 @@ impl ReadonlyFileNodeTrait for MeminfoFileNode {
         s += &format!("AnonPages:      {:>8} kB\n", info.totalram / 100 / 1024);
         s += &format!("Mapped:         {:>8} kB\n", info.totalram / 100 / 1024);
         s += &format!("Shmem:                 0 kB\n");
+        s += &format!(
+            "AnonHugePages:  {:>8} kB\n",
+            ANON_HUGE_PAGES.load(Ordering::Relaxed) * MemoryDef::HUGE_PAGE_SIZE / 1024
+        );
 
         // it always change 0 to 2, that's weird
         //s += &format!("Shmem:          {} kB\n", 0);
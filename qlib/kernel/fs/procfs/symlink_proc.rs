@@ -37,8 +37,11 @@ use super::super::ramfs::symlink::*;
 use super::inode::*;
 
 use crate::qlib::kernel::fs::procfs::mounts::MountsNode;
+use crate::qlib::kernel::fs::procfs::task::cwd::CwdNode;
+use crate::qlib::kernel::fs::procfs::task::cwd::RootNode;
 use crate::qlib::kernel::fs::procfs::task::exe::ExeNode;
 use crate::qlib::kernel::fs::procfs::task::fds::FdNode;
+use crate::qlib::kernel::fs::procfs::task::namespace_symlink::NamespaceNode;
 use crate::qlib::kernel::fs::procfs::proc::ProcessSelfNode;
 use crate::qlib::kernel::fs::procfs::proc::ThreadSelfNode;
 
@@ -66,6 +69,9 @@ pub enum ReadLinkNode {
     FdNode(FdNode),
     ThreadSelfNode(ThreadSelfNode),
     ProcessSelfNode(ProcessSelfNode),
+    NamespaceNode(NamespaceNode),
+    CwdNode(CwdNode),
+    RootNode(RootNode),
 }
 
 #[derive(Clone)]
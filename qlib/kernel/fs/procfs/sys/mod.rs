@@ -14,4 +14,6 @@
 
 pub mod sys;
 pub mod vm;
+pub mod kernel;
 pub mod net;
+pub mod sysctl;
@@ -30,6 +30,7 @@ use super::super::super::super::mount::*;
 use super::super::super::super::ramfs::dir::*;
 use super::super::super::dir_proc::*;
 use super::super::super::inode::*;
+use super::super::sysctl::*;
 use super::ipv4::*;
 
 // ProcSysDirNode represents a /proc/sys directory.
@@ -56,6 +57,7 @@ impl DirDataNodeTrait for SysNetDirNode {
 pub fn NewNet(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     let mut contents = BTreeMap::new();
     contents.insert("ipv4".to_string(), NewIpv4(task, msrc));
+    contents.insert("core".to_string(), NewSysctlDir(task, msrc, "net.core"));
 
     let netDir = DirNode {
         dir: Dir::New(
@@ -32,6 +32,8 @@ use super::super::dir_proc::*;
 use super::super::inode::*;
 use super::vm::vm::*;
 use super::net::net::*;
+use super::kernel::kernel::*;
+use super::sysctl::*;
 
 #[derive(Clone)]
 // ProcSysDirNode represents a /proc/sys directory.
@@ -56,8 +58,10 @@ impl DirDataNodeTrait for ProcSysDirNode {
 
 pub fn NewSys(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     let mut contents = BTreeMap::new();
+    contents.insert("kernel".to_string(), NewKernel(task, msrc));
     contents.insert("vm".to_string(), NewVm(task, msrc));
     contents.insert("net".to_string(), NewNet(task, msrc));
+    contents.insert("fs".to_string(), NewSysctlDir(task, msrc, "fs"));
 
     let taskDir = DirNode {
         dir: Dir::New(
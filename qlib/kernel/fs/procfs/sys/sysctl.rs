@@ -0,0 +1,321 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use super::super::super::super::super::auth::*;
+use super::super::super::super::super::common::*;
+use super::super::super::super::super::linux_def::*;
+use super::super::super::super::kernel::sysctl::*;
+use super::super::super::super::kernel::waiter::*;
+use super::super::super::super::task::*;
+use super::super::super::attr::*;
+use super::super::super::dentry::*;
+use super::super::super::dirent::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::host::hostinodeop::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::super::ramfs::dir::*;
+use super::super::dir_proc::*;
+use super::super::inode::*;
+
+// SysctlDirNode represents a /proc/sys directory holding sysctls, such as
+// /proc/sys/fs.
+#[derive(Clone)]
+pub struct SysctlDirNode {}
+
+impl DirDataNodeTrait for SysctlDirNode {
+    fn Lookup(&self, d: &Dir, task: &Task, dir: &Inode, name: &str) -> Result<Dirent> {
+        return d.Lookup(task, dir, name);
+    }
+
+    fn GetFile(
+        &self,
+        d: &Dir,
+        task: &Task,
+        dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        return d.GetFile(task, dir, dirent, flags);
+    }
+}
+
+// NewSysctlContents returns the files of the sysctls of the dotted directory
+// dir, e.g. "net.core", and its subdirectories holding sysctls.
+pub fn NewSysctlContents(
+    task: &Task,
+    msrc: &Arc<QMutex<MountSource>>,
+    dir: &str,
+) -> BTreeMap<String, Inode> {
+    let mut contents = BTreeMap::new();
+    let prefix = dir.to_string() + ".";
+    for sysctl in SYSCTLS {
+        let (parent, name) = sysctl.Path();
+        if parent == dir {
+            contents.insert(name.to_string(), NewSysctl(task, msrc, sysctl));
+            continue;
+        }
+
+        if !parent.starts_with(&prefix) {
+            continue;
+        }
+
+        let subdir = match parent[prefix.len()..].find('.') {
+            None => &parent[prefix.len()..],
+            Some(idx) => &parent[prefix.len()..prefix.len() + idx],
+        };
+        if !contents.contains_key(subdir) {
+            let inode = NewSysctlDir(task, msrc, &(prefix.clone() + subdir));
+            contents.insert(subdir.to_string(), inode);
+        }
+    }
+
+    return contents;
+}
+
+// NewSysctlDir returns the /proc/sys directory of the dotted name dir.
+pub fn NewSysctlDir(task: &Task, msrc: &Arc<QMutex<MountSource>>, dir: &str) -> Inode {
+    let contents = NewSysctlContents(task, msrc, dir);
+
+    let sysctlDir = DirNode {
+        dir: Dir::New(
+            task,
+            contents,
+            &ROOT_OWNER,
+            &FilePermissions::FromMode(FileMode(0o0555)),
+        ),
+        data: SysctlDirNode {}.into(),
+    };
+
+    return NewProcInode(sysctlDir.into(), msrc, InodeType::SpecialDirectory, None);
+}
+
+// NewSysctl returns the /proc/sys file of a sysctl.
+pub fn NewSysctl(task: &Task, msrc: &Arc<QMutex<MountSource>>, sysctl: &'static Sysctl) -> Inode {
+    let mode = if sysctl.Writable() { 0o644 } else { 0o444 };
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(mode)),
+        FSMagic::PROC_SUPER_MAGIC,
+        false,
+        SysctlSimpleFileTrait { sysctl: sysctl }.into(),
+    );
+
+    return NewProcInode(v.into(), msrc, InodeType::SpecialFile, None);
+}
+
+pub struct SysctlSimpleFileTrait {
+    pub sysctl: &'static Sysctl,
+}
+
+impl SimpleFileTrait for SysctlSimpleFileTrait {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = SysctlFileOperations {
+            sysctl: self.sysctl,
+        };
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
+
+#[derive(Clone)]
+pub struct SysctlFileOperations {
+    pub sysctl: &'static Sysctl,
+}
+
+impl Waitable for SysctlFileOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        return mask;
+    }
+
+    fn EventRegister(&self, _task: &Task, _e: &WaitEntry, _mask: EventMask) {}
+
+    fn EventUnregister(&self, _task: &Task, _e: &WaitEntry) {}
+}
+
+impl SpliceOperations for SysctlFileOperations {}
+
+impl FileOperations for SysctlFileOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::SysctlFileOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return true;
+    }
+
+    fn Seek(&self, task: &Task, f: &File, whence: i32, current: i64, offset: i64) -> Result<i64> {
+        return SeekWithDirCursor(task, f, whence, current, offset, None);
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let ns = SysctlNamespaces::FromTask(task);
+        let buf = self.sysctl.Get(&ns) + "\n";
+        if offset as usize >= buf.len() {
+            return Ok(0);
+        }
+
+        let n = task.CopyDataOutToIovs(&buf.as_bytes()[offset as usize..], dsts, true)?;
+        return Ok(n as i64);
+    }
+
+    fn WriteAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        srcs: &[IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        if !self.sysctl.Writable() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let size = IoVec::NumBytes(srcs);
+        if size == 0 {
+            return Ok(0);
+        }
+
+        let mut buf: Vec<u8> = vec![0; size];
+        let n = task.CopyDataInFromIovs(&mut buf, srcs, true)?;
+        buf.truncate(n);
+
+        let ns = SysctlNamespaces::FromTask(task);
+        if !self.sysctl.IsString() {
+            // As proc_dointvec() does for sysctl_writes_strict, a write which
+            // doesn't start at the beginning of the file is ignored.
+            if offset != 0 {
+                return Ok(n as i64);
+            }
+
+            if let Some(end) = buf.iter().position(|c| *c == 0) {
+                buf.truncate(end);
+            }
+
+            self.sysctl.Set(&ns, &String::from_utf8_lossy(&buf))?;
+            return Ok(n as i64);
+        }
+
+        // As proc_dostring() does for sysctl_writes_strict, a write continues
+        // the value at the file offset, and one past its end is ignored. The
+        // value ends at the first newline or NUL, and is truncated to fit.
+        let mut val = self.sysctl.Get(&ns).into_bytes();
+        if offset as usize > val.len() {
+            return Ok(n as i64);
+        }
+
+        val.truncate(offset as usize);
+        for c in &buf {
+            if *c == b'\n' || *c == 0 || val.len() >= self.sysctl.maxLen - 1 {
+                break;
+            }
+            val.push(*c);
+        }
+
+        self.sysctl.Set(&ns, &String::from_utf8_lossy(&val))?;
+        return Ok(n as i64);
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Ok(());
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for SysctlFileOperations {}
@@ -0,0 +1,85 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::super::super::super::auth::*;
+use super::super::super::super::super::common::*;
+use super::super::super::super::super::linux_def::*;
+use super::super::super::super::task::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
+use super::super::super::cgroupfs::fs::CONTROLLERS;
+use super::super::super::dirent::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::inode::*;
+
+// NewCgroup returns /proc/[pid]/cgroup, the cgroups the process belongs to.
+pub fn NewCgroup(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let io = CgroupData {};
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o444)),
+        FSMagic::PROC_SUPER_MAGIC,
+        false,
+        io.into(),
+    );
+    return NewProcInode(
+        v.into(),
+        msrc,
+        InodeType::SpecialFile,
+        Some(thread.clone()),
+    );
+}
+
+pub struct CgroupData {}
+
+impl CgroupData {
+    // GenSnapshot lists one cgroup v1 hierarchy for each controller followed
+    // by the cgroup2 hierarchy, in the format of cgroups(7). The cgroup
+    // filesystems only have a root cgroup, the limits of the container of the
+    // process are enforced by the sandbox, so the process is always in "/".
+    pub fn GenSnapshot(&self, _task: &Task) -> Vec<u8> {
+        let mut ret = "".to_string();
+        for (i, c) in CONTROLLERS.iter().enumerate().rev() {
+            ret += &format!("{}:{}:/\n", i + 1, c);
+        }
+        ret += "0::/\n";
+
+        return ret.as_bytes().to_vec();
+    }
+}
+
+impl SimpleFileTrait for CgroupData {
+    fn GetFile(
+        &self,
+        task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
@@ -13,32 +13,44 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
-use alloc::string::ToString;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
 
-use super::super::super::super::super::auth::*;
 use super::super::super::super::super::common::*;
 use super::super::super::super::super::linux_def::*;
+use super::super::super::super::loader::loader::TASK_COMM_LEN;
+use super::super::super::super::kernel::waiter::*;
 use super::super::super::super::task::*;
 use super::super::super::super::threadmgr::thread::*;
 use super::super::super::attr::*;
+use super::super::super::dentry::*;
 use super::super::super::dirent::*;
 use super::super::super::file::*;
 use super::super::super::flags::*;
-use super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::fsutil::file::*;
 use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::host::hostinodeop::*;
 use super::super::super::inode::*;
 use super::super::super::mount::*;
 use super::super::inode::*;
 
+// NewComm returns /proc/[pid]/comm, the name of the task. The threads of a
+// process can change their names through it as with prctl(PR_SET_NAME).
 pub fn NewComm(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
-    let v = NewCommSimpleFileInode(
+    let v = SimpleFileInode::New(
         task,
-        thread,
         &ROOT_OWNER,
-        &FilePermissions::FromMode(FileMode(0o400)),
+        &FilePermissions::FromMode(FileMode(0o644)),
         FSMagic::PROC_SUPER_MAGIC,
+        false,
+        CommSimpleFileTrait {
+            thread: thread.clone(),
+        }
+        .into(),
     );
+
     return NewProcInode(
         v.into(),
         msrc,
@@ -47,25 +59,6 @@ pub fn NewComm(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) ->
     );
 }
 
-pub fn NewCommSimpleFileInode(
-    task: &Task,
-    thread: &Thread,
-    owner: &FileOwner,
-    perms: &FilePermissions,
-    typ: u64,
-) -> SimpleFileInode {
-    return SimpleFileInode::New(
-        task,
-        owner,
-        perms,
-        typ,
-        false,
-        CommSimpleFileTrait {
-            thread: thread.clone(),
-        }.into(),
-    );
-}
-
 pub struct CommSimpleFileTrait {
     pub thread: Thread,
 }
@@ -78,28 +71,58 @@ impl SimpleFileTrait for CommSimpleFileTrait {
         dirent: &Dirent,
         flags: FileFlags,
     ) -> Result<File> {
-        let fops = NewCommReadonlyFileOperations(&self.thread);
+        let fops = CommFileOperations {
+            thread: self.thread.clone(),
+        };
         let file = File::New(dirent, &flags, fops.into());
         return Ok(file);
     }
 }
 
-pub fn NewCommReadonlyFileOperations(
-    thread: &Thread,
-) -> ReadonlyFileOperations {
-    return ReadonlyFileOperations {
-        node: CommReadonlyFileNode {
-            thread: thread.clone(),
-        }.into(),
-    };
-}
-
 #[derive(Clone)]
-pub struct CommReadonlyFileNode {
+pub struct CommFileOperations {
     pub thread: Thread,
 }
 
-impl ReadonlyFileNodeTrait for CommReadonlyFileNode {
+impl Waitable for CommFileOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        return mask;
+    }
+
+    fn EventRegister(&self, _task: &Task, _e: &WaitEntry, _mask: EventMask) {}
+
+    fn EventUnregister(&self, _task: &Task, _e: &WaitEntry) {}
+}
+
+impl SpliceOperations for CommFileOperations {}
+
+impl FileOperations for CommFileOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::CommFileOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return true;
+    }
+
+    fn Seek(&self, task: &Task, f: &File, whence: i32, current: i64, offset: i64) -> Result<i64> {
+        return SeekWithDirCursor(task, f, whence, current, offset, None);
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
     fn ReadAt(
         &self,
         task: &Task,
@@ -112,14 +135,85 @@ impl ReadonlyFileNodeTrait for CommReadonlyFileNode {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
-        let buf = self.thread.lock().name.to_string() + "\n";
-        //info!("CommReadonlyFileNode buf is {}", &buf);
-        if offset as usize > buf.len() {
+        let buf = self.thread.Name() + "\n";
+        if offset as usize >= buf.len() {
             return Ok(0);
         }
 
         let n = task.CopyDataOutToIovs(&buf.as_bytes()[offset as usize..], dsts, true)?;
-
         return Ok(n as i64);
     }
+
+    fn WriteAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        // As in Linux, only the threads of the same process can be renamed.
+        if task.Thread().ThreadGroup() != self.thread.ThreadGroup() {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let size = IoVec::NumBytes(srcs);
+        let mut buf: Vec<u8> = vec![0; core::cmp::min(size, TASK_COMM_LEN - 1)];
+        let n = task.CopyDataInFromIovs(&mut buf, srcs, true)?;
+        buf.truncate(n);
+
+        // The name ends at the first newline or NUL, if any.
+        if let Some(end) = buf.iter().position(|&c| c == b'\n' || c == 0) {
+            buf.truncate(end);
+        }
+
+        let name = String::from_utf8_lossy(&buf);
+        self.thread.SetName(&name);
+        return Ok(size as i64);
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Ok(());
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
 }
+
+impl SockOperations for CommFileOperations {}
@@ -0,0 +1,82 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use super::super::super::super::super::common::*;
+use super::super::super::super::task::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::dirent::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::super::ramfs::symlink::*;
+use super::super::symlink_proc::*;
+
+// CwdNode is /proc/[pid]/cwd, a symlink to the working directory of the task.
+#[derive(Clone)]
+pub struct CwdNode {
+    pub thread: Thread,
+}
+
+impl ReadLinkNodeTrait for CwdNode {
+    fn ReadLink(&self, _link: &Symlink, task: &Task, _dir: &Inode) -> Result<String> {
+        let cwd = self.thread.lock().fsc.WorkDirectory();
+
+        let root = task.Root();
+        let (name, _) = cwd.FullName(&root);
+        return Ok(name);
+    }
+
+    fn GetLink(&self, _link: &Symlink, _task: &Task, _dir: &Inode) -> Result<Dirent> {
+        return Ok(self.thread.lock().fsc.WorkDirectory());
+    }
+}
+
+pub fn NewCwd(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let node = CwdNode {
+        thread: thread.clone(),
+    };
+
+    return SymlinkNode::New(task, msrc, node.into(), Some(thread.clone()));
+}
+
+// RootNode is /proc/[pid]/root, a symlink to the root directory of the task.
+#[derive(Clone)]
+pub struct RootNode {
+    pub thread: Thread,
+}
+
+impl ReadLinkNodeTrait for RootNode {
+    fn ReadLink(&self, _link: &Symlink, task: &Task, _dir: &Inode) -> Result<String> {
+        let taskRoot = self.thread.lock().fsc.RootDirectory();
+
+        let root = task.Root();
+        let (name, _) = taskRoot.FullName(&root);
+        return Ok(name);
+    }
+
+    fn GetLink(&self, _link: &Symlink, _task: &Task, _dir: &Inode) -> Result<Dirent> {
+        return Ok(self.thread.lock().fsc.RootDirectory());
+    }
+}
+
+pub fn NewRoot(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let node = RootNode {
+        thread: thread.clone(),
+    };
+
+    return SymlinkNode::New(task, msrc, node.into(), Some(thread.clone()));
+}
@@ -0,0 +1,259 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use super::super::super::super::super::auth::*;
+use super::super::super::super::super::auth::id::*;
+use super::super::super::super::super::common::*;
+use super::super::super::super::super::linux_def::*;
+use super::super::super::super::kernel::waiter::*;
+use super::super::super::super::task::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
+use super::super::super::dentry::*;
+use super::super::super::dirent::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::host::hostinodeop::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::inode::*;
+
+// NewLoginUID returns /proc/[pid]/loginuid, the audit login uid of the
+// process.
+pub fn NewLoginUID(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o644)),
+        FSMagic::PROC_SUPER_MAGIC,
+        false,
+        LoginUIDSimpleFileTrait {
+            thread: thread.clone(),
+        }
+        .into(),
+    );
+
+    return NewProcInode(
+        v.into(),
+        msrc,
+        InodeType::SpecialFile,
+        Some(thread.clone()),
+    );
+}
+
+pub struct LoginUIDSimpleFileTrait {
+    pub thread: Thread,
+}
+
+impl SimpleFileTrait for LoginUIDSimpleFileTrait {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = LoginUIDFileOperations {
+            thread: self.thread.clone(),
+        };
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
+
+#[derive(Clone)]
+pub struct LoginUIDFileOperations {
+    pub thread: Thread,
+}
+
+impl Waitable for LoginUIDFileOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        return mask;
+    }
+
+    fn EventRegister(&self, _task: &Task, _e: &WaitEntry, _mask: EventMask) {}
+
+    fn EventUnregister(&self, _task: &Task, _e: &WaitEntry) {}
+}
+
+impl SpliceOperations for LoginUIDFileOperations {}
+
+impl FileOperations for LoginUIDFileOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::LoginUIDFileOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return true;
+    }
+
+    fn Seek(&self, task: &Task, f: &File, whence: i32, current: i64, offset: i64) -> Result<i64> {
+        return SeekWithDirCursor(task, f, whence, current, offset, None);
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        if offset < 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let loginuid = self.thread.ThreadGroup().lock().loginuid;
+        let uid = if loginuid.Ok() {
+            loginuid.In(&task.Thread().UserNamespace()).OrOverflow().0
+        } else {
+            NO_ID
+        };
+
+        let buf = uid.to_string() + "\n";
+        if offset as usize >= buf.len() {
+            return Ok(0);
+        }
+
+        let n = task.CopyDataOutToIovs(&buf.as_bytes()[offset as usize..], dsts, true)?;
+        return Ok(n as i64);
+    }
+
+    fn WriteAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        srcs: &[IoVec],
+        offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        // As in Linux, a process can only set its own loginuid, in a single
+        // write.
+        if task.Thread().ThreadGroup() != self.thread.ThreadGroup() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        if offset != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let size = IoVec::NumBytes(srcs);
+        if size == 0 {
+            return Ok(0);
+        }
+
+        if size >= MemoryDef::PAGE_SIZE as usize {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let mut buf: Vec<u8> = vec![0; size];
+        let n = task.CopyDataInFromIovs(&mut buf, srcs, true)?;
+        buf.truncate(n);
+
+        let s = String::from_utf8_lossy(&buf);
+        let uid = match s.trim().parse::<u32>() {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(v) => v,
+        };
+
+        let loginuid = if uid == NO_ID {
+            KUID(NO_ID)
+        } else {
+            let kuid = task.Thread().UserNamespace().MapToKUID(UID(uid));
+            if !kuid.Ok() {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+            kuid
+        };
+
+        // Once set, the loginuid can only be changed with CAP_AUDIT_CONTROL.
+        let tg = self.thread.ThreadGroup();
+        if tg.lock().loginuid.Ok()
+            && !task.Thread().HasCapability(Capability::CAP_AUDIT_CONTROL)
+        {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        tg.lock().loginuid = loginuid;
+        return Ok(size as i64);
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Ok(());
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for LoginUIDFileOperations {}
@@ -13,17 +13,28 @@
 // limitations under the License.
 
 pub mod auxvec;
+pub mod cgroup;
 pub mod comm;
+pub mod cwd;
 pub mod exe;
 pub mod exec_args;
 pub mod fds;
 pub mod io;
+pub mod limits;
+pub mod loginuid;
 pub mod maps;
 pub mod mounts;
+pub mod namespace_symlink;
+pub mod oom_score;
+pub mod oom_score_adj;
+pub mod personality;
+pub mod sched;
+pub mod setgroups;
+pub mod smaps;
 pub mod stat;
 pub mod statm;
 pub mod status;
 pub mod subtasks;
 pub mod task;
 pub mod uid_pid_map;
-//pub mod namespace_symlink;
+pub mod wchan;
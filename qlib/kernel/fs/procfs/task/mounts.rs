@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
+use alloc::string::String;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -25,6 +26,7 @@ use super::super::super::super::threadmgr::thread::*;
 use super::super::super::attr::*;
 use super::super::super::dirent::*;
 use super::super::super::file::*;
+use super::super::super::filesystems::*;
 use super::super::super::flags::*;
 use super::super::super::fsutil::file::readonly_file::*;
 use super::super::super::fsutil::inode::simple_file_inode::*;
@@ -56,6 +58,34 @@ pub fn ForEachMount(thread: &Thread, mountns: MountNs, f: &mut FnMut(&str, &Arc<
     }
 }
 
+// MountOptionsString formats the per-mount options shown in
+// /proc/[pid]/mounts and field (6) of /proc/[pid]/mountinfo.
+pub fn MountOptionsString(flags: &MountSourceFlags) -> String {
+    let mut opts = if flags.ReadOnly {
+        "ro".to_string()
+    } else {
+        "rw".to_string()
+    };
+
+    if flags.NoSuid {
+        opts += ",nosuid";
+    }
+
+    if flags.NoDev {
+        opts += ",nodev";
+    }
+
+    if flags.NoExec {
+        opts += ",noexec";
+    }
+
+    if flags.NoAtime {
+        opts += ",noatime";
+    }
+
+    return opts;
+}
+
 pub fn NewMountInfoFile(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
     let v = NewMountInfoFileSimpleFileInode(
         task,
@@ -125,33 +155,19 @@ impl MountInfoFile {
 
                 // (4) Root: the pathname of the directory in the filesystem
                 // which forms the root of this mount.
-                //
-                // NOTE(b/78135857): This will always be "/" until we implement
-                // bind mounts.
-                ret += "/ ";
+                ret += &format!("{} ", m.lock().BindRoot());
 
                 // (5) Mount point (relative to process root).
                 ret += &format!("{} ", mountPath);
 
                 // (6) Mount options.
                 let mountSource = mroot.Inode().lock().MountSource.clone();
-                let flags = mountSource.lock().Flags;
-                let mut opts = "rw".to_string();
-                if flags.ReadOnly {
-                    opts = "ro".to_string();
-                }
-
-                if flags.NoAtime {
-                    opts += ",noatime";
-                }
-
-                if flags.NoExec {
-                    opts += ",noexec";
-                }
-
-                ret += &format!("{} ", opts);
+                let flags = m.lock().flags;
+                ret += &format!("{} ", MountOptionsString(&flags));
 
                 // (7) Optional fields: zero or more fields of the form "tag[:value]".
+                ret += &m.lock().PropagationString();
+
                 // (8) Separator: the end of the optional fields is marked by a single hyphen.
                 ret += "- ";
 
@@ -163,7 +179,7 @@ impl MountInfoFile {
 
                 // (11) Superblock options. Only "ro/rw" is supported for now,
                 // and is the same as the filesystem option.
-                ret += &format!("{}\n", opts);
+                ret += &format!("{}\n", MountOptionsString(&flags));
             },
         );
 
@@ -230,16 +246,14 @@ impl MountsFile {
             &mut |mountPath: &str, m: &Arc<QMutex<Mount>>| {
                 let mroot = m.lock().Root();
                 let mountSource = mroot.Inode().lock().MountSource.clone();
-                let flags = mountSource.lock().Flags;
-
-                let opts = if flags.ReadOnly { "ro" } else { "rw" };
+                let flags = m.lock().flags;
 
                 ret += &format!(
                     "{} {} {} {} {} {}\n",
                     "none",
                     mountPath,
                     mountSource.lock().FileSystemType,
-                    opts,
+                    MountOptionsString(&flags),
                     0,
                     0
                 );
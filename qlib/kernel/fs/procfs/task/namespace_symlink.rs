@@ -12,38 +12,177 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::sync::Arc;
-use alloc::string::String;
 use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
 
+use super::super::super::super::super::auth::*;
 use super::super::super::super::super::common::*;
+use super::super::super::super::super::device::*;
 use super::super::super::super::super::linux_def::*;
+use super::super::super::super::kernel::nsfs::*;
 use super::super::super::super::task::*;
-use super::super::super::ramfs::symlink::*;
+use super::super::super::super::threadmgr::task_exit::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
 use super::super::super::dirent::*;
-use super::super::super::mount::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
 use super::super::super::inode::*;
-use super::super::super::super::threadmgr::thread::*;
+use super::super::super::mount::*;
+use super::super::super::ramfs::dir::*;
+use super::super::super::ramfs::symlink::*;
+use super::super::dir_proc::*;
+use super::super::inode::*;
 use super::super::symlink_proc::*;
 
+// NamespaceFile is the file a /proc/[pid]/ns/* symlink resolves to. An open
+// file for it can be passed to setns(2).
+pub struct NamespaceFile {
+    pub ns: Namespace,
+}
+
+impl SimpleFileTrait for NamespaceFile {
+    fn GetFile(
+        &self,
+        _task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        return Ok(File::New(dirent, &flags, NoReadWriteFile {}.into()));
+    }
+}
+
+// NamespaceNode is a /proc/[pid]/ns/* symlink. The namespace is looked up each
+// time the link is read, since the task may change its namespaces with
+// unshare(2) or setns(2).
+#[derive(Clone)]
 pub struct NamespaceNode {
     pub thread: Thread,
+    pub name: &'static str,
+}
+
+impl NamespaceNode {
+    pub fn Namespace(&self) -> Result<Namespace> {
+        if self.thread.lock().exitState == TaskExitState::TaskExitDead {
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+
+        let ns = match self.name {
+            "ipc" => Namespace::IPC(self.thread.lock().ipcns.clone()),
+            "mnt" => Namespace::Mount(self.thread.lock().mountNS.clone()),
+            "pid" => Namespace::PID(self.thread.PIDNamespace()),
+            "pid_for_children" => {
+                let pidns = self.thread.lock().childPIDNamespace.clone();
+                match pidns {
+                    None => Namespace::PID(self.thread.PIDNamespace()),
+                    Some(pidns) => Namespace::PID(pidns),
+                }
+            }
+            "user" => Namespace::User(self.thread.Credentials().lock().UserNamespace.clone()),
+            "uts" => Namespace::UTS(self.thread.lock().utsns.clone()),
+            _ => return Err(Error::SysError(SysErr::ENOENT)),
+        };
+
+        return Ok(ns);
+    }
+}
+
+impl ReadLinkNodeTrait for NamespaceNode {
+    fn ReadLink(&self, _link: &Symlink, _task: &Task, _dir: &Inode) -> Result<String> {
+        let ns = self.Namespace()?;
+        return Ok(format!("{}:[{}]", ns.Name(), ns.Inode()));
+    }
+
+    fn GetLink(&self, _link: &Symlink, task: &Task, _dir: &Inode) -> Result<Dirent> {
+        let ns = self.Namespace()?;
+        let name = format!("{}:[{}]", ns.Name(), ns.Inode());
+        let inodeId = ns.Inode();
+
+        let iops = SimpleFileInode::New(
+            task,
+            &ROOT_OWNER,
+            &FilePermissions::FromMode(FileMode(0o444)),
+            FSMagic::PROC_SUPER_MAGIC,
+            false,
+            NamespaceFile { ns: ns }.into(),
+        );
+
+        let deviceId = PROC_DEVICE.lock().id.DeviceID();
+        let attr = StableAttr {
+            Type: InodeType::SpecialFile,
+            DeviceId: deviceId,
+            InodeId: inodeId,
+            BlockSize: 4096,
+            DeviceFileMajor: 0,
+            DeviceFileMinor: 0,
+        };
+
+        let ms = Arc::new(QMutex::new(MountSource::NewPseudoMountSource()));
+        let inode = Inode::New(iops.into(), &ms, &attr);
+        return Ok(Dirent::New(&inode, &name));
+    }
+}
+
+// NamespaceDirNode represents the /proc/[pid]/ns directory.
+#[derive(Clone)]
+pub struct NamespaceDirNode {
+    pub thread: Thread,
 }
 
-impl ReadLinkNode for NamespaceNode {
-    fn ReadLink(&self, link: &Symlink, task: &Task, dir: &Inode) -> Result<String> {
-        return link.ReadLink(task, dir)
+impl DirDataNodeTrait for NamespaceDirNode {
+    fn Lookup(&self, d: &Dir, task: &Task, dir: &Inode, name: &str) -> Result<Dirent> {
+        return d.Lookup(task, dir, name);
     }
 
-    fn GetLink(&self, link: &Symlink, task: &Task, dir: &Inode) -> Result<Dirent> {
-        return link.GetLink(task, dir);
+    fn GetFile(
+        &self,
+        d: &Dir,
+        task: &Task,
+        dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        return d.GetFile(task, dir, dirent, flags);
     }
 }
 
-pub fn NewExe(task: &Task, msrc: &Arc<QMutex<MountSource>>) -> Inode {
-    let node = ExeNode {
-        thread: task.Thread(),
+pub fn NewNamespaceDir(task: &Task, thread: &Thread, msrc: &Arc<QMutex<MountSource>>) -> Inode {
+    let mut contents = BTreeMap::new();
+    for name in ["ipc", "mnt", "pid", "pid_for_children", "user", "uts"].iter() {
+        let node = NamespaceNode {
+            thread: thread.clone(),
+            name: *name,
+        };
+
+        contents.insert(
+            name.to_string(),
+            SymlinkNode::New(task, msrc, node.into(), Some(thread.clone())),
+        );
+    }
+
+    let nsDir = DirNode {
+        dir: Dir::New(
+            task,
+            contents,
+            &ROOT_OWNER,
+            &FilePermissions::FromMode(FileMode(0o0511)),
+        ),
+        data: NamespaceDirNode {
+            thread: thread.clone(),
+        }
+        .into(),
     };
 
-    return SymlinkNode::New(task, msrc, node)
+    return NewProcInode(
+        nsDir.into(),
+        msrc,
+        InodeType::SpecialDirectory,
+        Some(thread.clone()),
+    );
 }
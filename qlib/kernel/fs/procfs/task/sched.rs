@@ -0,0 +1,137 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::super::super::super::auth::*;
+use super::super::super::super::super::common::*;
+use super::super::super::super::super::linux_def::*;
+use super::super::super::super::task::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
+use super::super::super::dirent::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::inode::*;
+
+// NewSched returns /proc/[pid]/sched, the scheduler statistics of the task,
+// or /proc/[pid]/schedstat if stat is set.
+pub fn NewSched(
+    task: &Task,
+    thread: &Thread,
+    msrc: &Arc<QMutex<MountSource>>,
+    stat: bool,
+) -> Inode {
+    let io = SchedData {
+        thread: thread.clone(),
+        stat: stat,
+    };
+    let v = SimpleFileInode::New(
+        task,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o444)),
+        FSMagic::PROC_SUPER_MAGIC,
+        false,
+        io.into(),
+    );
+    return NewProcInode(
+        v.into(),
+        msrc,
+        InodeType::SpecialFile,
+        Some(thread.clone()),
+    );
+}
+
+pub struct SchedData {
+    thread: Thread,
+    stat: bool,
+}
+
+impl SchedData {
+    // GenSchedstat returns the content of /proc/[pid]/schedstat: the time
+    // spent on the cpu in ns, the time spent waiting on a runqueue in ns and
+    // the number of timeslices run on the cpu. The vcpu scheduler doesn't
+    // track the time tasks wait in its queues, so it is always 0.
+    fn GenSchedstat(&self) -> Vec<u8> {
+        let cpu = self.thread.CPUStats();
+        let switches = self.thread.lock().TaskSchedInfo().YieldCount;
+
+        let ret = format!("{} {} {}\n", cpu.UserTime + cpu.SysTime, 0, switches);
+        return ret.as_bytes().to_vec();
+    }
+
+    pub fn GenSnapshot(&self, task: &Task) -> Vec<u8> {
+        if self.stat {
+            return self.GenSchedstat();
+        }
+
+        let pidns = task.Thread().PIDNamespace();
+        let cpu = self.thread.CPUStats();
+        let info = self.thread.lock().TaskSchedInfo();
+        let (vruntime, weight) = self.thread.SchedLoad();
+        let (policy, _, _) = self.thread.SchedPolicy();
+        // The kernel priority, 0 to 99 for the real-time tasks and 100 to 139
+        // for the others.
+        let prio = self.thread.Priority() + 100;
+
+        let ms = |ns: u64| format!("{:>14}.{:06}", ns / 1_000_000, ns % 1_000_000);
+
+        let mut ret = format!(
+            "{} ({}, #threads: {})\n",
+            self.thread.Name(),
+            pidns.IDOfTask(&self.thread),
+            self.thread.ThreadGroup().Count()
+        );
+        ret += &"-".repeat(67);
+        ret += "\n";
+        ret += &format!("{:<45}:{}\n", "se.vruntime", ms(vruntime));
+        ret += &format!(
+            "{:<45}:{}\n",
+            "se.sum_exec_runtime",
+            ms((cpu.UserTime + cpu.SysTime) as u64)
+        );
+        ret += &format!("{:<45}:{:>21}\n", "se.nr_migrations", info.Migrations);
+        ret += &format!("{:<45}:{:>21}\n", "nr_switches", info.YieldCount);
+        ret += &format!(
+            "{:<45}:{:>21}\n",
+            "nr_voluntary_switches", info.YieldCount
+        );
+        ret += &format!("{:<45}:{:>21}\n", "nr_involuntary_switches", 0);
+        ret += &format!("{:<45}:{:>21}\n", "se.load.weight", weight);
+        ret += &format!("{:<45}:{:>21}\n", "policy", policy);
+        ret += &format!("{:<45}:{:>21}\n", "prio", prio);
+
+        return ret.as_bytes().to_vec();
+    }
+}
+
+impl SimpleFileTrait for SchedData {
+    fn GetFile(
+        &self,
+        task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::super::super::super::auth::*;
+use super::super::super::super::super::common::*;
+use super::super::super::super::super::linux_def::*;
+use super::super::super::super::memmgr::mm::*;
+use super::super::super::super::task::*;
+use super::super::super::super::threadmgr::thread::*;
+use super::super::super::attr::*;
+use super::super::super::dirent::*;
+use super::super::super::file::*;
+use super::super::super::flags::*;
+use super::super::super::fsutil::file::readonly_file::*;
+use super::super::super::fsutil::inode::simple_file_inode::*;
+use super::super::super::inode::*;
+use super::super::super::mount::*;
+use super::super::inode::*;
+
+// NewSmaps returns /proc/[pid]/smaps or, if rollup is set,
+// /proc/[pid]/smaps_rollup.
+pub fn NewSmaps(
+    task: &Task,
+    thread: &Thread,
+    msrc: &Arc<QMutex<MountSource>>,
+    rollup: bool,
+) -> Inode {
+    let v = NewSmapsSimpleFileInode(
+        task,
+        thread,
+        rollup,
+        &ROOT_OWNER,
+        &FilePermissions::FromMode(FileMode(0o400)),
+        FSMagic::PROC_SUPER_MAGIC,
+    );
+    return NewProcInode(
+        v.into(),
+        msrc,
+        InodeType::SpecialFile,
+        Some(thread.clone()),
+    );
+}
+
+pub fn NewSmapsSimpleFileInode(
+    task: &Task,
+    thread: &Thread,
+    rollup: bool,
+    owner: &FileOwner,
+    perms: &FilePermissions,
+    typ: u64,
+) -> SimpleFileInode {
+    let io = SmapsData {
+        mm: thread.lock().memoryMgr.clone(),
+        rollup: rollup,
+    };
+    return SimpleFileInode::New(task, owner, perms, typ, false, io.into());
+}
+
+pub struct SmapsData {
+    mm: MemoryManager,
+    rollup: bool,
+}
+
+impl SmapsData {
+    pub fn GenSnapshot(&self, task: &Task) -> Vec<u8> {
+        if self.rollup {
+            return self.mm.GenSmapsRollupSnapshot(task);
+        }
+
+        return self.mm.GenSmapsSnapshot(task);
+    }
+}
+
+impl SimpleFileTrait for SmapsData {
+    fn GetFile(
+        &self,
+        task: &Task,
+        _dir: &Inode,
+        dirent: &Dirent,
+        flags: FileFlags,
+    ) -> Result<File> {
+        let fops = NewSnapshotReadonlyFileOperations(self.GenSnapshot(task));
+        let file = File::New(dirent, &flags, fops.into());
+        return Ok(file);
+    }
+}
@@ -174,7 +174,8 @@ impl TaskStatData {
             Signal(0)
         };
         output += &format!("{} ", terminationSignal.0);
-        output += &format!("0 0 0 " /* processor rt_priority policy */);
+        let (policy, rtPriority, _) = self.t.SchedPolicy();
+        output += &format!("0 {} {} ", rtPriority, policy /* processor rt_priority policy */);
         output += &format!("0 0 0 " /* delayacct_blkio_ticks guest_time cguest_time */);
         output += &format!(
             "0 0 0 0 0 0 0 " /* start_data end_data start_brk arg_start arg_end env_start env_end */
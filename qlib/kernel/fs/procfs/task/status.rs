@@ -93,7 +93,11 @@ impl StatusData {
             }
         };
         ret += &format!("PPid:\t{}\n", ppid);
-        ret += &format!("TracerPid:\t{}\n", 0);
+        let tpid = match self.thread.Tracer() {
+            None => 0,
+            Some(tracer) => self.pidns.IDOfTask(&tracer),
+        };
+        ret += &format!("TracerPid:\t{}\n", tpid);
 
         let fdTbl = self.thread.lock().fdTbl.clone();
         let fds = fdTbl.Count();
@@ -114,7 +118,7 @@ impl StatusData {
         ret += &format!("CapPrm:\t{:016x}\n", creds.lock().PermittedCaps.0);
         ret += &format!("CapEff:\t{:016x}\n", creds.lock().EffectiveCaps.0);
         ret += &format!("CapBnd:\t{:016x}\n", creds.lock().BoundingCaps.0);
-        ret += &format!("Seccomp:\t{}\n", 0);
+        ret += &format!("Seccomp:\t{}\n", self.thread.SeccompMode());
 
         //ret += &format!("Mems_allowed:\t{}\n",
         //                "00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000000,00000001");
@@ -34,17 +34,29 @@ use super::super::dir_proc::*;
 use super::super::inode::*;
 use super::super::proc::*;
 use super::auxvec::*;
+use super::cgroup::*;
 use super::comm::*;
+use super::cwd::*;
 use super::exe::*;
 use super::exec_args::*;
 use super::fds::*;
 use super::io::*;
+use super::limits::*;
+use super::loginuid::*;
 use super::maps::*;
 use super::mounts::*;
+use super::namespace_symlink::*;
+use super::oom_score::*;
+use super::oom_score_adj::*;
+use super::personality::*;
+use super::sched::*;
+use super::setgroups::*;
+use super::smaps::*;
 use super::stat::*;
 use super::statm::*;
 use super::status::*;
 use super::uid_pid_map::*;
+use super::wchan::*;
 
 #[derive(Clone)]
 // taskDir represents a task-level directory.
@@ -80,11 +92,13 @@ impl ProcNode {
     ) -> Inode {
         let mut contents = BTreeMap::new();
         contents.insert("auxv".to_string(), NewAUXVec(task, thread, msrc));
+        contents.insert("cgroup".to_string(), NewCgroup(task, thread, msrc));
         contents.insert(
             "cmdline".to_string(),
             NewExecArg(task, thread, msrc, ExecArgType::CmdlineExecArg),
         );
         contents.insert("comm".to_string(), NewComm(task, thread, msrc));
+        contents.insert("cwd".to_string(), NewCwd(task, thread, msrc));
         contents.insert(
             "environ".to_string(),
             NewExecArg(task, thread, msrc, ExecArgType::EnvironExecArg),
@@ -94,12 +108,36 @@ impl ProcNode {
         contents.insert("fdinfo".to_string(), NewFdInfoDir(task, thread, msrc));
         contents.insert("gid_map".to_string(), NewIdMap(task, thread, msrc, true));
         contents.insert("io".to_string(), NewIO(task, thread, msrc));
+        contents.insert("limits".to_string(), NewLimits(task, thread, msrc));
+        contents.insert("loginuid".to_string(), NewLoginUID(task, thread, msrc));
         contents.insert("maps".to_string(), NewMaps(task, thread, msrc));
         contents.insert(
             "mountinfo".to_string(),
             NewMountInfoFile(task, thread, msrc),
         );
         contents.insert("mounts".to_string(), NewMountsFile(task, thread, msrc));
+        contents.insert("ns".to_string(), NewNamespaceDir(task, thread, msrc));
+        contents.insert("oom_score".to_string(), NewOOMScore(task, thread, msrc));
+        contents.insert(
+            "oom_score_adj".to_string(),
+            NewOOMScoreAdj(task, thread, msrc),
+        );
+        contents.insert(
+            "personality".to_string(),
+            NewPersonality(task, thread, msrc),
+        );
+        contents.insert("root".to_string(), NewRoot(task, thread, msrc));
+        contents.insert("sched".to_string(), NewSched(task, thread, msrc, false));
+        contents.insert(
+            "schedstat".to_string(),
+            NewSched(task, thread, msrc, true),
+        );
+        contents.insert("setgroups".to_string(), NewSetgroups(task, thread, msrc));
+        contents.insert("smaps".to_string(), NewSmaps(task, thread, msrc, false));
+        contents.insert(
+            "smaps_rollup".to_string(),
+            NewSmaps(task, thread, msrc, true),
+        );
         contents.insert(
             "stat".to_string(),
             NewStat(task, thread, showSubtasks, self.lock().pidns.clone(), msrc),
@@ -107,6 +145,7 @@ impl ProcNode {
         contents.insert("statm".to_string(), NewStatm(task, thread, msrc));
         contents.insert("status".to_string(), NewStatus(task, thread, msrc));
         contents.insert("uid_map".to_string(), NewIdMap(task, thread, msrc, false));
+        contents.insert("wchan".to_string(), NewWchan(task, thread, msrc));
 
         if showSubtasks {
             contents.insert("task".to_string(), self.NewSubTasksDir(task, thread, msrc));
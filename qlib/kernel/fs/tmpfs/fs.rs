@@ -21,12 +21,14 @@ use alloc::sync::Arc;
 use super::super::super::super::auth::id::*;
 use super::super::super::super::common::*;
 use super::super::super::super::linux_def::*;
+use super::super::super::super::usage::memory::TotalMemory;
 use super::super::super::task::*;
 use super::super::filesystems::*;
 use super::super::host::fs::*;
 use super::super::inode::*;
 use super::super::mount::*;
 use super::tmpfs_dir::*;
+use super::usage::*;
 
 // Set initial permissions for the root directory.
 pub const MODE_KEY: &str = "mode";
@@ -37,12 +39,66 @@ pub const ROOT_UIDKEY: &str = "uid";
 // GID for the root directory.
 pub const ROOT_GIDKEY: &str = "gid";
 
+// Maximum size of the file data, in bytes or as a percentage of the memory
+// with a % suffix.
+pub const SIZE_KEY: &str = "size";
+
+// Maximum size of the file data in pages.
+pub const NR_BLOCKS_KEY: &str = "nr_blocks";
+
+// Maximum number of inodes.
+pub const NR_INODES_KEY: &str = "nr_inodes";
+
 // Permissions that exceed modeMask will be rejected.
 pub const MODE_MASK: u16 = 0o1777;
 
 // Default permissions are read/write/execute.
 pub const DEFAULT_MODE: u16 = 0o777;
 
+// ParseMemSize parses a number with an optional k, m, g, t, p or e binary
+// suffix.
+//
+// See Linux's lib/cmdline.c:memparse().
+pub fn ParseMemSize(s: &str) -> Result<u64> {
+    let (num, shift) = match s.chars().last() {
+        Some('k') | Some('K') => (&s[..s.len() - 1], 10),
+        Some('m') | Some('M') => (&s[..s.len() - 1], 20),
+        Some('g') | Some('G') => (&s[..s.len() - 1], 30),
+        Some('t') | Some('T') => (&s[..s.len() - 1], 40),
+        Some('p') | Some('P') => (&s[..s.len() - 1], 50),
+        Some('e') | Some('E') => (&s[..s.len() - 1], 60),
+        _ => (s, 0),
+    };
+
+    let n = match num.parse::<u64>() {
+        Ok(v) => v,
+        Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+    };
+
+    return match n.checked_mul(1 << shift) {
+        None => Err(Error::SysError(SysErr::EINVAL)),
+        Some(v) => Ok(v),
+    };
+}
+
+// ParseTmpfsSize parses the value of the size option and returns it in
+// pages.
+//
+// See Linux's mm/shmem.c:shmem_parse_one().
+pub fn ParseTmpfsSize(s: &str) -> Result<u64> {
+    let size = if s.ends_with('%') {
+        let percent = match s[..s.len() - 1].parse::<u64>() {
+            Ok(v) => v,
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+        };
+        TotalMemory(0, 0) / 100 * percent
+    } else {
+        ParseMemSize(s)?
+    };
+
+    return Ok((size + MemoryDef::PAGE_SIZE - 1) / MemoryDef::PAGE_SIZE);
+}
+
 pub struct TmpfsFileSystem {}
 
 impl Filesystem for TmpfsFileSystem {
@@ -123,6 +179,43 @@ impl Filesystem for TmpfsFileSystem {
             }
         }
 
+        // The size limits of the mount. As in Linux, 0 means unlimited.
+        let mut maxBlocks = 0;
+        let mut maxInodes = 0;
+
+        match options.remove(SIZE_KEY) {
+            None => (),
+            Some(size) => match ParseTmpfsSize(&size) {
+                Ok(v) => maxBlocks = v,
+                Err(e) => {
+                    info!("size value not parsable 'size={}'", size);
+                    return Err(e);
+                }
+            },
+        }
+
+        match options.remove(NR_BLOCKS_KEY) {
+            None => (),
+            Some(blocks) => match ParseMemSize(&blocks) {
+                Ok(v) => maxBlocks = v,
+                Err(e) => {
+                    info!("nr_blocks value not parsable 'nr_blocks={}'", blocks);
+                    return Err(e);
+                }
+            },
+        }
+
+        match options.remove(NR_INODES_KEY) {
+            None => (),
+            Some(inodes) => match ParseMemSize(&inodes) {
+                Ok(v) => maxInodes = v,
+                Err(e) => {
+                    info!("nr_inodes value not parsable 'nr_inodes={}'", inodes);
+                    return Err(e);
+                }
+            },
+        }
+
         // Fail if the caller passed us more options than we can parse. They may be
         // expecting us to set something we can't set.
         if options.len() > 0 {
@@ -132,13 +225,16 @@ impl Filesystem for TmpfsFileSystem {
 
         let msrc = MountSource::NewCachingMountSource(self, flags);
 
+        let usage = TmpfsUsage::New(maxBlocks, maxInodes);
+
         let inode = NewTmpfsDir(
             task,
             BTreeMap::new(),
             &owner,
             &perms,
             Arc::new(QMutex::new(msrc)),
-        );
+            &usage,
+        )?;
         return Ok(inode);
     }
 
@@ -18,6 +18,7 @@ pub mod tmpfs_fifo;
 pub mod tmpfs_file;
 pub mod tmpfs_socket;
 pub mod tmpfs_symlink;
+pub mod usage;
 
 use crate::qlib::mutex::*;
 use alloc::sync::Arc;
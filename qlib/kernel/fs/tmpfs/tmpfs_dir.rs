@@ -38,14 +38,7 @@ use super::tmpfs_fifo::*;
 use super::tmpfs_file::*;
 use super::tmpfs_socket::*;
 use super::tmpfs_symlink::*;
-
-pub const TMPFS_FSINFO: FsInfo = FsInfo {
-    Type: FSMagic::TMPFS_MAGIC,
-    TotalBlocks: 0,
-    FreeBlocks: 0,
-    TotalFiles: 0,
-    FreeFiles: 0,
-};
+use super::usage::*;
 
 pub fn TmpfsRename(
     task: &Task,
@@ -83,9 +76,11 @@ pub fn NewTmpfsDir(
     owner: &FileOwner,
     perms: &FilePermissions,
     msrc: Arc<QMutex<MountSource>>,
-) -> Inode {
+    usage: &TmpfsUsage,
+) -> Result<Inode> {
+    let iusage = TmpfsInodeUsage::New(task, usage)?;
     let d = Dir::New(task, contents, owner, perms);
-    let d = TmpfsDir(d);
+    let d = TmpfsDir(d, iusage);
 
     let createOps = d.NewCreateOps();
     d.0.write().CreateOps = createOps;
@@ -101,26 +96,38 @@ pub fn NewTmpfsDir(
         DeviceFileMinor: 0,
     };
 
-    return Inode::New(d.into(), &msrc, &attr);
+    return Ok(Inode::New(d.into(), &msrc, &attr));
 }
 
 #[derive(Clone)]
-pub struct TmpfsDir(pub Dir);
+pub struct TmpfsDir(pub Dir, pub TmpfsInodeUsage);
+
+// MountUsage returns the usage of the tmpfs mount of the directory dir.
+fn MountUsage(dir: &Inode) -> Result<TmpfsUsage> {
+    let iops = dir.lock().InodeOp.clone();
+    match iops.TmpfsDir() {
+        None => return Err(Error::SysError(SysErr::ENOTDIR)),
+        Some(d) => return Ok(d.1.Usage()),
+    }
+}
 
 fn NewDirFn(task: &Task, dir: &Inode, perms: &FilePermissions) -> Result<Inode> {
     let msrc = dir.lock().MountSource.clone();
-    return Ok(NewTmpfsDir(
+    let usage = MountUsage(dir)?;
+    return NewTmpfsDir(
         task,
         BTreeMap::new(),
         &task.FileOwner(),
         perms,
         msrc,
-    ));
+        &usage,
+    );
 }
 
 fn NewSymlinkFn(task: &Task, dir: &Inode, target: &str) -> Result<Inode> {
     let msrc = dir.lock().MountSource.clone();
-    return Ok(NewTmpfsSymlink(task, target, &task.FileOwner(), &msrc));
+    let usage = MountUsage(dir)?;
+    return NewTmpfsSymlink(task, target, &task.FileOwner(), &msrc, &usage);
 }
 
 fn NewSocketFn(
@@ -130,13 +137,8 @@ fn NewSocketFn(
     perms: &FilePermissions,
 ) -> Result<Inode> {
     let msrc = dir.lock().MountSource.clone();
-    return Ok(NewTmpfsSocket(
-        task,
-        socket,
-        &task.FileOwner(),
-        perms,
-        &msrc,
-    ));
+    let usage = MountUsage(dir)?;
+    return NewTmpfsSocket(task, socket, &task.FileOwner(), perms, &msrc, &usage);
 }
 
 fn NewFileFn(task: &Task, dir: &Inode, perms: &FilePermissions) -> Result<Inode> {
@@ -148,14 +150,16 @@ fn NewFileFn(task: &Task, dir: &Inode, perms: &FilePermissions) -> Result<Inode>
     };
 
     let uattr = WithCurrentTime(task, &uattr);
+    let usage = MountUsage(dir)?;
 
-    return NewTmpfsFileInode(task, uattr, &msrc);
+    return NewTmpfsFileInode(task, uattr, &msrc, &usage);
 }
 
 fn NewFifoFn(task: &Task, dir: &Inode, perms: &FilePermissions) -> Result<Inode> {
     let msrc = dir.lock().MountSource.clone();
+    let usage = MountUsage(dir)?;
 
-    return NewTmpfsFifoInode(task, perms, &msrc);
+    return NewTmpfsFifoInode(task, perms, &msrc, &usage);
 }
 
 impl TmpfsDir {
@@ -348,7 +352,7 @@ impl InodeOperations for TmpfsDir {
     }
 
     fn StatFS(&self, _task: &Task) -> Result<FsInfo> {
-        return Ok(TMPFS_FSINFO);
+        return Ok(self.1.FsInfo());
     }
 
     fn Mappable(&self) -> Result<MMappable> {
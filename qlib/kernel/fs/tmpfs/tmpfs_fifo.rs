@@ -35,17 +35,21 @@ use super::super::host::hostinodeop::*;
 use super::super::inode::*;
 use super::super::mount::*;
 use super::tmpfs_dir::*;
+use super::usage::*;
 
 pub fn NewTmpfsFifoInode(
     task: &Task,
     perms: &FilePermissions,
     msrc: &Arc<QMutex<MountSource>>,
+    usage: &TmpfsUsage,
 ) -> Result<Inode> {
+    let iusage = TmpfsInodeUsage::New(task, usage)?;
+
     // First create a pipe.
     let (pipe, _drient) = Pipe::New(task, true, DEFAULT_PIPE_SIZE, MemoryDef::PAGE_SIZE as usize);
 
     let iops = NewPipeInodeOps(task, perms, pipe);
-    let fifo = TmpfsFifoInodeOp(iops);
+    let fifo = TmpfsFifoInodeOp(iops, iusage);
 
     let deviceId = TMPFS_DEVICE.lock().DeviceID();
     let inodeId = TMPFS_DEVICE.lock().NextIno();
@@ -62,7 +66,7 @@ pub fn NewTmpfsFifoInode(
 }
 
 #[derive(Clone)]
-pub struct TmpfsFifoInodeOp(PipeIops);
+pub struct TmpfsFifoInodeOp(PipeIops, TmpfsInodeUsage);
 
 impl InodeOperations for TmpfsFifoInodeOp {
     fn as_any(&self) -> &Any {
@@ -246,7 +250,7 @@ impl InodeOperations for TmpfsFifoInodeOp {
     }
 
     fn StatFS(&self, _task: &Task) -> Result<FsInfo> {
-        return Ok(TMPFS_FSINFO);
+        return Ok(self.1.FsInfo());
     }
 
     fn Mappable(&self) -> Result<MMappable> {
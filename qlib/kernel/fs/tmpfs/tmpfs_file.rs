@@ -35,12 +35,16 @@ use super::super::host::hostinodeop::*;
 use super::super::inode::*;
 use super::super::mount::*;
 use super::tmpfs_dir::*;
+use super::usage::*;
 
 pub fn NewTmpfsFileInode(
     task: &Task,
     uattr: UnstableAttr,
     msrc: &Arc<QMutex<MountSource>>,
+    usage: &TmpfsUsage,
 ) -> Result<Inode> {
+    let iusage = TmpfsInodeUsage::New(task, usage)?;
+
     let mut fstat = LibcStat::default();
     let tmpfd = HostSpace::NewTmpfsFile(TmpfsFileType::File, &mut fstat as *mut _ as u64) as i32;
     if tmpfd < 0 {
@@ -54,6 +58,7 @@ pub fn NewTmpfsFileInode(
         None => return Err(Error::SysError(SysErr::EBADF)),
         Some(iops) => iops.clone(),
     };
+    hostiops.lock().tmpfsUsage = Some(iusage);
 
     let ops = TmpfsFileInodeOp {
         inodeops: hostiops,
@@ -80,6 +85,12 @@ pub struct TmpfsFileInodeOp {
     pub uattr: Arc<QMutex<UnstableAttr>>,
 }
 
+impl TmpfsFileInodeOp {
+    pub fn Usage(&self) -> TmpfsInodeUsage {
+        return self.inodeops.lock().tmpfsUsage.clone().unwrap();
+    }
+}
+
 impl InodeOperations for TmpfsFileInodeOp {
     fn as_any(&self) -> &Any {
         return self;
@@ -235,10 +246,12 @@ impl InodeOperations for TmpfsFileInodeOp {
     }
 
     fn Truncate(&self, task: &Task, dir: &mut Inode, size: i64) -> Result<()> {
+        self.Usage().Truncate(size);
         return self.inodeops.Truncate(task, dir, size);
     }
 
     fn Allocate(&self, task: &Task, dir: &mut Inode, offset: i64, length: i64) -> Result<()> {
+        self.Usage().Allocate(task, offset, length)?;
         return self.inodeops.Allocate(task, dir, offset, length);
     }
 
@@ -267,7 +280,7 @@ impl InodeOperations for TmpfsFileInodeOp {
     }
 
     fn StatFS(&self, _task: &Task) -> Result<FsInfo> {
-        return Ok(TMPFS_FSINFO);
+        return Ok(self.Usage().FsInfo());
     }
 
     fn Mappable(&self) -> Result<MMappable> {
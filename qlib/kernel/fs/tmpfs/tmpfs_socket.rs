@@ -34,6 +34,7 @@ use super::super::inode::*;
 use super::super::mount::*;
 use super::super::ramfs::socket::*;
 use super::tmpfs_dir::*;
+use super::usage::*;
 
 pub fn NewTmpfsSocket(
     task: &Task,
@@ -41,9 +42,11 @@ pub fn NewTmpfsSocket(
     owner: &FileOwner,
     perms: &FilePermissions,
     msrc: &Arc<QMutex<MountSource>>,
-) -> Inode {
+    usage: &TmpfsUsage,
+) -> Result<Inode> {
+    let iusage = TmpfsInodeUsage::New(task, usage)?;
     let s = SocketInodeOps::New(task, socket, owner, perms);
-    let s = TmpfsSocket(s);
+    let s = TmpfsSocket(s, iusage);
 
     let deviceId = TMPFS_DEVICE.lock().DeviceID();
     let inodeId = TMPFS_DEVICE.lock().NextIno();
@@ -56,11 +59,11 @@ pub fn NewTmpfsSocket(
         DeviceFileMinor: 0,
     };
 
-    return Inode::New(s.into(), msrc, &attr);
+    return Ok(Inode::New(s.into(), msrc, &attr));
 }
 
 #[derive(Clone)]
-pub struct TmpfsSocket(SocketInodeOps);
+pub struct TmpfsSocket(SocketInodeOps, TmpfsInodeUsage);
 
 impl InodeOperations for TmpfsSocket {
     fn as_any(&self) -> &Any {
@@ -238,7 +241,7 @@ impl InodeOperations for TmpfsSocket {
     }
 
     fn StatFS(&self, _task: &Task) -> Result<FsInfo> {
-        return Ok(TMPFS_FSINFO);
+        return Ok(self.1.FsInfo());
     }
 
     fn Mappable(&self) -> Result<MMappable> {
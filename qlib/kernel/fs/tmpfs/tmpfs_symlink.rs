@@ -34,15 +34,18 @@ use super::super::inode::*;
 use super::super::mount::*;
 use super::super::ramfs::symlink::*;
 use super::tmpfs_dir::*;
+use super::usage::*;
 
 pub fn NewTmpfsSymlink(
     task: &Task,
     target: &str,
     owner: &FileOwner,
     msrc: &Arc<QMutex<MountSource>>,
-) -> Inode {
+    usage: &TmpfsUsage,
+) -> Result<Inode> {
+    let iusage = TmpfsInodeUsage::New(task, usage)?;
     let s = Symlink::New(task, owner, target);
-    let s = TmpfsSymlink(s);
+    let s = TmpfsSymlink(s, iusage);
 
     let deviceId = TMPFS_DEVICE.lock().DeviceID();
     let inodeId = TMPFS_DEVICE.lock().NextIno();
@@ -55,11 +58,11 @@ pub fn NewTmpfsSymlink(
         DeviceFileMinor: 0,
     };
 
-    return Inode::New(s.into(), msrc, &attr);
+    return Ok(Inode::New(s.into(), msrc, &attr));
 }
 
 #[derive(Clone)]
-pub struct TmpfsSymlink(Symlink);
+pub struct TmpfsSymlink(Symlink, TmpfsInodeUsage);
 
 impl InodeOperations for TmpfsSymlink {
     fn as_any(&self) -> &Any {
@@ -237,7 +240,7 @@ impl InodeOperations for TmpfsSymlink {
     }
 
     fn StatFS(&self, _task: &Task) -> Result<FsInfo> {
-        return Ok(TMPFS_FSINFO);
+        return Ok(self.1.FsInfo());
     }
 
     fn Mappable(&self) -> Result<MMappable> {
@@ -0,0 +1,402 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use super::super::super::super::common::*;
+use super::super::super::super::linux_def::*;
+use super::super::super::kernel::kernel::GetKernel;
+use super::super::super::task::*;
+use super::super::mount::*;
+
+// TmpfsUsageIntern is the space used by a tmpfs mount. Blocks are counted
+// in pages, as in Linux's mm/shmem.c.
+#[derive(Default, Debug)]
+pub struct TmpfsUsageIntern {
+    // maxBlocks is the maximum number of pages of file data, from the size
+    // or nr_blocks mount option. 0 means unlimited.
+    pub maxBlocks: u64,
+
+    // maxInodes is the maximum number of inodes, from the nr_inodes mount
+    // option. 0 means unlimited.
+    pub maxInodes: u64,
+
+    // blocks is the number of pages charged by the files of the mount.
+    pub blocks: u64,
+
+    // inodes is the number of inodes of the mount.
+    pub inodes: u64,
+}
+
+#[derive(Default, Clone)]
+pub struct TmpfsUsage(Arc<QMutex<TmpfsUsageIntern>>);
+
+impl Deref for TmpfsUsage {
+    type Target = Arc<QMutex<TmpfsUsageIntern>>;
+
+    fn deref(&self) -> &Arc<QMutex<TmpfsUsageIntern>> {
+        &self.0
+    }
+}
+
+impl TmpfsUsage {
+    pub fn New(maxBlocks: u64, maxInodes: u64) -> Self {
+        let intern = TmpfsUsageIntern {
+            maxBlocks: maxBlocks,
+            maxInodes: maxInodes,
+            ..Default::default()
+        };
+
+        return Self(Arc::new(QMutex::new(intern)));
+    }
+
+    pub fn ChargeInode(&self) -> Result<()> {
+        let mut u = self.lock();
+        if u.maxInodes != 0 && u.inodes >= u.maxInodes {
+            return Err(Error::SysError(SysErr::ENOSPC));
+        }
+
+        u.inodes += 1;
+        return Ok(());
+    }
+
+    pub fn UnchargeInode(&self) {
+        self.lock().inodes -= 1;
+    }
+
+    // ChargeBlocks charges up to count pages to the mount and returns the
+    // number of pages charged, which is less than count if the mount is
+    // short of space.
+    pub fn ChargeBlocks(&self, count: u64) -> u64 {
+        let mut u = self.lock();
+        let mut count = count;
+        if u.maxBlocks != 0 && u.blocks + count > u.maxBlocks {
+            count = u.maxBlocks - u.blocks;
+        }
+
+        u.blocks += count;
+        return count;
+    }
+
+    pub fn UnchargeBlocks(&self, count: u64) {
+        self.lock().blocks -= count;
+    }
+
+    // FsInfo returns the statfs(2) information of the mount. As in Linux, an
+    // unlimited mount reports 0 total and free blocks or inodes.
+    pub fn FsInfo(&self) -> FsInfo {
+        let u = self.lock();
+        let mut info = FsInfo {
+            Type: FSMagic::TMPFS_MAGIC,
+            ..Default::default()
+        };
+
+        if u.maxBlocks != 0 {
+            info.TotalBlocks = u.maxBlocks;
+            info.FreeBlocks = u.maxBlocks - u.blocks;
+        }
+
+        if u.maxInodes != 0 {
+            info.TotalFiles = u.maxInodes;
+            info.FreeFiles = u.maxInodes - u.inodes;
+        }
+
+        return info;
+    }
+}
+
+// TmpfsInodeUsageIntern is the space charged by a tmpfs inode. As in Linux,
+// a page is charged when it is written, fallocated or faulted in through a
+// writable shared mapping, not when the file is extended by truncate. The
+// inode and its pages are released when the last reference is dropped.
+pub struct TmpfsInodeUsageIntern {
+    pub usage: TmpfsUsage,
+
+    // containerID is the container whose memory cgroup the pages of the file
+    // are charged to, the container of the task that created the inode.
+    pub containerID: String,
+
+    // blocks is the number of pages charged by the file.
+    pub blocks: u64,
+
+    // charged maps the first page of each range of charged pages to the end
+    // of the range. The ranges are disjoint and not adjacent.
+    pub charged: BTreeMap<u64, u64>,
+}
+
+impl Drop for TmpfsInodeUsageIntern {
+    fn drop(&mut self) {
+        self.Resize(0);
+        self.usage.UnchargeInode();
+    }
+}
+
+impl TmpfsInodeUsageIntern {
+    // Resize releases the charged pages from page blocks on.
+    fn Resize(&mut self, blocks: u64) {
+        let mut count = 0;
+        if let Some((&start, &end)) = self.charged.range(..blocks).next_back() {
+            if end > blocks {
+                self.charged.insert(start, blocks);
+                count += end - blocks;
+            }
+        }
+
+        let tail: Vec<(u64, u64)> = self
+            .charged
+            .range(blocks..)
+            .map(|(&s, &e)| (s, e))
+            .collect();
+        for (start, end) in tail {
+            self.charged.remove(&start);
+            count += end - start;
+        }
+
+        if count > 0 {
+            self.blocks -= count;
+            self.usage.UnchargeBlocks(count);
+            GetKernel().UnchargeShmem(&self.containerID, count * MemoryDef::PAGE_SIZE);
+        }
+    }
+
+    // Gaps returns the ranges of pages in [start, end) which are not charged.
+    fn Gaps(&self, start: u64, end: u64) -> Vec<(u64, u64)> {
+        let mut gaps = Vec::new();
+        let mut curr = start;
+        if let Some((_, &e)) = self.charged.range(..start).next_back() {
+            if e > curr {
+                curr = e;
+            }
+        }
+
+        for (&s, &e) in self.charged.range(start..end) {
+            if s > curr {
+                gaps.push((curr, s));
+            }
+
+            if e > curr {
+                curr = e;
+            }
+        }
+
+        if curr < end {
+            gaps.push((curr, end));
+        }
+
+        return gaps;
+    }
+
+    // Insert marks the pages in [start, end) as charged, merging the range
+    // with the ones it overlaps or touches.
+    fn Insert(&mut self, start: u64, end: u64) {
+        let mut start = start;
+        let mut end = end;
+        if let Some((&s, &e)) = self.charged.range(..start).next_back() {
+            if e >= start {
+                start = s;
+                if e > end {
+                    end = e;
+                }
+            }
+        }
+
+        let merged: Vec<u64> = self.charged.range(start..=end).map(|(&s, _)| s).collect();
+        for s in merged {
+            let e = self.charged.remove(&s).unwrap();
+            if e > end {
+                end = e;
+            }
+        }
+
+        self.charged.insert(start, end);
+    }
+
+    // Charge charges count pages to the memory cgroup of the container of
+    // the file. The mount must be charged already.
+    fn Charge(&self, task: &Task, count: u64) -> Result<()> {
+        if count == 0 || self.containerID.len() == 0 {
+            return Ok(());
+        }
+
+        let bytes = count * MemoryDef::PAGE_SIZE;
+        GetKernel().ChargeMemory(task, bytes)?;
+        GetKernel().ChargeShmem(&self.containerID, bytes);
+        return Ok(());
+    }
+}
+
+#[derive(Clone)]
+pub struct TmpfsInodeUsage(Arc<QMutex<TmpfsInodeUsageIntern>>);
+
+impl Deref for TmpfsInodeUsage {
+    type Target = Arc<QMutex<TmpfsInodeUsageIntern>>;
+
+    fn deref(&self) -> &Arc<QMutex<TmpfsInodeUsageIntern>> {
+        &self.0
+    }
+}
+
+impl TmpfsInodeUsage {
+    // New charges a new inode to the mount, it fails with ENOSPC if the mount
+    // has nr_inodes inodes.
+    pub fn New(task: &Task, usage: &TmpfsUsage) -> Result<Self> {
+        usage.ChargeInode()?;
+
+        // Mounts made while the sandbox boots have no task thread and are
+        // not charged to any container.
+        let containerID = match &task.thread {
+            None => String::new(),
+            Some(t) => t.ThreadGroup().lock().containerID.clone(),
+        };
+        let intern = TmpfsInodeUsageIntern {
+            usage: usage.clone(),
+            containerID: containerID,
+            blocks: 0,
+            charged: BTreeMap::new(),
+        };
+
+        return Ok(Self(Arc::new(QMutex::new(intern))));
+    }
+
+    pub fn Usage(&self) -> TmpfsUsage {
+        return self.lock().usage.clone();
+    }
+
+    pub fn FsInfo(&self) -> FsInfo {
+        return self.lock().usage.FsInfo();
+    }
+
+    // Reserve charges the pages needed to write len bytes at offset and
+    // returns the number of bytes that fit in the mount and the memory
+    // limit of the container. It fails with ENOSPC if not even the first
+    // byte fits.
+    pub fn Reserve(&self, task: &Task, offset: i64, len: i64) -> Result<i64> {
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let start = offset as u64 / MemoryDef::PAGE_SIZE;
+        let end = (offset as u64 + len as u64 + MemoryDef::PAGE_SIZE - 1) / MemoryDef::PAGE_SIZE;
+
+        let mut u = self.lock();
+        let gaps = u.Gaps(start, end);
+        let want: u64 = gaps.iter().map(|&(s, e)| e - s).sum();
+        if want == 0 {
+            return Ok(len);
+        }
+
+        let count = u.usage.ChargeBlocks(want);
+        if let Err(e) = u.Charge(task, count) {
+            u.usage.UnchargeBlocks(count);
+            return Err(e);
+        }
+        u.blocks += count;
+
+        // The missing pages are charged in order, the write stops at the
+        // first one which doesn't fit.
+        let mut left = count;
+        let mut limit = end;
+        for (s, e) in gaps {
+            let n = core::cmp::min(left, e - s);
+            if n > 0 {
+                u.Insert(s, s + n);
+            }
+
+            left -= n;
+            if n < e - s {
+                limit = s + n;
+                break;
+            }
+        }
+
+        let avail = (limit * MemoryDef::PAGE_SIZE) as i64 - offset;
+        if avail <= 0 {
+            return Err(Error::SysError(SysErr::ENOSPC));
+        }
+
+        if avail < len {
+            return Ok(avail);
+        }
+
+        return Ok(len);
+    }
+
+    // ReservePage charges the page at offset when it is faulted in through a
+    // writable shared mapping, it fails with ENOSPC if the mount is full. The
+    // memory manager is locked, so the memory cgroup is left to the caller,
+    // which is told whether the page is newly charged.
+    pub fn ReservePage(&self, offset: u64) -> Result<bool> {
+        let page = offset / MemoryDef::PAGE_SIZE;
+
+        let mut u = self.lock();
+        if u.Gaps(page, page + 1).len() == 0 {
+            return Ok(false);
+        }
+
+        if u.usage.ChargeBlocks(1) == 0 {
+            return Err(Error::SysError(SysErr::ENOSPC));
+        }
+
+        if u.containerID.len() > 0 {
+            GetKernel().ChargeShmem(&u.containerID, MemoryDef::PAGE_SIZE);
+        }
+        u.blocks += 1;
+        u.Insert(page, page + 1);
+        return Ok(true);
+    }
+
+    // Allocate charges the pages covering [offset, offset+len), it fails
+    // with ENOSPC without charging anything if they don't fit.
+    pub fn Allocate(&self, task: &Task, offset: i64, len: i64) -> Result<()> {
+        let start = offset as u64 / MemoryDef::PAGE_SIZE;
+        let end = (offset as u64 + len as u64 + MemoryDef::PAGE_SIZE - 1) / MemoryDef::PAGE_SIZE;
+
+        let mut u = self.lock();
+        let gaps = u.Gaps(start, end);
+        let want: u64 = gaps.iter().map(|&(s, e)| e - s).sum();
+        if want == 0 {
+            return Ok(());
+        }
+
+        let count = u.usage.ChargeBlocks(want);
+        if count < want {
+            u.usage.UnchargeBlocks(count);
+            return Err(Error::SysError(SysErr::ENOSPC));
+        }
+
+        if let Err(e) = u.Charge(task, count) {
+            u.usage.UnchargeBlocks(count);
+            return Err(e);
+        }
+        u.blocks += count;
+
+        for (s, e) in gaps {
+            u.Insert(s, e);
+        }
+
+        return Ok(());
+    }
+
+    // Truncate releases the pages past a file of size bytes. Extending the
+    // file charges nothing, the new pages are charged when written.
+    pub fn Truncate(&self, size: i64) {
+        let blocks = (size as u64 + MemoryDef::PAGE_SIZE - 1) / MemoryDef::PAGE_SIZE;
+        self.lock().Resize(blocks);
+    }
+}
@@ -45,8 +45,18 @@ impl Filesystem for PtsTmpfs {
         flags: &MountSourceFlags,
         data: &str,
     ) -> Result<Inode> {
-        if data != "" {
-            return Err(Error::SysError(SysErr::EINVAL));
+        // Every mount gets a new instance, and the options which only tune
+        // the ownership and modes of its nodes are accepted and ignored.
+        for opt in data.split(',') {
+            let key = match opt.find('=') {
+                None => opt,
+                Some(idx) => &opt[..idx],
+            };
+
+            match key {
+                "" | "newinstance" | "ptmxmode" | "mode" | "gid" => (),
+                _ => return Err(Error::SysError(SysErr::EINVAL)),
+            }
         }
 
         let mops = Arc::new(QMutex::new(PtsSuperOperations {}));
@@ -56,7 +66,7 @@ impl Filesystem for PtsTmpfs {
     }
 
     fn AllowUserMount(&self) -> bool {
-        return false;
+        return true;
     }
 
     fn AllowUserList(&self) -> bool {
@@ -0,0 +1,269 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use super::super::super::common::*;
+use super::super::super::control_msg::UpdateArgs;
+use super::super::super::linux_def::*;
+use super::super::super::loader::CPULimit;
+use super::super::task::*;
+use super::super::threadmgr::thread::*;
+use super::super::TSC;
+use super::cpuset::*;
+use super::kernel::*;
+use super::time::*;
+
+// DEFAULT_CPU_PERIOD is the CPU bandwidth period in microseconds when the
+// spec sets a quota without a period, as cpu.cfs_period_us.
+pub const DEFAULT_CPU_PERIOD: u64 = 100_000;
+
+#[derive(Default, Debug)]
+pub struct CPUCgroupInternal {
+    // cpus is the mask of the vcpus the processes of the container may run
+    // on. 0 means all the vcpus.
+    pub cpus: u64,
+
+    // quota is the CPU time in nanoseconds the container may use in each
+    // period. 0 means that the container is not limited.
+    pub quota: i64,
+
+    // period is the length of a CPU bandwidth period in nanoseconds.
+    pub period: i64,
+
+    // generation is incremented each time the limits change, tasks don't
+    // charge the CPU time they used under an older generation.
+    pub generation: u64,
+
+    // periodStart is the monotonic time the current period started at.
+    pub periodStart: i64,
+
+    // usage is the CPU time used by the container in the current period in
+    // nanoseconds.
+    pub usage: i64,
+}
+
+// CPUCgroup is the cpu and cpuset controller of a container. It enforces the
+// cpu limits from the linux.resources of the container's OCI spec.
+//
+// The cpuset is enforced by the vcpu scheduler through the affinity of the
+// tasks. The quota is enforced when a task returns to the application: once
+// the tasks of the container have used up the quota of the current period,
+// they wait for the next period.
+#[derive(Default, Clone)]
+pub struct CPUCgroup(Arc<QMutex<CPUCgroupInternal>>);
+
+impl Deref for CPUCgroup {
+    type Target = Arc<QMutex<CPUCgroupInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<CPUCgroupInternal>> {
+        &self.0
+    }
+}
+
+impl CPUCgroup {
+    pub fn New(limit: &CPULimit) -> Self {
+        let cg = Self::default();
+        cg.Set(limit);
+        return cg;
+    }
+
+    // Set replaces the limits of the cgroup with limit.
+    pub fn Set(&self, limit: &CPULimit) {
+        let mut cpus = 0;
+        for cpu in &limit.CPUs {
+            if *cpu < 64 {
+                cpus |= 1 << *cpu;
+            }
+        }
+
+        let period = if limit.Period == 0 {
+            DEFAULT_CPU_PERIOD
+        } else {
+            limit.Period
+        };
+
+        let mut cg = self.lock();
+        cg.cpus = cpus;
+        cg.quota = limit.Quota as i64 * 1000;
+        cg.period = period as i64 * 1000;
+        cg.generation += 1;
+        cg.periodStart = 0;
+        cg.usage = 0;
+    }
+
+    // CPUMask returns the mask of the vcpus the container may run on, 0 means
+    // all the vcpus.
+    pub fn CPUMask(&self) -> u64 {
+        return self.lock().cpus;
+    }
+
+    // Charge adds usage nanoseconds of CPU time to the current period at time
+    // now. It returns the end of the period if the container is over its
+    // quota.
+    pub fn Charge(&self, now: i64, usage: i64) -> Option<Time> {
+        let mut cg = self.lock();
+        if cg.quota == 0 {
+            return None;
+        }
+
+        if now - cg.periodStart >= cg.period {
+            // The unused quota of the past periods is not carried over.
+            cg.periodStart = now - (now - cg.periodStart) % cg.period;
+            cg.usage = 0;
+        }
+
+        cg.usage += usage;
+        if cg.usage <= cg.quota {
+            return None;
+        }
+
+        return Some(Time(cg.periodStart + cg.period));
+    }
+}
+
+impl Thread {
+    // ChargeCPUCgroup charges the CPU time used by t since its last charge to
+    // the CPU controller of its container. It returns the time t has to wait
+    // until if the container has used up its quota.
+    pub fn ChargeCPUCgroup(&self) -> Option<Time> {
+        let cg = match self.ThreadGroup().lock().cpuCgroup.clone() {
+            None => return None,
+            Some(cg) => cg,
+        };
+
+        let generation = {
+            let cg = cg.lock();
+            if cg.quota == 0 {
+                return None;
+            }
+            cg.generation
+        };
+
+        let usage = {
+            let mut t = self.lock();
+            let stats = t.cpuStatsAt(TSC.Rdtsc());
+            let used = stats.UserTime + stats.SysTime;
+            let usage = if t.cpuCgroupGeneration == generation {
+                used - t.cpuCgroupCharged
+            } else {
+                0
+            };
+            t.cpuCgroupGeneration = generation;
+            t.cpuCgroupCharged = used;
+            usage
+        };
+
+        return cg.Charge(Task::MonoTimeNow().0, usage);
+    }
+}
+
+impl Kernel {
+    // SetContainerCgroups installs the cpu and pids controllers of the
+    // container.
+    pub fn SetContainerCgroups(&self, cid: &str, cpuLimit: &CPULimit, pidsLimit: u64) {
+        self.cpuCgroups
+            .lock()
+            .insert(cid.to_string(), CPUCgroup::New(cpuLimit));
+        self.pidsLimits.lock().insert(cid.to_string(), pidsLimit);
+    }
+
+    pub fn RemoveContainerCgroups(&self, cid: &str) {
+        self.cpuCgroups.lock().remove(cid);
+        self.pidsLimits.lock().remove(cid);
+    }
+
+    pub fn CPUCgroup(&self, cid: &str) -> Option<CPUCgroup> {
+        return self.cpuCgroups.lock().get(cid).cloned();
+    }
+
+    // ContainerCPUSet returns the initial cpu mask of a task of the
+    // container.
+    pub fn ContainerCPUSet(&self, cid: &str) -> CPUSet {
+        let mut mask = CPUSet::NewFullCPUSet(self.applicationCores);
+        if let Some(cg) = self.CPUCgroup(cid) {
+            let cpus = cg.CPUMask();
+            if cpus != 0 && mask.Mask() & cpus != 0 {
+                mask.Intersect(cpus);
+            }
+        }
+
+        return mask;
+    }
+
+    // ChargePids checks that the container cid may create one more task.
+    pub fn ChargePids(&self, cid: &str) -> Result<()> {
+        let limit = match self.pidsLimits.lock().get(cid) {
+            None => return Ok(()),
+            Some(l) => *l,
+        };
+
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let mut count = 0;
+        for (tg, _) in self.ContainerThreadGroups(cid) {
+            count += tg.lock().tasks.len() as u64;
+        }
+
+        // "Attempts to fork(2) or clone(2) a new process in the cgroup fail with
+        // EAGAIN once the limit is reached." - cgroups(7)
+        if count >= limit {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
+
+        return Ok(());
+    }
+
+    // UpdateContainer applies the new resource limits of a running
+    // container.
+    pub fn UpdateContainer(&self, args: &UpdateArgs) {
+        self.UpdateMemCgroup(&args.cid, &args.memoryLimit);
+        self.pidsLimits
+            .lock()
+            .insert(args.cid.to_string(), args.pidsLimit);
+
+        let cg = match self.CPUCgroup(&args.cid) {
+            Some(cg) => {
+                cg.Set(&args.cpuLimit);
+                cg
+            }
+            None => {
+                let cg = CPUCgroup::New(&args.cpuLimit);
+                self.cpuCgroups
+                    .lock()
+                    .insert(args.cid.to_string(), cg.clone());
+                cg
+            }
+        };
+
+        // As when cpuset.cpus is written, the affinity of all the tasks is
+        // reset to the new cpuset.
+        let mask = self.ContainerCPUSet(&args.cid);
+        for (tg, _) in self.ContainerThreadGroups(&args.cid) {
+            tg.lock().cpuCgroup = Some(cg.clone());
+            let threads: Vec<Thread> = tg.lock().tasks.iter().cloned().collect();
+            for t in threads {
+                if let Err(e) = t.SetCPUMask(mask.Copy()) {
+                    info!("UpdateContainer: set cpu mask of {} fail {:?}", &args.cid, e);
+                }
+            }
+        }
+    }
+}
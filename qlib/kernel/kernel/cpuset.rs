@@ -115,6 +115,28 @@ impl CPUSet {
         }
     }
 
+    // Mask returns the bitmask of the first 64 cpus of the CPUSet, which is the
+    // form the vcpu scheduler keeps the affinity of a task in.
+    pub fn Mask(&self) -> u64 {
+        let mut mask = 0;
+        for i in 0..core::cmp::min(self.0.len(), BYTES_PER_LONG) {
+            mask |= (self.0[i] as u64) << (i * BITS_PER_BYTE);
+        }
+
+        return mask;
+    }
+
+    // Intersect removes the cpus which are not in mask from the CPUSet.
+    pub fn Intersect(&mut self, mask: u64) {
+        for i in 0..self.0.len() {
+            if i < BYTES_PER_LONG {
+                self.0[i] &= (mask >> (i * BITS_PER_BYTE)) as u8;
+            } else {
+                self.0[i] = 0;
+            }
+        }
+    }
+
     // ForEachCPU iterates over the CPUSet and calls fn with the cpu index if
     // it's set.
     pub fn ForEachCPU(&self, mut f: impl FnMut(usize)) {
@@ -535,6 +535,10 @@ impl FDTableInternal {
             lockCtx
                 .Posix
                 .UnlockRegion(task, lockUniqueID, &Range::Max());
+            if let Some(fd) = file.HostLockFd() {
+                // Unlocking the host file never fails because of a conflict.
+                let _ = lockCtx.Posix.SyncHostRegion(fd, &Range::Max());
+            }
 
             if inode.StableAttr().IsDir() {
                 ev |= InotifyEvent::IN_ISDIR;
@@ -0,0 +1,859 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::collections::vec_deque::VecDeque;
+use alloc::sync::Arc;
+use alloc::sync::Weak;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::ops::Deref;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use super::super::super::common::*;
+use super::super::super::linux::io_uring::*;
+use super::super::super::linux_def::*;
+use super::super::super::range::*;
+use super::super::kernel::eventfd::*;
+use super::super::kernel::waiter::*;
+use super::super::memmgr::mm::*;
+use super::super::quring::uring_async::*;
+use super::super::task::*;
+use super::super::IOURING;
+
+use super::super::fs::anon::*;
+use super::super::fs::attr::*;
+use super::super::fs::dentry::*;
+use super::super::fs::dirent::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::fs::host::hostinodeop::*;
+
+// Layout of the SQ ring region mapped at IORING_OFF_SQ_RING. The offsets are
+// reported to the application in io_uring_params.sq_off.
+const SQ_HEAD_OFF: u32 = 0;
+const SQ_TAIL_OFF: u32 = 4;
+const SQ_RING_MASK_OFF: u32 = 8;
+const SQ_RING_ENTRIES_OFF: u32 = 12;
+const SQ_FLAGS_OFF: u32 = 16;
+const SQ_DROPPED_OFF: u32 = 20;
+const SQ_ARRAY_OFF: u32 = 64;
+
+// Layout of the CQ ring region mapped at IORING_OFF_CQ_RING. The offsets are
+// reported to the application in io_uring_params.cq_off.
+const CQ_HEAD_OFF: u32 = 0;
+const CQ_TAIL_OFF: u32 = 4;
+const CQ_RING_MASK_OFF: u32 = 8;
+const CQ_RING_ENTRIES_OFF: u32 = 12;
+const CQ_OVERFLOW_OFF: u32 = 16;
+const CQ_FLAGS_OFF: u32 = 20;
+const CQ_CQES_OFF: u32 = 64;
+
+// IoUringRequest is a submission which could not complete without blocking.
+// It is reissued from io_uring_enter(2) once the file it refers to becomes
+// ready.
+#[derive(Clone)]
+pub struct IoUringRequest {
+    pub sqe: IoUringSqe,
+
+    // file is the file the request operates on, for the opcodes which take
+    // one.
+    pub file: Option<File>,
+
+    // connecting is set for an IORING_OP_CONNECT which returned EINPROGRESS;
+    // the reissue only has to collect the result of the connection.
+    pub connecting: bool,
+
+    // mm is the address space of the task which submitted the request. The
+    // user addresses in the SQE refer to it.
+    pub mm: MemoryManager,
+}
+
+pub enum IoUringPollKind {
+    // PollAdd is an IORING_OP_POLL_ADD request. It completes with the
+    // returned events as soon as one of the requested events is signalled.
+    PollAdd { userData: u64, events: EventMask },
+
+    // Retry is a request waiting for its file to become ready.
+    Retry(IoUringRequest),
+}
+
+pub struct IoUringPollEntry {
+    pub file: File,
+    pub entry: WaitEntry,
+    pub kind: IoUringPollKind,
+}
+
+pub struct IoUringPollIntern {
+    pub ring: Weak<QMutex<IoUringIntern>>,
+    pub id: u64,
+    pub fired: AtomicBool,
+}
+
+// IoUringPoll is the wait entry context of a file waited on by an io_uring
+// instance.
+//
+// It only holds a weak reference to the ring: wait entries of polls which
+// are still armed when the ring is released stay on the file's queue until
+// that file goes away, and must not keep the ring alive.
+#[derive(Clone)]
+pub struct IoUringPoll(Arc<IoUringPollIntern>);
+
+impl Deref for IoUringPoll {
+    type Target = Arc<IoUringPollIntern>;
+
+    fn deref(&self) -> &Arc<IoUringPollIntern> {
+        &self.0
+    }
+}
+
+impl IoUringPoll {
+    // Callback is called with the file's wait queue locked, so it only moves
+    // the request over to the ring. The wait entry is unregistered later on,
+    // in task context.
+    pub fn Callback(&self, mask: EventMask) {
+        if self.fired.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let ring = match self.ring.upgrade() {
+            None => return,
+            Some(r) => IoUring(r),
+        };
+
+        ring.PollFired(self.id, mask);
+    }
+}
+
+pub struct IoUringTimeoutEntry {
+    pub userData: u64,
+
+    // target is the number of completions after which the timeout completes
+    // successfully, or 0 for a pure timeout.
+    pub target: u64,
+
+    // hostId is the QUring slot of the timer, once it has been submitted.
+    pub hostId: Option<usize>,
+
+    // done is set once the completion of the timeout has been posted. The
+    // entry itself is only removed when the timer completes, so that its slot
+    // is not reused while it may still be cancelled.
+    pub done: bool,
+}
+
+pub struct IoUringIntern {
+    // queue is notified when completions are posted or requests become ready
+    // to be reissued.
+    pub queue: Queue,
+
+    // memfd backs the three regions shared with the application.
+    pub memfd: HostInodeOp,
+
+    pub flags: u32,
+    pub sqEntries: u32,
+    pub cqEntries: u32,
+
+    // Kernel addresses of the SQ ring, CQ ring and SQE array.
+    pub sqRing: u64,
+    pub cqRing: u64,
+    pub sqes: u64,
+
+    // Sizes of the SQ ring, CQ ring and SQE array regions.
+    pub sqRingSize: u64,
+    pub cqRingSize: u64,
+    pub sqesSize: u64,
+
+    // overflow holds the completions which didn't fit in the CQ ring
+    // (IORING_FEAT_NODROP). They are flushed to the ring by io_uring_enter(2).
+    pub overflow: VecDeque<IoUringCqe>,
+
+    // completed counts the posted completions, except those of timeouts. It
+    // is the sequence IORING_OP_TIMEOUT counts against.
+    pub completed: u64,
+
+    pub eventfd: Option<EventOperations>,
+
+    // eventfdAsync is set by IORING_REGISTER_EVENTFD_ASYNC: only completions
+    // which were not posted inline by io_uring_enter(2) signal the eventfd.
+    pub eventfdAsync: bool,
+
+    pub nextId: u64,
+    pub timeouts: BTreeMap<u64, IoUringTimeoutEntry>,
+    pub polls: BTreeMap<u64, IoUringPollEntry>,
+
+    // firedPolls are the polls whose wait entries have to be unregistered.
+    pub firedPolls: Vec<IoUringPollEntry>,
+
+    // ready are the requests to reissue.
+    pub ready: VecDeque<IoUringRequest>,
+}
+
+impl Drop for IoUringIntern {
+    fn drop(&mut self) {
+        let mut memfd = self.memfd.lock();
+        memfd.DecrRefOn(&Range::New(IORING_OFF_SQ_RING, self.sqRingSize));
+        memfd.DecrRefOn(&Range::New(IORING_OFF_CQ_RING, self.cqRingSize));
+        memfd.DecrRefOn(&Range::New(IORING_OFF_SQES, self.sqesSize));
+    }
+}
+
+impl IoUringIntern {
+    fn sqWord(&self, off: u32) -> &AtomicU32 {
+        return unsafe { &*((self.sqRing + off as u64) as *const AtomicU32) };
+    }
+
+    fn cqWord(&self, off: u32) -> &AtomicU32 {
+        return unsafe { &*((self.cqRing + off as u64) as *const AtomicU32) };
+    }
+
+    // cqSpace returns the number of free CQEs in the CQ ring.
+    fn cqSpace(&self) -> u32 {
+        let head = self.cqWord(CQ_HEAD_OFF).load(Ordering::Acquire);
+        let tail = self.cqWord(CQ_TAIL_OFF).load(Ordering::Relaxed);
+        return self.cqEntries - tail.wrapping_sub(head);
+    }
+
+    fn writeCqe(&self, cqe: &IoUringCqe) {
+        let tail = self.cqWord(CQ_TAIL_OFF).load(Ordering::Relaxed);
+        let idx = tail & (self.cqEntries - 1);
+        let addr = self.cqRing
+            + CQ_CQES_OFF as u64
+            + idx as u64 * core::mem::size_of::<IoUringCqe>() as u64;
+        unsafe {
+            core::ptr::write_volatile(addr as *mut IoUringCqe, *cqe);
+        }
+
+        self.cqWord(CQ_TAIL_OFF)
+            .store(tail.wrapping_add(1), Ordering::Release);
+    }
+
+    fn setSqFlag(&self, flag: u32, set: bool) {
+        if set {
+            self.sqWord(SQ_FLAGS_OFF).fetch_or(flag, Ordering::SeqCst);
+        } else {
+            self.sqWord(SQ_FLAGS_OFF).fetch_and(!flag, Ordering::SeqCst);
+        }
+    }
+
+    // pushCqe queues a completion. It is written to the CQ ring if there is
+    // room and no older completion is waiting in the overflow list.
+    fn pushCqe(&mut self, userData: u64, res: i32) {
+        let cqe = IoUringCqe {
+            UserData: userData,
+            Res: res,
+            Flags: 0,
+        };
+
+        if self.overflow.len() == 0 && self.cqSpace() > 0 {
+            self.writeCqe(&cqe);
+            return;
+        }
+
+        self.overflow.push_back(cqe);
+        self.setSqFlag(IORING_SQ_CQ_OVERFLOW, true);
+    }
+
+    // completeTimeouts completes the timeouts whose completion count has been
+    // reached. Equivalent to io_uring/timeout.c:io_flush_timeouts.
+    fn completeTimeouts(&mut self) {
+        let completed = self.completed;
+        let mut fired = Vec::new();
+        for (_, t) in self.timeouts.iter_mut() {
+            if !t.done && t.target != 0 && t.target <= completed {
+                t.done = true;
+                fired.push((t.userData, t.hostId));
+            }
+        }
+
+        for (userData, hostId) in fired {
+            self.pushCqe(userData, 0);
+            match hostId {
+                None => (),
+                Some(id) => {
+                    IOURING.AsyncTimerRemove(id as u64);
+                }
+            }
+        }
+    }
+
+    fn postCompletion(&mut self, userData: u64, res: i32) {
+        self.pushCqe(userData, res);
+        self.completed += 1;
+        self.completeTimeouts();
+    }
+
+    fn eventfdToSignal(&self, inline: bool) -> Option<EventOperations> {
+        if inline && self.eventfdAsync {
+            return None;
+        }
+
+        if self.cqWord(CQ_FLAGS_OFF).load(Ordering::Relaxed) & IORING_CQ_EVENTFD_DISABLED != 0 {
+            return None;
+        }
+
+        return self.eventfd.clone();
+    }
+
+    fn newId(&mut self) -> u64 {
+        self.nextId += 1;
+        return self.nextId;
+    }
+}
+
+#[derive(Clone)]
+pub struct IoUring(Arc<QMutex<IoUringIntern>>);
+
+impl Deref for IoUring {
+    type Target = Arc<QMutex<IoUringIntern>>;
+
+    fn deref(&self) -> &Arc<QMutex<IoUringIntern>> {
+        &self.0
+    }
+}
+
+impl IoUring {
+    // New creates an io_uring instance with room for sqEntries submissions
+    // and cqEntries completions, and fills in the ring offsets of params.
+    // Both sizes must be powers of 2 no larger than IORING_MAX_ENTRIES and
+    // IORING_MAX_CQ_ENTRIES.
+    pub fn New(
+        task: &Task,
+        sqEntries: u32,
+        cqEntries: u32,
+        params: &mut IoUringParams,
+    ) -> Result<Self> {
+        let sqRingSize = Self::RoundUp(SQ_ARRAY_OFF as u64 + sqEntries as u64 * 4);
+        let cqRingSize = Self::RoundUp(
+            CQ_CQES_OFF as u64 + cqEntries as u64 * core::mem::size_of::<IoUringCqe>() as u64,
+        );
+        let sqesSize = Self::RoundUp(sqEntries as u64 * core::mem::size_of::<IoUringSqe>() as u64);
+
+        // The memfd is sparse: only the three regions are ever touched.
+        let memfd = HostInodeOp::NewMemfdIops((IORING_OFF_SQES + sqesSize) as i64)?;
+
+        let sqRing = Self::Pin(task, &memfd, IORING_OFF_SQ_RING, sqRingSize)?;
+        let cqRing = Self::Pin(task, &memfd, IORING_OFF_CQ_RING, cqRingSize)?;
+        let sqes = Self::Pin(task, &memfd, IORING_OFF_SQES, sqesSize)?;
+
+        let intern = IoUringIntern {
+            queue: Queue::default(),
+            memfd: memfd,
+            flags: params.Flags,
+            sqEntries: sqEntries,
+            cqEntries: cqEntries,
+            sqRing: sqRing,
+            cqRing: cqRing,
+            sqes: sqes,
+            sqRingSize: sqRingSize,
+            cqRingSize: cqRingSize,
+            sqesSize: sqesSize,
+            overflow: VecDeque::new(),
+            completed: 0,
+            eventfd: None,
+            eventfdAsync: false,
+            nextId: 0,
+            timeouts: BTreeMap::new(),
+            polls: BTreeMap::new(),
+            firedPolls: Vec::new(),
+            ready: VecDeque::new(),
+        };
+
+        intern
+            .sqWord(SQ_RING_MASK_OFF)
+            .store(sqEntries - 1, Ordering::SeqCst);
+        intern
+            .sqWord(SQ_RING_ENTRIES_OFF)
+            .store(sqEntries, Ordering::SeqCst);
+        intern
+            .cqWord(CQ_RING_MASK_OFF)
+            .store(cqEntries - 1, Ordering::SeqCst);
+        intern
+            .cqWord(CQ_RING_ENTRIES_OFF)
+            .store(cqEntries, Ordering::SeqCst);
+
+        params.SqEntries = sqEntries;
+        params.CqEntries = cqEntries;
+        params.Features = IORING_FEAT_NODROP | IORING_FEAT_SUBMIT_STABLE | IORING_FEAT_RW_CUR_POS;
+        params.SqOff = IoSqringOffsets {
+            Head: SQ_HEAD_OFF,
+            Tail: SQ_TAIL_OFF,
+            RingMask: SQ_RING_MASK_OFF,
+            RingEntries: SQ_RING_ENTRIES_OFF,
+            Flags: SQ_FLAGS_OFF,
+            Dropped: SQ_DROPPED_OFF,
+            Array: SQ_ARRAY_OFF,
+            ..Default::default()
+        };
+        params.CqOff = IoCqringOffsets {
+            Head: CQ_HEAD_OFF,
+            Tail: CQ_TAIL_OFF,
+            RingMask: CQ_RING_MASK_OFF,
+            RingEntries: CQ_RING_ENTRIES_OFF,
+            Overflow: CQ_OVERFLOW_OFF,
+            Cqes: CQ_CQES_OFF,
+            Flags: CQ_FLAGS_OFF,
+            ..Default::default()
+        };
+
+        return Ok(Self(Arc::new(QMutex::new(intern))));
+    }
+
+    fn RoundUp(size: u64) -> u64 {
+        return (size + MemoryDef::PAGE_SIZE - 1) & !(MemoryDef::PAGE_SIZE - 1);
+    }
+
+    // Pin maps a region of the memfd into the kernel and holds a reference
+    // on it, so that it stays mapped when the application unmaps it.
+    fn Pin(task: &Task, memfd: &HostInodeOp, offset: u64, len: u64) -> Result<u64> {
+        let fr = Range::New(offset, len);
+        let iovs = memfd.MapInternal(task, &fr)?;
+
+        // IORING_MAX_ENTRIES keeps every region within a single chunk.
+        assert!(iovs.len() == 1);
+        memfd.lock().IncrRefOn(&fr);
+        return Ok(iovs[0].start);
+    }
+
+    pub fn Downgrade(&self) -> Weak<QMutex<IoUringIntern>> {
+        return Arc::downgrade(&self.0);
+    }
+
+    // ValidateMmap checks that [offset, offset+len) lies within one of the
+    // ring regions. Equivalent to io_uring.c:io_uring_validate_mmap_request.
+    pub fn ValidateMmap(&self, offset: u64, len: u64) -> Result<()> {
+        let r = self.lock();
+        let size = match offset {
+            IORING_OFF_SQ_RING => r.sqRingSize,
+            IORING_OFF_CQ_RING => r.cqRingSize,
+            IORING_OFF_SQES => r.sqesSize,
+            _ => return Err(Error::SysError(SysErr::EINVAL)),
+        };
+
+        if len > size {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        return Ok(());
+    }
+
+    pub fn Memfd(&self) -> HostInodeOp {
+        return self.lock().memfd.clone();
+    }
+
+    // SqReady returns the number of SQEs the application has queued in the
+    // SQ ring and which have not been consumed yet.
+    pub fn SqReady(&self) -> u32 {
+        let r = self.lock();
+        let head = r.sqWord(SQ_HEAD_OFF).load(Ordering::Relaxed);
+        let tail = r.sqWord(SQ_TAIL_OFF).load(Ordering::Acquire);
+        return tail.wrapping_sub(head);
+    }
+
+    // NextSqe consumes the next SQE from the SQ ring. As io_get_sqe() does,
+    // an invalid index in the SQ array is consumed and accounted in the
+    // dropped counter, and ends the submission.
+    pub fn NextSqe(&self) -> Option<IoUringSqe> {
+        let r = self.lock();
+        let head = r.sqWord(SQ_HEAD_OFF).load(Ordering::Relaxed);
+        let tail = r.sqWord(SQ_TAIL_OFF).load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let slot = r.sqRing + SQ_ARRAY_OFF as u64 + (head & (r.sqEntries - 1)) as u64 * 4;
+        let idx = unsafe { core::ptr::read_volatile(slot as *const u32) };
+        r.sqWord(SQ_HEAD_OFF)
+            .store(head.wrapping_add(1), Ordering::Release);
+
+        if idx >= r.sqEntries {
+            r.sqWord(SQ_DROPPED_OFF).fetch_add(1, Ordering::SeqCst);
+            return None;
+        }
+
+        let addr = r.sqes + idx as u64 * core::mem::size_of::<IoUringSqe>() as u64;
+        let sqe = unsafe { core::ptr::read_volatile(addr as *const IoUringSqe) };
+        return Some(sqe);
+    }
+
+    // CqReady returns the number of completions the application has not
+    // consumed yet.
+    pub fn CqReady(&self) -> u32 {
+        let r = self.lock();
+        let head = r.cqWord(CQ_HEAD_OFF).load(Ordering::Acquire);
+        let tail = r.cqWord(CQ_TAIL_OFF).load(Ordering::Relaxed);
+        return tail.wrapping_sub(head);
+    }
+
+    // FlushOverflow moves the overflowed completions to the CQ ring as far as
+    // the application made room for them.
+    pub fn FlushOverflow(&self) {
+        let mut r = self.lock();
+        while r.overflow.len() > 0 && r.cqSpace() > 0 {
+            let cqe = r.overflow.pop_front().unwrap();
+            r.writeCqe(&cqe);
+        }
+
+        if r.overflow.len() == 0 {
+            r.setSqFlag(IORING_SQ_CQ_OVERFLOW, false);
+        }
+    }
+
+    fn notify(&self, eventfd: Option<EventOperations>) {
+        let queue = self.lock().queue.clone();
+        queue.Notify(READABLE_EVENT);
+
+        // Notify the event file after the completion is visible, so that
+        // the thread we wake up finds it.
+        match eventfd {
+            None => (),
+            Some(e) => {
+                e.Signal(1).ok();
+            }
+        }
+    }
+
+    // PostCompletion posts the completion of a request. inline is set for
+    // completions posted from io_uring_enter(2) itself.
+    pub fn PostCompletion(&self, userData: u64, res: i32, inline: bool) {
+        let eventfd = {
+            let mut r = self.lock();
+            r.postCompletion(userData, res);
+            r.eventfdToSignal(inline)
+        };
+
+        self.notify(eventfd);
+    }
+
+    // AddTimeout arms an IORING_OP_TIMEOUT request, which completes with
+    // -ETIME after ns nanoseconds or with 0 once count other requests have
+    // completed.
+    pub fn AddTimeout(&self, userData: u64, count: u32, ns: i64) {
+        let id = {
+            let mut r = self.lock();
+            let id = r.newId();
+            let target = if count == 0 {
+                0
+            } else {
+                r.completed + count as u64
+            };
+
+            r.timeouts.insert(
+                id,
+                IoUringTimeoutEntry {
+                    userData: userData,
+                    target: target,
+                    hostId: None,
+                    done: false,
+                },
+            );
+            id
+        };
+
+        let hostId = IOURING.AUCall(AsyncOps::IoUringTimeout(IoUringTimeout::New(self, id, ns)));
+
+        let mut r = self.lock();
+        match r.timeouts.get_mut(&id) {
+            None => (),
+            Some(t) => t.hostId = Some(hostId),
+        }
+    }
+
+    // TimeoutFired is called when the timer of the timeout id completes.
+    pub fn TimeoutFired(&self, id: u64) {
+        let eventfd = {
+            let mut r = self.lock();
+            let t = match r.timeouts.remove(&id) {
+                None => return,
+                Some(t) => t,
+            };
+
+            if t.done {
+                return;
+            }
+
+            r.pushCqe(t.userData, -SysErr::ETIME);
+            r.eventfdToSignal(false)
+        };
+
+        self.notify(eventfd);
+    }
+
+    // ArmPoll waits for one of the events in mask on file, on behalf of kind.
+    pub fn ArmPoll(&self, task: &Task, file: &File, mask: EventMask, kind: IoUringPollKind) {
+        let entry = WaitEntry::New();
+        let poll = {
+            let mut r = self.lock();
+            let id = r.newId();
+            let poll = IoUringPoll(Arc::new(IoUringPollIntern {
+                ring: self.Downgrade(),
+                id: id,
+                fired: AtomicBool::new(false),
+            }));
+
+            entry.lock().context = WaitContext::IoUringPoll(poll.clone());
+            r.polls.insert(
+                id,
+                IoUringPollEntry {
+                    file: file.clone(),
+                    entry: entry.clone(),
+                    kind: kind,
+                },
+            );
+            poll
+        };
+
+        file.EventRegister(task, &entry, mask | EVENT_ERR | EVENT_HUP);
+
+        // Catch the events which were signalled before the registration.
+        let ready = file.Readiness(task, mask | EVENT_ERR | EVENT_HUP);
+        if ready != 0 {
+            poll.Callback(ready);
+        }
+    }
+
+    pub fn PollFired(&self, id: u64, mask: EventMask) {
+        let eventfd = {
+            let mut r = self.lock();
+            let p = match r.polls.remove(&id) {
+                None => return,
+                Some(p) => p,
+            };
+
+            let eventfd = match &p.kind {
+                IoUringPollKind::PollAdd { userData, events } => {
+                    let userData = *userData;
+                    let res = mask & (*events | EVENT_ERR | EVENT_HUP);
+                    r.postCompletion(userData, res as i32);
+                    r.eventfdToSignal(false)
+                }
+                IoUringPollKind::Retry(req) => {
+                    // There is no completion yet. The eventfd is signalled
+                    // anyway so that event loops waiting on it call
+                    // io_uring_enter(2), which reissues the request.
+                    let req = req.clone();
+                    r.ready.push_back(req);
+                    r.eventfdToSignal(false)
+                }
+            };
+
+            r.firedPolls.push(p);
+            eventfd
+        };
+
+        self.notify(eventfd);
+    }
+
+    // Cleanup unregisters the wait entries of the polls which have fired.
+    pub fn Cleanup(&self, task: &Task) {
+        let fired = core::mem::replace(&mut self.lock().firedPolls, Vec::new());
+        for p in fired {
+            p.file.EventUnregister(task, &p.entry);
+        }
+    }
+
+    // TakeReady returns the requests which are ready to be reissued.
+    pub fn TakeReady(&self) -> VecDeque<IoUringRequest> {
+        return core::mem::replace(&mut self.lock().ready, VecDeque::new());
+    }
+
+    pub fn RegisterEventfd(&self, eventfd: EventOperations, asyncOnly: bool) -> Result<()> {
+        let mut r = self.lock();
+        if r.eventfd.is_some() {
+            return Err(Error::SysError(SysErr::EBUSY));
+        }
+
+        r.eventfd = Some(eventfd);
+        r.eventfdAsync = asyncOnly;
+        return Ok(());
+    }
+
+    pub fn UnregisterEventfd(&self) -> Result<()> {
+        let mut r = self.lock();
+        if r.eventfd.is_none() {
+            return Err(Error::SysError(SysErr::ENXIO));
+        }
+
+        r.eventfd = None;
+        return Ok(());
+    }
+}
+
+// NewIoUringFile returns the file of a new io_uring instance.
+pub fn NewIoUringFile(task: &Task, ring: IoUring) -> File {
+    // name matches io_uring.c:io_uring_get_file.
+    let inode = NewAnonInode(task);
+    let dirent = Dirent::New(&inode, "anon_inode:[io_uring]");
+
+    let ops = IoUringOperations { ring: ring };
+
+    return File::New(
+        &dirent,
+        &FileFlags {
+            Read: true,
+            Write: true,
+            ..Default::default()
+        },
+        ops.into(),
+    );
+}
+
+// IoUringOperations implements the file descriptor returned by
+// io_uring_setup(2).
+#[derive(Clone)]
+pub struct IoUringOperations {
+    pub ring: IoUring,
+}
+
+impl Waitable for IoUringOperations {
+    // The ring is readable when there are completions to reap, or requests
+    // which io_uring_enter(2) has to reissue; it is writable when there is
+    // room in the SQ ring. Equivalent to io_uring.c:io_uring_poll.
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        let mut ready = 0;
+
+        let r = self.ring.lock();
+        let sqHead = r.sqWord(SQ_HEAD_OFF).load(Ordering::Acquire);
+        let sqTail = r.sqWord(SQ_TAIL_OFF).load(Ordering::Acquire);
+        if sqTail.wrapping_sub(sqHead) < r.sqEntries {
+            ready |= WRITEABLE_EVENT;
+        }
+
+        let cqHead = r.cqWord(CQ_HEAD_OFF).load(Ordering::Acquire);
+        let cqTail = r.cqWord(CQ_TAIL_OFF).load(Ordering::Acquire);
+        if cqHead != cqTail || r.overflow.len() > 0 || r.ready.len() > 0 {
+            ready |= READABLE_EVENT;
+        }
+
+        return mask & ready;
+    }
+
+    fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
+        let q = self.ring.lock().queue.clone();
+        q.EventRegister(task, e, mask)
+    }
+
+    fn EventUnregister(&self, task: &Task, e: &WaitEntry) {
+        let q = self.ring.lock().queue.clone();
+        q.EventUnregister(task, e)
+    }
+}
+
+impl SpliceOperations for IoUringOperations {}
+
+impl FileOperations for IoUringOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::IoUringOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return false;
+    }
+
+    fn Seek(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _whence: i32,
+        _current: i64,
+        _offset: i64,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ESPIPE));
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _dsts: &mut [IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn WriteAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    // The rings are mapped straight from the backing memfd: the file offset
+    // of each region is its IORING_OFF_* magic offset.
+    fn Mappable(&self) -> Result<MMappable> {
+        return Ok(MMappable::FromHostIops(self.ring.Memfd()));
+    }
+}
+
+impl SockOperations for IoUringOperations {}
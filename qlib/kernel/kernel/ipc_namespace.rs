@@ -22,11 +22,76 @@ use super::super::super::auth::userns::*;
 use super::super::super::auth::*;
 use super::super::super::auth::id::*;
 use super::super::super::linux::ipc::*;
+use super::super::super::linux::msgqueue::*;
+use super::super::super::linux::sem::*;
+use super::super::super::linux::shm::*;
 use super::super::task::*;
 use crate::qlib::mutex::*;
 use super::semaphore;
 use super::shm;
 use super::msgqueue;
+use super::mqueue;
+use super::nsfs::*;
+
+// IPCLimitsInternal holds the SysV IPC limits of an IPC namespace, which are
+// set through the kernel.shm*, kernel.msg* and kernel.sem sysctls.
+#[derive(Debug, Clone, Copy)]
+pub struct IPCLimitsInternal {
+    // shmMax is the maximum size of a shared memory segment in bytes.
+    pub shmMax: u64,
+
+    // shmAll is the maximum total size of the shared memory segments in pages.
+    pub shmAll: u64,
+
+    // shmMni is the maximum number of shared memory segments.
+    pub shmMni: u64,
+
+    // msgMax is the maximum size of a message in bytes.
+    pub msgMax: u64,
+
+    // msgMnb is the default maximum size of a message queue in bytes.
+    pub msgMnb: u64,
+
+    // msgMni is the maximum number of message queues.
+    pub msgMni: u64,
+
+    // semMsl, semMns, semOpm and semMni are the fields of kernel.sem: the
+    // maximum number of semaphores of a set, the maximum total number of
+    // semaphores, the maximum number of operations of a semop(2) call and
+    // the maximum number of semaphore sets.
+    pub semMsl: u32,
+    pub semMns: u32,
+    pub semOpm: u32,
+    pub semMni: u32,
+}
+
+impl Default for IPCLimitsInternal {
+    fn default() -> Self {
+        return Self {
+            shmMax: SHMMAX,
+            shmAll: SHMALL,
+            shmMni: SHMMNI,
+            msgMax: MSGMAX as u64,
+            msgMnb: MSGMNB as u64,
+            msgMni: MSGMNI as u64,
+            semMsl: SEMMSL,
+            semMns: SEMMNS,
+            semOpm: SEMOPM,
+            semMni: SEMMNI,
+        };
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct IPCLimits(Arc<QMutex<IPCLimitsInternal>>);
+
+impl Deref for IPCLimits {
+    type Target = Arc<QMutex<IPCLimitsInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<IPCLimitsInternal>> {
+        &self.0
+    }
+}
 
 #[derive(Clone)]
 pub struct IPCNamespace {
@@ -34,6 +99,9 @@ pub struct IPCNamespace {
     pub semphores: semaphore::SemRegistry,
     pub shms: shm::ShmRegistry,
     pub queues: msgqueue::MQRegistry,
+    pub posixQueues: mqueue::PosixQueueRegistry,
+    pub limits: IPCLimits,
+    pub id: u64,
 }
 
 impl Default for IPCNamespace {
@@ -49,9 +117,21 @@ impl IPCNamespace {
             semphores: semaphore::SemRegistry::New(userNS),
             shms: shm::ShmRegistry::New(userNS),
             queues: msgqueue::MQRegistry::New(userNS),
+            posixQueues: mqueue::PosixQueueRegistry::New(userNS),
+            limits: IPCLimits::default(),
+            id: NewNamespaceInode(),
         };
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.id;
+    }
+
+    // Limits returns the current SysV IPC limits of the namespace.
+    pub fn Limits(&self) -> IPCLimitsInternal {
+        return *self.limits.lock();
+    }
+
     pub fn SemaphoreRegistry(&self) -> semaphore::SemRegistry {
         return self.semphores.clone();
     }
@@ -63,6 +143,10 @@ impl IPCNamespace {
     pub fn MsgqueueRegistry(&self) -> msgqueue::MQRegistry {
         return self.queues.clone();
     }
+
+    pub fn PosixQueueRegistry(&self) -> mqueue::PosixQueueRegistry {
+        return self.posixQueues.clone();
+    }
 }
 
 // Key is a user-provided identifier for IPC objects.
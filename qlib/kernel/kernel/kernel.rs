@@ -23,6 +23,7 @@ use core::sync::atomic::AtomicBool;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering;
 
+use super::super::super::auth::id::*;
 use super::super::super::auth::userns::*;
 use super::super::super::auth::*;
 use super::super::super::auxv::*;
@@ -38,6 +39,7 @@ use super::super::fs::mount::*;
 use super::super::loader::loader::*;
 use super::super::task::*;
 use super::super::threadmgr::pid_namespace::*;
+use super::super::threadmgr::task_coredump::*;
 use super::super::threadmgr::task_sched::*;
 use super::super::threadmgr::task_start::*;
 use super::super::threadmgr::thread::*;
@@ -46,9 +48,13 @@ use super::super::threadmgr::threads::*;
 use super::super::uid::NewUID;
 use super::super::SignalDef::*;
 use super::super::SHARESPACE;
+use super::cgroup::*;
+use super::seccomp::SyscallPolicy;
+use super::sysctl::SysctlValues;
 use super::cpuset::*;
 use super::fd_table::*;
 use super::ipc_namespace::*;
+use super::oom::*;
 use super::platform::*;
 use super::signal_handler::*;
 use super::time::*;
@@ -147,6 +153,27 @@ pub struct KernelInternal {
 
     // syslog is the kernel log.
     pub syslog: SysLog,
+
+    // corePattern is the template of core dump file names, as configured by
+    // /proc/sys/kernel/core_pattern.
+    pub corePattern: QMutex<String>,
+
+    // memCgroups holds the memory limits of the containers, keyed by
+    // container ID.
+    pub memCgroups: QMutex<BTreeMap<String, MemCgroup>>,
+
+    // cpuCgroups and pidsLimits hold the cpu and pids limits of the
+    // containers, keyed by container ID.
+    pub cpuCgroups: QMutex<BTreeMap<String, CPUCgroup>>,
+    pub pidsLimits: QMutex<BTreeMap<String, u64>>,
+
+    // seccompPolicies holds the compiled linux.seccomp profiles of the
+    // containers, keyed by container ID.
+    pub seccompPolicies: QMutex<BTreeMap<String, Arc<SyscallPolicy>>>,
+
+    // sysctls holds the global sysctls which aren't backed by another kernel
+    // object, as configured by /proc/sys.
+    pub sysctls: SysctlValues,
 }
 
 impl KernelInternal {
@@ -166,6 +193,8 @@ impl KernelInternal {
             limits: limit.clone(),
             containerID: cid.to_string(),
             execId: execId.clone(),
+            cpuCgroup: self.cpuCgroups.lock().get(cid).cloned(),
+            loginuid: KUID(NO_ID),
             ..Default::default()
         };
 
@@ -224,6 +253,12 @@ impl Kernel {
             platform: DefaultPlatform::default(),
             lastProcessTime: QMutex::new(0),
             syslog: SysLog::default(),
+            corePattern: QMutex::new(DEFAULT_CORE_PATTERN.to_string()),
+            memCgroups: QMutex::new(BTreeMap::new()),
+            cpuCgroups: QMutex::new(BTreeMap::new()),
+            pidsLimits: QMutex::new(BTreeMap::new()),
+            seccompPolicies: QMutex::new(BTreeMap::new()),
+            sysctls: SysctlValues::default(),
         };
 
         //error!("hasXSAVEOPT is {}", internal.featureSet.lock().UseXsaveopt());
@@ -247,6 +282,14 @@ impl Kernel {
         return self.syslog.clone();
     }
 
+    pub fn CorePattern(&self) -> String {
+        return self.corePattern.lock().clone();
+    }
+
+    pub fn SetCorePattern(&self, pattern: &str) {
+        *self.corePattern.lock() = pattern.to_string();
+    }
+
     pub fn Atomically(&self, mut f: impl FnMut()) {
         let _t = self.lastProcessTime.lock();
         f();
@@ -315,10 +358,13 @@ impl Kernel {
             &args.ExecId,
         );
 
+        let oomScoreAdj = self.ContainerOOMScoreAdj(&args.ContainerID);
         {
             let mut tglock = tg.lock();
             tglock.liveThreads.Add(1);
             tglock.root = true;
+            tglock.oomScoreAdj = oomScoreAdj;
+            tglock.oomScoreAdjMin = oomScoreAdj;
         }
 
         if args.Filename.as_str() == "" {
@@ -369,11 +415,13 @@ impl Kernel {
             Credentials: args.Credentials.clone(),
             Niceness: 0,
             NetworkNamespaced: false,
-            AllowedCPUMask: CPUSet::NewFullCPUSet(self.applicationCores),
+            AllowedCPUMask: self.ContainerCPUSet(&args.ContainerID),
             UTSNamespace: args.UTSNamespace.clone(),
             IPCNamespace: args.IPCNamespace.clone(),
+            MountNamespace: mns.clone(),
             Blocker: task.blocker.clone(),
             ContainerID: args.ContainerID.to_string(),
+            SetTIDs: Vec::new(),
         };
 
         let ts = self.tasks.clone();
@@ -32,6 +32,7 @@ pub mod eventfd;
 pub mod fasync;
 pub mod fs_context;
 pub mod futex;
+pub mod io_uring;
 pub mod ipc_namespace;
 pub mod kernel;
 pub mod pipe;
@@ -39,5 +40,14 @@ pub mod platform;
 pub mod signal_handler;
 pub mod signalfd;
 pub mod msgqueue;
+pub mod mqueue;
+pub mod nsfs;
+pub mod oom;
+pub mod cgroup;
+pub mod seccomp;
+pub mod sysctl;
+pub mod perf_event;
+pub mod pidfd;
 pub mod syslog;
+pub mod userfaultfd;
 pub mod socket_store;
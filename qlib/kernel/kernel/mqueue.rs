@@ -0,0 +1,623 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::vec_deque::VecDeque;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use super::super::super::auth::userns::*;
+use super::super::super::auth::*;
+use super::super::super::common::*;
+use super::super::super::linux::mqueue::*;
+use super::super::super::linux_def::*;
+use super::super::fs::dirent::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::fs::mqueue::queue::*;
+use super::super::kernel::time::*;
+use super::super::kernel::waiter::*;
+use super::super::task::*;
+use super::super::threadmgr::thread_group::*;
+use super::super::SignalDef::*;
+
+// PosixMessage is a message sent to a POSIX message queue.
+pub struct PosixMessage {
+    // Text is the message's text.
+    pub Text: Vec<u8>,
+
+    // Priority is the message's priority. Messages with a higher priority
+    // are received first.
+    pub Priority: u32,
+}
+
+// Subscriber is a process registered with mq_notify(3) to be notified when a
+// message arrives on an empty queue.
+pub struct Subscriber {
+    // tg is the thread group that registered for notification.
+    pub tg: ThreadGroupWeak,
+
+    // userns is the user namespace of the registering task, used to translate
+    // the sender's UID in SIGEV_SIGNAL notifications.
+    pub userns: UserNameSpace,
+
+    // sigev is the struct sigevent passed to mq_notify(3).
+    pub sigev: Sigevent,
+
+    // sock is the netlink socket that receives SIGEV_THREAD notifications.
+    pub sock: Option<File>,
+
+    // cookie is the data sent on sock with SIGEV_THREAD notifications.
+    pub cookie: [u8; NOTIFY_COOKIE_LEN],
+}
+
+impl Subscriber {
+    // Owner returns true if the subscriber was registered by task's thread
+    // group.
+    pub fn Owner(&self, task: &Task) -> bool {
+        match self.tg.Upgrade() {
+            None => return false,
+            Some(tg) => return tg == task.Thread().ThreadGroup(),
+        }
+    }
+
+    // notify delivers a notification to the subscriber on behalf of task,
+    // which has just sent a message to the queue.
+    fn notify(&mut self, task: &Task) {
+        match self.sigev.Notify {
+            SIGEV_SIGNAL => {
+                let tg = match self.tg.Upgrade() {
+                    None => return,
+                    Some(tg) => tg,
+                };
+
+                let mut info = SignalInfo {
+                    Signo: self.sigev.Signo,
+                    Code: SignaCode::SI_MESGQ,
+                    ..Default::default()
+                };
+
+                let sigRt = info.SigRt();
+                let pidns = tg.PIDNamespace();
+                sigRt.pid = pidns.IDOfThreadGroup(&task.Thread().ThreadGroup());
+                sigRt.uid = task.Creds().lock().RealKUID.In(&self.userns).OrOverflow().0;
+                sigRt.sigval = self.sigev.Value;
+
+                match tg.SendSignal(&info) {
+                    Err(e) => info!("mqueue: failed to send notification: {:?}", e),
+                    Ok(()) => (),
+                }
+            }
+            SIGEV_THREAD => self.sendCookie(NOTIFY_WOKENUP),
+            _ => (),
+        }
+    }
+
+    // sendCookie queues the subscriber's cookie, tagged with code, on the
+    // receive queue of its netlink socket.
+    fn sendCookie(&mut self, code: u8) {
+        let sock = match &self.sock {
+            None => return,
+            Some(f) => f.clone(),
+        };
+
+        self.cookie[NOTIFY_COOKIE_LEN - 1] = code;
+        match sock.FileOp {
+            FileOps::HostSocketOperations(ref s) => s.QueueNotification(&self.cookie),
+            _ => (),
+        }
+    }
+}
+
+pub struct PosixQueueInternal {
+    // queue is used to notify interested parties when the message queue
+    // becomes readable or writable.
+    pub queue: Queue,
+
+    // messages is the list of messages currently in the queue, ordered by
+    // descending priority. Messages of equal priority are kept in the order
+    // they were sent.
+    pub messages: VecDeque<PosixMessage>,
+
+    // subscriber is the process registered for notification, if any.
+    pub subscriber: Option<Subscriber>,
+
+    // maxMessageCount is the maximum number of messages in the queue.
+    pub maxMessageCount: i64,
+
+    // maxMessageSize is the maximum size of each message in the queue.
+    pub maxMessageSize: i64,
+
+    // byteCount is the number of bytes of data in all messages in the queue.
+    pub byteCount: u64,
+
+    // blockedReceivers is the number of tasks blocked in mq_timedreceive(2).
+    // Notifications are only sent if no receiver is waiting for a message.
+    pub blockedReceivers: usize,
+}
+
+#[derive(Clone)]
+pub struct PosixQueue(Arc<QMutex<PosixQueueInternal>>);
+
+impl Deref for PosixQueue {
+    type Target = Arc<QMutex<PosixQueueInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<PosixQueueInternal>> {
+        &self.0
+    }
+}
+
+impl PosixQueue {
+    pub fn New(maxMessageCount: i64, maxMessageSize: i64) -> Self {
+        let internal = PosixQueueInternal {
+            queue: Queue::default(),
+            messages: VecDeque::new(),
+            subscriber: None,
+            maxMessageCount: maxMessageCount,
+            maxMessageSize: maxMessageSize,
+            byteCount: 0,
+            blockedReceivers: 0,
+        };
+
+        return Self(Arc::new(QMutex::new(internal)));
+    }
+
+    // NewDefault returns a queue with the default attributes, used when no
+    // attributes are passed to mq_open(3).
+    pub fn NewDefault() -> Self {
+        return Self::New(
+            core::cmp::min(DFLT_MSG, DFLT_MSGMAX),
+            core::cmp::min(DFLT_MSGSIZE, DFLT_MSGSIZEMAX),
+        );
+    }
+
+    // Attr returns the queue's attributes as reported by mq_getsetattr(2).
+    // MqFlags is left to the caller, since it belongs to the open file.
+    pub fn Attr(&self) -> MqAttr {
+        let q = self.lock();
+        return MqAttr {
+            MqMaxmsg: q.maxMessageCount,
+            MqMsgsize: q.maxMessageSize,
+            MqCurmsgs: q.messages.len() as i64,
+            ..Default::default()
+        };
+    }
+
+    // MaxMessageSize returns the maximum size of a message in the queue.
+    pub fn MaxMessageSize(&self) -> i64 {
+        return self.lock().maxMessageSize;
+    }
+
+    // Readiness returns the ready events for the queue.
+    pub fn Readiness(&self, mask: EventMask) -> EventMask {
+        let q = self.lock();
+
+        let mut ready = 0;
+        if q.messages.len() > 0 {
+            ready |= READABLE_EVENT;
+        }
+
+        if (q.messages.len() as i64) < q.maxMessageCount {
+            ready |= WRITEABLE_EVENT;
+        }
+
+        return mask & ready;
+    }
+
+    // Status returns the contents of the queue's file as read from the mqueue
+    // filesystem. See ipc/mqueue.c:mqueue_read_file.
+    pub fn Status(&self, task: &Task) -> String {
+        let q = self.lock();
+
+        let (notify, signo, pid) = match &q.subscriber {
+            None => (0, 0, 0),
+            Some(s) => {
+                let pid = match s.tg.Upgrade() {
+                    None => 0,
+                    Some(tg) => task.Thread().PIDNamespace().IDOfThreadGroup(&tg),
+                };
+
+                let signo = if s.sigev.Notify == SIGEV_SIGNAL {
+                    s.sigev.Signo
+                } else {
+                    0
+                };
+
+                (s.sigev.Notify, signo, pid)
+            }
+        };
+
+        return format!(
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}\n",
+            q.byteCount, notify, signo, pid
+        );
+    }
+
+    // Send adds msg to the queue, blocking until there is room in the queue
+    // if block is true. deadline is an absolute CLOCK_REALTIME timeout. See
+    // mq_timedsend(2).
+    //
+    // Preconditions: msg is no larger than the queue's maximum message size.
+    pub fn Send(
+        &self,
+        task: &Task,
+        msg: PosixMessage,
+        block: bool,
+        deadline: Option<Time>,
+    ) -> Result<()> {
+        let mut msg = Some(msg);
+        match self.push(task, &mut msg) {
+            Err(Error::SysError(SysErr::EAGAIN)) => (),
+            res => return res,
+        }
+
+        if !block {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
+
+        // Slow path: at this point, the queue was found to be full, and we were
+        // asked to block.
+        let general = task.blocker.generalEntry.clone();
+        let queue = self.lock().queue.clone();
+
+        queue.EventRegister(task, &general, EVENT_OUT);
+        defer!(queue.EventUnregister(task, &general));
+        loop {
+            match self.push(task, &mut msg) {
+                Err(Error::SysError(SysErr::EAGAIN)) => (),
+                res => return res,
+            }
+
+            match task.blocker.BlockWithRealTimer(true, deadline) {
+                // "mq_timedsend() is never restarted after being interrupted
+                // by a signal handler" - signal(7)
+                Err(Error::ErrInterrupted) => return Err(Error::SysError(SysErr::EINTR)),
+                Err(e) => return Err(e),
+                Ok(()) => (),
+            }
+        }
+    }
+
+    // push inserts the message in msg after all messages of the same or
+    // higher priority, and notifies waiting receivers. It returns EAGAIN,
+    // leaving msg untouched, if the queue is full.
+    fn push(&self, task: &Task, msg: &mut Option<PosixMessage>) -> Result<()> {
+        let mut subscriber = None;
+        let queue;
+
+        {
+            let mut q = self.lock();
+            if q.messages.len() as i64 >= q.maxMessageCount {
+                return Err(Error::SysError(SysErr::EAGAIN));
+            }
+
+            let msg = msg.take().unwrap();
+            let wasEmpty = q.messages.len() == 0;
+
+            let mut idx = q.messages.len();
+            for (i, m) in q.messages.iter().enumerate() {
+                if m.Priority < msg.Priority {
+                    idx = i;
+                    break;
+                }
+            }
+
+            q.byteCount += msg.Text.len() as u64;
+            q.messages.insert(idx, msg);
+
+            // "Message notification occurs only when a new message arrives and
+            // the queue was previously empty. ... If some other process or
+            // thread is waiting to receive a message from an empty queue
+            // using mq_receive(3), then any message notification registration
+            // is ignored: the message is delivered to the process or thread
+            // calling mq_receive(3), and the message notification registration
+            // remains in effect." - mq_notify(3)
+            //
+            // "After notification occurs, the registration is removed and the
+            // message queue is available for further registration."
+            if wasEmpty && q.blockedReceivers == 0 {
+                subscriber = q.subscriber.take();
+            }
+
+            queue = q.queue.clone();
+        }
+
+        queue.Notify(READABLE_EVENT);
+
+        if let Some(mut s) = subscriber {
+            s.notify(task);
+        }
+
+        return Ok(());
+    }
+
+    // Receive removes and returns the highest priority message from the
+    // queue, blocking until a message arrives if block is true. deadline is
+    // an absolute CLOCK_REALTIME timeout. See mq_timedreceive(2).
+    pub fn Receive(
+        &self,
+        task: &Task,
+        size: i64,
+        block: bool,
+        deadline: Option<Time>,
+    ) -> Result<PosixMessage> {
+        // "EMSGSIZE msg_len was less than the mq_msgsize attribute of the
+        // message queue." - mq_receive(3)
+        if size < self.MaxMessageSize() {
+            return Err(Error::SysError(SysErr::EMSGSIZE));
+        }
+
+        match self.pop(false) {
+            Err(Error::SysError(SysErr::EAGAIN)) => (),
+            res => return res,
+        }
+
+        if !block {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
+
+        // Slow path: at this point, the queue was found to be empty, and we
+        // were asked to block.
+        let general = task.blocker.generalEntry.clone();
+        let queue = self.lock().queue.clone();
+
+        queue.EventRegister(task, &general, EVENT_IN);
+        defer!(queue.EventUnregister(task, &general));
+        loop {
+            match self.pop(true) {
+                Err(Error::SysError(SysErr::EAGAIN)) => (),
+                res => return res,
+            }
+
+            let res = task.blocker.BlockWithRealTimer(true, deadline);
+            self.lock().blockedReceivers -= 1;
+
+            match res {
+                // "mq_timedreceive() is never restarted after being
+                // interrupted by a signal handler" - signal(7)
+                Err(Error::ErrInterrupted) => return Err(Error::SysError(SysErr::EINTR)),
+                Err(e) => return Err(e),
+                Ok(()) => (),
+            }
+        }
+    }
+
+    // pop removes and returns the message at the head of the queue, and
+    // notifies waiting senders. If the queue is empty it returns EAGAIN, and
+    // if wait is true counts the caller as a blocked receiver until it
+    // decrements blockedReceivers again.
+    fn pop(&self, wait: bool) -> Result<PosixMessage> {
+        let msg;
+        let queue;
+
+        {
+            let mut q = self.lock();
+            msg = match q.messages.pop_front() {
+                None => {
+                    if wait {
+                        q.blockedReceivers += 1;
+                    }
+                    return Err(Error::SysError(SysErr::EAGAIN));
+                }
+                Some(m) => m,
+            };
+
+            q.byteCount -= msg.Text.len() as u64;
+            queue = q.queue.clone();
+        }
+
+        queue.Notify(WRITEABLE_EVENT);
+        return Ok(msg);
+    }
+
+    // Subscribe registers s for notification. See mq_notify(3).
+    pub fn Subscribe(&self, s: Subscriber) -> Result<()> {
+        let mut q = self.lock();
+
+        // "EBUSY Another process has already registered to receive
+        // notification for this message queue." - mq_notify(3)
+        //
+        // A registration whose process has exited is stale and replaced.
+        if let Some(ref s) = q.subscriber {
+            if s.tg.Upgrade().is_some() {
+                return Err(Error::SysError(SysErr::EBUSY));
+            }
+        }
+
+        q.subscriber = Some(s);
+        return Ok(());
+    }
+
+    // Unsubscribe removes the notification registration if it belongs to
+    // task's thread group.
+    pub fn Unsubscribe(&self, task: &Task) {
+        let subscriber = {
+            let mut q = self.lock();
+            let owner = match &q.subscriber {
+                None => false,
+                Some(s) => s.Owner(task),
+            };
+
+            if !owner {
+                return;
+            }
+
+            q.subscriber.take()
+        };
+
+        if let Some(mut s) = subscriber {
+            if s.sigev.Notify == SIGEV_THREAD {
+                s.sendCookie(NOTIFY_REMOVED);
+            }
+        }
+    }
+}
+
+// PosixQueueRegistryInternal holds the POSIX message queues of an IPC
+// namespace, as the contents of its mqueue filesystem root.
+pub struct PosixQueueRegistryInternal {
+    // userNS is the user namespace owning the IPC namespace this registry
+    // belongs to. Immutable.
+    pub userNS: UserNameSpace,
+
+    // root is the root directory of the mqueue filesystem. It is created on
+    // first use, since building it requires a task.
+    pub root: Option<Dirent>,
+}
+
+#[derive(Clone)]
+pub struct PosixQueueRegistry(Arc<QMutex<PosixQueueRegistryInternal>>);
+
+impl Deref for PosixQueueRegistry {
+    type Target = Arc<QMutex<PosixQueueRegistryInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<PosixQueueRegistryInternal>> {
+        &self.0
+    }
+}
+
+impl PosixQueueRegistry {
+    pub fn New(userNS: &UserNameSpace) -> Self {
+        let internal = PosixQueueRegistryInternal {
+            userNS: userNS.clone(),
+            root: None,
+        };
+
+        return Self(Arc::new(QMutex::new(internal)));
+    }
+
+    // Root returns the root directory of the registry's mqueue filesystem.
+    pub fn Root(&self, task: &Task) -> Dirent {
+        let mut r = self.lock();
+        return r.rootLocked(task);
+    }
+
+    // FindOrCreate opens the queue called name, creating it first if create
+    // is true and it doesn't exist. attr holds the maximum message count and
+    // size requested by the caller, if any. See mq_open(3).
+    pub fn FindOrCreate(
+        &self,
+        task: &Task,
+        name: &str,
+        flags: &FileFlags,
+        create: bool,
+        exclusive: bool,
+        perms: &FilePermissions,
+        attr: Option<MqAttr>,
+    ) -> Result<File> {
+        let mut r = self.lock();
+        let root = r.rootLocked(task);
+
+        match root.Walk(task, &root, name) {
+            Ok(d) => {
+                // "EEXIST Both O_CREAT and O_EXCL were specified in oflag, but
+                // a queue with this name already exists." - mq_open(3)
+                if create && exclusive {
+                    return Err(Error::SysError(SysErr::EEXIST));
+                }
+
+                let inode = d.Inode();
+                inode.CheckPermission(
+                    task,
+                    &PermMask {
+                        read: flags.Read,
+                        write: flags.Write,
+                        ..Default::default()
+                    },
+                )?;
+
+                return inode.GetFile(task, &d, flags);
+            }
+            Err(Error::SysError(SysErr::ENOENT)) => (),
+            Err(e) => return Err(e),
+        }
+
+        // "ENOENT The O_CREAT flag was not specified in oflag, and no queue
+        // with this name exists." - mq_open(3)
+        if !create {
+            return Err(Error::SysError(SysErr::ENOENT));
+        }
+
+        let dir = root.Inode();
+        dir.CheckPermission(
+            task,
+            &PermMask {
+                write: true,
+                execute: true,
+                ..Default::default()
+            },
+        )?;
+
+        let queue = match attr {
+            None => PosixQueue::NewDefault(),
+            Some(attr) => {
+                r.checkAttr(task, &attr)?;
+                PosixQueue::New(attr.MqMaxmsg, attr.MqMsgsize)
+            }
+        };
+
+        let mut inode = NewQueueInode(task, &dir, &task.FileOwner(), perms, &queue);
+        AddQueueToRoot(task, &dir, name, &mut inode)?;
+
+        let d = root.Walk(task, &root, name)?;
+        return inode.GetFile(task, &d, flags);
+    }
+
+    // Remove removes the queue called name. The queue itself is destroyed
+    // once all open descriptors referring to it are closed. See
+    // mq_unlink(3).
+    pub fn Remove(&self, task: &Task, name: &str) -> Result<()> {
+        let mut r = self.lock();
+        let root = r.rootLocked(task);
+
+        root.MayDelete(task, &root, name)?;
+        return root.Remove(task, &root, name, false);
+    }
+}
+
+impl PosixQueueRegistryInternal {
+    fn rootLocked(&mut self, task: &Task) -> Dirent {
+        if let Some(ref root) = self.root {
+            return root.clone();
+        }
+
+        let inode = NewMqueueRoot(task);
+        let root = Dirent::New(&inode, "/");
+        self.root = Some(root.clone());
+        return root;
+    }
+
+    // checkAttr validates the attributes passed to mq_open(3) when creating a
+    // queue. See ipc/mqueue.c:mq_attr_ok.
+    fn checkAttr(&self, task: &Task, attr: &MqAttr) -> Result<()> {
+        if attr.MqMaxmsg <= 0 || attr.MqMsgsize <= 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let creds = task.Creds();
+        if creds.HasCapabilityIn(Capability::CAP_SYS_RESOURCE, &self.userNS) {
+            if attr.MqMaxmsg > HARD_MSGMAX || attr.MqMsgsize > HARD_MSGSIZEMAX {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+        } else {
+            if attr.MqMaxmsg > DFLT_MSGMAX || attr.MqMsgsize > DFLT_MSGSIZEMAX {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -29,15 +29,6 @@ use super::waiter::*;
 use super::time::*;
 use super::ipc_namespace::*;
 
-// System-wide limit for maximum number of queues.
-pub const MAX_QUEUES : usize = MSGMNI;
-
-// Maximum size of a queue in bytes.
-pub const MAX_QUEUE_BYTES : usize = MSGMNB;
-
-// Maximum size of a message in bytes.
-pub const MAX_MESSAGE_BYTES : usize = MSGMAX;
-
 pub struct MQRegistryIntern(RegistryInternal<MsgQueue>);
 
 impl Deref for MQRegistryIntern {
@@ -65,7 +56,7 @@ impl MQRegistryIntern {
             receiveTime: Time::default(),
             changeTime: task.Now(),
             byteCount: 0,
-            maxBytes: MAX_QUEUE_BYTES as _,
+            maxBytes: task.IPCNamespace().Limits().msgMnb,
             sendPID: 0,
             receivePID: 0
         };
@@ -112,7 +103,7 @@ impl MQRegistry {
         }
 
         // Check system-wide limits.
-        if me.ObjectCount() > MAX_QUEUES {
+        if me.ObjectCount() > task.IPCNamespace().Limits().msgMni as usize {
             return Err(Error::SysError(SysErr::ENOSPC))
         }
 
@@ -143,13 +134,14 @@ impl MQRegistry {
     }
 
     // IPCInfo reports global parameters for message queues. See msgctl(IPC_INFO).
-    pub fn IPCInfo(&self, _task: &Task) -> MsgInfo {
+    pub fn IPCInfo(&self, task: &Task) -> MsgInfo {
+        let limits = task.IPCNamespace().Limits();
         return MsgInfo {
             MsgPool: MSGPOOL as _,
             MsgMap:  MSGMAP as _,
-            MsgMax:  MSGMAX as _,
-            MsgMnb:  MSGMNB as _,
-            MsgMni:  MSGMNI as _,
+            MsgMax:  limits.msgMax as _,
+            MsgMnb:  limits.msgMnb as _,
+            MsgMni:  limits.msgMni as _,
             MsgSsz:  MSGSSZ as _,
             MsgTql:  MSGTQL as _,
             MsgSeg: MSGSEG as _,
@@ -157,7 +149,8 @@ impl MQRegistry {
     }
 
     // MsgInfo reports global parameters for message queues. See msgctl(MSG_INFO).
-    pub fn MsgInfo(&self, _task: &Task) -> MsgInfo {
+    pub fn MsgInfo(&self, task: &Task) -> MsgInfo {
+        let limits = task.IPCNamespace().Limits();
         let me = self.lock();
 
         let mut messages = 0;
@@ -172,9 +165,9 @@ impl MQRegistry {
             MsgPool: me.ObjectCount() as _,
             MsgMap:  messages as _,
             MsgTql:  bytes as _,
-            MsgMax:  MSGMAX as _,
-            MsgMnb:  MSGMNB as _,
-            MsgMni:  MSGMNI as _,
+            MsgMax:  limits.msgMax as _,
+            MsgMnb:  limits.msgMnb as _,
+            MsgMni:  limits.msgMni as _,
             MsgSsz:  MSGSSZ as _,
             MsgSeg: MSGSEG as _,
         }
@@ -341,7 +334,7 @@ impl Mechanism<MsgQueue> {
                    truncate: bool,
                    except: bool,
                    pid: i32) -> Result<Message> {
-        if maxSize < 0 || maxSize > MAX_MESSAGE_BYTES as _ {
+        if maxSize < 0 || maxSize > task.IPCNamespace().Limits().msgMax as i64 {
             return Err(Error::SysError(SysErr::EINVAL));
         }
         let max = maxSize as u64;
@@ -466,7 +459,7 @@ impl Mechanism<MsgQueue> {
         let mut mech = self.lock();
 
         let creds = task.creds.clone();
-        if ds.MsgQbytes > MAX_QUEUE_BYTES as _
+        if ds.MsgQbytes > task.IPCNamespace().Limits().msgMnb
             && !creds.HasCapabilityIn(Capability::CAP_SYS_RESOURCE, &mech.userNS) {
             // "An attempt (IPC_SET) was made to increase msg_qbytes beyond the
             // system parameter MSGMNB, but the caller is not privileged (Linux:
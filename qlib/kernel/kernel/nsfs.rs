@@ -0,0 +1,96 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use super::super::super::auth::userns::*;
+use super::super::super::linux_def::*;
+use super::super::fs::mount::*;
+use super::super::threadmgr::pid_namespace::*;
+use super::ipc_namespace::*;
+use super::uts_namespace::*;
+
+// PROC_DYNAMIC_FIRST is the first inode number Linux hands out to
+// namespaces (fs/proc/generic.c).
+pub const PROC_DYNAMIC_FIRST: u64 = 0xF0000000;
+
+static NEXT_NAMESPACE_INODE: AtomicU64 = AtomicU64::new(PROC_DYNAMIC_FIRST);
+
+// NewNamespaceInode returns a new inode number identifying a namespace, as
+// shown by the /proc/[pid]/ns/* symlinks.
+pub fn NewNamespaceInode() -> u64 {
+    return NEXT_NAMESPACE_INODE.fetch_add(1, Ordering::SeqCst);
+}
+
+// Namespace is a reference to one of the namespaces a task may join with
+// setns(2).
+#[derive(Clone)]
+pub enum Namespace {
+    UTS(UTSNamespace),
+    IPC(IPCNamespace),
+    Mount(MountNs),
+    PID(PIDNamespace),
+    User(UserNameSpace),
+}
+
+impl Namespace {
+    // Type returns the CLONE_NEW* flag for the namespace's type.
+    pub fn Type(&self) -> i32 {
+        match self {
+            Self::UTS(_) => CloneOp::CLONE_NEWUTS,
+            Self::IPC(_) => CloneOp::CLONE_NEWIPC,
+            Self::Mount(_) => CloneOp::CLONE_NEWNS,
+            Self::PID(_) => CloneOp::CLONE_NEWPID,
+            Self::User(_) => CloneOp::CLONE_NEWUSER,
+        }
+    }
+
+    // Name returns the name of the namespace's type, as used in the
+    // /proc/[pid]/ns/* symlink targets.
+    pub fn Name(&self) -> &'static str {
+        match self {
+            Self::UTS(_) => "uts",
+            Self::IPC(_) => "ipc",
+            Self::Mount(_) => "mnt",
+            Self::PID(_) => "pid",
+            Self::User(_) => "user",
+        }
+    }
+
+    // Inode returns the namespace's inode number.
+    pub fn Inode(&self) -> u64 {
+        match self {
+            Self::UTS(ns) => ns.ID(),
+            Self::IPC(ns) => ns.ID(),
+            Self::Mount(ns) => ns.ID(),
+            Self::PID(ns) => ns.ID(),
+            Self::User(ns) => ns.ID(),
+        }
+    }
+
+    // UserNamespace returns the user namespace that owns the namespace.
+    pub fn UserNamespace(&self) -> UserNameSpace {
+        match self {
+            Self::UTS(ns) => ns.UserNamespace(),
+            Self::IPC(ns) => ns.userNS.clone(),
+            Self::Mount(ns) => ns.UserNamespace(),
+            Self::PID(ns) => ns.UserNamespace(),
+            Self::User(ns) => match ns.lock().parent.clone() {
+                None => ns.clone(),
+                Some(p) => p,
+            },
+        }
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::collections::btree_set::BTreeSet;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use super::super::super::common::*;
+use super::super::super::linux_def::*;
+use super::super::super::loader::MemoryLimit;
+use super::super::super::usage::memory::TotalMemory;
+use super::super::boot::controller::WriteOOMEventResponse;
+use super::super::task::*;
+use super::super::threadmgr::thread::*;
+use super::super::threadmgr::thread_group::*;
+use super::super::SignalDef::*;
+use super::kernel::*;
+
+// Bounds of /proc/[pid]/oom_score_adj. A thread group whose oom_score_adj is
+// OOM_SCORE_ADJ_MIN is never chosen by the OOM killer.
+pub const OOM_SCORE_ADJ_MIN: i32 = -1000;
+pub const OOM_SCORE_ADJ_MAX: i32 = 1000;
+
+// MemCgroup is the memory controller of a container. It enforces the memory
+// limit from the linux.resources of the container's OCI spec.
+//
+// The memory usage of a container is the sum of the RSS of the memory
+// managers of its processes and of the tmpfs pages charged to it.
+#[derive(Default, Debug, Clone)]
+pub struct MemCgroup {
+    // limit is the maximum memory usage of the container in bytes. 0 means
+    // that the container is not limited.
+    pub limit: u64,
+
+    // disableOOMKiller makes charges over limit fail with ENOMEM instead of
+    // killing a process of the container.
+    pub disableOOMKiller: bool,
+
+    // oomScoreAdj is the oom_score_adj of the processes started by the
+    // container runtime.
+    pub oomScoreAdj: i32,
+
+    // oomKills is the number of processes killed by the OOM killer.
+    pub oomKills: u64,
+
+    // shmem is the size in bytes of the tmpfs pages charged to the
+    // container.
+    pub shmem: u64,
+
+    // usage is the memory usage of the container in bytes when it was last
+    // measured, and charged is the size of the charges since then. The usage
+    // is only measured again, which walks the processes of the container,
+    // when usage + charged reaches limit.
+    pub usage: u64,
+    pub charged: u64,
+}
+
+impl MemCgroup {
+    pub fn New(limit: &MemoryLimit) -> Self {
+        let mut oomScoreAdj = limit.OOMScoreAdj;
+        if oomScoreAdj < OOM_SCORE_ADJ_MIN {
+            oomScoreAdj = OOM_SCORE_ADJ_MIN;
+        } else if oomScoreAdj > OOM_SCORE_ADJ_MAX {
+            oomScoreAdj = OOM_SCORE_ADJ_MAX;
+        }
+
+        return Self {
+            limit: limit.Limit,
+            disableOOMKiller: limit.DisableOOMKiller,
+            oomScoreAdj: oomScoreAdj,
+            oomKills: 0,
+            shmem: 0,
+            usage: 0,
+            charged: 0,
+        };
+    }
+
+    // TotalPages returns the number of pages the badness of the processes in
+    // the container is relative to.
+    pub fn TotalPages(&self) -> u64 {
+        let total = if self.limit != 0 {
+            self.limit
+        } else {
+            TotalMemory(0, 0)
+        };
+
+        return total / MemoryDef::PAGE_SIZE;
+    }
+}
+
+// ThreadGroupRSS returns the ID of the memory manager of tg and its RSS in
+// bytes, or None if the leader of tg has been released.
+fn ThreadGroupRSS(tg: &ThreadGroup) -> Option<(u64, u64)> {
+    let leader = match tg.lock().leader.Upgrade() {
+        None => return None,
+        Some(l) => l,
+    };
+
+    let mm = leader.MemoryManager();
+    return Some((mm.uid, mm.ResidentSetSize()));
+}
+
+// ThreadGroupAnonRSS returns the id of the memory manager of tg and the size of
+// its resident anonymous pages.
+fn ThreadGroupAnonRSS(tg: &ThreadGroup) -> Option<(u64, u64)> {
+    let leader = match tg.lock().leader.Upgrade() {
+        None => return None,
+        Some(l) => l,
+    };
+
+    let mm = leader.MemoryManager();
+    return Some((mm.uid, mm.AnonResidentSetSize()));
+}
+
+// OOMBadness returns the badness of tg, which is its RSS in pages adjusted by
+// its oom_score_adj in thousandths of totalPages. It returns None if tg must
+// not be killed.
+//
+// See Linux's mm/oom_kill.c:oom_badness().
+pub fn OOMBadness(tg: &ThreadGroup, totalPages: u64) -> Option<i64> {
+    let oomScoreAdj = tg.lock().oomScoreAdj;
+    if oomScoreAdj == OOM_SCORE_ADJ_MIN || tg.lock().liveTasks == 0 {
+        return None;
+    }
+
+    let rss = match ThreadGroupRSS(tg) {
+        None => return None,
+        Some((_, rss)) => rss,
+    };
+
+    let points = (rss / MemoryDef::PAGE_SIZE) as i64;
+    return Some(points + oomScoreAdj as i64 * (totalPages / 1000) as i64);
+}
+
+impl Kernel {
+    pub fn SetMemCgroup(&self, cid: &str, limit: &MemoryLimit) {
+        self.memCgroups
+            .lock()
+            .insert(cid.to_string(), MemCgroup::New(limit));
+    }
+
+    // UpdateMemCgroup replaces the memory limit of the container, keeping its
+    // OOM kill count and tmpfs usage.
+    pub fn UpdateMemCgroup(&self, cid: &str, limit: &MemoryLimit) {
+        let mut cgroups = self.memCgroups.lock();
+        let mut cg = MemCgroup::New(limit);
+        if let Some(old) = cgroups.get(cid) {
+            cg.oomKills = old.oomKills;
+            cg.shmem = old.shmem;
+            cg.usage = old.usage;
+            cg.charged = old.charged;
+        }
+        cgroups.insert(cid.to_string(), cg);
+    }
+
+    pub fn RemoveMemCgroup(&self, cid: &str) {
+        self.memCgroups.lock().remove(cid);
+    }
+
+    pub fn MemCgroup(&self, cid: &str) -> Option<MemCgroup> {
+        return self.memCgroups.lock().get(cid).cloned();
+    }
+
+    // ChargeShmem adds len bytes of tmpfs pages to the memory usage of the
+    // container. The caller must have checked the charge with ChargeMemory.
+    pub fn ChargeShmem(&self, cid: &str, len: u64) {
+        if let Some(cg) = self.memCgroups.lock().get_mut(cid) {
+            cg.shmem += len;
+        }
+    }
+
+    pub fn UnchargeShmem(&self, cid: &str, len: u64) {
+        if let Some(cg) = self.memCgroups.lock().get_mut(cid) {
+            cg.shmem = cg.shmem.saturating_sub(len);
+        }
+    }
+
+    // ContainerOOMScoreAdj returns the oom_score_adj of a new process started
+    // in the container by the container runtime.
+    pub fn ContainerOOMScoreAdj(&self, cid: &str) -> i32 {
+        match self.memCgroups.lock().get(cid) {
+            None => return 0,
+            Some(cg) => return cg.oomScoreAdj,
+        }
+    }
+
+    // ContainerThreadGroups returns the thread groups of the container with
+    // their thread group IDs in the root PID namespace.
+    pub fn ContainerThreadGroups(&self, cid: &str) -> Vec<(ThreadGroup, ThreadID)> {
+        let _r = self.tasks.ReadLock();
+        let root = self.tasks.Root();
+
+        let mut tgs = Vec::new();
+        for (tg, tgid) in root.lock().tgids.iter() {
+            if &tg.lock().containerID == cid {
+                tgs.push((tg.clone(), *tgid));
+            }
+        }
+
+        return tgs;
+    }
+
+    // ContainerMemoryUsage returns the memory usage of the container in bytes.
+    pub fn ContainerMemoryUsage(&self, cid: &str) -> u64 {
+        let tgs = self.ContainerThreadGroups(cid);
+
+        // Thread groups created by vfork share the memory manager of their
+        // parent until they exec, count it once.
+        let mut mms = BTreeSet::new();
+        let mut usage = match self.memCgroups.lock().get(cid) {
+            None => 0,
+            Some(cg) => cg.shmem,
+        };
+        for (tg, _) in &tgs {
+            if let Some((mmID, rss)) = ThreadGroupAnonRSS(tg) {
+                if mms.insert(mmID) {
+                    usage += rss;
+                }
+            }
+        }
+
+        return usage;
+    }
+
+    // OOMScore returns the value of /proc/[pid]/oom_score for tg, the badness
+    // of tg scaled to [0, 2000].
+    //
+    // See Linux's fs/proc/base.c:proc_oom_score().
+    pub fn OOMScore(&self, tg: &ThreadGroup) -> i64 {
+        let totalPages = TotalMemory(0, 0) / MemoryDef::PAGE_SIZE;
+        let badness = match OOMBadness(tg, totalPages) {
+            None => return 0,
+            Some(b) => b,
+        };
+
+        let score = (1000 + badness * 1000 / totalPages as i64) * 2 / 3;
+        if score < 0 {
+            return 0;
+        }
+
+        return score;
+    }
+
+    // ChargeMemory checks that the container of task can grow its memory
+    // usage by len bytes. Pages of an address space are charged once they are
+    // faulted in or populated, see MemoryManager::ChargePending, so address
+    // space reservations never invoke the OOM killer.
+    //
+    // If the charge would exceed the memory limit of the container, the OOM
+    // killer sends SIGKILL to the process of the container with the highest
+    // badness and reports the OOM event to the container runtime. The charge
+    // then succeeds, as the memory of the victim is released when it exits,
+    // unless the victim is the process of task itself. It fails with ENOMEM if
+    // the OOM killer is disabled, len is larger than the limit or there is no
+    // process that can be killed.
+    //
+    // Preconditions: The caller must not hold the mapping lock of any
+    // MemoryManager or the TaskSet lock.
+    pub fn ChargeMemory(&self, task: &Task, len: u64) -> Result<()> {
+        let tg = task.Thread().ThreadGroup();
+        let cid = tg.lock().containerID.clone();
+
+        let cg = {
+            let mut cgroups = self.memCgroups.lock();
+            let cg = match cgroups.get_mut(&cid) {
+                None => return Ok(()),
+                Some(cg) => cg,
+            };
+
+            if cg.limit == 0 || cg.usage + cg.charged + len <= cg.limit {
+                cg.charged += len;
+                return Ok(());
+            }
+
+            cg.clone()
+        };
+
+        let usage = self.ContainerMemoryUsage(&cid);
+        let fits = usage + len <= cg.limit;
+        if let Some(cg) = self.memCgroups.lock().get_mut(&cid) {
+            cg.usage = usage;
+            cg.charged = if fits { len } else { 0 };
+        }
+
+        if fits {
+            return Ok(());
+        }
+
+        // Killing processes can't make room for a charge larger than the
+        // limit.
+        if cg.disableOOMKiller || len > cg.limit {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        let totalPages = cg.TotalPages();
+        let mut victim: Option<(ThreadGroup, ThreadID, i64)> = None;
+        for (t, tgid) in self.ContainerThreadGroups(&cid) {
+            // Don't kill another process while an earlier victim is still
+            // releasing its memory.
+            if t.lock().oomKilled && t.lock().liveTasks > 0 {
+                if t == tg {
+                    return Err(Error::SysError(SysErr::ENOMEM));
+                }
+                return Ok(());
+            }
+
+            let badness = match OOMBadness(&t, totalPages) {
+                None => continue,
+                Some(b) => b,
+            };
+
+            let better = match &victim {
+                None => true,
+                Some((_, _, max)) => badness > *max,
+            };
+
+            if better {
+                victim = Some((t, tgid, badness));
+            }
+        }
+
+        let (victim, tgid, _) = match victim {
+            None => return Err(Error::SysError(SysErr::ENOMEM)),
+            Some(v) => v,
+        };
+
+        self.OOMKill(&cid, &victim, tgid, cg.limit);
+
+        if victim == tg {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        return Ok(());
+    }
+
+    // OOMKill kills the victim of the OOM killer of container cid.
+    fn OOMKill(&self, cid: &str, victim: &ThreadGroup, tgid: ThreadID, limit: u64) {
+        let name = match victim.lock().leader.Upgrade() {
+            None => String::new(),
+            Some(leader) => leader.lock().name.to_string(),
+        };
+
+        let rss = match ThreadGroupRSS(victim) {
+            None => 0,
+            Some((_, rss)) => rss,
+        };
+
+        error!(
+            "Memory cgroup out of memory: container {} limit {}kB, killed process {} ({}) rss:{}kB oom_score_adj:{}",
+            cid,
+            limit / 1024,
+            tgid,
+            name,
+            rss / 1024,
+            victim.lock().oomScoreAdj
+        );
+
+        victim.lock().oomKilled = true;
+        match victim.SendSignal(&SignalInfo::SignalInfoPriv(Signal(Signal::SIGKILL))) {
+            Ok(()) => (),
+            Err(e) => info!("OOMKill: fail to kill process {} with error {:?}", tgid, e),
+        }
+
+        if let Some(cg) = self.memCgroups.lock().get_mut(cid) {
+            cg.oomKills += 1;
+        }
+
+        WriteOOMEventResponse(cid.to_string());
+    }
+}
@@ -0,0 +1,608 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::sync::Arc;
+use alloc::sync::Weak;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::ops::Deref;
+
+use super::super::super::common::*;
+use super::super::super::linux::perf_event::*;
+use super::super::super::linux_def::*;
+use super::super::kernel::waiter::*;
+use super::super::task::*;
+use super::super::threadmgr::task_sched::*;
+use super::super::threadmgr::thread::*;
+use super::super::uid::*;
+use super::super::Tsc;
+use super::super::TSC;
+
+use super::super::fs::anon::*;
+use super::super::fs::attr::*;
+use super::super::fs::dentry::*;
+use super::super::fs::dirent::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::fs::host::hostinodeop::*;
+
+// PerfEventInternal is a software counter attached to a single task.
+pub struct PerfEventInternal {
+    // id is the value returned by PERF_EVENT_IOC_ID and PERF_FORMAT_ID.
+    pub id: u64,
+
+    pub attr: PerfEventAttr,
+
+    // sched is the scheduling state of the counted task, which holds the
+    // raw counters. It outlives the task, so the event keeps its final value
+    // after the task exits.
+    pub sched: TaskSchedInfo,
+
+    pub enabled: bool,
+
+    // count and timeEnabled are accumulated over the previous periods in
+    // which the event was enabled.
+    pub count: u64,
+    pub timeEnabled: u64,
+
+    // base and timeBase are the raw counter and the task clock at the time
+    // the event was last enabled or reset.
+    pub base: u64,
+    pub timeBase: u64,
+
+    // leader is the group leader, if the event was created as a member of
+    // another event's group.
+    pub leader: Option<PerfEventWeak>,
+
+    // siblings are the other members of the group this event leads. A
+    // sibling leaves the group when its file is closed.
+    pub siblings: Vec<PerfEventWeak>,
+
+    // children are the events inherited by the task's descendants. Their
+    // counts are included in this event's.
+    pub children: Vec<PerfEvent>,
+}
+
+impl PerfEventInternal {
+    // rawAt returns the raw value of the counter and of the task clock, in
+    // nanoseconds, at time now.
+    fn rawAt(&self, now: i64) -> (u64, u64) {
+        let s = *self.sched.lock();
+        let user = (Tsc::Scale(s.userTicksAt(now)) * 1000) as u64;
+        let sys = (Tsc::Scale(s.sysTicksAt(now)) * 1000) as u64;
+
+        let val = match self.attr.Config {
+            PERF_COUNT_SW_CPU_CLOCK | PERF_COUNT_SW_TASK_CLOCK => {
+                let mut clock = 0;
+                if !self.attr.ExcludeUser() {
+                    clock += user;
+                }
+                if !self.attr.ExcludeKernel() {
+                    clock += sys;
+                }
+                clock
+            }
+            PERF_COUNT_SW_PAGE_FAULTS | PERF_COUNT_SW_PAGE_FAULTS_MIN => s.PageFaults,
+            PERF_COUNT_SW_CONTEXT_SWITCHES => s.YieldCount,
+            PERF_COUNT_SW_CPU_MIGRATIONS => s.Migrations,
+            // Pages are never read from backing storage on fault, and
+            // alignment and emulation faults are not emulated.
+            _ => 0,
+        };
+
+        return (val, user + sys);
+    }
+
+    fn enableAt(&mut self, now: i64) {
+        if self.enabled {
+            return;
+        }
+
+        let (val, time) = self.rawAt(now);
+        self.base = val;
+        self.timeBase = time;
+        self.enabled = true;
+    }
+
+    fn disableAt(&mut self, now: i64) {
+        if !self.enabled {
+            return;
+        }
+
+        let (val, time) = self.rawAt(now);
+        self.count += val.saturating_sub(self.base);
+        self.timeEnabled += time.saturating_sub(self.timeBase);
+        self.enabled = false;
+    }
+
+    fn resetAt(&mut self, now: i64) {
+        let (val, _) = self.rawAt(now);
+        self.count = 0;
+        self.base = val;
+    }
+}
+
+#[derive(Clone)]
+pub struct PerfEvent(Arc<QMutex<PerfEventInternal>>);
+
+impl Deref for PerfEvent {
+    type Target = Arc<QMutex<PerfEventInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<PerfEventInternal>> {
+        &self.0
+    }
+}
+
+impl PartialEq for PerfEvent {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+#[derive(Clone)]
+pub struct PerfEventWeak(Weak<QMutex<PerfEventInternal>>);
+
+impl PerfEventWeak {
+    pub fn Upgrade(&self) -> Option<PerfEvent> {
+        return self.0.upgrade().map(|e| PerfEvent(e));
+    }
+}
+
+impl PerfEvent {
+    pub fn New(attr: &PerfEventAttr, sched: &TaskSchedInfo, leader: Option<&PerfEvent>) -> Self {
+        let internal = PerfEventInternal {
+            id: NewUID(),
+            attr: *attr,
+            sched: sched.clone(),
+            enabled: false,
+            count: 0,
+            timeEnabled: 0,
+            base: 0,
+            timeBase: 0,
+            leader: leader.map(|l| l.Downgrade()),
+            siblings: Vec::new(),
+            children: Vec::new(),
+        };
+
+        let e = Self(Arc::new(QMutex::new(internal)));
+        if !attr.Disabled() {
+            e.lock().enableAt(TSC.Rdtsc());
+        }
+
+        if let Some(leader) = leader {
+            leader.lock().siblings.push(e.Downgrade());
+        }
+
+        return e;
+    }
+
+    pub fn Downgrade(&self) -> PerfEventWeak {
+        return PerfEventWeak(Arc::downgrade(&self.0));
+    }
+
+    pub fn Id(&self) -> u64 {
+        return self.lock().id;
+    }
+
+    pub fn Attr(&self) -> PerfEventAttr {
+        return self.lock().attr;
+    }
+
+    pub fn IsCounting(&self, sched: &TaskSchedInfo) -> bool {
+        return Arc::ptr_eq(self.lock().sched.deref(), sched.deref());
+    }
+
+    // IsGroupLeader returns true if the event is not a member of another
+    // event's group. An event whose leader has been closed is promoted to
+    // leading its own group.
+    pub fn IsGroupLeader(&self) -> bool {
+        return self.Leader() == *self;
+    }
+
+    pub fn Leader(&self) -> PerfEvent {
+        let leader = self.lock().leader.clone();
+        match leader.and_then(|l| l.Upgrade()) {
+            None => return self.clone(),
+            Some(l) => return l,
+        }
+    }
+
+    // Group returns the group leader followed by the remaining members of the
+    // group.
+    pub fn Group(&self) -> Vec<PerfEvent> {
+        let leader = self.Leader();
+        let mut group = vec![leader.clone()];
+        let mut l = leader.lock();
+        l.siblings.retain(|s| match s.Upgrade() {
+            None => false,
+            Some(s) => {
+                group.push(s);
+                true
+            }
+        });
+
+        return group;
+    }
+
+    // Values returns the value of the counter, the time it was enabled and
+    // the time it was running, including the counts of inherited events.
+    // Events are never multiplexed, so they run whenever they are enabled.
+    pub fn Values(&self, now: i64) -> (u64, u64, u64) {
+        let (mut val, mut time, children) = {
+            let e = self.lock();
+            let mut val = e.count;
+            let mut time = e.timeEnabled;
+            if e.enabled {
+                let (v, t) = e.rawAt(now);
+                val += v.saturating_sub(e.base);
+                time += t.saturating_sub(e.timeBase);
+            }
+            (val, time, e.children.clone())
+        };
+
+        for c in &children {
+            let (v, t, _) = c.Values(now);
+            val += v;
+            time += t;
+        }
+
+        return (val, time, time);
+    }
+
+    // forEachChild applies f to the event and to every event inherited from
+    // it, as Linux's kernel/events/core.c:perf_event_for_each_child.
+    fn forEachChild(&self, f: &mut impl FnMut(&mut PerfEventInternal)) {
+        let children = {
+            let mut e = self.lock();
+            f(&mut e);
+            e.children.clone()
+        };
+
+        for c in &children {
+            c.forEachChild(f);
+        }
+    }
+
+    pub fn Enable(&self) {
+        let now = TSC.Rdtsc();
+        self.forEachChild(&mut |e| e.enableAt(now));
+    }
+
+    pub fn Disable(&self) {
+        let now = TSC.Rdtsc();
+        self.forEachChild(&mut |e| e.disableAt(now));
+    }
+
+    pub fn Reset(&self) {
+        let now = TSC.Rdtsc();
+        self.forEachChild(&mut |e| e.resetAt(now));
+    }
+
+    // Inherit creates the event counting the task with scheduling state
+    // sched, which has just been cloned from the task counted by this event.
+    pub fn Inherit(&self, sched: &TaskSchedInfo) -> PerfEvent {
+        let (attr, enabled) = {
+            let e = self.lock();
+            (e.attr, e.enabled)
+        };
+
+        let mut internal = PerfEventInternal {
+            id: NewUID(),
+            attr: attr,
+            sched: sched.clone(),
+            enabled: false,
+            count: 0,
+            timeEnabled: 0,
+            base: 0,
+            timeBase: 0,
+            leader: None,
+            siblings: Vec::new(),
+            children: Vec::new(),
+        };
+
+        if enabled {
+            internal.enableAt(TSC.Rdtsc());
+        }
+
+        let child = PerfEvent(Arc::new(QMutex::new(internal)));
+        self.lock().children.push(child.clone());
+        return child;
+    }
+
+    // Read implements read(2) with the event's read_format. Source:
+    // kernel/events/core.c:perf_read_one and perf_read_group.
+    pub fn Read(&self, task: &Task, dsts: &mut [IoVec]) -> Result<i64> {
+        let format = self.Attr().ReadFormat;
+        let now = TSC.Rdtsc();
+
+        let mut vals: Vec<u64> = Vec::new();
+        if format & PERF_FORMAT_GROUP != 0 {
+            let group = self.Group();
+            let (_, enabled, running) = group[0].Values(now);
+            vals.push(group.len() as u64);
+            if format & PERF_FORMAT_TOTAL_TIME_ENABLED != 0 {
+                vals.push(enabled);
+            }
+            if format & PERF_FORMAT_TOTAL_TIME_RUNNING != 0 {
+                vals.push(running);
+            }
+            for e in &group {
+                let (val, _, _) = e.Values(now);
+                vals.push(val);
+                if format & PERF_FORMAT_ID != 0 {
+                    vals.push(e.Id());
+                }
+                if format & PERF_FORMAT_LOST != 0 {
+                    vals.push(0);
+                }
+            }
+        } else {
+            let (val, enabled, running) = self.Values(now);
+            vals.push(val);
+            if format & PERF_FORMAT_TOTAL_TIME_ENABLED != 0 {
+                vals.push(enabled);
+            }
+            if format & PERF_FORMAT_TOTAL_TIME_RUNNING != 0 {
+                vals.push(running);
+            }
+            if format & PERF_FORMAT_ID != 0 {
+                vals.push(self.Id());
+            }
+            if format & PERF_FORMAT_LOST != 0 {
+                vals.push(0);
+            }
+        }
+
+        // "If you attempt to read into a buffer that is not big enough to
+        // hold the data, the error ENOSPC results." - perf_event_open(2)
+        let size = vals.len() * 8;
+        if IoVec::NumBytes(dsts) < size {
+            return Err(Error::SysError(SysErr::ENOSPC));
+        }
+
+        let bytes = unsafe { core::slice::from_raw_parts(vals.as_ptr() as *const u8, size) };
+        task.CopyDataOutToIovs(bytes, dsts, false)?;
+        return Ok(size as i64);
+    }
+}
+
+// InheritPerfEvents returns the events counting a task with scheduling state
+// sched that has just been cloned from a task counted by events.
+//
+// "inherit ... counts events of descendant tasks as well as the task
+// specified. This applies only to new children, not to any existing
+// children at the time the counter is created" - perf_event_open(2)
+pub fn InheritPerfEvents(events: &[PerfEventWeak], sched: &TaskSchedInfo) -> Vec<PerfEventWeak> {
+    let mut inherited = Vec::new();
+    for e in events {
+        if let Some(e) = e.Upgrade() {
+            if e.Attr().Inherit() {
+                inherited.push(e.Inherit(sched).Downgrade());
+            }
+        }
+    }
+
+    return inherited;
+}
+
+// AttachPerfEvent adds e to the events counting the thread t, so that it can
+// be inherited by t's children and enabled on exec.
+pub fn AttachPerfEvent(t: &Thread, e: &PerfEvent) {
+    let mut t = t.lock();
+    t.perfEvents.retain(|e| e.Upgrade().is_some());
+    t.perfEvents.push(e.Downgrade());
+}
+
+// EnablePerfEventsOnExec enables the events counting t that were created
+// with enable_on_exec, after t has successfully executed a new image.
+pub fn EnablePerfEventsOnExec(t: &Thread) {
+    let events = t.lock().perfEvents.clone();
+    for e in &events {
+        if let Some(e) = e.Upgrade() {
+            if e.Attr().EnableOnExec() {
+                e.Enable();
+            }
+        }
+    }
+}
+
+pub fn NewPerfEventFile(task: &Task, event: &PerfEvent) -> File {
+    // name matches kernel/events/core.c:perf_event_open.
+    let inode = NewAnonInode(task);
+    let dirent = Dirent::New(&inode, "anon_inode:[perf_event]");
+
+    let ops = PerfEventOperations {
+        event: event.clone(),
+    };
+
+    return File::New(
+        &dirent,
+        &FileFlags {
+            Read: true,
+            Write: true,
+            ..Default::default()
+        },
+        ops.into(),
+    );
+}
+
+// PerfEventOperations implements a file descriptor returned by
+// perf_event_open(2). Only counting mode is supported; there is no sampling
+// ring buffer to mmap.
+#[derive(Clone)]
+pub struct PerfEventOperations {
+    pub event: PerfEvent,
+}
+
+impl PerfEventOperations {
+    pub fn Event(&self) -> PerfEvent {
+        return self.event.clone();
+    }
+}
+
+impl Waitable for PerfEventOperations {
+    // Counting events never have samples to read.
+    fn Readiness(&self, _task: &Task, _mask: EventMask) -> EventMask {
+        return 0;
+    }
+}
+
+impl SpliceOperations for PerfEventOperations {}
+
+impl FileOperations for PerfEventOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::PerfEventOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return false;
+    }
+
+    fn Seek(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _whence: i32,
+        _current: i64,
+        _offset: i64,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ESPIPE));
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return self.event.Read(task, dsts);
+    }
+
+    fn WriteAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
+        // "If the ioctl argument is PERF_IOC_FLAG_GROUP, then the operation
+        // is applied to all events in a group" - perf_event_open(2)
+        let events = if val & PERF_IOC_FLAG_GROUP != 0 {
+            self.event.Group()
+        } else {
+            vec![self.event.clone()]
+        };
+
+        match request {
+            PERF_EVENT_IOC_ENABLE => {
+                for e in &events {
+                    e.Enable();
+                }
+                return Ok(());
+            }
+            PERF_EVENT_IOC_DISABLE => {
+                for e in &events {
+                    e.Disable();
+                }
+                return Ok(());
+            }
+            PERF_EVENT_IOC_RESET => {
+                for e in &events {
+                    e.Reset();
+                }
+                return Ok(());
+            }
+            PERF_EVENT_IOC_ID => {
+                let id = self.event.Id();
+                task.CopyOutObj(&id, val)?;
+                return Ok(());
+            }
+            // These only apply to sampling events.
+            PERF_EVENT_IOC_REFRESH
+            | PERF_EVENT_IOC_PERIOD
+            | PERF_EVENT_IOC_SET_OUTPUT
+            | PERF_EVENT_IOC_SET_FILTER
+            | PERF_EVENT_IOC_SET_BPF
+            | PERF_EVENT_IOC_PAUSE_OUTPUT
+            | PERF_EVENT_IOC_QUERY_BPF
+            | PERF_EVENT_IOC_MODIFY_ATTRIBUTES => {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+            _ => return Err(Error::SysError(SysErr::ENOTTY)),
+        }
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for PerfEventOperations {}
@@ -0,0 +1,199 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::any::Any;
+
+use super::super::super::common::*;
+use super::super::super::linux_def::*;
+use super::super::kernel::waiter::*;
+use super::super::task::*;
+use super::super::threadmgr::thread_group::*;
+
+use super::super::fs::anon::*;
+use super::super::fs::attr::*;
+use super::super::fs::dentry::*;
+use super::super::fs::dirent::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::fs::host::hostinodeop::*;
+
+// Constants for pidfd_open(2).
+pub const PIDFD_NONBLOCK: i32 = Flags::O_NONBLOCK;
+
+// NewPidfd returns a pidfd referring to the thread group tg.
+pub fn NewPidfd(task: &Task, tg: &ThreadGroup, nonBlocking: bool) -> File {
+    // name matches kernel/pid.c:pidfd_create.
+    let inode = NewAnonInode(task);
+    let dirent = Dirent::New(&inode, "anon_inode:[pidfd]");
+
+    let ops = PidfdOperations { tg: tg.clone() };
+
+    return File::New(
+        &dirent,
+        &FileFlags {
+            Read: true,
+            Write: true,
+            NonBlocking: nonBlocking,
+            ..Default::default()
+        },
+        ops.into(),
+    );
+}
+
+// PidfdOperations implements a file descriptor referring to a process, as
+// returned by pidfd_open(2) and clone(2) with CLONE_PIDFD. The process is
+// identified by its thread group, so the pidfd keeps referring to the same
+// process even after its PID is reused.
+#[derive(Clone)]
+pub struct PidfdOperations {
+    pub tg: ThreadGroup,
+}
+
+impl PidfdOperations {
+    pub fn ThreadGroup(&self) -> ThreadGroup {
+        return self.tg.clone();
+    }
+
+    // Exited returns true if every task in the thread group has become a
+    // zombie. Equivalent to kernel/pid.c:pidfd_poll's thread_group_exited.
+    pub fn Exited(&self) -> bool {
+        return self.tg.lock().liveTasks == 0;
+    }
+}
+
+impl Waitable for PidfdOperations {
+    // "When the process that it refers to terminates, the file descriptor
+    // indicates as readable." - pidfd_open(2)
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        if self.Exited() {
+            return mask & READABLE_EVENT;
+        }
+
+        return 0;
+    }
+
+    fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
+        let q = self.tg.lock().pidfdQueue.clone();
+        q.EventRegister(task, e, mask)
+    }
+
+    fn EventUnregister(&self, task: &Task, e: &WaitEntry) {
+        let q = self.tg.lock().pidfdQueue.clone();
+        q.EventUnregister(task, e)
+    }
+}
+
+impl SpliceOperations for PidfdOperations {}
+
+impl FileOperations for PidfdOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::PidfdOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return false;
+    }
+
+    fn Seek(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _whence: i32,
+        _current: i64,
+        _offset: i64,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ESPIPE));
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _dsts: &mut [IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn WriteAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, _task: &Task, _f: &File, _fd: i32, _request: u64, _val: u64) -> Result<()> {
+        return Err(Error::SysError(SysErr::ENOTTY));
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for PidfdOperations {}
@@ -0,0 +1,395 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::super::super::common::*;
+use super::super::super::linux::seccomp::*;
+use super::super::super::linux_def::*;
+use super::super::boot::oci::*;
+use super::kernel::*;
+
+// SYSCALL_NAMES maps the x86_64 syscall numbers 0 to 334 to their names, as
+// used by the OCI seccomp profiles.
+pub const SYSCALL_NAMES: [&str; 335] = [
+    "read", "write", "open", "close", "stat", "fstat", "lstat", "poll",
+    "lseek", "mmap", "mprotect", "munmap", "brk", "rt_sigaction", "rt_sigprocmask", "rt_sigreturn",
+    "ioctl", "pread64", "pwrite64", "readv", "writev", "access", "pipe", "select",
+    "sched_yield", "mremap", "msync", "mincore", "madvise", "shmget", "shmat", "shmctl",
+    "dup", "dup2", "pause", "nanosleep", "getitimer", "alarm", "setitimer", "getpid",
+    "sendfile", "socket", "connect", "accept", "sendto", "recvfrom", "sendmsg", "recvmsg",
+    "shutdown", "bind", "listen", "getsockname", "getpeername", "socketpair", "setsockopt", "getsockopt",
+    "clone", "fork", "vfork", "execve", "exit", "wait4", "kill", "uname",
+    "semget", "semop", "semctl", "shmdt", "msgget", "msgsnd", "msgrcv", "msgctl",
+    "fcntl", "flock", "fsync", "fdatasync", "truncate", "ftruncate", "getdents", "getcwd",
+    "chdir", "fchdir", "rename", "mkdir", "rmdir", "creat", "link", "unlink",
+    "symlink", "readlink", "chmod", "fchmod", "chown", "fchown", "lchown", "umask",
+    "gettimeofday", "getrlimit", "getrusage", "sysinfo", "times", "ptrace", "getuid", "syslog",
+    "getgid", "setuid", "setgid", "geteuid", "getegid", "setpgid", "getppid", "getpgrp",
+    "setsid", "setreuid", "setregid", "getgroups", "setgroups", "setresuid", "getresuid", "setresgid",
+    "getresgid", "getpgid", "setfsuid", "setfsgid", "getsid", "capget", "capset", "rt_sigpending",
+    "rt_sigtimedwait", "rt_sigqueueinfo", "rt_sigsuspend", "sigaltstack", "utime", "mknod", "uselib", "personality",
+    "ustat", "statfs", "fstatfs", "sysfs", "getpriority", "setpriority", "sched_setparam", "sched_getparam",
+    "sched_setscheduler", "sched_getscheduler", "sched_get_priority_max", "sched_get_priority_min", "sched_rr_get_interval", "mlock", "munlock", "mlockall",
+    "munlockall", "vhangup", "modify_ldt", "pivot_root", "_sysctl", "prctl", "arch_prctl", "adjtimex",
+    "setrlimit", "chroot", "sync", "acct", "settimeofday", "mount", "umount2", "swapon",
+    "swapoff", "reboot", "sethostname", "setdomainname", "iopl", "ioperm", "create_module", "init_module",
+    "delete_module", "get_kernel_syms", "query_module", "quotactl", "nfsservctl", "getpmsg", "putpmsg", "afs_syscall",
+    "tuxcall", "security", "gettid", "readahead", "setxattr", "lsetxattr", "fsetxattr", "getxattr",
+    "lgetxattr", "fgetxattr", "listxattr", "llistxattr", "flistxattr", "removexattr", "lremovexattr", "fremovexattr",
+    "tkill", "time", "futex", "sched_setaffinity", "sched_getaffinity", "set_thread_area", "io_setup", "io_destroy",
+    "io_getevents", "io_submit", "io_cancel", "get_thread_area", "lookup_dcookie", "epoll_create", "epoll_ctl_old", "epoll_wait_old",
+    "remap_file_pages", "getdents64", "set_tid_address", "restart_syscall", "semtimedop", "fadvise64", "timer_create", "timer_settime",
+    "timer_gettime", "timer_getoverrun", "timer_delete", "clock_settime", "clock_gettime", "clock_getres", "clock_nanosleep", "exit_group",
+    "epoll_wait", "epoll_ctl", "tgkill", "utimes", "vserver", "mbind", "set_mempolicy", "get_mempolicy",
+    "mq_open", "mq_unlink", "mq_timedsend", "mq_timedreceive", "mq_notify", "mq_getsetattr", "kexec_load", "waitid",
+    "add_key", "request_key", "keyctl", "ioprio_set", "ioprio_get", "inotify_init", "inotify_add_watch", "inotify_rm_watch",
+    "migrate_pages", "openat", "mkdirat", "mknodat", "fchownat", "futimesat", "newfstatat", "unlinkat",
+    "renameat", "linkat", "symlinkat", "readlinkat", "fchmodat", "faccessat", "pselect6", "ppoll",
+    "unshare", "set_robust_list", "get_robust_list", "splice", "tee", "sync_file_range", "vmsplice", "move_pages",
+    "utimensat", "epoll_pwait", "signalfd", "timerfd_create", "eventfd", "fallocate", "timerfd_settime", "timerfd_gettime",
+    "accept4", "signalfd4", "eventfd2", "epoll_create1", "dup3", "pipe2", "inotify_init1", "preadv",
+    "pwritev", "rt_tgsigqueueinfo", "perf_event_open", "recvmmsg", "fanotify_init", "fanotify_mark", "prlimit64", "name_to_handle_at",
+    "open_by_handle_at", "clock_adjtime", "syncfs", "sendmmsg", "setns", "getcpu", "process_vm_readv", "process_vm_writev",
+    "kcmp", "finit_module", "sched_setattr", "sched_getattr", "renameat2", "seccomp", "getrandom", "memfd_create",
+    "kexec_file_load", "bpf", "execveat", "userfaultfd", "membarrier", "mlock2", "copy_file_range", "preadv2",
+    "pwritev2", "pkey_mprotect", "pkey_alloc", "pkey_free", "statx", "io_pgetevents", "rseq",
+];
+
+// SYSCALL_NAMES_EXT_OFFSET is the number of the first syscall in
+// SYSCALL_NAMES_EXT. Linux skips the numbers 335 to 423 on x86_64.
+pub const SYSCALL_NAMES_EXT_OFFSET: usize = 424;
+pub const SYSCALL_NAMES_EXT: [&str; 27] = [
+    "pidfd_send_signal", "io_uring_setup", "io_uring_enter", "io_uring_register", "open_tree", "move_mount", "fsopen", "fsconfig",
+    "fsmount", "fspick", "pidfd_open", "clone3", "close_range", "openat2", "pidfd_getfd", "faccessat2",
+    "process_madvise", "epoll_pwait2", "mount_setattr", "quotactl_fd", "landlock_create_ruleset", "landlock_add_rule", "landlock_restrict_self", "memfd_secret",
+    "process_mrelease", "futex_waitv", "set_mempolicy_home_node",
+];
+
+// SyscallNumber returns the x86_64 syscall number of the syscall name.
+pub fn SyscallNumber(name: &str) -> Option<i32> {
+    if let Some(nr) = SYSCALL_NAMES.iter().position(|n| *n == name) {
+        return Some(nr as i32);
+    }
+
+    if let Some(nr) = SYSCALL_NAMES_EXT.iter().position(|n| *n == name) {
+        return Some((nr + SYSCALL_NAMES_EXT_OFFSET) as i32);
+    }
+
+    return None;
+}
+
+// SeccompArgRule is a comparison of a syscall argument in a seccomp rule.
+#[derive(Debug, Clone, Copy)]
+pub struct SeccompArgRule {
+    pub index: usize,
+    pub value: u64,
+    pub valueTwo: u64,
+    pub op: LinuxSeccompOperator,
+}
+
+impl SeccompArgRule {
+    pub fn Match(&self, args: &[u64; 6]) -> bool {
+        let arg = args[self.index];
+        return match self.op {
+            LinuxSeccompOperator::SCMP_CMP_NE => arg != self.value,
+            LinuxSeccompOperator::SCMP_CMP_LT => arg < self.value,
+            LinuxSeccompOperator::SCMP_CMP_LE => arg <= self.value,
+            LinuxSeccompOperator::SCMP_CMP_EQ => arg == self.value,
+            LinuxSeccompOperator::SCMP_CMP_GE => arg >= self.value,
+            LinuxSeccompOperator::SCMP_CMP_GT => arg > self.value,
+            LinuxSeccompOperator::SCMP_CMP_MASKED_EQ => arg & self.value == self.valueTwo,
+        };
+    }
+}
+
+// SeccompRule is a rule of a syscall policy. It applies its action when all
+// of its argument comparisons match.
+#[derive(Debug, Clone)]
+pub struct SeccompRule {
+    // action is the SECCOMP_RET_* value returned when the rule matches.
+    pub action: u32,
+    pub args: Vec<SeccompArgRule>,
+}
+
+impl SeccompRule {
+    pub fn Match(&self, args: &[u64; 6]) -> bool {
+        for a in &self.args {
+            if !a.Match(args) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+}
+
+// SyscallPolicy is the linux.seccomp profile of a container compiled for the
+// syscall dispatcher. It is evaluated together with the seccomp-bpf filters
+// installed by the application and yields the same SECCOMP_RET_* values.
+#[derive(Debug, Clone, Default)]
+pub struct SyscallPolicy {
+    pub defaultAction: u32,
+
+    // rules maps syscall numbers to their rules, in the profile order.
+    pub rules: BTreeMap<i32, Vec<SeccompRule>>,
+}
+
+// SeccompRet returns the SECCOMP_RET_* value of an OCI seccomp action. errno
+// is the errno returned by SCMP_ACT_ERRNO and passed to the tracer by
+// SCMP_ACT_TRACE, EPERM when not set as in runc.
+pub fn SeccompRet(action: LinuxSeccompAction, errno: Option<u32>) -> u32 {
+    let data = errno.unwrap_or(SysErr::EPERM as u32) & SECCOMP_RET_DATA;
+    return match action {
+        LinuxSeccompAction::SCMP_ACT_KILL => SECCOMP_RET_KILL_THREAD,
+        LinuxSeccompAction::SCMP_ACT_KILL_PROCESS => SECCOMP_RET_KILL_PROCESS,
+        LinuxSeccompAction::SCMP_ACT_TRAP => SECCOMP_RET_TRAP,
+        LinuxSeccompAction::SCMP_ACT_ERRNO => SECCOMP_RET_ERRNO | data,
+        LinuxSeccompAction::SCMP_ACT_TRACE => SECCOMP_RET_TRACE | data,
+        LinuxSeccompAction::SCMP_ACT_LOG => SECCOMP_RET_LOG,
+        LinuxSeccompAction::SCMP_ACT_ALLOW => SECCOMP_RET_ALLOW,
+    };
+}
+
+impl SyscallPolicy {
+    // New compiles the seccomp profile. Like runc, syscall names unknown to
+    // the sandbox are skipped, so that one profile fits several kernel
+    // versions.
+    pub fn New(spec: &LinuxSeccomp) -> Result<Self> {
+        let mut policy = Self {
+            defaultAction: SeccompRet(spec.default_action, spec.default_errno_ret),
+            rules: BTreeMap::new(),
+        };
+
+        for s in &spec.syscalls {
+            let mut args = Vec::with_capacity(s.args.len());
+            for a in &s.args {
+                if a.index >= 6 {
+                    return Err(Error::Common(format!(
+                        "seccomp: invalid argument index {} for syscall {:?}",
+                        a.index, s.names
+                    )));
+                }
+
+                args.push(SeccompArgRule {
+                    index: a.index,
+                    value: a.value,
+                    valueTwo: a.value_two,
+                    op: a.op,
+                });
+            }
+
+            let rule = SeccompRule {
+                action: SeccompRet(s.action, s.errno_ret),
+                args: args,
+            };
+
+            let mut names = s.names.clone();
+            if s.name.len() != 0 {
+                names.push(s.name.to_string());
+            }
+
+            for name in &names {
+                match SyscallNumber(name) {
+                    None => info!("seccomp: unknown syscall {} is ignored", name),
+                    Some(nr) => policy
+                        .rules
+                        .entry(nr)
+                        .or_insert_with(Vec::new)
+                        .push(rule.clone()),
+                }
+            }
+        }
+
+        return Ok(policy);
+    }
+
+    // Evaluate returns the SECCOMP_RET_* value of the syscall. When several
+    // rules of the syscall match, the least permissive action wins, as for
+    // seccomp-bpf filters.
+    pub fn Evaluate(&self, sysno: i32, args: &[u64; 6]) -> u32 {
+        let rules = match self.rules.get(&sysno) {
+            None => return self.defaultAction,
+            Some(rules) => rules,
+        };
+
+        let mut ret = None;
+        for r in rules {
+            if !r.Match(args) {
+                continue;
+            }
+
+            ret = match ret {
+                None => Some(r.action),
+                Some(cur) => Some(LeastPermissive(cur, r.action)),
+            };
+        }
+
+        return ret.unwrap_or(self.defaultAction);
+    }
+}
+
+// LeastPermissive returns the least permissive of two SECCOMP_RET_* values,
+// a if they are equally permissive.
+pub fn LeastPermissive(a: u32, b: u32) -> u32 {
+    if ((b & SECCOMP_RET_ACTION_FULL) as i32) < ((a & SECCOMP_RET_ACTION_FULL) as i32) {
+        return b;
+    }
+
+    return a;
+}
+
+impl Kernel {
+    // SetContainerSeccomp installs the seccomp profile of the container, which
+    // applies to all the tasks created in the container afterwards.
+    pub fn SetContainerSeccomp(&self, cid: &str, seccomp: &Option<LinuxSeccomp>) -> Result<()> {
+        match seccomp {
+            None => {
+                self.seccompPolicies.lock().remove(cid);
+            }
+            Some(spec) => {
+                let policy = SyscallPolicy::New(spec)?;
+                self.seccompPolicies
+                    .lock()
+                    .insert(cid.to_string(), Arc::new(policy));
+            }
+        }
+
+        return Ok(());
+    }
+
+    pub fn RemoveContainerSeccomp(&self, cid: &str) {
+        self.seccompPolicies.lock().remove(cid);
+    }
+
+    pub fn ContainerSeccomp(&self, cid: &str) -> Option<Arc<SyscallPolicy>> {
+        return self.seccompPolicies.lock().get(cid).cloned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn rule(action: u32, args: &[(usize, LinuxSeccompOperator, u64, u64)]) -> SeccompRule {
+        return SeccompRule {
+            action: action,
+            args: args
+                .iter()
+                .map(|&(index, op, value, valueTwo)| SeccompArgRule {
+                    index: index,
+                    value: value,
+                    valueTwo: valueTwo,
+                    op: op,
+                })
+                .collect(),
+        };
+    }
+
+    fn policy(rules: Vec<(i32, SeccompRule)>) -> SyscallPolicy {
+        let mut policy = SyscallPolicy {
+            defaultAction: SECCOMP_RET_ERRNO | SysErr::EPERM as u32,
+            rules: BTreeMap::new(),
+        };
+
+        for (nr, r) in rules {
+            policy.rules.entry(nr).or_insert_with(Vec::new).push(r);
+        }
+
+        return policy;
+    }
+
+    #[test]
+    fn test_EvaluateDefaultAction() {
+        let p = policy(vec![(0, rule(SECCOMP_RET_ALLOW, &[]))]);
+        assert_eq!(p.Evaluate(0, &[0; 6]), SECCOMP_RET_ALLOW);
+        assert_eq!(
+            p.Evaluate(1, &[0; 6]),
+            SECCOMP_RET_ERRNO | SysErr::EPERM as u32
+        );
+    }
+
+    #[test]
+    fn test_EvaluateArgs() {
+        let p = policy(vec![(
+            1,
+            rule(
+                SECCOMP_RET_ALLOW,
+                &[
+                    (0, LinuxSeccompOperator::SCMP_CMP_LE, 2, 0),
+                    (2, LinuxSeccompOperator::SCMP_CMP_MASKED_EQ, 0xf0, 0x10),
+                ],
+            ),
+        )]);
+
+        assert_eq!(p.Evaluate(1, &[2, 0, 0x1f, 0, 0, 0]), SECCOMP_RET_ALLOW);
+        // every comparison of the rule has to match
+        assert_eq!(
+            p.Evaluate(1, &[3, 0, 0x1f, 0, 0, 0]),
+            SECCOMP_RET_ERRNO | SysErr::EPERM as u32
+        );
+        assert_eq!(
+            p.Evaluate(1, &[2, 0, 0x2f, 0, 0, 0]),
+            SECCOMP_RET_ERRNO | SysErr::EPERM as u32
+        );
+    }
+
+    #[test]
+    fn test_EvaluateLeastPermissive() {
+        let p = policy(vec![
+            (1, rule(SECCOMP_RET_ALLOW, &[])),
+            (
+                1,
+                rule(
+                    SECCOMP_RET_KILL_PROCESS,
+                    &[(0, LinuxSeccompOperator::SCMP_CMP_EQ, 2, 0)],
+                ),
+            ),
+            (
+                1,
+                rule(
+                    SECCOMP_RET_TRACE,
+                    &[(0, LinuxSeccompOperator::SCMP_CMP_NE, 0, 0)],
+                ),
+            ),
+        ]);
+
+        assert_eq!(p.Evaluate(1, &[0; 6]), SECCOMP_RET_ALLOW);
+        assert_eq!(p.Evaluate(1, &[1, 0, 0, 0, 0, 0]), SECCOMP_RET_TRACE);
+        assert_eq!(p.Evaluate(1, &[2, 0, 0, 0, 0, 0]), SECCOMP_RET_KILL_PROCESS);
+    }
+
+    #[test]
+    fn test_SeccompRet() {
+        assert_eq!(
+            SeccompRet(LinuxSeccompAction::SCMP_ACT_ERRNO, None),
+            SECCOMP_RET_ERRNO | SysErr::EPERM as u32
+        );
+        assert_eq!(
+            SeccompRet(
+                LinuxSeccompAction::SCMP_ACT_ERRNO,
+                Some(SysErr::ENOSYS as u32)
+            ),
+            SECCOMP_RET_ERRNO | SysErr::ENOSYS as u32
+        );
+        assert_eq!(
+            SeccompRet(LinuxSeccompAction::SCMP_ACT_KILL, None),
+            SECCOMP_RET_KILL_THREAD
+        );
+    }
+}
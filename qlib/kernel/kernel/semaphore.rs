@@ -39,16 +39,6 @@ pub unsafe fn InitSingleton() {
 
 pub const VALUE_MAX: i16 = SEMMNI as i16; // SEMVMX
 
-// setMax is "system-wide limit on the number of semaphore sets" (SEMMNI).
-pub const SETS_MAX: u32 = SEMMNI;
-
-// semaphoresMax is "maximum number of semaphores per semaphore ID" (SEMMSL).
-pub const SEMAPHORES_MAX: u32 = SEMMSL;
-
-// semaphoresTotalMax is "system-wide limit on the number of semaphores"
-// (SEMMNS = SEMMNI*SEMMSL).
-pub const SEMAPHORES_TOTAL_MAX: u32 = SEMMNS;
-
 #[derive(Default)]
 pub struct SemRegistryInternal {
     pub userNS: UserNameSpace,
@@ -169,7 +159,12 @@ impl SemRegistry {
         create: bool,
         exclusive: bool,
     ) -> Result<Set> {
-        if nsems < 0 || nsems > SEMAPHORES_MAX as i32 {
+        // The limits are "maximum number of semaphores per semaphore ID"
+        // (SEMMSL), "system-wide limit on the number of semaphore sets"
+        // (SEMMNI) and "system-wide limit on the number of semaphores"
+        // (SEMMNS), as set by the kernel.sem sysctl.
+        let limits = task.IPCNamespace().Limits();
+        if nsems < 0 || nsems > limits.semMsl as i32 {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
@@ -211,11 +206,11 @@ impl SemRegistry {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
-        if me.semaphores.len() >= SETS_MAX as usize {
+        if me.semaphores.len() >= limits.semMni as usize {
             return Err(Error::SysError(SysErr::ENOSPC));
         }
 
-        if me.totalSems() > SEMAPHORES_TOTAL_MAX as usize - nsems as usize {
+        if me.totalSems() + nsems as usize > limits.semMns as usize {
             return Err(Error::SysError(SysErr::ENOSPC));
         }
 
@@ -224,14 +219,15 @@ impl SemRegistry {
         return me.newSet(task, clone, key, &owner, &owner, &perms, nsems);
     }
 
-    pub fn IPCInfo(&self) -> SemInfo {
+    pub fn IPCInfo(&self, task: &Task) -> SemInfo {
+        let limits = task.IPCNamespace().Limits();
         return SemInfo {
             SemMap: SEMMAP,
-            SemMni: SEMMNI,
-            SemMns: SEMMNS,
+            SemMni: limits.semMni,
+            SemMns: limits.semMns,
             SemMnu: SEMMNU,
-            SemMsl: SEMMSL,
-            SemOpm: SEMOPM,
+            SemMsl: limits.semMsl,
+            SemOpm: limits.semOpm,
             SemUme: SEMUME,
             SemUsz: SEMUSZ,
             SemVmx: SEMVMX,
@@ -239,9 +235,9 @@ impl SemRegistry {
         }
     }
 
-    pub fn SemInfo(&self) -> SemInfo {
+    pub fn SemInfo(&self, task: &Task) -> SemInfo {
+        let mut info = self.IPCInfo(task);
         let me = self.lock();
-        let mut info = self.IPCInfo();
         info.SemUsz = me.semaphores.len() as _;
         info.SemAem = me.totalSems() as _;
         return info
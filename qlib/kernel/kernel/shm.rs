@@ -90,13 +90,14 @@ impl ShmRegistry {
 
     pub fn FindOrCreate(&self, task: &Task, pid: i32, key: Key, size: u64,
                         mode: &FileMode, private: bool, create: bool, exclusive: bool) -> Result<Shm> {
-        if (create || private) && (size < SHMMIN || size > SHMMAX) {
+        let limits = task.IPCNamespace().Limits();
+        if (create || private) && (size < SHMMIN || size > limits.shmMax) {
             return Err(Error::SysError(SysErr::EINVAL))
         }
 
         {
             let me = self.lock();
-            if me.shms.len() > SHMMNI as usize {
+            if me.shms.len() > limits.shmMni as usize {
                 return Err(Error::SysError(SysErr::ENOSPC))
             }
 
@@ -135,7 +136,7 @@ impl ShmRegistry {
             };
 
             let numPages = sizeAligned / MemoryDef::PAGE_SIZE;
-            if me.totalPages + numPages > SHMALL {
+            if me.totalPages + numPages > limits.shmAll {
                 return Err(Error::SysError(SysErr::ENOSPC));
             }
         }
@@ -195,13 +196,14 @@ impl ShmRegistry {
         return Err(Error::SysError(SysErr::ENOSPC));
     }
 
-    pub fn IPCInfo(&self) -> ShmParams {
+    pub fn IPCInfo(&self, task: &Task) -> ShmParams {
+        let limits = task.IPCNamespace().Limits();
         return ShmParams {
-            ShmMax: SHMMAX,
+            ShmMax: limits.shmMax,
             ShmMin: SHMMIN,
-            ShmMni: SHMMNI,
+            ShmMni: limits.shmMni,
             ShmSeg: SHMSEG,
-            ShmAll: SHMALL,
+            ShmAll: limits.shmAll,
         }
     }
 
@@ -0,0 +1,457 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::btree_map::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use super::super::super::common::*;
+use super::super::super::linux_def::*;
+use super::super::kernel_util::*;
+use super::super::task::*;
+use super::super::threadmgr::task_coredump::*;
+use super::super::version::*;
+use super::ipc_namespace::*;
+use super::kernel::*;
+use super::uts_namespace::*;
+
+// NEW_UTS_LEN is the maximum length of kernel.hostname and kernel.domainname.
+pub const NEW_UTS_LEN: usize = 64;
+
+// IPCMNI is the maximum value of kernel.shmmni, kernel.msgmni and SEMMNI.
+pub const IPCMNI: u64 = 32768;
+
+// PID_MAX_DEFAULT is the initial value of kernel.pid_max, PID_MAX_MIN and
+// PID_MAX_LIMIT are its bounds. See include/linux/threads.h.
+pub const PID_MAX_DEFAULT: u32 = 1 << 16;
+pub const PID_MAX_MIN: u32 = 301;
+pub const PID_MAX_LIMIT: u32 = 4 * 1024 * 1024;
+
+// NR_OPEN_DEFAULT is the initial value of fs.nr_open, NR_OPEN_MIN and
+// NR_OPEN_MAX are its bounds. See fs/file.c.
+pub const NR_OPEN_DEFAULT: u64 = 1024 * 1024;
+pub const NR_OPEN_MIN: u64 = 64;
+pub const NR_OPEN_MAX: u64 = (i32::MAX as u64) & !63;
+
+// FILE_MAX_DEFAULT is the initial value of fs.file-max.
+pub const FILE_MAX_DEFAULT: u64 = i64::MAX as u64;
+
+// SOMAXCONN_DEFAULT is the initial value of net.core.somaxconn.
+pub const SOMAXCONN_DEFAULT: u32 = 1024;
+
+// SysctlValues holds the global sysctls which aren't backed by another kernel
+// object.
+pub struct SysctlValues {
+    // pidMax is kernel.pid_max, one more than the largest thread ID.
+    pub pidMax: AtomicU32,
+
+    // fileMax is fs.file-max. It is reported but not enforced.
+    pub fileMax: AtomicU64,
+
+    // nrOpen is fs.nr_open, the maximum hard limit of RLIMIT_NOFILE.
+    pub nrOpen: AtomicU64,
+
+    // somaxconn is net.core.somaxconn, the maximum listen(2) backlog.
+    pub somaxconn: AtomicU32,
+
+    // bootID is kernel.random.boot_id, generated on first use.
+    pub bootID: QMutex<String>,
+}
+
+impl Default for SysctlValues {
+    fn default() -> Self {
+        return Self {
+            pidMax: AtomicU32::new(PID_MAX_DEFAULT),
+            fileMax: AtomicU64::new(FILE_MAX_DEFAULT),
+            nrOpen: AtomicU64::new(NR_OPEN_DEFAULT),
+            somaxconn: AtomicU32::new(SOMAXCONN_DEFAULT),
+            bootID: QMutex::new(String::new()),
+        };
+    }
+}
+
+// SysctlNamespaces holds the namespaces that the namespaced sysctls apply to:
+// those of the task accessing /proc/sys, or the root namespaces when the
+// linux.sysctl of an OCI spec is applied.
+#[derive(Clone)]
+pub struct SysctlNamespaces {
+    pub uts: UTSNamespace,
+    pub ipc: IPCNamespace,
+}
+
+impl SysctlNamespaces {
+    pub fn FromTask(task: &Task) -> Self {
+        let thread = task.Thread();
+        return Self {
+            uts: thread.UTSNamespace(),
+            ipc: thread.IPCNamespace(),
+        };
+    }
+
+    pub fn Root(kernel: &Kernel) -> Self {
+        return Self {
+            uts: kernel.RootUTSNamesapce(),
+            ipc: kernel.RootIPCNamespace(),
+        };
+    }
+}
+
+// Sysctl is an entry of the sysctl table, exposed as /proc/sys/<name> with
+// the dots of name replaced by slashes.
+pub struct Sysctl {
+    // name is the dotted name of the sysctl, e.g. "kernel.pid_max".
+    pub name: &'static str,
+
+    // maxLen is the maximum length of a string sysctl, whose writes follow
+    // proc_dostring() rather than proc_dointvec(). It is 0 for numeric ones.
+    pub maxLen: usize,
+
+    // get returns the current value, without the trailing newline.
+    pub get: fn(ns: &SysctlNamespaces) -> String,
+
+    // set validates and applies a new value. It is None for read-only
+    // sysctls.
+    pub set: Option<fn(ns: &SysctlNamespaces, val: &str) -> Result<()>>,
+}
+
+impl Sysctl {
+    pub fn IsString(&self) -> bool {
+        return self.maxLen > 0;
+    }
+
+    pub fn Writable(&self) -> bool {
+        return self.set.is_some();
+    }
+
+    // Path returns the dotted name of the /proc/sys directory of the sysctl,
+    // e.g. "kernel.random" for kernel.random.uuid, and its file name.
+    pub fn Path(&self) -> (&'static str, &'static str) {
+        match self.name.rfind('.') {
+            None => return ("", self.name),
+            Some(idx) => return (&self.name[..idx], &self.name[idx + 1..]),
+        }
+    }
+
+    pub fn Get(&self, ns: &SysctlNamespaces) -> String {
+        return (self.get)(ns);
+    }
+
+    pub fn Set(&self, ns: &SysctlNamespaces, val: &str) -> Result<()> {
+        match self.set {
+            None => return Err(Error::SysError(SysErr::EPERM)),
+            Some(set) => return set(ns, val),
+        }
+    }
+}
+
+// SYSCTLS is the sysctl table backing /proc/sys/{kernel,fs,net/core}.
+pub static SYSCTLS: &[Sysctl] = &[
+    Sysctl {
+        name: "fs.file-max",
+        maxLen: 0,
+        get: |_| GetKernel().sysctls.fileMax.load(Ordering::Relaxed).to_string(),
+        set: Some(|_, val| {
+            let v = ParseUint(val, 0, u64::MAX)?;
+            GetKernel().sysctls.fileMax.store(v, Ordering::Relaxed);
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "fs.nr_open",
+        maxLen: 0,
+        get: |_| GetKernel().NrOpen().to_string(),
+        set: Some(|_, val| {
+            let v = ParseUint(val, NR_OPEN_MIN, NR_OPEN_MAX)?;
+            GetKernel().sysctls.nrOpen.store(v, Ordering::Relaxed);
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.core_pattern",
+        maxLen: CORENAME_MAX_SIZE,
+        get: |_| GetKernel().CorePattern(),
+        set: Some(|_, val| {
+            GetKernel().SetCorePattern(val);
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.domainname",
+        maxLen: NEW_UTS_LEN + 1,
+        get: |ns| ns.uts.DomainName(),
+        set: Some(|ns, val| {
+            ns.uts.SetDomainName(val.to_string());
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.hostname",
+        maxLen: NEW_UTS_LEN + 1,
+        get: |ns| ns.uts.HostName(),
+        set: Some(|ns, val| {
+            ns.uts.SetHostName(val.to_string());
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.msgmax",
+        maxLen: 0,
+        get: |ns| ns.ipc.Limits().msgMax.to_string(),
+        set: Some(|ns, val| {
+            let v = ParseUint(val, 0, i32::MAX as u64)?;
+            ns.ipc.limits.lock().msgMax = v;
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.msgmnb",
+        maxLen: 0,
+        get: |ns| ns.ipc.Limits().msgMnb.to_string(),
+        set: Some(|ns, val| {
+            let v = ParseUint(val, 0, i32::MAX as u64)?;
+            ns.ipc.limits.lock().msgMnb = v;
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.msgmni",
+        maxLen: 0,
+        get: |ns| ns.ipc.Limits().msgMni.to_string(),
+        set: Some(|ns, val| {
+            let v = ParseUint(val, 0, IPCMNI)?;
+            ns.ipc.limits.lock().msgMni = v;
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.osrelease",
+        maxLen: 0,
+        get: |_| VERSION.Release.to_string(),
+        set: None,
+    },
+    Sysctl {
+        name: "kernel.ostype",
+        maxLen: 0,
+        get: |_| VERSION.Sysname.to_string(),
+        set: None,
+    },
+    Sysctl {
+        name: "kernel.pid_max",
+        maxLen: 0,
+        get: |_| GetKernel().PidMax().to_string(),
+        set: Some(|_, val| {
+            let v = ParseUint(val, PID_MAX_MIN as u64, PID_MAX_LIMIT as u64)?;
+            GetKernel().sysctls.pidMax.store(v as u32, Ordering::Relaxed);
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.random.boot_id",
+        maxLen: 0,
+        get: |_| {
+            let kernel = GetKernel();
+            let mut bootID = kernel.sysctls.bootID.lock();
+            if bootID.len() == 0 {
+                *bootID = NewUUID();
+            }
+            return bootID.clone();
+        },
+        set: None,
+    },
+    Sysctl {
+        name: "kernel.random.uuid",
+        maxLen: 0,
+        get: |_| NewUUID(),
+        set: None,
+    },
+    Sysctl {
+        name: "kernel.sem",
+        maxLen: 0,
+        get: |ns| {
+            let limits = ns.ipc.Limits();
+            return format!(
+                "{}\t{}\t{}\t{}",
+                limits.semMsl, limits.semMns, limits.semOpm, limits.semMni
+            );
+        },
+        set: Some(|ns, val| {
+            // kernel.sem is "SEMMSL SEMMNS SEMOPM SEMMNI". As proc_dointvec()
+            // does, the values that aren't given are left unchanged.
+            let fields: Vec<&str> = val.split_whitespace().collect();
+            if fields.len() == 0 || fields.len() > 4 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let mut vals = [0; 4];
+            for i in 0..fields.len() {
+                vals[i] = ParseUint(fields[i], 0, i32::MAX as u64)? as u32;
+            }
+
+            if fields.len() == 4 && vals[3] as u64 > IPCMNI {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let mut limits = ns.ipc.limits.lock();
+            let old = [limits.semMsl, limits.semMns, limits.semOpm, limits.semMni];
+            for i in fields.len()..4 {
+                vals[i] = old[i];
+            }
+
+            limits.semMsl = vals[0];
+            limits.semMns = vals[1];
+            limits.semOpm = vals[2];
+            limits.semMni = vals[3];
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.shmall",
+        maxLen: 0,
+        get: |ns| ns.ipc.Limits().shmAll.to_string(),
+        set: Some(|ns, val| {
+            let v = ParseUint(val, 0, u64::MAX)?;
+            ns.ipc.limits.lock().shmAll = v;
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.shmmax",
+        maxLen: 0,
+        get: |ns| ns.ipc.Limits().shmMax.to_string(),
+        set: Some(|ns, val| {
+            let v = ParseUint(val, 0, u64::MAX)?;
+            ns.ipc.limits.lock().shmMax = v;
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.shmmni",
+        maxLen: 0,
+        get: |ns| ns.ipc.Limits().shmMni.to_string(),
+        set: Some(|ns, val| {
+            let v = ParseUint(val, 0, IPCMNI)?;
+            ns.ipc.limits.lock().shmMni = v;
+            return Ok(());
+        }),
+    },
+    Sysctl {
+        name: "kernel.version",
+        maxLen: 0,
+        get: |_| VERSION.Version.to_string(),
+        set: None,
+    },
+    Sysctl {
+        name: "net.core.somaxconn",
+        maxLen: 0,
+        get: |_| GetKernel().Somaxconn().to_string(),
+        set: Some(|_, val| {
+            let v = ParseUint(val, 0, i32::MAX as u64)?;
+            GetKernel().sysctls.somaxconn.store(v as u32, Ordering::Relaxed);
+            return Ok(());
+        }),
+    },
+];
+
+// LookupSysctl returns the sysctl with the dotted name. As sysctl(8) does, a
+// name may also be given with slashes.
+pub fn LookupSysctl(name: &str) -> Option<&'static Sysctl> {
+    let name = name.replace('/', ".");
+    for s in SYSCTLS {
+        if s.name == name {
+            return Some(s);
+        }
+    }
+
+    return None;
+}
+
+// ParseUint parses the decimal value of a numeric sysctl, and checks it
+// against the [min, max] range.
+fn ParseUint(val: &str, min: u64, max: u64) -> Result<u64> {
+    let v = match val.trim().parse::<u64>() {
+        Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+        Ok(v) => v,
+    };
+
+    if v < min || v > max {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    return Ok(v);
+}
+
+// NewUUID returns a random version 4 UUID, as kernel.random.uuid does.
+fn NewUUID() -> String {
+    let mut b = [0u8; 16];
+    b[..8].copy_from_slice(&RandU64().unwrap_or(0).to_le_bytes());
+    b[8..].copy_from_slice(&RandU64().unwrap_or(0).to_le_bytes());
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+
+    let mut s = String::with_capacity(36);
+    for i in 0..b.len() {
+        if i == 4 || i == 6 || i == 8 || i == 10 {
+            s.push('-');
+        }
+        s += &format!("{:02x}", b[i]);
+    }
+
+    return s;
+}
+
+impl Kernel {
+    pub fn PidMax(&self) -> u32 {
+        return self.sysctls.pidMax.load(Ordering::Relaxed);
+    }
+
+    pub fn NrOpen(&self) -> u64 {
+        return self.sysctls.nrOpen.load(Ordering::Relaxed);
+    }
+
+    pub fn Somaxconn(&self) -> u32 {
+        return self.sysctls.somaxconn.load(Ordering::Relaxed);
+    }
+
+    // SetContainerSysctls applies the linux.sysctl of the spec of a container.
+    // As all the containers share the root UTS and IPC namespaces, the
+    // namespaced sysctls apply to the whole sandbox.
+    pub fn SetContainerSysctls(&self, sysctls: &BTreeMap<String, String>) -> Result<()> {
+        let ns = SysctlNamespaces::Root(self);
+        for (name, val) in sysctls {
+            let sysctl = match LookupSysctl(name) {
+                None => {
+                    return Err(Error::Common(format!("unsupported sysctl {}", name)));
+                }
+                Some(s) => s,
+            };
+
+            match sysctl.Set(&ns, val) {
+                Err(e) => {
+                    return Err(Error::Common(format!(
+                        "invalid value {:?} of sysctl {}: {:?}",
+                        val, name, e
+                    )));
+                }
+                Ok(()) => (),
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,763 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::qlib::mutex::*;
+use alloc::collections::vec_deque::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::ops::Deref;
+
+use super::super::super::common::*;
+use super::super::super::linux::userfaultfd::*;
+use super::super::super::linux_def::*;
+use super::super::super::range::*;
+use super::super::kernel::fd_table::*;
+use super::super::kernel::waiter::*;
+use super::super::memmgr::mm::*;
+use super::super::task::*;
+
+use super::super::fs::anon::*;
+use super::super::fs::attr::*;
+use super::super::fs::dentry::*;
+use super::super::fs::dirent::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::fs::host::hostinodeop::*;
+
+// Constants for userfaultfd(2).
+pub const UFFD_CLOEXEC: i32 = Flags::O_CLOEXEC;
+pub const UFFD_NONBLOCK: i32 = Flags::O_NONBLOCK;
+
+// UFFDIO_COPY and UFFDIO_ZEROPAGE fill at most this many bytes under a
+// single hold of the mapping lock.
+const FILL_CHUNK: u64 = 16 * MemoryDef::PAGE_SIZE;
+
+// UserfaultfdFault is a missing page fault waiting to be resolved by
+// UFFDIO_COPY, UFFDIO_ZEROPAGE or UFFDIO_WAKE.
+pub struct UserfaultfdFault {
+    pub addr: u64,
+    pub entry: WaitEntry,
+}
+
+// UserfaultfdMsg is a message queued for the reader. For UFFD_EVENT_FORK,
+// fork is the userfaultfd of the child, which gets a file descriptor in the
+// reader when the message is read.
+pub struct UserfaultfdMsg {
+    pub msg: UffdMsg,
+    pub fork: Option<Userfaultfd>,
+}
+
+pub struct UserfaultfdInternal {
+    // queue is notified when messages are queued.
+    pub queue: Queue,
+
+    // mm is the address space whose registered ranges route their missing
+    // page faults to this userfaultfd.
+    pub mm: MemoryManagerWeak,
+
+    // flags are the O_CLOEXEC, O_NONBLOCK and UFFD_USER_MODE_ONLY flags of
+    // userfaultfd(2). They also apply to the userfaultfds created for
+    // UFFD_EVENT_FORK.
+    pub flags: i32,
+
+    // api is set once the UFFDIO_API handshake is done. Reads and the other
+    // ioctls fail until then.
+    pub api: bool,
+
+    // features are the UFFD_FEATURE_* requested in UFFDIO_API.
+    pub features: u64,
+
+    pub msgs: VecDeque<UserfaultfdMsg>,
+    pub faults: Vec<UserfaultfdFault>,
+
+    // released is set once the file is closed.
+    pub released: bool,
+}
+
+#[derive(Clone)]
+pub struct Userfaultfd(Arc<QMutex<UserfaultfdInternal>>);
+
+impl Deref for Userfaultfd {
+    type Target = Arc<QMutex<UserfaultfdInternal>>;
+
+    fn deref(&self) -> &Arc<QMutex<UserfaultfdInternal>> {
+        &self.0
+    }
+}
+
+impl PartialEq for Userfaultfd {
+    fn eq(&self, other: &Self) -> bool {
+        return Arc::ptr_eq(&self.0, &other.0);
+    }
+}
+
+impl Userfaultfd {
+    pub fn New(mm: &MemoryManager, flags: i32) -> Self {
+        let internal = UserfaultfdInternal {
+            queue: Queue::default(),
+            mm: mm.Downgrade(),
+            flags: flags,
+            api: false,
+            features: 0,
+            msgs: VecDeque::new(),
+            faults: Vec::new(),
+            released: false,
+        };
+
+        return Self(Arc::new(QMutex::new(internal)));
+    }
+
+    // Fork returns the userfaultfd of the child address space mm, which
+    // inherits the registrations of the parent. Equivalent to Linux's
+    // fs/userfaultfd.c:dup_userfaultfd.
+    pub fn Fork(&self, mm: &MemoryManager) -> Self {
+        let (flags, features) = {
+            let u = self.lock();
+            (u.flags, u.features)
+        };
+
+        let child = Self::New(mm, flags);
+        {
+            let mut c = child.lock();
+            c.api = true;
+            c.features = features;
+        }
+
+        return child;
+    }
+
+    // UserModeOnly returns whether the faults of the kernel accessing the
+    // registered ranges fail with EFAULT rather than being reported.
+    pub fn UserModeOnly(&self) -> bool {
+        return self.lock().flags & UFFD_USER_MODE_ONLY != 0;
+    }
+
+    pub fn Features(&self) -> u64 {
+        return self.lock().features;
+    }
+
+    // HandleFault reports the missing page fault at addr to the reader and
+    // waits until it is resolved. ml is the mapping lock held by the page
+    // fault handler or FixPermission; it is released before blocking. The
+    // faulting access is retried once HandleFault returns.
+    pub fn HandleFault(
+        &self,
+        task: &Task,
+        ml: QUpgradableLockGuard,
+        addr: u64,
+        write: bool,
+    ) -> Result<()> {
+        let entry = task.blocker.generalEntry.clone();
+        entry.Clear();
+        entry.SetMask(EVENT_IN);
+
+        let queue = {
+            let mut u = self.lock();
+            if u.released {
+                return Ok(());
+            }
+
+            let mut msg = UffdMsg {
+                Event: UFFD_EVENT_PAGEFAULT,
+                ..Default::default()
+            };
+
+            if write {
+                msg.Arg[0] = UFFD_PAGEFAULT_FLAG_WRITE;
+            }
+            msg.Arg[1] = addr;
+            if u.features & UFFD_FEATURE_THREAD_ID != 0 {
+                msg.Arg[2] = task.Thread().ThreadID() as u32 as u64;
+            }
+
+            u.msgs.push_back(UserfaultfdMsg {
+                msg: msg,
+                fork: None,
+            });
+            u.faults.push(UserfaultfdFault {
+                addr: addr,
+                entry: entry.clone(),
+            });
+            u.queue.clone()
+        };
+
+        core::mem::drop(ml);
+        queue.Notify(READABLE_EVENT);
+
+        let res = task.blocker.BlockWithMonoTimer(true, None);
+
+        // If the wait was interrupted, the fault is reported again when it is
+        // retried.
+        self.lock().faults.retain(|f| f.entry != entry);
+        return res;
+    }
+
+    // Wake wakes up the faults in ar.
+    pub fn Wake(&self, ar: &Range) {
+        let mut u = self.lock();
+        u.faults.retain(|f| {
+            if ar.Contains(f.addr) {
+                f.entry.Notify(EVENT_IN);
+                return false;
+            }
+
+            return true;
+        });
+    }
+
+    fn post(&self, msg: UffdMsg, fork: Option<Userfaultfd>) {
+        let queue = {
+            let mut u = self.lock();
+            if u.released {
+                return;
+            }
+
+            u.msgs.push_back(UserfaultfdMsg {
+                msg: msg,
+                fork: fork,
+            });
+            u.queue.clone()
+        };
+
+        queue.Notify(READABLE_EVENT);
+    }
+
+    // PostFork reports that the registered ranges have been duplicated into
+    // a child address space, handled by child.
+    pub fn PostFork(&self, child: &Userfaultfd) {
+        let msg = UffdMsg {
+            Event: UFFD_EVENT_FORK,
+            ..Default::default()
+        };
+
+        self.post(msg, Some(child.clone()));
+    }
+
+    // PostRemap reports that mremap(2) moved a registered range.
+    pub fn PostRemap(&self, from: u64, to: u64, len: u64) {
+        if self.Features() & UFFD_FEATURE_EVENT_REMAP == 0 {
+            return;
+        }
+
+        let msg = UffdMsg {
+            Event: UFFD_EVENT_REMAP,
+            Arg: [from, to, len],
+            ..Default::default()
+        };
+
+        self.post(msg, None);
+    }
+
+    // PostUnmap reports that munmap(2) removed (part of) a registered range.
+    pub fn PostUnmap(&self, ar: &Range) {
+        if self.Features() & UFFD_FEATURE_EVENT_UNMAP == 0 {
+            return;
+        }
+
+        let msg = UffdMsg {
+            Event: UFFD_EVENT_UNMAP,
+            Arg: [ar.Start(), ar.End(), 0],
+            ..Default::default()
+        };
+
+        self.post(msg, None);
+    }
+
+    // Release unregisters every range of the userfaultfd and wakes up the
+    // pending faults, which then populate their pages as usual. Equivalent
+    // to Linux's fs/userfaultfd.c:userfaultfd_release.
+    pub fn Release(&self) {
+        let (mm, faults, forks) = {
+            let mut u = self.lock();
+            if u.released {
+                return;
+            }
+            u.released = true;
+
+            let faults: Vec<UserfaultfdFault> = u.faults.drain(..).collect();
+            let forks: Vec<Userfaultfd> = u.msgs.drain(..).filter_map(|m| m.fork).collect();
+            (u.mm.TryUpgrade(), faults, forks)
+        };
+
+        if let Some(mm) = mm {
+            mm.UnregisterUserfaultfd(self, &Range::New(0, MemoryDef::LOWER_TOP));
+        }
+
+        for f in faults {
+            f.entry.Notify(EVENT_IN);
+        }
+
+        // The children whose UFFD_EVENT_FORK was never read have no file
+        // descriptor which could release them later.
+        for child in forks {
+            child.Release();
+        }
+    }
+
+    fn MM(&self) -> Result<MemoryManager> {
+        match self.lock().mm.TryUpgrade() {
+            None => return Err(Error::SysError(SysErr::ESRCH)),
+            Some(mm) => return Ok(mm),
+        }
+    }
+
+    fn CheckApi(&self) -> Result<()> {
+        if !self.lock().api {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        return Ok(());
+    }
+
+    pub fn Read(&self, task: &Task, dsts: &mut [IoVec]) -> Result<i64> {
+        self.CheckApi()?;
+
+        let msgSize = core::mem::size_of::<UffdMsg>();
+        let size = IoVec::NumBytes(dsts);
+        if size < msgSize {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let mut msgs = Vec::new();
+        {
+            let mut u = self.lock();
+            if u.msgs.len() == 0 {
+                return Err(Error::SysError(SysErr::EWOULDBLOCK));
+            }
+
+            while msgs.len() < size / msgSize {
+                match u.msgs.pop_front() {
+                    None => break,
+                    Some(m) => msgs.push(m),
+                }
+            }
+        }
+
+        let mut buf = DataBuff::New(msgs.len() * msgSize);
+        for (i, m) in msgs.iter().enumerate() {
+            let mut msg = m.msg;
+            if let Some(ref child) = m.fork {
+                let fd = match NewUserfaultfdFD(task, child) {
+                    Err(e) => {
+                        child.Release();
+                        return Err(e);
+                    }
+                    Ok(fd) => fd,
+                };
+                msg.Arg[0] = fd as u32 as u64;
+            }
+
+            let ptr = &msg as *const _ as *const u8;
+            let bytes = unsafe { core::slice::from_raw_parts(ptr, msgSize) };
+            buf.buf[i * msgSize..(i + 1) * msgSize].copy_from_slice(bytes);
+        }
+
+        task.CopyDataOutToIovs(&buf.buf, dsts, false)?;
+        return Ok(buf.Len() as i64);
+    }
+
+    pub fn Ioctl(&self, task: &Task, request: u64, val: u64) -> Result<()> {
+        match request {
+            UFFDIO_API => return self.Api(task, val),
+            UFFDIO_REGISTER => return self.Register(task, val),
+            UFFDIO_UNREGISTER => return self.Unregister(task, val),
+            UFFDIO_WAKE => return self.WakeIoctl(task, val),
+            UFFDIO_COPY => return self.Copy(task, val),
+            UFFDIO_ZEROPAGE => return self.Zeropage(task, val),
+            _ => return Err(Error::SysError(SysErr::ENOTTY)),
+        }
+    }
+
+    fn Api(&self, task: &Task, val: u64) -> Result<()> {
+        let mut api: UffdioApi = task.CopyInObj(val)?;
+
+        let mut u = self.lock();
+        if u.api || api.Api != UFFD_API || api.Features & !UFFD_API_FEATURES != 0 {
+            // Linux clears the structure on failure.
+            task.CopyOutObj(&UffdioApi::default(), val)?;
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let features = api.Features;
+        api.Features = UFFD_API_FEATURES;
+        api.Ioctls = UFFD_API_IOCTLS;
+        task.CopyOutObj(&api, val)?;
+
+        u.api = true;
+        u.features = features;
+        return Ok(());
+    }
+
+    fn Register(&self, task: &Task, val: u64) -> Result<()> {
+        self.CheckApi()?;
+
+        let mut reg: UffdioRegister = task.CopyInObj(val)?;
+        if reg.Mode == 0
+            || reg.Mode
+                & !(UFFDIO_REGISTER_MODE_MISSING
+                    | UFFDIO_REGISTER_MODE_WP
+                    | UFFDIO_REGISTER_MODE_MINOR)
+                != 0
+        {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // Write-protect and minor faults are not supported.
+        if reg.Mode != UFFDIO_REGISTER_MODE_MISSING {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let ar = ValidateRange(reg.Range.Start, reg.Range.Len)?;
+        self.MM()?.RegisterUserfaultfd(self, &ar)?;
+
+        reg.Ioctls = UFFD_API_RANGE_IOCTLS;
+        task.CopyOutObj(&reg, val)?;
+        return Ok(());
+    }
+
+    fn Unregister(&self, task: &Task, val: u64) -> Result<()> {
+        self.CheckApi()?;
+
+        let r: UffdioRange = task.CopyInObj(val)?;
+        let ar = ValidateRange(r.Start, r.Len)?;
+        self.MM()?.UnregisterUserfaultfd(self, &ar);
+        self.Wake(&ar);
+        return Ok(());
+    }
+
+    fn WakeIoctl(&self, task: &Task, val: u64) -> Result<()> {
+        self.CheckApi()?;
+
+        let r: UffdioRange = task.CopyInObj(val)?;
+        let ar = ValidateRange(r.Start, r.Len)?;
+        self.Wake(&ar);
+        return Ok(());
+    }
+
+    fn Copy(&self, task: &Task, val: u64) -> Result<()> {
+        self.CheckApi()?;
+
+        let mut copy: UffdioCopy = task.CopyInObj(val)?;
+        if copy.Mode & !UFFDIO_COPY_MODE_DONTWAKE != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let ar = ValidateRange(copy.Dst, copy.Len)?;
+        if copy.Src.checked_add(copy.Len).is_none() || ar.Overlaps(&Range::New(copy.Src, copy.Len))
+        {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let res = self.Fill(task, &ar, Some(copy.Src));
+        copy.Copy = match res {
+            Err(Error::SysError(e)) => -e as i64,
+            Err(_) => -SysErr::EFAULT as i64,
+            Ok(n) => n as i64,
+        };
+        task.CopyOutObj(&copy, val)?;
+
+        let n = res?;
+        if copy.Mode & UFFDIO_COPY_MODE_DONTWAKE == 0 {
+            self.Wake(&Range::New(ar.Start(), n));
+        }
+
+        if n != ar.Len() {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
+
+        return Ok(());
+    }
+
+    fn Zeropage(&self, task: &Task, val: u64) -> Result<()> {
+        self.CheckApi()?;
+
+        let mut zero: UffdioZeropage = task.CopyInObj(val)?;
+        if zero.Mode & !UFFDIO_ZEROPAGE_MODE_DONTWAKE != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let ar = ValidateRange(zero.Range.Start, zero.Range.Len)?;
+        let res = self.Fill(task, &ar, None);
+        zero.Zeropage = match res {
+            Err(Error::SysError(e)) => -e as i64,
+            Err(_) => -SysErr::EFAULT as i64,
+            Ok(n) => n as i64,
+        };
+        task.CopyOutObj(&zero, val)?;
+
+        let n = res?;
+        if zero.Mode & UFFDIO_ZEROPAGE_MODE_DONTWAKE == 0 {
+            self.Wake(&Range::New(ar.Start(), n));
+        }
+
+        if n != ar.Len() {
+            return Err(Error::SysError(SysErr::EAGAIN));
+        }
+
+        return Ok(());
+    }
+
+    // Fill populates the missing pages of ar, with the data at src in the
+    // caller's address space or with zeros. It returns the number of bytes
+    // filled, and fails only if nothing was filled.
+    fn Fill(&self, task: &Task, ar: &Range, src: Option<u64>) -> Result<u64> {
+        let mm = self.MM()?;
+
+        let mut done = 0;
+        while done < ar.Len() {
+            let len = core::cmp::min(ar.Len() - done, FILL_CHUNK);
+
+            // The source is copied in before the destination mapping is
+            // locked, as both may be in the same address space.
+            let data = match src {
+                None => None,
+                Some(src) => match task.CopyInVec::<u8>(src + done, len as usize) {
+                    Err(e) => {
+                        if done == 0 {
+                            return Err(e);
+                        }
+                        break;
+                    }
+                    Ok(data) => Some(data),
+                },
+            };
+
+            let chunk = Range::New(ar.Start() + done, len);
+            match mm.FillUserfaultfd(self, &chunk, data.as_ref().map(|d| &d[..])) {
+                Err(e) => {
+                    if done == 0 {
+                        return Err(e);
+                    }
+                    break;
+                }
+                Ok(n) => {
+                    done += n;
+                    if n < len {
+                        break;
+                    }
+                }
+            }
+        }
+
+        return Ok(done);
+    }
+}
+
+// ValidateRange checks a range passed to the userfaultfd ioctls. Equivalent
+// to Linux's fs/userfaultfd.c:validate_range.
+fn ValidateRange(start: u64, len: u64) -> Result<Range> {
+    if start & (MemoryDef::PAGE_SIZE - 1) != 0 || len & (MemoryDef::PAGE_SIZE - 1) != 0 {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    if len == 0 || start >= MemoryDef::LOWER_TOP || len > MemoryDef::LOWER_TOP - start {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    return Ok(Range::New(start, len));
+}
+
+// NewUserfaultfd returns a userfaultfd handling the faults of the address
+// space of task.
+pub fn NewUserfaultfd(task: &Task, flags: i32) -> File {
+    let uffd = Userfaultfd::New(&task.mm, flags);
+    return NewUserfaultfdFile(task, &uffd);
+}
+
+fn NewUserfaultfdFile(task: &Task, uffd: &Userfaultfd) -> File {
+    // name matches fs/userfaultfd.c:new_userfaultfd.
+    let inode = NewAnonInode(task);
+    let dirent = Dirent::New(&inode, "anon_inode:[userfaultfd]");
+
+    let flags = uffd.lock().flags;
+    let ops = UserfaultfdOperations(Arc::new(UserfaultfdOperationsInternal {
+        uffd: uffd.clone(),
+    }));
+
+    return File::New(
+        &dirent,
+        &FileFlags {
+            Read: true,
+            Write: true,
+            NonBlocking: flags & UFFD_NONBLOCK != 0,
+            NonSeekable: true,
+            ..Default::default()
+        },
+        ops.into(),
+    );
+}
+
+// NewUserfaultfdFD installs a file descriptor for the userfaultfd of a
+// forked child in the reader of UFFD_EVENT_FORK.
+fn NewUserfaultfdFD(task: &Task, uffd: &Userfaultfd) -> Result<i32> {
+    let flags = uffd.lock().flags;
+    let file = NewUserfaultfdFile(task, uffd);
+    return task.NewFDFrom(
+        0,
+        &file,
+        &FDFlags {
+            CloseOnExec: flags & UFFD_CLOEXEC != 0,
+        },
+    );
+}
+
+pub struct UserfaultfdOperationsInternal {
+    pub uffd: Userfaultfd,
+}
+
+impl Drop for UserfaultfdOperationsInternal {
+    fn drop(&mut self) {
+        self.uffd.Release();
+    }
+}
+
+// UserfaultfdOperations implements the file returned by userfaultfd(2).
+#[derive(Clone)]
+pub struct UserfaultfdOperations(Arc<UserfaultfdOperationsInternal>);
+
+impl Deref for UserfaultfdOperations {
+    type Target = Arc<UserfaultfdOperationsInternal>;
+
+    fn deref(&self) -> &Arc<UserfaultfdOperationsInternal> {
+        &self.0
+    }
+}
+
+impl Waitable for UserfaultfdOperations {
+    fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
+        let u = self.uffd.lock();
+
+        let mut ready = 0;
+        if u.msgs.len() > 0 {
+            ready |= READABLE_EVENT;
+        }
+
+        return mask & ready;
+    }
+
+    fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
+        let q = self.uffd.lock().queue.clone();
+        q.EventRegister(task, e, mask)
+    }
+
+    fn EventUnregister(&self, task: &Task, e: &WaitEntry) {
+        let q = self.uffd.lock().queue.clone();
+        q.EventUnregister(task, e)
+    }
+}
+
+impl SpliceOperations for UserfaultfdOperations {}
+
+impl FileOperations for UserfaultfdOperations {
+    fn as_any(&self) -> &Any {
+        return self;
+    }
+
+    fn FopsType(&self) -> FileOpsType {
+        return FileOpsType::UserfaultfdOperations;
+    }
+
+    fn Seekable(&self) -> bool {
+        return false;
+    }
+
+    fn Seek(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _whence: i32,
+        _current: i64,
+        _offset: i64,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ESPIPE));
+    }
+
+    fn ReadDir(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _offset: i64,
+        _serializer: &mut DentrySerializer,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::ENOTDIR));
+    }
+
+    fn ReadAt(
+        &self,
+        task: &Task,
+        _f: &File,
+        dsts: &mut [IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return self.uffd.Read(task, dsts);
+    }
+
+    fn WriteAt(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _srcs: &[IoVec],
+        _offset: i64,
+        _blocking: bool,
+    ) -> Result<i64> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Append(&self, task: &Task, f: &File, srcs: &[IoVec]) -> Result<(i64, i64)> {
+        let n = self.WriteAt(task, f, srcs, 0, false)?;
+        return Ok((n, 0));
+    }
+
+    fn Fsync(
+        &self,
+        _task: &Task,
+        _f: &File,
+        _start: i64,
+        _end: i64,
+        _syncType: SyncType,
+    ) -> Result<()> {
+        return Err(Error::SysError(SysErr::EINVAL));
+    }
+
+    fn Flush(&self, _task: &Task, _f: &File) -> Result<()> {
+        return Ok(());
+    }
+
+    fn UnstableAttr(&self, task: &Task, f: &File) -> Result<UnstableAttr> {
+        let inode = f.Dirent.Inode();
+        return inode.UnstableAttr(task);
+    }
+
+    fn Ioctl(&self, task: &Task, _f: &File, _fd: i32, request: u64, val: u64) -> Result<()> {
+        return self.uffd.Ioctl(task, request, val);
+    }
+
+    fn IterateDir(
+        &self,
+        _task: &Task,
+        _d: &Dirent,
+        _dirCtx: &mut DirCtx,
+        _offset: i32,
+    ) -> (i32, Result<i64>) {
+        return (0, Err(Error::SysError(SysErr::ENOTDIR)));
+    }
+
+    fn Mappable(&self) -> Result<MMappable> {
+        return Err(Error::SysError(SysErr::ENODEV));
+    }
+}
+
+impl SockOperations for UserfaultfdOperations {}
@@ -19,12 +19,14 @@ use alloc::sync::Arc;
 use core::ops::Deref;
 
 use super::super::super::auth::userns::*;
+use super::nsfs::*;
 
 #[derive(Default)]
 pub struct UTSNamespaceInternal {
     pub hostName: String,
     pub domainName: String,
     pub userns: UserNameSpace,
+    pub id: u64,
 }
 
 #[derive(Clone, Default)]
@@ -44,6 +46,7 @@ impl UTSNamespace {
             hostName: hostName,
             domainName: domainName,
             userns: userns,
+            id: NewNamespaceInode(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
@@ -65,6 +68,10 @@ impl UTSNamespace {
         self.lock().domainName = domain;
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.lock().id;
+    }
+
     pub fn UserNamespace(&self) -> UserNameSpace {
         return self.lock().userns.clone();
     }
@@ -75,6 +82,7 @@ impl UTSNamespace {
             hostName: me.hostName.to_string(),
             domainName: me.domainName.to_string(),
             userns: userns.clone(),
+            id: NewNamespaceInode(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
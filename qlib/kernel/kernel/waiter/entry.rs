@@ -21,6 +21,7 @@ use core::ops::Deref;
 use super::super::epoll::epoll_entry::*;
 use super::super::fasync::*;
 use super::super::futex::*;
+use super::super::io_uring::*;
 use super::waiter::*;
 use super::*;
 use crate::qlib::TaskId;
@@ -31,6 +32,8 @@ pub enum WaitContext {
     EpollContext(PollEntry),
     // use Arc instead of Weak as the Unregister will be called in the File Drop
     FileAsync(FileAsync),
+    // io_uring poll requests hold a weak reference to their ring.
+    IoUringPoll(IoUringPoll),
 }
 
 impl Drop for WaitContext {
@@ -85,6 +88,9 @@ impl WaitContext {
             WaitContext::FileAsync(_) => {
                 return None
             }
+            WaitContext::IoUringPoll(_) => {
+                return None
+            }
             _ => (),
         }
 
@@ -103,6 +109,9 @@ impl WaitContext {
             WaitContext::FileAsync(a) => {
                 a.Callback(mask);
             }
+            WaitContext::IoUringPoll(p) => {
+                p.Callback(mask);
+            }
             _ => (),
         }
     }
@@ -30,6 +30,7 @@ use super::super::super::addr::*;
 use super::super::super::auxv::*;
 use super::super::super::common::*;
 use super::super::super::limits::*;
+use super::super::super::linux::userfaultfd::*;
 use super::super::super::linux_def::*;
 use super::super::super::mem::areaset::*;
 use super::super::super::pagetable::*;
@@ -39,6 +40,7 @@ use super::super::arch::x86_64::context::*;
 use super::super::asm::*;
 use super::super::fs::dirent::*;
 use super::super::kernel::aio::aio_context::*;
+use super::super::kernel::userfaultfd::*;
 use super::super::mm::*;
 use super::super::stack::*;
 use super::super::task::*;
@@ -55,6 +57,55 @@ use super::*;
 use crate::qlib::kernel::SHARESPACE;
 use crate::qlib::vcpu_mgr::VcpuMode;
 
+// SmapsUsage is the memory usage of a range of a MemoryManager as reported by
+// /proc/[pid]/smaps. All fields are in bytes.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SmapsUsage {
+    pub rss: u64,
+    pub pss: u64,
+    pub sharedClean: u64,
+    pub sharedDirty: u64,
+    pub privateClean: u64,
+    pub privateDirty: u64,
+    pub referenced: u64,
+    pub anonymous: u64,
+    pub anonHugePages: u64,
+    pub swap: u64,
+    pub locked: u64,
+}
+
+impl SmapsUsage {
+    pub fn Add(&mut self, other: &Self) {
+        self.rss += other.rss;
+        self.pss += other.pss;
+        self.sharedClean += other.sharedClean;
+        self.sharedDirty += other.sharedDirty;
+        self.privateClean += other.privateClean;
+        self.privateDirty += other.privateDirty;
+        self.referenced += other.referenced;
+        self.anonymous += other.anonymous;
+        self.anonHugePages += other.anonHugePages;
+        self.swap += other.swap;
+        self.locked += other.locked;
+    }
+
+    pub fn String(&self) -> String {
+        let mut ret = "".to_string();
+        ret += &format!("Rss:            {:>8} kB\n", self.rss / 1024);
+        ret += &format!("Pss:            {:>8} kB\n", self.pss / 1024);
+        ret += &format!("Shared_Clean:   {:>8} kB\n", self.sharedClean / 1024);
+        ret += &format!("Shared_Dirty:   {:>8} kB\n", self.sharedDirty / 1024);
+        ret += &format!("Private_Clean:  {:>8} kB\n", self.privateClean / 1024);
+        ret += &format!("Private_Dirty:  {:>8} kB\n", self.privateDirty / 1024);
+        ret += &format!("Referenced:     {:>8} kB\n", self.referenced / 1024);
+        ret += &format!("Anonymous:      {:>8} kB\n", self.anonymous / 1024);
+        ret += &format!("AnonHugePages:  {:>8} kB\n", self.anonHugePages / 1024);
+        ret += &format!("Swap:           {:>8} kB\n", self.swap / 1024);
+        ret += &format!("Locked:         {:>8} kB\n", self.locked / 1024);
+        return ret;
+    }
+}
+
 pub struct MMMapping {
     pub vmas: AreaSet<VMA>,
 
@@ -171,6 +222,12 @@ pub struct MemoryManagerInternal {
     pub layout: QMutex<MmapLayout>,
     pub aioManager: AIOManager,
     pub membarrierPrivateEnabled: AtomicBool,
+
+    // pendingCharge is the size in bytes of the pages allocated for the
+    // address space which are not charged to the memory limit of the
+    // container yet. Pages are allocated with the mapping lock held, so they
+    // are charged once it is released.
+    pub pendingCharge: AtomicU64,
 }
 
 #[derive(Clone)]
@@ -208,6 +265,13 @@ impl MemoryManagerWeak {
     pub fn Upgrade(&self) -> MemoryManager {
         return MemoryManager(self.data.upgrade().expect("MemoryManagerWeak upgrade fail"));
     }
+
+    pub fn TryUpgrade(&self) -> Option<MemoryManager> {
+        match self.data.upgrade() {
+            None => return None,
+            Some(data) => return Some(MemoryManager(data)),
+        }
+    }
 }
 
 impl MemoryManager {
@@ -223,12 +287,16 @@ impl MemoryManager {
             private: true,
             growsDown: false,
             dontfork: false,
+            dontdump: false,
+            hugepage: false,
+            nohugepage: false,
             mlockMode: MLockMode::MlockNone,
             kernel: true,
             hint: String::from("Kernel Space"),
             id: None,
             numaPolicy: 0,
             numaNodemask: 0,
+            uffd: None,
         };
 
         let gap = vmas.FindGap(MemoryDef::PHY_LOWER_ADDR);
@@ -297,6 +365,7 @@ impl MemoryManager {
             layout: QMutex::new(layout),
             aioManager: AIOManager::default(),
             membarrierPrivateEnabled: AtomicBool::new(false),
+            pendingCharge: AtomicU64::new(0),
         };
 
         let mm = Self(Arc::new(internal));
@@ -678,6 +747,183 @@ impl MemoryManager {
         return ret.as_bytes().to_vec();
     }
 
+    // SmapsUsageLocked walks the page table entries of the vma at range and
+    // accounts them the way Linux's smaps_pte_entry does. A page's
+    // proportional share is its size divided by the number of references to
+    // it held by the page pool.
+    //
+    // Preconditions: mm.mappingRWLock must be locked.
+    pub fn SmapsUsageLocked(&self, vma: &VMA, range: &Range) -> SmapsUsage {
+        let mut usage = SmapsUsage::default();
+        let anonymous = vma.id.is_none();
+        let locked = vma.mlockMode != MLockMode::MlockNone;
+
+        let _ = self.pagetable.read().pt.Traverse(
+            Addr(range.Start()),
+            Addr(range.End()),
+            |entry, _vaddr| {
+                let size = if IsHugeEntry(entry) {
+                    MemoryDef::HUGE_PAGE_SIZE
+                } else {
+                    MemoryDef::PAGE_SIZE
+                };
+
+                let flags = entry.flags();
+                if flags & PageTableFlags::PRESENT != PageTableFlags::PRESENT {
+                    if flags & PageTableFlags::BIT_9 == PageTableFlags::BIT_9 {
+                        // bit9 indicates the page is swapped out
+                        usage.swap += size;
+                    }
+                    return;
+                }
+
+                let refs = match PAGE_MGR.GetRef(entry.addr().as_u64()) {
+                    Ok(0) | Err(_) => 1,
+                    Ok(refs) => refs,
+                };
+
+                let dirty = flags & PageTableFlags::DIRTY == PageTableFlags::DIRTY;
+                usage.rss += size;
+                usage.pss += size / refs;
+                match (refs > 1, dirty) {
+                    (true, true) => usage.sharedDirty += size,
+                    (true, false) => usage.sharedClean += size,
+                    (false, true) => usage.privateDirty += size,
+                    (false, false) => usage.privateClean += size,
+                }
+
+                if flags & PageTableFlags::ACCESSED == PageTableFlags::ACCESSED {
+                    usage.referenced += size;
+                }
+
+                if anonymous {
+                    usage.anonymous += size;
+                    if IsHugeEntry(entry) {
+                        usage.anonHugePages += size;
+                    }
+                }
+
+                if locked {
+                    usage.locked += size;
+                }
+            },
+            false,
+        );
+
+        return usage;
+    }
+
+    // SmapsUsages returns the maps entry and the memory usage of each
+    // non-kernel vma of mm.
+    fn SmapsUsages(&self, task: &Task) -> Vec<(String, Range, SmapsUsage)> {
+        let _ml = self.MappingReadLock();
+
+        let mut vmas = Vec::new();
+        {
+            let mapping = self.mapping.lock();
+            let mut seg = mapping.vmas.FirstSeg();
+            while !seg.IsTail() {
+                let vma = seg.Value();
+                if !vma.kernel {
+                    vmas.push((vma, seg.Range()));
+                }
+                seg = seg.NextSeg();
+            }
+        }
+
+        let mut ret = Vec::with_capacity(vmas.len());
+        for (vma, range) in &vmas {
+            let usage = self.SmapsUsageLocked(vma, range);
+            ret.push((self.PrintVma(task, vma, range), range.clone(), usage));
+        }
+
+        return ret;
+    }
+
+    // GenSmapsSnapshot returns the content of /proc/[pid]/smaps: the maps
+    // entries, each followed by the memory usage of the vma.
+    pub fn GenSmapsSnapshot(&self, task: &Task) -> Vec<u8> {
+        let mut ret = "".to_string();
+        for (entry, range, usage) in &self.SmapsUsages(task) {
+            ret += entry;
+            ret += "\n";
+            ret += &format!("Size:           {:>8} kB\n", range.Len() / 1024);
+            ret += &usage.String();
+        }
+
+        ret += Self::VSYSCALL_MAPS_ENTRY;
+        ret += &format!("Size:           {:>8} kB\n", MemoryDef::PAGE_SIZE / 1024);
+        ret += &SmapsUsage::default().String();
+
+        return ret.as_bytes().to_vec();
+    }
+
+    // GenSmapsRollupSnapshot returns the content of /proc/[pid]/smaps_rollup:
+    // the memory usage of all vmas of mm summed up under a single entry
+    // spanning them.
+    pub fn GenSmapsRollupSnapshot(&self, task: &Task) -> Vec<u8> {
+        let usages = self.SmapsUsages(task);
+
+        let (start, end) = match (usages.first(), usages.last()) {
+            (Some(first), Some(last)) => (first.1.Start(), last.1.End()),
+            _ => (0, 0),
+        };
+
+        let mut total = SmapsUsage::default();
+        for (_, _, usage) in &usages {
+            total.Add(usage);
+        }
+
+        let mut ret = format!("{:08x}-{:08x} ---p 00000000 00:00 0", start, end);
+        let pad = if ret.len() < 73 { 73 - ret.len() } else { 1 };
+        ret += &String::from_utf8(vec![b' '; pad]).unwrap();
+        ret += "[rollup]\n";
+        ret += &total.String();
+
+        return ret.as_bytes().to_vec();
+    }
+
+    // FileResidentSetSize returns the resident bytes of the file backed vmas
+    // of mm, which are accounted as page cache.
+    pub fn FileResidentSetSize(&self) -> u64 {
+        return self.residentBytes(true);
+    }
+
+    // AnonResidentSetSize returns the resident bytes of the anonymous vmas of
+    // mm, which are the pages charged to the memory limit of the container.
+    pub fn AnonResidentSetSize(&self) -> u64 {
+        return self.residentBytes(false);
+    }
+
+    fn residentBytes(&self, file: bool) -> u64 {
+        let _ml = self.MappingReadLock();
+
+        let mut ranges = Vec::new();
+        {
+            let mapping = self.mapping.lock();
+            let mut seg = mapping.vmas.FirstSeg();
+            while !seg.IsTail() {
+                let vma = seg.Value();
+                if !vma.kernel && vma.id.is_some() == file {
+                    ranges.push(seg.Range());
+                }
+                seg = seg.NextSeg();
+            }
+        }
+
+        let mut total = 0;
+        for range in &ranges {
+            let (rss, _) = self
+                .pagetable
+                .read()
+                .pt
+                .ResidentBytes(range.Start(), range.End());
+            total += rss;
+        }
+
+        return total;
+    }
+
     pub fn SetExecutable(&self, dirent: &Dirent) {
         self.metadata.lock().executable = Some(dirent.clone());
     }
@@ -1026,6 +1272,13 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EFAULT));
         }
 
+        // Missing pages of a range registered with a userfaultfd are only
+        // populated by its reader. The page fault handler and FixPermission
+        // wait for them once the mapping lock is released.
+        if vma.uffd.is_some() {
+            return Err(Error::SysError(SysErr::EFAULT));
+        }
+
         let exec = vma.effectivePerms.Exec();
         match &vma.mappable.HostIops() {
             Some(iops) => {
@@ -1044,12 +1297,20 @@ impl MemoryManager {
                         CopyPage(page, phyAddr);
                         self.MapPageWriteLocked(pageAddr, page, exec);
                         super::super::PAGE_MGR.DerefPage(page);
+                        self.AddPendingCharge(MemoryDef::PAGE_SIZE);
                     } else {
                         self.MapPageReadLocked(pageAddr, phyAddr, exec);
                     }
                 } else {
                     let writeable = vma.effectivePerms.Write();
                     if writeable {
+                        // a tmpfs page is charged when it may be written
+                        let tmpfsUsage = iops.lock().tmpfsUsage.clone();
+                        if let Some(usage) = tmpfsUsage {
+                            if usage.ReservePage(fileOffset)? {
+                                self.AddPendingCharge(MemoryDef::PAGE_SIZE);
+                            }
+                        }
                         self.MapPageWriteLocked(pageAddr, phyAddr, exec);
                     } else {
                         self.MapPageReadLocked(pageAddr, phyAddr, exec);
@@ -1082,6 +1343,10 @@ impl MemoryManager {
                 //let vmaOffset = pageAddr - range.Start();
                 //let phyAddr = vmaOffset + vma.offset; // offset in the phyAddr
 
+                if self.InstallHugePageLocked(vma, pageAddr, range, exec) {
+                    return Ok(());
+                }
+
                 let phyAddr = super::super::PAGE_MGR.AllocPage(true).unwrap();
                 let writeable = vma.effectivePerms.Write();
                 if writeable {
@@ -1091,11 +1356,49 @@ impl MemoryManager {
                 }
 
                 super::super::PAGE_MGR.DerefPage(phyAddr);
+                self.AddPendingCharge(MemoryDef::PAGE_SIZE);
                 return Ok(());
             }
         }
     }
 
+    // InstallHugePageLocked backs the 2MB aligned range around pageAddr with a
+    // 2MB page if the vma allows it, the range is inside the vma and none of
+    // the range is mapped yet. It returns false if the caller should map a
+    // 4KB page instead.
+    pub fn InstallHugePageLocked(&self, vma: &VMA, pageAddr: u64, range: &Range, exec: bool) -> bool {
+        if !vma.HugePageEnabled() {
+            return false;
+        }
+
+        let start = pageAddr & !(MemoryDef::HUGE_PAGE_SIZE - 1);
+        if start < range.Start() || start + MemoryDef::HUGE_PAGE_SIZE > range.End() {
+            return false;
+        }
+
+        let pt = self.pagetable.write();
+        if !pt.pt.HugePageMappable(start) {
+            return false;
+        }
+
+        let phyAddr = match super::super::PAGE_MGR.AllocHugePage(false) {
+            Err(_) => return false,
+            Ok(addr) => addr,
+        };
+
+        let flags = PageOpts::New(true, vma.effectivePerms.Write(), exec).Val();
+        match pt.pt.MapHugePage(Addr(start), Addr(phyAddr), flags, &*PAGE_MGR) {
+            Ok(true) => {
+                self.AddPendingCharge(MemoryDef::HUGE_PAGE_SIZE);
+                return true;
+            }
+            _ => {
+                super::super::PAGE_MGR.FreeHugePage(phyAddr).unwrap();
+                return false;
+            }
+        }
+    }
+
     pub fn MapPageWriteLocked(&self, vAddr: u64, pAddr: u64, exec: bool) {
         let pt = self.pagetable.write();
         pt.pt
@@ -1141,6 +1444,7 @@ impl MemoryManager {
         let page = { super::super::PAGE_MGR.AllocPage(false).unwrap() };
         CopyPage(page, phyAddr);
         self.MapPageWriteLocked(pageAddr, page, exec);
+        self.AddPendingCharge(MemoryDef::PAGE_SIZE);
     }
 
     pub fn CopyOnWrite(&self, pageAddr: u64, vma: &VMA) {
@@ -1263,9 +1567,56 @@ impl MemoryManager {
             return Err(Error::SysError(SysErr::EFAULT));
         }
 
-        let rl = self.MappingReadLock();
+        loop {
+            let rl = self.MappingReadLock();
+
+            let res = self.FixPermissionLocked(task, &rl, vAddr, len, writeReq, allowPartial);
+            let start = match res {
+                Ok(n) if n == len => return res,
+                Ok(n) => vAddr + n,
+                Err(Error::SysError(SysErr::EFAULT)) => vAddr,
+                Err(_) => return res,
+            };
+
+            // As copy_from_user and copy_to_user do in Linux, wait for the
+            // reader of the userfaultfd to populate a missing page of a
+            // registered range, then check the range again.
+            let (uffd, addr) = match self.MissingUserfaultPageLocked(start, vAddr + len) {
+                None => return res,
+                Some(f) => f,
+            };
 
-        self.FixPermissionLocked(task, &rl, vAddr, len, writeReq, allowPartial)
+            match uffd.HandleFault(task, rl, addr, writeReq) {
+                Err(Error::ErrInterrupted) => return Err(Error::SysError(SysErr::ERESTARTSYS)),
+                Err(e) => return Err(e),
+                Ok(()) => (),
+            }
+        }
+    }
+
+    // MissingUserfaultPageLocked returns the first missing page in
+    // [start, end) which is in a range registered with a userfaultfd handling
+    // kernel mode faults, and that userfaultfd. It stops at the first page
+    // which is not mapped by any vma.
+    fn MissingUserfaultPageLocked(&self, start: u64, end: u64) -> Option<(Userfaultfd, u64)> {
+        let mut addr = Addr(start).RoundDown().ok()?.0;
+        while addr < end {
+            if self.VirtualToPhyLocked(addr).is_err() {
+                let (vma, _) = self.GetVmaAndRangeLocked(addr)?;
+                if !vma.effectivePerms.Any() {
+                    return None;
+                }
+
+                match vma.uffd {
+                    Some(uffd) if !uffd.UserModeOnly() => return Some((uffd, addr)),
+                    _ => (),
+                }
+            }
+
+            addr += MemoryDef::PAGE_SIZE;
+        }
+
+        return None;
     }
 
     // check whether the address range is legal.
@@ -1502,6 +1853,7 @@ impl MemoryManager {
         };
 
         let mm2 = MemoryManager(Arc::new(mmIntern2));
+        let mut uffds: Vec<(Userfaultfd, Userfaultfd)> = Vec::new();
         {
             let mappingInternal1 = self.mapping.lock();
             let mut mappingInternal2 = mm2.mapping.lock();
@@ -1560,6 +1912,26 @@ impl MemoryManager {
 
                 vma.mlockMode = MLockMode::MlockNone;
 
+                // "If this feature is enabled, the userfaultfd objects
+                // associated with a parent process are duplicated into the
+                // child process during fork(2) and a UFFD_EVENT_FORK event is
+                // delivered to the userfaultfd monitor." - ioctl_userfaultfd(2)
+                //
+                // Otherwise the child's vmas are not registered.
+                if let Some(uffd) = vma.uffd.take() {
+                    if uffd.Features() & UFFD_FEATURE_EVENT_FORK != 0 {
+                        let child = match uffds.iter().find(|(parent, _)| *parent == uffd) {
+                            Some((_, child)) => child.clone(),
+                            None => {
+                                let child = uffd.Fork(&mm2);
+                                uffds.push((uffd.clone(), child.clone()));
+                                child
+                            }
+                        };
+                        vma.uffd = Some(child);
+                    }
+                }
+
                 if vma.kernel == false {
                     //info!("vma kernel is {}, private is {}, hint is {}", vma.kernel, vma.private, vma.hint);
                     if vma.private {
@@ -1592,6 +1964,10 @@ impl MemoryManager {
 
         self.TlbShootdown();
 
+        for (parent, child) in &uffds {
+            parent.PostFork(child);
+        }
+
         SHARESPACE.hiberMgr.AddMemMgr(&mm2);
         return Ok(mm2);
     }
@@ -113,6 +113,14 @@ impl PageMgr {
         return self.pagepool.FreePage(addr)
     }
 
+    pub fn AllocHugePage(&self, incrRef: bool) -> Result<u64> {
+        return self.pagepool.AllocHugePage(incrRef);
+    }
+
+    pub fn FreeHugePage(&self, addr: u64) -> Result<()> {
+        return self.pagepool.FreeHugePage(addr);
+    }
+
     pub fn VsyscallPages(&self) -> Arc<Vec<u64>> {
         let pages = {
             let mut pages = self.vsyscallPages.lock();
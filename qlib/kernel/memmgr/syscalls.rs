@@ -13,16 +13,20 @@
 // limitations under the License.
 
 use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
 use core::u64;
 
 use super::*;
 use super::super::kernel::futex::*;
+use super::super::kernel::kernel::GetKernel;
+use super::super::kernel::userfaultfd::*;
 use super::super::memmgr::mm::*;
 use super::super::memmgr::vma::*;
 use super::super::super::addr::*;
 use super::super::super::common::*;
 use super::super::super::limits::*;
 use super::super::super::linux::limits::*;
+use super::super::super::linux::userfaultfd::*;
 use super::super::super::linux_def::*;
 use super::super::super::range::*;
 use super::super::task::*;
@@ -36,6 +40,10 @@ pub struct MSyncOpts {
     pub Invalidate: bool,
 }
 
+// CHARGE_BATCH is the size of the pages charged to the memory limit of the
+// container at once.
+pub const CHARGE_BATCH: u64 = 64 * MemoryDef::PAGE_SIZE;
+
 impl MemoryManager {
     // MMap establishes a memory mapping.
     pub fn MMap(&self, task: &Task, opts: &mut MMapOpts) -> Result<u64> {
@@ -180,8 +188,14 @@ impl MemoryManager {
 
         let ar = Addr(addr).ToRange(length)?;
 
+        let uffds = self.UserfaultfdsLocked(&ar);
         let ret = self.RemoveVMAsLocked(&ar);
         self.TlbShootdown();
+
+        for uffd in &uffds {
+            uffd.PostUnmap(&ar);
+        }
+
         return ret;
     }
 
@@ -410,8 +424,26 @@ impl MemoryManager {
         // Call vseg.Value() (rather than vseg.ValuePtr()) to make a copy of the
         // vma.
         let vseg = self.mapping.lock().vmas.Isolate(&vseg, &oldAR);
-        let vma = vseg.Value();
+        let mut vma = vseg.Value();
         self.mapping.lock().vmas.Remove(&vseg);
+
+        // "If this feature is enabled, when the faulting process invokes
+        // mremap(2), the userfaultfd monitor will receive an event of type
+        // UFFD_EVENT_REMAP." - ioctl_userfaultfd(2)
+        //
+        // Otherwise the moved range is no longer registered.
+        let remapUffd = match vma.uffd.take() {
+            Some(uffd) => {
+                if uffd.Features() & UFFD_FEATURE_EVENT_REMAP != 0 {
+                    vma.uffd = Some(uffd.clone());
+                    Some(uffd)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
         let gap = self.mapping.lock().vmas.FindGap(newAR.Start());
         let vseg = self.mapping.lock().vmas.Insert(&gap, &newAR, vma.clone());
 
@@ -425,6 +457,10 @@ impl MemoryManager {
         self.PopulateVMARemapLocked(task, &vseg, &newAR, &Range::New(oldAddr, oldSize), true)?;
         self.TlbShootdown();
 
+        if let Some(uffd) = remapUffd {
+            uffd.PostRemap(oldAddr, newAR.Start(), oldSize);
+        }
+
         return Ok(newAR.Start());
     }
 
@@ -782,6 +818,215 @@ impl MemoryManager {
         return Ok(());
     }
 
+    pub fn SetDontDump(&self, _task: &Task, addr: u64, length: u64, dontdump: bool) -> Result<()> {
+        let ar = match Addr(addr).ToRange(length) {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(r) => r,
+        };
+
+        let _ml = self.MappingWriteLock();
+
+        let mut mapping = self.mapping.lock();
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        while vseg.Ok() && vseg.Range().Start() < ar.End() {
+            vseg = mapping.vmas.Isolate(&vseg, &ar);
+            let mut vma = vseg.Value();
+            vma.dontdump = dontdump;
+            vseg.SetValue(vma);
+
+            vseg = vseg.NextSeg();
+        }
+
+        mapping.vmas.MergeRange(&ar);
+        mapping.vmas.MergeAdjacent(&ar);
+
+        if mapping.vmas.SpanRange(&ar) != ar.Len() {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        return Ok(());
+    }
+
+    // SetHugePage sets the MADV_HUGEPAGE (hugepage == true) or
+    // MADV_NOHUGEPAGE advice for the vmas in [addr, addr+length). The 2MB
+    // pages already mapped are left in place, as Linux does.
+    pub fn SetHugePage(&self, _task: &Task, addr: u64, length: u64, hugepage: bool) -> Result<()> {
+        let ar = match Addr(addr).ToRange(length) {
+            Err(_) => return Err(Error::SysError(SysErr::EINVAL)),
+            Ok(r) => r,
+        };
+
+        let _ml = self.MappingWriteLock();
+
+        let mut mapping = self.mapping.lock();
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        while vseg.Ok() && vseg.Range().Start() < ar.End() {
+            vseg = mapping.vmas.Isolate(&vseg, &ar);
+            let mut vma = vseg.Value();
+            vma.hugepage = hugepage;
+            vma.nohugepage = !hugepage;
+            vseg.SetValue(vma);
+
+            vseg = vseg.NextSeg();
+        }
+
+        mapping.vmas.MergeRange(&ar);
+        mapping.vmas.MergeAdjacent(&ar);
+
+        if mapping.vmas.SpanRange(&ar) != ar.Len() {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        return Ok(());
+    }
+
+    // RegisterUserfaultfd routes the missing page faults of ar to uffd. Only
+    // private anonymous mappings are supported.
+    pub fn RegisterUserfaultfd(&self, uffd: &Userfaultfd, ar: &Range) -> Result<()> {
+        let _ml = self.MappingWriteLock();
+
+        let mut mapping = self.mapping.lock();
+
+        // Check the whole range before changing any vma, as Linux's
+        // fs/userfaultfd.c:userfaultfd_register does.
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        let mut lastEnd = ar.Start();
+        while lastEnd < ar.End() {
+            if !vseg.Ok() || lastEnd < vseg.Range().Start() {
+                return Err(Error::SysError(SysErr::ENOMEM));
+            }
+
+            let vma = vseg.Value();
+            match vma.mappable {
+                MMappable::None => (),
+                _ => return Err(Error::SysError(SysErr::EINVAL)),
+            }
+
+            if vma.kernel || !vma.private {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            match vma.uffd {
+                Some(ref other) if other != uffd => {
+                    return Err(Error::SysError(SysErr::EBUSY));
+                }
+                _ => (),
+            }
+
+            lastEnd = vseg.Range().End();
+            vseg = vseg.NextSeg();
+        }
+
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        while vseg.Ok() && vseg.Range().Start() < ar.End() {
+            vseg = mapping.vmas.Isolate(&vseg, &ar);
+            let mut vma = vseg.Value();
+            vma.uffd = Some(uffd.clone());
+            vseg.SetValue(vma);
+
+            vseg = vseg.NextSeg();
+        }
+
+        mapping.vmas.MergeRange(&ar);
+        mapping.vmas.MergeAdjacent(&ar);
+
+        return Ok(());
+    }
+
+    // UserfaultfdsLocked returns the userfaultfds registered in ar.
+    pub fn UserfaultfdsLocked(&self, ar: &Range) -> Vec<Userfaultfd> {
+        let mut uffds: Vec<Userfaultfd> = Vec::new();
+
+        let mapping = self.mapping.lock();
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        while vseg.Ok() && vseg.Range().Start() < ar.End() {
+            if let Some(uffd) = vseg.Value().uffd {
+                if !uffds.contains(&uffd) {
+                    uffds.push(uffd);
+                }
+            }
+
+            vseg = vseg.NextSeg();
+        }
+
+        return uffds;
+    }
+
+    // UnregisterUserfaultfd removes the parts of ar registered with uffd.
+    pub fn UnregisterUserfaultfd(&self, uffd: &Userfaultfd, ar: &Range) {
+        let _ml = self.MappingWriteLock();
+
+        let mut mapping = self.mapping.lock();
+        let mut vseg = mapping.vmas.LowerBoundSeg(ar.Start());
+        while vseg.Ok() && vseg.Range().Start() < ar.End() {
+            if vseg.Value().uffd.as_ref() == Some(uffd) {
+                vseg = mapping.vmas.Isolate(&vseg, &ar);
+                let mut vma = vseg.Value();
+                vma.uffd = None;
+                vseg.SetValue(vma);
+            }
+
+            vseg = vseg.NextSeg();
+        }
+
+        mapping.vmas.MergeRange(&ar);
+        mapping.vmas.MergeAdjacent(&ar);
+    }
+
+    // FillUserfaultfd populates the missing pages of ar, which must be
+    // registered with uffd, with data or with zeros if data is None. It
+    // returns the number of bytes filled, and fails only if the first page
+    // can't be filled.
+    pub fn FillUserfaultfd(
+        &self,
+        uffd: &Userfaultfd,
+        ar: &Range,
+        data: Option<&[u8]>,
+    ) -> Result<u64> {
+        let _ml = self.MappingWriteLock();
+
+        let mut addr = ar.Start();
+        while addr < ar.End() {
+            let err = match self.GetVmaAndRangeLocked(addr) {
+                None => Some(SysErr::ENOENT),
+                Some((vma, _)) => {
+                    if vma.uffd.as_ref() != Some(uffd) {
+                        Some(SysErr::ENOENT)
+                    } else if self.VirtualToPhyLocked(addr).is_ok() {
+                        Some(SysErr::EEXIST)
+                    } else {
+                        let page = super::super::PAGE_MGR.AllocPage(true)?;
+                        if let Some(data) = data {
+                            let offset = (addr - ar.Start()) as usize;
+                            CopyPage(page, &data[offset] as *const _ as u64);
+                        }
+
+                        let exec = vma.effectivePerms.Exec();
+                        if vma.effectivePerms.Write() {
+                            self.MapPageWriteLocked(addr, page, exec);
+                        } else {
+                            self.MapPageReadLocked(addr, page, exec);
+                        }
+                        super::super::PAGE_MGR.DerefPage(page);
+                        self.AddPendingCharge(MemoryDef::PAGE_SIZE);
+                        None
+                    }
+                }
+            };
+
+            if let Some(err) = err {
+                if addr == ar.Start() {
+                    return Err(Error::SysError(err));
+                }
+                break;
+            }
+
+            addr += MemoryDef::PAGE_SIZE;
+        }
+
+        return Ok(addr - ar.Start());
+    }
+
     pub fn VirtualMemorySizeRangeLocked(&self, ar: &Range) -> u64 {
         return self.mapping.lock().vmas.SpanRange(&ar);
     }
@@ -800,6 +1045,32 @@ impl MemoryManager {
         return self.VirtualMemorySizeLocked();
     }
 
+    // AddPendingCharge records len bytes of pages allocated for the address
+    // space, which ChargePending charges to the memory limit of the container.
+    pub fn AddPendingCharge(&self, len: u64) {
+        self.pendingCharge.fetch_add(len, Ordering::Relaxed);
+    }
+
+    // ChargePending charges the pages allocated for the address space since
+    // the last charge to the memory limit of the container of task, which may
+    // invoke the OOM killer. As Linux's MEMCG_CHARGE_BATCH, pages are charged
+    // in batches to keep the container lookup off most page faults.
+    //
+    // Preconditions: The caller must not hold the mapping lock of any
+    // MemoryManager or the TaskSet lock.
+    pub fn ChargePending(&self, task: &Task) -> Result<()> {
+        if self.pendingCharge.load(Ordering::Relaxed) < CHARGE_BATCH {
+            return Ok(());
+        }
+
+        let len = self.pendingCharge.swap(0, Ordering::Relaxed);
+        if len == 0 {
+            return Ok(());
+        }
+
+        return GetKernel().ChargeMemory(task, len);
+    }
+
     pub fn ResidentSetSizeLocked(&self) -> u64 {
         return self.pagetable.read().curRSS;
     }
@@ -19,18 +19,21 @@ use core::fmt;
 
 use super::super::super::addr::*;
 use super::super::super::common::*;
+use super::super::super::config::*;
 use super::super::super::linux_def::*;
 use super::super::super::limits::*;
 use super::super::fs::host::hostinodeop::*;
 use super::super::task::*;
 use super::super::kernel::shm::*;
 use super::super::kernel::aio::aio_context::*;
+use super::super::kernel::userfaultfd::*;
 use super::super::super::mem::areaset::*;
 use super::super::super::range::*;
 use super::arch::*;
 use super::mm::*;
 use super::*;
 use crate::qlib::bytestream::*;
+use crate::qlib::kernel::SHARESPACE;
 
 // map32Start/End are the bounds to which MAP_32BIT mappings are constrained,
 // and are equivalent to Linux's MAP32_BASE and MAP32_MAX respectively.
@@ -314,12 +317,16 @@ impl MemoryManager {
             private: opts.Private,
             growsDown: opts.GrowsDown,
             dontfork: false,
+            dontdump: false,
+            hugepage: false,
+            nohugepage: false,
             mlockMode: opts.MLockMode,
             kernel: opts.Kernel,
             hint: opts.Hint.to_string(),
             id: opts.Mapping.clone(),
             numaPolicy: 0,
             numaNodemask: 0,
+            uffd: None,
         };
 
         mapping.usageAS += opts.Length;
@@ -536,6 +543,16 @@ pub struct VMA {
     // dontfork is the MADV_DONTFORK setting for this vma configured by madvise().
     pub dontfork: bool,
 
+    // dontdump is the MADV_DONTDUMP setting for this vma configured by
+    // madvise(). A dontdump vma is excluded from core dumps.
+    pub dontdump: bool,
+
+    // hugepage and nohugepage are the MADV_HUGEPAGE and MADV_NOHUGEPAGE
+    // settings for this vma configured by madvise(), as Linux's VM_HUGEPAGE
+    // and VM_NOHUGEPAGE. At most one of them is set.
+    pub hugepage: bool,
+    pub nohugepage: bool,
+
     pub mlockMode: MLockMode,
 
     pub kernel: bool,
@@ -547,6 +564,11 @@ pub struct VMA {
 
     // numaNodemask is the NUMA nodemask for this vma set by mbind().
     pub numaNodemask: u64,
+
+    // uffd is the userfaultfd this vma is registered with by
+    // UFFDIO_REGISTER. Its missing page faults are reported to the
+    // userfaultfd reader instead of being populated.
+    pub uffd: Option<Userfaultfd>,
 }
 
 impl fmt::Debug for VMA {
@@ -565,6 +587,26 @@ impl fmt::Debug for VMA {
 }
 
 impl VMA {
+    // HugePageEnabled returns whether the vma may be backed by 2MB pages under
+    // the transparent huge page policy and its madvise() advice. Only private
+    // anonymous memory is backed by 2MB pages.
+    pub fn HugePageEnabled(&self) -> bool {
+        if self.nohugepage || !self.private || self.kernel || self.uffd.is_some() {
+            return false;
+        }
+
+        match self.mappable {
+            MMappable::None => (),
+            _ => return false,
+        }
+
+        match SHARESPACE.config.read().TransparentHugepage {
+            THPMode::Always => return true,
+            THPMode::Madvise => return self.hugepage,
+            THPMode::Never => return false,
+        }
+    }
+
     pub fn Copy(&self) -> Self {
         let copy = VMA {
             mappable: self.mappable.clone(),
@@ -576,12 +618,16 @@ impl VMA {
             private: self.private,
             growsDown: self.growsDown,
             dontfork: self.dontfork,
+            dontdump: self.dontdump,
+            hugepage: self.hugepage,
+            nohugepage: self.nohugepage,
             mlockMode: self.mlockMode,
             kernel: self.kernel,
             hint: self.hint.to_string(),
             id: self.id.clone(),
             numaPolicy: 0,
             numaNodemask: 0,
+            uffd: self.uffd.clone(),
         };
 
         return copy;
@@ -688,11 +734,15 @@ impl AreaValue for VMA {
             || vma1.private != vma2.private
             || vma1.growsDown != vma2.growsDown
             || vma1.dontfork != vma2.dontfork
+            || vma1.dontdump != vma2.dontdump
+            || vma1.hugepage != vma2.hugepage
+            || vma1.nohugepage != vma2.nohugepage
             || vma1.mlockMode != vma2.mlockMode
             || vma1.kernel != vma2.kernel
             || vma1.numaPolicy != vma2.numaPolicy
             || vma1.numaNodemask != vma2.numaNodemask
             || vma1.hint != vma2.hint
+            || vma1.uffd != vma2.uffd
         {
             return None;
         }
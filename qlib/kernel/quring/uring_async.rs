@@ -33,6 +33,7 @@ use super::super::fs::file::*;
 use super::super::kernel::aio::aio_context::*;
 use super::super::kernel::async_wait::*;
 use super::super::kernel::eventfd::*;
+use super::super::kernel::io_uring::*;
 use super::super::kernel::timer;
 use super::super::kernel::waiter::qlock::*;
 use super::super::kernel::waiter::*;
@@ -80,6 +81,10 @@ pub enum AsyncOps {
     AsyncSend(AsyncSend),
     PollHostEpollWait(PollHostEpollWait),
     AsyncConnect(AsyncConnect),
+    IoUringRead(IoUringRead),
+    IoUringWrite(IoUringWrite),
+    IoUringFsync(IoUringFsync),
+    IoUringTimeout(IoUringTimeout),
     None(AsyncNone),
 }
 
@@ -119,6 +124,10 @@ impl AsyncOps {
             AsyncOps::AsyncSend(_) => return 21,
             AsyncOps::PollHostEpollWait(_) => return 22,
             AsyncOps::AsyncConnect(_) => return 23,
+            AsyncOps::IoUringRead(_) => return 24,
+            AsyncOps::IoUringWrite(_) => return 25,
+            AsyncOps::IoUringFsync(_) => return 26,
+            AsyncOps::IoUringTimeout(_) => return 27,
             AsyncOps::None(_) => (),
         };
 
@@ -1296,6 +1305,218 @@ impl AIOFsync {
     }
 }
 
+// IoUringRead is the read of a guest io_uring request on a host regular
+// file. The data is read into a kernel buffer and copied out to the
+// application's buffers on completion.
+pub struct IoUringRead {
+    pub fd: i32,
+    pub buf: DataBuff,
+    pub iovs: Vec<IoVec>,
+    pub offset: i64,
+    pub taskId: u64,
+
+    pub userData: u64,
+    pub ring: IoUring,
+    // file keeps the host fd open until the request completes.
+    pub file: File,
+}
+
+impl AsyncOpsTrait for IoUringRead {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = Read::new(
+            types::Fd(self.fd),
+            self.buf.Ptr() as *mut u8,
+            self.buf.Len() as u32,
+        )
+        .offset(self.offset);
+
+        if SHARESPACE.config.read().UringFixedFile {
+            return op.build().flags(squeue::Flags::FIXED_FILE);
+        } else {
+            return op.build();
+        }
+    }
+
+    fn Process(&mut self, result: i32) -> bool {
+        let mut result = result;
+        if result > 0 {
+            let task = Task::GetTask(self.taskId);
+            let buf = &self.buf.buf[0..result as usize];
+            match task.CopyDataOutToIovsManual(buf, &self.iovs, true) {
+                Err(Error::SysError(e)) => result = -e,
+                Err(_) => result = -SysErr::EFAULT,
+                Ok(n) => result = n as i32,
+            }
+        }
+
+        self.ring.PostCompletion(self.userData, result, false);
+        return false;
+    }
+}
+
+impl IoUringRead {
+    pub fn New(
+        task: &Task,
+        ring: &IoUring,
+        file: &File,
+        fd: i32,
+        iovs: Vec<IoVec>,
+        offset: i64,
+        userData: u64,
+    ) -> Result<Self> {
+        task.FixPermissionForIovs(&iovs, true)?;
+        let size = IoVec::NumBytes(&iovs);
+        let buf = DataBuff::New(size as usize);
+
+        return Ok(Self {
+            fd: fd,
+            buf: buf,
+            iovs: iovs,
+            offset: offset,
+            taskId: task.taskId,
+            userData: userData,
+            ring: ring.clone(),
+            file: file.clone(),
+        });
+    }
+}
+
+// IoUringWrite is the write of a guest io_uring request on a host regular
+// file. The data is copied in when the request is submitted.
+pub struct IoUringWrite {
+    pub fd: i32,
+    pub buf: DataBuff,
+    pub offset: i64,
+
+    pub userData: u64,
+    pub ring: IoUring,
+    pub file: File,
+}
+
+impl AsyncOpsTrait for IoUringWrite {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = Write::new(
+            types::Fd(self.fd),
+            self.buf.Ptr() as *const u8,
+            self.buf.Len() as u32,
+        )
+        .offset(self.offset);
+
+        if SHARESPACE.config.read().UringFixedFile {
+            return op.build().flags(squeue::Flags::FIXED_FILE);
+        } else {
+            return op.build();
+        }
+    }
+
+    fn Process(&mut self, result: i32) -> bool {
+        self.ring.PostCompletion(self.userData, result, false);
+        return false;
+    }
+}
+
+impl IoUringWrite {
+    pub fn New(
+        task: &Task,
+        ring: &IoUring,
+        file: &File,
+        fd: i32,
+        iovs: &[IoVec],
+        offset: i64,
+        userData: u64,
+    ) -> Result<Self> {
+        task.FixPermissionForIovs(iovs, false)?;
+        let size = IoVec::NumBytes(iovs);
+        let mut buf = DataBuff::New(size);
+        task.CopyDataInFromIovs(&mut buf.buf, iovs, false)?;
+
+        return Ok(Self {
+            fd: fd,
+            buf: buf,
+            offset: offset,
+            userData: userData,
+            ring: ring.clone(),
+            file: file.clone(),
+        });
+    }
+}
+
+pub struct IoUringFsync {
+    pub fd: i32,
+    pub dataSyncOnly: bool,
+
+    pub userData: u64,
+    pub ring: IoUring,
+    pub file: File,
+}
+
+impl AsyncOpsTrait for IoUringFsync {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = if self.dataSyncOnly {
+            Fsync::new(types::Fd(self.fd)).flags(types::FsyncFlags::DATASYNC)
+        } else {
+            Fsync::new(types::Fd(self.fd))
+        };
+
+        if SHARESPACE.config.read().UringFixedFile {
+            return op.build().flags(squeue::Flags::FIXED_FILE);
+        } else {
+            return op.build();
+        }
+    }
+
+    fn Process(&mut self, result: i32) -> bool {
+        self.ring.PostCompletion(self.userData, result, false);
+        return false;
+    }
+}
+
+impl IoUringFsync {
+    pub fn New(ring: &IoUring, file: &File, fd: i32, dataSyncOnly: bool, userData: u64) -> Self {
+        return Self {
+            fd: fd,
+            dataSyncOnly: dataSyncOnly,
+            userData: userData,
+            ring: ring.clone(),
+            file: file.clone(),
+        };
+    }
+}
+
+// IoUringTimeout is the timer of a guest IORING_OP_TIMEOUT request.
+pub struct IoUringTimeout {
+    pub ts: types::Timespec,
+    pub id: u64,
+    pub ring: IoUring,
+}
+
+impl AsyncOpsTrait for IoUringTimeout {
+    fn SEntry(&self) -> squeue::Entry {
+        let op = Timeout::new(&self.ts);
+        return op.build();
+    }
+
+    fn Process(&mut self, _result: i32) -> bool {
+        // The timer either expired or was removed because the timeout
+        // completed on its completion count; the ring tells them apart.
+        self.ring.TimeoutFired(self.id);
+        return false;
+    }
+}
+
+impl IoUringTimeout {
+    pub fn New(ring: &IoUring, id: u64, ns: i64) -> Self {
+        return Self {
+            ts: types::Timespec {
+                tv_sec: ns / 1000_000_000,
+                tv_nsec: ns % 1000_000_000,
+            },
+            id: id,
+            ring: ring.clone(),
+        };
+    }
+}
+
 pub struct AsyncLinkTimeout {
     pub ts: types::Timespec,
 }
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
+use alloc::collections::vec_deque::VecDeque;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::any::Any;
@@ -95,6 +96,10 @@ pub struct HostSocketOperationsIntern {
     pub remoteAddr: QMutex<Option<SockAddr>>,
     pub hostops: HostInodeOp,
     passInq: AtomicBool,
+
+    // notifications are the datagrams queued by the kernel on a netlink
+    // socket, they are received before the ones of the host socket.
+    pub notifications: QMutex<VecDeque<Vec<u8>>>,
 }
 
 #[derive(Clone)]
@@ -130,6 +135,7 @@ impl HostSocketOperations {
             remoteAddr: QMutex::new(addr),
             hostops: hostops,
             passInq: AtomicBool::new(false),
+            notifications: QMutex::new(VecDeque::new()),
         };
 
         let ret = Self(Arc::new(ret));
@@ -179,6 +185,68 @@ impl HostSocketOperations {
             Some(ref v) => Some(v.ToVec().unwrap()),
         };
     }
+
+    // QueueNotification queues buf as a datagram sent by the kernel to the
+    // socket. It delivers the SIGEV_THREAD notifications of POSIX message
+    // queues, which glibc waits for on a netlink socket.
+    pub fn QueueNotification(&self, buf: &[u8]) {
+        self.notifications.lock().push_back(buf.to_vec());
+        self.Notify(READABLE_EVENT);
+    }
+
+    // RecvNotification receives the oldest datagram queued by
+    // QueueNotification, if any.
+    fn RecvNotification(
+        &self,
+        task: &Task,
+        dsts: &mut [IoVec],
+        flags: i32,
+        senderRequested: bool,
+    ) -> Result<Option<(i64, i32, Option<(SockAddr, usize)>, Vec<u8>)>> {
+        let msg = match self.notifications.lock().front().cloned() {
+            None => return Ok(None),
+            Some(msg) => msg,
+        };
+
+        let count = core::cmp::min(IoVec::NumBytes(dsts), msg.len());
+        task.CopyDataOutToIovs(&msg[0..count], dsts, false)?;
+
+        // Dequeue the notification only once it is delivered, so that a
+        // failed copy doesn't lose it. It may have been dequeued by another
+        // reader meanwhile.
+        if flags & MsgType::MSG_PEEK == 0 {
+            let mut notifications = self.notifications.lock();
+            if notifications.front() == Some(&msg) {
+                notifications.pop_front();
+            }
+        }
+
+        let mut msgFlags = 0;
+        if count < msg.len() {
+            msgFlags |= MsgType::MSG_TRUNC;
+        }
+
+        let ret = if flags & MsgType::MSG_TRUNC != 0 {
+            msg.len()
+        } else {
+            count
+        };
+
+        // the kernel is the sender
+        let senderAddr = if senderRequested {
+            let addr = SockAddrNetlink {
+                Family: AFType::AF_NETLINK as u16,
+                Padding: 0,
+                PortID: 0,
+                Groups: 0,
+            };
+            Some((SockAddr::Netlink(addr), addr.Len()))
+        } else {
+            None
+        };
+
+        return Ok(Some((ret as i64, msgFlags, senderAddr, Vec::new())));
+    }
 }
 
 pub const SIZEOF_SOCKADDR: usize = SocketSize::SIZEOF_SOCKADDR_INET6;
@@ -192,7 +260,12 @@ impl Waitable for HostSocketOperations {
 
     fn Readiness(&self, _task: &Task, mask: EventMask) -> EventMask {
         let fd = self.fd;
-        return NonBlockingPoll(fd, mask);
+        let mut ready = NonBlockingPoll(fd, mask);
+        if !self.notifications.lock().is_empty() {
+            ready |= mask & READABLE_EVENT;
+        }
+
+        return ready;
     }
 
     fn EventRegister(&self, task: &Task, e: &WaitEntry, mask: EventMask) {
@@ -254,6 +327,10 @@ impl FileOperations for HostSocketOperations {
         _offset: i64,
         _blocking: bool,
     ) -> Result<i64> {
+        if let Some((ret, _, _, _)) = self.RecvNotification(task, dsts, 0, false)? {
+            return Ok(ret);
+        }
+
         let size = IoVec::NumBytes(dsts);
         let buf = DataBuff::New(size);
         let iovs = buf.Iovs(size);
@@ -812,6 +889,10 @@ impl SockOperations for HostSocketOperations {
             msgHdr.msgControl = ptr::null::<u8>() as u64;
         }
 
+        if let Some(ret) = self.RecvNotification(task, dsts, flags, senderRequested)? {
+            return Ok(ret);
+        }
+
         let general = task.blocker.generalEntry.clone();
         self.EventRegister(task, &general, EVENT_READ);
         defer!(self.EventUnregister(task, &general));
@@ -838,6 +919,10 @@ impl SockOperations for HostSocketOperations {
                 _ => (),
             }
 
+            if let Some(ret) = self.RecvNotification(task, dsts, flags, senderRequested)? {
+                return Ok(ret);
+            }
+
             res = Kernel::HostSpace::IORecvMsg(
                 self.fd,
                 &mut msgHdr as *mut _ as u64,
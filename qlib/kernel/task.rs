@@ -19,6 +19,7 @@ use alloc::sync::Arc;
 use core::mem;
 use core::ops::Deref;
 use core::ptr;
+use core::sync::atomic::AtomicBool;
 use core::sync::atomic::Ordering;
 
 //use super::arch::x86_64::arch_x86::*;
@@ -194,6 +195,12 @@ pub struct Task {
     
     pub perfcounters: Option<Arc<Counters>>,
 
+    // traced and hasSyscallFilters mirror whether the thread has a ptrace
+    // tracer and seccomp filters, so that the syscall path can check them
+    // without locking the thread.
+    pub traced: AtomicBool,
+    pub hasSyscallFilters: AtomicBool,
+
     pub guard: Guard,
     //check whether the stack overflow
 }
@@ -313,6 +320,8 @@ impl Task {
             sched: TaskSchedInfo::default(),
             exiting: false,
             perfcounters: None,
+            traced: AtomicBool::new(false),
+            hasSyscallFilters: AtomicBool::new(false),
             guard: Guard::default(),
         };
 
@@ -372,10 +381,29 @@ impl Task {
             t.UserTicks += now - t.Timestamp;
         }
 
+        if state == SchedState::Blocked {
+            // The task is being switched back in.
+            t.YieldCount += 1;
+            let cpu = CPULocal::CpuId();
+            match t.LastCpu {
+                Some(last) if last != cpu => t.Migrations += 1,
+                _ => (),
+            }
+            t.LastCpu = Some(cpu);
+        }
+
         t.Timestamp = now;
         t.State = SchedState::RunningSys;
     }
 
+    pub fn AccountPageFault(&self) {
+        if self.taskId == CPULocal::WaitTask() || self.exiting == true {
+            return;
+        }
+
+        self.sched.lock().PageFaults += 1;
+    }
+
     pub fn StackOverflowCheck() {
         let rsp = GetRsp();
         let task = rsp & DEFAULT_STACK_MAST;
@@ -669,6 +697,8 @@ impl Task {
                     sched: TaskSchedInfo::default(),
                     exiting: false,
                     perfcounters: perfcounters,
+                    traced: AtomicBool::new(false),
+                    hasSyscallFilters: AtomicBool::new(false),
                     guard: Guard::default(),
                 },
             );
@@ -772,6 +802,8 @@ impl Task {
                     sched: TaskSchedInfo::default(),
                     exiting: false,
                     perfcounters: None,
+                    traced: AtomicBool::new(false),
+                    hasSyscallFilters: AtomicBool::new(false),
                     guard: Guard::default(),
                 },
             );
@@ -29,6 +29,7 @@ use super::Shutdown;
 use super::ASYNC_PROCESS;
 use super::KERNEL_STACK_ALLOCATOR;
 use super::SHARESPACE;
+use super::Scale;
 use super::TSC;
 
 static ACTIVE_TASK: AtomicU32 = AtomicU32::new(0);
@@ -57,8 +58,60 @@ extern "C" {
     pub fn context_swap_to(_fromCxt: u64, _toCtx: u64, _one: u64, _zero: u64);
 }
 
+// SchedSwitch charges the time the from task has run to its vruntime and
+// starts the time slice of the to task.
+pub fn SchedSwitch(from: Option<TaskId>, to: TaskId) {
+    let now = TSC.Rdtsc();
+    if let Some(from) = from {
+        ChargeTask(from, now);
+    }
+
+    let sched = &to.GetTask().context.sched;
+    sched.sliceStart.store(now, Ordering::Relaxed);
+    sched.chargeStart.store(now, Ordering::Relaxed);
+    if sched.Class() == SCHED_CLASS_FAIR {
+        SHARESPACE.scheduler.UpdateMinVruntime(sched.Vruntime());
+    }
+}
+
+// ChargeTask charges the time the task has run since the last charge to its
+// vruntime.
+pub fn ChargeTask(taskId: TaskId, now: i64) {
+    let sched = &taskId.GetTask().context.sched;
+    let start = sched.chargeStart.swap(now, Ordering::Relaxed);
+    if start != 0 && now > start {
+        sched.Charge(Scale(now - start) as u64 * 1000);
+    }
+}
+
+// Preempt is called on the thread timeout interrupt. It gives the vcpu to a
+// ready task when the priority and the time slice of the current task allow
+// it, e.g. a nice 19 task yields to a nice 0 task once it has used its share
+// and a SCHED_FIFO task only yields to a real-time task of higher priority.
+pub fn Preempt() {
+    let current = Task::TaskId();
+    let now = TSC.Rdtsc();
+    ChargeTask(current, now);
+
+    let sliceStart = current.GetTask().context.sched.sliceStart.load(Ordering::Relaxed);
+    let ran = if now > sliceStart {
+        Scale(now - sliceStart) as u64 * 1000
+    } else {
+        0
+    };
+
+    let vcpuId = CPULocal::CpuId() as usize;
+    if !SHARESPACE.scheduler.ShouldPreempt(current, vcpuId, ran) {
+        return;
+    }
+
+    SHARESPACE.scheduler.Schedule(current, false);
+    Wait();
+}
+
 fn switch_to(to: TaskId) {
     to.GetTask().AccountTaskLeave(SchedState::Blocked);
+    SchedSwitch(None, to);
 
     CPULocal::SetCurrentTask(to.Addr());
     let toCtx = to.GetTask();
@@ -256,7 +309,7 @@ impl Scheduler {
         }
 
         let vcpuCount = self.vcpuCnt;
-        match self.queue[0].Steal() {
+        match self.queue[0].Steal(vcpuId) {
             None => (),
             Some(t) => {
                 return Some(t)
@@ -266,7 +319,7 @@ impl Scheduler {
         // skip the current vcpu
         for i in 1..vcpuCount {
             let idx = (i + vcpuId) % vcpuCount;
-            match self.queue[idx].Steal() {
+            match self.queue[idx].Steal(vcpuId) {
                 None => (),
                 Some(t) => {
                     return Some(t)
@@ -327,7 +380,14 @@ impl Scheduler {
 
 
     pub fn Schedule(&self, taskId: TaskId, cpuAff: bool) {
-        let vcpuId = taskId.GetTask().QueueId();
+        let mut vcpuId = taskId.GetTask().QueueId();
+        let mask = taskId.GetTask().context.CPUMask();
+        if mask != 0 && mask & (1 << vcpuId) == 0 {
+            // The affinity of the task has changed since it was queued, move
+            // it to the first vcpu it may run on.
+            vcpuId = mask.trailing_zeros() as usize;
+            taskId.GetTask().SetQueueId(vcpuId);
+        }
         //assert!(CPULocal::CpuId()==vcpuId, "cpu {}, target cpu {}", CPULocal::CpuId(), vcpuId);
         self.ScheduleQ(taskId, vcpuId as _, cpuAff);
     }
@@ -21,13 +21,14 @@ use core::ops::Deref;
 use super::super::super::auth::userns::*;
 use super::super::super::common::*;
 use super::super::super::linux_def::*;
+use super::super::kernel::kernel::*;
+use super::super::kernel::nsfs::*;
 use super::processgroup::*;
 use super::session::*;
 use super::thread::*;
 use super::thread_group::*;
 use super::threads::*;
 
-pub const TASKS_LIMIT: ThreadID = 1 << 16;
 pub const INIT_TID: ThreadID = 1;
 
 #[derive(Default)]
@@ -49,6 +50,9 @@ pub struct PIDNamespaceInternal {
     pub pgids: BTreeMap<ProcessGroup, ProcessGroupID>,
     //ProcessGroup uid to ProcessGroup id of this namespace
     pub exiting: bool,
+
+    // id is the namespace's inode number.
+    pub id: u64,
 }
 
 #[derive(Clone, Default)]
@@ -85,6 +89,7 @@ impl PIDNamespace {
             processGroups: BTreeMap::new(),
             pgids: BTreeMap::new(),
             exiting: false,
+            id: NewNamespaceInode(),
         };
 
         return Self(Arc::new(QMutex::new(internal)));
@@ -208,6 +213,16 @@ impl PIDNamespace {
         return self.lock().userns.clone();
     }
 
+    pub fn ID(&self) -> u64 {
+        return self.lock().id;
+    }
+
+    // Parent returns the parent of the namespace, or None for the root PID
+    // namespace.
+    pub fn Parent(&self) -> Option<PIDNamespace> {
+        return self.lock().parent.clone();
+    }
+
     pub fn IDOfSession(&self, s: &Session) -> SessionID {
         let owner = self.lock().owner.clone();
         let _r = owner.ReadLock();
@@ -263,11 +278,14 @@ impl PIDNamespace {
             return Err(Error::SysError(SysErr::ENOMEM));
         }
 
+        // Thread IDs are below kernel.pid_max, which may have been lowered
+        // under me.last.
+        let pidMax = GetKernel().PidMax() as ThreadID;
         let mut tid = me.last;
 
-        loop {
+        for _ in 0..pidMax {
             tid += 1;
-            if tid > TASKS_LIMIT {
+            if tid >= pidMax {
                 tid = INIT_TID;
             }
 
@@ -275,10 +293,47 @@ impl PIDNamespace {
                 me.last = tid;
                 return Ok(tid);
             }
+        }
 
-            if tid == me.last {
-                return Err(Error::SysError(SysErr::EAGAIN));
-            }
+        return Err(Error::SysError(SysErr::EAGAIN));
+    }
+
+    // AllocateSpecificTID reserves the ThreadID tid in ns, as requested via
+    // clone3(2)'s set_tid. Equivalent to the set_tid handling in
+    // kernel/pid.c:alloc_pid.
+    pub fn AllocateSpecificTID(&self, tid: ThreadID) -> Result<ThreadID> {
+        let me = self.lock();
+
+        if me.exiting {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        if tid < INIT_TID || tid >= GetKernel().PidMax() as ThreadID {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // The first task in a PID namespace must be its init process.
+        if tid != INIT_TID && !me.tasks.contains_key(&INIT_TID) {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        if me.tasks.contains_key(&tid) {
+            return Err(Error::SysError(SysErr::EEXIST));
+        }
+
+        return Ok(tid);
+    }
+
+    // Level returns the nesting depth of ns; the root PID namespace is at
+    // level 0.
+    pub fn Level(&self) -> usize {
+        let mut level = 0;
+        let mut ns = self.Parent();
+        while let Some(p) = ns {
+            level += 1;
+            ns = p.Parent();
         }
+
+        return level;
     }
 }
@@ -212,7 +212,9 @@ impl IOUsage for ThreadGroup {
         let owner = self.TaskSet();
         let _r = owner.ReadLock();
 
-        let io = self.lock().ioUsage.clone();
+        // ioUsage is shared with the thread group, accumulate into a copy.
+        let io = IO::default();
+        io.Accumulate(&self.lock().ioUsage);
         for t in &self.lock().tasks {
             io.Accumulate(&t.IOUsage())
         }
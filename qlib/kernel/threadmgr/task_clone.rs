@@ -15,14 +15,24 @@
 use alloc::boxed::Box;
 use alloc::string::ToString;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ptr;
+use core::sync::atomic::AtomicBool;
 
 use super::super::super::super::kernel_def::*;
+use super::super::super::auth::userns::*;
 use super::super::super::common::*;
+use super::super::super::linux::clone::*;
 use super::super::super::linux_def::*;
 use super::super::super::task_mgr::*;
 use super::super::arch::x86_64::context::*;
+use super::super::fs::mount::*;
+use super::super::kernel::fd_table::*;
+use super::super::kernel::fs_context::*;
 use super::super::kernel::ipc_namespace::*;
+use super::super::kernel::nsfs::*;
+use super::super::kernel::perf_event::*;
+use super::super::kernel::pidfd::*;
 use super::super::threadmgr::task_start::*;
 use super::super::threadmgr::thread::*;
 use super::super::SignalDef::*;
@@ -30,6 +40,7 @@ use super::super::*;
 //use super::super::syscalls::sys_tls::*;
 use super::super::task::*;
 use super::task_block::*;
+use super::task_ptrace::*;
 use super::task_stop::*;
 
 pub fn IsValidSegmentBase(addr: u64) -> bool {
@@ -90,6 +101,10 @@ pub struct SharingOptions {
     // If NewIPCNamespace is true, the task should have an independent IPC
     // namespace.
     pub NewIPCNamespace: bool,
+
+    // If NewMountNamespace is true, the task should have an independent mount
+    // namespace, initially holding a copy of the mounts of its current one.
+    pub NewMountNamespace: bool,
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -143,6 +158,21 @@ pub struct CloneOptions {
     // for it. If both Untraced and InheritTracer are true, no event will be
     // reported, but tracer inheritance will still occur.
     pub InheritTracer: bool,
+
+    // If PIDFD is true, a pidfd referring to the new thread group is installed
+    // in the caller's file descriptor table, and its number is written to
+    // address PIDFDAddr in the caller's memory.
+    pub PIDFD: bool,
+    pub PIDFDAddr: u64,
+
+    // If ClearSignalHandlers is true, all signal handlers of the new task
+    // that are not SIG_IGN are reset to SIG_DFL, as on execve(2).
+    pub ClearSignalHandlers: bool,
+
+    // SetTID[..SetTIDSize] holds the TIDs requested for the new task via
+    // clone3(2)'s set_tid, starting with the new task's own PID namespace.
+    pub SetTID: [ThreadID; MAX_PID_NS_LEVEL],
+    pub SetTIDSize: usize,
 }
 
 impl CloneOptions {
@@ -171,6 +201,7 @@ impl CloneOptions {
                 NewFSContext: flags & CloneOp::CLONE_FS == 0,
                 NewUTSNamespace: flags & CloneOp::CLONE_NEWUTS != 0,
                 NewIPCNamespace: flags & CloneOp::CLONE_NEWIPC != 0,
+                NewMountNamespace: flags & CloneOp::CLONE_NEWNS != 0,
             },
 
             Stack: cStack,
@@ -184,15 +215,10 @@ impl CloneOptions {
             Vfork: flags & CloneOp::CLONE_VFORK != 0,
             Untraced: flags & CloneOp::CLONE_UNTRACED != 0,
             InheritTracer: flags & CloneOp::CLONE_PTRACE != 0,
+            PIDFD: flags & CloneOp::CLONE_PIDFD != 0,
+            ..Default::default()
         };
 
-        if opts.sharingOption.NewUserNamespace {
-            // todo: handle NewUserNamespace
-            //panic!("doesn't support new usernamespace ...");
-            error!("doesn't support new usernamespace ...");
-            return Err(Error::SysError(SysErr::EINVAL));
-        }
-
         // Since signal actions may refer to application signal handlers by virtual
         // address, any set of signal handlers must refer to the same address
         // space.
@@ -223,36 +249,83 @@ impl CloneOptions {
             return Err(Error::SysError(SysErr::EINVAL));
         }
 
+        // "EINVAL Both CLONE_FS and CLONE_NEWNS were specified in flags." -
+        // clone(2)
+        if opts.sharingOption.NewMountNamespace && !opts.sharingOption.NewFSContext {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        // "EINVAL CLONE_PIDFD was specified together with CLONE_DETACHED." and
+        // "EINVAL CLONE_PIDFD was specified together with CLONE_THREAD." -
+        // clone(2)
+        if opts.PIDFD
+            && (!opts.sharingOption.NewThreadGroup || flags & CloneOp::CLONE_DETACHED != 0)
+        {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
         return Ok(opts);
     }
 }
 
+// forkMountNamespace creates a copy of mountns owned by userns, and moves the
+// root and working directories of fsc into it.
+fn forkMountNamespace(
+    task: &Task,
+    mountns: &MountNs,
+    fsc: &FSContext,
+    userns: &UserNameSpace,
+) -> Result<MountNs> {
+    let newns = mountns.Fork(task, userns)?;
+    let root = newns.Translate(task, mountns, &fsc.RootDirectory())?;
+    let cwd = newns.Translate(task, mountns, &fsc.WorkDirectory())?;
+    fsc.SetRootDirectory(&root);
+    fsc.SetWorkDirectory(&cwd);
+    return Ok(newns);
+}
+
 impl Thread {
     pub fn Clone(&self, opts: &CloneOptions, stackAddr: u64) -> Result<Self> {
-        let pidns = self.PIDNamespace();
-        let ts = pidns.Owner();
-        let _wl = ts.WriteLock();
-
-        let t = self.lock();
-        let creds = t.creds.clone();
+        let creds = self.Credentials();
         let mut userns = creds.lock().UserNamespace.clone();
 
         if opts.sharingOption.NewUserNamespace {
-            if t.IsChrooted() {
+            if self.lock().IsChrooted() {
                 return Err(Error::SysError(SysErr::EPERM));
             }
 
             userns = creds.NewChildUserNamespace()?;
         }
 
-        if opts.sharingOption.NewPIDNamespace
+        if (opts.sharingOption.NewPIDNamespace
             || opts.sharingOption.NewNetworkNamespace
             || opts.sharingOption.NewUTSNamespace
-                && !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
+            || opts.sharingOption.NewIPCNamespace
+            || opts.sharingOption.NewMountNamespace)
+            && !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &userns)
         {
             return Err(Error::SysError(SysErr::EPERM));
         }
 
+        let mut fsc = self.lock().fsc.clone();
+        if opts.sharingOption.NewFSContext {
+            let temp = fsc.Fork();
+            fsc = temp;
+        }
+
+        // Copying the mount namespace walks the file system, so it must be done
+        // before taking the TaskSet lock.
+        let mut mountns = self.lock().mountNS.clone();
+        if opts.sharingOption.NewMountNamespace {
+            mountns = forkMountNamespace(Task::Current(), &mountns, &fsc, &userns)?;
+        }
+
+        let pidns = self.PIDNamespace();
+        let ts = pidns.Owner();
+        let _wl = ts.WriteLock();
+
+        let t = self.lock();
+
         let mut utsns = t.utsns.clone();
         if opts.sharingOption.NewUTSNamespace {
             let tmp = utsns.Fork(&userns);
@@ -272,26 +345,17 @@ impl Thread {
 
         let vforkParent = if opts.Vfork { Some(self.clone()) } else { None };
 
-        let mut fsc = t.fsc.clone();
-        if opts.sharingOption.NewFSContext {
-            let temp = fsc.Fork();
-            fsc = temp;
-        }
-
         let mut fdTbl = t.fdTbl.clone();
         if opts.sharingOption.NewFiles {
             let newFDTbl = fdTbl.Fork(i32::MAX);
             fdTbl = newFDTbl;
         }
 
-        let pidns = t.tg.PIDNamespace();
-
+        let mut pidns = t.tg.PIDNamespace();
         if t.childPIDNamespace.is_some() {
-            panic!("doesn't support childPIDNamespace********************");
-            //pidns = t.childPIDNamespace.clone().unwrap();
+            pidns = t.childPIDNamespace.clone().unwrap();
         } else if opts.sharingOption.NewPIDNamespace {
-            panic!("doesn't support NewPIDNamespace********************");
-            //pidns = pidns.NewChild(&userns);
+            pidns = pidns.NewChild(&userns);
         }
 
         let mut tg = t.tg.clone();
@@ -301,9 +365,16 @@ impl Thread {
                 sh = sh.Fork();
             }
 
+            if opts.ClearSignalHandlers {
+                sh = sh.CopyForExec();
+            }
+
             let kernel = t.k.clone();
             let limit = tg.lock().limits.clone();
             let cid = tg.lock().containerID.clone();
+            let oomScoreAdj = tg.lock().oomScoreAdj;
+            let oomScoreAdjMin = tg.lock().oomScoreAdjMin;
+            let loginuid = tg.lock().loginuid;
             tg = kernel.newThreadGroup(
                 &pidns,
                 &sh,
@@ -312,6 +383,37 @@ impl Thread {
                 &cid,
                 &None,
             );
+            tg.lock().oomScoreAdj = oomScoreAdj;
+            tg.lock().oomScoreAdjMin = oomScoreAdjMin;
+            tg.lock().loginuid = loginuid;
+        }
+
+        // "EINVAL set_tid_size is greater than the number of nested PID
+        // namespaces." and "EPERM set_tid_size was greater than zero, and the
+        // caller lacks the CAP_SYS_ADMIN capability in one or more of the user
+        // namespaces that own the corresponding PID namespaces." - clone(2)
+        let mut setTIDs = Vec::new();
+        if opts.SetTIDSize > 0 {
+            if opts.SetTIDSize > tg.PIDNamespace().Level() + 1 {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            let mut ns = Some(tg.PIDNamespace());
+            for i in 0..opts.SetTIDSize {
+                let cur = ns.unwrap();
+                if !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &cur.UserNamespace()) {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                setTIDs.push(opts.SetTID[i]);
+                ns = cur.Parent();
+            }
+        }
+
+        // With SCHED_RESET_ON_FORK, a negative nice value is not inherited.
+        let mut niceness = t.niceness;
+        if niceness < 0 && t.schedResetOnFork {
+            niceness = 0;
         }
 
         let mut cfg = TaskConfig {
@@ -325,13 +427,15 @@ impl Thread {
             FSContext: fsc,
             Fdtbl: fdTbl,
             Credentials: creds.clone(),
-            Niceness: t.niceness,
+            Niceness: niceness,
             NetworkNamespaced: false,
             AllowedCPUMask: t.allowedCPUMask.Copy(),
             UTSNamespace: utsns,
             IPCNamespace: ipcns,
+            MountNamespace: mountns,
             Blocker: Blocker::New(stackAddr),
             ContainerID: t.containerID.to_string(),
+            SetTIDs: setTIDs,
         };
 
         if opts.sharingOption.NewThreadGroup {
@@ -354,6 +458,33 @@ impl Thread {
 
         nt.lock().name = name;
 
+        let personality = self.lock().personality;
+        nt.lock().personality = personality;
+
+        // With SCHED_RESET_ON_FORK, a real-time policy is not inherited, as
+        // SchedEntity::Fork does for the scheduling entity of the child.
+        let (mut policy, mut rtPriority, resetOnFork) = self.SchedPolicy();
+        if resetOnFork && SchedEntity::IsRealtime(policy) {
+            policy = Sched::SCHED_NORMAL;
+            rtPriority = 0;
+        }
+
+        {
+            let mut ntl = nt.lock();
+            ntl.schedPolicy = policy;
+            ntl.rtPriority = rtPriority;
+            ntl.schedResetOnFork = false;
+        }
+
+        // "If any filters are attached to a thread, they are inherited by its
+        // children across fork(2), clone(2) and execve(2)." - seccomp(2)
+        let filters = self.lock().syscallFilters.clone();
+        nt.lock().syscallFilters = filters;
+
+        let events = self.lock().perfEvents.clone();
+        let sched = nt.lock().sched.clone();
+        nt.lock().perfEvents = InheritPerfEvents(&events, &sched);
+
         if userns != creds.lock().UserNamespace.clone() {
             nt.SetUserNamespace(&userns)
                 .expect("Task.Clone: SetUserNamespace failed: ")
@@ -361,7 +492,6 @@ impl Thread {
 
         if opts.Vfork {
             nt.lock().vforkParent = vforkParent;
-            self.MaybeBeginVforkStop(&nt);
         }
 
         return Ok(nt);
@@ -418,14 +548,32 @@ impl Task {
     }
 
     pub fn Clone(&self, flags: u64, cStack: u64, pTid: u64, cTid: u64, tls: u64) -> Result<i32> {
-        let opts = CloneOptions::New(flags, cStack, pTid, cTid, tls, false)?;
+        let mut opts = CloneOptions::New(flags, cStack, pTid, cTid, tls, false)?;
+
+        // "CLONE_PIDFD ... If this flag is specified, then clone() stores the
+        // PID file descriptor at the location pointed to by parent_tid. ...
+        // it is not possible to specify both CLONE_PIDFD and
+        // CLONE_PARENT_SETTID in clone()." - clone(2)
+        if opts.PIDFD {
+            if opts.ParentSetTID {
+                return Err(Error::SysError(SysErr::EINVAL));
+            }
+
+            opts.PIDFDAddr = pTid;
+        }
+
+        return self.CloneWithOptions(&opts);
+    }
 
+    // CloneWithOptions creates a new task as described by opts, and returns
+    // its thread ID in the caller's PID namespace.
+    pub fn CloneWithOptions(&self, opts: &CloneOptions) -> Result<i32> {
         if opts.SetTLS && !IsValidSegmentBase(opts.TLS) {
             return Err(Error::SysError(SysErr::EPERM));
         }
 
-        let mut userSp = cStack;
-        if opts.sharingOption.NewAddressSpace || cStack == 0 {
+        let mut userSp = opts.Stack;
+        if opts.sharingOption.NewAddressSpace || opts.Stack == 0 {
             userSp = Self::Current().GetPtRegs().rsp;
         }
 
@@ -433,28 +581,52 @@ impl Task {
 
         let (pid, childTask) = self.CloneVM(&opts, userSp)?; //, cStack as * const u8);
         if opts.ParentSetTID {
-            self.CopyOutObj(&pid, pTid)?;
+            self.CopyOutObj(&pid, opts.ParentTID)?;
         }
 
         let cTask = unsafe { &mut (*childTask) };
 
+        if opts.PIDFD {
+            let file = NewPidfd(self, &cTask.Thread().ThreadGroup(), false);
+            let fd = self.NewFDFrom(0, &file, &FDFlags { CloseOnExec: true })?;
+            self.CopyOutObj(&fd, opts.PIDFDAddr)?;
+        }
+
         if opts.ChildClearTID == true {
-            cTask.SetClearTID(cTid);
+            cTask.SetClearTID(opts.ChildTID);
         }
 
         if opts.ChildSetTID == true {
             // can't use the GetTypeMut as it is used with current pagetable.
             //*Task::GetTask(cTask.taskId).GetTypeMut(cTid)? = pid;
 
-            cTask.CopyOutObjManual(&pid, cTid)?;
+            cTask.CopyOutObjManual(&pid, opts.ChildTID)?;
         }
 
         if opts.SetTLS {
-            cTask.context.fs = tls;
+            cTask.context.fs = opts.TLS;
         }
 
+        let thread = self.Thread();
+        let nt = cTask.Thread();
+        let ptraceEvent = thread.PtraceClone(PtraceCloneKind::New(&opts), &nt, &opts);
+
         taskMgr::NewTask(TaskId::New(cTask.taskId));
 
+        // Report the new task to the tracer before the vfork stop, if any, so
+        // that the tracer can see the child while the parent is blocked.
+        if ptraceEvent {
+            self.DoStop();
+        }
+
+        if opts.Vfork {
+            thread.MaybeBeginVforkStop(&nt);
+            self.DoStop();
+            if thread.PtraceVforkDone(pid) {
+                self.DoStop();
+            }
+        }
+
         return Ok(pid);
     }
 
@@ -500,6 +672,8 @@ impl Task {
             };
 
             let ioUsage = nt.lock().ioUsage.clone();
+            let traced = nt.HasTracer();
+            let hasSyscallFilters = nt.HasSyscallFilters();
 
             ptr::write_volatile(
                 taskPtr,
@@ -510,7 +684,7 @@ impl Task {
                     tidInfo: Default::default(),
                     isWaitThread: false,
                     signalStack: signalStack,
-                    mountNS: task.mountNS.clone(),
+                    mountNS: nt.lock().mountNS.clone(),
                     // Arc::new(QMutex::new(Default::default())),
                     creds: creds,
                     utsns: utsns,
@@ -527,6 +701,8 @@ impl Task {
                     sched: sched,
                     exiting: false,
                     perfcounters: None, //Some(THREAD_COUNTS.lock().NewCounters()),
+                    traced: AtomicBool::new(traced),
+                    hasSyscallFilters: AtomicBool::new(hasSyscallFilters),
                     guard: Guard::default(),
                 },
             );
@@ -584,7 +760,7 @@ impl Task {
             let creds = t.Credentials();
             let newUserNs = creds.NewChildUserNamespace()?;
             t.SetUserNamespace(&newUserNs)?;
-            self.creds = creds;
+            self.creds = t.Credentials();
         }
 
         let creds = self.creds.clone();
@@ -641,6 +817,123 @@ impl Task {
             tlock.fsc = self.fsContext.clone();
         }
 
+        core::mem::drop(tlock);
+        if opts.NewMountNamespace {
+            if !haveCapSysAdmin {
+                return Err(Error::SysError(SysErr::EPERM));
+            }
+
+            let userns = creds.lock().UserNamespace.clone();
+            let mountns = self.mountNS.clone();
+            let fsc = self.fsContext.clone();
+            self.mountNS = forkMountNamespace(self, &mountns, &fsc, &userns)?;
+            t.lock().mountNS = self.mountNS.clone();
+        }
+
+        return Ok(());
+    }
+
+    // Setns implements setns(2): it moves the task into the namespace ns.
+    // nstype is the CLONE_NEW* flag passed by the caller, or 0 to allow any
+    // type of namespace.
+    pub fn Setns(&mut self, ns: &Namespace, nstype: i32) -> Result<()> {
+        // "EINVAL ... The caller tried to join a namespace whose type doesn't
+        // match nstype." - setns(2)
+        if nstype != 0 && nstype != ns.Type() {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        let t = self.Thread();
+        let creds = t.Credentials();
+
+        // "Reassociating the calling thread with a namespace requires
+        // CAP_SYS_ADMIN in the user namespace that owns the target namespace
+        // ..." - setns(2). The user namespace case is checked by
+        // SetUserNamespace below.
+        match ns {
+            Namespace::User(_) => (),
+            _ => {
+                if !creds.HasCapabilityIn(Capability::CAP_SYS_ADMIN, &ns.UserNamespace())
+                    || !creds.HasCapability(Capability::CAP_SYS_ADMIN)
+                {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+            }
+        }
+
+        match ns {
+            Namespace::UTS(utsns) => {
+                self.utsns = utsns.clone();
+                t.lock().utsns = utsns.clone();
+            }
+            Namespace::IPC(ipcns) => {
+                self.ipcns = ipcns.clone();
+                t.lock().ipcns = ipcns.clone();
+            }
+            Namespace::Mount(mountns) => {
+                // "Changing the mount namespace requires that the caller
+                // possess both CAP_SYS_CHROOT and CAP_SYS_ADMIN capabilities
+                // in its own user namespace ..." - setns(2)
+                if !creds.HasCapability(Capability::CAP_SYS_CHROOT) {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                // Linux refuses to change the mount namespace of a task that
+                // shares its filesystem information with other tasks. We
+                // don't track that sharing, so give the task a private copy
+                // instead.
+                let fsc = self.fsContext.Fork();
+                let root = mountns.Root();
+                fsc.SetRootDirectory(&root);
+                fsc.SetWorkDirectory(&root);
+
+                self.fsContext = fsc.clone();
+                self.mountNS = mountns.clone();
+                let mut tlock = t.lock();
+                tlock.fsc = fsc;
+                tlock.mountNS = mountns.clone();
+            }
+            Namespace::PID(pidns) => {
+                // "EINVAL ... The caller attempted to join a PID namespace
+                // that is not a descendant of its own PID namespace (or the
+                // namespace itself)." - setns(2)
+                let current = t.PIDNamespace();
+                let mut p = Some(pidns.clone());
+                loop {
+                    match p {
+                        None => return Err(Error::SysError(SysErr::EINVAL)),
+                        Some(ref n) if *n == current => break,
+                        Some(n) => p = n.Parent(),
+                    }
+                }
+
+                // The calling task's PID namespace is unchanged; only its
+                // subsequently created children are placed in pidns.
+                t.lock().childPIDNamespace = Some(pidns.clone());
+            }
+            Namespace::User(userns) => {
+                // "EINVAL ... The caller is multithreaded and tried to join a
+                // new user namespace." - setns(2)
+                let tg = t.ThreadGroup();
+                if tg.lock().tasksCount != 1 {
+                    return Err(Error::SysError(SysErr::EINVAL));
+                }
+
+                // A task can't regain capabilities by reentering its own user
+                // namespace.
+                if *userns == creds.lock().UserNamespace {
+                    return Err(Error::SysError(SysErr::EINVAL));
+                }
+
+                if self.IsChrooted() {
+                    return Err(Error::SysError(SysErr::EPERM));
+                }
+
+                t.SetUserNamespace(userns)?;
+                self.creds = t.Credentials();
+            }
+        }
+
         return Ok(());
     }
 }
@@ -661,6 +954,8 @@ pub fn CreateCloneTask(fromTask: &Task, toTask: &mut Task, userSp: u64) {
 
         toTask.context.SetReady(1);
         toTask.context.fs = fromTask.context.fs;
+        toTask.context.SetCPUMask(fromTask.context.CPUMask());
+        toTask.context.sched.Fork(&fromTask.context.sched);
         toTask.context.rsp = toTask.GetPtRegs() as *const _ as u64 - 8;
         toTask.context.rdi = userSp;
         toTask.context.savefpsate = true;
@@ -0,0 +1,825 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem;
+use core::slice;
+
+use crate::qlib::mutex::*;
+
+use super::super::super::addr::*;
+use super::super::super::common::*;
+use super::super::super::limits::*;
+use super::super::super::linux::elf::*;
+use super::super::super::linux::time::*;
+use super::super::super::linux_def::*;
+use super::super::super::path::*;
+use super::super::super::range::*;
+use super::super::super::task_mgr::*;
+use super::super::fs::dirent::*;
+use super::super::fs::file::*;
+use super::super::fs::flags::*;
+use super::super::kernel::kernel::*;
+use super::super::kernel::waiter::waitgroup::*;
+use super::super::memmgr::metadata::*;
+use super::super::memmgr::vma::*;
+use super::super::task::*;
+use super::super::taskMgr;
+use super::super::SignalDef::*;
+use super::super::Tsc;
+use super::task_exit::*;
+use super::thread::*;
+use super::thread_group::*;
+
+// DEFAULT_CORE_PATTERN is the initial value of /proc/sys/kernel/core_pattern.
+pub const DEFAULT_CORE_PATTERN: &str = "core";
+
+// CORENAME_MAX_SIZE is the maximum length of core_pattern.
+pub const CORENAME_MAX_SIZE: usize = 128;
+
+// CORE_WRITE_BUF_SIZE is the amount of core file data buffered between
+// writes.
+const CORE_WRITE_BUF_SIZE: usize = 64 * 1024;
+
+// CoreState tracks a core dump in progress. It is analogous to Linux's struct
+// core_state.
+#[derive(Clone)]
+pub struct CoreState {
+    // dumper is the task writing the core dump.
+    pub dumper: Thread,
+
+    // startup counts the other tasks of the thread group which have yet to
+    // park in the exit path.
+    pub startup: WaitGroup,
+
+    // threads are the tasks which have parked, in the order they did.
+    pub threads: Arc<QMutex<Vec<Thread>>>,
+
+    // done is released once the core dump has been written.
+    pub done: WaitGroup,
+}
+
+impl Thread {
+    // PrepareGroupCoreDump is PrepareGroupExit for a signal whose default
+    // action is to dump core. If t initiated the group exit and the process is
+    // dumpable, it returns the CoreState that t must finish with
+    // FinishCoreDump once the dump is written. This is analogous to Linux's
+    // kernel/signal.c:do_coredump() => coredump_wait().
+    pub fn PrepareGroupCoreDump(&self, es: ExitStatus) -> Option<CoreState> {
+        let tg = self.lock().tg.clone();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        if !self.prepareGroupExitLocked(&tg, es) {
+            return None;
+        }
+
+        let mm = self.lock().memoryMgr.clone();
+        if mm.Dumpability() == NOT_DUMPABLE {
+            return None;
+        }
+
+        // Linux refuses to write core files smaller than a page
+        // (binfmt->min_coredump), which covers RLIMIT_CORE = 0.
+        if tg.Limits().Get(LimitType::Core).Cur < MemoryDef::PAGE_SIZE {
+            return None;
+        }
+
+        // Every sibling which hasn't begun exiting yet will park in
+        // exitThreadGroup once it handles the SIGKILL sent above.
+        let mut others = 0;
+        let tasks: Vec<Thread> = tg.lock().tasks.iter().cloned().collect();
+        for t in &tasks {
+            if *t != *self && t.lock().exitState == TaskExitState::TaskExitNone {
+                others += 1;
+            }
+        }
+
+        let core = CoreState {
+            dumper: self.clone(),
+            startup: WaitGroup::New(others),
+            threads: Arc::new(QMutex::new(Vec::new())),
+            done: WaitGroup::New(1),
+        };
+
+        tg.lock().coreState = Some(core.clone());
+        return Some(core);
+    }
+
+    // FinishCoreDump releases the tasks parked for core, and records in the
+    // exit status whether the core file was written.
+    pub fn FinishCoreDump(&self, core: &CoreState, dumped: bool) {
+        let tg = self.lock().tg.clone();
+        let lock = tg.lock().signalLock.clone();
+        {
+            let _s = lock.lock();
+            tg.lock().coreState = None;
+
+            if dumped {
+                tg.lock().exitStatus.CoreDumped = true;
+                let tasks: Vec<Thread> = tg.lock().tasks.iter().cloned().collect();
+                for t in &tasks {
+                    t.lock().exitStatus.CoreDumped = true;
+                }
+            }
+        }
+
+        core.done.Done();
+    }
+
+    // parkForCoreDumpLocked is called by exitThreadGroup once t has begun
+    // exiting. If a sibling is dumping core, it tells the dumper that t's
+    // registers are about to stop changing.
+    //
+    // Preconditions: The signal mutex must be locked.
+    pub fn parkForCoreDumpLocked(&self, tg: &ThreadGroup) {
+        let core = match tg.lock().coreState.clone() {
+            None => return,
+            Some(core) => core,
+        };
+
+        if core.dumper == *self {
+            return;
+        }
+
+        core.threads.lock().push(self.clone());
+        core.startup.Done();
+    }
+
+    // WaitCoreDump blocks until a core dump in progress in t's thread group is
+    // complete. It is analogous to Linux's kernel/exit.c:coredump_task_exit().
+    pub fn WaitCoreDump(&self, task: &Task) {
+        let tg = self.lock().tg.clone();
+        let lock = tg.lock().signalLock.clone();
+        let core = {
+            let _s = lock.lock();
+            match tg.lock().coreState.clone() {
+                None => return,
+                Some(core) => core,
+            }
+        };
+
+        if core.dumper == *self {
+            return;
+        }
+
+        // Parked tasks are already killed, so the wait is uninterruptible.
+        task.blocker.WaitGroupWait(task, &core.done);
+    }
+}
+
+// coreVma is the part of a vma recorded in a core file.
+struct CoreVma {
+    range: Range,
+    perms: AccessType,
+
+    // dumpSize is the number of bytes at the start of the vma written to the
+    // core file.
+    dumpSize: u64,
+
+    // file is the file offset and name of a file backed vma.
+    file: Option<(u64, String)>,
+}
+
+// coreNote is an ELF note of a core file.
+struct CoreNote {
+    name: &'static str,
+    typ: u32,
+    desc: Vec<u8>,
+}
+
+impl CoreNote {
+    fn New(name: &'static str, typ: u32, desc: &[u8]) -> Self {
+        return Self {
+            name: name,
+            typ: typ,
+            desc: desc.to_vec(),
+        };
+    }
+
+    fn Size(&self) -> usize {
+        return mem::size_of::<Elf64Nhdr>() + align4(self.name.len() + 1) + align4(self.desc.len());
+    }
+
+    fn Serialize(&self, buf: &mut Vec<u8>) {
+        let hdr = Elf64Nhdr {
+            Namesz: self.name.len() as u32 + 1,
+            Descsz: self.desc.len() as u32,
+            Type: self.typ,
+        };
+
+        buf.extend_from_slice(bytesOf(&hdr));
+        let start = buf.len();
+        buf.extend_from_slice(self.name.as_bytes());
+        buf.resize(start + align4(self.name.len() + 1), 0);
+        let start = buf.len();
+        buf.extend_from_slice(&self.desc);
+        buf.resize(start + align4(self.desc.len()), 0);
+    }
+}
+
+fn align4(n: usize) -> usize {
+    return (n + 3) & !3;
+}
+
+fn bytesOf<T: Sized>(t: &T) -> &[u8] {
+    return unsafe { slice::from_raw_parts(t as *const T as *const u8, mem::size_of::<T>()) };
+}
+
+fn timeval(tscTicks: i64) -> ElfTimeval {
+    let ns = Tsc::Scale(tscTicks) * 1000;
+    return ElfTimeval {
+        Sec: ns / SECOND,
+        Usec: (ns % SECOND) / MICROSECOND,
+    };
+}
+
+// escapeCoreName replaces the '/' in a core_pattern expansion, so that it
+// can't add path components. This is analogous to Linux's cn_esc_printf().
+fn escapeCoreName(s: &str) -> String {
+    return s.replace('/', "!");
+}
+
+// coreWriter writes a core file, enforcing RLIMIT_CORE.
+struct CoreWriter<'a> {
+    task: &'a Task,
+    file: File,
+    buf: Vec<u8>,
+    written: u64,
+    limit: u64,
+}
+
+impl<'a> CoreWriter<'a> {
+    fn Write(&mut self, data: &[u8]) -> Result<()> {
+        // Like Linux's dump_emit(), give up once the limit is exceeded
+        // rather than writing a truncated file that claims to be complete.
+        if self.written + data.len() as u64 > self.limit {
+            return Err(Error::SysError(SysErr::EFBIG));
+        }
+
+        self.buf.extend_from_slice(data);
+        self.written += data.len() as u64;
+        if self.buf.len() >= CORE_WRITE_BUF_SIZE {
+            self.Flush()?;
+        }
+
+        return Ok(());
+    }
+
+    fn Pad(&mut self, offset: u64) -> Result<()> {
+        if offset > self.written {
+            let zeros = vec![0; (offset - self.written) as usize];
+            self.Write(&zeros)?;
+        }
+
+        return Ok(());
+    }
+
+    fn Flush(&mut self) -> Result<()> {
+        let mut start = 0;
+        while start < self.buf.len() {
+            let iovs = [IoVec::New(&self.buf[start..])];
+            let n = self.file.Writev(self.task, &iovs)?;
+            if n <= 0 {
+                return Err(Error::SysError(SysErr::EIO));
+            }
+            start += n as usize;
+        }
+
+        self.buf.clear();
+        return Ok(());
+    }
+}
+
+impl Task {
+    // DoCoreDump handles a signal whose default action is to terminate the
+    // thread group with a core dump.
+    pub fn DoCoreDump(&mut self, info: &SignalInfo, es: ExitStatus) {
+        let t = self.Thread();
+        let core = match t.PrepareGroupCoreDump(es) {
+            None => return,
+            Some(core) => core,
+        };
+
+        let dumped = match self.CoreDump(info, &core) {
+            Ok(()) => true,
+            Err(e) => {
+                info!("Signal {}: core dump failed: {:?}", info.Signo, e);
+                false
+            }
+        };
+
+        t.FinishCoreDump(&core, dumped);
+    }
+
+    // CoreDump writes an ELF core file of the current process, as Linux's
+    // fs/binfmt_elf.c:elf_core_dump() does.
+    pub fn CoreDump(&mut self, info: &SignalInfo, core: &CoreState) -> Result<()> {
+        if !self.context.savefpsate {
+            self.SaveFp();
+        }
+
+        let task: &Task = self;
+        task.blocker.WaitGroupWait(task, &core.startup);
+
+        // The parked tasks may not be switched out yet. Wait until they are so
+        // that their registers are stable.
+        let mut others = Vec::new();
+        for t in core.threads.lock().iter() {
+            others.push((t.clone(), TaskId::New(t.lock().taskId).GetTask()));
+        }
+
+        for (_, other) in &others {
+            while other.context.Ready() == 0 {
+                taskMgr::Yield();
+            }
+        }
+
+        let thread = task.Thread();
+        let tg = thread.ThreadGroup();
+        let mm = task.mm.clone();
+        let limit = tg.Limits().Get(LimitType::Core).Cur;
+
+        let pattern = GetKernel().CorePattern();
+        if pattern.starts_with('|') {
+            info!("core dump to a pipe ({}) is not supported", pattern);
+            return Err(Error::SysError(SysErr::EOPNOTSUPP));
+        }
+
+        let name = task.coreName(&pattern, info.Signo, limit);
+        if mm.Dumpability() == ROOT_DUMPABLE && !name.starts_with('/') {
+            info!(
+                "Unsafe core_pattern {} used with suid_dumpable=2, pipe or absolute path required",
+                pattern
+            );
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let vmas = task.coreVmas();
+
+        // Notes, in the order Linux writes them: the dumping thread comes
+        // first and carries the process wide notes.
+        let psinfo = task.prpsinfo(&thread);
+        let mut notes = vec![
+            task.prstatusNote(&thread, task, info.Signo),
+            CoreNote::New(NOTE_NAME_CORE, NT_PRPSINFO, bytesOf(&psinfo)),
+            CoreNote::New(NOTE_NAME_CORE, NT_SIGINFO, bytesOf(info)),
+            CoreNote::New(NOTE_NAME_CORE, NT_AUXV, &task.coreAuxv()),
+            CoreNote::New(NOTE_NAME_CORE, NT_FILE, &coreFileNote(&vmas)),
+        ];
+        fpNotes(task, &mut notes);
+        for (t, other) in &others {
+            notes.push(task.prstatusNote(t, other, info.Signo));
+            fpNotes(other, &mut notes);
+        }
+
+        let file = task.openCoreFile(&name)?;
+        let mut w = CoreWriter {
+            task: task,
+            file: file,
+            buf: Vec::with_capacity(CORE_WRITE_BUF_SIZE),
+            written: 0,
+            limit: limit,
+        };
+
+        let ehdrSize = mem::size_of::<Elf64Ehdr>();
+        let phdrSize = mem::size_of::<Elf64Phdr>();
+        let phnum = vmas.len() + 1;
+        let notesOffset = ehdrSize + phnum * phdrSize;
+        let notesSize: usize = notes.iter().map(|n| n.Size()).sum();
+        let dataOffset = Addr((notesOffset + notesSize) as u64).RoundUp()?.0;
+
+        let mut ident = [0; EI_NIDENT];
+        ident[..4].copy_from_slice(&ELFMAG);
+        ident[EI_CLASS] = ELFCLASS64;
+        ident[EI_DATA] = ELFDATA2LSB;
+        ident[EI_VERSION] = EV_CURRENT;
+        ident[EI_OSABI] = ELFOSABI_NONE;
+        let ehdr = Elf64Ehdr {
+            Ident: ident,
+            Type: ET_CORE,
+            Machine: EM_X86_64,
+            Version: EV_CURRENT as u32,
+            Phoff: ehdrSize as u64,
+            Ehsize: ehdrSize as u16,
+            Phentsize: phdrSize as u16,
+            Phnum: phnum as u16,
+            ..Default::default()
+        };
+        w.Write(bytesOf(&ehdr))?;
+
+        let note = Elf64Phdr {
+            Type: PT_NOTE,
+            Offset: notesOffset as u64,
+            Filesz: notesSize as u64,
+            Align: 4,
+            ..Default::default()
+        };
+        w.Write(bytesOf(&note))?;
+
+        let mut offset = dataOffset;
+        for vma in &vmas {
+            let mut flags = 0;
+            if vma.perms.Read() {
+                flags |= PF_R;
+            }
+            if vma.perms.Write() {
+                flags |= PF_W;
+            }
+            if vma.perms.Exec() {
+                flags |= PF_X;
+            }
+
+            let phdr = Elf64Phdr {
+                Type: PT_LOAD,
+                Flags: flags,
+                Offset: offset,
+                Vaddr: vma.range.Start(),
+                Paddr: 0,
+                Filesz: vma.dumpSize,
+                Memsz: vma.range.Len(),
+                Align: MemoryDef::PAGE_SIZE,
+            };
+            w.Write(bytesOf(&phdr))?;
+            offset += vma.dumpSize;
+        }
+
+        let mut buf = Vec::with_capacity(notesSize);
+        for n in &notes {
+            n.Serialize(&mut buf);
+        }
+        w.Write(&buf)?;
+        w.Pad(dataOffset)?;
+
+        let zeros = vec![0; MemoryDef::PAGE_SIZE as usize];
+        for vma in &vmas {
+            let mut addr = vma.range.Start();
+            let end = addr + vma.dumpSize;
+            while addr < end {
+                // Pages of anonymous memory that were never touched read as
+                // zeros; don't fault them in just to dump them.
+                let populated = vma.file.is_some() || mm.VirtualToPhy(addr).is_ok();
+                let data = if populated {
+                    mm.CopyInVecManaul::<u8>(task, addr, MemoryDef::PAGE_SIZE as usize)
+                } else {
+                    Err(Error::SysError(SysErr::EFAULT))
+                };
+
+                match data {
+                    Ok(data) => w.Write(&data)?,
+                    Err(_) => w.Write(&zeros)?,
+                }
+                addr += MemoryDef::PAGE_SIZE;
+            }
+        }
+
+        return w.Flush();
+    }
+
+    // coreName expands core_pattern, as Linux's fs/coredump.c:format_corename()
+    // does. Unknown specifiers are dropped.
+    fn coreName(&self, pattern: &str, signo: i32, limit: u64) -> String {
+        let t = self.Thread();
+        let tg = t.ThreadGroup();
+        let pidns = tg.PIDNamespace();
+        let rootns = GetKernel().RootPIDNamespace();
+        let creds = self.Creds();
+
+        let mut name = String::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                name.push(c);
+                continue;
+            }
+
+            let s = match chars.next() {
+                None => break,
+                Some('%') => "%".to_string(),
+                Some('p') => format!("{}", pidns.IDOfThreadGroup(&tg)),
+                Some('P') => format!("{}", rootns.IDOfThreadGroup(&tg)),
+                Some('i') => format!("{}", pidns.IDOfTask(&t)),
+                Some('I') => format!("{}", rootns.IDOfTask(&t)),
+                Some('u') => format!("{}", creds.lock().RealKUID.0),
+                Some('g') => format!("{}", creds.lock().RealKGID.0),
+                Some('d') => format!("{}", self.mm.Dumpability()),
+                Some('s') => format!("{}", signo),
+                Some('t') => format!("{}", Task::RealTimeNow().Seconds()),
+                Some('h') => escapeCoreName(&t.UTSNamespace().HostName()),
+                Some('e') => escapeCoreName(&t.Name()),
+                Some('E') => match &self.mm.metadata.lock().executable {
+                    None => "".to_string(),
+                    Some(d) => escapeCoreName(&d.MyFullName()),
+                },
+                Some('c') => format!("{}", limit),
+                Some(_) => "".to_string(),
+            };
+            name += &s;
+        }
+
+        return name;
+    }
+
+    // openCoreFile opens the core file at path, relative to the working
+    // directory. An existing file is truncated, but only if it is a regular
+    // file that the process could have created itself.
+    fn openCoreFile(&self, path: &str) -> Result<File> {
+        let root = self.fsContext.RootDirectory();
+        let cwd = self.fsContext.WorkDirectory();
+        let mns = self.mountNS.clone();
+
+        let (dir, name) = SplitLast(path);
+        let mut remainingTraversals = MAX_SYMLINK_TRAVERSALS;
+        let parent = mns.FindDirent(self, &root, Some(cwd), dir, &mut remainingTraversals, true)?;
+        if !parent.Inode().StableAttr().IsDir() {
+            return Err(Error::SysError(SysErr::ENOTDIR));
+        }
+
+        let flags = FileFlags {
+            Write: true,
+            LargeFile: true,
+            NoFollow: true,
+            ..Default::default()
+        };
+
+        match mns.FindDirent(
+            self,
+            &root,
+            Some(parent.clone()),
+            name,
+            &mut remainingTraversals,
+            false,
+        ) {
+            Ok(d) => {
+                let mut inode = d.Inode();
+                if !inode.StableAttr().IsRegular() {
+                    return Err(Error::SysError(SysErr::EACCES));
+                }
+
+                let attr = inode.UnstableAttr(self)?;
+                if attr.Links > 1 || attr.Owner.UID != self.FileOwner().UID {
+                    return Err(Error::SysError(SysErr::EACCES));
+                }
+
+                inode.CheckPermission(
+                    self,
+                    &PermMask {
+                        write: true,
+                        ..Default::default()
+                    },
+                )?;
+
+                inode.Truncate(self, &d, 0)?;
+                return inode.GetFile(self, &d, &flags);
+            }
+            Err(Error::SysError(SysErr::ENOENT)) => {
+                parent.Inode().CheckPermission(
+                    self,
+                    &PermMask {
+                        write: true,
+                        execute: true,
+                        ..Default::default()
+                    },
+                )?;
+
+                let perms = FilePermissions::FromMode(FileMode(0o600 & !self.Umask() as u16));
+                return parent.Create(self, &root, name, &flags, &perms);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    // coreVmas returns the vmas to dump and how much of each, following
+    // Linux's fs/coredump.c:vma_dump_size() with the default coredump_filter:
+    // private and shared anonymous memory, and the ELF headers of mapped
+    // files.
+    fn coreVmas(&self) -> Vec<CoreVma> {
+        let mm = self.mm.clone();
+        let mut vmas = Vec::new();
+        {
+            let _ml = mm.MappingReadLock();
+            let mapping = mm.mapping.lock();
+            let mut seg = mapping.vmas.FirstSeg();
+            while seg.Ok() {
+                let vma = seg.Value();
+                let range = seg.Range();
+                seg = seg.NextSeg();
+                if vma.kernel {
+                    continue;
+                }
+
+                let file = match (&vma.mappable, &vma.id) {
+                    (MMappable::HostIops(_), Some(id)) => Some((vma.offset, id.MappedName(self))),
+                    _ => None,
+                };
+
+                let dumpSize = if vma.dontdump || !vma.realPerms.Read() {
+                    0
+                } else if !vma.private {
+                    // Shared memory is dumped only if it isn't backed by a
+                    // file.
+                    if file.is_none() {
+                        range.Len()
+                    } else {
+                        0
+                    }
+                } else if file.is_none() || vma.realPerms.Write() {
+                    // Anonymous memory, and private file mappings which may
+                    // have been written to.
+                    range.Len()
+                } else {
+                    0
+                };
+
+                vmas.push((
+                    CoreVma {
+                        range: range,
+                        perms: vma.realPerms,
+                        dumpSize: dumpSize,
+                        file: file,
+                    },
+                    vma.offset,
+                ));
+            }
+        }
+
+        let mut ret = Vec::with_capacity(vmas.len());
+        for (mut vma, offset) in vmas {
+            // The first page of a mapped ELF file lets debuggers identify it
+            // by its build ID.
+            if vma.dumpSize == 0 && vma.file.is_some() && offset == 0 && vma.perms.Read() {
+                let magic: Result<Vec<u8>> =
+                    mm.CopyInVecManaul(self, vma.range.Start(), ELFMAG.len());
+                if let Ok(magic) = magic {
+                    if magic[..] == ELFMAG[..] {
+                        vma.dumpSize = MemoryDef::PAGE_SIZE;
+                    }
+                }
+            }
+            ret.push(vma);
+        }
+
+        return ret;
+    }
+
+    // coreAuxv returns the descriptor of NT_AUXV.
+    fn coreAuxv(&self) -> Vec<u8> {
+        let metadata = self.mm.metadata.lock();
+        let mut buf = Vec::new();
+        // auxv[0] is the AT_NULL terminator, see /proc/[pid]/auxv.
+        for i in 1..metadata.auxv.len() {
+            let e = &metadata.auxv[i];
+            buf.extend_from_slice(&(e.Key as u64).to_le_bytes());
+            buf.extend_from_slice(&e.Val.to_le_bytes());
+        }
+
+        buf.extend_from_slice(&[0; 16]);
+        return buf;
+    }
+
+    // prstatusNote returns the NT_PRSTATUS note of t, whose task is tt.
+    fn prstatusNote(&self, t: &Thread, tt: &Task, signo: i32) -> CoreNote {
+        let pidns = self.Thread().PIDNamespace();
+        let tg = t.ThreadGroup();
+
+        let mut status = ElfPrstatus::default();
+        status.Info.Signo = signo;
+        status.Cursig = signo as i16;
+        status.Sigpend = t.lock().pendingSignals.pendingSet.0;
+        status.Sighold = t.SignalMask().0;
+        status.Pid = pidns.IDOfTask(t);
+        status.Ppid = match t.Parent() {
+            None => 0,
+            Some(parent) => pidns.IDOfThreadGroup(&parent.ThreadGroup()),
+        };
+        if let Some(pg) = tg.ProcessGroup() {
+            status.Pgrp = pidns.IDOfProcessGroup(&pg);
+        }
+        if let Some(s) = tg.Session() {
+            status.Sid = pidns.IDOfSession(&s);
+        }
+
+        // As in Linux, the thread group leader reports the times of the whole
+        // thread group.
+        let isLeader = match tg.Leader() {
+            None => false,
+            Some(leader) => leader == *t,
+        };
+        let stats = if isLeader {
+            tg.CPUStats()
+        } else {
+            t.CPUStats()
+        };
+        status.Utime = timeval(stats.UserTime);
+        status.Stime = timeval(stats.SysTime);
+        let stats = tg.JoinedChildCPUStats();
+        status.Cutime = timeval(stats.UserTime);
+        status.Cstime = timeval(stats.SysTime);
+
+        let regs = PtraceRegs::New(tt.GetPtRegs(), tt.context.fs);
+        status.Reg = unsafe { mem::transmute::<PtraceRegs, ElfGregset>(regs) };
+        status.Fpvalid = 1;
+        return CoreNote::New(NOTE_NAME_CORE, NT_PRSTATUS, bytesOf(&status));
+    }
+
+    // prpsinfo returns the descriptor of NT_PRPSINFO.
+    fn prpsinfo(&self, t: &Thread) -> ElfPrpsinfo {
+        let pidns = t.PIDNamespace();
+        let tg = t.ThreadGroup();
+
+        let mut info = ElfPrpsinfo::default();
+        // The dumping task is running.
+        info.State = 0;
+        info.Sname = b'R';
+        info.Nice = t.Niceness() as i8;
+
+        let creds = self.Creds();
+        let userns = creds.lock().UserNamespace.clone();
+        info.Uid = creds.lock().RealKUID.In(&userns).OrOverflow().0;
+        info.Gid = creds.lock().RealKGID.In(&userns).OrOverflow().0;
+
+        info.Pid = pidns.IDOfThreadGroup(&tg);
+        info.Ppid = match t.Parent() {
+            None => 0,
+            Some(parent) => pidns.IDOfThreadGroup(&parent.ThreadGroup()),
+        };
+        if let Some(pg) = tg.ProcessGroup() {
+            info.Pgrp = pidns.IDOfProcessGroup(&pg);
+        }
+        if let Some(s) = tg.Session() {
+            info.Sid = pidns.IDOfSession(&s);
+        }
+
+        let name = t.Name();
+        let len = name.len().min(info.Fname.len() - 1);
+        info.Fname[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+        // pr_psargs is the command line with the NULs between arguments
+        // replaced by spaces.
+        let argv = self.mm.metadata.lock().argv;
+        if argv.Start() != 0 {
+            let len = argv.Len().min(ELF_PRARGSZ as u64 - 1) as usize;
+            let args: Result<Vec<u8>> = self.mm.CopyInVecManaul(self, argv.Start(), len);
+            if let Ok(args) = args {
+                for i in 0..len {
+                    info.Psargs[i] = if args[i] == 0 { b' ' } else { args[i] };
+                }
+            }
+        }
+
+        return info;
+    }
+}
+
+// fpNotes appends the NT_PRFPREG and NT_X86_XSTATE notes of tt.
+fn fpNotes(tt: &Task, notes: &mut Vec<CoreNote>) {
+    let fp = tt.context.X86fpstate.as_ref().unwrap();
+    notes.push(CoreNote::New(
+        NOTE_NAME_CORE,
+        NT_PRFPREG,
+        &fp.Slice()[..USER_I387_SIZE],
+    ));
+    notes.push(CoreNote::New(
+        NOTE_NAME_LINUX,
+        NT_X86_XSTATE,
+        &fp.Slice()[..fp.Size()],
+    ));
+}
+
+// coreFileNote returns the descriptor of NT_FILE: the number of file backed
+// vmas and the page size, then the start, end and file offset in pages of
+// each of them, then their NUL terminated names.
+fn coreFileNote(vmas: &[CoreVma]) -> Vec<u8> {
+    let files: Vec<&CoreVma> = vmas.iter().filter(|v| v.file.is_some()).collect();
+
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(files.len() as u64).to_le_bytes());
+    buf.extend_from_slice(&MemoryDef::PAGE_SIZE.to_le_bytes());
+    for v in &files {
+        let (offset, _) = v.file.as_ref().unwrap();
+        buf.extend_from_slice(&v.range.Start().to_le_bytes());
+        buf.extend_from_slice(&v.range.End().to_le_bytes());
+        buf.extend_from_slice(&(offset / MemoryDef::PAGE_SIZE).to_le_bytes());
+    }
+
+    for v in &files {
+        let (_, name) = v.file.as_ref().unwrap();
+        buf.extend_from_slice(name.as_bytes());
+        buf.push(0);
+    }
+
+    return buf;
+}
@@ -44,6 +44,10 @@ pub struct ExitStatus {
     // Signo is the signal that caused the exit. If the exit was not caused by
     // a signal, Signo is 0.
     pub Signo: i32,
+
+    // CoreDumped is true if the signal that caused the exit also caused a core
+    // dump to be written.
+    pub CoreDumped: bool,
 }
 
 impl ExitStatus {
@@ -51,6 +55,7 @@ impl ExitStatus {
         return ExitStatus {
             Code: code,
             Signo: signo,
+            CoreDumped: false,
         };
     }
 
@@ -63,7 +68,12 @@ impl ExitStatus {
     // Status returns the numeric representation of the ExitStatus returned by e.g.
     // the wait4() system call.
     pub fn Status(&self) -> u32 {
-        return (((self.Code as u32) & 0xff) << 8) | ((self.Signo as u32) & 0xff);
+        let mut status = (((self.Code as u32) & 0xff) << 8) | ((self.Signo as u32) & 0xff);
+        if self.CoreDumped {
+            status |= WaitStatus::CORE;
+        }
+
+        return status;
     }
 
     // ShellExitCode returns the numeric exit code that Bash would return for an
@@ -251,6 +261,12 @@ impl Thread {
         let lock = tg.lock().signalLock.clone();
         let _s = lock.lock();
 
+        self.prepareGroupExitLocked(&tg, es);
+    }
+
+    // prepareGroupExitLocked is PrepareGroupExit with the signal mutex held.
+    // It returns true if t initiated the group exit.
+    pub fn prepareGroupExitLocked(&self, tg: &ThreadGroup, es: ExitStatus) -> bool {
         let exiting = tg.lock().exiting;
         let execing = tg.lock().execing.Upgrade();
         if exiting || execing.is_some() {
@@ -265,7 +281,7 @@ impl Thread {
             // kernel/exit.c:do_group_exit() =>
             // include/linux/sched.h:signal_group_exit()).
             self.lock().exitStatus = tg.lock().exitStatus;
-            return;
+            return false;
         }
 
         tg.lock().exiting = true;
@@ -277,6 +293,8 @@ impl Thread {
                 sibling.lock().killLocked();
             }
         }
+
+        return true;
     }
 
     // exitThreadGroup transitions t to TaskExitInitiated, indicating to t's thread
@@ -299,6 +317,7 @@ impl Thread {
                 TaskExitState::TaskExitNone,
                 TaskExitState::TaskExitInitiated,
             );
+            self.parkForCoreDumpLocked(&tg);
             tg.lock().activeTasks -= 1;
             last = tg.lock().activeTasks == 0;
 
@@ -492,7 +511,7 @@ impl Thread {
         let children: Vec<Thread> = parent.lock().children.iter().cloned().collect();
         for child in &children {
             let child = child.clone();
-            if !opts.matchesTask(&child, &pidns) {
+            if !opts.matchesTask(&child, &pidns, false) {
                 continue;
             }
 
@@ -505,7 +524,7 @@ impl Thread {
                 && !child.lock().exitParentAcked
             {
                 anyWaitableTasks = true;
-                let wr = self.waitCollectZombieLocked(&child, opts);
+                let wr = self.waitCollectZombieLocked(&child, opts, false);
                 if wr.is_some() {
                     return (wr, anyWaitableTasks);
                 }
@@ -521,6 +540,20 @@ impl Thread {
                 continue;
             }
 
+            // If the waiter is in the same thread group as the task's
+            // tracer, do not report its group stops; they will be reported
+            // as ptrace stops instead. This also skips checking for group
+            // continues, but they'll be checked for when scanning tracees
+            // below. (Per kernel/exit.c:wait_consider_task(): "If a
+            // ptracer wants to distinguish the two events for its own
+            // children, it should create a separate process which takes
+            // the role of real parent.")
+            if let Some(tracer) = child.Tracer() {
+                if tracer.ThreadGroup() == parenttg {
+                    continue;
+                }
+            }
+
             anyWaitableTasks = true;
             if opts.Events & EVENT_CHILD_GROUP_STOP != 0 {
                 let wr = self.waitCollectChildGroupStopLocked(&child, opts);
@@ -537,6 +570,45 @@ impl Thread {
             }
         }
 
+        let tracees: Vec<Thread> = parent.lock().ptraceTracees.iter().cloned().collect();
+        for tracee in &tracees {
+            if !opts.matchesTask(tracee, &pidns, true) {
+                continue;
+            }
+
+            // Non-leaders do notify tracers on exit.
+            if opts.Events & EVENT_EXIT != 0 && !tracee.lock().exitTracerAcked {
+                anyWaitableTasks = true;
+                let wr = self.waitCollectZombieLocked(tracee, opts, true);
+                if wr.is_some() {
+                    return (wr, anyWaitableTasks);
+                }
+            }
+
+            if opts.Events & (EVENT_TRACEE_STOP | EVENT_GROUP_CONTINUE) == 0 {
+                continue;
+            }
+
+            if tracee.lock().exitState >= TaskExitState::TaskExitInitiated {
+                continue;
+            }
+
+            anyWaitableTasks = true;
+            if opts.Events & EVENT_TRACEE_STOP != 0 {
+                let wr = self.waitCollectTraceeStopLocked(tracee, opts);
+                if wr.is_some() {
+                    return (wr, anyWaitableTasks);
+                }
+            }
+
+            if opts.Events & EVENT_GROUP_CONTINUE != 0 {
+                let wr = self.waitCollectGroupContinueLocked(tracee, opts);
+                if wr.is_some() {
+                    return (wr, anyWaitableTasks);
+                }
+            }
+        }
+
         return (None, anyWaitableTasks);
     }
 
@@ -544,8 +616,13 @@ impl Thread {
         &self,
         target: &Thread,
         opts: &WaitOptions,
+        asPtracer: bool,
     ) -> Option<WaitResult> {
-        if !target.lock().exitParentNotified {
+        if asPtracer && !target.lock().exitTracerNotified {
+            return None;
+        }
+
+        if !asPtracer && !target.lock().exitParentNotified {
             return None;
         }
 
@@ -590,16 +667,24 @@ impl Thread {
             status = targetTg.lock().exitStatus.Status();
         }
 
+        // t may be (in the thread group of) target's parent, tracer, or both. We
+        // don't need to check for !exitTracerAcked because tracees are detached
+        // here, and we don't need to check for !exitParentAcked because zombies
+        // will be reaped here.
+        if let Some(tracer) = target.Tracer() {
+            if tracer.ThreadGroup() == tg && target.lock().exitTracerNotified {
+                target.lock().exitTracerAcked = true;
+                target.lock().ptraceTracer = None;
+                tracer.lock().ptraceTracees.remove(&target);
+            }
+        }
+
         let targetParent = target.lock().parent.clone();
         let exitParentNotified = target.lock().exitParentNotified;
 
-        assert!(
-            targetParent.is_some(),
-            "waitCollectZombieLocked parent should not be none"
-        );
-        let parentTg = targetParent.unwrap().lock().tg.clone();
+        let parentTg = targetParent.map(|p| p.lock().tg.clone());
         let targetLead = targetTg.lock().leader.Upgrade();
-        if parentTg != targetTg && exitParentNotified {
+        if parentTg == Some(tg.clone()) && exitParentNotified {
             target.lock().exitParentAcked = true;
             if targetLead.is_some() && target == targetLead.unwrap() {
                 // target.tg.exitedCPUStats doesn't include target.CPUStats() yet,
@@ -733,7 +818,34 @@ impl Thread {
         let exitTracerNotified = t.lock().exitTracerNotified;
         if !exitTracerNotified {
             t.lock().exitTracerNotified = true;
-            t.lock().exitTracerAcked = true;
+            match t.Tracer() {
+                None => {
+                    t.lock().exitTracerAcked = true;
+                }
+                Some(tracer) => {
+                    let tg = t.lock().tg.clone();
+                    let leader = tg.lock().leader.Upgrade();
+                    let parentTg = t.lock().parent.clone().map(|p| p.ThreadGroup());
+                    let tracerTg = tracer.ThreadGroup();
+                    if Some(t.clone()) == leader && Some(tracerTg.clone()) == parentTg {
+                        // The tracer is in the parent thread group, which is
+                        // notified below; don't report the exit twice.
+                        t.lock().exitTracerAcked = true;
+                    } else {
+                        // Tracers don't care about t's exit status.
+                        let lock = tracerTg.lock().signalLock.clone();
+                        let _s = lock.lock();
+                        let signalInfo =
+                            t.exitNotificationSignal(Signal(Signal::SIGCHLD), &tracer);
+                        tracer.sendSignalLocked(&signalInfo, true).ok();
+                        core::mem::drop(_s);
+                        tracerTg
+                            .lock()
+                            .eventQueue
+                            .Notify(EVENT_EXIT | EVENT_TRACEE_STOP | EVENT_GROUP_CONTINUE);
+                    }
+                }
+            }
         }
 
         let exitTracerAcked = t.lock().exitTracerAcked;
@@ -869,6 +981,11 @@ impl Thread {
                 parent.unwrap().lock().children.remove(&t);
                 t.lock().parent = None;
             }
+
+            let tracer = t.lock().ptraceTracer.take();
+            if let Some(tracer) = tracer {
+                tracer.lock().ptraceTracees.remove(&t);
+            }
         }
     }
 
@@ -891,7 +1008,11 @@ impl Thread {
 
         let signaled = self.lock().exitStatus.Signaled();
         if signaled {
-            info.Code = SignalInfo::CLD_KILLED;
+            info.Code = if self.lock().exitStatus.CoreDumped {
+                SignalInfo::CLD_DUMPED
+            } else {
+                SignalInfo::CLD_KILLED
+            };
             info.SigChld().status = self.lock().exitStatus.Signo;
         } else {
             info.Code = SignalInfo::CLD_EXITED;
@@ -923,6 +1044,19 @@ impl Thread {
         self.lock().parentDeathSignal = sig;
     }
 
+    pub fn Personality(&self) -> u32 {
+        return self.lock().personality;
+    }
+
+    // SetPersonality sets the personality of the task to persona and returns
+    // the previous one.
+    pub fn SetPersonality(&self, persona: u32) -> u32 {
+        let mut t = self.lock();
+        let old = t.personality;
+        t.personality = persona;
+        return old;
+    }
+
     pub fn Signaled(&self) -> bool {
         let tg = self.lock().tg.clone();
 
@@ -936,6 +1070,13 @@ impl Thread {
         let lastExiter = self.exitThreadGroup();
         let tg = self.lock().tg.clone();
 
+        // todo: fix this
+        let task = Task::Current();
+
+        // If another task of the thread group is dumping core, wait for it to
+        // finish with our registers.
+        self.WaitCoreDump(task);
+
         {
             let pidns = tg.PIDNamespace();
             let owner = pidns.lock().owner.clone();
@@ -943,8 +1084,6 @@ impl Thread {
             self.updateRSSLocked();
         }
 
-        // todo: fix this
-        let task = Task::Current();
         // Handle the robust futex list.
         self.ExitRobustList(task);
 
@@ -956,6 +1095,9 @@ impl Thread {
             tg.release();
         }
 
+        // Detach tracees.
+        self.exitPtrace();
+
         self.exitChildren();
         //self.ExitNotify();
     }
@@ -1002,6 +1144,13 @@ impl Thread {
             }
         }
 
+        // The thread group has exited once its last task is a zombie; wake up
+        // anyone polling a pidfd for it.
+        if tg.lock().liveTasks == 0 {
+            let queue = tg.lock().pidfdQueue.clone();
+            queue.Notify(READABLE_EVENT);
+        }
+
         self.exitNotifyLocked();
         if isRootProcess && tg.lock().liveTasks == 0 {
             let execId = execId.unwrap_or_default();
@@ -1159,7 +1308,7 @@ pub struct WaitOptions {
 
 impl WaitOptions {
     // Preconditions: The TaskSet mutex must be locked (for reading or writing).
-    pub fn matchesTask(&self, t: &Thread, pidns: &PIDNamespace) -> bool {
+    pub fn matchesTask(&self, t: &Thread, pidns: &PIDNamespace, tracee: bool) -> bool {
         if self.SpecificTID != 0 {
             // && self.SpecificTID != *pidns.lock().tids.get(t).unwrap() {
             let id = match pidns.lock().tids.get(t) {
@@ -1183,6 +1332,11 @@ impl WaitOptions {
             return false;
         }
 
+        // Tracees are always eligible.
+        if tracee {
+            return true;
+        }
+
         let leader = tg.lock().leader.Upgrade();
         if Some(t.clone()) == leader && tg.lock().terminationSignal.0 == Signal::SIGCHLD {
             return self.NonCloneTasks;
@@ -1217,6 +1371,10 @@ pub struct WaitResult {
 impl Task {
     pub fn RunExit(&mut self) -> TaskRunState {
         let t = self.Thread();
+        if t.PtraceExit() {
+            self.DoStop();
+        }
+
         t.ExitMain();
         return TaskRunState::RunExitNotify;
     }
@@ -1237,6 +1395,10 @@ impl Task {
 
     pub fn RunThreadExit(&mut self) -> TaskRunState {
         let t = self.Thread();
+        if t.PtraceExit() {
+            self.DoStop();
+        }
+
         t.ExitMain();
         return TaskRunState::RunThreadExitNotify;
     }
@@ -545,6 +545,10 @@ impl Thread {
         info!("SetExtraGIDs 2");
         let mut kgids = Vec::with_capacity(gids.len());
         let userns = t.creds.lock().UserNamespace.clone();
+        if !userns.SetgroupsAllowed() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
         for gid in gids {
             let kgid = userns.MapToKGID(*gid);
             if !kgid.Ok() {
@@ -0,0 +1,1302 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+
+use super::super::super::common::*;
+use super::super::super::linux_def::*;
+use super::super::super::task_mgr::*;
+use super::super::memmgr::metadata::*;
+use super::super::task::*;
+use super::super::SignalDef::*;
+use super::task_clone::*;
+use super::task_exit::*;
+use super::task_stop::*;
+use super::thread::*;
+
+// PtraceSyscallMode controls the behavior of a ptraced task at syscall entry
+// and exit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PtraceSyscallMode {
+    // None indicates that the task has never ptrace-stopped, or that it
+    // was resumed from its last ptrace-stop by PTRACE_CONT or
+    // PTRACE_DETACH. The task's syscalls will not be intercepted.
+    None,
+
+    // Intercept indicates that the task was resumed from its last
+    // ptrace-stop by PTRACE_SYSCALL. The next time the task enters or exits a
+    // syscall, a ptrace-stop will occur.
+    Intercept,
+
+    // Emu indicates that the task was resumed from its last ptrace-stop by
+    // PTRACE_SYSEMU or PTRACE_SYSEMU_SINGLESTEP. The next time the task
+    // enters a syscall, the syscall will be skipped, and a ptrace-stop will
+    // occur.
+    Emu,
+}
+
+impl Default for PtraceSyscallMode {
+    fn default() -> Self {
+        return Self::None;
+    }
+}
+
+// PtraceCloneKind is the kind of clone(2) being performed, which determines
+// the PTRACE_EVENT reported for it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PtraceCloneKind {
+    // Clone indicates a call to Task.Clone where the termination signal is
+    // not SIGCHLD and the CLONE_VFORK flag is not set.
+    Clone,
+
+    // Fork indicates a call to Task.Clone where the termination signal is
+    // SIGCHLD and the CLONE_VFORK flag is not set.
+    Fork,
+
+    // Vfork indicates a call to Task.Clone where the CLONE_VFORK flag is set.
+    Vfork,
+}
+
+impl PtraceCloneKind {
+    pub fn New(opts: &CloneOptions) -> Self {
+        if opts.Vfork {
+            return Self::Vfork;
+        }
+
+        if opts.sharingOption.TerminationSignal.0 == Signal::SIGCHLD {
+            return Self::Fork;
+        }
+
+        return Self::Clone;
+    }
+
+    pub fn Event(&self) -> i32 {
+        match self {
+            Self::Clone => return LibcConst::PTRACE_EVENT_CLONE as i32,
+            Self::Fork => return LibcConst::PTRACE_EVENT_FORK as i32,
+            Self::Vfork => return LibcConst::PTRACE_EVENT_VFORK as i32,
+        }
+    }
+}
+
+// PtraceOptions are the subset of options controlling a task's ptrace behavior
+// that are set by ptrace(PTRACE_SETOPTIONS).
+#[derive(Debug, Copy, Clone, Default)]
+pub struct PtraceOptions {
+    // ExitKill is true if the tracee should be sent SIGKILL when the tracer
+    // exits.
+    pub ExitKill: bool,
+
+    // If SysGood is true, set bit 7 in the signal number for
+    // syscall-entry-stop and syscall-exit-stop traps delivered to this task's
+    // tracer.
+    pub SysGood: bool,
+
+    // TraceClone is true if the tracer wants to receive PTRACE_EVENT_CLONE
+    // events.
+    pub TraceClone: bool,
+
+    // TraceExec is true if the tracer wants to receive PTRACE_EVENT_EXEC
+    // events.
+    pub TraceExec: bool,
+
+    // TraceExit is true if the tracer wants to receive PTRACE_EVENT_EXIT
+    // events.
+    pub TraceExit: bool,
+
+    // TraceFork is true if the tracer wants to receive PTRACE_EVENT_FORK
+    // events.
+    pub TraceFork: bool,
+
+    // TraceVfork is true if the tracer wants to receive PTRACE_EVENT_VFORK
+    // events.
+    pub TraceVfork: bool,
+
+    // TraceVforkDone is true if the tracer wants to receive
+    // PTRACE_EVENT_VFORK_DONE events.
+    pub TraceVforkDone: bool,
+
+    // TraceSeccomp is true if the tracer wants to receive
+    // PTRACE_EVENT_SECCOMP events.
+    pub TraceSeccomp: bool,
+}
+
+// ptraceStop is a TaskStop placed on tasks in a ptrace-stop.
+#[derive(Default)]
+pub struct PtraceStop {
+    // If frozen is true, the stopped task's tracer is currently operating on
+    // it, so Task.Kill should not remove the stop.
+    pub frozen: AtomicBool,
+
+    // If listen is true, the stopped task's tracer invoked PTRACE_LISTEN, so
+    // ptraceFreeze should fail.
+    pub listen: AtomicBool,
+}
+
+impl TaskStop for PtraceStop {
+    fn Type(&self) -> TaskStopType {
+        return TaskStopType::PTRACESTOP;
+    }
+
+    fn Killable(&self) -> bool {
+        return !self.frozen.load(Ordering::SeqCst);
+    }
+
+    fn AsPtraceStop(&self) -> Option<&PtraceStop> {
+        return Some(self);
+    }
+}
+
+impl ThreadInternal {
+    // ptraceSetOptionsLocked changes the ptrace options in use by t.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn ptraceSetOptionsLocked(&mut self, opts: u64) -> Result<()> {
+        const VALID_OPTIONS: u64 = LibcConst::PTRACE_O_EXITKILL
+            | LibcConst::PTRACE_O_TRACESYSGOOD
+            | LibcConst::PTRACE_O_TRACECLONE
+            | LibcConst::PTRACE_O_TRACEEXEC
+            | LibcConst::PTRACE_O_TRACEEXIT
+            | LibcConst::PTRACE_O_TRACEFORK
+            | LibcConst::PTRACE_O_TRACEVFORK
+            | LibcConst::PTRACE_O_TRACEVFORKDONE
+            | LibcConst::PTRACE_O_TRACESECCOMP;
+
+        if opts & !VALID_OPTIONS != 0 {
+            return Err(Error::SysError(SysErr::EINVAL));
+        }
+
+        self.ptraceOpts = PtraceOptions {
+            ExitKill: opts & LibcConst::PTRACE_O_EXITKILL != 0,
+            SysGood: opts & LibcConst::PTRACE_O_TRACESYSGOOD != 0,
+            TraceClone: opts & LibcConst::PTRACE_O_TRACECLONE != 0,
+            TraceExec: opts & LibcConst::PTRACE_O_TRACEEXEC != 0,
+            TraceExit: opts & LibcConst::PTRACE_O_TRACEEXIT != 0,
+            TraceFork: opts & LibcConst::PTRACE_O_TRACEFORK != 0,
+            TraceVfork: opts & LibcConst::PTRACE_O_TRACEVFORK != 0,
+            TraceVforkDone: opts & LibcConst::PTRACE_O_TRACEVFORKDONE != 0,
+            TraceSeccomp: opts & LibcConst::PTRACE_O_TRACESECCOMP != 0,
+        };
+
+        return Ok(());
+    }
+
+    // isPtraceStoppedLocked returns true if t is in a ptrace-stop.
+    //
+    // Preconditions: The signal mutex must be locked.
+    pub fn isPtraceStoppedLocked(&self) -> bool {
+        match &self.stop {
+            None => return false,
+            Some(s) => return s.Type() == TaskStopType::PTRACESTOP,
+        }
+    }
+
+    // isPtraceListenStoppedLocked returns true if t is in a ptrace-stop
+    // entered by PTRACE_LISTEN.
+    //
+    // Preconditions: The signal mutex must be locked.
+    pub fn isPtraceListenStoppedLocked(&self) -> bool {
+        match &self.stop {
+            None => return false,
+            Some(s) => match s.AsPtraceStop() {
+                None => return false,
+                Some(ps) => return ps.listen.load(Ordering::SeqCst),
+            },
+        }
+    }
+}
+
+impl Thread {
+    // Tracer returns t's ptrace Tracer.
+    pub fn Tracer(&self) -> Option<Thread> {
+        return self.lock().ptraceTracer.clone();
+    }
+
+    // HasTracer returns true if t has a ptrace tracer attached.
+    pub fn HasTracer(&self) -> bool {
+        return self.lock().ptraceTracer.is_some();
+    }
+
+    // updateTracedLocked mirrors whether t has a tracer into its task, where
+    // the syscall path checks it without locking t. Tasks which have exited
+    // are skipped, as their kernel stack, and the task on it, may be freed.
+    //
+    // Preconditions: The TaskSet mutex must be locked.
+    pub fn updateTracedLocked(&self) {
+        let t = self.lock();
+        if t.exitState >= TaskExitState::TaskExitZombie {
+            return;
+        }
+
+        let traced = t.ptraceTracer.is_some();
+        TaskId::New(t.taskId).GetTask().traced.store(traced, Ordering::Release);
+    }
+
+    // canTraceLocked returns true if t can ptrace target. This is analogous to
+    // Linux's kernel/ptrace.c:__ptrace_may_access(PTRACE_MODE_REALCREDS), minus
+    // LSM checks.
+    //
+    // Preconditions: The TaskSet mutex must be locked.
+    pub fn canTraceLocked(&self, target: &Thread) -> bool {
+        // "1. If the calling thread and the target thread are in the same thread
+        // group, access is always allowed." - ptrace(2)
+        if self.ThreadGroup() == target.ThreadGroup() {
+            return true;
+        }
+
+        // "3. If the access mode specifies PTRACE_MODE_REALCREDS, use the
+        // caller's real UID and GID for the checks in the next step."
+        //
+        // "4. Deny access if neither of the following is true:
+        //
+        // - The real, effective, and saved-set user IDs of the target match the
+        // caller's user ID, and the real, effective, and saved-set group IDs of
+        // the target match the caller's group ID.
+        //
+        // - The caller has the CAP_SYS_PTRACE capability in the user namespace
+        // of the target."
+        //
+        // "5. Deny access if the target process "dumpable" attribute has a value
+        // other than 1 (SUID_DUMP_USER), and the caller does not have the
+        // CAP_SYS_PTRACE capability in the user namespace of the target
+        // process." - ptrace(2)
+        let creds = self.Credentials();
+        let targetCreds = target.Credentials();
+        let targetUserns = targetCreds.lock().UserNamespace.clone();
+        if creds.HasCapabilityIn(Capability::CAP_SYS_PTRACE, &targetUserns) {
+            return true;
+        }
+
+        {
+            let c = creds.lock();
+            let tc = targetCreds.lock();
+            if c.RealKUID != tc.RealKUID
+                || c.RealKUID != tc.EffectiveKUID
+                || c.RealKUID != tc.SavedKUID
+                || c.RealKGID != tc.RealKGID
+                || c.RealKGID != tc.EffectiveKGID
+                || c.RealKGID != tc.SavedKGID
+            {
+                return false;
+            }
+        }
+
+        if target.MemoryManager().Dumpability() != USER_DUMPABLE {
+            return false;
+        }
+
+        return true;
+    }
+
+    // CanTrace checks that t is permitted to access target's state, as defined
+    // by ptrace(2), subsection "Ptrace access mode checking".
+    pub fn CanTrace(&self, target: &Thread) -> bool {
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+        return self.canTraceLocked(target);
+    }
+
+    // beginPtraceStopLocked initiates an unfrozen ptrace-stop on t. If t has
+    // been killed, the stop is skipped, and beginPtraceStopLocked returns
+    // false.
+    //
+    // beginPtraceStopLocked does not signal t's tracer or wake it if it is
+    // waiting.
+    //
+    // Preconditions: The TaskSet mutex must be locked. The caller must be
+    // running on the task goroutine.
+    pub fn beginPtraceStopLocked(&self) -> bool {
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        // This is analogous to Linux's kernel/signal.c:ptrace_stop() => ... =>
+        // kernel/sched/core.c:__schedule() => signal_pending_state() check, which
+        // is what prevents tasks from entering ptrace-stops after being killed.
+        // Note that if t was SIGKILLed and beingPtraceStopLocked is being called
+        // for PTRACE_EVENT_EXIT, the task will have dequeued the signal before
+        // entering the exit path, so t.killedLocked() will no longer return true.
+        // This is consistent with Linux: "Bugs: ... A SIGKILL signal may still
+        // cause a PTRACE_EVENT_EXIT stop before actual signal death. This may be
+        // changed in the future; SIGKILL is meant to always immediately kill tasks
+        // even under ptrace. Last confirmed on Linux 3.13." - ptrace(2)
+        let mut t = self.lock();
+        if t.killedLocked() || t.stop.is_some() {
+            return false;
+        }
+
+        // Save the FPU state now rather than when t is switched out, so that
+        // the tracer can access t's registers as soon as the stop is frozen.
+        let task = Task::Current();
+        if !task.context.savefpsate {
+            task.SaveFp();
+        }
+
+        t.beginInternalStopLocked(&Arc::new(PtraceStop::default()));
+        return true;
+    }
+
+    // ptraceNotifyTracerLocked signals t's tracer that t has entered a
+    // ptrace-stop, and wakes it if it is waiting.
+    //
+    // Preconditions: The TaskSet mutex must be locked.
+    pub fn ptraceNotifyTracerLocked(&self, code: i32, status: i32) {
+        let tracer = match self.Tracer() {
+            None => return,
+            Some(t) => t,
+        };
+
+        tracer.signalStop(self, code, status);
+        let tracerTg = tracer.ThreadGroup();
+        tracerTg.lock().eventQueue.Notify(EVENT_TRACEE_STOP);
+    }
+
+    // ptraceTrapLocked initiates a ptrace-stop with the given ptraceCode,
+    // reported to the tracer as a SIGTRAP.
+    //
+    // Preconditions: The TaskSet mutex must be locked. The caller must be
+    // running on the task goroutine.
+    pub fn ptraceTrapLocked(&self, code: i32) -> bool {
+        let info = SignalInfo {
+            Signo: Signal::SIGTRAP,
+            Code: code,
+            ..Default::default()
+        };
+
+        let pidns = self.PIDNamespace();
+        let userns = self.UserNamespace();
+        info.Kill().pid = pidns.IDOfTaskLocked(self);
+        info.Kill().uid = self
+            .Credentials()
+            .lock()
+            .RealKUID
+            .In(&userns)
+            .OrOverflow()
+            .0;
+
+        {
+            let mut t = self.lock();
+            t.ptraceCode = code;
+            t.ptraceSiginfo = Some(Box::new(info));
+        }
+
+        if !self.beginPtraceStopLocked() {
+            return false;
+        }
+
+        self.ptraceNotifyTracerLocked(SignalInfo::CLD_TRAPPED, Signal::SIGTRAP);
+        return true;
+    }
+
+    // ptraceEventLocked reports the given event to t's tracer. It returns true
+    // if t entered a ptrace-stop, in which case the caller must DoStop.
+    //
+    // Preconditions: The TaskSet mutex must be locked. The caller must be
+    // running on the task goroutine.
+    pub fn ptraceEventLocked(&self, event: i32, msg: u64) -> bool {
+        self.lock().ptraceEventMsg = msg;
+
+        // """
+        // PTRACE_EVENT stops are observed by the tracer as waitpid(2) returning
+        // with WIFSTOPPED(status), and WSTOPSIG(status) returns SIGTRAP. An
+        // additional bit is set in the higher byte of the status word: the value
+        // status>>8 will be
+        //
+        //   (SIGTRAP | PTRACE_EVENT_foo << 8).
+        //
+        // ...
+        //
+        // """ - ptrace(2)
+        return self.ptraceTrapLocked(Signal::SIGTRAP | (event << 8));
+    }
+
+    // ptraceSyscallStopLocked enters a syscall-enter-stop or syscall-exit-stop.
+    //
+    // Preconditions: The TaskSet mutex must be locked.
+    fn ptraceSyscallStopLocked(&self) -> bool {
+        let mut code = Signal::SIGTRAP;
+        if self.lock().ptraceOpts.SysGood {
+            code |= 0x80;
+        }
+
+        return self.ptraceTrapLocked(code);
+    }
+
+    // PtraceSyscallEnter is called immediately before entering a syscall. If
+    // the syscall is being intercepted by t's tracer, PtraceSyscallEnter enters
+    // a syscall-enter-stop and returns the mode under which it was entered;
+    // the caller must then DoStop before continuing.
+    pub fn PtraceSyscallEnter(&self) -> Option<PtraceSyscallMode> {
+        if !self.HasTracer() {
+            return None;
+        }
+
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        let mode = self.lock().ptraceSyscallMode;
+        match mode {
+            PtraceSyscallMode::None => return None,
+            PtraceSyscallMode::Intercept => {
+                info!("Entering syscall-enter-stop from PTRACE_SYSCALL");
+            }
+            PtraceSyscallMode::Emu => {
+                info!("Entering syscall-enter-stop from PTRACE_SYSEMU");
+            }
+        }
+
+        self.ptraceSyscallStopLocked();
+        return Some(mode);
+    }
+
+    // PtraceSyscallExit is called immediately after leaving a syscall. It
+    // returns true if t entered a syscall-exit-stop, in which case the caller
+    // must DoStop.
+    pub fn PtraceSyscallExit(&self) -> bool {
+        if !self.HasTracer() {
+            return false;
+        }
+
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if self.lock().ptraceSyscallMode != PtraceSyscallMode::Intercept {
+            return false;
+        }
+
+        info!("Entering syscall-exit-stop");
+        return self.ptraceSyscallStopLocked();
+    }
+
+    // PtraceAfterSyscallStop delivers the signal, if any, that t's tracer
+    // asked to be injected when resuming t from a syscall-enter-stop or
+    // syscall-exit-stop. It returns true if t has been killed, in which case
+    // a pending syscall must not be invoked.
+    pub fn PtraceAfterSyscallStop(&self) -> bool {
+        let sig = Signal(self.lock().ptraceCode);
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+        if sig.IsValid() {
+            self.sendSignalLocked(&SignalInfo::SignalInfoPriv(sig), false)
+                .ok();
+        }
+
+        return self.lock().killedLocked();
+    }
+
+    // ptraceSignal enters a signal-delivery-stop for the dequeued signal info.
+    // It returns true if the signal was handed to t's tracer, in which case
+    // the caller must DoStop and then continue with
+    // PtraceAfterSignalDeliveryStop.
+    //
+    // Preconditions: The signal mutex must not be locked.
+    pub fn ptraceSignal(&self, info: &SignalInfo) -> bool {
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if !self.HasTracer() {
+            return false;
+        }
+
+        {
+            let mut t = self.lock();
+            t.ptraceCode = info.Signo;
+            t.ptraceSiginfo = Some(Box::new(*info));
+        }
+
+        info!("Entering signal-delivery-stop for signal {}", info.Signo);
+        if self.beginPtraceStopLocked() {
+            self.ptraceNotifyTracerLocked(SignalInfo::CLD_TRAPPED, info.Signo);
+        }
+
+        return true;
+    }
+
+    // PtraceAfterSignalDeliveryStop returns the signal, possibly changed by
+    // t's tracer, that should be delivered after a signal-delivery-stop, or
+    // None if the tracer suppressed it.
+    pub fn PtraceAfterSignalDeliveryStop(&self) -> Option<SignalInfo> {
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        let (sig, info) = {
+            let mut t = self.lock();
+            (Signal(t.ptraceCode), t.ptraceSiginfo.take())
+        };
+
+        if !sig.IsValid() {
+            return None;
+        }
+
+        let mut info = match info {
+            None => SignalInfo::SignalInfoPriv(sig),
+            Some(info) => *info,
+        };
+
+        if sig.0 != info.Signo {
+            info.Signo = sig.0;
+            info.Errno = 0;
+            info.Code = SignalInfo::SIGNAL_INFO_USER;
+
+            // pid isn't a typo; the tracer's pid is used.
+            match self.Tracer() {
+                None => {
+                    info.Kill().pid = 0;
+                    info.Kill().uid = 0;
+                }
+                Some(tracer) => {
+                    let pidns = self.PIDNamespace();
+                    let userns = self.UserNamespace();
+                    info.Kill().pid = pidns.IDOfTaskLocked(&tracer);
+                    info.Kill().uid = tracer
+                        .Credentials()
+                        .lock()
+                        .RealKUID
+                        .In(&userns)
+                        .OrOverflow()
+                        .0;
+                }
+            }
+        }
+
+        return Some(info);
+    }
+
+    // PtraceGroupStop enters the ptrace-stop that replaces a group stop for a
+    // traced task. It returns false if t has no tracer.
+    //
+    // Preconditions: The TaskSet mutex must be locked.
+    pub fn ptraceGroupStopLocked(&self, sig: Signal, notifyParent: &mut bool) -> bool {
+        let tracer = match self.Tracer() {
+            None => return false,
+            Some(t) => t,
+        };
+
+        let mut sig = sig;
+        let seized = self.lock().ptraceSeized;
+        if seized {
+            if sig.0 == 0 {
+                sig = Signal(Signal::SIGTRAP);
+            }
+
+            // "If tracee was attached using PTRACE_SEIZE, group-stop is
+            // indicated by PTRACE_EVENT_STOP: status>>16 ==
+            // PTRACE_EVENT_STOP. This allows detection of group-stops
+            // without requiring an extra PTRACE_GETSIGINFO call." -
+            // "Group-stop", ptrace(2)
+            let code = sig.0 | (LibcConst::PTRACE_EVENT_STOP as i32) << 8;
+            let info = SignalInfo {
+                Signo: sig.0,
+                Code: code,
+                ..Default::default()
+            };
+
+            let pidns = self.PIDNamespace();
+            let userns = self.UserNamespace();
+            info.Kill().pid = pidns.IDOfTaskLocked(self);
+            info.Kill().uid = self
+                .Credentials()
+                .lock()
+                .RealKUID
+                .In(&userns)
+                .OrOverflow()
+                .0;
+
+            let mut t = self.lock();
+            t.ptraceCode = code;
+            t.ptraceSiginfo = Some(Box::new(info));
+        } else {
+            let mut t = self.lock();
+            t.ptraceCode = sig.0;
+            t.ptraceSiginfo = None;
+        }
+
+        if self.beginPtraceStopLocked() {
+            tracer.signalStop(self, SignalInfo::CLD_STOPPED, sig.0);
+            // For consistency with Linux, if the parent and tracer are in the
+            // same thread group, deduplicate notification signals.
+            let tracerTg = tracer.ThreadGroup();
+            let tg = self.ThreadGroup();
+            let leader = tg.lock().leader.Upgrade();
+            let parentTg = leader.and_then(|l| l.lock().parent.clone()).map(|p| p.ThreadGroup());
+            if *notifyParent && Some(tracerTg.clone()) == parentTg {
+                *notifyParent = false;
+                tracerTg
+                    .lock()
+                    .eventQueue
+                    .Notify(EVENT_CHILD_GROUP_STOP | EVENT_TRACEE_STOP);
+            } else {
+                tracerTg.lock().eventQueue.Notify(EVENT_TRACEE_STOP);
+            }
+        }
+
+        return true;
+    }
+
+    // ptraceFreeze checks if t is in a ptraceStop. If so, it freezes the
+    // ptraceStop, temporarily preventing it from being removed by a concurrent
+    // Task.Kill, and returns true. Otherwise it returns false.
+    //
+    // Preconditions: The TaskSet mutex must be locked. The caller must be
+    // running on the task goroutine of t's tracer.
+    pub fn ptraceFreeze(&self) -> bool {
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        let stop = match self.lock().stop.clone() {
+            None => return false,
+            Some(s) => s,
+        };
+
+        match stop.AsPtraceStop() {
+            None => return false,
+            Some(s) => {
+                if s.listen.load(Ordering::SeqCst) {
+                    return false;
+                }
+
+                s.frozen.store(true, Ordering::SeqCst);
+                return true;
+            }
+        }
+    }
+
+    // PtraceUnfreeze ends the effect of a previous successful call to
+    // ptraceFreeze.
+    pub fn PtraceUnfreeze(&self) {
+        // t.tg.signalHandlers is stable because t is in a frozen ptrace-stop,
+        // preventing its thread group from completing execve.
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+        self.ptraceUnfreezeLocked();
+    }
+
+    // Preconditions: t must be in a frozen ptraceStop. t's signal mutex must be
+    // locked.
+    pub fn ptraceUnfreezeLocked(&self) {
+        // Do this even if the task has been killed to ensure a panic if t.stop is
+        // nil or not a ptraceStop.
+        let stop = self.lock().stop.clone().unwrap();
+        stop.AsPtraceStop().unwrap().frozen.store(false, Ordering::SeqCst);
+
+        let mut t = self.lock();
+        if t.killedLocked() {
+            t.endInternalStopLocked();
+        }
+    }
+
+    // PtraceUnstop implements ptrace request PTRACE_CONT, PTRACE_SYSCALL,
+    // PTRACE_SINGLESTEP, PTRACE_SYSEMU, or PTRACE_SYSEMU_SINGLESTEP depending on
+    // mode and singlestep.
+    //
+    // Preconditions: t must be in a frozen ptrace stop.
+    //
+    // Postconditions: If ptraceUnstop returns nil, t will no longer be in a
+    // ptrace stop.
+    pub fn PtraceUnstop(&self, mode: PtraceSyscallMode, singlestep: bool, sig: Signal) -> Result<()> {
+        if sig.0 != 0 && !sig.IsValid() {
+            return Err(Error::SysError(SysErr::EIO));
+        }
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        let mut t = self.lock();
+        t.ptraceCode = sig.0;
+        t.ptraceSyscallMode = mode;
+        t.ptraceSinglestep = singlestep;
+        t.endInternalStopLocked();
+        return Ok(());
+    }
+
+    // PtraceListen implements ptrace(PTRACE_LISTEN).
+    //
+    // Preconditions: t must be in a frozen ptrace stop.
+    pub fn PtraceListen(&self) -> Result<()> {
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        {
+            let t = self.lock();
+            if !t.ptraceSeized {
+                return Err(Error::SysError(SysErr::EIO));
+            }
+
+            match &t.ptraceSiginfo {
+                None => return Err(Error::SysError(SysErr::EIO)),
+                Some(info) => {
+                    if info.Code >> 8 != LibcConst::PTRACE_EVENT_STOP as i32 {
+                        return Err(Error::SysError(SysErr::EIO));
+                    }
+                }
+            }
+        }
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        if self.lock().trapNotifyPending {
+            self.lock().endInternalStopLocked();
+        } else {
+            let stop = self.lock().stop.clone().unwrap();
+            stop.AsPtraceStop().unwrap().listen.store(true, Ordering::SeqCst);
+            self.ptraceUnfreezeLocked();
+        }
+
+        return Ok(());
+    }
+
+    // PtraceTraceme implements ptrace(PTRACE_TRACEME).
+    pub fn PtraceTraceme(&self) -> Result<()> {
+        let owner = self.TaskSet();
+        let _w = owner.WriteLock();
+
+        if self.lock().ptraceTracer.is_some() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let parent = match self.lock().parent.clone() {
+            // In Linux, only init can not have a parent, and init is assumed
+            // never to invoke PTRACE_TRACEME. Here, TGID 1 is an arbitrary user
+            // application that may invoke PTRACE_TRACEME; having no parent can
+            // also occur if all tasks in the parent thread group have exited,
+            // and failed to find a living thread group to reparent to. The
+            // former case is treated as if TGID 1 has an exited parent in an
+            // invisible ancestor PID namespace that is an owner of the root user
+            // namespace (and consequently has CAP_SYS_PTRACE), and the latter
+            // case is a special form of the exited parent case below. In either
+            // case, returning nil here is correct.
+            None => return Ok(()),
+            Some(p) => p,
+        };
+
+        if !parent.canTraceLocked(self) {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        if parent.lock().exitState != TaskExitState::TaskExitNone {
+            // Fail silently, as if we were successfully attached but then
+            // immediately detached. This is consistent with Linux.
+            return Ok(());
+        }
+
+        self.lock().ptraceTracer = Some(parent.clone());
+        self.updateTracedLocked();
+        parent.lock().ptraceTracees.insert(self.clone());
+        return Ok(());
+    }
+
+    // PtraceAttach implements ptrace(PTRACE_ATTACH, target) if seize is false,
+    // and ptrace(PTRACE_SEIZE, target, 0, opts) if seize is true. t is the
+    // caller.
+    pub fn PtraceAttach(&self, target: &Thread, seize: bool, opts: u64) -> Result<()> {
+        if self.ThreadGroup() == target.ThreadGroup() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        let owner = self.TaskSet();
+        let _w = owner.WriteLock();
+
+        if !self.canTraceLocked(target) {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        if target.lock().ptraceTracer.is_some() {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        // Attaching to zombies and dead tasks is not permitted; the exit
+        // notification logic relies on this. Linux allows attaching to PF_EXITING
+        // tasks, though.
+        if target.lock().exitState >= TaskExitState::TaskExitZombie {
+            return Err(Error::SysError(SysErr::EPERM));
+        }
+
+        if seize {
+            target.lock().ptraceSetOptionsLocked(opts)?;
+        }
+
+        target.lock().ptraceTracer = Some(self.clone());
+        target.updateTracedLocked();
+        self.lock().ptraceTracees.insert(target.clone());
+        target.lock().ptraceSeized = seize;
+
+        let tg = target.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        // "Unlike PTRACE_ATTACH, PTRACE_SEIZE does not stop the process." -
+        // ptrace(2)
+        if !seize {
+            target
+                .sendSignalLocked(
+                    &SignalInfo {
+                        Signo: Signal::SIGSTOP,
+                        Code: SignalInfo::SIGNAL_INFO_USER,
+                        ..Default::default()
+                    },
+                    false,
+                )
+                .ok();
+        }
+
+        // Undocumented Linux feature: If the tracee is already group-stopped (and
+        // consequently will not report the SIGSTOP just sent), force it to leave
+        // and re-enter the stop so that it will switch to a ptrace-stop.
+        let mut t = target.lock();
+        let groupStopped = match &t.stop {
+            None => false,
+            Some(s) => s.Type() == TaskStopType::GROUPSTOP,
+        };
+
+        if groupStopped {
+            t.trapStopPending = true;
+            t.endInternalStopLocked();
+            // Without this, the tracee's stop-signal will be ignored.
+            t.interrupt();
+        }
+
+        return Ok(());
+    }
+
+    // PtraceDetach implements ptrace(PTRACE_DETACH, target, 0, sig). t is the
+    // caller.
+    //
+    // Preconditions: target must be a tracee of t in a frozen ptrace stop.
+    //
+    // Postconditions: If ptraceDetach returns nil, target will no longer be in
+    // a ptrace stop.
+    pub fn PtraceDetach(&self, target: &Thread, sig: Signal) -> Result<()> {
+        if sig.0 != 0 && !sig.IsValid() {
+            return Err(Error::SysError(SysErr::EIO));
+        }
+
+        let owner = self.TaskSet();
+        let _w = owner.WriteLock();
+
+        target.lock().ptraceCode = sig.0;
+        target.forgetTracerLocked();
+        self.lock().ptraceTracees.remove(target);
+        return Ok(());
+    }
+
+    // PtraceKill implements ptrace(PTRACE_KILL, target). t is the caller.
+    pub fn PtraceKill(&self, target: &Thread) -> Result<()> {
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if target.Tracer() != Some(self.clone()) {
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+
+        let tg = target.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        // "This operation is deprecated; do not use it! Instead, send a SIGKILL
+        // directly using kill(2) or tgkill(2). The problem with PTRACE_KILL is
+        // that it requires the tracee to be in signal-delivery-stop, otherwise it
+        // may not work (i.e., may complete successfully but won't kill the
+        // tracee)." - ptrace(2)
+        if !target.lock().isPtraceStoppedLocked() {
+            return Ok(());
+        }
+
+        target.lock().ptraceCode = Signal::SIGKILL;
+        target.lock().endInternalStopLocked();
+        return Ok(());
+    }
+
+    // PtraceInterrupt implements ptrace(PTRACE_INTERRUPT, target). t is the
+    // caller.
+    pub fn PtraceInterrupt(&self, target: &Thread) -> Result<()> {
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if target.Tracer() != Some(self.clone()) {
+            return Err(Error::SysError(SysErr::ESRCH));
+        }
+
+        if !target.lock().ptraceSeized {
+            return Err(Error::SysError(SysErr::EIO));
+        }
+
+        let tg = target.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        let mut t = target.lock();
+        if t.killedLocked() || t.exitState >= TaskExitState::TaskExitInitiated {
+            return Ok(());
+        }
+
+        t.trapStopPending = true;
+        if t.isPtraceListenStoppedLocked() {
+            t.endInternalStopLocked();
+        }
+
+        t.interrupt();
+        return Ok(());
+    }
+
+    // forgetTracerLocked detaches t's tracer and ensures that t is no longer
+    // ptrace-stopped.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn forgetTracerLocked(&self) {
+        {
+            let mut t = self.lock();
+            t.ptraceSeized = false;
+            t.ptraceOpts = PtraceOptions::default();
+            t.ptraceSyscallMode = PtraceSyscallMode::None;
+            t.ptraceSinglestep = false;
+            t.ptraceTracer = None;
+        }
+        self.updateTracedLocked();
+
+        let exitTracerNotified = self.lock().exitTracerNotified;
+        let exitTracerAcked = self.lock().exitTracerAcked;
+        if exitTracerNotified && !exitTracerAcked {
+            self.lock().exitTracerAcked = true;
+            self.exitNotifyLocked();
+        }
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        let (groupStopComplete, groupStopPendingCount) = {
+            let tglock = tg.lock();
+            (tglock.groupStopComplete, tglock.groupStopPendingCount)
+        };
+
+        let mut t = self.lock();
+        // Unset t.trapStopPending, which might have been set by PTRACE_INTERRUPT. If
+        // it wasn't, it will be reset via t.groupStopPending after the following.
+        t.trapStopPending = false;
+
+        // If t's thread group is in a group stop and t is eligible to participate,
+        // make it do so. This is essentially the reverse of the special case in
+        // ptraceAttach, which converts a group stop to a ptrace stop. ("Handling
+        // of restart from group-stop is currently buggy, but the "as planned"
+        // behavior is to leave stopped tracee stopped and waiting for SIGCONT." -
+        // ptrace(2))
+        if (groupStopComplete || groupStopPendingCount != 0)
+            && !t.groupStopPending
+            && t.exitState < TaskExitState::TaskExitInitiated
+        {
+            t.groupStopPending = true;
+            // t already participated in the group stop when it unset
+            // groupStopPending.
+            t.groupStopAcknowledged = true;
+            t.interrupt();
+        }
+
+        if t.isPtraceStoppedLocked() {
+            t.endInternalStopLocked();
+        }
+    }
+
+    // exitPtrace is called in the exit path to detach all of t's tracees.
+    pub fn exitPtrace(&self) {
+        let owner = self.TaskSet();
+        let _w = owner.WriteLock();
+
+        let tracees: Vec<Thread> = self.lock().ptraceTracees.iter().cloned().collect();
+        for target in &tracees {
+            if target.lock().ptraceOpts.ExitKill {
+                let tg = target.ThreadGroup();
+                let lock = tg.lock().signalLock.clone();
+                let _s = lock.lock();
+                target
+                    .sendSignalLocked(
+                        &SignalInfo {
+                            Signo: Signal::SIGKILL,
+                            ..Default::default()
+                        },
+                        false,
+                    )
+                    .ok();
+            }
+
+            // Leave ptraceCode unchanged so that if the task is ptrace-stopped, it
+            // observes the ptraceCode it set before it entered the stop. I believe
+            // this is consistent with Linux.
+            target.forgetTracerLocked();
+        }
+
+        self.lock().ptraceTracees.clear();
+    }
+
+    // PtraceClone is called at the end of a clone or fork syscall to report
+    // the new task to t's tracer and, if appropriate, attach the tracer to
+    // child. It returns true if t entered a ptrace-stop, in which case the
+    // caller must DoStop.
+    pub fn PtraceClone(&self, kind: PtraceCloneKind, child: &Thread, opts: &CloneOptions) -> bool {
+        if !self.HasTracer() {
+            return false;
+        }
+
+        let owner = self.TaskSet();
+        let _w = owner.WriteLock();
+
+        let mut event = false;
+        if !opts.Untraced {
+            let popts = self.lock().ptraceOpts;
+            event = match kind {
+                PtraceCloneKind::Clone => popts.TraceClone,
+                PtraceCloneKind::Fork => popts.TraceFork,
+                PtraceCloneKind::Vfork => popts.TraceVfork,
+            };
+        }
+
+        // "If the PTRACE_O_TRACEFORK, PTRACE_O_TRACEVFORK, or PTRACE_O_TRACECLONE
+        // options are in effect, then children created by, respectively, vfork(2)
+        // or clone(2) with the CLONE_VFORK flag, fork(2) or clone(2) with the exit
+        // signal set to SIGCHLD, and other kinds of clone(2), are automatically
+        // attached to the same tracer which traced their parent. SIGSTOP is
+        // delivered to the children, causing them to enter signal-delivery-stop
+        // after they exit the system call which created them." - ptrace(2)
+        //
+        // clone(2)'s documentation of CLONE_UNTRACED and CLONE_PTRACE is
+        // confusingly wrong; see kernel/fork.c:_do_fork() => copy_process() =>
+        // include/linux/ptrace.h:ptrace_init_task().
+        if event || opts.InheritTracer {
+            if let Some(tracer) = self.Tracer() {
+                let (seized, popts) = {
+                    let t = self.lock();
+                    (t.ptraceSeized, t.ptraceOpts)
+                };
+
+                {
+                    let mut c = child.lock();
+                    c.ptraceTracer = Some(tracer.clone());
+                    // "The "seized" behavior ... is inherited by children that are
+                    // automatically attached using PTRACE_O_TRACEFORK,
+                    // PTRACE_O_TRACEVFORK, and PTRACE_O_TRACECLONE." - ptrace(2)
+                    c.ptraceSeized = seized;
+                    // "Flags are inherited by new tracees created and "auto-attached"
+                    // via active PTRACE_O_TRACEFORK, PTRACE_O_TRACEVFORK, or
+                    // PTRACE_O_TRACECLONE options." - ptrace(2)
+                    c.ptraceOpts = popts;
+                }
+                child.updateTracedLocked();
+
+                tracer.lock().ptraceTracees.insert(child.clone());
+
+                let childTg = child.ThreadGroup();
+                let lock = childTg.lock().signalLock.clone();
+                let _s = lock.lock();
+
+                let mut c = child.lock();
+                // "PTRACE_SEIZE: ... Automatically attached children stop with
+                // PTRACE_EVENT_STOP and WSTOPSIG(status) returns SIGTRAP instead
+                // of having SIGSTOP signal delivered to them." - ptrace(2)
+                if seized {
+                    c.trapStopPending = true;
+                } else {
+                    c.pendingSignals
+                        .Enque(
+                            Box::new(SignalInfo {
+                                Signo: Signal::SIGSTOP,
+                                ..Default::default()
+                            }),
+                            None,
+                        )
+                        .ok();
+                }
+
+                // The child handles the interrupt in child_clone before it first
+                // returns to the application.
+                c.interrupt();
+            }
+        }
+
+        if event {
+            let pid = self.PIDNamespace().IDOfTaskLocked(child);
+            return self.ptraceEventLocked(kind.Event(), pid as u64);
+        }
+
+        return false;
+    }
+
+    // PtraceVforkDone is called after the end of a vfork stop to determine if t
+    // should enter a PTRACE_EVENT_VFORK_DONE stop. child is the new task's
+    // thread ID in t's PID namespace. It returns true if t entered a
+    // ptrace-stop, in which case the caller must DoStop.
+    pub fn PtraceVforkDone(&self, child: i32) -> bool {
+        if !self.HasTracer() {
+            return false;
+        }
+
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if !self.lock().ptraceOpts.TraceVforkDone {
+            return false;
+        }
+
+        return self.ptraceEventLocked(LibcConst::PTRACE_EVENT_VFORK_DONE as i32, child as u64);
+    }
+
+    // PtraceSeccomp is called when a seccomp filter returns SECCOMP_RET_TRACE
+    // to determine if t should enter a PTRACE_EVENT_SECCOMP stop. data is the
+    // SECCOMP_RET_DATA portion of the filter result. It returns true if t
+    // entered a ptrace-stop, in which case the caller must DoStop.
+    pub fn PtraceSeccomp(&self, data: u16) -> bool {
+        if !self.HasTracer() {
+            return false;
+        }
+
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if !self.lock().ptraceOpts.TraceSeccomp {
+            return false;
+        }
+
+        return self.ptraceEventLocked(LibcConst::PTRACE_EVENT_SECCOMP as i32, data as u64);
+    }
+
+    // PtraceExec is called at the end of an execve syscall to report the exec
+    // to t's tracer. oldTID is t's thread ID, in its *tracer's* PID namespace,
+    // prior to the execve. (If t did not have a tracer at the time oldTID was
+    // read, oldTID may be 0. This is consistent with Linux.) It returns true
+    // if t entered a ptrace-stop, in which case the caller must DoStop.
+    pub fn PtraceExec(&self, oldTID: ThreadID) -> bool {
+        if !self.HasTracer() {
+            return false;
+        }
+
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        // Recheck with the TaskSet mutex locked. Most ptrace points don't need to
+        // do this because detaching resets ptrace options, but PTRACE_EVENT_EXEC
+        // is special because both TraceExec and !TraceExec do something if a
+        // tracer is attached.
+        if !self.HasTracer() {
+            return false;
+        }
+
+        if self.lock().ptraceOpts.TraceExec {
+            return self.ptraceEventLocked(LibcConst::PTRACE_EVENT_EXEC as i32, oldTID as u64);
+        }
+
+        // "If the PTRACE_O_TRACEEXEC option is not in effect for the execing
+        // tracee, and if the tracee was PTRACE_ATTACHed rather that [sic]
+        // PTRACE_SEIZEd, the kernel delivers an extra SIGTRAP to the tracee after
+        // execve(2) returns. This is an ordinary signal (similar to one which can
+        // be generated by `kill -TRAP`, not a special kind of ptrace-stop).
+        // Employing PTRACE_GETSIGINFO for this signal returns si_code set to 0
+        // (SI_USER). This signal may be blocked by signal mask, and thus may be
+        // delivered (much) later." - ptrace(2)
+        if self.lock().ptraceSeized {
+            return false;
+        }
+
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+        self.sendSignalLocked(
+            &SignalInfo {
+                Signo: Signal::SIGTRAP,
+                Code: SignalInfo::SIGNAL_INFO_USER,
+                ..Default::default()
+            },
+            false,
+        )
+        .ok();
+
+        return false;
+    }
+
+    // PtraceExit is called early in the task exit path to check if t should
+    // enter PTRACE_EVENT_EXIT stop. It returns true if t entered a
+    // ptrace-stop, in which case the caller must DoStop.
+    pub fn PtraceExit(&self) -> bool {
+        if !self.HasTracer() {
+            return false;
+        }
+
+        let owner = self.TaskSet();
+        let _r = owner.ReadLock();
+
+        if !self.lock().ptraceOpts.TraceExit {
+            return false;
+        }
+
+        let status = {
+            let tg = self.ThreadGroup();
+            let lock = tg.lock().signalLock.clone();
+            let _s = lock.lock();
+            self.lock().exitStatus.Status()
+        };
+
+        info!("Entering PTRACE_EVENT_EXIT stop");
+        return self.ptraceEventLocked(LibcConst::PTRACE_EVENT_EXIT as i32, status as u64);
+    }
+
+    // waitCollectTraceeStopLocked collects a ptrace-stop of target for a
+    // wait(2) by t.
+    //
+    // Preconditions: The TaskSet mutex must be locked for writing.
+    pub fn waitCollectTraceeStopLocked(
+        &self,
+        target: &Thread,
+        opts: &WaitOptions,
+    ) -> Option<WaitResult> {
+        let tg = target.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        if !target.lock().isPtraceStoppedLocked() {
+            return None;
+        }
+
+        let code = target.lock().ptraceCode;
+        if code == 0 {
+            return None;
+        }
+
+        let pidns = self.PIDNamespace();
+        let pid = pidns.IDOfTaskLocked(target);
+
+        let creds = target.Credentials();
+        let userns = self.UserNamespace();
+        let uid = creds.lock().RealKUID.In(&userns).OrOverflow();
+
+        if opts.ConsumeEvent {
+            target.lock().ptraceCode = 0;
+        }
+
+        return Some(WaitResult {
+            Thread: target.clone(),
+            TID: pid,
+            UID: uid,
+            Event: EVENT_TRACEE_STOP,
+            Status: (code as u32) << 8 | 0x7f,
+        });
+    }
+}
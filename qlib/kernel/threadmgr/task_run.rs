@@ -38,6 +38,13 @@ impl Task {
             return TaskRunState::RunInterrupt;
         }
 
+        // If the container has used up its CPU quota, wait for the next
+        // period before going back to the application.
+        if let Some(deadline) = t.ChargeCPUCgroup() {
+            let _ = self.blocker.BlockWithMonoTimer(false, Some(deadline));
+            return TaskRunState::RunApp;
+        }
+
         // We're about to switch to the application again. If there's still a
         // unhandled SyscallRestartErrno that wasn't translated to an EINTR,
         // restart the syscall that was interrupted. If there's a saved signal
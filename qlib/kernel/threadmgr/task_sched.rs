@@ -19,6 +19,7 @@ use core::ops::Deref;
 
 use super::super::super::common::*;
 use super::super::super::limits::*;
+use super::super::super::task_mgr::*;
 use super::super::super::linux::time::*;
 use super::super::super::linux_def::*;
 use super::super::super::usage::cpu::*;
@@ -99,6 +100,17 @@ pub struct TaskSchedInfoInternal {
     // Task.InterruptibleSleepStart, Task.UninterruptibleSleepStart, or
     // Task.Yield(), voluntarily ceasing execution.
     pub YieldCount: u64,
+
+    // PageFaults is the number of page faults the task has taken.
+    pub PageFaults: u64,
+
+    // LastCpu is the vcpu the task last ran on, or None if the task has not
+    // run yet.
+    pub LastCpu: Option<usize>,
+
+    // Migrations is the number of times the task has been resumed on a
+    // different vcpu than the one it last ran on.
+    pub Migrations: u64,
 }
 
 impl TaskSchedInfoInternal {
@@ -216,6 +228,25 @@ impl ThreadInternal {
             }
         }
     }
+
+    // Wchan returns the wait channel of the task, appropriate for
+    // /proc/[pid]/wchan. The sandbox doesn't keep a kernel call chain for
+    // blocked tasks, only stopped tasks have a known wait channel, which is
+    // "0" otherwise as for a running task in Linux.
+    pub fn Wchan(&self) -> &str {
+        if self.TaskSchedInfo().State != SchedState::Stopped {
+            return "0";
+        }
+
+        let tg = self.tg.clone();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+        match self.stop.clone() {
+            Some(stop) if stop.Type() == TaskStopType::GROUPSTOP => return "do_signal_stop",
+            Some(stop) if stop.Type() == TaskStopType::PTRACESTOP => return "ptrace_stop",
+            _ => return "0",
+        }
+    }
 }
 
 impl Thread {
@@ -282,6 +313,15 @@ impl Thread {
         // Remove CPUs in mask above Kernel.applicationCores.
         mask.ClearAbove(applicationCores);
 
+        // Remove CPUs outside of the cpuset of the container.
+        let cpuCgroup = self.ThreadGroup().lock().cpuCgroup.clone();
+        if let Some(cg) = cpuCgroup {
+            let cpus = cg.CPUMask();
+            if cpus != 0 {
+                mask.Intersect(cpus);
+            }
+        }
+
         // Ensure that at least 1 CPU is still allowed.
         if mask.NumCPUs() == 0 {
             return Err(Error::SysError(SysErr::EINVAL));
@@ -306,6 +346,7 @@ impl Thread {
         let mut t = self.lock();
         t.allowedCPUMask = mask.Copy();
         t.cpu = assignCPU(&mask, rootTID);
+        TaskId::New(t.taskId).GetTask().context.SetCPUMask(mask.Mask());
         return Ok(());
     }
 
@@ -314,14 +355,104 @@ impl Thread {
         return self.lock().niceness;
     }
 
-    // Priority returns t's priority.
+    // Priority returns t's priority, as the priority field of
+    // /proc/[pid]/stat: -1 - rt_priority for the real-time tasks and
+    // niceness + 20 for the other tasks.
     pub fn Priority(&self) -> i32 {
-        return self.lock().niceness + 20;
+        let t = self.lock();
+        if SchedEntity::IsRealtime(t.schedPolicy) {
+            return -1 - t.rtPriority;
+        }
+
+        return t.niceness + 20;
     }
 
     // SetNiceness sets t's niceness to n.
     pub fn SetNiceness(&self, n: i32) {
-        self.lock().niceness = n;
+        let mut t = self.lock();
+        t.niceness = n;
+
+        // The kernel stack of an exited task, and the task on it, may be
+        // freed.
+        if t.exitState < TaskExitState::TaskExitZombie {
+            TaskId::New(t.taskId).GetTask().context.sched.SetNice(n);
+        }
+    }
+
+    // SchedPolicy returns t's scheduling policy, real-time priority and
+    // whether SCHED_RESET_ON_FORK is set.
+    pub fn SchedPolicy(&self) -> (i32, i32, bool) {
+        let t = self.lock();
+        return (t.schedPolicy, t.rtPriority, t.schedResetOnFork);
+    }
+
+    // SchedLoad returns t's virtual runtime in ns and its load weight, as
+    // seen by the fair scheduler. The virtual runtime of an exited task is
+    // reported as 0.
+    pub fn SchedLoad(&self) -> (u64, u64) {
+        let t = self.lock();
+        let weight = SchedEntity::WeightOf(t.schedPolicy, t.niceness);
+        if t.exitState >= TaskExitState::TaskExitZombie {
+            return (0, weight);
+        }
+
+        let vruntime = TaskId::New(t.taskId).GetTask().context.sched.Vruntime();
+        return (vruntime, weight);
+    }
+
+    // SchedSlice returns t's time slice in ns, as returned by
+    // sched_rr_get_interval(2).
+    pub fn SchedSlice(&self) -> u64 {
+        let t = self.lock();
+        return SchedEntity::SliceOf(t.schedPolicy, t.niceness);
+    }
+
+    // SetSchedPolicy sets t's scheduling policy and real-time priority. It
+    // takes effect the next time t is queued to run.
+    pub fn SetSchedPolicy(&self, policy: i32, rtPriority: i32, resetOnFork: bool) {
+        let mut t = self.lock();
+        t.schedPolicy = policy;
+        t.rtPriority = rtPriority;
+        t.schedResetOnFork = resetOnFork;
+
+        if t.exitState < TaskExitState::TaskExitZombie {
+            TaskId::New(t.taskId)
+                .GetTask()
+                .context
+                .sched
+                .SetPolicy(policy, rtPriority, resetOnFork);
+        }
+    }
+
+    // MaySetSched returns whether t may change the niceness or the scheduling
+    // policy of target. This is analogous to Linux's set_one_prio_perm and
+    // check_same_owner: t's effective UID must be the real or effective UID
+    // of target, or t must have CAP_SYS_NICE in target's user namespace.
+    pub fn MaySetSched(&self, target: &Thread) -> bool {
+        let euid = self.Credentials().lock().EffectiveKUID;
+        let (uid, teuid, userns) = {
+            let creds = target.Credentials();
+            let c = creds.lock();
+            (c.RealKUID, c.EffectiveKUID, c.UserNamespace.clone())
+        };
+
+        if euid == uid || euid == teuid {
+            return true;
+        }
+
+        return self.HasCapabilityIn(Capability::CAP_SYS_NICE, &userns);
+    }
+
+    // CanNice returns whether t may set the niceness of target to nice. This
+    // is analogous to Linux's can_nice: the RLIMIT_NICE of target allows a
+    // niceness down to 20 - RLIMIT_NICE, a lower one requires CAP_SYS_NICE.
+    pub fn CanNice(&self, target: &Thread, nice: i32) -> bool {
+        let limit = target.ThreadGroup().Limits().Get(LimitType::Nice).Cur;
+        if (20 - nice) as u64 <= limit {
+            return true;
+        }
+
+        return self.HasCapability(Capability::CAP_SYS_NICE);
     }
 
     // NumaPolicy returns t's current numa policy.
@@ -0,0 +1,289 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
+
+use super::super::super::bpf::interpreter::*;
+use super::super::super::common::*;
+use super::super::super::linux::seccomp::*;
+use super::super::super::linux_def::*;
+use super::super::super::task_mgr::*;
+use super::super::kernel::seccomp::*;
+use super::super::task::*;
+use super::super::SignalDef::*;
+use super::task_exit::*;
+use super::thread::*;
+
+// MAX_SYSCALL_FILTER_INSTRUCTIONS is the maximum total number of
+// instructions in all of a task's seccomp filters, equal to Linux's
+// MAX_INSNS_PER_PATH.
+pub const MAX_SYSCALL_FILTER_INSTRUCTIONS: usize = 1 << 15;
+
+// SeccompAction is the outcome of checking a syscall against a task's
+// seccomp filters, as seen by the syscall dispatcher.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SeccompAction {
+    // Allow indicates that the syscall should be executed.
+    Allow,
+
+    // Deny indicates that the syscall must not be executed; its return value
+    // has already been set.
+    Deny,
+
+    // KillThread indicates that the task should exit with SIGSYS.
+    KillThread,
+
+    // KillProcess indicates that the thread group should exit with SIGSYS.
+    KillProcess,
+
+    // Trace indicates that the task entered a PTRACE_EVENT_SECCOMP stop. The
+    // caller must DoStop, then execute the (possibly modified) syscall.
+    Trace,
+}
+
+// seccompSiginfo returns the SignalInfo sent to a task whose syscall was
+// rejected with SECCOMP_RET_TRAP.
+fn seccompSiginfo(errno: i32, sysno: i32, ip: u64) -> SignalInfo {
+    let info = SignalInfo {
+        Signo: Signal::SIGSYS,
+        Errno: errno,
+        Code: SignalInfo::SYS_SECCOMP,
+        ..Default::default()
+    };
+
+    let sigsys = info.SigSys();
+    sigsys.callAddr = ip;
+    sigsys.syscall = sysno;
+    sigsys.arch = AUDIT_ARCH_X86_64;
+    return info;
+}
+
+impl Thread {
+    // HasSyscallFilters returns true if t has any seccomp filters installed,
+    // including the seccomp profile of its container.
+    pub fn HasSyscallFilters(&self) -> bool {
+        let t = self.lock();
+        return t.syscallFilters.len() != 0 || t.containerSeccomp.is_some();
+    }
+
+    // updateSyscallFilters mirrors whether t has seccomp filters into its
+    // task, where the syscall path checks it without locking t. Tasks which
+    // have exited are skipped, as their kernel stack, and the task on it, may
+    // be freed.
+    fn updateSyscallFilters(&self) {
+        let t = self.lock();
+        if t.exitState >= TaskExitState::TaskExitZombie {
+            return;
+        }
+
+        let has = t.syscallFilters.len() != 0 || t.containerSeccomp.is_some();
+        TaskId::New(t.taskId).GetTask().hasSyscallFilters.store(has, Ordering::Release);
+    }
+
+    // SeccompMode returns a SECCOMP_MODE_* constant indicating the task's
+    // current seccomp syscall filtering mode, appropriate for both
+    // prctl(PR_GET_SECCOMP) and /proc/[pid]/status.
+    pub fn SeccompMode(&self) -> i32 {
+        if self.HasSyscallFilters() {
+            return SECCOMP_MODE_FILTER;
+        }
+
+        return SECCOMP_MODE_NONE;
+    }
+
+    // AppendSyscallFilter adds BPF program p as a system call filter. If
+    // syncAll is set, the filters of all the threads in the thread group are
+    // replaced with the filters of t. As seccomp_can_sync_threads() does, this
+    // requires the filters of every other thread to be an ancestor of those
+    // of t, i.e. a prefix of them. Otherwise nothing is changed and the
+    // thread ID of the first thread which can't be synchronized is returned;
+    // 0 is returned on success.
+    //
+    // Preconditions: The caller must be running on the task goroutine.
+    pub fn AppendSyscallFilter(&self, p: Program, syncAll: bool) -> Result<ThreadID> {
+        // We must take the signal mutex to prevent our read-copy-update from
+        // happening while another task is syncing syscall filters to us; this
+        // keeps the filters in a consistent state.
+        let tg = self.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let _s = lock.lock();
+
+        // Cap the combined length of all syscall filters (plus a penalty of 4
+        // instructions per filter beyond the first) to
+        // MAX_SYSCALL_FILTER_INSTRUCTIONS. This restriction is inherited from
+        // Linux.
+        let mut totalLength = p.Length();
+        let mut newFilters: Vec<Arc<Program>> = Vec::new();
+        for f in &self.lock().syscallFilters {
+            totalLength += f.Length() + 4;
+            newFilters.push(f.clone());
+        }
+
+        if totalLength > MAX_SYSCALL_FILTER_INSTRUCTIONS {
+            return Err(Error::SysError(SysErr::ENOMEM));
+        }
+
+        let tasks: Vec<Thread> = if syncAll {
+            tg.lock().tasks.iter().cloned().collect()
+        } else {
+            Vec::new()
+        };
+
+        for ot in &tasks {
+            if *ot == *self {
+                continue;
+            }
+
+            let isAncestor = {
+                let filters = &ot.lock().syscallFilters;
+                filters.len() <= newFilters.len()
+                    && filters
+                        .iter()
+                        .zip(newFilters.iter())
+                        .all(|(a, b)| Arc::ptr_eq(a, b))
+            };
+
+            if !isAncestor {
+                // The pid namespace is locked before the signal mutex.
+                core::mem::drop(_s);
+                return Ok(self.PIDNamespace().IDOfTask(ot));
+            }
+        }
+
+        newFilters.push(Arc::new(p));
+
+        // Note: No new privs is always assumed to be set.
+        for ot in &tasks {
+            if *ot != *self {
+                ot.lock().syscallFilters = newFilters.clone();
+                ot.updateSyscallFilters();
+            }
+        }
+
+        self.lock().syscallFilters = newFilters;
+        self.updateSyscallFilters();
+        return Ok(0);
+    }
+
+    // evaluateSyscallFilters returns the result of evaluating t's syscall
+    // filters against the given syscall.
+    fn evaluateSyscallFilters(&self, sysno: i32, args: &[u64; 6], ip: u64) -> u32 {
+        let data = SeccompData {
+            Nr: sysno,
+            Arch: AUDIT_ARCH_X86_64,
+            InstructionPointer: ip,
+            Args: *args,
+        };
+        let input = data.AsBytes();
+
+        let (filters, policy) = {
+            let t = self.lock();
+            (t.syscallFilters.clone(), t.containerSeccomp.clone())
+        };
+
+        // The container profile behaves as the first installed filter.
+        let mut ret = match policy {
+            None => SECCOMP_RET_ALLOW,
+            Some(p) => p.Evaluate(sysno, args),
+        };
+        // "Every filter successfully installed will be evaluated (in reverse
+        // order) for each system call the task makes." - kernel/seccomp.c
+        for i in (0..filters.len()).rev() {
+            let thisRet = match filters[i].Exec(input) {
+                Ok(r) => r,
+                Err(e) => {
+                    debug!("seccomp-bpf filter {} returned error: {:?}", i, e);
+                    SECCOMP_RET_KILL_THREAD
+                }
+            };
+
+            // "If multiple filters exist, the return value for the evaluation of
+            // a given system call will always use the highest precedent value." -
+            // Documentation/prctl/seccomp_filter.txt
+            //
+            // "The ordering ensures that a min_t() over composed return values
+            // always selects the least permissive choice." -
+            // include/uapi/linux/seccomp.h
+            ret = LeastPermissive(ret, thisRet);
+        }
+
+        return ret;
+    }
+}
+
+impl Task {
+    // CheckSeccompSyscall applies the task's seccomp filters before the
+    // execution of syscall sysno at instruction pointer ip. (These parameters
+    // must be passed in because vsyscalls do not use the values in the task's
+    // registers.)
+    pub fn CheckSeccompSyscall(&mut self, sysno: i32, args: &[u64; 6], ip: u64) -> SeccompAction {
+        let t = self.Thread();
+        let result = t.evaluateSyscallFilters(sysno, args, ip);
+        let data = result & SECCOMP_RET_DATA;
+        match result & SECCOMP_RET_ACTION_FULL {
+            SECCOMP_RET_TRAP => {
+                // "Results in the kernel sending a SIGSYS signal to the triggering
+                // task without executing the system call. ... The SECCOMP_RET_DATA
+                // portion of the return value will be passed as si_errno." -
+                // Documentation/prctl/seccomp_filter.txt
+                t.SendSignal(&seccompSiginfo(data as i32, sysno, ip)).ok();
+                // "The return value register will contain an arch-dependent
+                // value." In practice, it's ~always the syscall number.
+                self.SetReturn(sysno as u64);
+                return SeccompAction::Deny;
+            }
+            SECCOMP_RET_ERRNO => {
+                // "Results in the lower 16-bits of the return value being passed
+                // to userland as the errno without executing the system call."
+                self.SetReturn(-(data as i64) as u64);
+                return SeccompAction::Deny;
+            }
+            SECCOMP_RET_TRACE => {
+                // "When returned, this value will cause the kernel to attempt to
+                // notify a ptrace()-based tracer prior to executing the system
+                // call. If there is no tracer present, -ENOSYS is returned to
+                // userland and the system call is not executed."
+                if !t.PtraceSeccomp(data as u16) {
+                    self.SetReturn(-SysErr::ENOSYS as u64);
+                    return SeccompAction::Deny;
+                }
+
+                return SeccompAction::Trace;
+            }
+            SECCOMP_RET_LOG => {
+                // "Results in the system call being executed after it is
+                // logged."
+                info!("seccomp: syscall {} allowed with SECCOMP_RET_LOG", sysno);
+                return SeccompAction::Allow;
+            }
+            SECCOMP_RET_ALLOW => {
+                // "Results in the system call being executed."
+                return SeccompAction::Allow;
+            }
+            SECCOMP_RET_KILL_THREAD => {
+                // "Results in the task exiting immediately without executing the
+                // system call. The exit status of the task will be SIGSYS, not
+                // SIGKILL."
+                return SeccompAction::KillThread;
+            }
+            _ => {
+                // Unknown actions, and SECCOMP_RET_KILL_PROCESS, kill the whole
+                // thread group. This is consistent with Linux.
+                return SeccompAction::KillProcess;
+            }
+        }
+    }
+}
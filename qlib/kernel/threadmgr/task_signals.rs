@@ -29,6 +29,7 @@ use super::super::threadmgr::thread_group::*;
 use super::super::SignalDef::*;
 //use super::super::eventchannel::*;
 use super::task_exit::*;
+use super::task_ptrace::*;
 use super::task_stop::*;
 use super::task_syscall::*;
 
@@ -347,6 +348,13 @@ impl Thread {
 
             t2.groupStopPending = true;
             t2.groupStopAcknowledged = false;
+            if t2.ptraceSeized {
+                t2.trapNotifyPending = true;
+                if t2.isPtraceListenStoppedLocked() {
+                    t2.endInternalStopLocked();
+                }
+            }
+
             t2.interrupt();
 
             add += 1;
@@ -761,7 +769,14 @@ impl ThreadGroupInternal {
         for t in &self.tasks {
             let mut t = t.lock();
             t.groupStopPending = false;
-            if t.stop.is_some() && t.stop.clone().unwrap().Type() == TaskStopType::GROUPSTOP {
+            if t.ptraceSeized {
+                t.trapNotifyPending = true;
+                if t.isPtraceListenStoppedLocked() {
+                    t.endInternalStopLocked();
+                }
+            } else if t.stop.is_some()
+                && t.stop.clone().unwrap().Type() == TaskStopType::GROUPSTOP
+            {
                 t.endInternalStopLocked();
             }
         }
@@ -933,7 +948,36 @@ impl Task {
             // leader's) tracer are in the same thread group, deduplicate
             // notifications.
             let leader = tg.lock().leader.Upgrade().unwrap();
-            let notifyParent = leader.lock().parent.is_some();
+            let mut notifyParent = leader.lock().parent.is_some();
+            if let Some(tracer) = leader.Tracer() {
+                let tracerTg = tracer.ThreadGroup();
+                let parentTg = leader.lock().parent.clone().map(|p| p.ThreadGroup());
+                if notifyParent && Some(tracerTg.clone()) == parentTg {
+                    notifyParent = false;
+                }
+
+                // Sending CLD_STOPPED to the tracer doesn't really make any sense;
+                // the thread group leader may have already entered the stop and
+                // notified its tracer accordingly. But it's consistent with
+                // Linux...
+                if intr {
+                    tracer.signalStop(&leader, SignalInfo::CLD_STOPPED, sig.0);
+                    if !notifyParent {
+                        tracerTg.lock().eventQueue.Notify(
+                            EVENT_GROUP_CONTINUE | EVENT_TRACEE_STOP | EVENT_CHILD_GROUP_STOP,
+                        );
+                    } else {
+                        tracerTg
+                            .lock()
+                            .eventQueue
+                            .Notify(EVENT_GROUP_CONTINUE | EVENT_TRACEE_STOP);
+                    }
+                } else {
+                    tracer.signalStop(&leader, SignalInfo::CLD_CONTINUED, sig.0);
+                    tracerTg.lock().eventQueue.Notify(EVENT_GROUP_CONTINUE);
+                }
+            }
+
             if notifyParent {
                 // If groupContInterrupted, do as Linux does and pretend the group
                 // stop completed just before it ended. The theoretical behavior in
@@ -986,7 +1030,10 @@ impl Task {
                 notifyParent = false;
             }
 
-            {
+            // If the task is traced, the group stop is reported to the tracer
+            // as a ptrace stop instead.
+            let traced = t.ptraceGroupStopLocked(sig, &mut notifyParent);
+            if !traced {
                 let _s = lock.lock();
                 let killedLocked = t.lock().killedLocked();
                 if !killedLocked {
@@ -1001,6 +1048,13 @@ impl Task {
                 ptg.lock().eventQueue.Notify(EVENT_CHILD_GROUP_STOP);
             }
 
+            if traced {
+                // The tracer may act on the task before it next runs, so enter
+                // the stop now rather than on the way back to user mode.
+                core::mem::drop(_r);
+                task.DoStop();
+            }
+
             return TaskRunState::RunInterrupt;
         }
 
@@ -1020,12 +1074,63 @@ impl Task {
             tg.lock().groupStopDequeued = true;
         }
 
+        if info.Signo != Signal::SIGKILL && t.HasTracer() {
+            // The tracer might change this signal into a stop signal, in which case
+            // any SIGCONT received after the signal was originally dequeued should
+            // cancel it. This is consistent with Linux.
+            tg.lock().groupStopDequeued = true;
+            // This is unconditional in ptrace_stop().
+            t.lock().trapStopPending = false;
+            // Can't lock the TaskSet mutex while holding a signal mutex.
+            core::mem::drop(locker);
+
+            if t.ptraceSignal(&info) {
+                // Dequeueing the signal action must wait until after the
+                // signal-delivery-stop ends since the tracer can change or
+                // suppress the signal.
+                task.DoStop();
+                return task.RunInterruptAfterSignalDeliveryStop();
+            }
+
+            let _s = lock.lock();
+            let sh = tg.lock().signalHandlers.clone();
+            let act = sh.DequeAct(Signal(info.Signo));
+            core::mem::drop(_s);
+            return task.ThreadDeliverSignal(&info, &act);
+        }
+
         let sh = tg.lock().signalHandlers.clone();
         let act = sh.DequeAct(Signal(info.Signo));
         core::mem::drop(locker);
         return task.ThreadDeliverSignal(&info, &act);
     }
 
+    // RunInterruptAfterSignalDeliveryStop delivers the signal, if any, left by
+    // the tracer when the task's signal-delivery-stop ended.
+    pub fn RunInterruptAfterSignalDeliveryStop(&mut self) -> TaskRunState {
+        let t = self.Thread();
+        let info = match t.PtraceAfterSignalDeliveryStop() {
+            None => return TaskRunState::RunInterrupt,
+            Some(info) => info,
+        };
+
+        let tg = t.ThreadGroup();
+        let lock = tg.lock().signalLock.clone();
+        let locker = lock.lock();
+
+        // If the signal is masked, re-queue it.
+        let sig = Signal(info.Signo);
+        if SignalSet::New(sig).0 & t.lock().signalMask.0 != 0 {
+            t.sendSignalLocked(&info, false).ok();
+            return TaskRunState::RunInterrupt;
+        }
+
+        let sh = tg.lock().signalHandlers.clone();
+        let act = sh.DequeAct(sig);
+        core::mem::drop(locker);
+        return self.ThreadDeliverSignal(&info, &act);
+    }
+
     // deliverSignal delivers the given signal and returns the following run state.
     pub fn ThreadDeliverSignal(&mut self, info: &SignalInfo, act: &SigAct) -> TaskRunState {
         let sigact = ComputeAction(Signal(info.Signo), act);
@@ -1080,10 +1185,12 @@ impl Task {
                     _ => (),
                 }*/
                 //Emit(&Event::UncaughtSignal(ucs)).unwrap();
-                self.Thread().PrepareGroupExit(ExitStatus {
-                    Code: 0,
-                    Signo: info.Signo,
-                });
+                let es = ExitStatus::New(0, info.Signo);
+                if sigact == SignalAction::CORE {
+                    self.DoCoreDump(info, es);
+                } else {
+                    self.Thread().PrepareGroupExit(es);
+                }
 
                 return TaskRunState::RunExit;
             }
@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use alloc::string::String;
+use alloc::vec::Vec;
 
 //use super::super::syscalls::util::KLoadBinary;
 use super::super::super::auth::*;
+use super::super::fs::mount::*;
 use super::super::kernel::cpuset::*;
 use super::super::kernel::fd_table::*;
 use super::super::kernel::fs_context::*;
@@ -77,7 +79,15 @@ pub struct TaskConfig {
     // IPCNamespace is the IPCNamespace of the new task.
     pub IPCNamespace: IPCNamespace,
 
+    // MountNamespace is the MountNs of the new task.
+    pub MountNamespace: MountNs,
+
     pub Blocker: Blocker,
 
     pub ContainerID: String,
+
+    // SetTIDs holds the TIDs requested via clone3(2)'s set_tid, starting with
+    // the new task's own PID namespace. If it is empty, TIDs are allocated
+    // normally.
+    pub SetTIDs: Vec<ThreadID>,
 }
@@ -17,6 +17,7 @@ use alloc::vec::Vec;
 
 use super::super::threadmgr::thread::*;
 use super::super::threadmgr::threads::*;
+use super::task_ptrace::*;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum TaskStopType {
@@ -31,6 +32,11 @@ pub enum TaskStopType {
 pub trait TaskStop: Sync + Send {
     fn Type(&self) -> TaskStopType;
     fn Killable(&self) -> bool;
+
+    // AsPtraceStop returns the stop as a PtraceStop if it is one.
+    fn AsPtraceStop(&self) -> Option<&PtraceStop> {
+        return None;
+    }
 }
 
 impl ThreadInternal {
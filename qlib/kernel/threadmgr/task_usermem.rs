@@ -23,6 +23,7 @@ use core::sync::atomic::{AtomicU32, Ordering};
 use super::super::super::common::*;
 use super::super::super::mutex::*;
 use super::super::super::linux_def::*;
+use super::super::super::addr::*;
 use super::super::super::mem::block::*;
 use super::super::memmgr::mm::*;
 use super::super::task::*;
@@ -137,6 +138,50 @@ impl MemoryManager {
         return self.CopyDataOutLocked(task, &rl, from, vaddr, len, allowPartial);
     }
 
+    // CopyDataOutForceManual copies raw data from kernel to user as
+    // ptrace(PTRACE_POKETEXT) does: the write is allowed to read-only
+    // mappings as long as they may ever be made writable, in which case the
+    // pages are broken out of copy-on-write first.
+    pub fn CopyDataOutForceManual(&self, task: &Task, from: u64, vaddr: u64, len: usize) -> Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+
+        let rl = self.MappingReadLock();
+
+        let mut addr = Addr(vaddr).RoundDown()?.0;
+        while addr < vaddr + len as u64 {
+            match self.GetVmaAndRangeLocked(addr) {
+                None => return Err(Error::SysError(SysErr::EFAULT)),
+                Some((vma, _)) => {
+                    if !vma.maxPerms.Write() {
+                        return Err(Error::SysError(SysErr::EFAULT));
+                    }
+                }
+            }
+
+            addr += MemoryDef::PAGE_SIZE;
+        }
+
+        // V2PLocked copies the pages on write even for the read request, so
+        // the physical pages returned are private to mm.
+        let mut iovs = Vec::with_capacity(4);
+        self.V2PLocked(task, &rl, vaddr, len as u64, &mut iovs, false, false)?;
+
+        let mut offset = 0;
+        for iov in &iovs {
+            unsafe {
+                let dstPtr = iov.start as *mut u8;
+                let srcPtr = (from + offset) as *const u8;
+                core::ptr::copy_nonoverlapping(srcPtr, dstPtr, iov.len);
+            }
+
+            offset += iov.len as u64;
+        }
+
+        return Ok(());
+    }
+
     pub fn ZeroDataOut(&self, task: &Task, vaddr: u64, len: usize, allowPartial: bool) -> Result<usize> {
         let rl = self.MappingReadLock();
 
@@ -13,22 +13,28 @@
 // limitations under the License.
 
 use crate::qlib::mutex::*;
+use alloc::boxed::Box;
 use alloc::collections::btree_set::BTreeSet;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::sync::Weak;
+use alloc::vec::Vec;
 use core::cmp::*;
 use core::ops::Deref;
 
 use super::super::super::auth::*;
+use super::super::super::bpf::interpreter::*;
 use super::super::super::linux_def::*;
 use super::super::super::usage::io::*;
+use super::super::fs::mount::*;
 use super::super::kernel::cpuset::*;
 use super::super::kernel::fd_table::*;
 use super::super::kernel::fs_context::*;
 use super::super::kernel::ipc_namespace::*;
 use super::super::kernel::kernel::*;
+use super::super::kernel::perf_event::*;
+use super::super::kernel::seccomp::SyscallPolicy;
 use super::super::kernel::time::*;
 use super::super::kernel::uts_namespace::*;
 use super::super::kernel::waiter::queue::*;
@@ -36,6 +42,7 @@ use super::super::kernel::waiter::waitgroup::*;
 use super::super::memmgr::mm::*;
 use super::super::threadmgr::task_block::*;
 use super::super::threadmgr::task_exit::*;
+use super::super::threadmgr::task_ptrace::*;
 use super::super::threadmgr::task_sched::*;
 use super::super::threadmgr::task_stop::*;
 use super::super::SignalDef::*;
@@ -80,6 +87,7 @@ pub struct ThreadInternal {
 
     pub utsns: UTSNamespace,
     pub ipcns: IPCNamespace,
+    pub mountNS: MountNs,
 
     pub SignalQueue: Queue,
 
@@ -203,8 +211,8 @@ pub struct ThreadInternal {
 
     //pub containerID: String,
 
-    // This is mostly a fake cpumask just for sched_set/getaffinity as we
-    // don't really control the affinity.
+    // allowedCPUMask is the mask of the vcpus the task may run on. It is
+    // mirrored to the task context, which the vcpu scheduler honors.
     //
     // Invariant: allowedCPUMask.Size() ==
     // sched.CPUMaskSize(Kernel.applicationCores).
@@ -219,15 +227,25 @@ pub struct ThreadInternal {
     pub cpu: i32,
 
     // This is used to keep track of changes made to a process' priority/niceness.
-    // It is mostly used to provide some reasonable return value from
-    // getpriority(2) after a call to setpriority(2) has been made.
-    // We currently do not actually modify a process' scheduling priority.
+    // It is mirrored to the scheduling entity of the task, see
+    // Thread::SetNiceness.
     // NOTE: This represents the userspace view of priority (nice).
     // This means that the value should be in the range [-20, 19].
     //
     // niceness is protected by mu.
     pub niceness: i32,
 
+    // schedPolicy, rtPriority and schedResetOnFork are the scheduling policy,
+    // the real-time priority and SCHED_RESET_ON_FORK of the task. As
+    // niceness, they are the source of truth and are mirrored to the
+    // scheduling entity of the task until it exits, see
+    // Thread::SetSchedPolicy.
+    //
+    // They are protected by mu.
+    pub schedPolicy: i32,
+    pub rtPriority: i32,
+    pub schedResetOnFork: bool,
+
     // This is used to track the numa policy for the current thread. This can be
     // modified through a set_mempolicy(2) syscall. Since we always report a
     // single numa node, all policies are no-ops. We only track this information
@@ -253,6 +271,12 @@ pub struct ThreadInternal {
     // parentDeathSignal is protected by mu.
     pub parentDeathSignal: Signal,
 
+    // personality is the execution domain of the task as set by
+    // personality(2). It is inherited by children created with clone(2).
+    //
+    // personality is protected by mu.
+    pub personality: u32,
+
     // If stop is not nil, it is the internally-initiated condition that
     // currently prevents the task goroutine from running.
     //
@@ -322,20 +346,108 @@ pub struct ThreadInternal {
     pub ioUsage: IO,
 
     pub robust_list_head: u64,
+
+    // ptraceTracer is the task that is ptrace-attached to this one. If
+    // ptraceTracer is None, this task is not being traced.
+    //
+    // ptraceTracer is protected by the TaskSet mutex.
+    pub ptraceTracer: Option<Thread>,
+
+    // ptraceTracees is the set of tasks that this task is ptrace-attached to.
+    //
+    // ptraceTracees is protected by the TaskSet mutex.
+    pub ptraceTracees: BTreeSet<Thread>,
+
+    // ptraceSeized is true if ptraceTracer attached to this task with
+    // PTRACE_SEIZE.
+    //
+    // ptraceSeized is protected by the TaskSet mutex.
+    pub ptraceSeized: bool,
+
+    // ptraceOpts contains ptrace options explicitly set by the tracer. If
+    // ptraceTracer is None, ptraceOpts is expected to be the zero value.
+    //
+    // ptraceOpts is protected by the TaskSet mutex.
+    pub ptraceOpts: PtraceOptions,
+
+    // ptraceSyscallMode controls ptrace behavior around syscall entry and
+    // exit.
+    //
+    // ptraceSyscallMode is protected by the TaskSet mutex.
+    pub ptraceSyscallMode: PtraceSyscallMode,
+
+    // If ptraceSinglestep is true, the next time the task executes application
+    // code, single-stepping should be enabled. ptraceSinglestep is stored
+    // independently of the architecture-specific trap flag because tracer
+    // detaching (which can happen concurrently with the tracee's execution if
+    // the tracer exits) must disable single-stepping, and the task's
+    // architectural state is implicitly exclusive to the task goroutine (no
+    // synchronization occurs before passing registers to SwitchToApp).
+    //
+    // ptraceSinglestep is analogous to Linux's TIF_SINGLESTEP.
+    //
+    // ptraceSinglestep is protected by the TaskSet mutex.
+    pub ptraceSinglestep: bool,
+
+    // If t is ptrace-stopped, ptraceCode is a ptrace-defined value set at the
+    // time that t entered the ptrace stop, reset to 0 when the tracer
+    // acknowledges the stop with a wait*() syscall. Otherwise, it is the
+    // signal number passed to the ptrace operation that ended the last ptrace
+    // stop on this task. In the latter case, the effect of ptraceCode depends
+    // on the nature of the ptrace stop; signal-delivery-stop uses it to
+    // conditionally override ptraceSiginfo, syscall-entry/exit-stops send the
+    // signal to the task after leaving the stop, and PTRACE_EVENT stops and
+    // traced group stops ignore it entirely.
+    //
+    // Linux contextually stores the equivalent of ptraceCode in
+    // task_struct::exit_code.
+    //
+    // ptraceCode is protected by the TaskSet mutex.
+    pub ptraceCode: i32,
+
+    // ptraceSiginfo is the value returned to the tracer by
+    // ptrace(PTRACE_GETSIGINFO) and modified by ptrace(PTRACE_SETSIGINFO).
+    // (Despite the name, PTRACE_PEEKSIGINFO is completely unrelated.)
+    // ptraceSiginfo is None if the task is in a ptraced group-stop (this is
+    // required for PTRACE_GETSIGINFO to return EINVAL during such stops).
+    //
+    // ptraceSiginfo is protected by the signal mutex.
+    pub ptraceSiginfo: Option<Box<SignalInfo>>,
+
+    // ptraceEventMsg is the value set by PTRACE_EVENT stops and returned to
+    // the tracer by ptrace(PTRACE_GETEVENTMSG).
+    //
+    // ptraceEventMsg is protected by the TaskSet mutex.
+    pub ptraceEventMsg: u64,
+
+    // syscallFilters is all seccomp-bpf syscall filters applicable to the
+    // task, in the order in which they were installed. Filters are shared
+    // between tasks that inherit them, and are never mutated once installed.
+    //
+    // syscallFilters is protected by the signal mutex.
+    pub syscallFilters: Vec<Arc<Program>>,
+
+    // containerSeccomp is the linux.seccomp profile of the container of the
+    // task. Unlike syscallFilters it can't be changed by the application.
+    pub containerSeccomp: Option<Arc<SyscallPolicy>>,
+
+    // perfEvents are the perf events counting this task, which are inherited
+    // by its children and enabled on exec as requested by their attributes.
+    // The events are owned by their files, or by the event they were
+    // inherited from.
+    pub perfEvents: Vec<PerfEventWeak>,
+
+    // cpuCgroupCharged is the CPU time of the task already charged to the CPU
+    // controller of its container, under cpuCgroupGeneration of its limits.
+    pub cpuCgroupGeneration: u64,
+    pub cpuCgroupCharged: i64,
 }
 
 impl ThreadInternal {
     pub fn IsChrooted(&self) -> bool {
-        let realRoot = self
-            .k
-            .mounts
-            .read()
-            .get(&self.containerID)
-            .unwrap()
-            .root
-            .clone();
+        let realRoot = self.mountNS.Root();
         let root = self.fsc.RootDirectory();
-        return realRoot == root;
+        return root != realRoot;
     }
 
     pub fn SetRet(&mut self, ret: u64) {
@@ -426,6 +538,14 @@ impl Thread {
         return self.lock().utsns.clone();
     }
 
+    pub fn IPCNamespace(&self) -> IPCNamespace {
+        return self.lock().ipcns.clone();
+    }
+
+    pub fn MountNamespace(&self) -> MountNs {
+        return self.lock().mountNS.clone();
+    }
+
     pub fn MemoryManager(&self) -> MemoryManager {
         return self.lock().memoryMgr.clone();
     }
@@ -22,6 +22,7 @@ use alloc::vec::Vec;
 use core::cmp::*;
 use core::ops::Deref;
 
+use super::super::super::auth::id::*;
 use super::super::super::common::*;
 use super::super::super::limits::*;
 use super::super::super::linux;
@@ -30,10 +31,12 @@ use super::super::super::usage::cpu::*;
 use super::super::super::usage::io::*;
 use super::super::kernel::posixtimer::*;
 use super::super::kernel::signal_handler::*;
+use super::super::kernel::cgroup::*;
 use super::super::kernel::timer::timer::Setting;
 use super::super::kernel::timer::timer::Timer;
 use super::super::kernel::waiter::queue::*;
 use super::super::kernel::waiter::waitgroup::*;
+use super::super::threadmgr::task_coredump::*;
 use super::super::threadmgr::task_exit::*;
 use super::super::SignalDef::*;
 
@@ -51,6 +54,11 @@ pub struct ThreadGroupInternal {
 
     pub eventQueue: Queue,
 
+    // pidfdQueue is notified when the last task in the thread group becomes a
+    // zombie, at which point pidfds referring to the thread group become
+    // readable.
+    pub pidfdQueue: Queue,
+
     // leader is the thread group's leader, which is the oldest task in the
     // thread group; usually the last task in the thread group to call
     // execve(), or if no such task exists then the first task in the thread
@@ -188,9 +196,16 @@ pub struct ThreadGroupInternal {
     // exitStatus is the thread group's exit status.
     //
     // While exiting is false, exitStatus is protected by the signal mutex.
-    // When exiting becomes true, exitStatus becomes immutable.
+    // When exiting becomes true, exitStatus becomes immutable, except for
+    // recording that a core dump was written.
     pub exitStatus: ExitStatus,
 
+    // coreState is set while a task of the thread group is writing a core
+    // dump. coreState is analogous to Linux's signal_struct.core_state.
+    //
+    // coreState is protected by the signal mutex.
+    pub coreState: Option<CoreState>,
+
     // terminationSignal is the signal that this thread group's leader will
     // send to its parent when it exits.
     //
@@ -278,6 +293,26 @@ pub struct ThreadGroupInternal {
 
     // root track whether this threadgroup is directly started by container provisioning
     pub root: bool,
+
+    // oomScoreAdj is added to the badness of the thread group when the OOM
+    // killer picks a victim, as set by /proc/[pid]/oom_score_adj.
+    // oomScoreAdjMin is the lowest value oomScoreAdj can be set to without
+    // CAP_SYS_RESOURCE. Both are inherited by child thread groups.
+    pub oomScoreAdj: i32,
+    pub oomScoreAdjMin: i32,
+
+    // loginuid is the audit login uid of the thread group, as set by
+    // /proc/[pid]/loginuid. It is unset (NO_ID) until then and is inherited
+    // by child thread groups.
+    pub loginuid: KUID,
+
+    // oomKilled is true once the OOM killer has chosen the thread group as
+    // its victim.
+    pub oomKilled: bool,
+
+    // cpuCgroup is the CPU controller of the container of the thread group.
+    pub cpuCgroup: Option<CPUCgroup>,
+
     pub timerMu: Arc<QMutex<()>>,
     // todo: handle tty
     //pub tty: Option<TTY>
@@ -18,6 +18,7 @@ use alloc::string::ToString;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::ops::Deref;
+use core::sync::atomic::Ordering;
 use spin::*;
 
 use super::super::super::auth::userns::*;
@@ -34,6 +35,7 @@ use super::super::SignalDef::*;
 use super::pid_namespace::*;
 use super::session::*;
 use super::task_exit::*;
+use super::task_ptrace::*;
 use super::task_sched::*;
 use super::thread::*;
 use super::thread_group::*;
@@ -47,7 +49,10 @@ pub struct TaskSetInternal {
 }
 
 impl TaskSetInternal {
-    pub fn AssignTids(&mut self, t: &Thread) -> Result<()> {
+    // AssignTids allocates a TID for t in its PID namespace and in each of its
+    // ancestors. If setTIDs is not empty, setTIDs[0] is the TID to use in t's
+    // own PID namespace, setTIDs[1] the TID to use in its parent, and so on.
+    pub fn AssignTids(&mut self, t: &Thread, setTIDs: &[ThreadID]) -> Result<()> {
         struct AllocatedTID {
             ns: PIDNamespace,
             tid: ThreadID,
@@ -59,7 +64,14 @@ impl TaskSetInternal {
         let mut allocatedTIDs: Vec<AllocatedTID> = Vec::new();
 
         loop {
-            let tid = match pidns.AllocateTID() {
+            let level = allocatedTIDs.len();
+            let res = if level < setTIDs.len() {
+                pidns.AllocateSpecificTID(setTIDs[level])
+            } else {
+                pidns.AllocateTID()
+            };
+
+            let tid = match res {
                 Err(e) => {
                     for a in allocatedTIDs {
                         let tns = a.ns.clone();
@@ -67,7 +79,7 @@ impl TaskSetInternal {
                         //error!("AssignTids remove tid {}", a.tid);
                         tns.lock().tids.remove(&t);
                         if tg.lock().leader.Upgrade().is_none() {
-                            pidns.lock().tgids.remove(&tg);
+                            tns.lock().tgids.remove(&tg);
                         }
                     }
 
@@ -170,6 +182,10 @@ impl TaskSet {
     pub fn NewTask(&self, cfg: &TaskConfig, fromContext: bool, kernel: &Kernel) -> Result<Thread> {
         let tg = cfg.ThreadGroup.clone();
 
+        // Enforce the pids limit of the container before taking the TaskSet
+        // lock, counting the tasks of the container needs it.
+        kernel.ChargePids(&cfg.ContainerID)?;
+
         let internal = ThreadInternal {
             id: 0,
             name: "".to_string(),
@@ -183,6 +199,7 @@ impl TaskSet {
             creds: cfg.Credentials.clone(),
             utsns: cfg.UTSNamespace.clone(),
             ipcns: cfg.IPCNamespace.clone(),
+            mountNS: cfg.MountNamespace.clone(),
             SignalQueue: Queue::default(),
             tg: tg.clone(),
             parent: cfg.Parent.clone(),
@@ -205,10 +222,14 @@ impl TaskSet {
             allowedCPUMask: cfg.AllowedCPUMask.Copy(),
             cpu: 0,
             niceness: cfg.Niceness,
+            schedPolicy: Sched::SCHED_NORMAL,
+            rtPriority: 0,
+            schedResetOnFork: false,
             numaPolicy: 0,
             numaNodeMask: 0,
             netns: false,
             parentDeathSignal: Signal::default(),
+            personality: 0,
             stop: None,
             stopCount: WaitGroup::default(),
             exitStatus: ExitStatus::default(),
@@ -221,6 +242,20 @@ impl TaskSet {
             containerID: cfg.ContainerID.to_string(),
             ioUsage: IO::default(),
             robust_list_head: 0,
+            ptraceTracer: None,
+            ptraceTracees: BTreeSet::new(),
+            ptraceSeized: false,
+            ptraceOpts: PtraceOptions::default(),
+            ptraceSyscallMode: PtraceSyscallMode::None,
+            ptraceSinglestep: false,
+            ptraceCode: 0,
+            ptraceSiginfo: None,
+            ptraceEventMsg: 0,
+            syscallFilters: Vec::new(),
+            containerSeccomp: kernel.ContainerSeccomp(&cfg.ContainerID),
+            perfEvents: Vec::new(),
+            cpuCgroupGeneration: 0,
+            cpuCgroupCharged: 0,
         };
 
         let t = Thread {
@@ -233,6 +268,9 @@ impl TaskSet {
             let ioUsage = t.lock().ioUsage.clone();
             task.thread = Some(t.clone());
             task.ioUsage = ioUsage;
+            task.traced.store(false, Ordering::Release);
+            task.hasSyscallFilters.store(t.HasSyscallFilters(), Ordering::Release);
+            task.context.SetCPUMask(cfg.AllowedCPUMask.Mask());
         }
 
         {
@@ -252,7 +290,7 @@ impl TaskSet {
                 }
             }
 
-            tslock.AssignTids(&t)?;
+            tslock.AssignTids(&t, &cfg.SetTIDs)?;
             tslock.IncrTaskCount();
         }
 
@@ -0,0 +1,49 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Flags accepted only by clone3(2), from include/uapi/linux/sched.h. They
+// don't fit in the 32-bit flags argument of clone(2).
+pub const CLONE_CLEAR_SIGHAND: u64 = 0x100000000;
+pub const CLONE_INTO_CGROUP: u64 = 0x200000000;
+
+// CSIGNAL is the mask of the exit signal in the clone(2) flags.
+pub const CSIGNAL: u64 = 0xff;
+
+// Sizes of the versions of struct clone_args, from include/uapi/linux/sched.h.
+pub const CLONE_ARGS_SIZE_VER0: usize = 64; // sizeof first published struct
+pub const CLONE_ARGS_SIZE_VER1: usize = 80; // sizeof second published struct
+pub const CLONE_ARGS_SIZE_VER2: usize = 88; // sizeof third published struct
+
+// MAX_PID_NS_LEVEL is the maximum nesting depth of PID namespaces, and so
+// the maximum number of entries in clone_args.set_tid. Source:
+// include/linux/pid_namespace.h
+pub const MAX_PID_NS_LEVEL: usize = 32;
+
+// CloneArgs is equivalent to struct clone_args. Source:
+// include/uapi/linux/sched.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CloneArgs {
+    pub Flags: u64,      // Flags bit mask.
+    pub Pidfd: u64,      // Where to store PID file descriptor (int *).
+    pub ChildTID: u64,   // Where to store child TID, in child's memory (pid_t *).
+    pub ParentTID: u64,  // Where to store child TID, in parent's memory (pid_t *).
+    pub ExitSignal: u64, // Signal to deliver to parent on child termination.
+    pub Stack: u64,      // Pointer to lowest byte of stack.
+    pub StackSize: u64,  // Size of stack.
+    pub TLS: u64,        // Location of new TLS.
+    pub SetTID: u64,     // Pointer to a pid_t array.
+    pub SetTIDSize: u64, // Number of elements in set_tid.
+    pub Cgroup: u64,     // File descriptor for target cgroup of child.
+}
@@ -0,0 +1,194 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// ELF definitions used to write core files, from include/uapi/linux/elf.h
+// and include/linux/elfcore.h.
+
+pub const ELFMAG: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+pub const ELFCLASS64: u8 = 2;
+pub const ELFDATA2LSB: u8 = 1;
+pub const EV_CURRENT: u8 = 1;
+pub const ELFOSABI_NONE: u8 = 0;
+
+pub const EI_CLASS: usize = 4;
+pub const EI_DATA: usize = 5;
+pub const EI_VERSION: usize = 6;
+pub const EI_OSABI: usize = 7;
+pub const EI_NIDENT: usize = 16;
+
+// e_type.
+pub const ET_CORE: u16 = 4;
+
+// e_machine.
+pub const EM_X86_64: u16 = 62;
+
+// p_type.
+pub const PT_LOAD: u32 = 1;
+pub const PT_NOTE: u32 = 4;
+
+// p_flags.
+pub const PF_X: u32 = 0x1;
+pub const PF_W: u32 = 0x2;
+pub const PF_R: u32 = 0x4;
+
+// Note types found in core files.
+pub const NT_PRSTATUS: u32 = 1;
+pub const NT_PRFPREG: u32 = 2;
+pub const NT_PRPSINFO: u32 = 3;
+pub const NT_AUXV: u32 = 6;
+pub const NT_SIGINFO: u32 = 0x53494749;
+pub const NT_FILE: u32 = 0x46494c45;
+pub const NT_X86_XSTATE: u32 = 0x202;
+
+// Note names. NT_X86_XSTATE and the other architecture specific register
+// sets use "LINUX", the generic notes use "CORE".
+pub const NOTE_NAME_CORE: &str = "CORE";
+pub const NOTE_NAME_LINUX: &str = "LINUX";
+
+// ElfGregset is elf_gregset_t, laid out as struct user_regs_struct.
+pub const ELF_NGREG: usize = 27;
+pub type ElfGregset = [u64; ELF_NGREG];
+
+// USER_I387_SIZE is the size of struct user_i387_struct, the descriptor of
+// NT_PRFPREG: the legacy FXSAVE area.
+pub const USER_I387_SIZE: usize = 512;
+
+// ELF_PRARGSZ is the size of elf_prpsinfo.pr_psargs.
+pub const ELF_PRARGSZ: usize = 80;
+
+// Elf64Ehdr is Elf64_Ehdr.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Ehdr {
+    pub Ident: [u8; EI_NIDENT],
+    pub Type: u16,
+    pub Machine: u16,
+    pub Version: u32,
+    pub Entry: u64,
+    pub Phoff: u64,
+    pub Shoff: u64,
+    pub Flags: u32,
+    pub Ehsize: u16,
+    pub Phentsize: u16,
+    pub Phnum: u16,
+    pub Shentsize: u16,
+    pub Shnum: u16,
+    pub Shstrndx: u16,
+}
+
+// Elf64Phdr is Elf64_Phdr.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Phdr {
+    pub Type: u32,
+    pub Flags: u32,
+    pub Offset: u64,
+    pub Vaddr: u64,
+    pub Paddr: u64,
+    pub Filesz: u64,
+    pub Memsz: u64,
+    pub Align: u64,
+}
+
+// Elf64Nhdr is Elf64_Nhdr. The name and the descriptor follow the header,
+// each padded to a 4 byte boundary.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Nhdr {
+    pub Namesz: u32,
+    pub Descsz: u32,
+    pub Type: u32,
+}
+
+// ElfSiginfo is struct elf_siginfo.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ElfSiginfo {
+    pub Signo: i32,
+    pub Code: i32,
+    pub Errno: i32,
+}
+
+// ElfTimeval is struct __kernel_old_timeval.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ElfTimeval {
+    pub Sec: i64,
+    pub Usec: i64,
+}
+
+// ElfPrstatus is struct elf_prstatus for x86_64, the descriptor of
+// NT_PRSTATUS.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ElfPrstatus {
+    pub Info: ElfSiginfo,
+    pub Cursig: i16,
+    pub _pad0: u16,
+    pub Sigpend: u64,
+    pub Sighold: u64,
+    pub Pid: i32,
+    pub Ppid: i32,
+    pub Pgrp: i32,
+    pub Sid: i32,
+    pub Utime: ElfTimeval,
+    pub Stime: ElfTimeval,
+    pub Cutime: ElfTimeval,
+    pub Cstime: ElfTimeval,
+    pub Reg: ElfGregset,
+    pub Fpvalid: i32,
+    pub _pad1: u32,
+}
+
+// ElfPrpsinfo is struct elf_prpsinfo for x86_64, the descriptor of
+// NT_PRPSINFO.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ElfPrpsinfo {
+    pub State: u8,
+    pub Sname: u8,
+    pub Zomb: u8,
+    pub Nice: i8,
+    pub _pad0: u32,
+    pub Flag: u64,
+    pub Uid: u32,
+    pub Gid: u32,
+    pub Pid: i32,
+    pub Ppid: i32,
+    pub Pgrp: i32,
+    pub Sid: i32,
+    pub Fname: [u8; 16],
+    pub Psargs: [u8; ELF_PRARGSZ],
+}
+
+impl Default for ElfPrpsinfo {
+    fn default() -> Self {
+        return Self {
+            State: 0,
+            Sname: 0,
+            Zomb: 0,
+            Nice: 0,
+            _pad0: 0,
+            Flag: 0,
+            Uid: 0,
+            Gid: 0,
+            Pid: 0,
+            Ppid: 0,
+            Pgrp: 0,
+            Sid: 0,
+            Fname: [0; 16],
+            Psargs: [0; ELF_PRARGSZ],
+        };
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Flags for io_uring_setup(2)'s io_uring_params.flags, from
+// include/uapi/linux/io_uring.h.
+pub const IORING_SETUP_IOPOLL: u32 = 1 << 0; // io_context is polled
+pub const IORING_SETUP_SQPOLL: u32 = 1 << 1; // SQ poll thread
+pub const IORING_SETUP_SQ_AFF: u32 = 1 << 2; // sq_thread_cpu is valid
+pub const IORING_SETUP_CQSIZE: u32 = 1 << 3; // app defines CQ size
+pub const IORING_SETUP_CLAMP: u32 = 1 << 4; // clamp SQ/CQ ring sizes
+pub const IORING_SETUP_ATTACH_WQ: u32 = 1 << 5; // attach to existing wq
+pub const IORING_SETUP_R_DISABLED: u32 = 1 << 6; // start with ring disabled
+
+// IORING_MAX_ENTRIES and IORING_MAX_CQ_ENTRIES bound the ring sizes. Linux
+// allows 32768 SQ entries; we stop at 4096 so that every region of the ring
+// fits in a single huge page chunk of the backing memfd.
+pub const IORING_MAX_ENTRIES: u32 = 4096;
+pub const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+
+// Magic offsets for the application to mmap the data it needs.
+pub const IORING_OFF_SQ_RING: u64 = 0;
+pub const IORING_OFF_CQ_RING: u64 = 0x8000000;
+pub const IORING_OFF_SQES: u64 = 0x10000000;
+
+// sq_ring->flags
+pub const IORING_SQ_NEED_WAKEUP: u32 = 1 << 0; // needs io_uring_enter wakeup
+pub const IORING_SQ_CQ_OVERFLOW: u32 = 1 << 1; // CQ ring is overflown
+
+// cq_ring->flags
+pub const IORING_CQ_EVENTFD_DISABLED: u32 = 1 << 0; // disable eventfd notifications
+
+// io_uring_enter(2) flags.
+pub const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+pub const IORING_ENTER_SQ_WAKEUP: u32 = 1 << 1;
+pub const IORING_ENTER_SQ_WAIT: u32 = 1 << 2;
+pub const IORING_ENTER_EXT_ARG: u32 = 1 << 3;
+
+// io_uring_params->features flags.
+pub const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+pub const IORING_FEAT_NODROP: u32 = 1 << 1;
+pub const IORING_FEAT_SUBMIT_STABLE: u32 = 1 << 2;
+pub const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3;
+pub const IORING_FEAT_CUR_PERSONALITY: u32 = 1 << 4;
+pub const IORING_FEAT_FAST_POLL: u32 = 1 << 5;
+pub const IORING_FEAT_POLL_32BITS: u32 = 1 << 6;
+
+// sqe->flags
+pub const IOSQE_FIXED_FILE: u8 = 1 << 0; // use fixed fileset
+pub const IOSQE_IO_DRAIN: u8 = 1 << 1; // issue after inflight IO
+pub const IOSQE_IO_LINK: u8 = 1 << 2; // links next sqe
+pub const IOSQE_IO_HARDLINK: u8 = 1 << 3; // like LINK, but stronger
+pub const IOSQE_ASYNC: u8 = 1 << 4; // always go async
+pub const IOSQE_BUFFER_SELECT: u8 = 1 << 5; // select buffer from sqe->buf_group
+
+// Opcodes for io_uring_sqe.opcode.
+pub const IORING_OP_NOP: u8 = 0;
+pub const IORING_OP_READV: u8 = 1;
+pub const IORING_OP_WRITEV: u8 = 2;
+pub const IORING_OP_FSYNC: u8 = 3;
+pub const IORING_OP_READ_FIXED: u8 = 4;
+pub const IORING_OP_WRITE_FIXED: u8 = 5;
+pub const IORING_OP_POLL_ADD: u8 = 6;
+pub const IORING_OP_POLL_REMOVE: u8 = 7;
+pub const IORING_OP_SYNC_FILE_RANGE: u8 = 8;
+pub const IORING_OP_SENDMSG: u8 = 9;
+pub const IORING_OP_RECVMSG: u8 = 10;
+pub const IORING_OP_TIMEOUT: u8 = 11;
+pub const IORING_OP_TIMEOUT_REMOVE: u8 = 12;
+pub const IORING_OP_ACCEPT: u8 = 13;
+pub const IORING_OP_ASYNC_CANCEL: u8 = 14;
+pub const IORING_OP_LINK_TIMEOUT: u8 = 15;
+pub const IORING_OP_CONNECT: u8 = 16;
+pub const IORING_OP_FALLOCATE: u8 = 17;
+pub const IORING_OP_OPENAT: u8 = 18;
+pub const IORING_OP_CLOSE: u8 = 19;
+pub const IORING_OP_FILES_UPDATE: u8 = 20;
+pub const IORING_OP_STATX: u8 = 21;
+pub const IORING_OP_READ: u8 = 22;
+pub const IORING_OP_WRITE: u8 = 23;
+pub const IORING_OP_FADVISE: u8 = 24;
+pub const IORING_OP_MADVISE: u8 = 25;
+pub const IORING_OP_SEND: u8 = 26;
+pub const IORING_OP_RECV: u8 = 27;
+pub const IORING_OP_LAST: u8 = 28;
+
+// sqe->fsync_flags
+pub const IORING_FSYNC_DATASYNC: u32 = 1 << 0;
+
+// sqe->timeout_flags
+pub const IORING_TIMEOUT_ABS: u32 = 1 << 0;
+
+// io_uring_register(2) opcodes and arguments.
+pub const IORING_REGISTER_BUFFERS: u32 = 0;
+pub const IORING_UNREGISTER_BUFFERS: u32 = 1;
+pub const IORING_REGISTER_FILES: u32 = 2;
+pub const IORING_UNREGISTER_FILES: u32 = 3;
+pub const IORING_REGISTER_EVENTFD: u32 = 4;
+pub const IORING_UNREGISTER_EVENTFD: u32 = 5;
+pub const IORING_REGISTER_FILES_UPDATE: u32 = 6;
+pub const IORING_REGISTER_EVENTFD_ASYNC: u32 = 7;
+pub const IORING_REGISTER_PROBE: u32 = 8;
+
+// io_uring_probe_op->flags
+pub const IO_URING_OP_SUPPORTED: u16 = 1 << 0;
+
+// Maximum number of ops io_uring_register(IORING_REGISTER_PROBE) fills in.
+pub const IO_URING_PROBE_MAX_OPS: usize = 256;
+
+// IoSqringOffsets is equivalent to struct io_sqring_offsets. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoSqringOffsets {
+    pub Head: u32,
+    pub Tail: u32,
+    pub RingMask: u32,
+    pub RingEntries: u32,
+    pub Flags: u32,
+    pub Dropped: u32,
+    pub Array: u32,
+    pub Resv1: u32,
+    pub Resv2: u64,
+}
+
+// IoCqringOffsets is equivalent to struct io_cqring_offsets. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoCqringOffsets {
+    pub Head: u32,
+    pub Tail: u32,
+    pub RingMask: u32,
+    pub RingEntries: u32,
+    pub Overflow: u32,
+    pub Cqes: u32,
+    pub Flags: u32,
+    pub Resv1: u32,
+    pub Resv2: u64,
+}
+
+// IoUringParams is equivalent to struct io_uring_params. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoUringParams {
+    pub SqEntries: u32,
+    pub CqEntries: u32,
+    pub Flags: u32,
+    pub SqThreadCpu: u32,
+    pub SqThreadIdle: u32,
+    pub Features: u32,
+    pub WqFd: u32,
+    pub Resv: [u32; 3],
+    pub SqOff: IoSqringOffsets,
+    pub CqOff: IoCqringOffsets,
+}
+
+// IoUringSqe is equivalent to struct io_uring_sqe. The unions of the C
+// struct are flattened into the member that shares their storage. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoUringSqe {
+    pub Opcode: u8,       // type of operation for this sqe
+    pub Flags: u8,        // IOSQE_ flags
+    pub Ioprio: u16,      // ioprio for the request
+    pub Fd: i32,          // file descriptor to do IO on
+    pub Off: u64,         // offset into file, or addr2
+    pub Addr: u64,        // pointer to buffer or iovecs
+    pub Len: u32,         // buffer size or number of iovecs
+    pub OpFlags: u32,     // rw_flags, fsync_flags, poll32_events, msg_flags, ...
+    pub UserData: u64,    // data to be passed back at completion time
+    pub BufIndex: u16,    // index into fixed buffers, or buf_group
+    pub Personality: u16, // personality to use, if used
+    pub SpliceFdIn: i32,
+    pub Pad2: [u64; 2],
+}
+
+// IoUringCqe is equivalent to struct io_uring_cqe. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoUringCqe {
+    pub UserData: u64, // sqe->data submission passed back
+    pub Res: i32,      // result code for this event
+    pub Flags: u32,
+}
+
+// IoUringProbe is equivalent to the fixed part of struct io_uring_probe;
+// it is followed by ops_len IoUringProbeOp entries. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoUringProbe {
+    pub LastOp: u8, // last opcode supported
+    pub OpsLen: u8, // length of ops[] array below
+    pub Resv: u16,
+    pub Resv2: [u32; 3],
+}
+
+// IoUringProbeOp is equivalent to struct io_uring_probe_op. Source:
+// include/uapi/linux/io_uring.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct IoUringProbeOp {
+    pub Op: u8,
+    pub Resv: u8,
+    pub Flags: u16, // IO_URING_OP_* flags
+    pub Resv2: u32,
+}
@@ -12,19 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod clone;
+pub mod elf;
 pub mod fcntl;
 pub mod futex;
 pub mod inotify;
+pub mod io_uring;
 pub mod ipc;
 pub mod limits;
 pub mod membarrier;
+pub mod mqueue;
 pub mod netdevice;
+pub mod openat2;
+pub mod perf_event;
 pub mod rusage;
+pub mod seccomp;
 pub mod sem;
 pub mod shm;
 pub mod signal;
 pub mod socket;
 pub mod time;
+pub mod userfaultfd;
 pub mod msgqueue;
 
 pub type TimeID = i32;
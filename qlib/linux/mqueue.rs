@@ -0,0 +1,51 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Default values for POSIX message queues. Source:
+// include/linux/ipc_namespace.h
+pub const DFLT_QUEUESMAX: usize = 256;
+pub const MIN_MSGMAX: i64 = 1;
+pub const DFLT_MSG: i64 = 10;
+pub const DFLT_MSGMAX: i64 = 10;
+pub const HARD_MSGMAX: i64 = 65536;
+pub const MIN_MSGSIZEMAX: i64 = 128;
+pub const DFLT_MSGSIZE: i64 = 8192;
+pub const DFLT_MSGSIZEMAX: i64 = 8192;
+pub const HARD_MSGSIZEMAX: i64 = 16 * 1024 * 1024;
+
+// Maximum values for a message queue. Source: include/uapi/linux/mqueue.h
+pub const MQ_PRIO_MAX: u32 = 32768;
+pub const MQ_BYTES_MAX: u64 = 819200;
+
+// Codes used by SIGEV_THREAD notifications, stored in the last byte of the
+// notification cookie. Source: include/uapi/linux/mqueue.h
+pub const NOTIFY_NONE: u8 = 0;
+pub const NOTIFY_WOKENUP: u8 = 1;
+pub const NOTIFY_REMOVED: u8 = 2;
+
+// NOTIFY_COOKIE_LEN is the length of the cookie passed with SIGEV_THREAD
+// notifications.
+pub const NOTIFY_COOKIE_LEN: usize = 32;
+
+// MqAttr is equivalent to struct mq_attr. Source:
+// include/uapi/linux/mqueue.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MqAttr {
+    pub MqFlags: i64,   // Message queue flags.
+    pub MqMaxmsg: i64,  // Maximum number of messages.
+    pub MqMsgsize: i64, // Maximum message size.
+    pub MqCurmsgs: i64, // Number of messages currently queued.
+    pub Reserved: [i64; 4],
+}
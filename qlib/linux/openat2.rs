@@ -0,0 +1,45 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Flags for openat2(2)'s open_how.resolve, from include/uapi/linux/openat2.h.
+pub const RESOLVE_NO_XDEV: u64 = 0x01; // Block mount-point crossings (includes bind-mounts).
+pub const RESOLVE_NO_MAGICLINKS: u64 = 0x02; // Block traversal through procfs-style "magic-links".
+pub const RESOLVE_NO_SYMLINKS: u64 = 0x04; // Block traversal through all symlinks (implies RESOLVE_NO_MAGICLINKS).
+pub const RESOLVE_BENEATH: u64 = 0x08; // Block "lexical" trickery like "..", symlinks, and absolute paths which escape the dirfd.
+pub const RESOLVE_IN_ROOT: u64 = 0x10; // Make all jumps to "/" and ".." be scoped inside the dirfd (similar to chroot(2)).
+pub const RESOLVE_CACHED: u64 = 0x20; // Only complete if resolution can be completed through cached lookup.
+
+pub const RESOLVE_ALL: u64 = RESOLVE_NO_XDEV
+    | RESOLVE_NO_MAGICLINKS
+    | RESOLVE_NO_SYMLINKS
+    | RESOLVE_BENEATH
+    | RESOLVE_IN_ROOT
+    | RESOLVE_CACHED;
+
+// RESOLVE_SCOPED are the flags which restrict the lookup to the subtree of
+// the dirfd.
+pub const RESOLVE_SCOPED: u64 = RESOLVE_BENEATH | RESOLVE_IN_ROOT;
+
+// Sizes of the versions of struct open_how, from include/uapi/linux/openat2.h.
+pub const OPEN_HOW_SIZE_VER0: usize = 24; // sizeof first published struct
+
+// OpenHow is equivalent to struct open_how. Source:
+// include/uapi/linux/openat2.h
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct OpenHow {
+    pub Flags: u64,   // O_* flags.
+    pub Mode: u64,    // O_CREAT/O_TMPFILE file mode.
+    pub Resolve: u64, // RESOLVE_* flags.
+}
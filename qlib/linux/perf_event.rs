@@ -0,0 +1,146 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Event types for perf_event_attr.type, from include/uapi/linux/perf_event.h.
+pub const PERF_TYPE_HARDWARE: u32 = 0;
+pub const PERF_TYPE_SOFTWARE: u32 = 1;
+pub const PERF_TYPE_TRACEPOINT: u32 = 2;
+pub const PERF_TYPE_HW_CACHE: u32 = 3;
+pub const PERF_TYPE_RAW: u32 = 4;
+pub const PERF_TYPE_BREAKPOINT: u32 = 5;
+
+// Software event ids for perf_event_attr.config when type is
+// PERF_TYPE_SOFTWARE.
+pub const PERF_COUNT_SW_CPU_CLOCK: u64 = 0;
+pub const PERF_COUNT_SW_TASK_CLOCK: u64 = 1;
+pub const PERF_COUNT_SW_PAGE_FAULTS: u64 = 2;
+pub const PERF_COUNT_SW_CONTEXT_SWITCHES: u64 = 3;
+pub const PERF_COUNT_SW_CPU_MIGRATIONS: u64 = 4;
+pub const PERF_COUNT_SW_PAGE_FAULTS_MIN: u64 = 5;
+pub const PERF_COUNT_SW_PAGE_FAULTS_MAJ: u64 = 6;
+pub const PERF_COUNT_SW_ALIGNMENT_FAULTS: u64 = 7;
+pub const PERF_COUNT_SW_EMULATION_FAULTS: u64 = 8;
+pub const PERF_COUNT_SW_DUMMY: u64 = 9;
+pub const PERF_COUNT_SW_BPF_OUTPUT: u64 = 10;
+pub const PERF_COUNT_SW_CGROUP_SWITCHES: u64 = 11;
+
+// Bits for perf_event_attr.read_format.
+pub const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+pub const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+pub const PERF_FORMAT_ID: u64 = 1 << 2;
+pub const PERF_FORMAT_GROUP: u64 = 1 << 3;
+pub const PERF_FORMAT_LOST: u64 = 1 << 4;
+
+pub const PERF_FORMAT_ALL: u64 = PERF_FORMAT_TOTAL_TIME_ENABLED
+    | PERF_FORMAT_TOTAL_TIME_RUNNING
+    | PERF_FORMAT_ID
+    | PERF_FORMAT_GROUP
+    | PERF_FORMAT_LOST;
+
+// Bits of the perf_event_attr bitfield that follows read_format.
+pub const PERF_ATTR_FLAG_DISABLED: u64 = 1 << 0;
+pub const PERF_ATTR_FLAG_INHERIT: u64 = 1 << 1;
+pub const PERF_ATTR_FLAG_PINNED: u64 = 1 << 2;
+pub const PERF_ATTR_FLAG_EXCLUSIVE: u64 = 1 << 3;
+pub const PERF_ATTR_FLAG_EXCLUDE_USER: u64 = 1 << 4;
+pub const PERF_ATTR_FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+pub const PERF_ATTR_FLAG_EXCLUDE_HV: u64 = 1 << 6;
+pub const PERF_ATTR_FLAG_EXCLUDE_IDLE: u64 = 1 << 7;
+pub const PERF_ATTR_FLAG_FREQ: u64 = 1 << 10;
+pub const PERF_ATTR_FLAG_INHERIT_STAT: u64 = 1 << 11;
+pub const PERF_ATTR_FLAG_ENABLE_ON_EXEC: u64 = 1 << 12;
+pub const PERF_ATTR_FLAG_INHERIT_THREAD: u64 = 1 << 35;
+pub const PERF_ATTR_FLAG_REMOVE_ON_EXEC: u64 = 1 << 36;
+pub const PERF_ATTR_FLAG_SIGTRAP: u64 = 1 << 37;
+
+// Flags for perf_event_open(2).
+pub const PERF_FLAG_FD_NO_GROUP: u64 = 1 << 0;
+pub const PERF_FLAG_FD_OUTPUT: u64 = 1 << 1;
+pub const PERF_FLAG_PID_CGROUP: u64 = 1 << 2;
+pub const PERF_FLAG_FD_CLOEXEC: u64 = 1 << 3;
+
+pub const PERF_FLAG_ALL: u64 =
+    PERF_FLAG_FD_NO_GROUP | PERF_FLAG_FD_OUTPUT | PERF_FLAG_PID_CGROUP | PERF_FLAG_FD_CLOEXEC;
+
+// Ioctls for perf event file descriptors.
+pub const PERF_EVENT_IOC_ENABLE: u64 = 0x2400;
+pub const PERF_EVENT_IOC_DISABLE: u64 = 0x2401;
+pub const PERF_EVENT_IOC_REFRESH: u64 = 0x2402;
+pub const PERF_EVENT_IOC_RESET: u64 = 0x2403;
+pub const PERF_EVENT_IOC_PERIOD: u64 = 0x40082404;
+pub const PERF_EVENT_IOC_SET_OUTPUT: u64 = 0x2405;
+pub const PERF_EVENT_IOC_SET_FILTER: u64 = 0x40082406;
+pub const PERF_EVENT_IOC_ID: u64 = 0x80082407;
+pub const PERF_EVENT_IOC_SET_BPF: u64 = 0x40042408;
+pub const PERF_EVENT_IOC_PAUSE_OUTPUT: u64 = 0x40042409;
+pub const PERF_EVENT_IOC_QUERY_BPF: u64 = 0xc008240a;
+pub const PERF_EVENT_IOC_MODIFY_ATTRIBUTES: u64 = 0x4008240b;
+
+// PERF_IOC_FLAG_GROUP applies an ioctl to every event in the group.
+pub const PERF_IOC_FLAG_GROUP: u64 = 1 << 0;
+
+// Sizes of the versions of struct perf_event_attr.
+pub const PERF_ATTR_SIZE_VER0: usize = 64; // sizeof first published struct
+
+// PerfEventAttr is equivalent to struct perf_event_attr (up to
+// PERF_ATTR_SIZE_VER8). Unions are represented by their first member.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PerfEventAttr {
+    pub Type: u32,
+    pub Size: u32,
+    pub Config: u64,
+    pub SamplePeriod: u64, // or sample_freq if PERF_ATTR_FLAG_FREQ is set.
+    pub SampleType: u64,
+    pub ReadFormat: u64,
+    pub Flags: u64,        // bitfield, see PERF_ATTR_FLAG_*.
+    pub WakeupEvents: u32, // or wakeup_watermark.
+    pub BpType: u32,
+    pub Config1: u64,
+    pub Config2: u64,
+    pub BranchSampleType: u64,
+    pub SampleRegsUser: u64,
+    pub SampleStackUser: u32,
+    pub ClockId: i32,
+    pub SampleRegsIntr: u64,
+    pub AuxWatermark: u32,
+    pub SampleMaxStack: u16,
+    pub Reserved2: u16,
+    pub AuxSampleSize: u32,
+    pub Reserved3: u32,
+    pub SigData: u64,
+    pub Config3: u64,
+}
+
+impl PerfEventAttr {
+    pub fn Disabled(&self) -> bool {
+        return self.Flags & PERF_ATTR_FLAG_DISABLED != 0;
+    }
+
+    pub fn Inherit(&self) -> bool {
+        return self.Flags & PERF_ATTR_FLAG_INHERIT != 0;
+    }
+
+    pub fn ExcludeUser(&self) -> bool {
+        return self.Flags & PERF_ATTR_FLAG_EXCLUDE_USER != 0;
+    }
+
+    pub fn ExcludeKernel(&self) -> bool {
+        return self.Flags & PERF_ATTR_FLAG_EXCLUDE_KERNEL != 0;
+    }
+
+    pub fn EnableOnExec(&self) -> bool {
+        return self.Flags & PERF_ATTR_FLAG_ENABLE_ON_EXEC != 0;
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Seccomp constants taken from <linux/seccomp.h>.
+pub const SECCOMP_MODE_NONE: i32 = 0;
+pub const SECCOMP_MODE_FILTER: i32 = 2;
+
+pub const SECCOMP_RET_ACTION_FULL: u32 = 0xffff0000;
+pub const SECCOMP_RET_ACTION: u32 = 0x7fff0000;
+pub const SECCOMP_RET_DATA: u32 = 0x0000ffff;
+
+pub const SECCOMP_SET_MODE_FILTER: u64 = 1;
+pub const SECCOMP_GET_ACTION_AVAIL: u64 = 2;
+
+pub const SECCOMP_FILTER_FLAG_TSYNC: u64 = 1;
+
+// Seccomp filter return actions, ordered from the least to the most
+// permissive when masked with SECCOMP_RET_ACTION_FULL (as signed values).
+pub const SECCOMP_RET_KILL_PROCESS: u32 = 0x80000000;
+pub const SECCOMP_RET_KILL_THREAD: u32 = 0x00000000;
+pub const SECCOMP_RET_TRAP: u32 = 0x00030000;
+pub const SECCOMP_RET_ERRNO: u32 = 0x00050000;
+pub const SECCOMP_RET_TRACE: u32 = 0x7ff00000;
+pub const SECCOMP_RET_LOG: u32 = 0x7ffc0000;
+pub const SECCOMP_RET_ALLOW: u32 = 0x7fff0000;
+
+// AUDIT_ARCH_X86_64 identifies AMD64 in struct seccomp_data.arch, from
+// <linux/audit.h>.
+pub const AUDIT_ARCH_X86_64: u32 = 0xc000003e;
+
+// SeccompData is equivalent to struct seccomp_data, which contains the data
+// evaluated by seccomp filters.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SeccompData {
+    // Nr is the system call number.
+    pub Nr: i32,
+
+    // Arch is an AUDIT_ARCH_* value indicating the system call convention.
+    pub Arch: u32,
+
+    // InstructionPointer is the value of the instruction pointer at the time
+    // of the system call.
+    pub InstructionPointer: u64,
+
+    // Args contains the first 6 system call arguments.
+    pub Args: [u64; 6],
+}
+
+impl SeccompData {
+    // AsBytes returns the seccomp_data in the form evaluated by filters.
+    pub fn AsBytes(&self) -> &[u8] {
+        let ptr = self as *const _ as *const u8;
+        return unsafe { core::slice::from_raw_parts(ptr, core::mem::size_of::<Self>()) };
+    }
+}
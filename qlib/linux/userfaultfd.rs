@@ -0,0 +1,149 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// UFFD_API is the API version of userfaultfd(2), from
+// include/uapi/linux/userfaultfd.h.
+pub const UFFD_API: u64 = 0xAA;
+
+// Flags for userfaultfd(2).
+pub const UFFD_USER_MODE_ONLY: i32 = 1;
+
+// uffd_msg.event.
+pub const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+pub const UFFD_EVENT_FORK: u8 = 0x13;
+pub const UFFD_EVENT_REMAP: u8 = 0x14;
+pub const UFFD_EVENT_REMOVE: u8 = 0x15;
+pub const UFFD_EVENT_UNMAP: u8 = 0x16;
+
+// uffd_msg.arg.pagefault.flags.
+pub const UFFD_PAGEFAULT_FLAG_WRITE: u64 = 1 << 0;
+pub const UFFD_PAGEFAULT_FLAG_WP: u64 = 1 << 1;
+pub const UFFD_PAGEFAULT_FLAG_MINOR: u64 = 1 << 2;
+
+// uffdio_api.features.
+pub const UFFD_FEATURE_PAGEFAULT_FLAG_WP: u64 = 1 << 0;
+pub const UFFD_FEATURE_EVENT_FORK: u64 = 1 << 1;
+pub const UFFD_FEATURE_EVENT_REMAP: u64 = 1 << 2;
+pub const UFFD_FEATURE_EVENT_REMOVE: u64 = 1 << 3;
+pub const UFFD_FEATURE_MISSING_HUGETLBFS: u64 = 1 << 4;
+pub const UFFD_FEATURE_MISSING_SHMEM: u64 = 1 << 5;
+pub const UFFD_FEATURE_EVENT_UNMAP: u64 = 1 << 6;
+pub const UFFD_FEATURE_SIGBUS: u64 = 1 << 7;
+pub const UFFD_FEATURE_THREAD_ID: u64 = 1 << 8;
+
+// UFFD_API_FEATURES is the set of features we support: missing faults on
+// private anonymous memory, and the non-cooperative fork, mremap and munmap
+// events.
+pub const UFFD_API_FEATURES: u64 = UFFD_FEATURE_EVENT_FORK
+    | UFFD_FEATURE_EVENT_REMAP
+    | UFFD_FEATURE_EVENT_UNMAP
+    | UFFD_FEATURE_THREAD_ID;
+
+// ioctl command numbers, before encoding.
+pub const _UFFDIO_REGISTER: u64 = 0x00;
+pub const _UFFDIO_UNREGISTER: u64 = 0x01;
+pub const _UFFDIO_WAKE: u64 = 0x02;
+pub const _UFFDIO_COPY: u64 = 0x03;
+pub const _UFFDIO_ZEROPAGE: u64 = 0x04;
+pub const _UFFDIO_API: u64 = 0x3F;
+
+// UFFD_API_IOCTLS are the ioctls supported on the userfaultfd itself.
+pub const UFFD_API_IOCTLS: u64 = 1 << _UFFDIO_REGISTER | 1 << _UFFDIO_UNREGISTER | 1 << _UFFDIO_API;
+
+// UFFD_API_RANGE_IOCTLS are the ioctls supported on registered ranges.
+pub const UFFD_API_RANGE_IOCTLS: u64 =
+    1 << _UFFDIO_WAKE | 1 << _UFFDIO_COPY | 1 << _UFFDIO_ZEROPAGE;
+
+// ioctls for userfaultfd(2).
+pub const UFFDIO_API: u64 = 0xc018aa3f;
+pub const UFFDIO_REGISTER: u64 = 0xc020aa00;
+pub const UFFDIO_UNREGISTER: u64 = 0x8010aa01;
+pub const UFFDIO_WAKE: u64 = 0x8010aa02;
+pub const UFFDIO_COPY: u64 = 0xc028aa03;
+pub const UFFDIO_ZEROPAGE: u64 = 0xc020aa04;
+
+// uffdio_register.mode.
+pub const UFFDIO_REGISTER_MODE_MISSING: u64 = 1 << 0;
+pub const UFFDIO_REGISTER_MODE_WP: u64 = 1 << 1;
+pub const UFFDIO_REGISTER_MODE_MINOR: u64 = 1 << 2;
+
+// uffdio_copy.mode.
+pub const UFFDIO_COPY_MODE_DONTWAKE: u64 = 1 << 0;
+pub const UFFDIO_COPY_MODE_WP: u64 = 1 << 1;
+
+// uffdio_zeropage.mode.
+pub const UFFDIO_ZEROPAGE_MODE_DONTWAKE: u64 = 1 << 0;
+
+// UffdMsg is equivalent to struct uffd_msg. Arg holds the event specific
+// union:
+//
+// - UFFD_EVENT_PAGEFAULT: flags, address, ptid.
+// - UFFD_EVENT_FORK: ufd.
+// - UFFD_EVENT_REMAP: from, to, len.
+// - UFFD_EVENT_REMOVE/UFFD_EVENT_UNMAP: start, end.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UffdMsg {
+    pub Event: u8,
+    pub Reserved1: u8,
+    pub Reserved2: u16,
+    pub Reserved3: u32,
+    pub Arg: [u64; 3],
+}
+
+// UffdioApi is equivalent to struct uffdio_api.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UffdioApi {
+    pub Api: u64,
+    pub Features: u64,
+    pub Ioctls: u64,
+}
+
+// UffdioRange is equivalent to struct uffdio_range.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UffdioRange {
+    pub Start: u64,
+    pub Len: u64,
+}
+
+// UffdioRegister is equivalent to struct uffdio_register.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UffdioRegister {
+    pub Range: UffdioRange,
+    pub Mode: u64,
+    pub Ioctls: u64,
+}
+
+// UffdioCopy is equivalent to struct uffdio_copy.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UffdioCopy {
+    pub Dst: u64,
+    pub Src: u64,
+    pub Len: u64,
+    pub Mode: u64,
+    pub Copy: i64,
+}
+
+// UffdioZeropage is equivalent to struct uffdio_zeropage.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UffdioZeropage {
+    pub Range: UffdioRange,
+    pub Mode: u64,
+    pub Zeropage: i64,
+}
@@ -149,6 +149,19 @@ impl Sched {
     // reverted back to SCHED_NORMAL on fork.
     pub const SCHED_RESET_ON_FORK :i32 = 0x40000000;
 
+    // the sched_attr.sched_flags of sched_setattr(2).
+    pub const SCHED_FLAG_RESET_ON_FORK :u64 = 0x01;
+    pub const SCHED_FLAG_RECLAIM       :u64 = 0x02;
+    pub const SCHED_FLAG_DL_OVERRUN    :u64 = 0x04;
+    pub const SCHED_FLAG_KEEP_POLICY   :u64 = 0x08;
+    pub const SCHED_FLAG_KEEP_PARAMS   :u64 = 0x10;
+    pub const SCHED_FLAG_UTIL_CLAMP_MIN :u64 = 0x20;
+    pub const SCHED_FLAG_UTIL_CLAMP_MAX :u64 = 0x40;
+
+    // the sizes of the versions of struct sched_attr.
+    pub const SCHED_ATTR_SIZE_VER0 :usize = 48;
+    pub const SCHED_ATTR_SIZE_VER1 :usize = 56;
+
     pub const PRIO_PGRP    :i32 = 0x1;
     pub const PRIO_PROCESS :i32 = 0x0;
     pub const PRIO_USER    :i32 = 0x2;
@@ -563,8 +576,11 @@ pub struct FSMagic {}
 
 impl FSMagic {
     pub const ANON_INODE_FS_MAGIC: u64 = 0x09041934;
+    pub const CGROUP_SUPER_MAGIC: u64 = 0x27e0eb;
+    pub const CGROUP2_SUPER_MAGIC: u64 = 0x63677270;
     pub const DEVPTS_SUPER_MAGIC: u64 = 0x00001cd1;
     pub const EXT_SUPER_MAGIC: u64 = 0xef53;
+    pub const MQUEUE_MAGIC: u64 = 0x19800202;
     pub const OVERLAYFS_SUPER_MAGIC: u64 = 0x794c7630;
     pub const PIPEFS_MAGIC: u64 = 0x50495045;
     pub const PROC_SUPER_MAGIC: u64 = 0x9fa0;
@@ -690,6 +706,9 @@ impl ATType {
 
     // Constants for fstatat(2)
     pub const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+
+    // Constant for faccessat2(2)
+    pub const AT_EACCESS: i32 = 0x200;
 }
 
 // Values for linux_dirent64.d_type.
@@ -1456,6 +1475,7 @@ impl LibcConst {
     pub const MNT_DETACH: u64 = 0x2;
     pub const MNT_EXPIRE: u64 = 0x4;
     pub const MNT_FORCE: u64 = 0x1;
+    pub const UMOUNT_NOFOLLOW: u64 = 0x8;
     pub const MSG_CMSG_CLOEXEC: u64 = 0x40000000;
     pub const MSG_CONFIRM: u64 = 0x800;
     pub const MSG_CTRUNC: u64 = 0x8;
@@ -1668,6 +1688,8 @@ impl LibcConst {
     pub const PTRACE_EVENT_EXEC: u64 = 0x4;
     pub const PTRACE_EVENT_EXIT: u64 = 0x6;
     pub const PTRACE_EVENT_FORK: u64 = 0x1;
+    pub const PTRACE_EVENT_SECCOMP: u64 = 0x7;
+    pub const PTRACE_EVENT_STOP: u64 = 0x80;
     pub const PTRACE_EVENT_VFORK: u64 = 0x2;
     pub const PTRACE_EVENT_VFORK_DONE: u64 = 0x5;
     pub const PTRACE_GETEVENTMSG: u64 = 0x4201;
@@ -1676,14 +1698,19 @@ impl LibcConst {
     pub const PTRACE_GETREGS: u64 = 0xc;
     pub const PTRACE_GETREGSET: u64 = 0x4204;
     pub const PTRACE_GETSIGINFO: u64 = 0x4202;
+    pub const PTRACE_GETSIGMASK: u64 = 0x420a;
     pub const PTRACE_GET_THREAD_AREA: u64 = 0x19;
+    pub const PTRACE_INTERRUPT: u64 = 0x4207;
     pub const PTRACE_KILL: u64 = 0x8;
+    pub const PTRACE_LISTEN: u64 = 0x4208;
     pub const PTRACE_OLDSETOPTIONS: u64 = 0x15;
+    pub const PTRACE_O_EXITKILL: u64 = 0x100000;
     pub const PTRACE_O_MASK: u64 = 0x7f;
     pub const PTRACE_O_TRACECLONE: u64 = 0x8;
     pub const PTRACE_O_TRACEEXEC: u64 = 0x10;
     pub const PTRACE_O_TRACEEXIT: u64 = 0x40;
     pub const PTRACE_O_TRACEFORK: u64 = 0x2;
+    pub const PTRACE_O_TRACESECCOMP: u64 = 0x80;
     pub const PTRACE_O_TRACESYSGOOD: u64 = 0x1;
     pub const PTRACE_O_TRACEVFORK: u64 = 0x4;
     pub const PTRACE_O_TRACEVFORKDONE: u64 = 0x20;
@@ -1693,12 +1720,14 @@ impl LibcConst {
     pub const PTRACE_POKEDATA: u64 = 0x5;
     pub const PTRACE_POKETEXT: u64 = 0x4;
     pub const PTRACE_POKEUSR: u64 = 0x6;
+    pub const PTRACE_SEIZE: u64 = 0x4206;
     pub const PTRACE_SETFPREGS: u64 = 0xf;
     pub const PTRACE_SETFPXREGS: u64 = 0x13;
     pub const PTRACE_SETOPTIONS: u64 = 0x4200;
     pub const PTRACE_SETREGS: u64 = 0xd;
     pub const PTRACE_SETREGSET: u64 = 0x4205;
     pub const PTRACE_SETSIGINFO: u64 = 0x4203;
+    pub const PTRACE_SETSIGMASK: u64 = 0x420b;
     pub const PTRACE_SET_THREAD_AREA: u64 = 0x1a;
     pub const PTRACE_SINGLEBLOCK: u64 = 0x21;
     pub const PTRACE_SINGLESTEP: u64 = 0x9;
@@ -2137,6 +2166,9 @@ impl Cmd {
     pub const F_GETSIG: i32 = 11;
     pub const F_SETOWN_EX: i32 = 15;
     pub const F_GETOWN_EX: i32 = 16;
+    pub const F_OFD_GETLK: i32 = 36;
+    pub const F_OFD_SETLK: i32 = 37;
+    pub const F_OFD_SETLKW: i32 = 38;
     pub const F_DUPFD_CLOEXEC: i32 = 1024 + 6;
     pub const F_SETPIPE_SZ: i32 = 1024 + 7;
     pub const F_GETPIPE_SZ: i32 = 1024 + 8;
@@ -2821,6 +2853,7 @@ impl IDType {
     pub const P_ALL: i32 = 0x0;
     pub const P_PID: i32 = 0x1;
     pub const P_PGID: i32 = 0x2;
+    pub const P_PIDFD: i32 = 0x3;
 }
 
 pub struct MAdviseOp {}
@@ -2863,6 +2896,7 @@ impl CloneOp {
     pub const CLONE_NEWUTS: i32 = 0x4000000;
     pub const CLONE_PARENT: i32 = 0x8000;
     pub const CLONE_PARENT_SETTID: i32 = 0x100000;
+    pub const CLONE_PIDFD: i32 = 0x1000;
     pub const CLONE_PTRACE: i32 = 0x2000;
     pub const CLONE_SETTLS: i32 = 0x80000;
     pub const CLONE_SIGHAND: i32 = 0x800;
@@ -3272,4 +3306,9 @@ impl LibcStat {
         let x = self.st_mode as u16 & ModeType::S_IFMT;
         return x == ModeType::S_IFREG;
     }
+
+    pub fn IsDir(&self) -> bool {
+        let x = self.st_mode as u16 & ModeType::S_IFMT;
+        return x == ModeType::S_IFDIR;
+    }
 }
@@ -12,12 +12,57 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use alloc::collections::btree_map::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 
 use super::auth::cap_set::*;
+use super::kernel::boot::oci::LinuxSeccomp;
 use super::limits::*;
 
+// MemoryLimit is the memory limit of a container, as set by the
+// linux.resources of its OCI spec.
+#[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+pub struct MemoryLimit {
+    // Limit is the maximum memory usage of the container in bytes. 0 means
+    // that the container is not limited.
+    pub Limit: u64,
+
+    // DisableOOMKiller makes allocations over Limit fail instead of invoking
+    // the OOM killer.
+    pub DisableOOMKiller: bool,
+
+    // OOMScoreAdj is the initial oom_score_adj of the container processes.
+    pub OOMScoreAdj: i32,
+}
+
+// CPULimit is the cpu and cpuset limit of a container, as set by the
+// linux.resources of its OCI spec.
+#[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+pub struct CPULimit {
+    // CPUs are the vCPUs the processes of the container may run on. Empty
+    // means all the vCPUs.
+    pub CPUs: Vec<u32>,
+
+    // Quota is the CPU time in microseconds the container may use in each
+    // Period. 0 means that the container is not limited.
+    pub Quota: u64,
+
+    // Period is the length of a CPU bandwidth period in microseconds.
+    pub Period: u64,
+}
+
+// Mount is a mount from the OCI spec of a container. The host has already
+// mounted it in the container rootfs, the guest mounts it again so that the
+// mount table of the container matches the spec.
+#[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
+pub struct Mount {
+    pub Destination: String,
+    pub Type: String,
+    pub Source: String,
+    pub Options: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Eq, PartialEq, Clone)]
 pub struct Process {
     //user
@@ -41,9 +86,21 @@ pub struct Process {
 
     //Container
     pub limitSet: LimitSetInternal,
+    pub memoryLimit: MemoryLimit,
+    pub cpuLimit: CPULimit,
+    // pidsLimit is the maximum number of tasks of the container, 0 means
+    // unlimited.
+    pub pidsLimit: u64,
+    // Seccomp is the linux.seccomp profile of the container, enforced by the
+    // sandbox kernel for all the container processes.
+    pub Seccomp: Option<LinuxSeccomp>,
+    // Sysctl holds the linux.sysctl of the container, applied by the sandbox
+    // kernel when the container starts.
+    pub Sysctl: BTreeMap<String, String>,
     pub ID: String,
 
     pub Root: String,
+    pub Mounts: Vec<Mount>,
     pub Stdiofds: [i32; 3],
     pub ExecId: Option<String>,
 }
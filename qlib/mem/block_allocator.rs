@@ -145,6 +145,64 @@ impl PageBlockAlloc {
         return Ok(addr)
     }
 
+    // AllocHugePage allocates 2MB of contiguous, 2MB aligned memory: the upper
+    // half of a PageBlock. Its 4KB pages keep their own refcounts in the
+    // PageBlock, so the huge page can be split and freed page by page.
+    pub fn AllocHugePage(&self, incrRef: bool) -> Result<u64> {
+        let addr = {
+            let mut al = self.data.lock();
+
+            let mut pbAddr = al.pageBlockList;
+            let mut addr = 0;
+            while pbAddr != 0 {
+                let pb = PageBlock::FromAddr(pbAddr);
+                match pb.AllocHuge() {
+                    Some((hugeAddr, count)) => {
+                        if count == 0 {
+                            al.UnlinkPageBlock(pb);
+                        }
+                        self.freeCount.fetch_sub(HUGE_PAGE_COUNT, Ordering::Release);
+                        addr = hugeAddr;
+                        break;
+                    }
+                    None => {
+                        pbAddr = pb.allocator.lock().next;
+                    }
+                }
+            }
+
+            if addr == 0 {
+                let newpb = PageBlock::AllocPageBlock()?;
+                self.freeCount.fetch_add(BLOCK_PAGE_COUNT - HUGE_PAGE_COUNT, Ordering::Release);
+                let (hugeAddr, _) = newpb.AllocHuge().unwrap();
+                al.Insert(newpb);
+                addr = hugeAddr;
+            }
+
+            addr
+        };
+
+        for i in 0..HUGE_PAGE_COUNT {
+            let page = addr + i * MemoryDef::PAGE_SIZE_4K;
+            if incrRef {
+                self.Ref(page)?;
+            }
+            ZeroPage(page);
+        }
+
+        return Ok(addr)
+    }
+
+    // FreeHugePage frees a huge page from AllocHugePage which was never
+    // mapped.
+    pub fn FreeHugePage(&self, addr: u64) -> Result<()> {
+        for i in 0..HUGE_PAGE_COUNT {
+            self.FreePage(addr + i * MemoryDef::PAGE_SIZE_4K)?;
+        }
+
+        return Ok(())
+    }
+
     pub fn FreePage(&self, addr: u64) -> Result<()> {
         let pb = PageBlock::FromPageAddr(addr);
         let action = pb.FreePage(addr)?;
@@ -252,6 +310,10 @@ pub const BLOCK_SIZE : u64 = 2 * MemoryDef::PAGE_SIZE_2M;
 pub const BLOCK_PAGE_COUNT : u64 = 1023;
 pub const PAGE_BLOCK_MAGIC : u64 = 0x1234567890abc;
 
+// the upper half of a PageBlock is 2MB aligned and serves as a huge page
+pub const HUGE_PAGE_COUNT : u64 = 512;
+pub const HUGE_PAGE_IDX : usize = 512;
+
 pub struct FreePageBitmap {
     pub l1bitmap: u64,
     pub l2bitmap: [u64; 16],
@@ -305,6 +367,25 @@ impl FreePageBitmap {
         return l1idx * 64 + l2idx;
     }
 
+    // PopHuge allocates all the pages from HUGE_PAGE_IDX to the end of the
+    // PageBlock, if they are free.
+    pub fn PopHuge(&mut self) -> bool {
+        let start = HUGE_PAGE_IDX / 64;
+        for l1idx in start..self.l2bitmap.len() {
+            if self.l2bitmap[l1idx] != u64::MAX {
+                return false;
+            }
+        }
+
+        for l1idx in start..self.l2bitmap.len() {
+            self.l2bitmap[l1idx] = 0;
+            self.l1bitmap &= !(1<<l1idx);
+        }
+
+        self.totalFreeCount -= HUGE_PAGE_COUNT;
+        return true;
+    }
+
     pub fn Push(&mut self, idx: usize) {
         let l1idx = idx / 64;
         let l2idx = idx % 64;
@@ -475,6 +556,16 @@ impl PageBlock {
         return (self.IdxToAddr(idx), allocaor.freePageList.totalFreeCount)
     }
 
+    // return (huge page addr, left page count)
+    pub fn AllocHuge(&self) -> Option<(u64, u64)> {
+        let mut allocaor = self.allocator.lock();
+        if !allocaor.freePageList.PopHuge() {
+            return None;
+        }
+
+        return Some((self.IdxToAddr(HUGE_PAGE_IDX), allocaor.freePageList.totalFreeCount))
+    }
+
     pub fn GetRef(&self, addr: u64) -> Result<u64> {
         let idx = self.Idx(addr);
         
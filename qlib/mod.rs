@@ -55,6 +55,7 @@ pub mod task_mgr;
 pub mod uring;
 pub mod usage;
 pub mod backtracer;
+pub mod bpf;
 
 pub mod kernel;
 pub mod rdma_share;
@@ -702,6 +703,9 @@ pub struct ShareSpace {
     pub reapFileAvaiable: CachePadded<AtomicBool>,
     pub hibernatePause: CachePadded<AtomicBool>,
     pub hiberMgr: CachePadded<HiberMgr>,
+    // restored is set by the host when the sandbox is resumed from a checkpoint
+    // image, the guest clears it once it has reattached to the new host process.
+    pub restored: CachePadded<AtomicBool>,
 
     pub supportMemoryBarrier: bool,
     pub controlSock: i32,
@@ -37,6 +37,28 @@ use super::kernel::Kernel::HostSpace;
 use super::linux_def::*;
 use super::mutex::*;
 
+// ANON_HUGE_PAGES is the number of 2MB pages mapped by the page tables. It is
+// reported as AnonHugePages in /proc/meminfo.
+pub static ANON_HUGE_PAGES: AtomicU64 = AtomicU64::new(0);
+
+// IsHugeEntry returns whether the page directory entry maps a 2MB page.
+#[inline]
+pub fn IsHugeEntry(entry: &PageTableEntry) -> bool {
+    return entry.flags() & PageTableFlags::HUGE_PAGE == PageTableFlags::HUGE_PAGE;
+}
+
+// PageAddr returns the physical address of the 4KB page at vaddr, where entry
+// is the entry returned by VirtualToEntry, which might map a 2MB page.
+#[inline]
+pub fn PageAddr(entry: &PageTableEntry, vaddr: u64) -> u64 {
+    if IsHugeEntry(entry) {
+        return entry.addr().as_u64()
+            + (vaddr & (MemoryDef::HUGE_PAGE_SIZE - 1) & !MemoryDef::PAGE_MASK);
+    }
+
+    return entry.addr().as_u64();
+}
+
 #[derive(Default)]
 pub struct PageTables {
     //Root page guest physical address
@@ -142,8 +164,9 @@ impl PageTables {
         while vAddr < start + len {
             match self.VirtualToEntry(vAddr) {
                 Ok(entry) => {
-                    let phyAddr = entry.addr().as_u64();
-                    to.MapPage(Addr(vAddr), Addr(phyAddr), entry.flags(), pagePool)?;
+                    let phyAddr = PageAddr(entry, vAddr);
+                    let flags = entry.flags() & !PageTableFlags::HUGE_PAGE;
+                    to.MapPage(Addr(vAddr), Addr(phyAddr), flags, pagePool)?;
                 }
                 Err(_) => (),
             }
@@ -173,7 +196,7 @@ impl PageTables {
         while vAddr < start + len {
             match self.VirtualToEntry(vAddr) {
                 Ok(entry) => {
-                    let phyAddr = entry.addr().as_u64();
+                    let phyAddr = PageAddr(entry, vAddr);
                     to.MapPage(
                         Addr(vAddr),
                         Addr(phyAddr),
@@ -259,6 +282,10 @@ impl PageTables {
                 return Err(Error::AddressNotMap(addr));
             }
 
+            if IsHugeEntry(pmdEntry) {
+                return Ok(pmdEntry);
+            }
+
             let pteTbl = pmdEntry.addr().as_u64() as *mut PageTable;
             let pteEntry = &mut (*pteTbl)[p1Idx];
             if pteEntry.is_unused() {
@@ -280,7 +307,7 @@ impl PageTables {
 
         let vaddr = VirtAddr::new(vaddr);
         let pageAddr: u64 = vaddr.page_offset().into();
-        let phyAddr = pteEntry.addr().as_u64() + pageAddr;
+        let phyAddr = PageAddr(pteEntry, vaddr.as_u64()) + pageAddr;
         let permission = AccessType::NewFromPageFlags(pteEntry.flags());
 
         return Ok((phyAddr, permission));
@@ -373,6 +400,9 @@ impl PageTables {
                         | PageTableFlags::USER_ACCESSIBLE,
                 );
             } else {
+                if IsHugeEntry(pmdEntry) {
+                    self.splitHugeEntry(pmdEntry, vaddr.0, pagePool)?;
+                }
                 pteTbl = pmdEntry.addr().as_u64() as *mut PageTable;
             }
 
@@ -398,6 +428,164 @@ impl PageTables {
         return Ok(res);
     }
 
+    // PmdEntry returns the page directory entry of vaddr, or None if there is
+    // no page directory for it.
+    pub fn PmdEntry(&self, vaddr: u64) -> Option<&mut PageTableEntry> {
+        let vaddr = VirtAddr::new(vaddr);
+        let pt: *mut PageTable = self.GetRoot() as *mut PageTable;
+        unsafe {
+            let pgdEntry = &(*pt)[vaddr.p4_index()];
+            if pgdEntry.is_unused() {
+                return None;
+            }
+
+            let pudTbl = pgdEntry.addr().as_u64() as *mut PageTable;
+            let pudEntry = &(*pudTbl)[vaddr.p3_index()];
+            if pudEntry.is_unused() {
+                return None;
+            }
+
+            let pmdTbl = pudEntry.addr().as_u64() as *mut PageTable;
+            return Some(&mut (*pmdTbl)[vaddr.p2_index()]);
+        }
+    }
+
+    // HugePageMappable returns whether none of the 2MB aligned range at vaddr
+    // is mapped, so that MapHugePage can map it.
+    pub fn HugePageMappable(&self, vaddr: u64) -> bool {
+        let pmdEntry = match self.PmdEntry(vaddr) {
+            None => return true,
+            Some(e) => e,
+        };
+
+        if pmdEntry.is_unused() {
+            return true;
+        }
+
+        if IsHugeEntry(pmdEntry) {
+            return false;
+        }
+
+        let pteTbl = pmdEntry.addr().as_u64() as *const PageTable;
+        return Self::UnusedEntryCount(pteTbl) == MemoryDef::ENTRY_COUNT as usize;
+    }
+
+    // MapHugePage maps the 2MB page at phyAddr to the 2MB aligned vaddr with a
+    // page directory entry. The mapping holds a reference to each 4KB page of
+    // the 2MB page. It returns false, and maps nothing, if part of the range
+    // is mapped already.
+    pub fn MapHugePage(
+        &self,
+        vaddr: Addr,
+        phyAddr: Addr,
+        flags: PageTableFlags,
+        pagePool: &Allocator,
+    ) -> Result<bool> {
+        assert!(vaddr.0 & (MemoryDef::HUGE_PAGE_SIZE - 1) == 0);
+        assert!(phyAddr.0 & (MemoryDef::HUGE_PAGE_SIZE - 1) == 0);
+
+        let pt: *mut PageTable = self.GetRoot() as *mut PageTable;
+        unsafe {
+            let p4Idx = VirtAddr::new(vaddr.0).p4_index();
+            let p3Idx = VirtAddr::new(vaddr.0).p3_index();
+            let p2Idx = VirtAddr::new(vaddr.0).p2_index();
+
+            let pgdEntry = &mut (*pt)[p4Idx];
+            let pudTbl: *mut PageTable;
+
+            if pgdEntry.is_unused() {
+                pudTbl = pagePool.AllocPage(true)? as *mut PageTable;
+                pgdEntry.set_addr(
+                    PhysAddr::new(pudTbl as u64),
+                    PageTableFlags::PRESENT
+                        | PageTableFlags::WRITABLE
+                        | PageTableFlags::USER_ACCESSIBLE,
+                );
+            } else {
+                pudTbl = pgdEntry.addr().as_u64() as *mut PageTable;
+            }
+
+            let pudEntry = &mut (*pudTbl)[p3Idx];
+            let pmdTbl: *mut PageTable;
+
+            if pudEntry.is_unused() {
+                pmdTbl = pagePool.AllocPage(true)? as *mut PageTable;
+                pudEntry.set_addr(
+                    PhysAddr::new(pmdTbl as u64),
+                    PageTableFlags::PRESENT
+                        | PageTableFlags::WRITABLE
+                        | PageTableFlags::USER_ACCESSIBLE,
+                );
+            } else {
+                pmdTbl = pudEntry.addr().as_u64() as *mut PageTable;
+            }
+
+            let pmdEntry = &mut (*pmdTbl)[p2Idx];
+            if !pmdEntry.is_unused() {
+                if IsHugeEntry(pmdEntry) {
+                    return Ok(false);
+                }
+
+                let pteTbl = pmdEntry.addr().as_u64() as *const PageTable;
+                if Self::UnusedEntryCount(pteTbl) != MemoryDef::ENTRY_COUNT as usize {
+                    return Ok(false);
+                }
+
+                // replace the empty page table
+                let currAddr = pmdEntry.addr().as_u64();
+                let refCnt = pagePool.Deref(currAddr)?;
+                if refCnt == 0 {
+                    self.FreePage(currAddr);
+                }
+                pmdEntry.set_unused();
+            }
+
+            for i in 0..MemoryDef::ENTRY_COUNT as u64 {
+                pagePool.Ref(phyAddr.0 + i * MemoryDef::PAGE_SIZE)?;
+            }
+
+            pmdEntry.set_addr(
+                PhysAddr::new(phyAddr.0),
+                flags | PageTableFlags::HUGE_PAGE,
+            );
+            Invlpg(vaddr.0);
+        }
+
+        ANON_HUGE_PAGES.fetch_add(1, Ordering::Relaxed);
+        return Ok(true);
+    }
+
+    // SplitHugePage splits the 2MB page mapped at vaddr, if there is one, into
+    // 4KB pages. It returns whether a 2MB page is split.
+    pub fn SplitHugePage(&self, vaddr: u64, pagePool: &Allocator) -> Result<bool> {
+        match self.PmdEntry(vaddr) {
+            Some(pmdEntry) if IsHugeEntry(pmdEntry) => {
+                self.splitHugeEntry(pmdEntry, vaddr, pagePool)?;
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        }
+    }
+
+    // SplitHugePages splits the 2MB pages which [start, end) covers partially,
+    // so that the range can be changed in 4KB pages. The 2MB pages inside the
+    // range are kept.
+    pub fn SplitHugePages(&self, start: u64, end: u64, pagePool: &Allocator) -> Result<()> {
+        if start >= end {
+            return Ok(());
+        }
+
+        if start & (MemoryDef::HUGE_PAGE_SIZE - 1) != 0 {
+            self.SplitHugePage(start, pagePool)?;
+        }
+
+        if end & (MemoryDef::HUGE_PAGE_SIZE - 1) != 0 {
+            self.SplitHugePage(end, pagePool)?;
+        }
+
+        return Ok(());
+    }
+
     pub fn FreePage(&self, page: u64) {
         self.freePages.lock().push(page);
     }
@@ -445,7 +633,7 @@ impl PageTables {
             let entry = self.VirtualToEntry(oldStart.0 + offset);
             match entry {
                 Ok(oldentry) => {
-                    let phyAddr = oldentry.addr().as_u64();
+                    let phyAddr = PageAddr(oldentry, oldStart.0 + offset);
                     addrs.push(Some(phyAddr));
                     pagePool.Ref(phyAddr).unwrap();
                     self.Unmap(
@@ -523,7 +711,7 @@ impl PageTables {
                 let entry = self.VirtualToEntry(oldStart.0 + offset);
                 match entry {
                     Ok(oldentry) => {
-                        let phyAddr = oldentry.addr().as_u64();
+                        let phyAddr = PageAddr(oldentry, oldStart.0 + offset);
                         self.MapPage(Addr(start.0 + offset), Addr(phyAddr), flags, pagePool)?;
                         self.Unmap(
                             oldStart.0 + offset,
@@ -699,6 +887,7 @@ impl PageTables {
     pub fn Unmap(&self, start: u64, end: u64, pagePool: &Allocator) -> Result<()> {
         Addr(start).PageAligned()?;
         Addr(end).PageAligned()?;
+        self.SplitHugePages(start, end, pagePool)?;
         let mut start = start;
         let pt: *mut PageTable = self.GetRoot() as *mut PageTable;
         unsafe {
@@ -738,6 +927,17 @@ impl PageTables {
                             continue;
                         }
 
+                        // the 2MB page is inside the range, as SplitHugePages
+                        // has split the ones at the range edges.
+                        if IsHugeEntry(pmdEntry) {
+                            self.freeHugeEntry(pmdEntry, pagePool)?;
+                            Invlpg(start);
+                            clearPMDEntries += 1;
+                            start = Self::UnmapNext(start, MemoryDef::PMD_SIZE);
+                            p2Idx += 1;
+                            continue;
+                        }
+
                         let pteTbl = pmdEntry.addr().as_u64() as *mut PageTable;
                         let mut clearPTEEntries = 0;
                         let mut p1Idx: u16 = VirtAddr::new(start).p1_index().into();
@@ -905,7 +1105,7 @@ impl PageTables {
 
                             p1Idx = PageTableIndex::new(0);
                             continue;
-                        } else {
+                        } else if !IsHugeEntry(pmdEntry) {
                             //error!("l4 page {:x}", pmdEntry.addr().as_u64());
                             // add l4 pagetable page address
                             pages.insert(pmdEntry.addr().as_u64());
@@ -1017,6 +1217,19 @@ impl PageTables {
                             }
 
                             p1Idx = PageTableIndex::new(0);
+                            continue;
+                        } else if IsHugeEntry(pmdEntry) {
+                            // a 2MB page is passed to f as its page directory entry
+                            p1Idx = PageTableIndex::new(0);
+                            f(pmdEntry, Self::ToVirtualAddr(p4Idx, p3Idx, p2Idx, p1Idx).0);
+
+                            if p2Idx == PageTableIndex::new(MemoryDef::ENTRY_COUNT - 1) {
+                                p2Idx = PageTableIndex::new(0);
+                                break;
+                            } else {
+                                p2Idx = PageTableIndex::new(u16::from(p2Idx) + 1);
+                            }
+
                             continue;
                         } else {
                             pteTbl = pmdEntry.addr().as_u64() as *mut PageTable;
@@ -1066,6 +1279,28 @@ impl PageTables {
         return Ok(());
     }
 
+    // ResidentBytes returns the bytes mapped in [start, end), and how many of
+    // them are mapped by 2MB pages.
+    pub fn ResidentBytes(&self, start: u64, end: u64) -> (u64, u64) {
+        let mut rss = 0;
+        let mut huge = 0;
+        let _ = self.Traverse(
+            Addr(start),
+            Addr(end),
+            |entry, _virtualAddr| {
+                if IsHugeEntry(entry) {
+                    rss += MemoryDef::HUGE_PAGE_SIZE;
+                    huge += MemoryDef::HUGE_PAGE_SIZE;
+                } else {
+                    rss += MemoryDef::PAGE_SIZE;
+                }
+            },
+            false,
+        );
+
+        return (rss, huge);
+    }
+
     pub fn SetPageFlags(&self, addr: Addr, flags: PageTableFlags) {
         //self.MProtect(addr, addr.AddLen(4096).unwrap(), PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE, false).unwrap();
         self.MProtect(
@@ -1099,6 +1334,11 @@ impl PageTables {
             Addr(MemoryDef::PAGE_SIZE),
             Addr(MemoryDef::PHY_LOWER_ADDR),
             |entry: &mut PageTableEntry , _virtualAddr| {
+                // 2MB pages are kept in memory
+                if IsHugeEntry(entry) {
+                    return;
+                }
+
                 let phyAddr = entry.addr().as_u64();
                 if start <= phyAddr && phyAddr < end {
                     let mut flags = entry.flags();
@@ -1123,6 +1363,10 @@ impl PageTables {
             Addr(MemoryDef::PHY_UPPER_ADDR),
             Addr(MemoryDef::LOWER_TOP),
             |entry, _virtualAddr| {
+                if IsHugeEntry(entry) {
+                    return;
+                }
+
                 let phyAddr = entry.addr().as_u64();
                 if start <= phyAddr && phyAddr < end {
                     let mut flags = entry.flags();
@@ -1177,6 +1421,9 @@ impl PageTables {
 
             if pmdEntry.is_unused() {
                 return Ok(0)
+            } else if IsHugeEntry(pmdEntry) {
+                // 2MB pages are never swapped out
+                return Ok(PageAddr(pmdEntry, vaddr.0))
             } else {
                 pteTbl = pmdEntry.addr().as_u64() as *mut PageTable;
             }
@@ -1265,12 +1512,17 @@ impl PageTables {
     ) -> Result<()> {
         //info!("MProtoc: start={:x}, end={:x}, flag = {:?}", start.0, end.0, flags);
         defer!(self.EnableTlbShootdown());
+        self.SplitHugePages(start.0, end.0, &*PAGE_MGR)?;
         return self.Traverse(
             start,
             end,
             |entry, virtualAddr| {
-                self.HandlingSwapInPage(virtualAddr, entry);
-                entry.set_flags(flags);
+                if IsHugeEntry(entry) {
+                    entry.set_flags(flags | PageTableFlags::HUGE_PAGE);
+                } else {
+                    self.HandlingSwapInPage(virtualAddr, entry);
+                    entry.set_flags(flags);
+                }
                 Invlpg(virtualAddr);
             },
             failFast,
@@ -1288,6 +1540,53 @@ impl PageTables {
         return Ok(true);
     }
 
+    // splitHugeEntry replaces the 2MB page entry with a page table of its 512
+    // 4KB pages, which take over the references held by the 2MB page entry.
+    fn splitHugeEntry(
+        &self,
+        entry: &mut PageTableEntry,
+        vaddr: u64,
+        pagePool: &Allocator,
+    ) -> Result<()> {
+        let phyAddr = entry.addr().as_u64();
+        let flags = entry.flags() & !PageTableFlags::HUGE_PAGE;
+
+        let pteTbl = pagePool.AllocPage(true)? as *mut PageTable;
+        unsafe {
+            for i in 0..MemoryDef::ENTRY_COUNT {
+                (*pteTbl)[PageTableIndex::new(i)].set_addr(
+                    PhysAddr::new(phyAddr + i as u64 * MemoryDef::PAGE_SIZE),
+                    flags,
+                );
+            }
+        }
+
+        entry.set_addr(
+            PhysAddr::new(pteTbl as u64),
+            PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE,
+        );
+        Invlpg(vaddr);
+        ANON_HUGE_PAGES.fetch_sub(1, Ordering::Relaxed);
+        self.EnableTlbShootdown();
+        return Ok(());
+    }
+
+    fn freeHugeEntry(&self, entry: &mut PageTableEntry, pagePool: &Allocator) -> Result<()> {
+        let phyAddr = entry.addr().as_u64();
+        for i in 0..MemoryDef::ENTRY_COUNT as u64 {
+            let page = phyAddr + i * MemoryDef::PAGE_SIZE;
+            let refCnt = pagePool.Deref(page)?;
+            if refCnt == 0 {
+                self.FreePage(page);
+            }
+        }
+
+        entry.set_unused();
+        ANON_HUGE_PAGES.fetch_sub(1, Ordering::Relaxed);
+        self.EnableTlbShootdown();
+        return Ok(());
+    }
+
     // if kernel == true, don't need to reference in the pagePool
     fn mapCanonical(
         &self,
@@ -1354,6 +1653,9 @@ impl PageTables {
                                     | PageTableFlags::USER_ACCESSIBLE,
                             );
                         } else {
+                            if IsHugeEntry(pmdEntry) {
+                                self.splitHugeEntry(pmdEntry, curAddr.0, pagePool)?;
+                            }
                             pteTbl = pmdEntry.addr().as_u64() as *mut PageTable;
                         }
 
@@ -1623,4 +1925,230 @@ mod tests {
             );
         }
     }
+
+    // HugePageAddr returns the first 2MB aligned address in mem.
+    fn HugePageAddr(mem: &Vec<Page>) -> u64 {
+        let start = &mem[0] as *const _ as u64;
+        return (start + MemoryDef::HUGE_PAGE_SIZE - 1) & !(MemoryDef::HUGE_PAGE_SIZE - 1);
+    }
+
+    #[test]
+    fn test_MapHugePage() {
+        let mem: Vec<Page> = vec![Default::default(); 1024]; //1024 Pages
+        let mut allocator = MemAllocator::Init(&mem[0] as *const _ as u64, 10); //2^10
+        let mut pt = PageTables::New(&Allocator).unwrap();
+
+        let vAddr = MemoryDef::HUGE_PAGE_SIZE * 16;
+        let phyAddr = HugePageAddr(&mem);
+        let mapped = pt
+            .MapHugePage(
+                Addr(vAddr),
+                Addr(phyAddr),
+                PageOpts::UserReadWrite().Val(),
+                &Allocator,
+            )
+            .unwrap();
+        assert!(mapped);
+        assert!(IsHugeEntry(pt.VirtualToEntry(vAddr).unwrap()));
+
+        for i in 0..MemoryDef::ENTRY_COUNT as u64 {
+            let offset = i * MemoryDef::PAGE_SIZE;
+            let (pAddr, _) = pt.VirtualToPhy(vAddr + offset).unwrap();
+            assert_eq!(phyAddr + offset, pAddr);
+        }
+
+        // the range is mapped already
+        let mapped = pt
+            .MapHugePage(
+                Addr(vAddr),
+                Addr(phyAddr),
+                PageOpts::UserReadWrite().Val(),
+                &Allocator,
+            )
+            .unwrap();
+        assert!(!mapped);
+    }
+
+    #[test]
+    fn test_SplitHugePageUnmap() {
+        let mem: Vec<Page> = vec![Default::default(); 1024]; //1024 Pages
+        let mut allocator = MemAllocator::Init(&mem[0] as *const _ as u64, 10); //2^10
+        let mut pt = PageTables::New(&Allocator).unwrap();
+
+        let vAddr = MemoryDef::HUGE_PAGE_SIZE * 16;
+        let phyAddr = HugePageAddr(&mem);
+        pt.MapHugePage(
+            Addr(vAddr),
+            Addr(phyAddr),
+            PageOpts::UserReadWrite().Val(),
+            &Allocator,
+        )
+        .unwrap();
+
+        // munmap of part of the 2MB page
+        pt.Unmap(
+            vAddr + 10 * MemoryDef::PAGE_SIZE,
+            vAddr + 20 * MemoryDef::PAGE_SIZE,
+            &Allocator,
+        )
+        .unwrap();
+        assert!(!IsHugeEntry(pt.PmdEntry(vAddr).unwrap()));
+
+        for i in 0..MemoryDef::ENTRY_COUNT as u64 {
+            let offset = i * MemoryDef::PAGE_SIZE;
+            if i >= 10 && i < 20 {
+                assert!(pt.VirtualToPhy(vAddr + offset).is_err());
+            } else {
+                let (pAddr, _) = pt.VirtualToPhy(vAddr + offset).unwrap();
+                assert_eq!(phyAddr + offset, pAddr);
+                assert_eq!(
+                    pt.VirtualToEntry(vAddr + offset).unwrap().flags(),
+                    PageOpts::UserReadWrite().Val()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_SplitHugePageMProtect() {
+        let mem: Vec<Page> = vec![Default::default(); 1024]; //1024 Pages
+        let mut allocator = MemAllocator::Init(&mem[0] as *const _ as u64, 10); //2^10
+        let mut pt = PageTables::New(&Allocator).unwrap();
+
+        let vAddr = MemoryDef::HUGE_PAGE_SIZE * 16;
+        let phyAddr = HugePageAddr(&mem);
+        pt.MapHugePage(
+            Addr(vAddr),
+            Addr(phyAddr),
+            PageOpts::UserReadWrite().Val(),
+            &Allocator,
+        )
+        .unwrap();
+
+        // mprotect of the second half of the 2MB page
+        let half = MemoryDef::HUGE_PAGE_SIZE / 2;
+        pt.MProtect(
+            Addr(vAddr + half),
+            Addr(vAddr + MemoryDef::HUGE_PAGE_SIZE),
+            PageOpts::UserReadOnly().Val(),
+            true,
+        )
+        .unwrap();
+        assert!(!IsHugeEntry(pt.PmdEntry(vAddr).unwrap()));
+
+        for i in 0..MemoryDef::ENTRY_COUNT as u64 {
+            let offset = i * MemoryDef::PAGE_SIZE;
+            let (pAddr, _) = pt.VirtualToPhy(vAddr + offset).unwrap();
+            assert_eq!(phyAddr + offset, pAddr);
+
+            let flags = if offset < half {
+                PageOpts::UserReadWrite().Val()
+            } else {
+                PageOpts::UserReadOnly().Val()
+            };
+            assert_eq!(pt.VirtualToEntry(vAddr + offset).unwrap().flags(), flags);
+        }
+
+        // mprotect of the whole 2MB page keeps it
+        let vAddr2 = vAddr + MemoryDef::HUGE_PAGE_SIZE;
+        pt.MapHugePage(
+            Addr(vAddr2),
+            Addr(phyAddr),
+            PageOpts::UserReadWrite().Val(),
+            &Allocator,
+        )
+        .unwrap();
+        pt.MProtect(
+            Addr(vAddr2),
+            Addr(vAddr2 + MemoryDef::HUGE_PAGE_SIZE),
+            PageOpts::UserReadOnly().Val(),
+            true,
+        )
+        .unwrap();
+        let entry = pt.VirtualToEntry(vAddr2).unwrap();
+        assert!(IsHugeEntry(entry));
+        assert_eq!(
+            entry.flags(),
+            PageOpts::UserReadOnly().Val() | PageTableFlags::HUGE_PAGE
+        );
+    }
+
+    #[test]
+    fn test_ForkHugePage() {
+        let mem: Vec<Page> = vec![Default::default(); 1024]; //1024 Pages
+        let mut allocator = MemAllocator::Init(&mem[0] as *const _ as u64, 10); //2^10
+        let mut pt = PageTables::New(&Allocator).unwrap();
+
+        let vAddr = MemoryDef::HUGE_PAGE_SIZE * 16;
+        let phyAddr = HugePageAddr(&mem);
+        pt.MapHugePage(
+            Addr(vAddr),
+            Addr(phyAddr),
+            PageOpts::UserReadWrite().Val(),
+            &Allocator,
+        )
+        .unwrap();
+
+        let mut nPt = PageTables::New(&Allocator).unwrap();
+        pt.ForkRange(&mut nPt, vAddr, MemoryDef::HUGE_PAGE_SIZE, &Allocator)
+            .unwrap();
+
+        // both sides are read only for the copy on write, the child maps 4KB
+        // pages
+        assert_eq!(
+            pt.VirtualToEntry(vAddr).unwrap().flags(),
+            PageOpts::UserReadOnly().Val() | PageTableFlags::HUGE_PAGE
+        );
+        for i in 0..MemoryDef::ENTRY_COUNT as u64 {
+            let offset = i * MemoryDef::PAGE_SIZE;
+            let (pAddr, _) = nPt.VirtualToPhy(vAddr + offset).unwrap();
+            assert_eq!(phyAddr + offset, pAddr);
+            assert_eq!(
+                nPt.VirtualToEntry(vAddr + offset).unwrap().flags(),
+                PageOpts::UserReadOnly().Val()
+            );
+        }
+
+        // the copy on write of one page splits the 2MB page of the parent
+        pt.SplitHugePages(vAddr, vAddr + MemoryDef::PAGE_SIZE, &Allocator)
+            .unwrap();
+        pt.MapPage(
+            Addr(vAddr),
+            Addr(phyAddr + MemoryDef::HUGE_PAGE_SIZE),
+            PageOpts::UserReadWrite().Val(),
+            &Allocator,
+        )
+        .unwrap();
+        let (pAddr, _) = pt.VirtualToPhy(vAddr).unwrap();
+        assert_eq!(phyAddr + MemoryDef::HUGE_PAGE_SIZE, pAddr);
+        let (pAddr, _) = nPt.VirtualToPhy(vAddr).unwrap();
+        assert_eq!(phyAddr, pAddr);
+    }
+
+    #[test]
+    fn test_FreeHugePage() {
+        let mem: Vec<Page> = vec![Default::default(); 1024]; //1024 Pages
+        let mut allocator = MemAllocator::Init(&mem[0] as *const _ as u64, 10); //2^10
+        let mut pt = PageTables::New(&Allocator).unwrap();
+
+        let vAddr = MemoryDef::HUGE_PAGE_SIZE * 16;
+        let phyAddr = HugePageAddr(&mem);
+        let hugePages = ANON_HUGE_PAGES.load(Ordering::Relaxed);
+        pt.MapHugePage(
+            Addr(vAddr),
+            Addr(phyAddr),
+            PageOpts::UserReadWrite().Val(),
+            &Allocator,
+        )
+        .unwrap();
+        assert_eq!(ANON_HUGE_PAGES.load(Ordering::Relaxed), hugePages + 1);
+
+        pt.Unmap(vAddr, vAddr + MemoryDef::HUGE_PAGE_SIZE, &Allocator)
+            .unwrap();
+        assert_eq!(ANON_HUGE_PAGES.load(Ordering::Relaxed), hugePages);
+        assert!(pt.VirtualToEntry(vAddr).is_err());
+
+        // all the 4KB pages of the 2MB page are released
+        assert_eq!(pt.freePages.lock().len(), MemoryDef::ENTRY_COUNT as usize);
+    }
 }
@@ -122,6 +122,10 @@ pub enum Msg {
     SwapOut(SwapOut),
     SwapIn(SwapIn),
     Proxy(Proxy),
+    Checkpoint(Checkpoint),
+    HostInotifyInit(HostInotifyInit),
+    HostInotifyAddWatch(HostInotifyAddWatch),
+    HostInotifyRmWatch(HostInotifyRmWatch),
 }
 
 #[derive(Clone, Default, Debug)]
@@ -134,6 +138,11 @@ pub struct Proxy {
 #[derive(Clone, Default, Debug)]
 pub struct SwapOut {}
 
+#[derive(Clone, Default, Debug)]
+pub struct Checkpoint {
+    pub imagePath: u64,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct SwapIn {}
 
@@ -193,6 +202,22 @@ pub struct SetTscOffset {
     pub offset: i64,
 }
 
+#[derive(Clone, Default, Debug)]
+pub struct HostInotifyInit {}
+
+#[derive(Clone, Default, Debug)]
+pub struct HostInotifyAddWatch {
+    pub fd: i32,
+    pub targetFd: i32,
+    pub mask: u32,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct HostInotifyRmWatch {
+    pub fd: i32,
+    pub wd: i32,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct UpdateWaitInfo {
     pub fd: i32,
@@ -14,10 +14,13 @@
 
 use super::mutex::*;
 use alloc::boxed::Box;
-use alloc::collections::vec_deque::VecDeque;
+use alloc::collections::btree_map::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 use cache_padded::CachePadded;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicI32;
+use core::sync::atomic::AtomicI64;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::AtomicIsize;
@@ -25,6 +28,7 @@ use core::sync::atomic::Ordering;
 use core::cmp::PartialEq;
 
 use super::kernel::arch::x86_64::arch_x86::*;
+use super::linux_def::Sched;
 
 use super::vcpu_mgr::*;
 
@@ -55,6 +59,240 @@ impl TaskId {
     }
 }
 
+// SCHED_PRIO_TO_WEIGHT maps the nice values [-20, 19] to the load weights of
+// the fair scheduling class, as sched_prio_to_weight in Linux. A nice level
+// is about 10% of cpu time.
+pub const SCHED_PRIO_TO_WEIGHT: [u64; 40] = [
+    /* -20 */ 88761, 71755, 56483, 46273, 36291,
+    /* -15 */ 29154, 23254, 18705, 14949, 11916,
+    /* -10 */ 9548, 7620, 6100, 4904, 3906,
+    /*  -5 */ 3121, 2501, 1991, 1586, 1277,
+    /*   0 */ 1024, 820, 655, 526, 423,
+    /*   5 */ 335, 272, 215, 172, 137,
+    /*  10 */ 110, 87, 70, 56, 45,
+    /*  15 */ 36, 29, 23, 18, 15,
+];
+
+pub const NICE_0_WEIGHT: u64 = 1024;
+
+// IDLE_WEIGHT is the weight of the SCHED_IDLE tasks, as WEIGHT_IDLEPRIO.
+pub const IDLE_WEIGHT: u64 = 3;
+
+pub const MAX_RT_PRIO: i32 = 99;
+pub const MIN_RT_PRIO: i32 = 1;
+
+// RR_TIMESLICE is the time slice of the SCHED_RR tasks.
+pub const RR_TIMESLICE: u64 = 100_000_000; // 100ms
+
+// SCHED_LATENCY is the period in which every runnable fair task runs once.
+pub const SCHED_LATENCY: u64 = 6_000_000; // 6ms
+
+// SCHED_MIN_GRANULARITY is the minimum time a fair task runs before it is
+// preempted by another fair task.
+pub const SCHED_MIN_GRANULARITY: u64 = 750_000; // 0.75ms
+
+// the scheduling classes, in the order they are picked from a run queue
+pub const SCHED_CLASS_RT: u64 = 0;
+pub const SCHED_CLASS_FAIR: u64 = 1;
+pub const SCHED_CLASS_IDLE: u64 = 2;
+
+// SchedEntity is the scheduling state of a task.
+#[derive(Debug, Default)]
+pub struct SchedEntity {
+    // policy is one of Sched::SCHED_NORMAL, SCHED_FIFO, SCHED_RR, SCHED_BATCH
+    // and SCHED_IDLE.
+    pub policy: AtomicI32,
+
+    // rtPriority is the real-time priority [1, 99] of a SCHED_FIFO/SCHED_RR
+    // task, it is 0 for the other policies.
+    pub rtPriority: AtomicI32,
+
+    // nice is the nice value [-20, 19] of a fair task.
+    pub nice: AtomicI32,
+
+    // resetOnFork is SCHED_RESET_ON_FORK, the children of the task don't
+    // inherit a real-time policy or a negative nice value.
+    pub resetOnFork: AtomicBool,
+
+    // vruntime is the weighted time in ns the task has run. The fair tasks
+    // with the smallest vruntime run first.
+    pub vruntime: AtomicU64,
+
+    // sliceStart is the tsc when the task started to run on the vcpu.
+    pub sliceStart: AtomicI64,
+
+    // chargeStart is the tsc from which the run time is not yet charged to
+    // vruntime.
+    pub chargeStart: AtomicI64,
+}
+
+impl SchedEntity {
+    pub fn Policy(&self) -> i32 {
+        return self.policy.load(Ordering::Relaxed);
+    }
+
+    pub fn RTPriority(&self) -> i32 {
+        return self.rtPriority.load(Ordering::Relaxed);
+    }
+
+    pub fn Nice(&self) -> i32 {
+        return self.nice.load(Ordering::Relaxed);
+    }
+
+    pub fn ResetOnFork(&self) -> bool {
+        return self.resetOnFork.load(Ordering::Relaxed);
+    }
+
+    pub fn Vruntime(&self) -> u64 {
+        return self.vruntime.load(Ordering::Relaxed);
+    }
+
+    pub fn SetPolicy(&self, policy: i32, rtPriority: i32, resetOnFork: bool) {
+        self.policy.store(policy, Ordering::Relaxed);
+        self.rtPriority.store(rtPriority, Ordering::Relaxed);
+        self.resetOnFork.store(resetOnFork, Ordering::Relaxed);
+    }
+
+    pub fn SetNice(&self, nice: i32) {
+        self.nice.store(nice, Ordering::Relaxed);
+    }
+
+    pub fn IsRealtime(policy: i32) -> bool {
+        return policy == Sched::SCHED_FIFO || policy == Sched::SCHED_RR;
+    }
+
+    pub fn Class(&self) -> u64 {
+        let policy = self.Policy();
+        if Self::IsRealtime(policy) {
+            return SCHED_CLASS_RT;
+        }
+
+        if policy == Sched::SCHED_IDLE {
+            return SCHED_CLASS_IDLE;
+        }
+
+        return SCHED_CLASS_FAIR;
+    }
+
+    pub fn Weight(&self) -> u64 {
+        return Self::WeightOf(self.Policy(), self.Nice());
+    }
+
+    // WeightOf returns the load weight of a task with the policy and nice
+    // value.
+    pub fn WeightOf(policy: i32, nice: i32) -> u64 {
+        if policy == Sched::SCHED_IDLE {
+            return IDLE_WEIGHT;
+        }
+
+        let idx = (nice + 20).max(0).min(39) as usize;
+        return SCHED_PRIO_TO_WEIGHT[idx];
+    }
+
+    // Slice returns the time in ns the task runs before the tasks of the
+    // same priority get the vcpu, as returned by sched_rr_get_interval(2).
+    pub fn Slice(&self) -> u64 {
+        return Self::SliceOf(self.Policy(), self.Nice());
+    }
+
+    // SliceOf returns the time slice of a task with the policy and nice
+    // value.
+    pub fn SliceOf(policy: i32, nice: i32) -> u64 {
+        match policy {
+            Sched::SCHED_FIFO => return 0,
+            Sched::SCHED_RR => return RR_TIMESLICE,
+            _ => {
+                let slice = SCHED_LATENCY * Self::WeightOf(policy, nice) / NICE_0_WEIGHT;
+                return slice.max(SCHED_MIN_GRANULARITY).min(RR_TIMESLICE);
+            }
+        }
+    }
+
+    // Charge adds the run time delta in ns to vruntime, scaled by the weight
+    // of the task.
+    pub fn Charge(&self, delta: u64) {
+        if Self::IsRealtime(self.Policy()) {
+            return;
+        }
+
+        let delta = delta * NICE_0_WEIGHT / self.Weight();
+        self.vruntime.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    // Place keeps a fair task which has slept for long from monopolizing the
+    // vcpu, it gets at most half of the scheduling latency of credit.
+    pub fn Place(&self, minVruntime: u64) {
+        let floor = minVruntime.saturating_sub(SCHED_LATENCY / 2);
+        self.vruntime.fetch_max(floor, Ordering::Relaxed);
+    }
+
+    // QueueKey returns the order of the task in a run queue: the real-time
+    // tasks by priority, then the fair tasks and the idle tasks by vruntime.
+    // seq keeps the tasks with the same key in FIFO order.
+    pub fn QueueKey(&self, seq: u64) -> (u64, u64, u64) {
+        let class = self.Class();
+        if class == SCHED_CLASS_RT {
+            return (class, (MAX_RT_PRIO - self.RTPriority()) as u64, seq);
+        }
+
+        return (class, self.Vruntime(), seq);
+    }
+
+    // Precedes returns whether the task has a higher priority than other,
+    // i.e. it is in a higher scheduling class or a real-time task with a
+    // higher real-time priority.
+    pub fn Precedes(&self, other: &SchedEntity) -> bool {
+        let class = self.Class();
+        let otherClass = other.Class();
+        if class != otherClass {
+            return class < otherClass;
+        }
+
+        return class == SCHED_CLASS_RT && self.RTPriority() > other.RTPriority();
+    }
+
+    // PreemptedBy returns whether the running task, which has run for ran ns
+    // since it got the vcpu, should give the vcpu to the ready task next.
+    pub fn PreemptedBy(&self, next: &SchedEntity, ran: u64) -> bool {
+        if next.Precedes(self) {
+            return true;
+        }
+
+        if self.Class() != next.Class() {
+            return false;
+        }
+
+        match self.Policy() {
+            Sched::SCHED_FIFO => return false,
+            Sched::SCHED_RR => {
+                return next.RTPriority() == self.RTPriority() && ran >= RR_TIMESLICE
+            }
+            _ => return ran >= SCHED_MIN_GRANULARITY && next.Vruntime() < self.Vruntime(),
+        }
+    }
+
+    // Fork initializes the scheduling state of a child task from its parent.
+    pub fn Fork(&self, parent: &SchedEntity) {
+        let mut policy = parent.Policy();
+        let mut rtPriority = parent.RTPriority();
+        let mut nice = parent.Nice();
+        if parent.ResetOnFork() {
+            if Self::IsRealtime(policy) {
+                policy = Sched::SCHED_NORMAL;
+                rtPriority = 0;
+            }
+
+            if nice < 0 {
+                nice = 0;
+            }
+        }
+
+        self.SetPolicy(policy, rtPriority, false);
+        self.SetNice(nice);
+        self.vruntime.store(parent.Vruntime(), Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Links {
     pub prev: AtomicU64,
@@ -80,6 +318,10 @@ pub struct Context {
     // job queue id
     pub queueId: AtomicUsize,
     pub links: Links,
+    // cpuMask is the mask of the vcpus the task is allowed to run on, 0 means
+    // all the vcpus.
+    pub cpuMask: AtomicU64,
+    pub sched: SchedEntity,
 }
 
 impl Context {
@@ -101,6 +343,8 @@ impl Context {
             X86fpstate: Some(Default::default()),
             queueId: AtomicUsize::new(0),
             links: Links::default(),
+            cpuMask: AtomicU64::new(0),
+            sched: SchedEntity::default(),
         };
     }
 
@@ -111,6 +355,20 @@ impl Context {
     pub fn SetReady(&self, val: u64) {
         return self.ready.store(val, Ordering::SeqCst);
     }
+
+    pub fn CPUMask(&self) -> u64 {
+        return self.cpuMask.load(Ordering::Relaxed);
+    }
+
+    pub fn SetCPUMask(&self, mask: u64) {
+        self.cpuMask.store(mask, Ordering::Relaxed);
+    }
+
+    // AllowedOn returns whether the task may run on vcpu vcpuId.
+    pub fn AllowedOn(&self, vcpuId: usize) -> bool {
+        let mask = self.CPUMask();
+        return mask == 0 || mask & (1 << vcpuId) != 0;
+    }
 }
 
 #[derive(Default)]
@@ -127,6 +385,10 @@ pub struct Scheduler {
 
     pub vcpuWaitMask: AtomicU64,
     pub VcpuArr: Vec<CPULocal>,
+
+    // minVruntime is the vruntime of the fair tasks which get the vcpus,
+    // it only increases.
+    pub minVruntime: AtomicU64,
 }
 
 impl Scheduler {
@@ -199,7 +461,33 @@ impl Scheduler {
     }
 
 
+    pub fn MinVruntime(&self) -> u64 {
+        return self.minVruntime.load(Ordering::Relaxed);
+    }
+
+    pub fn UpdateMinVruntime(&self, vruntime: u64) {
+        self.minVruntime.fetch_max(vruntime, Ordering::Relaxed);
+    }
+
+    // ShouldPreempt returns whether the current task of vcpu vcpuId, which
+    // has run for ran ns, should give the vcpu to a ready task.
+    pub fn ShouldPreempt(&self, current: TaskId, vcpuId: usize, ran: u64) -> bool {
+        let sched = &current.Context().sched;
+        match self.queue[vcpuId].Front() {
+            Some(next) => return sched.PreemptedBy(&next.Context().sched, ran),
+            None => {
+                // the ready tasks of the other vcpus may be stolen
+                if self.GlobalReadyTaskCnt() <= 0 || sched.Policy() == Sched::SCHED_FIFO {
+                    return false;
+                }
+
+                return ran >= sched.Slice();
+            }
+        }
+    }
+
     pub fn ScheduleQ(&self, task: TaskId, vcpuId: u64, cpuAff: bool) {
+        task.Context().sched.Place(self.MinVruntime());
         if self.queue[vcpuId as usize].Enqueue(task, cpuAff) {
             self.IncReadyTaskCount();
         }
@@ -261,11 +549,62 @@ impl Scheduler {
     }
 }
 
+// RunQueue is the ready tasks of a vcpu, ordered by SchedEntity::QueueKey.
+#[derive(Debug, Default)]
+pub struct RunQueue {
+    pub tasks: BTreeMap<(u64, u64, u64), TaskId>,
+    pub seq: u64,
+}
+
+impl RunQueue {
+    // PushBack queues the task after the ready tasks with the same priority.
+    // The key is taken when the task is queued, a task changing its
+    // scheduling parameters takes the new position when it is queued again.
+    pub fn PushBack(&mut self, task: TaskId) {
+        self.seq += 1;
+        let key = task.Context().sched.QueueKey(self.seq);
+        self.tasks.insert(key, task);
+    }
+
+    pub fn Front(&self) -> Option<TaskId> {
+        return self.tasks.values().next().cloned();
+    }
+
+    pub fn PopFront(&mut self) -> Option<TaskId> {
+        let key = match self.tasks.keys().next() {
+            None => return None,
+            Some(k) => *k,
+        };
+
+        return self.tasks.remove(&key);
+    }
+
+    // RemoveFirst removes the task with the highest priority which matches f.
+    pub fn RemoveFirst<F: Fn(TaskId) -> bool>(&mut self, f: F) -> Option<TaskId> {
+        let mut key = None;
+        for (k, t) in self.tasks.iter() {
+            if f(*t) {
+                key = Some(*k);
+                break;
+            }
+        }
+
+        match key {
+            None => return None,
+            Some(k) => return self.tasks.remove(&k),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.tasks.len();
+    }
+}
+
 #[derive(Debug)]
 pub struct TaskQueueIntern{
     pub workingTask: TaskId,
     pub workingTaskReady: bool,
-    pub queue: VecDeque<TaskId>
+    pub queue: RunQueue
 }
 
 impl Default for TaskQueueIntern {
@@ -273,7 +612,7 @@ impl Default for TaskQueueIntern {
         return Self {
             workingTask: TaskId::New(0),
             workingTaskReady: false,
-            queue: VecDeque::with_capacity(8),
+            queue: RunQueue::default(),
         }
     }
 }
@@ -303,10 +642,25 @@ impl TaskQueue {
         let mut data = self.data.lock();
         if data.workingTaskReady {
             data.workingTaskReady = false;
-            return Some((data.workingTask, false))
+            let working = data.workingTask;
+            let preempt = match data.queue.Front() {
+                None => false,
+                Some(t) => t.Context().sched.Precedes(&working.Context().sched),
+            };
+
+            if !preempt {
+                return Some((working, false))
+            }
+
+            // a queued task with a higher priority, e.g. a real-time task,
+            // runs before the working task. The ready task count is unchanged.
+            data.queue.PushBack(working);
+            let task = data.queue.PopFront().unwrap();
+            data.workingTask = task;
+            return Some((task, false))
         }
 
-        match data.queue.pop_front() {
+        match data.queue.PopFront() {
             None => {
                 return None
             },
@@ -342,8 +696,8 @@ impl TaskQueue {
         }
     }
 
-    // try to steal task from other vcpu's queue
-    pub fn Steal(&self) -> Option<TaskId> {
+    // try to steal task from other vcpu's queue for vcpu vcpuId
+    pub fn Steal(&self, vcpuId: usize) -> Option<TaskId> {
         if self.queueSize.load(Ordering::Acquire) == 0 {
             return None;
         }
@@ -351,17 +705,14 @@ impl TaskQueue {
         match self.data.try_lock() {
             None => return None,
             Some(mut data) => {
-                for _ in 0..data.queue.len() {
-                    match data.queue.pop_front() {
-                        None => panic!("TaskQueue none task"),
-                        Some(taskId) => {
-                            if taskId.GetTask().context.Ready() != 0 {
-                                self.queueSize.fetch_sub(1, Ordering::Release);
-                                return Some(taskId)
-                            }
-                            data.queue.push_back(taskId)
-                        }
-                    }
+                let task = data.queue.RemoveFirst(|taskId| {
+                    let context = &taskId.GetTask().context;
+                    context.Ready() != 0 && context.AllowedOn(vcpuId)
+                });
+
+                if task.is_some() {
+                    self.queueSize.fetch_sub(1, Ordering::Release);
+                    return task;
                 }
             }
         }
@@ -379,11 +730,16 @@ impl TaskQueue {
             return false;
         }
 
-        data.queue.push_back(task);
+        data.queue.PushBack(task);
         self.queueSize.fetch_add(1, Ordering::Release);
         return true;
     }
 
+    // Front returns the ready task with the highest priority.
+    pub fn Front(&self) -> Option<TaskId> {
+        return self.data.lock().queue.Front();
+    }
+
     pub fn ToString(&self) -> String {
         return format!("{:x?} ", self);
     }
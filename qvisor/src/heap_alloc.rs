@@ -10,6 +10,19 @@ use super::qlib::mem::bitmap_allocator::*;
 
 pub const ENABLE_HUGEPAGE: bool = false;
 
+// RESTORE_IMAGE_ENV is set for the sandbox process which is restored from a
+// checkpoint image. The heap content is loaded from the image, so the process
+// allocates from a bootstrap heap until the image is in place.
+pub const RESTORE_IMAGE_ENV: &str = "QUARK_RESTORE_IMAGE";
+const RESTORE_IMAGE_ENV_CSTR: &[u8] = b"QUARK_RESTORE_IMAGE\0";
+
+pub const BOOTSTRAP_HEAP_SIZE: u64 = 512 * MemoryDef::ONE_MB;
+pub static BOOTSTRAP_HEAP_ADDR: AtomicU64 = AtomicU64::new(0);
+
+pub fn IsRestoring() -> bool {
+    return unsafe { !libc::getenv(RESTORE_IMAGE_ENV_CSTR.as_ptr() as _).is_null() };
+}
+
 impl BitmapAllocatorWrapper {
     pub const fn New() -> Self {
         return Self {
@@ -59,6 +72,11 @@ impl HostAllocator {
     }
 
     pub fn Init(&self) {
+        if IsRestoring() {
+            self.InitBootstrapHeap();
+            return;
+        }
+
         let heapSize = MemoryDef::HEAP_SIZE as usize;
         let addr = unsafe {
             let mut flags = libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_FIXED;
@@ -96,6 +114,58 @@ impl HostAllocator {
         self.initialized.store(true, Ordering::Relaxed);
     }
 
+    // InitBootstrapHeap sets up the heap used before the heap of a checkpoint
+    // image is loaded at HEAP_OFFSET.
+    pub fn InitBootstrapHeap(&self) {
+        let heapSize = BOOTSTRAP_HEAP_SIZE as usize;
+        let addr = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                heapSize,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_NORESERVE,
+                -1,
+                0,
+            ) as u64
+        };
+
+        if addr == libc::MAP_FAILED as u64 {
+            panic!("mmap: failed to get mapped memory area for bootstrap heap");
+        }
+
+        self.listHeapAddr.store(addr, Ordering::SeqCst);
+        *self.Allocator() = ListAllocator::New(addr as _, addr + heapSize as u64);
+
+        let size = core::mem::size_of::<ListAllocator>();
+        self.Allocator().Add(addr as usize + size, heapSize - size);
+        BOOTSTRAP_HEAP_ADDR.store(addr, Ordering::SeqCst);
+        self.initialized.store(true, Ordering::Relaxed);
+    }
+
+    // SwitchToRestoredHeap moves the allocation to the heap loaded from a
+    // checkpoint image. The heap was locked when the image was written, the
+    // blocks allocated from the bootstrap heap are still freed to it.
+    pub fn SwitchToRestoredHeap(&self) {
+        let allocator = unsafe { &*(MemoryDef::HEAP_OFFSET as *const ListAllocator) };
+        allocator.UnlockAll();
+        self.listHeapAddr
+            .store(MemoryDef::HEAP_OFFSET, Ordering::SeqCst);
+    }
+
+    fn BootstrapAllocator(&self, ptr: *mut u8) -> Option<&mut ListAllocator> {
+        let bootstrap = BOOTSTRAP_HEAP_ADDR.load(Ordering::Relaxed);
+        if bootstrap == 0 {
+            return None;
+        }
+
+        let addr = ptr as u64;
+        if addr < bootstrap || addr >= bootstrap + BOOTSTRAP_HEAP_SIZE {
+            return None;
+        }
+
+        return Some(unsafe { &mut *(bootstrap as *mut ListAllocator) });
+    }
+
     pub fn Clear(&self) -> bool {
         //return self.Allocator().Free();
         return false;
@@ -113,6 +183,10 @@ unsafe impl GlobalAlloc for HostAllocator {
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if let Some(allocator) = self.BootstrapAllocator(ptr) {
+            return allocator.dealloc(ptr, layout);
+        }
+
         self.Allocator().dealloc(ptr, layout);
     }
 }
@@ -140,6 +214,24 @@ impl ListAllocator {
     }
 
     pub fn Check(&self) {}
+
+    // LockAll blocks all the allocations from the heap until UnlockAll. It is
+    // used to get a consistent heap content when writing a checkpoint image.
+    pub fn LockAll(&self) {
+        for buf in self.bufs.iter() {
+            core::mem::forget(buf.lock());
+        }
+        core::mem::forget(self.heap.lock());
+    }
+
+    pub fn UnlockAll(&self) {
+        unsafe {
+            self.heap.force_unlock();
+            for buf in self.bufs.iter() {
+                buf.force_unlock();
+            }
+        }
+    }
 }
 
 impl VcpuAllocator {
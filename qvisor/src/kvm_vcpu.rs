@@ -47,6 +47,7 @@ use super::qlib::task_mgr::*;
 use super::qlib::vcpu_mgr::*;
 use super::runc::runtime::vm::*;
 use super::syncmgr::*;
+use super::vmspace::checkpoint::*;
 use super::URING_MGR;
 
 #[repr(C)]
@@ -147,6 +148,10 @@ pub struct KVMVcpu {
 
     pub autoStart: bool,
     pub interrupting: Mutex<(bool, Vec<Sender<()>>)>,
+
+    // restoreState is the register state to resume from when the sandbox is
+    // restored from a checkpoint image.
+    pub restoreState: Option<VcpuState>,
 }
 
 //for pub shareSpace: * mut Mutex<ShareSpace>
@@ -226,6 +231,7 @@ impl KVMVcpu {
             shareSpaceAddr: shareSpaceAddr,
             autoStart: autoStart,
             interrupting: Mutex::new((false, vec![])),
+            restoreState: None,
         });
     }
 
@@ -411,12 +417,8 @@ impl KVMVcpu {
         );
     }
 
-    pub fn run(&self, tgid: i32) -> Result<()> {
-        SetExitSignal();
+    fn SetupBootState(&self) -> Result<()> {
         self.setup_long_mode()?;
-        let tid = unsafe { gettid() };
-        self.threadid.store(tid as u64, Ordering::SeqCst);
-        self.tgid.store(tgid as u64, Ordering::SeqCst);
 
         let regs: kvm_regs = kvm_regs {
             rflags: KERNEL_FLAGS_SET,
@@ -445,7 +447,19 @@ impl KVMVcpu {
             .set_regs(&regs)
             .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
 
-        self.SetXCR0()?;
+        return self.SetXCR0();
+    }
+
+    pub fn run(&self, tgid: i32) -> Result<()> {
+        SetExitSignal();
+        let tid = unsafe { gettid() };
+        self.threadid.store(tid as u64, Ordering::SeqCst);
+        self.tgid.store(tgid as u64, Ordering::SeqCst);
+
+        match &self.restoreState {
+            None => self.SetupBootState()?,
+            Some(state) => self.RestoreState(state)?,
+        }
 
         let mut lastVal: u32 = 0;
         let mut first = true;
@@ -995,6 +1009,108 @@ impl KVMVcpu {
         Ok(())
     }
 
+    // the msrs which are not part of kvm_regs/kvm_sregs and are set up by the
+    // qkernel, they are saved in the checkpoint image.
+    pub const CHECKPOINT_MSRS: [u32; 7] = [
+        0x10,       // MSR_IA32_TSC
+        0x277,      // MSR_IA32_CR_PAT
+        0xc0000081, // MSR_STAR
+        0xc0000082, // MSR_LSTAR
+        0xc0000083, // MSR_CSTAR
+        0xc0000084, // MSR_SYSCALL_MASK
+        0xc0000102, // MSR_KERNEL_GS_BASE
+    ];
+
+    // SaveState gets the register state of the vcpu, the vcpu must be stopped
+    // in the host.
+    pub fn SaveState(&self) -> Result<VcpuState> {
+        let regs = self
+            .vcpu
+            .get_regs()
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+        let sregs = self
+            .vcpu
+            .get_sregs()
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+        let xsave = self
+            .vcpu
+            .get_xsave()
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+        let xcrs = self
+            .vcpu
+            .get_xcrs()
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        let entries: Vec<kvm_msr_entry> = Self::CHECKPOINT_MSRS
+            .iter()
+            .map(|index| kvm_msr_entry {
+                index: *index,
+                ..Default::default()
+            })
+            .collect();
+        let mut msrs = Msrs::from_entries(&entries)
+            .map_err(|e| Error::Common(format!("SaveState msrs fail {:?}", e)))?;
+        let cnt = self
+            .vcpu
+            .get_msrs(&mut msrs)
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        let mut state = VcpuState {
+            Id: self.id,
+            Regs: ToBytes(&regs),
+            Sregs: ToBytes(&sregs),
+            Xsave: ToBytes(&xsave),
+            Xcrs: ToBytes(&xcrs),
+            Msrs: Vec::with_capacity(cnt),
+        };
+
+        for entry in &msrs.as_slice()[0..cnt] {
+            state.Msrs.push((entry.index, entry.data));
+        }
+
+        return Ok(state);
+    }
+
+    // RestoreState loads the register state saved in a checkpoint image.
+    pub fn RestoreState(&self, state: &VcpuState) -> Result<()> {
+        let sregs: kvm_sregs = FromBytes(&state.Sregs)?;
+        self.vcpu
+            .set_sregs(&sregs)
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        let xcrs: kvm_xcrs = FromBytes(&state.Xcrs)?;
+        self.vcpu
+            .set_xcrs(&xcrs)
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        let xsave: kvm_xsave = FromBytes(&state.Xsave)?;
+        self.vcpu
+            .set_xsave(&xsave)
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        let entries: Vec<kvm_msr_entry> = state
+            .Msrs
+            .iter()
+            .map(|(index, data)| kvm_msr_entry {
+                index: *index,
+                data: *data,
+                ..Default::default()
+            })
+            .collect();
+        let msrs = Msrs::from_entries(&entries)
+            .map_err(|e| Error::Common(format!("RestoreState msrs fail {:?}", e)))?;
+        self.vcpu
+            .set_msrs(&msrs)
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        let regs: kvm_regs = FromBytes(&state.Regs)?;
+        self.vcpu
+            .set_regs(&regs)
+            .map_err(|e| Error::IOError(format!("io::error is {:?}", e)))?;
+
+        return Ok(());
+    }
+
     pub fn interrupt(&self, waitCh: Option<Sender<()>>) {
         let mut interrupting = self.interrupting.lock();
         if let Some(w) = waitCh {
@@ -1089,6 +1205,34 @@ impl CPULocal {
             panic!("Vcpu::Init fail...");
         }
 
+        Self::SetupEpoll(vcpuId, epfd, eventfd);
+
+        self.eventfd = eventfd;
+        self.epollfd = epfd;
+        self.vcpuId = vcpuId;
+        self.data = 1;
+    }
+
+    // Restore recreates the eventfd and epollfd of the vcpu at the fd numbers
+    // recorded in a checkpoint image.
+    pub fn Restore(&mut self) -> Result<()> {
+        let epfd = unsafe { epoll_create1(0) };
+        if epfd == -1 {
+            return Err(Error::SysError(errno::errno().0));
+        }
+        MoveFd(epfd, self.epollfd)?;
+
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if eventfd < 0 {
+            return Err(Error::SysError(errno::errno().0));
+        }
+        MoveFd(eventfd, self.eventfd)?;
+
+        Self::SetupEpoll(self.vcpuId, self.epollfd, self.eventfd);
+        return Ok(());
+    }
+
+    fn SetupEpoll(vcpuId: usize, epfd: i32, eventfd: i32) {
         let mut ev = epoll_event {
             events: EVENT_READ as u32 | EPOLLET as u32,
             u64: eventfd as u64,
@@ -1099,7 +1243,7 @@ impl CPULocal {
         if ret == -1 {
             panic!(
                 "CPULocal::Init {} add eventfd fail, error is {}",
-                self.vcpuId,
+                vcpuId,
                 errno::errno().0
             );
         }
@@ -1121,7 +1265,7 @@ impl CPULocal {
         if ret == -1 {
             panic!(
                 "CPULocal::Init {} add host epollfd fail, error is {}",
-                self.vcpuId,
+                vcpuId,
                 errno::errno().0
             );
         }
@@ -1129,11 +1273,6 @@ impl CPULocal {
         let mut uring = URING_MGR.lock();
 
         uring.Addfd(eventfd).expect("fail to add vcpu eventfd");
-
-        self.eventfd = eventfd;
-        self.epollfd = epfd;
-        self.vcpuId = vcpuId;
-        self.data = 1;
     }
 
     pub fn ProcessOnce(sharespace: &ShareSpace) -> usize {
@@ -248,6 +248,18 @@ impl KVMVcpu {
                 SHARE_SPACE.hiberMgr.ReapSwapIn().unwrap();
                 ret = 0;
             }
+            Msg::Checkpoint(msg) => {
+                ret = super::vmspace::checkpoint::Checkpoint(msg.imagePath) as u64;
+            }
+            Msg::HostInotifyInit(_msg) => {
+                ret = super::VMSpace::HostInotifyInit() as u64;
+            }
+            Msg::HostInotifyAddWatch(msg) => {
+                ret = super::VMSpace::HostInotifyAddWatch(msg.fd, msg.targetFd, msg.mask) as u64;
+            }
+            Msg::HostInotifyRmWatch(msg) => {
+                ret = super::VMSpace::HostInotifyRmWatch(msg.fd, msg.wd) as u64;
+            }
             Msg::Proxy(msg) => {
                 ret = super::VMSpace::Proxy(msg.cmd, msg.addrIn, msg.addrOut) as u64;
             }
@@ -195,32 +195,37 @@ pub fn LoadPaths(pid: &str) -> Result<BTreeMap<String, String>> {
     return Ok(paths);
 }
 
-// countCpuset returns the number of CPU in a string formatted like:
+// ParseCpuset returns the CPUs in a string formatted like:
 // 		"0-2,7,12-14  # bits 0, 1, 2, 7, 12, 13, and 14 set" - man 7 cpuset
-pub fn CountCpuset(cpuset: &str) -> Result<usize> {
-    let mut count: usize = 0;
+pub fn ParseCpuset(cpuset: &str) -> Result<Vec<u32>> {
+    let mut cpus = Vec::new();
+
+    let cpuset = cpuset.trim();
+    if cpuset.len() == 0 {
+        return Ok(cpus);
+    }
 
     let arr: Vec<&str> = cpuset.split(',').collect();
     for p in arr {
-        let interval: Vec<&str> = p.split('-').collect();
+        let interval: Vec<&str> = p.trim().split('-').collect();
         match interval.len() {
             1 => {
-                match interval[0].parse::<usize>() {
-                    Ok(_i) => count += 1,
+                match interval[0].parse::<u32>() {
+                    Ok(i) => cpus.push(i),
                     Err(_e) => {
                         return Err(Error::Common(format!("invalid cpuset: {}", p)));
                     }
                 };
             }
             2 => {
-                let start = match interval[0].parse::<usize>() {
+                let start = match interval[0].parse::<u32>() {
                     Ok(i) => i,
                     Err(_e) => {
                         return Err(Error::Common(format!("invalid cpuset: {}", p)));
                     }
                 };
 
-                let end = match interval[0].parse::<usize>() {
+                let end = match interval[1].parse::<u32>() {
                     Ok(i) => i,
                     Err(_e) => {
                         return Err(Error::Common(format!("invalid cpuset: {}", p)));
@@ -231,7 +236,9 @@ pub fn CountCpuset(cpuset: &str) -> Result<usize> {
                     return Err(Error::Common(format!("invalid cpuset: {}", p)));
                 }
 
-                count += end - start + 1;
+                for i in start..end + 1 {
+                    cpus.push(i);
+                }
             }
             _ => {
                 return Err(Error::Common(format!("invalid cpuset: {}", p)));
@@ -239,7 +246,14 @@ pub fn CountCpuset(cpuset: &str) -> Result<usize> {
         }
     }
 
-    return Ok(count);
+    return Ok(cpus);
+}
+
+// countCpuset returns the number of CPU in a string formatted like:
+// 		"0-2,7,12-14  # bits 0, 1, 2, 7, 12, 13, and 14 set" - man 7 cpuset
+pub fn CountCpuset(cpuset: &str) -> Result<usize> {
+    let cpus = ParseCpuset(cpuset)?;
+    return Ok(cpus.len());
 }
 
 pub struct CgroupCleanup<'a> {
@@ -320,6 +334,22 @@ impl Cgroup {
         return Ok(());
     }
 
+    // Update applies 'res' to the controllers of an installed cgroup.
+    // Controllers that don't exist on the host are skipped.
+    pub fn Update(&self, res: &LinuxResources) -> Result<()> {
+        info!("Updating cgroup {}", &self.Name);
+        for controller in &CONTROLLERS {
+            let path = self.MakePath(&controller.0);
+            if !Path::new(&path).exists() {
+                continue;
+            }
+
+            controller.1(res, &path)?;
+        }
+
+        return Ok(());
+    }
+
     pub fn Uninstall(&self) {
         if !self.Own {
             return;
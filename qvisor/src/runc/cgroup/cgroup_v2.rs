@@ -329,3 +329,10 @@ pub fn ParseUint(s: &str, base: u32, bitSize: u32) -> Result<u64> {
         }
     }
 }
+
+// IsCgroup2UnifiedMode returns whether the host mounts the cgroup v2 unified
+// hierarchy at the cgroup root.
+pub fn IsCgroup2UnifiedMode() -> bool {
+    let path = format!("{}/{}", CGROUP_ROOT, CONTROLLERS_FILE);
+    return std::path::Path::new(&path).exists();
+}
@@ -17,6 +17,7 @@ use std::env;
 use clap::{App, AppSettings, Arg};
 
 use super::boot::*;
+use super::checkpoint::*;
 use super::cmd::*;
 use super::config;
 use super::config::*;
@@ -27,11 +28,13 @@ use super::kill::*;
 use super::list::*;
 use super::pause::*;
 use super::ps::*;
+use super::restore::*;
 use super::resume::*;
 use super::run::*;
 use super::sandbox::*;
 use super::start::*;
 use super::state::*;
+use super::update::*;
 use super::super::super::qlib::common::*;
 use super::wait::*;
 
@@ -184,6 +187,9 @@ pub fn Parse() -> Result<Arguments> {
         .subcommand(DeleteCmd::SubCommand(&common))
         .subcommand(StateCmd::SubCommand(&common))
         .subcommand(SandboxCmd::SubCommand(&common))
+        .subcommand(UpdateCmd::SubCommand(&common))
+        .subcommand(CheckpointCmd::SubCommand(&common))
+        .subcommand(RestoreCmd::SubCommand(&common))
         .get_matches_from(get_args());
 
     let level = match matches.occurrences_of("v") {
@@ -275,6 +281,18 @@ pub fn Parse() -> Result<Arguments> {
             config: gConfig,
             cmd: Command::SandboxCmd(SandboxCmd::Init(&cmd_matches)?),
         },
+        ("update", Some(cmd_matches)) => Arguments {
+            config: gConfig,
+            cmd: Command::UpdateCmd(UpdateCmd::Init(&cmd_matches)?),
+        },
+        ("checkpoint", Some(cmd_matches)) => Arguments {
+            config: gConfig,
+            cmd: Command::CheckpointCmd(CheckpointCmd::Init(&cmd_matches)?),
+        },
+        ("restore", Some(cmd_matches)) => Arguments {
+            config: gConfig,
+            cmd: Command::RestoreCmd(RestoreCmd::Init(&cmd_matches)?),
+        },
         // We should never reach here because clap already enforces this
         _ => panic!("command not recognized"),
     };
@@ -305,6 +323,9 @@ pub enum Command {
     DeleteCmd(DeleteCmd),
     StateCmd(StateCmd),
     SandboxCmd(SandboxCmd),
+    UpdateCmd(UpdateCmd),
+    CheckpointCmd(CheckpointCmd),
+    RestoreCmd(RestoreCmd),
 }
 
 pub fn Run(args: &mut Arguments) -> Result<()> {
@@ -324,5 +345,8 @@ pub fn Run(args: &mut Arguments) -> Result<()> {
         Command::DeleteCmd(cmd) => return cmd.Run(&mut args.config),
         Command::StateCmd(cmd) => return cmd.Run(&mut args.config),
         Command::SandboxCmd(cmd) => return cmd.Run(&mut args.config),
+        Command::UpdateCmd(cmd) => return cmd.Run(&mut args.config),
+        Command::CheckpointCmd(cmd) => return cmd.Run(&mut args.config),
+        Command::RestoreCmd(cmd) => return cmd.Run(&mut args.config),
     }
 }
@@ -71,6 +71,7 @@ impl CreateCmd {
             "",
             true,
             self.pivot,
+            "",
         )?;
 
         //eprintln!("Application error: teststasdfasfd");
@@ -0,0 +1,207 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::string::String;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serde_json;
+use std::fs::File;
+use std::io;
+
+use super::super::super::qlib::common::*;
+use super::super::cmd::config::*;
+use super::super::container::container::*;
+use super::super::oci::*;
+use super::command::*;
+
+#[derive(Default, Debug)]
+pub struct UpdateCmd {
+    pub id: String,
+    pub resources: String,
+    pub memory: String,
+    pub cpuQuota: String,
+    pub cpuPeriod: String,
+    pub cpuShare: String,
+    pub cpusetCpus: String,
+    pub cpusetMems: String,
+    pub pidsLimit: String,
+}
+
+impl UpdateCmd {
+    pub fn Init(cmd_matches: &ArgMatches) -> Result<Self> {
+        let value = |name: &str| -> String {
+            return cmd_matches.value_of(name).unwrap_or_default().to_string();
+        };
+
+        return Ok(Self {
+            id: value("id"),
+            resources: value("resources"),
+            memory: value("memory"),
+            cpuQuota: value("cpu-quota"),
+            cpuPeriod: value("cpu-period"),
+            cpuShare: value("cpu-share"),
+            cpusetCpus: value("cpuset-cpus"),
+            cpusetMems: value("cpuset-mems"),
+            pidsLimit: value("pids-limit"),
+        });
+    }
+
+    pub fn SubCommand<'a, 'b>(common: &CommonArgs<'a, 'b>) -> App<'a, 'b> {
+        return SubCommand::with_name("update")
+            .setting(AppSettings::ColoredHelp)
+            .arg(&common.id_arg)
+            .arg(
+                Arg::with_name("resources")
+                    .help("path to the file containing the resources to update, or '-' to read from stdin")
+                    .takes_value(true)
+                    .long("resources")
+                    .short("r"),
+            )
+            .arg(
+                Arg::with_name("memory")
+                    .help("memory limit (in bytes, or with a k, m or g suffix)")
+                    .takes_value(true)
+                    .long("memory"),
+            )
+            .arg(
+                Arg::with_name("cpu-quota")
+                    .help("CPU CFS hardcap limit (in usecs). Allowed cpu time in a given period")
+                    .takes_value(true)
+                    .long("cpu-quota"),
+            )
+            .arg(
+                Arg::with_name("cpu-period")
+                    .help("CPU CFS period to be used for hardcapping (in usecs)")
+                    .takes_value(true)
+                    .long("cpu-period"),
+            )
+            .arg(
+                Arg::with_name("cpu-share")
+                    .help("CPU shares (relative weight vs. other containers)")
+                    .takes_value(true)
+                    .long("cpu-share"),
+            )
+            .arg(
+                Arg::with_name("cpuset-cpus")
+                    .help("CPU(s) to use")
+                    .takes_value(true)
+                    .long("cpuset-cpus"),
+            )
+            .arg(
+                Arg::with_name("cpuset-mems")
+                    .help("memory node(s) to use")
+                    .takes_value(true)
+                    .long("cpuset-mems"),
+            )
+            .arg(
+                Arg::with_name("pids-limit")
+                    .help("maximum number of pids allowed in the container")
+                    .takes_value(true)
+                    .long("pids-limit"),
+            )
+            .about("update container resource constraints");
+    }
+
+    pub fn Run(&self, gCfg: &GlobalConfig) -> Result<()> {
+        let res = self.Resources()?;
+
+        let mut container = Container::Load(&gCfg.RootDir, &self.id)?;
+        container.Update(res)?;
+
+        return Ok(());
+    }
+
+    // Resources returns the resources to update, read from the resources
+    // file and overridden by the individual options.
+    pub fn Resources(&self) -> Result<LinuxResources> {
+        let mut res = match self.resources.as_str() {
+            "" => LinuxResources::default(),
+            "-" => serde_json::from_reader(io::stdin())
+                .map_err(|e| Error::Common(format!("parse resources fail: {:?}", e)))?,
+            path => {
+                let file = File::open(path).map_err(|e| {
+                    Error::IOError(format!("open resources file {} fail: {:?}", path, e))
+                })?;
+                serde_json::from_reader(file)
+                    .map_err(|e| Error::Common(format!("parse resources fail: {:?}", e)))?
+            }
+        };
+
+        if self.memory.len() != 0 {
+            let memory = res.memory.get_or_insert_with(LinuxMemory::default);
+            memory.limit = Some(ParseMemory(&self.memory)?);
+        }
+
+        if self.cpuQuota.len() != 0
+            || self.cpuPeriod.len() != 0
+            || self.cpuShare.len() != 0
+            || self.cpusetCpus.len() != 0
+            || self.cpusetMems.len() != 0
+        {
+            let cpu = res.cpu.get_or_insert_with(LinuxCPU::default);
+            if self.cpuQuota.len() != 0 {
+                cpu.quota = Some(ParseValue::<i64>("cpu-quota", &self.cpuQuota)?);
+            }
+            if self.cpuPeriod.len() != 0 {
+                cpu.period = Some(ParseValue::<u64>("cpu-period", &self.cpuPeriod)?);
+            }
+            if self.cpuShare.len() != 0 {
+                cpu.shares = Some(ParseValue::<u64>("cpu-share", &self.cpuShare)?);
+            }
+            if self.cpusetCpus.len() != 0 {
+                cpu.cpus = self.cpusetCpus.to_string();
+            }
+            if self.cpusetMems.len() != 0 {
+                cpu.mems = self.cpusetMems.to_string();
+            }
+        }
+
+        if self.pidsLimit.len() != 0 {
+            res.pids = Some(LinuxPids {
+                limit: ParseValue::<i64>("pids-limit", &self.pidsLimit)?,
+            });
+        }
+
+        return Ok(res);
+    }
+}
+
+fn ParseValue<T: core::str::FromStr>(name: &str, val: &str) -> Result<T> {
+    match val.parse::<T>() {
+        Ok(v) => return Ok(v),
+        Err(_) => {
+            return Err(Error::Common(format!(
+                "invalid value {} for {}",
+                val, name
+            )))
+        }
+    }
+}
+
+// ParseMemory parses a memory size like "512m". -1 means unlimited.
+fn ParseMemory(val: &str) -> Result<i64> {
+    let lower = val.trim().to_lowercase();
+    let (num, shift) = match lower.chars().last() {
+        Some('k') => (&lower[..lower.len() - 1], 10),
+        Some('m') => (&lower[..lower.len() - 1], 20),
+        Some('g') => (&lower[..lower.len() - 1], 30),
+        _ => (&lower[..], 0),
+    };
+
+    let num = ParseValue::<i64>("memory", num)?;
+    if num < 0 {
+        return Ok(-1);
+    }
+
+    return Ok(num << shift);
+}
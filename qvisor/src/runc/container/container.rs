@@ -43,6 +43,9 @@ use super::super::super::qlib::control_msg::*;
 use super::super::super::qlib::linux_def::*;
 use super::super::super::qlib::path::*;
 use super::super::super::ucall::ucall::*;
+use super::super::super::vmspace::limits::CreateCPULimit;
+use super::super::super::vmspace::limits::CreateMemoryLimit;
+use super::super::super::vmspace::limits::CreatePidsLimit;
 use super::super::runtime::fs::FsImageMounter;
 
 // metadataFilename is the name of the metadata file relative to the
@@ -439,6 +442,8 @@ impl Container {
         userlog: &str,
         detach: bool,
         pivot: bool,
+        // restoreImage is the checkpoint image to restore the sandbox from, empty for a new sandbox
+        restoreImage: &str,
     ) -> Result<Self> {
         info!("Create container {} in root dir: {}", id, &conf.RootDir);
         debug!("spec for creating container: {:#?}", &spec);
@@ -542,6 +547,7 @@ impl Container {
                     cg,
                     detach,
                     pivot,
+                    restoreImage,
                 );
 
                 c.Sandbox = match ret {
@@ -827,12 +833,56 @@ impl Container {
             userlog,
             detach,
             pivot,
+            "",
         )?;
         c.changeStatus(Status::Running);
 
         return c.Wait();
     }
 
+    // Restore creates the container in a new sandbox resumed from the
+    // checkpoint image. The processes of the container were already started
+    // when the checkpoint was taken, so the container is running once the
+    // sandbox is up.
+    pub fn Restore(
+        id: &str,
+        spec: Spec,
+        conf: &GlobalConfig,
+        bundleDir: &str,
+        consoleSocket: &str,
+        pidFile: &str,
+        detach: bool,
+        pivot: bool,
+        imagePath: &str,
+    ) -> Result<Self> {
+        info!("Restore container {} from {}", id, imagePath);
+
+        if !Path::new(imagePath).exists() {
+            return Err(Error::Common(format!(
+                "checkpoint image {} doesn't exist",
+                imagePath
+            )));
+        }
+
+        let mut c = Self::Create(
+            id,
+            RunAction::Create,
+            spec,
+            conf,
+            bundleDir,
+            consoleSocket,
+            pidFile,
+            "",
+            detach,
+            pivot,
+            imagePath,
+        )?;
+
+        c.changeStatus(Status::Running);
+        c.Save()?;
+        return Ok(c);
+    }
+
     // Wait waits for the container to exit, and returns its WaitStatus.
     // Call to wait on a stopped container is needed to retrieve the exit status
     // and wait returns immediately.
@@ -891,11 +941,95 @@ impl Container {
         return self.Save();
     }
 
+    // Checkpoint saves the state of the sandbox of the container to the
+    // imagePath directory. Unless leaveRunning is set the container is stopped
+    // once the image is written, as runc does.
+    pub fn Checkpoint(&mut self, imagePath: &str, leaveRunning: bool) -> Result<()> {
+        info!("Checkpoint container {} to {}", self.ID, imagePath);
+
+        let _unlock = self.Lock()?;
+
+        self.RequireStatus("checkpoint", &[Status::Running, Status::Paused])?;
+        if !IsRoot(&self.Spec) {
+            return Err(Error::Common(format!(
+                "cannot checkpoint container {}: only the root container of a sandbox can be checkpointed",
+                self.ID
+            )));
+        }
+
+        fs::create_dir_all(imagePath).map_err(|e| {
+            Error::Common(format!("create checkpoint image dir {} fail: {:?}", imagePath, e))
+        })?;
+        let imagePath = fs::canonicalize(imagePath)
+            .map_err(|e| Error::Common(format!("Container::Checkpoint error is {:?}", e)))?;
+        let imagePath = imagePath.to_str().unwrap().to_string();
+
+        let args = CheckpointArgs {
+            cid: self.ID.to_string(),
+            imagePath: imagePath,
+            leaveRunning: leaveRunning,
+        };
+
+        self.Sandbox.as_ref().unwrap().Checkpoint(&self.ID, args)?;
+
+        if !leaveRunning {
+            self.Stop()?;
+            self.changeStatus(Status::Stopped);
+        }
+
+        return self.Save();
+    }
+
     pub fn Processes(&self) -> Result<Vec<ProcessInfo>> {
         self.RequireStatus("get processes of", &[Status::Running, Status::Paused])?;
         return self.Sandbox.as_ref().unwrap().Processes(&self.ID);
     }
 
+    // Stats returns the resource usage of the container.
+    pub fn Stats(&self) -> Result<ContainerStats> {
+        self.RequireStatus("get stats of", &[Status::Running, Status::Paused])?;
+        return self.Sandbox.as_ref().unwrap().ContainerStats(&self.ID);
+    }
+
+    // Update applies new resource limits to the running container. The limits
+    // set in res replace the ones in the spec of the container, the others are
+    // kept.
+    pub fn Update(&mut self, res: LinuxResources) -> Result<()> {
+        info!("Update container {}", &self.ID);
+
+        let _unlock = self.Lock()?;
+
+        self.RequireStatus("update", &[Status::Running, Status::Paused])?;
+
+        // The cgroup of the sandbox is shared by all its containers, only the
+        // root container owns it.
+        if IsRoot(&self.Spec) {
+            if let Some(cg) = &self.Sandbox.as_ref().unwrap().Cgroup {
+                cg.Update(&res)?;
+            }
+        }
+
+        if self.Spec.linux.is_none() {
+            self.Spec.linux = Some(Linux::default());
+        }
+
+        let linux = self.Spec.linux.as_mut().unwrap();
+        match &mut linux.resources {
+            None => linux.resources = Some(res),
+            Some(resources) => MergeResources(resources, res),
+        }
+
+        let args = UpdateArgs {
+            cid: self.ID.to_string(),
+            memoryLimit: CreateMemoryLimit(&self.Spec),
+            cpuLimit: CreateCPULimit(&self.Spec),
+            pidsLimit: CreatePidsLimit(&self.Spec),
+        };
+
+        self.Sandbox.as_ref().unwrap().UpdateContainer(&self.ID, args)?;
+        return self.Save();
+    }
+
     // Start starts running the containerized process inside the sandbox.
     pub fn Start(&mut self) -> Result<()> {
         info!("Start container {}", &self.ID);
@@ -1127,6 +1261,68 @@ impl Container {
     }
 }
 
+// MergeResources overrides the resource limits in dst with the ones set in
+// src.
+pub fn MergeResources(dst: &mut LinuxResources, src: LinuxResources) {
+    if let Some(m) = src.memory {
+        let dm = dst.memory.get_or_insert_with(LinuxMemory::default);
+        if m.limit.is_some() {
+            dm.limit = m.limit;
+        }
+        if m.reservation.is_some() {
+            dm.reservation = m.reservation;
+        }
+        if m.swap.is_some() {
+            dm.swap = m.swap;
+        }
+        if m.kernel.is_some() {
+            dm.kernel = m.kernel;
+        }
+        if m.kernel_tcp.is_some() {
+            dm.kernel_tcp = m.kernel_tcp;
+        }
+        if m.swappiness.is_some() {
+            dm.swappiness = m.swappiness;
+        }
+        if m.disableOOMKiller.is_some() {
+            dm.disableOOMKiller = m.disableOOMKiller;
+        }
+    }
+
+    if let Some(c) = src.cpu {
+        let dc = dst.cpu.get_or_insert_with(LinuxCPU::default);
+        if c.shares.is_some() {
+            dc.shares = c.shares;
+        }
+        if c.quota.is_some() {
+            dc.quota = c.quota;
+        }
+        if c.period.is_some() {
+            dc.period = c.period;
+        }
+        if c.realtime_runtime.is_some() {
+            dc.realtime_runtime = c.realtime_runtime;
+        }
+        if c.realtime_period.is_some() {
+            dc.realtime_period = c.realtime_period;
+        }
+        if c.cpus.len() != 0 {
+            dc.cpus = c.cpus;
+        }
+        if c.mems.len() != 0 {
+            dc.mems = c.mems;
+        }
+    }
+
+    if src.pids.is_some() {
+        dst.pids = src.pids;
+    }
+
+    if src.block_io.is_some() {
+        dst.block_io = src.block_io;
+    }
+}
+
 pub fn runInCgroup(cg: &Option<Cgroup>, mut f: impl FnMut() -> Result<()>) -> Result<()> {
     if cg.is_none() {
         return f();
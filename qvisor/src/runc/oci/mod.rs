@@ -565,10 +565,13 @@ pub struct LinuxDevice {
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 #[repr(u32)]
 pub enum LinuxSeccompAction {
+    #[serde(alias = "SCMP_ACT_KILL_THREAD")]
     SCMP_ACT_KILL = 0x00000000,
+    SCMP_ACT_KILL_PROCESS = 0x80000000,
     SCMP_ACT_TRAP = 0x00030000,
     SCMP_ACT_ERRNO = 0x00050001, /* ERRNO + EPERM */
     SCMP_ACT_TRACE = 0x7ff00001, /* TRACE + EPERM */
+    SCMP_ACT_LOG = 0x7ffc0000,
     SCMP_ACT_ALLOW = 0x7fff0000,
 }
 
@@ -640,6 +643,8 @@ pub struct LinuxSyscall {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub names: Vec<String>,
     pub action: LinuxSeccompAction,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "errnoRet")]
+    pub errno_ret: Option<u32>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub args: Vec<LinuxSeccompArg>,
 }
@@ -648,6 +653,12 @@ pub struct LinuxSyscall {
 pub struct LinuxSeccomp {
     #[serde(rename = "defaultAction")]
     pub default_action: LinuxSeccompAction,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "defaultErrnoRet"
+    )]
+    pub default_errno_ret: Option<u32>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub architectures: Vec<Arch>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -49,6 +49,10 @@ pub struct Args {
     pub Rootfs: String,
 
     pub RDMASvcCliSock: i32,
+
+    // RestoreImage is the checkpoint image directory to restore the sandbox
+    // from, the sandbox boots normally when it is empty.
+    pub RestoreImage: String,
 }
 
 impl Args {
@@ -16,6 +16,7 @@ pub mod console;
 pub mod fs;
 pub mod loader;
 pub mod sandbox_process;
+pub mod seccomp;
 pub mod signal_handle;
 pub mod util;
 pub mod vm;
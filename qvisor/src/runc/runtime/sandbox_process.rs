@@ -41,6 +41,7 @@ use crate::runc::shim::shim_task::ShimTask;
 
 use super::console::*;
 use super::loader::*;
+use super::seccomp::*;
 use super::signal_handle::*;
 use super::super::cmd::config::*;
 use super::super::container::*;
@@ -51,10 +52,12 @@ use super::super::oci::*;
 use super::super::shim::container_io::*;
 use super::super::specutils::specutils::*;
 use super::super::super::console::pty::*;
+use super::super::super::heap_alloc::RESTORE_IMAGE_ENV;
 use super::super::super::console::unix_socket::UnixSocket;
 use super::super::super::namespace::*;
 use super::super::super::qlib::common::*;
 use super::super::super::qlib::config::DebugLevel;
+use super::super::super::qlib::config::HostSeccompMode;
 use super::super::super::qlib::linux_def::*;
 use super::super::super::qlib::path::*;
 use super::super::super::qlib::unix_socket;
@@ -113,6 +116,10 @@ pub struct SandboxProcess {
 
     pub UserNS: bool,
 
+    // HostUserNS is set when the sandbox runs in the user ns created for the
+    // host seccomp confinement instead of the user ns of the spec.
+    pub HostUserNS: bool,
+
     pub CCond: Cond,
     pub PCond: Cond,
 
@@ -122,6 +129,9 @@ pub struct SandboxProcess {
     pub SandboxRootDir: String,
 
     pub TaskSocket: Option<String>,
+
+    // RestoreImage is the checkpoint image directory the sandbox is restored from
+    pub RestoreImage: String,
 }
 
 impl SandboxProcess {
@@ -150,11 +160,13 @@ impl SandboxProcess {
             UidMappings: Vec::new(),
             GidMappings: Vec::new(),
             UserNS: false,
+            HostUserNS: false,
             CCond: Cond::New()?,
             PCond: Cond::New()?,
             Rootfs: "".to_string(),
             SandboxRootDir: Join(QUARK_SANDBOX_ROOT_PATH, id),
             TaskSocket: None,
+            RestoreImage: "".to_string(),
         };
 
         let spec = &process.spec;
@@ -170,7 +182,7 @@ impl SandboxProcess {
         return Ok(process);
     }
 
-    pub fn Run(&self, controlSock: i32, rdmaSvcCliSock: i32, taskSockFd: i32) {
+    pub fn Run(&self, kvmfd: i32, controlSock: i32, rdmaSvcCliSock: i32, taskSockFd: i32) -> Result<()> {
         let id = &self.containerId;
         let sid = unsafe {
             //signal (SIGHUP, SIG_IGN);
@@ -183,7 +195,6 @@ impl SandboxProcess {
 
         PrepareHandler().unwrap();
 
-        let kvmfd = Kvm::open_with_cloexec(false).expect("can't open kvm");
         let mut args = Args::default();
         args.ID = id.to_string();
         args.KvmFd = kvmfd;
@@ -194,18 +205,40 @@ impl SandboxProcess {
         args.Rootfs = Join(QUARK_SANDBOX_ROOT_PATH, id.as_str());
         args.ControlSock = controlSock;
         args.RDMASvcCliSock = rdmaSvcCliSock;
+        args.RestoreImage = self.RestoreImage.to_string();
         if taskSockFd > 0 {
             args.Pivot = false;
         }
 
         let exitStatus = match VirtualMachine::Init(args) {
             Ok(mut vm) => {
+                let hostSeccomp = QUARK_CONFIG.lock().HostSeccomp;
                 if taskSockFd > 0 {
                     if self.pivot {
                         crate::VMS.lock().PivotRoot(&self.SandboxRootDir);
                     }
+                } else if hostSeccomp != HostSeccompMode::Disable {
+                    // pivot into the sandbox root now instead of when the
+                    // first process is loaded, as neither pivot_root nor
+                    // CAP_SYS_ADMIN are left to qvisor after the boot
+                    let mut vms = crate::VMS.lock();
+                    if vms.pivot {
+                        let rootfs = vms.args.as_ref().unwrap().Rootfs.to_string();
+                        vms.PivotRoot(&rootfs);
+                        vms.pivot = false;
+                    }
+                }
+
+                // capabilities are per thread, they are dropped before the
+                // task service and vcpu threads are created
+                if hostSeccomp != HostSeccompMode::Disable {
+                    DropCapabilities()?;
+                }
+
+                if taskSockFd > 0 {
                     self.StartTaskService(taskSockFd as RawFd).unwrap();
                 }
+                InstallHostSeccomp(hostSeccomp)?;
                 let ret = vm.run().expect("vm.run() fail");
                 ret
             }
@@ -319,6 +352,23 @@ impl SandboxProcess {
         //todo: handle mount ns separated, to avoid crash OS when pivot root
         cf |= LinuxNamespaceType::mount as i32;
 
+        // with the host seccomp, the sandbox runs in a fresh user ns when the
+        // spec doesn't have one. The namespaces of the spec are owned by the
+        // host user ns, so they are joined before it is created, which isn't
+        // possible for a mount ns as the sandbox root is set up in it.
+        let hasUserNS = nss.iter().any(|ns| ns.typ == LinuxNamespaceType::user);
+        if !hasUserNS && QUARK_CONFIG.lock().HostSeccomp != HostSeccompMode::Disable {
+            let mountNS = LinuxNamespaceType::mount as i32;
+            if self.ToEnterNS.iter().any(|&(space, _)| space == mountNS) {
+                return Err(Error::Common(
+                    "host seccomp can't confine a sandbox joining a mount namespace".to_string(),
+                ));
+            }
+
+            cf |= LinuxNamespaceType::user as i32;
+            self.HostUserNS = true;
+        }
+
         if cf & LinuxNamespaceType::user as i32 != 0 {
             self.UserNS = true;
         }
@@ -330,6 +380,15 @@ impl SandboxProcess {
     pub fn EnableNamespace(&self) -> Result<()> {
         let mut mountFd = -1;
 
+        let mut toEnterNS = &self.ToEnterNS[..];
+        if self.HostUserNS {
+            for &(space, fd) in toEnterNS {
+                SetNamespace(fd, space)?;
+                Close(fd)?;
+            }
+            toEnterNS = &[];
+        }
+
         if self.UserNS {
             Unshare(CloneOp::CLONE_NEWUSER)?;
         }
@@ -343,7 +402,7 @@ impl SandboxProcess {
 
         error!("EnableNamespace ToEnterNS is {:?}", &self.ToEnterNS);
 
-        for &(space, fd) in &self.ToEnterNS {
+        for &(space, fd) in toEnterNS {
             if space == LinuxNamespaceType::mount as i32 {
                 // enter mount ns last
                 mountFd = fd;
@@ -510,6 +569,11 @@ impl SandboxProcess {
         cmd.arg("--pipefd");
         cmd.arg(&format!("{}", fd0));
 
+        if self.RestoreImage.len() > 0 {
+            // the heap of the restored sandbox is loaded from the image
+            cmd.env(RESTORE_IMAGE_ENV, &self.RestoreImage);
+        }
+
         let mut ptyMaster = None;
 
         if terminal {
@@ -592,6 +656,10 @@ impl SandboxProcess {
         cmd.arg("--pipefd");
         cmd.arg(&format!("{}", fd0));
 
+        if self.RestoreImage.len() > 0 {
+            cmd.env(RESTORE_IMAGE_ENV, &self.RestoreImage);
+        }
+
         io.Set(&mut cmd)?;
 
         let child = cmd.spawn().expect("Boot command failed to start");
@@ -653,13 +721,16 @@ impl SandboxProcess {
             // TODO control socket may not be abstract
             controlSock = USocket::CreateServerSocket(&addr).expect("can't create control sock");
         }
+        // kvm is opened before entering the user ns, whose ids may not have
+        // access to it
+        let kvmfd = Kvm::open_with_cloexec(false).expect("can't open kvm");
         self.MakeSandboxRootDirectory()?;
         self.EnableNamespace()?;
         if taskSockFd != 0 {
             // It seems control socket should be created in the same net ns
             controlSock = USocket::CreateServerSocket(&addr).expect("can't create control sock");
         }
-        self.Run(controlSock, rdmaSvcCliSock, taskSockFd);
+        self.Run(kvmfd, controlSock, rdmaSvcCliSock, taskSockFd)?;
         panic!("Child: should never reach here");
     }
 
@@ -669,9 +740,20 @@ impl SandboxProcess {
         self.CCond.Wait()?;
 
         if self.UserNS {
-            // write uid/gid map
-            WriteIDMapping(&format!("/proc/{}/uid_map", child), &linux.uid_mappings)?;
-            WriteIDMapping(&format!("/proc/{}/gid_map", child), &linux.gid_mappings)?;
+            // write uid/gid map, the user ns created for the host seccomp maps
+            // the sandbox ids to unprivileged host ids
+            let confined = [LinuxIDMapping {
+                host_id: QUARK_CONFIG.lock().HostSeccompIDBase,
+                container_id: 0,
+                size: 65536,
+            }];
+            let (uidMappings, gidMappings) = if self.HostUserNS {
+                (&confined[..], &confined[..])
+            } else {
+                (&linux.uid_mappings[..], &linux.gid_mappings[..])
+            };
+            WriteIDMapping(&format!("/proc/{}/uid_map", child), uidMappings)?;
+            WriteIDMapping(&format!("/proc/{}/gid_map", child), gidMappings)?;
         }
 
         self.PCond.Notify()?;
@@ -0,0 +1,449 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::vec::Vec;
+use caps::*;
+use libc;
+
+use super::super::super::qlib::bpf::*;
+use super::super::super::qlib::common::*;
+use super::super::super::qlib::config::HostSeccompMode;
+use super::super::super::qlib::linux::seccomp::*;
+
+// offsets of the seccomp_data fields evaluated by the host filter
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+
+fn SeccompDataArgLow(i: u32) -> u32 {
+    return 16 + 8 * i;
+}
+
+// the x32 syscall bit, x32 syscalls share the x86_64 audit arch
+const X32_SYSCALL_BIT: u32 = 0x40000000;
+
+const CLONE_NEW_MASK: u32 = (libc::CLONE_NEWNS
+    | libc::CLONE_NEWCGROUP
+    | libc::CLONE_NEWUTS
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUSER
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWNET) as u32;
+
+// HOST_SYSCALLS are the syscalls qvisor issues after the boot, from the
+// VMSpace host calls, the io_uring and KVM paths, the hibernation and the
+// ucall/task services. The sandbox root is pivoted before the filter is
+// installed and the subcontainer rootfs are mounted by the runtime outside of
+// the sandbox, so mount, umount2, pivot_root and chroot are not allowed.
+// clone, socket, prctl, ioctl, mmap, mprotect, kill and tgkill are allowed
+// separately with argument checks.
+pub const HOST_SYSCALLS: &[i64] = &[
+    // file io
+    libc::SYS_read,
+    libc::SYS_write,
+    libc::SYS_readv,
+    libc::SYS_writev,
+    libc::SYS_pread64,
+    libc::SYS_pwrite64,
+    libc::SYS_preadv,
+    libc::SYS_pwritev,
+    libc::SYS_preadv2,
+    libc::SYS_pwritev2,
+    libc::SYS_lseek,
+    libc::SYS_open,
+    libc::SYS_openat,
+    libc::SYS_close,
+    libc::SYS_dup,
+    libc::SYS_dup2,
+    libc::SYS_dup3,
+    libc::SYS_pipe2,
+    libc::SYS_fcntl,
+    libc::SYS_flock,
+    libc::SYS_fstat,
+    libc::SYS_stat,
+    libc::SYS_lstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_fstatfs,
+    libc::SYS_statfs,
+    libc::SYS_getdents64,
+    libc::SYS_readlink,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_access,
+    libc::SYS_ftruncate,
+    libc::SYS_fallocate,
+    libc::SYS_fsync,
+    libc::SYS_fdatasync,
+    libc::SYS_sync,
+    libc::SYS_syncfs,
+    libc::SYS_sync_file_range,
+    libc::SYS_readahead,
+    libc::SYS_fadvise64,
+    libc::SYS_utimensat,
+    libc::SYS_fchmod,
+    libc::SYS_fchmodat,
+    libc::SYS_chmod,
+    libc::SYS_fchown,
+    libc::SYS_fchownat,
+    libc::SYS_chown,
+    libc::SYS_lchown,
+    libc::SYS_mkdir,
+    libc::SYS_mkdirat,
+    libc::SYS_mknodat,
+    libc::SYS_unlink,
+    libc::SYS_unlinkat,
+    libc::SYS_rmdir,
+    libc::SYS_rename,
+    libc::SYS_renameat,
+    libc::SYS_renameat2,
+    libc::SYS_link,
+    libc::SYS_linkat,
+    libc::SYS_symlink,
+    libc::SYS_symlinkat,
+    libc::SYS_getxattr,
+    libc::SYS_lgetxattr,
+    libc::SYS_fgetxattr,
+    libc::SYS_lsetxattr,
+    libc::SYS_fsetxattr,
+    libc::SYS_flistxattr,
+    libc::SYS_fremovexattr,
+    libc::SYS_memfd_create,
+    libc::SYS_getcwd,
+    libc::SYS_chdir,
+    libc::SYS_fchdir,
+    libc::SYS_umask,
+    // notification
+    libc::SYS_epoll_create1,
+    libc::SYS_epoll_ctl,
+    libc::SYS_epoll_wait,
+    libc::SYS_epoll_pwait,
+    libc::SYS_poll,
+    libc::SYS_ppoll,
+    libc::SYS_select,
+    libc::SYS_pselect6,
+    libc::SYS_eventfd2,
+    libc::SYS_inotify_init1,
+    libc::SYS_inotify_add_watch,
+    libc::SYS_inotify_rm_watch,
+    libc::SYS_io_uring_setup,
+    libc::SYS_io_uring_enter,
+    libc::SYS_io_uring_register,
+    // network
+    libc::SYS_socketpair,
+    libc::SYS_bind,
+    libc::SYS_listen,
+    libc::SYS_accept,
+    libc::SYS_accept4,
+    libc::SYS_connect,
+    libc::SYS_shutdown,
+    libc::SYS_getsockname,
+    libc::SYS_getpeername,
+    libc::SYS_getsockopt,
+    libc::SYS_setsockopt,
+    libc::SYS_sendto,
+    libc::SYS_recvfrom,
+    libc::SYS_sendmsg,
+    libc::SYS_recvmsg,
+    libc::SYS_sendmmsg,
+    libc::SYS_recvmmsg,
+    // memory
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_madvise,
+    libc::SYS_msync,
+    libc::SYS_mlock,
+    libc::SYS_mlock2,
+    libc::SYS_munlock,
+    libc::SYS_brk,
+    libc::SYS_membarrier,
+    libc::SYS_userfaultfd,
+    // threads, signals and time
+    libc::SYS_futex,
+    libc::SYS_set_robust_list,
+    libc::SYS_get_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_sched_yield,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_sched_setaffinity,
+    libc::SYS_getcpu,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_rt_sigtimedwait,
+    libc::SYS_sigaltstack,
+    libc::SYS_wait4,
+    libc::SYS_waitid,
+    libc::SYS_nanosleep,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_getres,
+    libc::SYS_gettimeofday,
+    libc::SYS_timerfd_create,
+    libc::SYS_timerfd_settime,
+    libc::SYS_timerfd_gettime,
+    // process
+    libc::SYS_getpid,
+    libc::SYS_getppid,
+    libc::SYS_gettid,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    libc::SYS_getrlimit,
+    libc::SYS_setrlimit,
+    libc::SYS_prlimit64,
+    libc::SYS_getrusage,
+    libc::SYS_sysinfo,
+    libc::SYS_uname,
+    libc::SYS_getrandom,
+    libc::SYS_arch_prctl,
+    libc::SYS_setns,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+];
+
+// SOCKET_DOMAINS are the socket domains qvisor creates: the control, ucall
+// and console unix sockets, the host network sockets and the netlink sockets
+// used to query the host interfaces.
+const SOCKET_DOMAINS: &[u32] = &[
+    libc::AF_UNIX as u32,
+    libc::AF_INET as u32,
+    libc::AF_INET6 as u32,
+    libc::AF_NETLINK as u32,
+];
+
+// PRCTL_OPTIONS are the prctl options used by the runtime threads.
+const PRCTL_OPTIONS: &[u32] = &[
+    libc::PR_SET_NAME as u32,
+    libc::PR_GET_NAME as u32,
+    libc::PR_GET_DUMPABLE as u32,
+];
+
+// IOCTL_TYPES are the types, the second byte of the request, of the ioctls
+// qvisor issues: KVM, userfaultfd for the hibernation, the terminal and FIO*
+// requests of the console and host fds, and the socket requests of the host
+// network.
+const IOCTL_TYPES: &[u32] = &[0xAE00, 0xAA00, 0x5400, 0x8900];
+const IOCTL_TYPE_MASK: u32 = 0xFF00;
+
+// ArgInList appends a filter block which allows the syscall nr when its
+// argument arg is one of vals and returns action otherwise.
+fn ArgInList(prog: &mut Vec<BPFInstruction>, nr: i64, arg: u32, vals: &[u32], action: u32) {
+    ArgMaskedInList(prog, nr, arg, u32::MAX, vals, action);
+}
+
+// ArgMaskedInList appends a filter block which allows the syscall nr when its
+// argument arg masked by mask is one of vals and returns action otherwise.
+fn ArgMaskedInList(
+    prog: &mut Vec<BPFInstruction>,
+    nr: i64,
+    arg: u32,
+    mask: u32,
+    vals: &[u32],
+    action: u32,
+) {
+    // jeq nr + ld arg + and mask + one jeq per val + ret action + ret allow
+    let len = vals.len() + 4;
+    prog.push(Jump(JMP | JEQ | K, nr as u32, 0, len as u8));
+    prog.push(Stmt(LD | W | ABS, SeccompDataArgLow(arg)));
+    prog.push(Stmt(ALU | AND | K, mask));
+    for i in 0..vals.len() {
+        let toAllow = (vals.len() - i) as u8;
+        prog.push(Jump(JMP | JEQ | K, vals[i], toAllow, 0));
+    }
+    prog.push(Stmt(RET | K, action));
+    prog.push(Stmt(RET | K, SECCOMP_RET_ALLOW));
+    // the syscall nr is reloaded for the following checks
+    prog.push(Stmt(LD | W | ABS, SECCOMP_DATA_NR));
+}
+
+// ArgWithoutBits appends a filter block which allows the syscall nr when its
+// argument arg has none of bits set and returns action otherwise.
+fn ArgWithoutBits(prog: &mut Vec<BPFInstruction>, nr: i64, arg: u32, bits: u32, action: u32) {
+    prog.push(Jump(JMP | JEQ | K, nr as u32, 0, 4));
+    prog.push(Stmt(LD | W | ABS, SeccompDataArgLow(arg)));
+    prog.push(Jump(JMP | JSET | K, bits, 0, 1));
+    prog.push(Stmt(RET | K, action));
+    prog.push(Stmt(RET | K, SECCOMP_RET_ALLOW));
+    prog.push(Stmt(LD | W | ABS, SECCOMP_DATA_NR));
+}
+
+// HostSeccompFilter builds the host seccomp-bpf program of the qvisor process
+// pid. The violations are logged by the host kernel with the action of
+// SECCOMP_RET_LOG in the log mode and kill the qvisor process otherwise.
+pub fn HostSeccompFilter(mode: HostSeccompMode, pid: i32) -> Vec<BPFInstruction> {
+    let deny = if mode == HostSeccompMode::Log {
+        SECCOMP_RET_LOG
+    } else {
+        SECCOMP_RET_KILL_PROCESS
+    };
+
+    let mut prog = Vec::new();
+
+    prog.push(Stmt(LD | W | ABS, SECCOMP_DATA_ARCH));
+    prog.push(Jump(JMP | JEQ | K, AUDIT_ARCH_X86_64, 1, 0));
+    prog.push(Stmt(RET | K, deny));
+    prog.push(Stmt(LD | W | ABS, SECCOMP_DATA_NR));
+    prog.push(Jump(JMP | JGE | K, X32_SYSCALL_BIT, 0, 1));
+    prog.push(Stmt(RET | K, deny));
+
+    for &nr in HOST_SYSCALLS {
+        prog.push(Jump(JMP | JEQ | K, nr as u32, 0, 1));
+        prog.push(Stmt(RET | K, SECCOMP_RET_ALLOW));
+    }
+
+    // threads may be created, but not in new namespaces
+    prog.push(Jump(JMP | JEQ | K, libc::SYS_clone as u32, 0, 4));
+    prog.push(Stmt(LD | W | ABS, SeccompDataArgLow(0)));
+    prog.push(Jump(JMP | JSET | K, CLONE_NEW_MASK, 0, 1));
+    prog.push(Stmt(RET | K, deny));
+    prog.push(Stmt(RET | K, SECCOMP_RET_ALLOW));
+    prog.push(Stmt(LD | W | ABS, SECCOMP_DATA_NR));
+
+    // the clone3 flags are behind a pointer, make glibc fall back to clone
+    prog.push(Jump(JMP | JEQ | K, libc::SYS_clone3 as u32, 0, 1));
+    prog.push(Stmt(RET | K, SECCOMP_RET_ERRNO | libc::ENOSYS as u32));
+
+    ArgInList(&mut prog, libc::SYS_socket, 0, SOCKET_DOMAINS, deny);
+    ArgInList(&mut prog, libc::SYS_prctl, 0, PRCTL_OPTIONS, deny);
+    ArgMaskedInList(
+        &mut prog,
+        libc::SYS_ioctl,
+        1,
+        IOCTL_TYPE_MASK,
+        IOCTL_TYPES,
+        deny,
+    );
+
+    // the guest memory and the host buffers are never executable
+    ArgWithoutBits(&mut prog, libc::SYS_mmap, 2, libc::PROT_EXEC as u32, deny);
+    ArgWithoutBits(
+        &mut prog,
+        libc::SYS_mprotect,
+        2,
+        libc::PROT_EXEC as u32,
+        deny,
+    );
+
+    // signals are only sent to the qvisor threads, and SIGKILL to the process
+    // group of the sandbox when the whole sandbox is killed
+    ArgInList(&mut prog, libc::SYS_kill, 0, &[0, pid as u32], deny);
+    ArgInList(&mut prog, libc::SYS_tgkill, 0, &[pid as u32], deny);
+
+    prog.push(Stmt(RET | K, deny));
+    return prog;
+}
+
+// InstallHostSeccomp installs the host seccomp filter on all the threads of
+// the qvisor process. The threads created later inherit the filter.
+pub fn InstallHostSeccomp(mode: HostSeccompMode) -> Result<()> {
+    if mode == HostSeccompMode::Disable {
+        return Ok(());
+    }
+
+    let prog = HostSeccompFilter(mode, unsafe { libc::getpid() });
+    if prog.len() > MAX_INSTRUCTIONS {
+        return Err(Error::Common(format!(
+            "host seccomp filter is too long: {}",
+            prog.len()
+        )));
+    }
+
+    let fprog = SockFprog {
+        Len: prog.len() as u16,
+        pad: [0; 6],
+        Filter: &prog[0] as *const _ as u64,
+    };
+
+    let ret = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+    if ret < 0 {
+        return Err(Error::SysError(errno::errno().0));
+    }
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            SECCOMP_SET_MODE_FILTER,
+            SECCOMP_FILTER_FLAG_TSYNC,
+            &fprog as *const _ as u64,
+        )
+    };
+    if ret < 0 {
+        return Err(Error::SysError(errno::errno().0));
+    }
+
+    // with TSYNC, a positive return is the tid of the thread which can't be
+    // synchronized
+    if ret > 0 {
+        return Err(Error::Common(format!(
+            "host seccomp filter can't be synchronized to thread {}",
+            ret
+        )));
+    }
+
+    info!("host seccomp filter is installed in {:?} mode", mode);
+    return Ok(());
+}
+
+// SANDBOX_CAPS are the capabilities the sandbox process keeps after the boot,
+// for the host file operations on behalf of the guest, the memory locking and
+// binding the low ports of the host network. The sandbox root is pivoted
+// before they are dropped.
+const SANDBOX_CAPS: &[Capability] = &[
+    Capability::CAP_CHOWN,
+    Capability::CAP_DAC_READ_SEARCH,
+    Capability::CAP_FOWNER,
+    Capability::CAP_FSETID,
+    Capability::CAP_KILL,
+    Capability::CAP_MKNOD,
+    Capability::CAP_SYS_RESOURCE,
+    Capability::CAP_IPC_LOCK,
+    Capability::CAP_NET_BIND_SERVICE,
+];
+
+// DropCapabilities drops all the capabilities which are not in SANDBOX_CAPS
+// from the bounding, permitted, effective and inheritable sets. Capabilities
+// are per thread, so it has to be called before the process creates threads.
+pub fn DropCapabilities() -> Result<()> {
+    let mut keep = CapsHashSet::new();
+    for cap in SANDBOX_CAPS {
+        keep.insert(*cap);
+    }
+
+    for cap in ::caps::all() {
+        if keep.contains(&cap) {
+            continue;
+        }
+
+        if has_cap(None, CapSet::Bounding, cap).unwrap_or(false) {
+            ::caps::drop(None, CapSet::Bounding, cap)
+                .map_err(|e| Error::IOError(format!("io error is {:?}", e)))?;
+        }
+    }
+
+    let permitted: CapsHashSet = read(None, CapSet::Permitted)
+        .map_err(|e| Error::IOError(format!("io error is {:?}", e)))?
+        .intersection(&keep)
+        .cloned()
+        .collect();
+
+    set(None, CapSet::Inheritable, CapsHashSet::new())
+        .map_err(|e| Error::IOError(format!("io error is {:?}", e)))?;
+    set(None, CapSet::Effective, permitted.clone())
+        .map_err(|e| Error::IOError(format!("io error is {:?}", e)))?;
+    set(None, CapSet::Permitted, permitted)
+        .map_err(|e| Error::IOError(format!("io error is {:?}", e)))?;
+
+    return Ok(());
+}
@@ -28,6 +28,7 @@ use crate::qlib::MAX_VCPU_COUNT;
 //use crate::vmspace::hibernate::HiberMgr;
 
 use super::super::super::elf_loader::*;
+use super::super::super::heap_alloc::*;
 use super::super::super::kvm_vcpu::*;
 use super::super::super::print::LOG;
 use super::super::super::qlib::addr;
@@ -48,13 +49,15 @@ use super::super::super::qlib::perf_tunning::*;
 use super::super::super::qlib::task_mgr::*;
 use super::super::super::qlib::ShareSpace;
 use super::super::super::runc::runtime::loader::*;
+use super::super::super::runc::runtime::signal_handle::StartSignalHandle;
 use super::super::super::syncmgr;
+use super::super::super::vmspace::checkpoint::*;
 use super::super::super::vmspace::*;
 use super::super::super::SHARE_SPACE;
 use super::super::super::SHARE_SPACE_STRUCT;
 use super::super::super::{
-    ThreadId, KERNEL_IO_THREAD, PMA_KEEPER, QUARK_CONFIG, ROOT_CONTAINER_ID, THREAD_ID, URING_MGR,
-    VCPU, VMS,
+    ThreadId, FD_NOTIFIER, GLOBAL_ALLOCATOR, KERNEL_IO_THREAD, PMA_KEEPER, QUARK_CONFIG,
+    ROOT_CONTAINER_ID, THREAD_ID, URING_MGR, VCPU, VMS,
 };
 
 lazy_static! {
@@ -94,6 +97,8 @@ pub struct VirtualMachine {
     pub vmfd: VmFd,
     pub vcpus: Vec<Arc<KVMVcpu>>,
     pub elf: KernelELF,
+    // restored is true when the sandbox is restored from a checkpoint image
+    pub restored: bool,
 }
 
 impl VirtualMachine {
@@ -230,6 +235,80 @@ impl VirtualMachine {
         error!("VM::InitShareSpace, after call init 2");
     }
 
+    // RestoreShareSpace reattaches the ShareSpace loaded from a checkpoint image
+    // to the current process. The host fds which the guest knows by number are
+    // recreated at the numbers recorded in the image.
+    pub fn RestoreShareSpace(vmfd: &VmFd, image: &CheckpointImage, controlSock: i32) -> Result<()> {
+        SHARE_SPACE.SetValue(image.ShareSpaceAddr);
+        SHARESPACE.SetValue(image.ShareSpaceAddr);
+
+        unsafe {
+            vcpu::CPU_LOCAL.Init(&SHARESPACE.scheduler.VcpuArr);
+        }
+
+        let sharespace = unsafe { &mut *(image.ShareSpaceAddr as *mut ShareSpace) };
+        let logfd = super::super::super::print::LOG.Logfd();
+        URING_MGR.lock().Init();
+        URING_MGR.lock().Addfd(logfd).unwrap();
+        sharespace.SetLogfd(logfd);
+        sharespace
+            .hostEpollfd
+            .store(FD_NOTIFIER.Epollfd(), Ordering::SeqCst);
+
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if eventfd < 0 {
+            return Err(Error::SysError(errno::errno().0));
+        }
+        MoveFd(eventfd, sharespace.ioMgr.eventfd)?;
+
+        for i in 0..sharespace.scheduler.VcpuArr.len() {
+            sharespace.scheduler.VcpuArr[i].Restore()?;
+            let addr = MemoryDef::KVM_IOEVENTFD_BASEADDR + (i as u64) * 8;
+            Self::IoEventfdAddEvent(
+                vmfd.as_raw_fd(),
+                addr,
+                sharespace.scheduler.VcpuArr[i].eventfd,
+            );
+        }
+
+        MoveFd(controlSock, sharespace.controlSock)?;
+        VMS.lock().controlSock = sharespace.controlSock;
+        VMSpace::BlockFd(sharespace.controlSock);
+
+        KERNEL_IO_THREAD.Init(sharespace.scheduler.VcpuArr[0].eventfd);
+        URING_MGR
+            .lock()
+            .SetupEventfd(sharespace.scheduler.VcpuArr[0].eventfd);
+        URING_MGR
+            .lock()
+            .Addfd(sharespace.HostHostEpollfd())
+            .unwrap();
+        URING_MGR.lock().Addfd(sharespace.controlSock).unwrap();
+        sharespace.SetIOUringsAddr(URING_MGR.lock().IOUringsAddr());
+        IOURING.SetValue(sharespace.GetIOUringAddr());
+
+        image.RestoreHostFds()?;
+
+        unsafe {
+            KERNEL_PAGETABLE.SetRoot(image.PageTableRoot);
+            PAGE_MGR.SetValue(sharespace.GetPageMgrAddr());
+
+            KERNEL_STACK_ALLOCATOR.Init(AlignedAllocator::New(
+                MemoryDef::DEFAULT_STACK_SIZE as usize,
+                MemoryDef::DEFAULT_STACK_SIZE as usize,
+            ));
+
+            task::InitSingleton();
+            futex::InitSingleton();
+            timer::InitSingleton();
+        }
+
+        let syncPrint = sharespace.config.read().SyncPrint();
+        super::super::super::print::SetSyncPrint(syncPrint);
+        sharespace.restored.store(true, Ordering::SeqCst);
+        return Ok(());
+    }
+
     pub fn Init(args: Args /*args: &Args, kvmfd: i32*/) -> Result<Self> {
         PerfGoto(PerfType::Other);
 
@@ -243,6 +322,14 @@ impl VirtualMachine {
 
         let kvmfd = args.KvmFd;
 
+        let image = if args.RestoreImage.len() > 0 {
+            let image = CheckpointImage::Load(&args.RestoreImage)?;
+            image.ReserveFds(args.ControlSock)?;
+            Some(image)
+        } else {
+            None
+        };
+
         /*if QUARK_CONFIG.lock().EnableRDMA {
             // use default rdma device
             let rdmaDeviceName = "";
@@ -263,7 +350,11 @@ impl VirtualMachine {
             VMS.lock().cpuAffinit = true;
         }
 
-        let cpuCount = cpuCount.max(2); // minimal 2 cpus
+        let cpuCount = match &image {
+            // the vcpu count is part of the guest state
+            Some(image) => image.Vcpus.len(),
+            None => cpuCount.max(2), // minimal 2 cpus
+        };
 
         VMS.lock().vcpuCount = cpuCount; //VMSpace::VCPUCount();
         VMS.lock().RandomVcpuMapping();
@@ -311,6 +402,11 @@ impl VirtualMachine {
             MemoryDef::FILE_MAP_SIZE,
         );
 
+        if let Some(image) = &image {
+            image.LoadMemory(&args.RestoreImage)?;
+            GLOBAL_ALLOCATOR.SwitchToRestoredHeap();
+        }
+
         info!(
             "set map region start={:x}, end={:x}",
             MemoryDef::PHY_LOWER_ADDR,
@@ -337,43 +433,69 @@ impl VirtualMachine {
 
             vms.hostAddrTop =
                 MemoryDef::PHY_LOWER_ADDR + 64 * MemoryDef::ONE_MB + 2 * MemoryDef::ONE_GB;
-            vms.pageTables = PageTables::New(&vms.allocator)?;
-
-            vms.KernelMap(
-                addr::Addr(MemoryDef::KVM_IOEVENTFD_BASEADDR),
-                addr::Addr(MemoryDef::KVM_IOEVENTFD_BASEADDR + 0x1000),
-                addr::Addr(MemoryDef::KVM_IOEVENTFD_BASEADDR),
-                addr::PageOpts::Zero()
-                    .SetPresent()
-                    .SetWrite()
-                    .SetGlobal()
-                    .Val(),
-            )?;
-
-            //info!("the pageAllocatorBaseAddr is {:x}, the end of pageAllocator is {:x}", pageAllocatorBaseAddr, pageAllocatorBaseAddr + kernelMemSize);
-            vms.KernelMapHugeTable(
-                addr::Addr(MemoryDef::PHY_LOWER_ADDR),
-                addr::Addr(MemoryDef::PHY_LOWER_ADDR + kernelMemRegionSize * MemoryDef::ONE_GB),
-                addr::Addr(MemoryDef::PHY_LOWER_ADDR),
-                addr::PageOpts::Zero()
-                    .SetPresent()
-                    .SetWrite()
-                    .SetGlobal()
-                    .Val(),
-            )?;
+            match &image {
+                None => {
+                    vms.pageTables = PageTables::New(&vms.allocator)?;
+
+                    vms.KernelMap(
+                        addr::Addr(MemoryDef::KVM_IOEVENTFD_BASEADDR),
+                        addr::Addr(MemoryDef::KVM_IOEVENTFD_BASEADDR + 0x1000),
+                        addr::Addr(MemoryDef::KVM_IOEVENTFD_BASEADDR),
+                        addr::PageOpts::Zero()
+                            .SetPresent()
+                            .SetWrite()
+                            .SetGlobal()
+                            .Val(),
+                    )?;
+
+                    //info!("the pageAllocatorBaseAddr is {:x}, the end of pageAllocator is {:x}", pageAllocatorBaseAddr, pageAllocatorBaseAddr + kernelMemSize);
+                    vms.KernelMapHugeTable(
+                        addr::Addr(MemoryDef::PHY_LOWER_ADDR),
+                        addr::Addr(MemoryDef::PHY_LOWER_ADDR + kernelMemRegionSize * MemoryDef::ONE_GB),
+                        addr::Addr(MemoryDef::PHY_LOWER_ADDR),
+                        addr::PageOpts::Zero()
+                            .SetPresent()
+                            .SetWrite()
+                            .SetGlobal()
+                            .Val(),
+                    )?;
+                }
+                Some(image) => {
+                    PMA_KEEPER.RestoreHugePages(&image.HugePages);
+                    vms.pageTables = PageTables::Init(image.PageTableRoot);
+                }
+            }
             autoStart = args.AutoStart;
             vms.pivot = args.Pivot;
             vms.args = Some(args);
         }
 
-        Self::InitShareSpace(&vm_fd, cpuCount, controlSock, rdmaSvcCliSock, podId);
-
-        info!("before loadKernel");
-
-        let entry = elf.LoadKernel(Self::KERNEL_IMAGE)?;
-        //let vdsoMap = VDSOMemMap::Init(&"/home/brad/rust/quark/vdso/vdso.so".to_string()).unwrap();
-        elf.LoadVDSO(&"/usr/local/bin/vdso.so".to_string())?;
-        VMS.lock().vdsoAddr = elf.vdsoStart;
+        let entry = match &image {
+            None => {
+                Self::InitShareSpace(&vm_fd, cpuCount, controlSock, rdmaSvcCliSock, podId);
+
+                info!("before loadKernel");
+
+                let entry = elf.LoadKernel(Self::KERNEL_IMAGE)?;
+                //let vdsoMap = VDSOMemMap::Init(&"/home/brad/rust/quark/vdso/vdso.so".to_string()).unwrap();
+                elf.LoadVDSO(&"/usr/local/bin/vdso.so".to_string())?;
+                VMS.lock().vdsoAddr = elf.vdsoStart;
+                entry
+            }
+            Some(image) => {
+                // the files of the guest are opened in the pivoted root
+                if VMS.lock().pivot {
+                    let rootfs = VMS.lock().args.as_ref().unwrap().Rootfs.to_string();
+                    VMS.lock().PivotRoot(&rootfs);
+                    VMS.lock().pivot = false;
+                }
+
+                Self::RestoreShareSpace(&vm_fd, image, controlSock)?;
+                VMS.lock().vdsoAddr = image.VdsoAddr;
+                StartSignalHandle();
+                image.Entry
+            }
+        };
 
         let p = entry as *const u8;
         info!(
@@ -391,7 +513,7 @@ impl VirtualMachine {
         for i in 0..cpuCount
         /*args.NumCPU*/
         {
-            let vcpu = Arc::new(KVMVcpu::Init(
+            let mut vcpu = KVMVcpu::Init(
                 i as usize,
                 cpuCount,
                 &vm_fd,
@@ -399,7 +521,11 @@ impl VirtualMachine {
                 heapStartAddr,
                 SHARE_SPACE.Value(),
                 autoStart,
-            )?);
+            )?;
+            if let Some(image) = &image {
+                vcpu.restoreState = Some(image.Vcpus[i].clone());
+            }
+            let vcpu = Arc::new(vcpu);
             // enable cpuid in host
             vcpu.vcpu.set_cpuid2(&kvm_cpuid).unwrap();
             VMS.lock().vcpus.push(vcpu.clone());
@@ -411,6 +537,7 @@ impl VirtualMachine {
             vmfd: vm_fd,
             vcpus: vcpus,
             elf: elf,
+            restored: image.is_some(),
         };
 
         PerfGofrom(PerfType::Other);
@@ -439,7 +566,9 @@ impl VirtualMachine {
                 .unwrap(),
         );
 
-        syncmgr::SyncMgr::WaitShareSpaceReady();
+        if !self.restored {
+            syncmgr::SyncMgr::WaitShareSpaceReady();
+        }
         info!("shareSpace ready...");
         // start the vcpu threads
         for i in 1..self.vcpus.len() {
@@ -32,6 +32,9 @@ use super::super::super::qlib::*;
 use super::super::super::ucall::ucall::*;
 use super::super::super::ucall::ucall_client::*;
 use super::super::super::vmspace::limits::CreateLimitSet;
+use super::super::super::vmspace::limits::CreateCPULimit;
+use super::super::super::vmspace::limits::CreateMemoryLimit;
+use super::super::super::vmspace::limits::CreatePidsLimit;
 use super::super::super::vmspace::syscall::*;
 use super::super::cgroup::cgroup::*;
 use super::super::cmd::config::*;
@@ -197,6 +200,7 @@ impl Sandbox {
         cg: Option<Cgroup>,
         detach: bool,
         pivot: bool,
+        restoreImage: &str,
     ) -> Result<Self> {
         let mut s = Self {
             ID: id.to_string(),
@@ -209,7 +213,8 @@ impl Sandbox {
         let process = &spec.process;
         let terminal = process.terminal;
 
-        let process = SandboxProcess::New(conf, action, id, bundleDir, pivot)?;
+        let mut process = SandboxProcess::New(conf, action, id, bundleDir, pivot)?;
+        process.RestoreImage = restoreImage.to_string();
         //let pid = process.Fork()?;
         let (pid, console) = process.Execv(terminal, consoleSocket, detach)?;
 
@@ -307,6 +312,62 @@ impl Sandbox {
         }
     }
 
+    pub fn ContainerStats(&self, cid: &str) -> Result<ContainerStats> {
+        info!(
+            "Getting stats for container {} in sandbox {}",
+            cid, self.ID
+        );
+        let client = self.SandboxConnect()?;
+
+        let req = UCallReq::ContainerStats(cid.to_string());
+
+        let resp = client.Call(&req)?;
+        match resp {
+            UCallResp::ContainerStatsResp(stats) => Ok(stats),
+            resp => {
+                panic!("ContainerStats get unknow resp {:?}", resp);
+            }
+        }
+    }
+
+    pub fn UpdateContainer(&self, cid: &str, args: UpdateArgs) -> Result<()> {
+        info!(
+            "Updating resources of container {} in sandbox {}",
+            cid, self.ID
+        );
+        let client = self.SandboxConnect()?;
+
+        let req = UCallReq::UpdateContainer(args);
+
+        let resp = client.Call(&req)?;
+        match resp {
+            UCallResp::UpdateContainerResp => Ok(()),
+            resp => {
+                panic!("UpdateContainer get unknow resp {:?}", resp);
+            }
+        }
+    }
+
+    // Checkpoint saves the whole sandbox to the image directory in args. The
+    // sandbox is left paused unless args.leaveRunning is set.
+    pub fn Checkpoint(&self, cid: &str, args: CheckpointArgs) -> Result<()> {
+        info!(
+            "Checkpoint container {} in sandbox {} to {}",
+            cid, self.ID, &args.imagePath
+        );
+        let client = self.SandboxConnect()?;
+
+        let req = UCallReq::Checkpoint(args);
+
+        let resp = client.Call(&req)?;
+        match resp {
+            UCallResp::CheckpointResp => Ok(()),
+            resp => {
+                panic!("Checkpoint get unknow resp {:?}", resp);
+            }
+        }
+    }
+
     pub fn StartRootContainer(&self) -> Result<()> {
         let client = self.SandboxConnect()?;
 
@@ -383,9 +444,15 @@ impl Sandbox {
             limitSet: CreateLimitSet(&spec)
                 .expect("load limitSet fail")
                 .GetInternalCopy(),
+            memoryLimit: CreateMemoryLimit(&spec),
+            cpuLimit: CreateCPULimit(&spec),
+            pidsLimit: CreatePidsLimit(&spec),
+            Seccomp: specutils::Seccomp(&spec),
+            Sysctl: specutils::Sysctl(&spec),
             ID: id.to_string(),
             Caps: specutils::Capabilities(false, &spec.process.capabilities),
             Root: container_root,
+            Mounts: specutils::Mounts(&spec),
             ..Default::default()
         };
 
@@ -494,9 +561,12 @@ impl Sandbox {
         return Ok(client);
     }
 
-    pub fn GetWaitAllResp(client: &UCallClient) -> Result<WaitAllResp> {
+    // GetWaitAllResp returns the next event of the WaitAll stream, either a
+    // WaitAllResp or an OOMEventResp.
+    pub fn GetWaitAllResp(client: &UCallClient) -> Result<UCallResp> {
         let resp = match client.StreamGetRet()? {
-            UCallResp::WaitAllResp(resp) => resp,
+            UCallResp::WaitAllResp(resp) => UCallResp::WaitAllResp(resp),
+            UCallResp::OOMEventResp(resp) => UCallResp::OOMEventResp(resp),
             resp => panic!("sandbox::GetWaitAllResp get error {:?}", resp),
         };
         return Ok(resp);
@@ -23,9 +23,12 @@ use std::sync::mpsc::sync_channel;
 
 use containerd_shim::api::*;
 use containerd_shim::mount::*;
-use containerd_shim::protos::cgroups::metrics::Metrics;
+use containerd_shim::protos::cgroups::metrics::{
+    BlkIOEntry, BlkIOStat, CPUStat, CPUUsage, MemoryEntry, MemoryOomControl, MemoryStat, Metrics,
+    PidsStat,
+};
 use containerd_shim::protos::protobuf::{CodedInputStream, Message};
-use containerd_shim::protos::protobuf::well_known_types::Timestamp;
+use containerd_shim::protos::protobuf::well_known_types::{Any, Timestamp};
 use containerd_shim::util::*;
 use containerd_shim::util::read_spec_from_file;
 use nix::sys::stat::Mode;
@@ -35,6 +38,8 @@ use time::OffsetDateTime;
 
 use super::container_io::*;
 use super::process::*;
+use super::metrics_v2::*;
+use super::super::cgroup::cgroup_v2::IsCgroup2UnifiedMode;
 use super::super::cmd::config::*;
 use super::super::container::container::*;
 use super::super::super::qlib::common::*;
@@ -341,129 +346,108 @@ impl CommonContainer {
         return Err(Error::Unimplemented("CommonContainer::pids".to_string()));
     }
 
-    pub fn stats(&self) -> Result<Metrics> {
-        return Err(Error::Unimplemented("CommonContainer::pids".to_string()));
-        /*let mut metrics = Metrics::new();
-        // get container main process cgroup
-        let path = get_cgroups_relative_paths_by_pid(self.common.init.pid() as u32)?;
-        let cgroup = Cgroup::load_with_relative_paths(hierarchies::auto(), Path::new("."), path);
-
-        // to make it easy, fill the necessary metrics only.
-        for sub_system in Cgroup::subsystems(&cgroup) {
-            match sub_system {
-                Subsystem::CpuAcct(cpuacct_ctr) => {
-                    let mut cpu_usage = CPUUsage::new();
-                    cpu_usage.set_total(cpuacct_ctr.cpuacct().usage);
-                    let mut cpu_stat = CPUStat::new();
-                    cpu_stat.set_usage(cpu_usage);
-                    metrics.set_cpu(cpu_stat);
-                }
-                Subsystem::Mem(mem_ctr) => {
-                    let mem = mem_ctr.memory_stat();
-                    let mut mem_entry = MemoryEntry::new();
-                    mem_entry.set_usage(mem.usage_in_bytes);
-                    let mut mem_stat = MemoryStat::new();
-                    mem_stat.set_usage(mem_entry);
-                    mem_stat.set_total_inactive_file(mem.stat.total_inactive_file);
-                    metrics.set_memory(mem_stat);
-                }
-                _ => {}
-            }
+    // stats returns the metrics of the container in the format of the cgroup
+    // version of the host, as containerd decodes them by the type url.
+    pub fn stats(&self) -> Result<Any> {
+        let stats = self.container.Stats()?;
+
+        let mut any = Any::new();
+        if IsCgroup2UnifiedMode() {
+            let metrics = MetricsV2 {
+                PidsCurrent: stats.Pids,
+                CpuUsageUsec: (stats.UserTime + stats.SysTime) / 1000,
+                CpuUserUsec: stats.UserTime / 1000,
+                CpuSystemUsec: stats.SysTime / 1000,
+                MemoryAnon: stats.MemoryRSS,
+                MemoryFile: stats.MemoryCache,
+                MemoryUsage: stats.MemoryUsage,
+                MemoryUsageLimit: if stats.MemoryLimit == 0 {
+                    std::u64::MAX
+                } else {
+                    stats.MemoryLimit
+                },
+                OOMKills: stats.OOMKills,
+                IORBytes: stats.BytesRead,
+                IOWBytes: stats.BytesWritten,
+                IORios: stats.ReadSyscalls,
+                IOWios: stats.WriteSyscalls,
+                ..Default::default()
+            };
+            any.set_value(metrics.Encode());
+            any.set_type_url(METRICS_V2_TYPE_URL.to_string());
+            return Ok(any);
         }
-        Ok(metrics)*/
-    }
-
-    pub fn update(&mut self, _resources: &LinuxResources) -> Result<()> {
-        return Err(Error::Unimplemented("CommonContainer::pids".to_string()));
-        /*// get container main process cgroup
-        let path = get_cgroups_relative_paths_by_pid(self.common.init.pid() as u32)?;
-        let cgroup = Cgroup::load_with_relative_paths(hierarchies::auto(), Path::new("."), path);
-
-        for sub_system in Cgroup::subsystems(&cgroup) {
-            match sub_system {
-                Subsystem::Pid(pid_ctr) => {
-                    // set maximum number of PIDs
-                    if let Some(pids) = resources.pids() {
-                        pid_ctr
-                            .set_pid_max(MaxValue::Value(pids.limit()))
-                            .map_err(other_error!(e, "set pid max"))?;
-                    }
-                }
-                Subsystem::Mem(mem_ctr) => {
-                    if let Some(memory) = resources.memory() {
-                        // set memory limit in bytes
-                        if let Some(limit) = memory.limit() {
-                            mem_ctr
-                                .set_limit(limit)
-                                .map_err(other_error!(e, "set mem limit"))?;
-                        }
 
-                        // set memory swap limit in bytes
-                        if let Some(swap) = memory.swap() {
-                            mem_ctr
-                                .set_memswap_limit(swap)
-                                .map_err(other_error!(e, "set memsw limit"))?;
-                        }
-                    }
-                }
-                Subsystem::CpuSet(cpuset_ctr) => {
-                    if let Some(cpu) = resources.cpu() {
-                        // set CPUs to use within the cpuset
-                        if let Some(cpus) = cpu.cpus() {
-                            cpuset_ctr
-                                .set_cpus(cpus)
-                                .map_err(other_error!(e, "set CPU sets"))?;
-                        }
-
-                        // set list of memory nodes in the cpuset
-                        if let Some(mems) = cpu.mems() {
-                            cpuset_ctr
-                                .set_mems(mems)
-                                .map_err(other_error!(e, "set CPU memes"))?;
-                        }
-                    }
-                }
-                Subsystem::Cpu(cpu_ctr) => {
-                    if let Some(cpu) = resources.cpu() {
-                        // set CPU shares
-                        if let Some(shares) = cpu.shares() {
-                            cpu_ctr
-                                .set_shares(shares)
-                                .map_err(other_error!(e, "set CPU share"))?;
-                        }
+        let mut metrics = Metrics::new();
+
+        let mut cpu_usage = CPUUsage::new();
+        cpu_usage.set_total(stats.UserTime + stats.SysTime);
+        cpu_usage.set_user(stats.UserTime);
+        cpu_usage.set_kernel(stats.SysTime);
+        let mut cpu_stat = CPUStat::new();
+        cpu_stat.set_usage(cpu_usage);
+        metrics.set_cpu(cpu_stat);
+
+        let mut mem_entry = MemoryEntry::new();
+        mem_entry.set_usage(stats.MemoryUsage);
+        // an unlimited cgroup v1 reports the maximum page aligned value
+        if stats.MemoryLimit == 0 {
+            mem_entry.set_limit(std::i64::MAX as u64 & !0xfff);
+        } else {
+            mem_entry.set_limit(stats.MemoryLimit);
+        }
+        let mut mem_stat = MemoryStat::new();
+        mem_stat.set_usage(mem_entry);
+        mem_stat.set_rss(stats.MemoryRSS);
+        mem_stat.set_total_rss(stats.MemoryRSS);
+        mem_stat.set_cache(stats.MemoryCache);
+        mem_stat.set_total_cache(stats.MemoryCache);
+        metrics.set_memory(mem_stat);
+
+        let mut oom_control = MemoryOomControl::new();
+        oom_control.set_oom_kill(stats.OOMKills);
+        metrics.set_memory_oom_control(oom_control);
+
+        let mut pids_stat = PidsStat::new();
+        pids_stat.set_current(stats.Pids);
+        metrics.set_pids(pids_stat);
+
+        let mut blkio_stat = BlkIOStat::new();
+        for (op, bytes, ops) in &[
+            ("Read", stats.BytesRead, stats.ReadSyscalls),
+            ("Write", stats.BytesWritten, stats.WriteSyscalls),
+        ] {
+            let mut entry = BlkIOEntry::new();
+            entry.set_op(op.to_string());
+            entry.set_value(*bytes);
+            blkio_stat.mut_io_service_bytes_recursive().push(entry);
+
+            let mut entry = BlkIOEntry::new();
+            entry.set_op(op.to_string());
+            entry.set_value(*ops);
+            blkio_stat.mut_io_serviced_recursive().push(entry);
+        }
+        metrics.set_blkio(blkio_stat);
+
+        let mut data = Vec::new();
+        metrics
+            .write_to_vec(&mut data)
+            .map_err(|e| Error::Common(format!("{:?}", e)))?;
+        any.set_value(data);
+        any.set_type_url(metrics.descriptor().full_name().to_string());
+        Ok(any)
+    }
 
-                        // set CPU hardcap limit
-                        if let Some(quota) = cpu.quota() {
-                            cpu_ctr
-                                .set_cfs_quota(quota)
-                                .map_err(other_error!(e, "set CPU quota"))?;
-                        }
+    // update applies the new resource limits to the host cgroup of the sandbox
+    // and to the container in the guest kernel.
+    pub fn update(&mut self, resources: LinuxResources) -> Result<()> {
+        return self.container.Update(resources);
+    }
 
-                        // set CPU hardcap period
-                        if let Some(period) = cpu.period() {
-                            cpu_ctr
-                                .set_cfs_period(period)
-                                .map_err(other_error!(e, "set CPU period"))?;
-                        }
-                    }
-                }
-                Subsystem::HugeTlb(ht_ctr) => {
-                    // set the limit if "pagesize" hugetlb usage
-                    if let Some(hp_limits) = resources.hugepage_limits() {
-                        for limit in hp_limits {
-                            ht_ctr
-                                .set_limit_in_bytes(
-                                    limit.page_size().as_str(),
-                                    limit.limit() as u64,
-                                )
-                                .map_err(other_error!(e, "set huge page limit"))?;
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-        Ok(())*/
+    // checkpoint saves the whole sandbox to the image directory at path, the
+    // container keeps running.
+    pub fn checkpoint(&mut self, path: &str) -> Result<()> {
+        return self.container.Checkpoint(path, true);
     }
 
     pub fn start(&mut self, exec_id: Option<&str>) -> Result<i32> {
@@ -0,0 +1,142 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The containerd shim protos only have the cgroup v1 metrics, the subset of
+// io.containerd.cgroups.v2.Metrics filled by quark is encoded here. The field
+// numbers are the ones of github.com/containerd/cgroups/v2/stats/metrics.proto.
+
+pub const METRICS_V2_TYPE_URL: &str = "io.containerd.cgroups.v2.Metrics";
+
+// Metrics
+const METRICS_PIDS: u32 = 1;
+const METRICS_CPU: u32 = 2;
+const METRICS_MEMORY: u32 = 4;
+const METRICS_IO: u32 = 6;
+const METRICS_MEMORY_EVENTS: u32 = 8;
+
+// PidsStat
+const PIDS_CURRENT: u32 = 1;
+const PIDS_LIMIT: u32 = 2;
+
+// CPUStat
+const CPU_USAGE_USEC: u32 = 1;
+const CPU_USER_USEC: u32 = 2;
+const CPU_SYSTEM_USEC: u32 = 3;
+
+// MemoryStat
+const MEMORY_ANON: u32 = 1;
+const MEMORY_FILE: u32 = 2;
+const MEMORY_USAGE: u32 = 32;
+const MEMORY_USAGE_LIMIT: u32 = 33;
+
+// MemoryEvents
+const MEMORY_EVENTS_OOM: u32 = 4;
+const MEMORY_EVENTS_OOM_KILL: u32 = 5;
+
+// IOStat and IOEntry
+const IO_USAGE: u32 = 1;
+const IO_RBYTES: u32 = 3;
+const IO_WBYTES: u32 = 4;
+const IO_RIOS: u32 = 5;
+const IO_WIOS: u32 = 6;
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_LEN: u32 = 2;
+
+#[derive(Debug, Default)]
+pub struct MetricsV2 {
+    pub PidsCurrent: u64,
+    // PidsLimit is 0 when unlimited.
+    pub PidsLimit: u64,
+
+    // CPU times in microseconds.
+    pub CpuUsageUsec: u64,
+    pub CpuUserUsec: u64,
+    pub CpuSystemUsec: u64,
+
+    pub MemoryAnon: u64,
+    pub MemoryFile: u64,
+    pub MemoryUsage: u64,
+    // MemoryUsageLimit is u64::MAX when unlimited, as for "max" in memory.max.
+    pub MemoryUsageLimit: u64,
+
+    pub OOMKills: u64,
+
+    pub IORBytes: u64,
+    pub IOWBytes: u64,
+    pub IORios: u64,
+    pub IOWios: u64,
+}
+
+impl MetricsV2 {
+    pub fn Encode(&self) -> Vec<u8> {
+        let mut pids = Vec::new();
+        PutUint64(&mut pids, PIDS_CURRENT, self.PidsCurrent);
+        PutUint64(&mut pids, PIDS_LIMIT, self.PidsLimit);
+
+        let mut cpu = Vec::new();
+        PutUint64(&mut cpu, CPU_USAGE_USEC, self.CpuUsageUsec);
+        PutUint64(&mut cpu, CPU_USER_USEC, self.CpuUserUsec);
+        PutUint64(&mut cpu, CPU_SYSTEM_USEC, self.CpuSystemUsec);
+
+        let mut memory = Vec::new();
+        PutUint64(&mut memory, MEMORY_ANON, self.MemoryAnon);
+        PutUint64(&mut memory, MEMORY_FILE, self.MemoryFile);
+        PutUint64(&mut memory, MEMORY_USAGE, self.MemoryUsage);
+        PutUint64(&mut memory, MEMORY_USAGE_LIMIT, self.MemoryUsageLimit);
+
+        let mut entry = Vec::new();
+        PutUint64(&mut entry, IO_RBYTES, self.IORBytes);
+        PutUint64(&mut entry, IO_WBYTES, self.IOWBytes);
+        PutUint64(&mut entry, IO_RIOS, self.IORios);
+        PutUint64(&mut entry, IO_WIOS, self.IOWios);
+        let mut io = Vec::new();
+        PutMessage(&mut io, IO_USAGE, &entry);
+
+        let mut events = Vec::new();
+        PutUint64(&mut events, MEMORY_EVENTS_OOM, self.OOMKills);
+        PutUint64(&mut events, MEMORY_EVENTS_OOM_KILL, self.OOMKills);
+
+        let mut buf = Vec::new();
+        PutMessage(&mut buf, METRICS_PIDS, &pids);
+        PutMessage(&mut buf, METRICS_CPU, &cpu);
+        PutMessage(&mut buf, METRICS_MEMORY, &memory);
+        PutMessage(&mut buf, METRICS_IO, &io);
+        PutMessage(&mut buf, METRICS_MEMORY_EVENTS, &events);
+        return buf;
+    }
+}
+
+fn PutVarint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+// PutUint64 encodes a uint64 field, zero values are omitted as in proto3.
+fn PutUint64(buf: &mut Vec<u8>, field: u32, v: u64) {
+    if v == 0 {
+        return;
+    }
+    PutVarint(buf, (field << 3 | WIRE_VARINT) as u64);
+    PutVarint(buf, v);
+}
+
+fn PutMessage(buf: &mut Vec<u8>, field: u32, msg: &[u8]) {
+    PutVarint(buf, (field << 3 | WIRE_LEN) as u64);
+    PutVarint(buf, msg.len() as u64);
+    buf.extend_from_slice(msg);
+}
@@ -14,6 +14,7 @@
 
 pub mod container;
 pub mod container_io;
+pub mod metrics_v2;
 pub mod process;
 pub mod service;
 pub mod shim_task;
@@ -27,11 +27,11 @@ use std::thread;
 use containerd_shim::api;
 use containerd_shim::api::*;
 use containerd_shim::event::Event;
-use containerd_shim::protos::cgroups::metrics::Metrics;
 use containerd_shim::protos::events::task::{
-    TaskCreate, TaskDelete, TaskExecAdded, TaskExecStarted, TaskExit, TaskIO, TaskStart,
+    TaskCreate, TaskDelete, TaskExecAdded, TaskExecStarted, TaskExit, TaskIO, TaskOOM,
+    TaskStart,
 };
-use containerd_shim::protos::protobuf::well_known_types::{Any, Timestamp};
+use containerd_shim::protos::protobuf::well_known_types::Timestamp;
 use containerd_shim::protos::protobuf::{Message, SingularPtrField};
 use containerd_shim::protos::ttrpc::Error as TError;
 use containerd_shim::util::*;
@@ -44,6 +44,7 @@ use containerd_shim::TtrpcResult;
 use super::container::*;
 
 use super::super::super::runc::oci::LinuxResources;
+use super::super::super::qlib::control_msg::UCallResp;
 use super::super::super::runc::sandbox::sandbox::*;
 
 type EventSender = Sender<(String, Box<dyn Message>)>;
@@ -94,7 +95,13 @@ impl ShimTask {
 
                 error!("shim WaitAll {:?}", resp);
 
-                Self::Exit(&tx, &containers, resp.cid, resp.execId, resp.status as i32)
+                match resp {
+                    UCallResp::WaitAllResp(resp) => {
+                        Self::Exit(&tx, &containers, resp.cid, resp.execId, resp.status as i32)
+                    }
+                    UCallResp::OOMEventResp(resp) => Self::OOM(&tx, &containers, resp.cid),
+                    _ => (),
+                }
             }
         });
     }
@@ -184,6 +191,27 @@ impl ShimTask {
         }
     }
 
+    // handle oom event of container, the container runtime records the
+    // container as OOMKilled when it exits
+    pub fn OOM(
+        tx: &Arc<Mutex<EventSender>>,
+        containers: &Arc<Mutex<HashMap<String, CommonContainer>>>,
+        cid: String,
+    ) {
+        if !containers.lock().unwrap().contains_key(&cid) {
+            error!("ShimTask::OOM can't find container {}", cid);
+            return;
+        }
+
+        Self::SendEvent(
+            tx,
+            TaskOOM {
+                container_id: cid,
+                ..Default::default()
+            },
+        );
+    }
+
     fn SendEvent(tx: &Arc<Mutex<EventSender>>, event: impl Event) {
         let topic = event.topic();
         tx.lock()
@@ -451,7 +479,20 @@ impl Task for ShimTask {
         let resources: LinuxResources = serde_json::from_slice(req.get_resources().get_value())
             .map_err(|e| TtrpcError::Other(format!("{:?}", e)))?;
         container
-            .update(&resources)
+            .update(resources)
+            .map_err(|e| TtrpcError::Other(format!("{:?}", e)))?;
+        Ok(Empty::new())
+    }
+
+    fn checkpoint(&self, _ctx: &TtrpcContext, req: CheckpointTaskRequest) -> TtrpcResult<Empty> {
+        debug!("shim: Checkpoint request for {:?}", req);
+        let mut containers = self.containers.lock().unwrap();
+        let container = containers.get_mut(req.get_id()).ok_or_else(|| {
+            TtrpcError::Other(format!("can not find container by id {}", req.get_id()))
+        })?;
+
+        container
+            .checkpoint(req.get_path())
             .map_err(|e| TtrpcError::Other(format!("{:?}", e)))?;
         Ok(Empty::new())
     }
@@ -507,19 +548,12 @@ impl Task for ShimTask {
     fn stats(&self, _ctx: &TtrpcContext, req: StatsRequest) -> TtrpcResult<StatsResponse> {
         debug!("shim: Stats request for {:?}", req);
         let containers = self.containers.lock().unwrap();
-        let _container = containers.get(req.get_id()).ok_or_else(|| {
+        let container = containers.get(req.get_id()).ok_or_else(|| {
             TtrpcError::Other(format!("can not find container by id {}", req.get_id()))
         })?;
-        // TODO(Cong): implement stats
-        let stats = Metrics::default();
-        // marshal to ttrpc Any
-        let mut any = Any::new();
-        let mut data = Vec::new();
-        stats
-            .write_to_vec(&mut data)
+        let any = container
+            .stats()
             .map_err(|e| TtrpcError::Other(format!("{:?}", e)))?;
-        any.set_value(data);
-        any.set_type_url(stats.descriptor().full_name().to_string());
 
         let mut resp = StatsResponse::new();
         resp.set_stats(any);
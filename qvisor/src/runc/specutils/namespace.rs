@@ -14,6 +14,7 @@
 
 use alloc::vec::Vec;
 use capabilities;
+use kvm_ioctls::Kvm;
 use std::fs::File;
 use std::os::unix::io::AsRawFd;
 
@@ -147,7 +148,8 @@ pub fn StartInNS(subProcess: &mut SandboxProcess, nss: &[LinuxNamespace]) -> Res
     }
 
     error!("should not reach here, need control sock");
-    subProcess.Run(0, -1, -1);
+    let kvmfd = Kvm::open_with_cloexec(false).expect("can't open kvm");
+    subProcess.Run(kvmfd, 0, -1, -1)?;
 
     return Ok(());
 }
@@ -1,3 +1,4 @@
+use alloc::collections::btree_map::BTreeMap;
 use alloc::collections::btree_set::BTreeSet;
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -7,7 +8,9 @@ use std::fs::File;
 
 use super::super::super::qlib::auth::cap_set::*;
 use super::super::super::qlib::common::*;
+use super::super::super::qlib::kernel::boot::oci as guestoci;
 use super::super::super::qlib::linux_def::*;
+use super::super::super::qlib::loader;
 use super::super::super::qlib::path::*;
 use super::super::oci::*;
 use super::fs::*;
@@ -86,10 +89,6 @@ pub fn ValidateSpec(spec: &Spec) -> Result<()> {
         )
     }
 
-    if spec.linux.is_some() && spec.linux.as_ref().unwrap().seccomp.is_some() {
-        info!("Seccomp spec {:?} is being ignored", spec.linux)
-    }
-
     if spec.linux.is_some() && spec.linux.as_ref().unwrap().rootfs_propagation.len() != 0 {
         ValidateRootfsPropagation(&spec.linux.as_ref().unwrap().rootfs_propagation)?;
     }
@@ -165,6 +164,120 @@ pub fn Capabilities(enableRaw: bool, specCaps: &Option<LinuxCapabilities>) -> Ta
     return caps;
 }
 
+// Mounts returns the mounts of the spec to be set up in the guest.
+pub fn Mounts(spec: &Spec) -> Vec<loader::Mount> {
+    let mut mounts = Vec::with_capacity(spec.mounts.len());
+    for m in &spec.mounts {
+        mounts.push(loader::Mount {
+            Destination: m.destination.to_string(),
+            Type: m.typ.to_string(),
+            Source: m.source.to_string(),
+            Options: m.options.clone(),
+        });
+    }
+
+    return mounts;
+}
+
+// Sysctl returns the linux.sysctl of the spec to be applied by the sandbox
+// kernel.
+pub fn Sysctl(spec: &Spec) -> BTreeMap<String, String> {
+    let mut sysctls = BTreeMap::new();
+    if let Some(linux) = &spec.linux {
+        for (name, val) in &linux.sysctl {
+            sysctls.insert(name.to_string(), val.to_string());
+        }
+    }
+
+    return sysctls;
+}
+
+// Seccomp returns the linux.seccomp profile of the spec to be enforced by the
+// sandbox kernel.
+pub fn Seccomp(spec: &Spec) -> Option<guestoci::LinuxSeccomp> {
+    let seccomp = match &spec.linux {
+        None => return None,
+        Some(linux) => match &linux.seccomp {
+            None => return None,
+            Some(s) => s,
+        },
+    };
+
+    let mut syscalls = Vec::with_capacity(seccomp.syscalls.len());
+    for s in &seccomp.syscalls {
+        let mut args = Vec::with_capacity(s.args.len());
+        for a in &s.args {
+            args.push(guestoci::LinuxSeccompArg {
+                index: a.index,
+                value: a.value,
+                value_two: a.value_two,
+                op: SeccompOperator(a.op),
+            });
+        }
+
+        syscalls.push(guestoci::LinuxSyscall {
+            name: s.name.to_string(),
+            names: s.names.clone(),
+            action: SeccompAction(s.action),
+            errno_ret: s.errno_ret,
+            args: args,
+        });
+    }
+
+    return Some(guestoci::LinuxSeccomp {
+        default_action: SeccompAction(seccomp.default_action),
+        default_errno_ret: seccomp.default_errno_ret,
+        architectures: seccomp.architectures.iter().map(|a| SeccompArch(*a)).collect(),
+        syscalls: syscalls,
+    });
+}
+
+fn SeccompAction(action: LinuxSeccompAction) -> guestoci::LinuxSeccompAction {
+    match action {
+        LinuxSeccompAction::SCMP_ACT_KILL => guestoci::LinuxSeccompAction::SCMP_ACT_KILL,
+        LinuxSeccompAction::SCMP_ACT_KILL_PROCESS => guestoci::LinuxSeccompAction::SCMP_ACT_KILL_PROCESS,
+        LinuxSeccompAction::SCMP_ACT_TRAP => guestoci::LinuxSeccompAction::SCMP_ACT_TRAP,
+        LinuxSeccompAction::SCMP_ACT_ERRNO => guestoci::LinuxSeccompAction::SCMP_ACT_ERRNO,
+        LinuxSeccompAction::SCMP_ACT_TRACE => guestoci::LinuxSeccompAction::SCMP_ACT_TRACE,
+        LinuxSeccompAction::SCMP_ACT_LOG => guestoci::LinuxSeccompAction::SCMP_ACT_LOG,
+        LinuxSeccompAction::SCMP_ACT_ALLOW => guestoci::LinuxSeccompAction::SCMP_ACT_ALLOW,
+    }
+}
+
+fn SeccompOperator(op: LinuxSeccompOperator) -> guestoci::LinuxSeccompOperator {
+    match op {
+        LinuxSeccompOperator::SCMP_CMP_NE => guestoci::LinuxSeccompOperator::SCMP_CMP_NE,
+        LinuxSeccompOperator::SCMP_CMP_LT => guestoci::LinuxSeccompOperator::SCMP_CMP_LT,
+        LinuxSeccompOperator::SCMP_CMP_LE => guestoci::LinuxSeccompOperator::SCMP_CMP_LE,
+        LinuxSeccompOperator::SCMP_CMP_EQ => guestoci::LinuxSeccompOperator::SCMP_CMP_EQ,
+        LinuxSeccompOperator::SCMP_CMP_GE => guestoci::LinuxSeccompOperator::SCMP_CMP_GE,
+        LinuxSeccompOperator::SCMP_CMP_GT => guestoci::LinuxSeccompOperator::SCMP_CMP_GT,
+        LinuxSeccompOperator::SCMP_CMP_MASKED_EQ => guestoci::LinuxSeccompOperator::SCMP_CMP_MASKED_EQ,
+    }
+}
+
+fn SeccompArch(arch: Arch) -> guestoci::Arch {
+    match arch {
+        Arch::SCMP_ARCH_NATIVE => guestoci::Arch::SCMP_ARCH_NATIVE,
+        Arch::SCMP_ARCH_X86 => guestoci::Arch::SCMP_ARCH_X86,
+        Arch::SCMP_ARCH_X86_64 => guestoci::Arch::SCMP_ARCH_X86_64,
+        Arch::SCMP_ARCH_X32 => guestoci::Arch::SCMP_ARCH_X32,
+        Arch::SCMP_ARCH_ARM => guestoci::Arch::SCMP_ARCH_ARM,
+        Arch::SCMP_ARCH_AARCH64 => guestoci::Arch::SCMP_ARCH_AARCH64,
+        Arch::SCMP_ARCH_MIPS => guestoci::Arch::SCMP_ARCH_MIPS,
+        Arch::SCMP_ARCH_MIPS64 => guestoci::Arch::SCMP_ARCH_MIPS64,
+        Arch::SCMP_ARCH_MIPS64N32 => guestoci::Arch::SCMP_ARCH_MIPS64N32,
+        Arch::SCMP_ARCH_MIPSEL => guestoci::Arch::SCMP_ARCH_MIPSEL,
+        Arch::SCMP_ARCH_MIPSEL64 => guestoci::Arch::SCMP_ARCH_MIPSEL64,
+        Arch::SCMP_ARCH_MIPSEL64N32 => guestoci::Arch::SCMP_ARCH_MIPSEL64N32,
+        Arch::SCMP_ARCH_PPC => guestoci::Arch::SCMP_ARCH_PPC,
+        Arch::SCMP_ARCH_PPC64 => guestoci::Arch::SCMP_ARCH_PPC64,
+        Arch::SCMP_ARCH_PPC64LE => guestoci::Arch::SCMP_ARCH_PPC64LE,
+        Arch::SCMP_ARCH_S390 => guestoci::Arch::SCMP_ARCH_S390,
+        Arch::SCMP_ARCH_S390X => guestoci::Arch::SCMP_ARCH_S390X,
+    }
+}
+
 // Capabilities takes in spec and returns a TaskCapabilities corresponding to
 // the spec.
 pub fn CapsFromSpec(caps: &[LinuxCapabilityType], skipSet: &BTreeSet<u64>) -> CapSet {
@@ -32,12 +32,15 @@ pub enum UCallReq {
     Pause,
     Unpause,
     Ps(Cid),
+    ContainerStats(Cid),
     WaitContainer(Cid),
     WaitPid(WaitPid),
     Signal(SignalArgs),
     ContainerDestroy(Cid),
     CreateSubContainer(CreateArgs),
     StartSubContainer(StartArgs),
+    UpdateContainer(UpdateArgs),
+    Checkpoint(CheckpointArgs),
     WaitAll,
 }
 
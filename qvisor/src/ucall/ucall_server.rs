@@ -95,6 +95,11 @@ pub fn PsHandler(cid: &str) -> Result<ControlMsg> {
     return Ok(msg);
 }
 
+pub fn ContainerStatsHandler(cid: &str) -> Result<ControlMsg> {
+    let msg = ControlMsg::New(Payload::ContainerStats(cid.to_string()));
+    return Ok(msg);
+}
+
 pub fn WaitHandler(cid: &str) -> Result<ControlMsg> {
     let msg = ControlMsg::New(Payload::WaitContainer(cid.to_string()));
     return Ok(msg);
@@ -157,6 +162,16 @@ pub fn StartSubContainerHandler(args: &mut StartArgs) -> Result<ControlMsg> {
     return Ok(msg);
 }
 
+pub fn UpdateContainerHandler(args: &UpdateArgs) -> Result<ControlMsg> {
+    let msg = ControlMsg::New(Payload::UpdateContainer(args.clone()));
+    return Ok(msg);
+}
+
+pub fn CheckpointHandler(args: &CheckpointArgs) -> Result<ControlMsg> {
+    let msg = ControlMsg::New(Payload::Checkpoint(args.clone()));
+    return Ok(msg);
+}
+
 pub fn ProcessReqHandler(req: &mut UCallReq, fds: &[i32]) -> Result<ControlMsg> {
     let msg = match req {
         UCallReq::RootContainerStart(start) => RootContainerStartHandler(start)?,
@@ -164,12 +179,15 @@ pub fn ProcessReqHandler(req: &mut UCallReq, fds: &[i32]) -> Result<ControlMsg>
         UCallReq::Pause => PauseHandler()?,
         UCallReq::Unpause => UnpauseHandler()?,
         UCallReq::Ps(cid) => PsHandler(cid)?,
+        UCallReq::ContainerStats(cid) => ContainerStatsHandler(cid)?,
         UCallReq::WaitContainer(cid) => WaitHandler(cid)?,
         UCallReq::WaitPid(waitpid) => WaitPidHandler(waitpid)?,
         UCallReq::Signal(signalArgs) => SignalHandler(signalArgs)?,
         UCallReq::ContainerDestroy(cid) => ContainerDestroyHandler(cid)?,
         UCallReq::CreateSubContainer(args) => CreateSubContainerHandler(args, fds)?,
         UCallReq::StartSubContainer(args) => StartSubContainerHandler(args)?,
+        UCallReq::UpdateContainer(args) => UpdateContainerHandler(args)?,
+        UCallReq::Checkpoint(args) => CheckpointHandler(args)?,
         UCallReq::WaitAll => WaitAll()?,
     };
 
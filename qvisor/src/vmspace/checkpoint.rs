@@ -0,0 +1,831 @@
+// Copyright (c) 2021 Quark Container Authors / 2018 The gVisor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Checkpoint image of a sandbox. The image directory holds checkpoint.json, the
+// metadata of the sandbox, and pages.img, the content of the guest memory.
+//
+// The guest memory is identity mapped in the qvisor process and the qvisor heap
+// is shared with the qkernel, so the qkernel objects, the ShareSpace and the
+// qvisor objects referred by them are all captured with the memory. The host
+// state out of the guest memory is recorded in the metadata: the vcpu registers
+// and the host fds, which are recreated at the same fd numbers when restoring.
+//
+// Known limitations:
+// . the io_uring requests in flight are lost when the sandbox is restored,
+//   the qkernel rearms the host epoll poll and the control socket accept only.
+// . host file mappings are restored as private copies.
+// . pipes and connected sockets are replaced with /dev/null, memfd content is
+//   not saved.
+// . the image can only be restored with the same quark and qkernel binaries.
+
+use core::hint::spin_loop;
+use core::mem::size_of;
+use core::sync::atomic::Ordering;
+use libc::*;
+use serde_json;
+use std::ffi;
+use std::fs;
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::super::heap_alloc::*;
+use super::super::kvm_vcpu::*;
+use super::super::qlib::common::*;
+use super::super::qlib::kernel::util::cstring::CString;
+use super::super::qlib::kernel::GlobalIOMgr;
+use super::super::qlib::linux_def::*;
+use super::super::util::*;
+use super::super::{
+    FD_NOTIFIER, GLOBAL_ALLOCATOR, PMA_KEEPER, ROOT_CONTAINER_ID, SHARE_SPACE, URING_MGR, VMS,
+};
+
+pub const CHECKPOINT_VERSION: u32 = 1;
+pub const CHECKPOINT_META_FILE: &str = "checkpoint.json";
+pub const CHECKPOINT_PAGES_FILE: &str = "pages.img";
+
+// CHECKPOINT_WAIT_TIMEOUT bounds the wait for the vcpus to stop before the
+// image is written.
+pub const CHECKPOINT_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MemRegion {
+    pub Addr: u64,
+    pub Len: u64,
+    // File is true for the host file mappings, all their pages are saved.
+    pub File: bool,
+}
+
+// MemExtent is a range of guest memory saved at Offset of pages.img.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MemExtent {
+    pub Addr: u64,
+    pub Len: u64,
+    pub Offset: u64,
+}
+
+// VcpuState is the kvm register state of a vcpu, the kvm structures are saved
+// as raw bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VcpuState {
+    pub Id: usize,
+    pub Regs: Vec<u8>,
+    pub Sregs: Vec<u8>,
+    pub Xsave: Vec<u8>,
+    pub Xcrs: Vec<u8>,
+    pub Msrs: Vec<(u32, u64)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum HostFdKind {
+    // File is reopened by path and seeked to Offset.
+    File {
+        Path: String,
+        Flags: i32,
+        Offset: i64,
+    },
+    // Memfd is recreated with the same size.
+    Memfd { Size: i64, Flags: i32 },
+    // Socket is recreated unconnected, listening sockets are bound to Addr
+    // and listen again.
+    Socket {
+        Domain: i32,
+        Type: i32,
+        Protocol: i32,
+        Flags: i32,
+        Listening: bool,
+        Addr: Vec<u8>,
+    },
+    // Other is a fd which can't be recreated, e.g. a pipe.
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HostFdState {
+    pub Fd: i32,
+    pub Kind: HostFdKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CheckpointImage {
+    pub Version: u32,
+    pub ID: String,
+    pub ShareSpaceAddr: u64,
+    pub PageTableRoot: u64,
+    pub Entry: u64,
+    pub VdsoAddr: u64,
+
+    // the host fds created by qvisor for itself which the guest knows by number
+    pub ControlSock: i32,
+    pub IOMgrEventfd: i32,
+    pub VcpuFds: Vec<(i32, i32)>,
+
+    pub Regions: Vec<MemRegion>,
+    pub Extents: Vec<MemExtent>,
+    pub HugePages: Vec<u64>,
+    pub Vcpus: Vec<VcpuState>,
+    pub HostFds: Vec<HostFdState>,
+}
+
+// Checkpoint handles the Checkpoint hypercall, it is issued by the qkernel
+// after all the tasks are paused.
+pub fn Checkpoint(imagePath: u64) -> i64 {
+    let imagePath = CString::FromAddr(imagePath);
+    let imagePath = match imagePath.Str() {
+        Ok(path) => path.to_string(),
+        Err(_) => return -SysErr::EINVAL as i64,
+    };
+
+    match CheckpointImage::Dump(&imagePath) {
+        Ok(()) => return 0,
+        Err(Error::SysError(errno)) => {
+            error!("checkpoint to {} fail with errno {}", imagePath, errno);
+            return -errno as i64;
+        }
+        Err(e) => {
+            error!("checkpoint to {} fail with error {:?}", imagePath, e);
+            return -SysErr::EIO as i64;
+        }
+    }
+}
+
+impl CheckpointImage {
+    // Dump writes the image of the sandbox to imagePath.
+    pub fn Dump(imagePath: &str) -> Result<()> {
+        // wait until all other vcpu halt
+        WaitFor("the vcpus to halt", || {
+            SHARE_SPACE.scheduler.ReadyForHibernate()
+        })?;
+
+        // the restored vcpu issues the hypercall again if its rip was not moved
+        // past the hypercall when the image was written.
+        if SHARE_SPACE.restored.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(imagePath).map_err(Error::FromIOErr)?;
+
+        let sharespace = SHARE_SPACE.Ptr();
+        let mut image = CheckpointImage {
+            Version: CHECKPOINT_VERSION,
+            ID: ROOT_CONTAINER_ID.lock().clone(),
+            ShareSpaceAddr: SHARE_SPACE.Value(),
+            ControlSock: sharespace.controlSock,
+            IOMgrEventfd: sharespace.ioMgr.eventfd,
+            HugePages: PMA_KEEPER.AllocatedHugePages(),
+            ..Default::default()
+        };
+
+        for vcpu in sharespace.scheduler.VcpuArr.iter() {
+            image.VcpuFds.push((vcpu.eventfd, vcpu.epollfd));
+        }
+
+        let vcpus = {
+            let vms = VMS.lock();
+            image.PageTableRoot = vms.pageTables.GetRoot();
+            image.VdsoAddr = vms.vdsoAddr;
+            vms.vcpus.clone()
+        };
+
+        image.Entry = vcpus[0].entry;
+        for vcpu in &vcpus {
+            // the io thread vcpu might be draining the io_uring completions
+            WaitFor("the vcpus to exit to host", || {
+                vcpu.state.load(Ordering::Acquire) == KVMVcpuState::HOST as u64
+            })?;
+            image.Vcpus.push(vcpu.SaveState()?);
+        }
+
+        image.HostFds = SaveHostFds();
+        image.Regions = MappedRegions()?;
+
+        let path = Path::new(imagePath).join(CHECKPOINT_PAGES_FILE);
+        image.Extents = WritePages(&path, &image.Regions)?;
+
+        let data = serde_json::to_vec(&image)
+            .map_err(|e| Error::Common(format!("checkpoint image ser fail {:?}", e)))?;
+        let path = Path::new(imagePath).join(CHECKPOINT_META_FILE);
+        fs::write(path, data).map_err(Error::FromIOErr)?;
+
+        info!(
+            "checkpoint to {} done, {} extents, {} host fds",
+            imagePath,
+            image.Extents.len(),
+            image.HostFds.len()
+        );
+        return Ok(());
+    }
+
+    pub fn Load(imagePath: &str) -> Result<Self> {
+        let path = Path::new(imagePath).join(CHECKPOINT_META_FILE);
+        let data = fs::read(path).map_err(Error::FromIOErr)?;
+        let image: CheckpointImage = serde_json::from_slice(&data)
+            .map_err(|e| Error::Common(format!("checkpoint image de fail {:?}", e)))?;
+
+        if image.Version != CHECKPOINT_VERSION {
+            return Err(Error::Common(format!(
+                "checkpoint image version {} is not supported",
+                image.Version
+            )));
+        }
+
+        return Ok(image);
+    }
+
+    fn Fds(&self) -> Vec<i32> {
+        let mut fds = vec![self.ControlSock, self.IOMgrEventfd];
+        for (eventfd, epollfd) in &self.VcpuFds {
+            fds.push(*eventfd);
+            fds.push(*epollfd);
+        }
+
+        for state in &self.HostFds {
+            fds.push(state.Fd);
+        }
+
+        return fds;
+    }
+
+    // ReserveFds holds the fd numbers used in the image with /dev/null before
+    // the restoring qvisor process opens its own fds. The control socket is
+    // the only fd allowed to be open at its number already.
+    pub fn ReserveFds(&self, controlSock: i32) -> Result<()> {
+        let fds = self.Fds();
+        let maxFd = fds.iter().cloned().max().unwrap_or(0);
+
+        let devnull = OpenDevNull()?;
+        let placeholder = unsafe { fcntl(devnull, F_DUPFD_CLOEXEC, maxFd + 1) };
+        unsafe {
+            close(devnull);
+        }
+        let placeholder = GetRet(placeholder)?;
+        defer!(unsafe {
+            close(placeholder);
+        });
+
+        for fd in fds {
+            if fd == controlSock && fd == self.ControlSock {
+                continue;
+            }
+
+            if unsafe { fcntl(fd, F_GETFD) } >= 0 {
+                return Err(Error::Common(format!(
+                    "fd {} of the checkpoint image is in use",
+                    fd
+                )));
+            }
+
+            GetRet(unsafe { dup2(placeholder, fd) })?;
+        }
+
+        return Ok(());
+    }
+
+    // LoadMemory maps the guest memory regions and fills them with the pages
+    // saved in the image.
+    pub fn LoadMemory(&self, imagePath: &str) -> Result<()> {
+        for r in &self.Regions {
+            let addr = unsafe {
+                mmap(
+                    r.Addr as _,
+                    r.Len as _,
+                    PROT_READ | PROT_WRITE,
+                    MAP_PRIVATE | MAP_ANON | MAP_FIXED | MAP_NORESERVE,
+                    -1,
+                    0,
+                )
+            };
+
+            if addr == MAP_FAILED {
+                return Err(Error::SysError(errno::errno().0));
+            }
+        }
+
+        let path = Path::new(imagePath).join(CHECKPOINT_PAGES_FILE);
+        let file = File::open(path).map_err(Error::FromIOErr)?;
+        let fd = file.as_raw_fd();
+        for e in &self.Extents {
+            let mut done = 0;
+            while done < e.Len {
+                let ret = unsafe {
+                    pread(
+                        fd,
+                        (e.Addr + done) as _,
+                        (e.Len - done) as _,
+                        (e.Offset + done) as _,
+                    )
+                };
+
+                if ret == 0 {
+                    return Err(Error::Common(format!(
+                        "checkpoint pages image is truncated at {:x}",
+                        e.Offset + done
+                    )));
+                }
+
+                if ret < 0 {
+                    let errno = errno::errno().0;
+                    if errno == EINTR {
+                        continue;
+                    }
+                    return Err(Error::SysError(errno));
+                }
+
+                done += ret as u64;
+            }
+        }
+
+        return Ok(());
+    }
+
+    // RestoreHostFds recreates the host fds of the guest at the fd numbers
+    // recorded in the image. The fds which can't be recreated are replaced
+    // with /dev/null.
+    pub fn RestoreHostFds(&self) -> Result<()> {
+        for state in &self.HostFds {
+            let fd = match state.Kind.Recreate() {
+                Ok(fd) => fd,
+                Err(e) => {
+                    info!(
+                        "restore host fd {} {:?} fail with {:?}, replace it with /dev/null",
+                        state.Fd, state.Kind, e
+                    );
+                    OpenDevNull()?
+                }
+            };
+
+            MoveFd(fd, state.Fd)?;
+            URING_MGR.lock().Addfd(state.Fd)?;
+        }
+
+        // the fd wait masks of the guest are kept in the restored IOMgr, the
+        // new host epoll fd needs to know them.
+        let iomgr = GlobalIOMgr();
+        let fds = iomgr.fdTbl.lock().Fds();
+        for fd in fds {
+            let fdInfo = match iomgr.GetByHost(fd) {
+                None => continue,
+                Some(fdInfo) => fdInfo,
+            };
+
+            let mask = fdInfo.lock().waitInfo.lock().mask;
+            if mask != 0 {
+                FD_NOTIFIER.EpollCtlAdd(fd, mask)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+impl HostFdKind {
+    pub fn Recreate(&self) -> Result<i32> {
+        match self {
+            HostFdKind::File {
+                Path: path,
+                Flags: flags,
+                Offset: offset,
+            } => {
+                let path = ffi::CString::new(path.as_str())
+                    .map_err(|e| Error::Common(format!("{:?}", e)))?;
+                let flags = *flags & !(O_CREAT | O_EXCL | O_TRUNC);
+                let fd = GetRet(unsafe { open(path.as_ptr(), flags | O_CLOEXEC) })?;
+                if *offset > 0 && unsafe { lseek(fd, *offset, SEEK_SET) } < 0 {
+                    let errno = errno::errno().0;
+                    unsafe {
+                        close(fd);
+                    }
+                    return Err(Error::SysError(errno));
+                }
+
+                return Ok(fd);
+            }
+            HostFdKind::Memfd {
+                Size: size,
+                Flags: flags,
+            } => {
+                let name = ffi::CString::new("quark_restored").unwrap();
+                let fd = GetRet(unsafe { memfd_create(name.as_ptr(), MFD_CLOEXEC) })?;
+                if unsafe { ftruncate(fd, *size) } < 0 || unsafe { fcntl(fd, F_SETFL, *flags) } < 0
+                {
+                    let errno = errno::errno().0;
+                    unsafe {
+                        close(fd);
+                    }
+                    return Err(Error::SysError(errno));
+                }
+                return Ok(fd);
+            }
+            HostFdKind::Socket {
+                Domain: domain,
+                Type: stype,
+                Protocol: protocol,
+                Flags: flags,
+                Listening: listening,
+                Addr: addr,
+            } => {
+                let fd = GetRet(unsafe { socket(*domain, *stype | SOCK_CLOEXEC, *protocol) })?;
+                if unsafe { fcntl(fd, F_SETFL, *flags) } < 0 {
+                    let errno = errno::errno().0;
+                    unsafe {
+                        close(fd);
+                    }
+                    return Err(Error::SysError(errno));
+                }
+
+                if *listening {
+                    if let Err(e) = Listen(fd, *domain, addr) {
+                        unsafe {
+                            close(fd);
+                        }
+                        return Err(e);
+                    }
+                }
+
+                return Ok(fd);
+            }
+            HostFdKind::Other => return Err(Error::SysError(SysErr::ENOTSUP)),
+        }
+    }
+}
+
+fn Listen(fd: i32, domain: i32, addr: &[u8]) -> Result<()> {
+    let mut storage: sockaddr_storage = unsafe { core::mem::zeroed() };
+    let len = addr.len().min(size_of::<sockaddr_storage>());
+    unsafe {
+        core::ptr::copy_nonoverlapping(addr.as_ptr(), &mut storage as *mut _ as *mut u8, len);
+    }
+
+    if domain == AF_UNIX {
+        // the socket file of the checkpointed sandbox is stale
+        let sun = unsafe { &*(&storage as *const _ as *const sockaddr_un) };
+        if sun.sun_path[0] != 0 {
+            unsafe {
+                unlink(sun.sun_path.as_ptr());
+            }
+        }
+    } else {
+        let val: i32 = 1;
+        unsafe {
+            setsockopt(
+                fd,
+                SOL_SOCKET,
+                SO_REUSEADDR,
+                &val as *const _ as _,
+                size_of::<i32>() as _,
+            );
+        }
+    }
+
+    GetRet(unsafe { bind(fd, &storage as *const _ as *const sockaddr, len as _) })?;
+    GetRet(unsafe { listen(fd, SOMAXCONN) })?;
+    return Ok(());
+}
+
+fn SaveHostFds() -> Vec<HostFdState> {
+    let fds = GlobalIOMgr().fdTbl.lock().Fds();
+    let mut states = Vec::with_capacity(fds.len());
+    for fd in fds {
+        // the stdio of the sandbox process is not restored
+        if fd <= 2 {
+            continue;
+        }
+
+        let kind = match SaveHostFd(fd) {
+            Ok(kind) => kind,
+            Err(e) => {
+                info!("checkpoint host fd {} fail with {:?}", fd, e);
+                HostFdKind::Other
+            }
+        };
+
+        states.push(HostFdState { Fd: fd, Kind: kind });
+    }
+
+    return states;
+}
+
+fn SaveHostFd(fd: i32) -> Result<HostFdKind> {
+    let flags = GetRet(unsafe { fcntl(fd, F_GETFL) })?;
+    let mut st: stat = unsafe { core::mem::zeroed() };
+    GetRet(unsafe { fstat(fd, &mut st) })?;
+
+    match st.st_mode & S_IFMT {
+        S_IFSOCK => return SaveSocket(fd, flags),
+        S_IFIFO => return Ok(HostFdKind::Other),
+        _ => (),
+    }
+
+    let path = fs::read_link(format!("/proc/self/fd/{}", fd)).map_err(Error::FromIOErr)?;
+    let path = path.to_string_lossy().to_string();
+    if path.starts_with("/memfd:") {
+        return Ok(HostFdKind::Memfd {
+            Size: st.st_size,
+            Flags: flags,
+        });
+    }
+
+    if !path.starts_with("/") {
+        return Ok(HostFdKind::Other);
+    }
+
+    let offset = unsafe { lseek(fd, 0, SEEK_CUR) };
+    return Ok(HostFdKind::File {
+        Path: path,
+        Flags: flags,
+        Offset: if offset < 0 { 0 } else { offset },
+    });
+}
+
+fn GetSockOpt(fd: i32, opt: i32) -> Result<i32> {
+    let mut val: i32 = 0;
+    let mut len = size_of::<i32>() as socklen_t;
+    GetRet(unsafe { getsockopt(fd, SOL_SOCKET, opt, &mut val as *mut _ as _, &mut len) })?;
+    return Ok(val);
+}
+
+fn SaveSocket(fd: i32, flags: i32) -> Result<HostFdKind> {
+    let listening = GetSockOpt(fd, SO_ACCEPTCONN)? != 0;
+    let mut storage: sockaddr_storage = unsafe { core::mem::zeroed() };
+    let mut len = size_of::<sockaddr_storage>() as socklen_t;
+    GetRet(unsafe { getsockname(fd, &mut storage as *mut _ as *mut sockaddr, &mut len) })?;
+    let addr =
+        unsafe { core::slice::from_raw_parts(&storage as *const _ as *const u8, len as usize) };
+
+    return Ok(HostFdKind::Socket {
+        Domain: GetSockOpt(fd, SO_DOMAIN)?,
+        Type: GetSockOpt(fd, SO_TYPE)?,
+        Protocol: GetSockOpt(fd, SO_PROTOCOL)?,
+        Flags: flags,
+        Listening: listening,
+        Addr: addr.to_vec(),
+    });
+}
+
+// MappedRegions gets the host mappings in the guest memory region.
+fn MappedRegions() -> Result<Vec<MemRegion>> {
+    let start = MemoryDef::PHY_LOWER_ADDR;
+    let end = start + MemoryDef::KERNEL_MEM_INIT_REGION_SIZE * MemoryDef::ONE_GB;
+
+    let maps = fs::read_to_string("/proc/self/maps").map_err(Error::FromIOErr)?;
+    let mut regions: Vec<MemRegion> = Vec::new();
+    for line in maps.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 {
+            continue;
+        }
+
+        let range: Vec<&str> = fields[0].split('-').collect();
+        if range.len() != 2 {
+            continue;
+        }
+
+        let (rStart, rEnd) = match (
+            u64::from_str_radix(range[0], 16),
+            u64::from_str_radix(range[1], 16),
+        ) {
+            (Ok(s), Ok(e)) => (s.max(start), e.min(end)),
+            _ => continue,
+        };
+
+        if rStart >= rEnd {
+            continue;
+        }
+
+        let file = fields[4] != "0";
+        match regions.last_mut() {
+            Some(r) if r.Addr + r.Len == rStart && r.File == file => {
+                r.Len += rEnd - rStart;
+            }
+            _ => regions.push(MemRegion {
+                Addr: rStart,
+                Len: rEnd - rStart,
+                File: file,
+            }),
+        }
+    }
+
+    return Ok(regions);
+}
+
+// PresentExtents gets the pages of the regions which are present or swapped
+// out from /proc/self/pagemap and assigns their offsets in pages.img. It runs
+// with the heap locked so it may not allocate, it returns false if extents
+// runs out of the capacity reserved by the caller.
+fn PresentExtents(
+    pagemap: &File,
+    regions: &[MemRegion],
+    entries: &mut [u64],
+    extents: &mut Vec<MemExtent>,
+) -> Result<bool> {
+    const PAGEMAP_PRESENT: u64 = 1 << 63;
+    const PAGEMAP_SWAPPED: u64 = 1 << 62;
+
+    let fd = pagemap.as_raw_fd();
+    let mut offset = 0;
+    let mut addPage = |page: u64| -> bool {
+        match extents.last_mut() {
+            Some(e) if e.Addr + e.Len == page => {
+                e.Len += MemoryDef::PAGE_SIZE;
+            }
+            _ => {
+                if extents.len() == extents.capacity() {
+                    return false;
+                }
+
+                extents.push(MemExtent {
+                    Addr: page,
+                    Len: MemoryDef::PAGE_SIZE,
+                    Offset: offset,
+                })
+            }
+        }
+        offset += MemoryDef::PAGE_SIZE;
+        return true;
+    };
+
+    for r in regions {
+        let mut addr = r.Addr;
+        while addr < r.Addr + r.Len {
+            let pages = ((r.Addr + r.Len - addr) / MemoryDef::PAGE_SIZE).min(entries.len() as u64);
+            if r.File {
+                for i in 0..pages {
+                    if !addPage(addr + i * MemoryDef::PAGE_SIZE) {
+                        return Ok(false);
+                    }
+                }
+                addr += pages * MemoryDef::PAGE_SIZE;
+                continue;
+            }
+
+            let len = pages as usize * size_of::<u64>();
+            let ret = unsafe {
+                pread(
+                    fd,
+                    entries.as_mut_ptr() as _,
+                    len,
+                    ((addr / MemoryDef::PAGE_SIZE) * size_of::<u64>() as u64) as _,
+                )
+            };
+            if ret < 0 {
+                return Err(Error::SysError(errno::errno().0));
+            }
+
+            let cnt = ret as usize / size_of::<u64>();
+            if cnt == 0 {
+                return Err(Error::SysError(SysErr::EIO));
+            }
+
+            for i in 0..cnt {
+                if entries[i] & (PAGEMAP_PRESENT | PAGEMAP_SWAPPED) != 0 {
+                    if !addPage(addr + i as u64 * MemoryDef::PAGE_SIZE) {
+                        return Ok(false);
+                    }
+                }
+            }
+            addr += cnt as u64 * MemoryDef::PAGE_SIZE;
+        }
+    }
+
+    return Ok(true);
+}
+
+// WritePages writes the present pages of the regions to pages.img and returns
+// their extents. The heap is locked from before the extents are gathered
+// until the pages are written, so that a heap page first touched by the qvisor
+// threads in between isn't missed. Nothing here may allocate until the heap
+// is unlocked, the buffers are allocated in advance and the extents are
+// gathered again with a bigger buffer if it is too small.
+fn WritePages(path: &Path, regions: &[MemRegion]) -> Result<Vec<MemExtent>> {
+    const CHUNK_PAGES: u64 = MemoryDef::ONE_GB / MemoryDef::PAGE_SIZE;
+    const INIT_EXTENTS: usize = 4096;
+
+    let file = File::create(path).map_err(Error::FromIOErr)?;
+    let fd = file.as_raw_fd();
+    let pagemap = File::open("/proc/self/pagemap").map_err(Error::FromIOErr)?;
+    let mut entries: Vec<u64> = vec![0; CHUNK_PAGES as usize];
+    let mut extents: Vec<MemExtent> = Vec::with_capacity(INIT_EXTENTS);
+
+    let allocator = GLOBAL_ALLOCATOR.Allocator();
+    loop {
+        allocator.LockAll();
+        extents.clear();
+        match PresentExtents(&pagemap, regions, &mut entries, &mut extents) {
+            Ok(true) => break,
+            Ok(false) => {
+                allocator.UnlockAll();
+                let cap = extents.capacity();
+                extents.reserve(2 * cap);
+            }
+            Err(e) => {
+                allocator.UnlockAll();
+                return Err(e);
+            }
+        }
+    }
+
+    let mut errno = 0;
+    'out: for e in &extents {
+        let mut done = 0;
+        while done < e.Len {
+            let ret = unsafe {
+                pwrite(
+                    fd,
+                    (e.Addr + done) as _,
+                    (e.Len - done) as _,
+                    (e.Offset + done) as _,
+                )
+            };
+
+            if ret < 0 {
+                errno = errno::errno().0;
+                if errno == EINTR {
+                    errno = 0;
+                    continue;
+                }
+                break 'out;
+            }
+
+            done += ret as u64;
+        }
+    }
+    allocator.UnlockAll();
+
+    if errno != 0 {
+        return Err(Error::SysError(errno));
+    }
+
+    file.sync_all().map_err(Error::FromIOErr)?;
+    return Ok(extents);
+}
+
+// WaitFor spins until cond holds, it fails with ETIMEDOUT if cond doesn't
+// hold within CHECKPOINT_WAIT_TIMEOUT.
+fn WaitFor<F: Fn() -> bool>(what: &str, cond: F) -> Result<()> {
+    let start = Instant::now();
+    while !cond() {
+        if start.elapsed() > CHECKPOINT_WAIT_TIMEOUT {
+            error!("checkpoint timeout waiting for {}", what);
+            return Err(Error::SysError(SysErr::ETIMEDOUT));
+        }
+
+        for _ in 0..100 {
+            spin_loop();
+        }
+    }
+
+    return Ok(());
+}
+
+fn OpenDevNull() -> Result<i32> {
+    let path = ffi::CString::new("/dev/null").unwrap();
+    return GetRet(unsafe { open(path.as_ptr(), O_RDWR | O_CLOEXEC) });
+}
+
+// MoveFd moves the open file of fd to the fd number to, to is closed first if
+// it is open.
+pub fn MoveFd(fd: i32, to: i32) -> Result<()> {
+    if fd == to {
+        return Ok(());
+    }
+
+    GetRet(unsafe { dup2(fd, to) })?;
+    unsafe {
+        close(fd);
+    }
+    return Ok(());
+}
+
+pub fn ToBytes<T: Copy>(val: &T) -> Vec<u8> {
+    let data = unsafe { core::slice::from_raw_parts(val as *const _ as *const u8, size_of::<T>()) };
+    return data.to_vec();
+}
+
+pub fn FromBytes<T: Copy + Default>(data: &[u8]) -> Result<T> {
+    if data.len() != size_of::<T>() {
+        return Err(Error::Common(format!(
+            "checkpoint image data size {} mismatch, expect {}",
+            data.len(),
+            size_of::<T>()
+        )));
+    }
+
+    let mut val = T::default();
+    unsafe {
+        core::ptr::copy_nonoverlapping(data.as_ptr(), &mut val as *mut _ as *mut u8, data.len());
+    }
+    return Ok(val);
+}
@@ -69,6 +69,23 @@ impl HostPMAKeeper {
         }
     }
 
+    pub fn AllocatedHugePages(&self) -> Vec<u64> {
+        return self.allocPages.lock().iter().cloned().collect();
+    }
+
+    // RestoreHugePages marks the huge pages in use by a sandbox restored from a
+    // checkpoint image as allocated.
+    pub fn RestoreHugePages(&self, pages: &[u64]) {
+        let mut allocPages = self.allocPages.lock();
+        for page in pages {
+            allocPages.insert(*page);
+        }
+
+        self.hugePages
+            .lock()
+            .retain(|addr| !allocPages.contains(addr));
+    }
+
     pub fn DontNeed(&self) -> Result<()> {
         let alloced = self.allocPages.lock();
         for page in alloced.iter() {
@@ -17,6 +17,9 @@ use lazy_static::lazy_static;
 
 use super::super::qlib::common::*;
 use super::super::qlib::limits::*;
+use super::super::qlib::loader::CPULimit;
+use super::super::qlib::loader::MemoryLimit;
+use super::super::runc::cgroup::cgroup::ParseCpuset;
 use super::super::runc::oci::*;
 //use super::super::qlib::linux::limits::*;
 
@@ -223,3 +226,94 @@ pub fn CreateLimitSet(spec: &Spec) -> Result<LimitSet> {
 
     return Ok(ls);
 }
+
+// CreateMemoryLimit returns the memory limit of the container from the
+// linux.resources of its spec.
+pub fn CreateMemoryLimit(spec: &Spec) -> MemoryLimit {
+    let mut ml = MemoryLimit::default();
+
+    let resources = match &spec.linux {
+        None => return ml,
+        Some(linux) => match &linux.resources {
+            None => return ml,
+            Some(r) => r,
+        },
+    };
+
+    ml.DisableOOMKiller = resources.disable_oom_killer;
+    if let Some(adj) = resources.oom_score_adj {
+        ml.OOMScoreAdj = adj;
+    }
+
+    if let Some(memory) = &resources.memory {
+        // A negative limit means unlimited.
+        if let Some(limit) = memory.limit {
+            if limit > 0 {
+                ml.Limit = limit as u64;
+            }
+        }
+
+        if memory.disableOOMKiller == Some(true) {
+            ml.DisableOOMKiller = true;
+        }
+    }
+
+    return ml;
+}
+
+// CreateCPULimit returns the cpuset and CPU bandwidth limit of the container
+// from the linux.resources of its spec.
+pub fn CreateCPULimit(spec: &Spec) -> CPULimit {
+    let mut cl = CPULimit::default();
+
+    let cpu = match &spec.linux {
+        None => return cl,
+        Some(linux) => match &linux.resources {
+            None => return cl,
+            Some(r) => match &r.cpu {
+                None => return cl,
+                Some(c) => c,
+            },
+        },
+    };
+
+    match ParseCpuset(&cpu.cpus) {
+        Ok(cpus) => cl.CPUs = cpus,
+        Err(e) => error!("CreateCPULimit: ignore cpuset {}: {:?}", &cpu.cpus, e),
+    }
+
+    // A negative quota means unlimited.
+    if let Some(quota) = cpu.quota {
+        if quota > 0 {
+            cl.Quota = quota as u64;
+        }
+    }
+
+    if let Some(period) = cpu.period {
+        cl.Period = period;
+    }
+
+    return cl;
+}
+
+// CreatePidsLimit returns the maximum number of tasks of the container from
+// the linux.resources of its spec, 0 means unlimited.
+pub fn CreatePidsLimit(spec: &Spec) -> u64 {
+    let pids = match &spec.linux {
+        None => return 0,
+        Some(linux) => match &linux.resources {
+            None => return 0,
+            Some(r) => match &r.pids {
+                None => return 0,
+                Some(p) => p,
+            },
+        },
+    };
+
+    // A limit of 0 or -1 means unlimited.
+    if pids.limit > 0 {
+        return pids.limit as u64;
+    }
+
+    return 0;
+}
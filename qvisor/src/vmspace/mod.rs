@@ -24,6 +24,7 @@ pub mod syscall;
 pub mod time;
 pub mod uringMgr;
 pub mod hibernate;
+pub mod checkpoint;
 
 use std::env::temp_dir;
 use uuid::Uuid;
@@ -223,9 +224,15 @@ impl VMSpace {
         process.limitSet = CreateLimitSet(&spec)
             .expect("load limitSet fail")
             .GetInternalCopy();
+        process.memoryLimit = CreateMemoryLimit(&spec);
+        process.cpuLimit = CreateCPULimit(&spec);
+        process.pidsLimit = CreatePidsLimit(&spec);
+        process.Seccomp = Seccomp(&spec);
+        process.Sysctl = Sysctl(&spec);
         process.Caps = Capabilities(false, &spec.process.capabilities);
 
         process.HostName = spec.hostname.to_string();
+        process.Mounts = Mounts(&spec);
 
         process.NumCpu = self.vcpuCount as u32;
         process.ExecId = Some("".to_string());
@@ -1431,6 +1438,53 @@ impl VMSpace {
         return guestfd as i64;
     }
 
+    // HostInotifyInit creates a host inotify instance backing one guest inotify
+    // instance. The fd is registered with the IO manager so that host events
+    // are delivered to the guest through the host epoll notifier.
+    pub fn HostInotifyInit() -> i64 {
+        let fd = unsafe { inotify_init1(IN_NONBLOCK | IN_CLOEXEC) };
+
+        if fd < 0 {
+            return Self::GetRet(fd as i64);
+        }
+
+        let guestfd = GlobalIOMgr().AddFile(fd);
+
+        return guestfd as i64;
+    }
+
+    // HostInotifyAddWatch adds a host watch for the file referred by targetFd.
+    // The path goes through /proc/self/fd so that the watch follows the opened
+    // inode instead of the path which might be different in the host mount ns.
+    pub fn HostInotifyAddWatch(fd: i32, targetFd: i32, mask: u32) -> i64 {
+        let fd = match Self::GetOsfd(fd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        let targetFd = match Self::GetOsfd(targetFd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        let path = format!("/proc/self/fd/{}", targetFd);
+        let cstr = CString::New(&path);
+        let ret = unsafe { inotify_add_watch(fd, cstr.Ptr() as *const c_char, mask) };
+
+        return Self::GetRet(ret as i64);
+    }
+
+    pub fn HostInotifyRmWatch(fd: i32, wd: i32) -> i64 {
+        let fd = match Self::GetOsfd(fd) {
+            Some(fd) => fd,
+            None => return -SysErr::EBADF as i64,
+        };
+
+        let ret = unsafe { inotify_rm_watch(fd, wd) };
+
+        return Self::GetRet(ret as i64);
+    }
+
     pub fn NewFifo() -> i64 {
         let uid = NewUID();
         let path = format!("/tmp/fifo_{}", uid);
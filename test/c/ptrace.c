@@ -0,0 +1,214 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/ptrace.h>
+#include <sys/syscall.h>
+#include <sys/types.h>
+#include <sys/uio.h>
+#include <sys/user.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define PEEK_MAGIC 0x1122334455667788UL
+#define POKE_MAGIC 0x8877665544332211UL
+
+volatile unsigned long target = PEEK_MAGIC;
+char vmbuf[64] = "process_vm_readv";
+
+static int failures = 0;
+
+static void check(int ok, const char *what)
+{
+    if (ok) {
+        printf("PASS: %s\n", what);
+    } else {
+        printf("FAIL: %s (errno %d)\n", what, errno);
+        failures++;
+    }
+}
+
+static int waitStopped(pid_t pid, int *status)
+{
+    if (waitpid(pid, status, __WALL) != pid) {
+        return 0;
+    }
+    return WIFSTOPPED(*status);
+}
+
+// testTraceme covers PTRACE_TRACEME, PEEKDATA/POKEDATA, GETREGS/SETREGS and
+// syscall-enter/exit stops.
+static void testTraceme()
+{
+    int status;
+    pid_t pid = fork();
+    if (pid == 0) {
+        ptrace(PTRACE_TRACEME, 0, 0, 0);
+        raise(SIGSTOP);
+        long tid = syscall(SYS_getpid);
+        // The tracer rewrites the return value of getpid to 42.
+        if (tid != 42) {
+            _exit(2);
+        }
+        _exit(target == POKE_MAGIC ? 0 : 1);
+    }
+
+    check(waitStopped(pid, &status) && WSTOPSIG(status) == SIGSTOP, "TRACEME signal-delivery-stop");
+
+    errno = 0;
+    long data = ptrace(PTRACE_PEEKDATA, pid, (void *)&target, 0);
+    check(errno == 0 && (unsigned long)data == PEEK_MAGIC, "PEEKDATA");
+    check(ptrace(PTRACE_POKEDATA, pid, (void *)&target, (void *)POKE_MAGIC) == 0, "POKEDATA");
+    errno = 0;
+    data = ptrace(PTRACE_PEEKDATA, pid, (void *)&target, 0);
+    check(errno == 0 && (unsigned long)data == POKE_MAGIC, "PEEKDATA after POKEDATA");
+
+    struct user_regs_struct regs;
+    check(ptrace(PTRACE_GETREGS, pid, 0, &regs) == 0, "GETREGS");
+    check(ptrace(PTRACE_SETOPTIONS, pid, 0, (void *)PTRACE_O_TRACESYSGOOD) == 0, "SETOPTIONS");
+
+    // Step through syscall-stops until getpid enters and exits.
+    int entered = 0;
+    int exited = 0;
+    while (!exited) {
+        if (ptrace(PTRACE_SYSCALL, pid, 0, 0) != 0 || !waitStopped(pid, &status)) {
+            break;
+        }
+        if (WSTOPSIG(status) != (SIGTRAP | 0x80)) {
+            continue;
+        }
+        if (ptrace(PTRACE_GETREGS, pid, 0, &regs) != 0) {
+            break;
+        }
+        if (regs.orig_rax != SYS_getpid) {
+            continue;
+        }
+        if (!entered) {
+            entered = 1;
+            continue;
+        }
+        exited = 1;
+        check(regs.rax == (unsigned long)pid, "syscall-exit-stop return value");
+        regs.rax = 42;
+        check(ptrace(PTRACE_SETREGS, pid, 0, &regs) == 0, "SETREGS");
+    }
+    check(entered && exited, "syscall-enter/exit-stops");
+
+    ptrace(PTRACE_CONT, pid, 0, 0);
+    check(waitpid(pid, &status, 0) == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0,
+          "tracee observed POKEDATA and SETREGS");
+}
+
+// testAttach covers PTRACE_ATTACH and PTRACE_DETACH of a running child.
+static void testAttach()
+{
+    int status;
+    pid_t pid = fork();
+    if (pid == 0) {
+        for (;;) {
+            pause();
+        }
+    }
+
+    check(ptrace(PTRACE_ATTACH, pid, 0, 0) == 0, "ATTACH");
+    check(waitStopped(pid, &status) && WSTOPSIG(status) == SIGSTOP, "ATTACH stop");
+    errno = 0;
+    long data = ptrace(PTRACE_PEEKDATA, pid, (void *)&target, 0);
+    check(errno == 0 && (unsigned long)data == PEEK_MAGIC, "PEEKDATA after ATTACH");
+    check(ptrace(PTRACE_DETACH, pid, 0, 0) == 0, "DETACH");
+
+    kill(pid, SIGKILL);
+    waitpid(pid, &status, 0);
+}
+
+// testSeize covers PTRACE_SEIZE and PTRACE_INTERRUPT.
+static void testSeize()
+{
+    int status;
+    pid_t pid = fork();
+    if (pid == 0) {
+        for (;;) {
+            pause();
+        }
+    }
+
+    check(ptrace(PTRACE_SEIZE, pid, 0, 0) == 0, "SEIZE");
+    check(ptrace(PTRACE_INTERRUPT, pid, 0, 0) == 0, "INTERRUPT");
+    check(waitStopped(pid, &status) && (status >> 16) == PTRACE_EVENT_STOP, "INTERRUPT group-stop");
+    check(ptrace(PTRACE_DETACH, pid, 0, 0) == 0, "DETACH after SEIZE");
+
+    kill(pid, SIGKILL);
+    waitpid(pid, &status, 0);
+}
+
+// testProcessVM covers process_vm_readv and process_vm_writev, including
+// transfers split across several iovecs on both sides.
+static void testProcessVM()
+{
+    int status;
+    int toChild[2];
+    int toParent[2];
+    char c = 0;
+
+    if (pipe(toChild) != 0 || pipe(toParent) != 0) {
+        check(0, "pipe");
+        return;
+    }
+
+    pid_t pid = fork();
+    if (pid == 0) {
+        if (read(toChild[0], &c, 1) != 1) {
+            _exit(1);
+        }
+        _exit(strcmp(vmbuf, "written by parent") == 0 ? 0 : 1);
+    }
+
+    char a[8];
+    char b[56];
+    struct iovec local[2] = {{a, sizeof(a)}, {b, sizeof(b)}};
+    struct iovec remote[1] = {{vmbuf, sizeof(vmbuf)}};
+    check(process_vm_readv(pid, local, 2, remote, 1, 0) == sizeof(vmbuf), "process_vm_readv");
+    check(memcmp(a, "process_", 8) == 0 && strcmp(b, "vm_readv") == 0, "process_vm_readv data");
+
+    char msg[] = "written by parent";
+    struct iovec src[1] = {{msg, sizeof(msg)}};
+    struct iovec dst[2] = {{vmbuf, 5}, {vmbuf + 5, sizeof(vmbuf) - 5}};
+    check(process_vm_writev(pid, src, 1, dst, 2, 0) == sizeof(msg), "process_vm_writev");
+
+    if (write(toChild[1], &c, 1) != 1) {
+        check(0, "write");
+    }
+    check(waitpid(pid, &status, 0) == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0,
+          "process_vm_writev data");
+}
+
+int main()
+{
+    testTraceme();
+    testAttach();
+    testSeize();
+    testProcessVM();
+
+    if (failures != 0) {
+        printf("%d ptrace checks failed\n", failures);
+        return 1;
+    }
+    printf("all ptrace checks passed\n");
+    return 0;
+}
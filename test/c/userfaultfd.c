@@ -0,0 +1,184 @@
+// Copyright (c) 2021 Quark Container Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//gcc userfaultfd.c -o userfaultfd -lpthread
+// read(2) and write(2) with a buffer in a range registered with a userfaultfd
+// block until the monitor thread populates the missing pages.
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <poll.h>
+#include <sys/mman.h>
+#include <sys/ioctl.h>
+#include <sys/syscall.h>
+#include <linux/userfaultfd.h>
+
+#define PAGES 4
+
+int uffd = -1;
+long page_size;
+char *fill_page;
+
+void *monitor_thread(void *dummy)
+{
+    struct uffd_msg msg;
+    struct uffdio_copy copy;
+    int faults = 0;
+
+    while (faults < PAGES)
+    {
+        struct pollfd pfd = { .fd = uffd, .events = POLLIN };
+        if (poll(&pfd, 1, -1) < 0)
+        {
+            perror("poll");
+            exit(1);
+        }
+
+        if (read(uffd, &msg, sizeof(msg)) != sizeof(msg))
+        {
+            perror("read uffd");
+            exit(1);
+        }
+
+        if (msg.event != UFFD_EVENT_PAGEFAULT)
+        {
+            printf("unexpected event %d\n", msg.event);
+            exit(1);
+        }
+
+        memset(fill_page, 'a' + faults, page_size);
+        copy.src = (unsigned long)fill_page;
+        copy.dst = msg.arg.pagefault.address & ~(page_size - 1);
+        copy.len = page_size;
+        copy.mode = 0;
+        copy.copy = 0;
+        if (ioctl(uffd, UFFDIO_COPY, &copy) < 0)
+        {
+            perror("UFFDIO_COPY");
+            exit(1);
+        }
+
+        faults++;
+    }
+
+    return NULL;
+}
+
+int main()
+{
+    struct uffdio_api api = { .api = UFFD_API, .features = 0 };
+    struct uffdio_register reg;
+    pthread_t thread;
+    int pipefd[2];
+    char *area;
+    char *out;
+    long i;
+
+    page_size = sysconf(_SC_PAGESIZE);
+
+    uffd = syscall(SYS_userfaultfd, O_CLOEXEC);
+    if (uffd < 0)
+    {
+        perror("userfaultfd");
+        return 1;
+    }
+
+    if (ioctl(uffd, UFFDIO_API, &api) < 0)
+    {
+        perror("UFFDIO_API");
+        return 1;
+    }
+
+    fill_page = malloc(page_size);
+    out = malloc(2 * page_size);
+    area = mmap(NULL, PAGES * page_size, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (fill_page == NULL || out == NULL || area == MAP_FAILED)
+    {
+        printf("allocation failed\n");
+        return 1;
+    }
+
+    reg.range.start = (unsigned long)area;
+    reg.range.len = PAGES * page_size;
+    reg.mode = UFFDIO_REGISTER_MODE_MISSING;
+    if (ioctl(uffd, UFFDIO_REGISTER, &reg) < 0)
+    {
+        perror("UFFDIO_REGISTER");
+        return 1;
+    }
+
+    if (pthread_create(&thread, NULL, monitor_thread, NULL) != 0)
+    {
+        printf("pthread_create failed\n");
+        return 1;
+    }
+
+    if (pipe(pipefd) < 0)
+    {
+        perror("pipe");
+        return 1;
+    }
+
+    // write(2) reads the first two pages from the registered range.
+    if (write(pipefd[1], area, 2 * page_size) != 2 * page_size)
+    {
+        perror("write");
+        return 1;
+    }
+
+    if (read(pipefd[0], out, 2 * page_size) != 2 * page_size)
+    {
+        perror("read");
+        return 1;
+    }
+
+    for (i = 0; i < 2 * page_size; i++)
+    {
+        if (out[i] != 'a' + i / page_size)
+        {
+            printf("write: unexpected byte %d at %ld\n", out[i], i);
+            return 1;
+        }
+    }
+
+    // read(2) writes the last two pages of the registered range.
+    memset(out, 'z', 2 * page_size);
+    if (write(pipefd[1], out, 2 * page_size) != 2 * page_size)
+    {
+        perror("write");
+        return 1;
+    }
+
+    if (read(pipefd[0], area + 2 * page_size, 2 * page_size) != 2 * page_size)
+    {
+        perror("read");
+        return 1;
+    }
+
+    for (i = 2 * page_size; i < PAGES * page_size; i++)
+    {
+        if (area[i] != 'z')
+        {
+            printf("read: unexpected byte %d at %ld\n", area[i], i);
+            return 1;
+        }
+    }
+
+    pthread_join(thread, NULL);
+    printf("userfaultfd test passed\n");
+    return 0;
+}